@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+# Adds the legacy transactions of a recent mainnet block to the seeds of `solana_transaction`.
+# The committed seeds are hand-built and unsigned, this adds signed transactions from the cluster.
+# Needs `curl` and `jq`. Set `RPC` to use another endpoint and `COUNT` for more transactions.
+set -euo pipefail
+
+RPC="${RPC:-https://api.mainnet-beta.solana.com}"
+COUNT="${COUNT:-20}"
+SEEDS="$(dirname "$0")/seeds/solana_transaction"
+
+rpc() {
+    curl -sSf "$RPC" -H 'Content-Type: application/json' -d "$1"
+}
+
+slot=$(rpc '{"jsonrpc":"2.0","id":1,"method":"getSlot","params":[{"commitment":"finalized"}]}' | jq -r '.result')
+# The latest slot can be skipped, so take the latest block produced before it
+block=$(rpc "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"getBlocks\",\"params\":[$((slot - 50)),$slot]}" | jq -r '.result[-1]')
+
+rpc "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"getBlock\",\"params\":[$block,{\"encoding\":\"base64\",\"transactionDetails\":\"full\",\"rewards\":false,\"maxSupportedTransactionVersion\":0}]}" |
+    jq -r '.result.transactions[] | select(.version == "legacy") | .transaction[0]' |
+    head -n "$COUNT" |
+    while read -r encoded; do
+        name=$(printf '%s' "$encoded" | sha1sum | cut -c1-16)
+        printf '%s' "$encoded" | base64 -d >"$SEEDS/mainnet-$block-$name"
+    done
+
+echo "Added the seeds of block $block to $SEEDS"
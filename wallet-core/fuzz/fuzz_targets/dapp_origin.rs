@@ -0,0 +1,37 @@
+//! The origins of dapps screened by the phishing protection, their punycode labels and
+//! signed domain list files
+
+#![no_main]
+
+use atoll_wallet_core::{DomainHost, DomainList, DomainLookalike, DomainReputation, Punycode};
+use libfuzzer_sys::fuzz_target;
+use solana_pubkey::Pubkey;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(input) = core::str::from_utf8(data) else {
+        return;
+    };
+
+    let _ = Punycode::decode(input);
+    let _ = DomainList::from_signed(input, &Pubkey::default());
+
+    let Ok(host) = DomainHost::from_origin(input) else {
+        return;
+    };
+    assert!(host.ascii().is_ascii());
+    assert_eq!(host.domains().next(), Some(host.ascii()));
+    assert!(host.is_within(host.ascii()));
+
+    if !host.ascii().starts_with('[') {
+        assert_eq!(DomainHost::new(host.ascii()).ok().as_ref(), Some(&host));
+    }
+
+    if DomainLookalike::of(&host).is_some() {
+        assert!(DomainLookalike::popular(&host).is_none());
+    }
+
+    let report = DomainReputation::default()
+        .check(input, 0.0)
+        .expect("the origin was read");
+    assert_eq!(report.host(), &host);
+});
@@ -0,0 +1,76 @@
+//! Request envelopes and the typed params read from them
+
+#![no_main]
+
+use atoll_wallet_core::{
+    BitcoinAccountParams, ConnectedSitesParams, DappPolicyParams, DomainReputationPolicy,
+    DomainReputationPolicyParams, ExtensionMessage, ProtocolHeader, SendOptions,
+    SolanaAccountParams, SolanaSignInParams, StandardConnectParams, WholeNumberParams,
+};
+use libfuzzer_sys::fuzz_target;
+use serde::Deserialize;
+use serde_json::Value;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(envelope) = serde_json::from_slice::<Value>(data) else {
+        return;
+    };
+    let Ok(header) = ProtocolHeader::decode(&envelope) else {
+        return;
+    };
+
+    assert_eq!(header.version, ProtocolHeader::VERSION);
+    assert!(!header.id.is_empty());
+    assert_eq!(
+        ExtensionMessage::try_from(header.method.as_str()).ok(),
+        Some(header.method)
+    );
+
+    let origin = header
+        .resolve_origin(Some("https://dapp.example".to_string()))
+        .ok();
+    if let Some(claimed) = header.origin.as_ref() {
+        assert!(origin.is_none() || origin == Some(Some(claimed.clone())));
+    }
+
+    let params = &envelope["params"];
+    match header.method {
+        ExtensionMessage::StandardConnect => {
+            let _ = StandardConnectParams::deserialize(params);
+        }
+        ExtensionMessage::ListConnectedSites => {
+            if let Ok(params) = ConnectedSitesParams::deserialize(params) {
+                let _ = params.account();
+            }
+        }
+        ExtensionMessage::SolanaSignIn => {
+            let _ = SolanaSignInParams::deserialize(params);
+        }
+        ExtensionMessage::SolanaSignMessage
+        | ExtensionMessage::SolanaPreviewMessage
+        | ExtensionMessage::SolanaSignTransaction
+        | ExtensionMessage::SolanaSignAndSendTransaction => {
+            let _ = SolanaAccountParams::deserialize(&params["account"]);
+            let _ = SendOptions::deserialize(&params["options"]);
+        }
+        ExtensionMessage::SolanaEstimateSend | ExtensionMessage::SolanaSend => {
+            let _ = WholeNumberParams::deserialize(&params["amount"]);
+        }
+        ExtensionMessage::BitcoinSignMessage
+        | ExtensionMessage::BitcoinSignTransaction
+        | ExtensionMessage::BitcoinSignAndSendTransaction => {
+            let _ = BitcoinAccountParams::deserialize(&params["account"]);
+        }
+        ExtensionMessage::SetDomainPolicy => {
+            if let Ok(params) = DomainReputationPolicyParams::deserialize(params) {
+                let _ = params.into_policy(DomainReputationPolicy::default());
+            }
+        }
+        ExtensionMessage::SetDappPolicy => {
+            if let Ok(params) = DappPolicyParams::deserialize(params) {
+                let _ = params.into_policy(None);
+            }
+        }
+        _ => (),
+    }
+});
@@ -0,0 +1,64 @@
+//! The `SignInInput` of `solana:signIn`, which must read back from the message it formats
+//! to the same message and fields
+
+#![no_main]
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use atoll_wallet_core::{SignInInputParser, SolanaSignInParams};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(params) = serde_json::from_slice::<SolanaSignInParams>(data) else {
+        return;
+    };
+
+    let now = UNIX_EPOCH + Duration::from_secs(1_750_000_000);
+
+    let mut parser = SignInInputParser::new(now);
+    let Ok(parsed) = parser.parse(&params) else {
+        return;
+    };
+    let message = parsed.format();
+
+    let read = SolanaSignInParams::from_message(&message).expect("the message reads back");
+
+    let mut reparser = SignInInputParser::new(now);
+    let reformatted = reparser
+        .parse(&read)
+        .expect("the fields read back are valid")
+        .format();
+    assert_eq!(reformatted, message);
+
+    let fields = |params: &SolanaSignInParams| {
+        [
+            &params.domain,
+            &params.uri,
+            &params.version,
+            &params.chain_id,
+            &params.nonce,
+            &params.issued_at,
+            &params.expiration_time,
+            &params.not_before,
+            &params.request_id,
+        ]
+        .map(Option::is_some)
+    };
+    // The address and statement can only be told apart by the address being valid
+    let blocks = |params: &SolanaSignInParams| {
+        [&params.address, &params.statement]
+            .iter()
+            .filter(|block| block.is_some())
+            .count()
+    };
+    let resources = |params: &SolanaSignInParams| {
+        params
+            .resources
+            .iter()
+            .any(|resource| !resource.trim().is_empty())
+    };
+
+    assert_eq!(fields(&read), fields(&params));
+    assert_eq!(blocks(&read), blocks(&params));
+    assert_eq!(resources(&read), resources(&params));
+});
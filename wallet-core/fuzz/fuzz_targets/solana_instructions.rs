@@ -0,0 +1,78 @@
+//! The instruction decoders of the activity feed and of the compute budget
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use atoll_wallet_core::{
+    SolanaActivityRow, SolanaComputeBudget, SolanaInstructions, SplTokenProgram,
+};
+use libfuzzer_sys::fuzz_target;
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+#[derive(Debug, Arbitrary)]
+enum Program {
+    System,
+    Token,
+    Token2022,
+    AssociatedToken,
+    Memo,
+    MemoV1,
+    ComputeBudget,
+    Other([u8; 32]),
+}
+
+impl Program {
+    fn program_id(&self) -> Pubkey {
+        match self {
+            Self::System => SolanaInstructions::SYSTEM_PROGRAM_ID,
+            Self::Token => SplTokenProgram::Token.program_id(),
+            Self::Token2022 => SplTokenProgram::Token2022.program_id(),
+            Self::AssociatedToken => SolanaInstructions::ASSOCIATED_TOKEN_PROGRAM_ID,
+            Self::Memo => SolanaInstructions::MEMO_PROGRAM_ID,
+            Self::MemoV1 => SolanaActivityRow::MEMO_V1_PROGRAM_ID,
+            Self::ComputeBudget => SolanaInstructions::COMPUTE_BUDGET_PROGRAM_ID,
+            Self::Other(program_id) => Pubkey::new_from_array(*program_id),
+        }
+    }
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    program: Program,
+    accounts: Vec<[u8; 32]>,
+    data: Vec<u8>,
+    unit_limit: Option<u32>,
+    unit_price: Option<u64>,
+}
+
+fuzz_target!(|input: Input| {
+    let program_id = input.program.program_id();
+    let accounts = input
+        .accounts
+        .iter()
+        .map(|account| Pubkey::new_from_array(*account))
+        .collect::<Vec<Pubkey>>();
+
+    if let Some(row) = SolanaActivityRow::parse(&program_id, &accounts, &input.data) {
+        row.kind();
+        row.direction(&accounts.first().copied().unwrap_or_default());
+    }
+
+    let instruction = Instruction {
+        program_id,
+        accounts: accounts
+            .iter()
+            .map(|account| AccountMeta::new(*account, false))
+            .collect(),
+        data: input.data,
+    };
+
+    // Applying a budget replaces the limit and price set by the instruction
+    let budget = SolanaComputeBudget {
+        unit_limit: input.unit_limit,
+        unit_price: input.unit_price,
+    };
+    let applied = budget.apply(&[instruction]);
+    assert_eq!(SolanaComputeBudget::from_instructions(&applied), budget);
+});
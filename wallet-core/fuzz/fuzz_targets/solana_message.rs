@@ -0,0 +1,47 @@
+//! The bytes of `solana:signMessage`, which are signed as they are, as an off-chain message
+//! that encodes back to the same bytes, or refused
+
+#![no_main]
+
+use atoll_wallet_core::{
+    SolanaMessageMode, SolanaMessagePreview, SolanaOffchainMessage, SolanaSignableMessage,
+};
+use libfuzzer_sys::fuzz_target;
+use solana_pubkey::Pubkey;
+
+fuzz_target!(|data: &[u8]| {
+    let signer = data
+        .get(data.len().saturating_sub(32)..)
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .map(Pubkey::new_from_array)
+        .unwrap_or_default();
+
+    let preview = SolanaMessagePreview::new(data);
+    if let SolanaMessagePreview::Text(text) = &preview {
+        assert_eq!(text.as_bytes(), data);
+    }
+
+    let Ok(message) = SolanaSignableMessage::inspect(data.to_vec(), &signer) else {
+        return;
+    };
+    assert_eq!(message.bytes(), data);
+
+    match message.mode() {
+        SolanaMessageMode::Raw => {
+            assert!(!data.starts_with(SolanaOffchainMessage::SIGNING_DOMAIN));
+            assert!(!SolanaSignableMessage::is_transaction(data));
+        }
+        SolanaMessageMode::Offchain(offchain) => {
+            assert_eq!(offchain.encode(), data);
+            assert!(offchain.signers().contains(&signer));
+
+            let rebuilt = SolanaOffchainMessage::new(
+                *offchain.application_domain(),
+                offchain.signers().to_vec(),
+                offchain.message().to_vec(),
+            )
+            .expect("a decoded message is valid");
+            assert!(rebuilt.format() <= offchain.format());
+        }
+    }
+});
@@ -0,0 +1,27 @@
+//! Transaction bytes from `solana:signTransaction` and `solana:signAndSendTransaction`
+//! and what the background reads from them before signing
+
+#![no_main]
+
+use atoll_wallet_core::{SolanaActivityRow, SolanaComputeBudget, SolanaWireTransaction};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(transaction) = SolanaWireTransaction::decode(data) else {
+        return;
+    };
+
+    let message = &transaction.message;
+    SolanaComputeBudget::writable_accounts(message);
+    let instructions =
+        SolanaComputeBudget::decompile(message).expect("a decoded message decompiles");
+    SolanaComputeBudget::from_instructions(&instructions);
+    SolanaActivityRow::from_instructions(&message.account_keys, &message.instructions);
+
+    let encoded = bincode::serialize(&transaction).expect("a decoded transaction encodes");
+    assert!(encoded.len() as u64 <= SolanaWireTransaction::MAX_SIZE);
+    assert_eq!(
+        SolanaWireTransaction::decode(&encoded).expect("an encoded transaction decodes"),
+        transaction
+    );
+});
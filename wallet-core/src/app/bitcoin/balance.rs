@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+use crate::{App, AtollWalletResult, BitcoinBackend, EsploraBackend, HttpTransport, ProtocolValue};
+
+/// The input of `atoll:bitcoinBalance`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct BitcoinBalanceParams {
+    pub chain: Option<String>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:bitcoinBalance`. The optional params are `{ chain? }` and the
+    /// output is `[{ address, purpose, confirmed, unconfirmed }]` with amounts in satoshis.
+    pub async fn bitcoin_balance(
+        &self,
+        params: BitcoinBalanceParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let cluster = Self::bitcoin_cluster(params.chain.as_deref())?;
+
+        let backend = EsploraBackend::new_with_cluster(self.transport.clone(), cluster);
+
+        let mut balances = ProtocolValue::new_array();
+
+        for keypair in self.bitcoin_keypairs(cluster).await? {
+            let balance = backend.balance(keypair.address()).await?;
+
+            let mut output = ProtocolValue::new_object();
+            output
+                .set("address", keypair.address().to_string())
+                .set("purpose", keypair.purpose().as_str())
+                .set("confirmed", balance.confirmed.to_sat() as f64)
+                .set("unconfirmed", balance.unconfirmed.to_sat() as f64);
+
+            balances.push(output);
+        }
+
+        Ok(balances)
+    }
+}
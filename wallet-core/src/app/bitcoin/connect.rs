@@ -0,0 +1,71 @@
+use serde::Deserialize;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, BitcoinPurpose, BitcoinWalletAccount, HttpTransport,
+    ProtocolValue, ToProtocolValue,
+};
+
+/// The input of `bitcoin:connect`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct BitcoinConnectParams {
+    pub purposes: Option<Vec<String>>,
+    pub chain: Option<String>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `bitcoin:connect`. The optional params are `{ purposes?: ("payment" | "ordinals")[], chain? }`
+    /// and the output is `{ accounts }` with one account per purpose.
+    ///
+    /// The dapp at `origin` is connected with the active account as with `standard:connect`,
+    /// so its policy decides what the Bitcoin methods can do too.
+    pub async fn bitcoin_connect(
+        &self,
+        origin: Option<String>,
+        params: BitcoinConnectParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let origin = origin.ok_or(AtollWalletError::InvalidRequest(
+            "`bitcoin:connect` requires the origin of the page".to_string(),
+        ))?;
+        let account = self
+            .active_account()
+            .await
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        let purposes = match params.purposes {
+            Some(purposes) => purposes
+                .iter()
+                .map(|purpose| BitcoinPurpose::try_from(purpose.as_str()))
+                .collect::<AtollWalletResult<Vec<BitcoinPurpose>>>()?,
+            None => vec![BitcoinPurpose::Payment],
+        };
+
+        let cluster = Self::bitcoin_cluster(params.chain.as_deref())?;
+
+        let mut accounts = ProtocolValue::new_array();
+
+        {
+            let mut vault = self.vault.write().await;
+            let vault = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+            vault.policies_mut().connect(&origin, account)?;
+            vault.sites_mut().connect(&origin, account, None, now_ms);
+
+            for purpose in purposes {
+                let keypair = vault.bitcoin_keypair(purpose, cluster, 0)?;
+                accounts.push(BitcoinWalletAccount::new(&keypair).to_protocol_value());
+            }
+        }
+
+        self.save_policies().await?;
+        self.save_sites().await?;
+
+        let mut output = ProtocolValue::new_object();
+        output.set("accounts", accounts);
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,19 @@
+mod balance;
+pub use balance::*;
+
+mod connect;
+pub use connect::*;
+
+mod send;
+pub use send::*;
+
+mod sign_and_send_transaction;
+
+mod sign_message;
+pub use sign_message::*;
+
+mod sign_transaction;
+pub use sign_transaction::*;
+
+mod wallet_account;
+pub use wallet_account::*;
@@ -0,0 +1,163 @@
+use core::str::FromStr;
+
+use bitcoin::{Address, Amount};
+use serde::Deserialize;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, BitcoinAccountKeypair, BitcoinBackend,
+    BitcoinCluster, BitcoinPurpose, BitcoinSendBuilder, BitcoinSendPlan, BitcoinUtxo,
+    EsploraBackend, HttpTransport, ProtocolValue,
+};
+
+/// The input of `atoll:bitcoinEstimateSend` and `atoll:bitcoinSend`
+#[derive(Debug, PartialEq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitcoinSendParams {
+    pub recipient: String,
+    /// In satoshis
+    pub amount: u64,
+    /// In sat/vB, estimated for [BITCOIN_DEFAULT_CONFIRMATION_TARGET] if absent
+    #[serde(default)]
+    pub fee_rate: Option<f64>,
+    #[serde(default = "BitcoinSendParams::default_rbf")]
+    pub rbf: bool,
+    #[serde(default)]
+    pub chain: Option<String>,
+}
+
+impl BitcoinSendParams {
+    fn default_rbf() -> bool {
+        true
+    }
+}
+
+/// The confirmation target of the fee rate used when `feeRate` is not set
+pub const BITCOIN_DEFAULT_CONFIRMATION_TARGET: u16 = 6;
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:bitcoinEstimateSend`. The params are
+    /// `{ recipient, amount, feeRate?, rbf?, chain? }` with `amount` in satoshis and `feeRate`
+    /// in sat/vB. The output is `{ fee, feeRate, vsize, change, inputs }` without sending anything.
+    pub async fn bitcoin_estimate_send(
+        &self,
+        params: BitcoinSendParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let PlannedSend {
+            plan, sat_per_vb, ..
+        } = self.bitcoin_send_plan(params).await?;
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("fee", plan.fee().to_sat() as f64)
+            .set("feeRate", sat_per_vb)
+            .set("vsize", plan.estimated_vsize() as f64)
+            .set(
+                "change",
+                plan.change()
+                    .map(|change| change.value.to_sat())
+                    .unwrap_or_default() as f64,
+            )
+            .set("inputs", plan.inputs().len() as f64);
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:bitcoinSend` with the same params as `atoll:bitcoinEstimateSend`.
+    /// The transaction is signed by the payment account, broadcast and the output is `{ txid, fee }`.
+    pub async fn bitcoin_send(
+        &self,
+        params: BitcoinSendParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let PlannedSend {
+            plan,
+            cluster,
+            keypairs,
+            ..
+        } = self.bitcoin_send_plan(params).await?;
+
+        let mut psbt = plan.to_psbt(&keypairs.iter().collect::<Vec<_>>())?;
+        let signed = psbt.sign(&keypairs.iter().collect::<Vec<_>>(), &[])?;
+        psbt.finalize(&signed)?;
+
+        let transaction = psbt.extract_transaction()?;
+
+        let txid = EsploraBackend::new_with_cluster(self.transport.clone(), cluster)
+            .broadcast(&transaction)
+            .await?;
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("txid", txid.to_string())
+            .set("fee", plan.fee().to_sat() as f64);
+
+        Ok(output)
+    }
+
+    /// Fetches the unspent outputs of the payment account, including its change address,
+    /// and builds the transaction requested in `params`
+    async fn bitcoin_send_plan(&self, params: BitcoinSendParams) -> AtollWalletResult<PlannedSend> {
+        let cluster = Self::bitcoin_cluster(params.chain.as_deref())?;
+
+        let recipient = Address::from_str(&params.recipient)
+            .map_err(|error| AtollWalletError::Bitcoin(format!("Invalid recipient. {error}")))?
+            .require_network(cluster.network())
+            .map_err(|error| AtollWalletError::Bitcoin(format!("Invalid recipient. {error}")))?;
+
+        if params.amount == 0 {
+            return Err(AtollWalletError::Input(
+                "`amount` must be a positive number of satoshis".to_string(),
+            ));
+        }
+
+        let (receive, change) = {
+            let vault = self.vault.read().await;
+            let vault = vault
+                .as_ref()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+            (
+                vault.bitcoin_keypair(BitcoinPurpose::Payment, cluster, 0)?,
+                vault.bitcoin_change_keypair(BitcoinPurpose::Payment, cluster, 0)?,
+            )
+        };
+
+        let backend = EsploraBackend::new_with_cluster(self.transport.clone(), cluster);
+
+        let sat_per_vb = match params.fee_rate {
+            Some(fee_rate) => fee_rate,
+            None => backend
+                .fee_estimates()
+                .await?
+                .fee_rate(BITCOIN_DEFAULT_CONFIRMATION_TARGET)
+                .unwrap_or(BitcoinSendBuilder::MIN_FEE_RATE)
+                .max(BitcoinSendBuilder::MIN_FEE_RATE),
+        };
+
+        let mut utxos = Vec::<BitcoinUtxo>::new();
+        for keypair in [&receive, &change] {
+            utxos.extend(backend.utxos(keypair.address()).await?);
+        }
+
+        let plan = BitcoinSendBuilder::new(utxos, change.address())
+            .add_recipient(&recipient, Amount::from_sat(params.amount))
+            .set_fee_rate(sat_per_vb)?
+            .set_rbf(params.rbf)
+            .build()?;
+
+        Ok(PlannedSend {
+            plan,
+            sat_per_vb,
+            cluster,
+            keypairs: [receive, change],
+        })
+    }
+}
+
+/// A transaction built for `atoll:bitcoinSend` with what is needed to sign and broadcast it
+struct PlannedSend {
+    plan: BitcoinSendPlan,
+    sat_per_vb: f64,
+    cluster: BitcoinCluster,
+    /// The payment account and its change address
+    keypairs: [BitcoinAccountKeypair; 2],
+}
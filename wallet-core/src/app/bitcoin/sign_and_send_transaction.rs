@@ -0,0 +1,36 @@
+use crate::{
+    App, AtollWalletResult, BitcoinBackend, BitcoinSignTransactionParams, DappFeature,
+    EsploraBackend, HttpTransport, ProtocolValue,
+};
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `bitcoin:signAndSendTransaction`. The params are the same as
+    /// `bitcoin:signTransaction` without `finalize` since every signed input is finalized.
+    /// The transaction is broadcast through the Esplora endpoint of `chain` and the output is `[{ txid }]`.
+    /// The dapp at `origin` must be connected with a policy allowing `transactions`.
+    pub async fn bitcoin_sign_and_send_transaction(
+        &self,
+        origin: Option<String>,
+        params: BitcoinSignTransactionParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        self.authorize_bitcoin_dapp(origin.as_deref(), DappFeature::Transactions, now_ms)
+            .await?;
+
+        let cluster = Self::bitcoin_cluster(params.chain.as_deref())?;
+
+        let (mut psbt, signed) = self.bitcoin_sign_psbt(params).await?;
+        psbt.finalize(&signed)?;
+
+        let transaction = psbt.extract_transaction()?;
+
+        let txid = EsploraBackend::new_with_cluster(self.transport.clone(), cluster)
+            .broadcast(&transaction)
+            .await?;
+
+        let mut output = ProtocolValue::new_object();
+        output.set("txid", txid.to_string());
+
+        Ok(ProtocolValue::Array(vec![output]))
+    }
+}
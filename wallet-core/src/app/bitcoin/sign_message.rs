@@ -0,0 +1,59 @@
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, BitcoinAccountParams, BitcoinMessage,
+    BitcoinMessageProtocol, DappFeature, HttpTransport, ProtocolValue,
+};
+
+/// The input of `bitcoin:signMessage`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct BitcoinSignMessageParams {
+    pub account: BitcoinAccountParams,
+    pub message: ByteBuf,
+    #[serde(default)]
+    pub protocol: Option<String>,
+    #[serde(default)]
+    pub chain: Option<String>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `bitcoin:signMessage`. The params are
+    /// `{ account: { address }, message: Uint8Array, protocol?: "bip322" | "legacy", chain? }`
+    /// and the output is `[{ signedMessage, signature, signatureType }]`. The dapp at `origin`
+    /// must be connected with a policy allowing `signMessage`.
+    pub async fn bitcoin_sign_message(
+        &self,
+        origin: Option<String>,
+        params: BitcoinSignMessageParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        self.authorize_bitcoin_dapp(origin.as_deref(), DappFeature::SignMessage, now_ms)
+            .await?;
+
+        let address = params.account.address;
+        let message_bytes = params.message.into_vec();
+
+        let protocol = match params.protocol.as_deref() {
+            Some(protocol) => BitcoinMessageProtocol::try_from(protocol)?,
+            None => BitcoinMessageProtocol::default(),
+        };
+
+        let keypair = self
+            .bitcoin_keypairs(Self::bitcoin_cluster(params.chain.as_deref())?)
+            .await?
+            .into_iter()
+            .find(|keypair| keypair.address().to_string() == address)
+            .ok_or(AtollWalletError::AccountNotFound(address))?;
+
+        let signature = BitcoinMessage::sign(&keypair, &message_bytes, protocol)?;
+
+        let mut signed_message_output = ProtocolValue::new_object();
+        signed_message_output
+            .set("signedMessage", ProtocolValue::new_bytes(&message_bytes))
+            .set("signature", ProtocolValue::new_bytes(&signature))
+            .set("signatureType", protocol.as_str());
+
+        Ok(ProtocolValue::Array(vec![signed_message_output]))
+    }
+}
@@ -0,0 +1,141 @@
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, BitcoinAccountKeypair, BitcoinAccountParams,
+    BitcoinCluster, BitcoinInputsToSign, BitcoinPsbt, BitcoinPurpose, DappFeature, HttpTransport,
+    ProtocolValue,
+};
+
+/// The input of `bitcoin:signTransaction` and `bitcoin:signAndSendTransaction`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitcoinSignTransactionParams {
+    pub psbt: ByteBuf,
+    #[serde(default)]
+    pub inputs_to_sign: Vec<BitcoinInputToSignParams>,
+    #[serde(default)]
+    pub chain: Option<String>,
+    #[serde(default)]
+    pub finalize: bool,
+}
+
+/// An input of `inputsToSign`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BitcoinInputToSignParams {
+    pub account: BitcoinAccountParams,
+    pub signing_indexes: Vec<usize>,
+    #[serde(default)]
+    pub sig_hash: Option<u32>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `bitcoin:signTransaction`. The params are
+    /// `{ psbt: Uint8Array, inputsToSign?: [{ account, signingIndexes, sigHash? }], chain?, finalize? }`
+    /// and the output is `[{ signedPsbt: Uint8Array }]`. The dapp at `origin` must be connected
+    /// with a policy allowing `transactions`.
+    pub async fn bitcoin_sign_transaction(
+        &self,
+        origin: Option<String>,
+        params: BitcoinSignTransactionParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        self.authorize_bitcoin_dapp(origin.as_deref(), DappFeature::Transactions, now_ms)
+            .await?;
+
+        let finalize = params.finalize;
+        let (mut psbt, signed) = self.bitcoin_sign_psbt(params).await?;
+
+        if finalize {
+            psbt.finalize(&signed)?;
+        }
+
+        let mut output = ProtocolValue::new_object();
+        output.set("signedPsbt", ProtocolValue::new_bytes(&psbt.to_bytes()?));
+
+        Ok(ProtocolValue::Array(vec![output]))
+    }
+
+    /// Signs the `psbt` of `params` with the inputs in `inputsToSign`, or every input
+    /// of the wallet if absent, and returns the PSBT with the indexes of the signed inputs
+    pub(crate) async fn bitcoin_sign_psbt(
+        &self,
+        params: BitcoinSignTransactionParams,
+    ) -> AtollWalletResult<(BitcoinPsbt, Vec<usize>)> {
+        let inputs_to_sign = params
+            .inputs_to_sign
+            .into_iter()
+            .map(|input| BitcoinInputsToSign {
+                address: input.account.address,
+                signing_indexes: input.signing_indexes,
+                sighash_type: input.sig_hash,
+            })
+            .collect::<Vec<BitcoinInputsToSign>>();
+
+        let keypairs = self
+            .bitcoin_keypairs(Self::bitcoin_cluster(params.chain.as_deref())?)
+            .await?;
+
+        let mut psbt = BitcoinPsbt::from_bytes(&params.psbt)?;
+        let signed = psbt.sign(&keypairs.iter().collect::<Vec<_>>(), &inputs_to_sign)?;
+
+        if signed.is_empty() {
+            return Err(AtollWalletError::Bitcoin(
+                "None of the inputs of the PSBT belong to this wallet".to_string(),
+            ));
+        }
+
+        Ok((psbt, signed))
+    }
+
+    /// Checks that the dapp at `origin` can use `feature` with the active account. What a
+    /// Bitcoin transaction spends is not counted in lamports so it is refused under an
+    /// auto-approve rule.
+    pub(crate) async fn authorize_bitcoin_dapp(
+        &self,
+        origin: Option<&str>,
+        feature: DappFeature,
+        now_ms: f64,
+    ) -> AtollWalletResult<()> {
+        let account = self
+            .active_account()
+            .await
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        self.authorize_dapp(origin, now_ms, |policy| {
+            policy.authorize(&account, feature)?;
+
+            match feature {
+                DappFeature::Transactions => policy.spend_undecoded(),
+                DappFeature::SignIn | DappFeature::SignMessage => Ok(()),
+            }
+        })
+        .await
+    }
+
+    /// Derives the payment and ordinals accounts and the payment change address on `cluster`
+    pub(crate) async fn bitcoin_keypairs(
+        &self,
+        cluster: BitcoinCluster,
+    ) -> AtollWalletResult<Vec<BitcoinAccountKeypair>> {
+        let vault = self.vault.read().await;
+        let vault = vault
+            .as_ref()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        Ok(vec![
+            vault.bitcoin_keypair(BitcoinPurpose::Payment, cluster, 0)?,
+            vault.bitcoin_keypair(BitcoinPurpose::Ordinals, cluster, 0)?,
+            vault.bitcoin_change_keypair(BitcoinPurpose::Payment, cluster, 0)?,
+        ])
+    }
+
+    /// The cluster of the `chain` of a request, mainnet without one
+    pub(crate) fn bitcoin_cluster(chain: Option<&str>) -> AtollWalletResult<BitcoinCluster> {
+        match chain {
+            Some(chain) => BitcoinCluster::from_chain(chain),
+            None => Ok(BitcoinCluster::default()),
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use wallet_standard_base::Cluster;
+
+use crate::{BitcoinAccountKeypair, ProtocolValue, StandardFeaturesBitcoin, ToProtocolValue};
+
+/// The wallet-standard account object of a [BitcoinAccountKeypair]
+pub struct BitcoinWalletAccount<'wa> {
+    keypair: &'wa BitcoinAccountKeypair,
+    features: StandardFeaturesBitcoin,
+}
+
+impl<'wa> BitcoinWalletAccount<'wa> {
+    pub fn new(keypair: &'wa BitcoinAccountKeypair) -> Self {
+        Self {
+            keypair,
+            features: StandardFeaturesBitcoin::new(),
+        }
+    }
+}
+
+impl ToProtocolValue for BitcoinWalletAccount<'_> {
+    /// The account object with the extra `purpose` and `addressType` fields
+    /// used by dapps to tell payment and ordinals accounts apart
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut wallet_account_object = ProtocolValue::new_object();
+        wallet_account_object
+            .set("address", self.keypair.address().to_string())
+            .set(
+                "publicKey",
+                ProtocolValue::new_bytes(&self.keypair.public_key_bytes()),
+            )
+            .set(
+                "chains",
+                ProtocolValue::new_str_array(&[self.keypair.cluster().chain()]),
+            )
+            .set(
+                "features",
+                ProtocolValue::new_str_array(&self.features.features()),
+            )
+            .set("purpose", self.keypair.purpose().as_str())
+            .set("addressType", self.keypair.purpose().address_type());
+
+        wallet_account_object
+    }
+}
@@ -0,0 +1,130 @@
+use crate::{
+    App, AtollWalletError, AtollWalletResult, ExtensionMessage, HttpTransport, ProtocolParams,
+    ProtocolRequest, ProtocolValue,
+};
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles a request sent by the page at `sender_origin` at `now_ms`, in milliseconds
+    /// since the unix epoch. Requests of dapps are screened by the phishing protection first
+    /// and the popup-only methods are refused unless the sender is the extension itself.
+    pub async fn handle<P: ProtocolParams>(
+        &self,
+        request: &ProtocolRequest<P>,
+        sender_origin: Option<String>,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        // Checked against the sender alone since the origin a request claims is not trusted
+        if request.header.method.is_popup_only()
+            && (sender_origin.is_none() || sender_origin != self.extension_origin)
+        {
+            return Err(AtollWalletError::PopupOnlyMethod(
+                request.header.method.as_str().to_string(),
+            ));
+        }
+
+        let origin = request.header.resolve_origin(sender_origin)?;
+
+        if let (true, Some(origin)) = (request.header.method.is_dapp_request(), origin.as_deref()) {
+            self.screen_domain(origin, now_ms).await?;
+        }
+
+        match request.header.method {
+            ExtensionMessage::StandardConnect => {
+                self.standard_connect(origin, request.params_or_default()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::SolanaSignIn => {
+                self.solana_sign_in(origin, request.params_or_default()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::SolanaSignMessage => {
+                self.solana_sign_message(origin, request.params()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::SolanaSignTransaction => {
+                self.solana_sign_transaction(origin, request.params()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::SolanaSignAndSendTransaction => {
+                self.solana_sign_and_send_transaction(origin, request.params()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::BitcoinConnect => {
+                self.bitcoin_connect(origin, request.params_or_default()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::BitcoinSignMessage => {
+                self.bitcoin_sign_message(origin, request.params()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::BitcoinSignTransaction => {
+                self.bitcoin_sign_transaction(origin, request.params()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::BitcoinSignAndSendTransaction => {
+                self.bitcoin_sign_and_send_transaction(origin, request.params()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::VaultStatus => self.vault_status().await,
+            ExtensionMessage::CreateVault => self.create_vault(request.params()?).await,
+            ExtensionMessage::RestoreVault => self.restore_vault(request.params()?).await,
+            ExtensionMessage::UnlockVault => self.unlock_vault(request.params()?).await,
+            ExtensionMessage::LockVault => self.lock_vault().await,
+            ExtensionMessage::VaultSplitShares => self.vault_split_shares(request.params()?).await,
+            ExtensionMessage::VaultRecoverFromShares => {
+                self.vault_recover_from_shares(request.params()?).await
+            }
+            ExtensionMessage::ListAccounts => self.list_accounts().await,
+            ExtensionMessage::AddWatchOnlyAccount => {
+                self.add_watch_only_account(request.params()?).await
+            }
+            ExtensionMessage::RemoveWatchOnlyAccount => {
+                self.remove_watch_only_account(request.params()?).await
+            }
+            ExtensionMessage::SetActiveAccount => self.set_active_account(request.params()?).await,
+            ExtensionMessage::BitcoinBalance => {
+                self.bitcoin_balance(request.params_or_default()?).await
+            }
+            ExtensionMessage::BitcoinEstimateSend => {
+                self.bitcoin_estimate_send(request.params()?).await
+            }
+            ExtensionMessage::BitcoinSend => self.bitcoin_send(request.params()?).await,
+            ExtensionMessage::SolanaPortfolio => {
+                self.solana_portfolio(request.params_or_default()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::SolanaEstimateSend => {
+                self.solana_estimate_send(request.params()?).await
+            }
+            ExtensionMessage::SolanaSend => self.solana_send(request.params()?, now_ms).await,
+            ExtensionMessage::SolanaPriorityFees => {
+                self.solana_priority_fees(request.params_or_default()?)
+                    .await
+            }
+            ExtensionMessage::SetSolanaPriorityFee => {
+                self.set_solana_priority_fee(request.params_or_default()?)
+                    .await
+            }
+            ExtensionMessage::SolanaActivity => {
+                self.solana_activity(request.params_or_default()?, now_ms)
+                    .await
+            }
+            ExtensionMessage::ListDappPolicies => self.list_dapp_policies(now_ms).await,
+            ExtensionMessage::SetDappPolicy => {
+                self.set_dapp_policy(request.params()?, now_ms).await
+            }
+            ExtensionMessage::SolanaPreviewMessage => {
+                self.solana_preview_message(request.params()?).await
+            }
+            ExtensionMessage::CheckDomain => self.check_domain(request.params()?, now_ms).await,
+            ExtensionMessage::UpdateDomainList => self.update_domain_list(request.params()?).await,
+            ExtensionMessage::SetDomainPolicy => self.set_domain_policy(request.params()?).await,
+            ExtensionMessage::ListConnectedSites => {
+                self.list_connected_sites(request.params_or_default()?)
+                    .await
+            }
+            ExtensionMessage::RevokeSite => self.revoke_site(request.params()?).await,
+            ExtensionMessage::RevokeAllSites => self.revoke_all_sites().await,
+        }
+    }
+}
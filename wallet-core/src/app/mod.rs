@@ -0,0 +1,15 @@
+mod state;
+pub use state::*;
+
+mod dispatch;
+
+mod solana;
+pub use solana::*;
+
+mod bitcoin;
+pub use bitcoin::*;
+
+mod permissions;
+
+mod vault;
+pub use vault::*;
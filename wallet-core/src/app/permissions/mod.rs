@@ -0,0 +1,7 @@
+mod policies;
+
+mod reputation;
+
+mod sites;
+
+mod values;
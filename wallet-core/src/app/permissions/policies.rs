@@ -0,0 +1,81 @@
+use crate::{
+    App, AtollWalletError, AtollWalletResult, DappPolicy, DappPolicyParams, HttpTransport,
+    ProtocolValue,
+};
+
+use super::values::policy_value;
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:listDappPolicies`, the output is an array of the policy of each
+    /// origin that connected
+    pub async fn list_dapp_policies(&self, now_ms: f64) -> AtollWalletResult<ProtocolValue> {
+        let vault = self.vault.read().await;
+        let vault = vault
+            .as_ref()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        Ok(vault
+            .policies()
+            .iter()
+            .map(|policy| policy_value(policy, now_ms))
+            .collect())
+    }
+
+    /// Handles `atoll:setDappPolicy`. The params are `{ origin, accounts?, chains?, features?,
+    /// autoApprove?: { lamportsPerDay } | null }` and the output is the updated policy.
+    pub async fn set_dapp_policy(
+        &self,
+        params: DappPolicyParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let output = {
+            let mut vault = self.vault.write().await;
+            let policies = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+                .policies_mut();
+
+            let existing = policies.get(&params.origin).cloned();
+            let policy = params.into_policy(existing)?;
+            let output = policy_value(&policy, now_ms);
+            policies.insert(policy);
+
+            output
+        };
+
+        self.save_policies().await?;
+
+        Ok(output)
+    }
+
+    /// Runs `check` on the policy of the connected dapp at `origin`, recording when the
+    /// site was last used and what the policy spent once it passes
+    pub(crate) async fn authorize_dapp<O>(
+        &self,
+        origin: Option<&str>,
+        now_ms: f64,
+        check: impl FnOnce(&mut DappPolicy) -> AtollWalletResult<O>,
+    ) -> AtollWalletResult<O> {
+        let output = {
+            let mut vault = self.vault.write().await;
+            let vault = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+            let output = check(vault.policies_mut().connected_mut(origin)?)?;
+
+            if let Some(origin) = origin {
+                vault.sites_mut().touch(origin, now_ms);
+            }
+
+            output
+        };
+
+        self.save_policies().await?;
+        if origin.is_some() {
+            self.save_sites().await?;
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{
+    App, AtollWalletError, AtollWalletResult, DomainAction, DomainCheckParams, DomainHost,
+    DomainListParams, DomainReputationPolicyParams, HttpTransport, ProtocolValue, ToProtocolValue,
+};
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:checkDomain` for the warning shown before a dapp is approved. The
+    /// output is the report on the host of `origin`, see [DomainReport](crate::DomainReport).
+    pub async fn check_domain(
+        &self,
+        params: DomainCheckParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let vault = self.vault.read().await;
+        let vault = vault
+            .as_ref()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        Ok(vault
+            .reputation()
+            .check(&params.origin, now_ms)?
+            .to_protocol_value())
+    }
+
+    /// Handles `atoll:updateDomainList` with a list file signed by the publisher set with
+    /// [App::set_domain_list_publisher]. The output is `{ sequence, blocklist, allowlist }`
+    /// with the number of domains on each list.
+    pub async fn update_domain_list(
+        &self,
+        params: DomainListParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let publisher = self.domain_list_publisher.ok_or(AtollWalletError::Input(
+            "This build has no domain list publisher".to_string(),
+        ))?;
+
+        let mut output = ProtocolValue::new_object();
+
+        {
+            let mut vault = self.vault.write().await;
+            let reputation = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+                .reputation_mut();
+
+            let sequence = reputation.update(&params.file, &publisher)?;
+
+            output
+                .set("sequence", sequence.to_string())
+                .set("blocklist", reputation.list().blocklist().count() as f64)
+                .set("allowlist", reputation.list().allowlist().count() as f64);
+        }
+
+        self.save_reputation().await?;
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:setDomainPolicy`, the output is the updated policy
+    pub async fn set_domain_policy(
+        &self,
+        params: DomainReputationPolicyParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let policy = {
+            let mut vault = self.vault.write().await;
+            let reputation = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+                .reputation_mut();
+
+            let policy = params.into_policy(*reputation.policy())?;
+            reputation.set_policy(policy);
+
+            policy
+        };
+
+        self.save_reputation().await?;
+
+        Ok(policy.to_protocol_value())
+    }
+
+    /// Screens a request of the dapp at `origin`, refusing blocked domains and passing
+    /// the warnings to the hook set with [App::set_on_domain_warning]. The reputation is
+    /// stored again when the host was not seen before.
+    pub(crate) async fn screen_domain(&self, origin: &str, now_ms: f64) -> AtollWalletResult<()> {
+        let (report, newly_seen) = {
+            let mut vault = self.vault.write().await;
+            let reputation = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+                .reputation_mut();
+
+            let newly_seen = reputation
+                .first_seen(&DomainHost::from_origin(origin)?)
+                .is_none();
+
+            (reputation.authorize(origin, now_ms)?, newly_seen)
+        };
+
+        if newly_seen {
+            self.save_reputation().await?;
+        }
+
+        if let (DomainAction::Warn, Some(on_domain_warning)) =
+            (report.action(), self.on_domain_warning.as_ref())
+        {
+            on_domain_warning(&report);
+        }
+
+        Ok(())
+    }
+}
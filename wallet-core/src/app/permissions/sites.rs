@@ -0,0 +1,65 @@
+use crate::{
+    App, AtollWalletError, AtollWalletResult, ConnectedSitesParams, HttpTransport, ProtocolValue,
+    RevokeSiteParams, ToProtocolValue,
+};
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:listConnectedSites`, the output is an array of the sites connected
+    /// with the `account` of the params or with any account
+    pub async fn list_connected_sites(
+        &self,
+        params: ConnectedSitesParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let account = params.account()?;
+
+        let vault = self.vault.read().await;
+        let sites = vault
+            .as_ref()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+            .sites();
+
+        Ok(sites
+            .iter()
+            .filter(|site| account.is_none_or(|account| site.accounts().contains(&account)))
+            .map(ToProtocolValue::to_protocol_value)
+            .collect())
+    }
+
+    /// Handles `atoll:revokeSite`, disconnecting the site at `origin`. The output is
+    /// `{ revoked }`, `false` when the site was not connected.
+    pub async fn revoke_site(&self, params: RevokeSiteParams) -> AtollWalletResult<ProtocolValue> {
+        let revoked = self
+            .vault
+            .write()
+            .await
+            .as_mut()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+            .revoke_site(&params.origin)
+            .is_some();
+
+        self.save_policies().await?;
+        self.save_sites().await?;
+
+        let mut output = ProtocolValue::new_object();
+        output.set("revoked", revoked);
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:revokeAllSites`, the output is an array of the origins that were
+    /// connected
+    pub async fn revoke_all_sites(&self) -> AtollWalletResult<ProtocolValue> {
+        let origins = self
+            .vault
+            .write()
+            .await
+            .as_mut()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+            .revoke_all_sites();
+
+        self.save_policies().await?;
+        self.save_sites().await?;
+
+        Ok(origins.into_iter().map(ProtocolValue::from).collect())
+    }
+}
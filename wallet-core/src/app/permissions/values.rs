@@ -0,0 +1,137 @@
+use wallet_standard_base::Cluster;
+
+use crate::{
+    ConnectedSite, DappPolicy, DomainReport, DomainReputationPolicy, DomainVerdict, ProtocolValue,
+    SignInSession, ToProtocolValue,
+};
+
+impl ToProtocolValue for DomainReport {
+    /// Converts to `{ host, unicodeHost, verdict, action, lookalikeOf?, reason? }` where
+    /// `verdict` is `trusted`, `blocklisted`, `lookalike`, `newlySeen` or `known` and
+    /// `action` is `allow`, `warn` or `block`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("host", self.host().ascii())
+            .set("unicodeHost", self.host().unicode())
+            .set("verdict", self.verdict().as_str())
+            .set("action", self.action().as_str());
+
+        if let DomainVerdict::Lookalike(domain) = self.verdict() {
+            output.set("lookalikeOf", *domain);
+        }
+
+        if let Some(reason) = self.reason() {
+            output.set("reason", reason);
+        }
+
+        output
+    }
+}
+
+impl ToProtocolValue for DomainReputationPolicy {
+    /// Converts to `{ blocklisted, lookalike, newlySeen, newlySeenDays }`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("blocklisted", self.blocklisted.as_str())
+            .set("lookalike", self.lookalike.as_str())
+            .set("newlySeen", self.newly_seen.as_str())
+            .set("newlySeenDays", self.newly_seen_days as f64);
+
+        output
+    }
+}
+
+impl ToProtocolValue for ConnectedSite {
+    /// Converts to `{ origin, icon, firstConnected, lastUsed, accounts, signIn }` with the
+    /// times in milliseconds since the unix epoch and `icon` and `signIn` possibly `null`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("origin", self.origin())
+            .set("icon", self.icon())
+            .set("firstConnected", self.first_connected_ms() as f64)
+            .set("lastUsed", self.last_used_ms() as f64)
+            .set(
+                "accounts",
+                self.accounts()
+                    .iter()
+                    .map(|account| ProtocolValue::from(account.to_string()))
+                    .collect::<ProtocolValue>(),
+            )
+            .set(
+                "signIn",
+                self.sign_in()
+                    .map_or(ProtocolValue::Null, ToProtocolValue::to_protocol_value),
+            );
+
+        output
+    }
+}
+
+impl ToProtocolValue for SignInSession {
+    /// Converts to `{ account, domain, chainId, nonce, issuedAt, expirationTime, signedAt }`
+    /// where the fields the message left out are `null`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("account", self.account().to_string())
+            .set("domain", self.domain())
+            .set("chainId", self.chain_id())
+            .set("nonce", self.nonce())
+            .set("issuedAt", self.issued_at())
+            .set("expirationTime", self.expiration_time())
+            .set("signedAt", self.signed_at_ms() as f64);
+
+        output
+    }
+}
+
+/// Converts a [DappPolicy] to `{ origin, accounts, chains, features,
+/// autoApprove }` where `autoApprove` is `{ lamportsPerDay, remainingToday }` as of `now_ms`,
+/// or `null`
+pub(crate) fn policy_value(policy: &DappPolicy, now_ms: f64) -> ProtocolValue {
+    let auto_approve = policy.auto_approve().map(|auto_approve| {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set(
+                "lamportsPerDay",
+                auto_approve.lamports_per_day().to_string(),
+            )
+            .set("remainingToday", auto_approve.remaining(now_ms).to_string());
+
+        output
+    });
+
+    let mut output = ProtocolValue::new_object();
+    output
+        .set("origin", policy.origin())
+        .set(
+            "accounts",
+            policy
+                .accounts()
+                .iter()
+                .map(|account| ProtocolValue::from(account.to_string()))
+                .collect::<ProtocolValue>(),
+        )
+        .set(
+            "chains",
+            policy
+                .chains()
+                .iter()
+                .map(|chain| ProtocolValue::from(chain.chain()))
+                .collect::<ProtocolValue>(),
+        )
+        .set(
+            "features",
+            policy
+                .features()
+                .iter()
+                .map(|feature| ProtocolValue::from(feature.as_str()))
+                .collect::<ProtocolValue>(),
+        )
+        .set("autoApprove", auto_approve);
+
+    output
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    App, AtollStorageKeys, AtollWalletError, AtollWalletResult, HttpTransport, ProtocolValue,
+    SolanaAccountKeypair, ToProtocolValue,
+};
+
+/// The version of the watch-only accounts document in the storage
+const WATCH_ONLY_VERSION: u16 = 1;
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Lists all accounts, both signing and watch-only. Each entry is a wallet account
+    /// object with the extra `watchOnly`, `signerKind` and `active` fields.
+    pub async fn list_accounts(&self) -> AtollWalletResult<ProtocolValue> {
+        let active_hash = *self.active.read().await;
+
+        Ok(self
+            .keypairs
+            .read()
+            .await
+            .iter()
+            .map(|(hash, keypair)| {
+                let wallet_account = keypair.get_wallet_account();
+                let mut account_object = wallet_account.to_protocol_value();
+
+                account_object
+                    .set("watchOnly", wallet_account.watch_only())
+                    .set("signerKind", keypair.signer_kind().as_str())
+                    .set("active", *hash == active_hash);
+
+                account_object
+            })
+            .collect())
+    }
+
+    /// Adds a watch-only account. `address` is the base58 address of the account.
+    pub async fn add_watch_only_account(
+        &self,
+        address: String,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let keypair = SolanaAccountKeypair::new_watch_only(&address)?;
+        let hash = Self::hash_active(&keypair);
+
+        let mut keypairs = self.keypairs.write().await;
+
+        if keypairs.contains_key(&hash) {
+            return Err(AtollWalletError::AccountAlreadyExists(address));
+        }
+
+        let account = keypair.get_wallet_account().to_protocol_value();
+        keypairs.insert(hash, keypair);
+        drop(keypairs);
+
+        self.save_watch_only().await?;
+
+        Ok(account)
+    }
+
+    /// Removes a watch-only account. Accounts derived from the vault
+    /// cannot be removed. `address` is the base58 address of the account.
+    pub async fn remove_watch_only_account(
+        &self,
+        address: String,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let hash = Self::hash_address(&address)?;
+
+        let mut keypairs = self.keypairs.write().await;
+
+        match keypairs.get(&hash) {
+            Some(keypair) if keypair.is_watch_only() => {
+                keypairs.remove(&hash);
+                drop(keypairs);
+
+                self.save_watch_only().await?;
+
+                Ok(true.into())
+            }
+            Some(_) => Err(AtollWalletError::Input(format!(
+                "The account `{address}` is not a watch-only account and cannot be removed"
+            ))),
+            None => Err(AtollWalletError::AccountNotFound(address)),
+        }
+    }
+
+    /// Sets the account used for connecting and signing. `address` is the base58 address of the account.
+    pub async fn set_active_account(&self, address: String) -> AtollWalletResult<ProtocolValue> {
+        let hash = Self::hash_address(&address)?;
+
+        let keypairs = self.keypairs.read().await;
+        let keypair = keypairs
+            .get(&hash)
+            .ok_or(AtollWalletError::AccountNotFound(address))?;
+
+        *self.active.write().await = hash;
+
+        Ok(keypair.get_wallet_account().to_protocol_value())
+    }
+
+    /// Writes the addresses of the watch-only accounts to the storage. The accounts stay
+    /// locked until they are written so that an older list never replaces a newer one.
+    pub(crate) async fn save_watch_only(&self) -> AtollWalletResult<()> {
+        let Some(storage) = self.storage.as_ref() else {
+            return Ok(());
+        };
+
+        let keypairs = self.keypairs.read().await;
+        let mut accounts = keypairs
+            .values()
+            .filter(|keypair| keypair.is_watch_only())
+            .map(|keypair| keypair.pubkey().to_string())
+            .collect::<Vec<String>>();
+        accounts.sort();
+
+        let stored = StoredWatchOnlyAccounts {
+            version: WATCH_ONLY_VERSION,
+            accounts,
+        };
+        let json = serde_json::to_string(&stored).map_err(|error| {
+            AtollWalletError::Input(format!(
+                "The watch-only accounts could not be serialized. {error}"
+            ))
+        })?;
+
+        storage
+            .set(AtollStorageKeys::WATCH_ONLY_ACCOUNTS, json)
+            .await
+    }
+
+    /// Adds the watch-only accounts of the storage that are not already listed
+    pub(crate) async fn load_watch_only(&self, json: &str) -> AtollWalletResult<()> {
+        let stored = serde_json::from_str::<StoredWatchOnlyAccounts>(json).map_err(|error| {
+            AtollWalletError::Input(format!(
+                "The watch-only accounts could not be read. {error}"
+            ))
+        })?;
+
+        if stored.version != WATCH_ONLY_VERSION {
+            return Err(AtollWalletError::Input(format!(
+                "The version `{}` of the watch-only accounts is not supported",
+                stored.version
+            )));
+        }
+
+        let mut keypairs = self.keypairs.write().await;
+        for address in stored.accounts {
+            let keypair = SolanaAccountKeypair::new_watch_only(&address)?;
+            keypairs
+                .entry(Self::hash_active(&keypair))
+                .or_insert(keypair);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredWatchOnlyAccounts {
+    version: u16,
+    accounts: Vec<String>,
+}
@@ -0,0 +1,97 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_signature::Signature;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, HttpTransport, ProtocolValue, SolanaActivityLog,
+    SolanaCluster, SolanaRpc, ToProtocolValueFor,
+};
+
+/// The input of `atoll:solanaActivity`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct SolanaActivityParams {
+    pub address: Option<String>,
+    pub chain: Option<String>,
+    pub before: Option<String>,
+    pub limit: Option<usize>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:solanaActivity`. The optional params are
+    /// `{ address?, chain?, before?, limit? }` where `address` defaults to the active account
+    /// and `before` is the `nextCursor` of the previous page. The history of the account on
+    /// chain is merged into the transactions the wallet signed before the page is read.
+    /// The output is `{ entries, nextCursor }`.
+    pub async fn solana_activity(
+        &self,
+        params: SolanaActivityParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let hash = match params.address.as_deref() {
+            Some(address) => Self::hash_address(address)?,
+            None => *self.active.read().await,
+        };
+
+        let owner = self
+            .keypairs
+            .read()
+            .await
+            .get(&hash)
+            .map(|keypair| keypair.pubkey())
+            .ok_or(AtollWalletError::AccountNotFound(
+                params.address.unwrap_or("active account".to_string()),
+            ))?;
+
+        let cluster = params
+            .chain
+            .map(|chain| SolanaCluster::from(chain.as_str()))
+            .unwrap_or_default();
+
+        let before = params
+            .before
+            .map(|before| {
+                Signature::from_str(before.trim()).or(Err(AtollWalletError::Input(format!(
+                    "`{before}` is not a valid signature"
+                ))))
+            })
+            .transpose()?;
+
+        let limit = params
+            .limit
+            .filter(|limit| *limit >= 1)
+            .map(|limit| limit.min(SolanaActivityLog::MAX_PAGE_SIZE))
+            .unwrap_or(SolanaActivityLog::DEFAULT_PAGE_SIZE);
+
+        // Synced on a copy so that the lock is not held across the requests
+        let mut log = self
+            .activity
+            .read()
+            .await
+            .get(&hash)
+            .cloned()
+            .unwrap_or_default();
+
+        let rpc = SolanaRpc::new(self.transport.clone(), cluster);
+        log.sync(&rpc, &owner, cluster, before.as_ref(), limit, now_ms)
+            .await?;
+
+        let output = log
+            .page(cluster, before.as_ref(), limit)?
+            .to_protocol_value(&owner);
+
+        let mut activity = self.activity.write().await;
+        let stored = activity.entry(hash).or_default();
+
+        // Transactions signed while the history was fetched are kept
+        for entry in stored.entries() {
+            if log.get(&entry.signature).is_none() {
+                log.record(entry.clone());
+            }
+        }
+        *stored = log;
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,28 @@
+mod accounts;
+
+mod activity;
+pub use activity::*;
+
+mod portfolio;
+pub use portfolio::*;
+
+mod priority_fees;
+pub use priority_fees::*;
+
+mod send;
+pub use send::*;
+
+mod sign_and_send_transaction;
+pub use sign_and_send_transaction::*;
+
+mod sign_in;
+
+mod sign_message;
+pub use sign_message::*;
+
+mod sign_transaction;
+pub use sign_transaction::*;
+
+mod standard_connect;
+
+mod values;
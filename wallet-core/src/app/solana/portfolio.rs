@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, HttpTransport, ProtocolValue, SolanaCluster,
+    SolanaPortfolio, SolanaRpc, ToProtocolValue,
+};
+
+/// The input of `atoll:solanaPortfolio`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct SolanaPortfolioParams {
+    pub address: Option<String>,
+    pub chain: Option<String>,
+    pub refresh: bool,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:solanaPortfolio`. The optional params are `{ address?, chain?, refresh? }`
+    /// where `address` defaults to the active account. The portfolio is cached per account and
+    /// fetched again once stale, when the chain changes or when `refresh` is `true`.
+    pub async fn solana_portfolio(
+        &self,
+        params: SolanaPortfolioParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let hash = match params.address.as_deref() {
+            Some(address) => Self::hash_address(address)?,
+            None => *self.active.read().await,
+        };
+
+        let owner = self
+            .keypairs
+            .read()
+            .await
+            .get(&hash)
+            .map(|keypair| keypair.pubkey())
+            .ok_or(AtollWalletError::AccountNotFound(
+                params.address.unwrap_or("active account".to_string()),
+            ))?;
+
+        let cluster = params
+            .chain
+            .map(|chain| SolanaCluster::from(chain.as_str()))
+            .unwrap_or_default();
+
+        if !params.refresh
+            && let Some(portfolio) = self.portfolios.read().await.get(&hash)
+            && portfolio.cluster == cluster
+            && !portfolio.is_stale(now_ms)
+        {
+            return Ok(portfolio.to_protocol_value());
+        }
+
+        let rpc = SolanaRpc::new(self.transport.clone(), cluster);
+        let portfolio = SolanaPortfolio::fetch(&rpc, owner, cluster, now_ms).await?;
+        let output = portfolio.to_protocol_value();
+
+        self.portfolios.write().await.insert(hash, portfolio);
+
+        Ok(output)
+    }
+}
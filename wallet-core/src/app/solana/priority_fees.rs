@@ -0,0 +1,87 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_pubkey::Pubkey;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, HttpTransport, ProtocolValue, SolanaCluster,
+    SolanaPriorityFees, SolanaPriorityLevel, SolanaRpc, ToProtocolValue,
+};
+
+/// The input of `atoll:solanaPriorityFees`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct SolanaPriorityFeesParams {
+    pub accounts: Vec<String>,
+    pub chain: Option<String>,
+}
+
+/// The input of `atoll:setSolanaPriorityFee`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(default)]
+pub struct SetSolanaPriorityFeeParams {
+    pub level: Option<String>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:solanaPriorityFees`. The optional params are `{ accounts?, chain? }`
+    /// where `accounts` are the base58 addresses a transaction writes to. The output is
+    /// `{ low, medium, high, level }` in micro-lamports per compute unit, where `level`
+    /// is the level opted into or `null`.
+    pub async fn solana_priority_fees(
+        &self,
+        params: SolanaPriorityFeesParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let cluster = params
+            .chain
+            .map(|chain| SolanaCluster::from(chain.as_str()))
+            .unwrap_or_default();
+
+        let accounts = params
+            .accounts
+            .iter()
+            .map(|account| {
+                Pubkey::from_str(account.trim()).or(Err(AtollWalletError::Input(format!(
+                    "`{account}` is not a valid Solana address"
+                ))))
+            })
+            .collect::<AtollWalletResult<Vec<Pubkey>>>()?;
+
+        let rpc = SolanaRpc::new(self.transport.clone(), cluster);
+        let fees = SolanaPriorityFees::fetch(&rpc, &accounts).await?;
+
+        let mut output = fees.to_protocol_value();
+        output.set(
+            "level",
+            self.priority_fee
+                .read()
+                .await
+                .map(|level| ProtocolValue::from(level.as_str())),
+        );
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:setSolanaPriorityFee`. The params are `{ level }` where `level` is
+    /// `low`, `medium` or `high` to have the compute budget of sent transactions set from a
+    /// simulation, or `null` to send transactions as built. The output is `{ level }`.
+    pub async fn set_solana_priority_fee(
+        &self,
+        params: SetSolanaPriorityFeeParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let level = params
+            .level
+            .map(|level| SolanaPriorityLevel::try_from(level.as_str()))
+            .transpose()?;
+
+        *self.priority_fee.write().await = level;
+
+        let mut output = ProtocolValue::new_object();
+        output.set(
+            "level",
+            level.map(|level| ProtocolValue::from(level.as_str())),
+        );
+
+        Ok(output)
+    }
+}
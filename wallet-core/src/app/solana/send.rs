@@ -0,0 +1,182 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use solana_pubkey::Pubkey;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, HttpTransport, ProtocolValue, SendOptions,
+    SolanaActivityEntry, SolanaCluster, SolanaComputeBudget, SolanaPriorityFees, SolanaRpc,
+    SolanaTokenTransfer, SolanaTransferBuilder, SolanaTransferPlan, WholeNumberParams,
+};
+
+/// The input of `atoll:solanaEstimateSend` and `atoll:solanaSend`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolanaSendParams {
+    pub recipient: String,
+    pub amount: WholeNumberParams,
+    #[serde(default)]
+    pub mint: Option<String>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub priority_fee: Option<u64>,
+    #[serde(default)]
+    pub compute_unit_limit: Option<u32>,
+    #[serde(default)]
+    pub chain: Option<String>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:solanaEstimateSend`. The params are
+    /// `{ recipient, amount, mint?, memo?, priorityFee?, computeUnitLimit?, chain? }` where
+    /// `amount` is in lamports, or the smallest unit of `mint`, as a number or string and
+    /// `priorityFee` is in micro-lamports per compute unit. Without `priorityFee` the fee suggested
+    /// for the priority level opted into is used, if any. The output is `{ networkFee,
+    /// transferFee, received, computeUnitLimit, priorityFee, createsRecipientAccount }`.
+    pub async fn solana_estimate_send(
+        &self,
+        params: SolanaSendParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let sender = self.solana_sender().await?;
+        let PlannedTransfer { plan, .. } = self.solana_transfer_plan(sender, params).await?;
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("networkFee", plan.network_fee() as f64)
+            .set("transferFee", plan.transfer_fee().to_string())
+            .set("received", plan.received().to_string())
+            .set("computeUnitLimit", plan.compute_unit_limit())
+            .set("priorityFee", plan.compute_unit_price() as f64)
+            .set("createsRecipientAccount", plan.creates_recipient_account());
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:solanaSend` with the same params as `atoll:solanaEstimateSend`.
+    /// The transfer is signed by the active account, sent and recorded in its activity,
+    /// and the output is `{ signature, networkFee }`.
+    pub async fn solana_send(
+        &self,
+        params: SolanaSendParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let sender = self.solana_sender().await?;
+        let PlannedTransfer { plan, cluster } = self.solana_transfer_plan(sender, params).await?;
+
+        let rpc = SolanaRpc::new(self.transport.clone(), cluster);
+        let blockhash = rpc.get_latest_blockhash().await?;
+
+        let hash = *self.active.read().await;
+        let transaction = {
+            let keypairs = self.keypairs.read().await;
+            let keypair = keypairs
+                .get(&hash)
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+            plan.sign(keypair, blockhash).await?
+        };
+
+        let signature = rpc
+            .send_transaction(&transaction, &SendOptions::default())
+            .await?;
+
+        // The balances changed so the next portfolio request fetches them again
+        self.portfolios.write().await.remove(&hash);
+
+        self.activity
+            .write()
+            .await
+            .entry(hash)
+            .or_default()
+            .record(SolanaActivityEntry::signed(
+                signature,
+                cluster,
+                None,
+                &transaction.message,
+                now_ms,
+            ));
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("signature", signature.to_string())
+            .set("networkFee", plan.network_fee() as f64);
+
+        Ok(output)
+    }
+
+    async fn solana_sender(&self) -> AtollWalletResult<Pubkey> {
+        self.active_account()
+            .await
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)
+    }
+
+    /// Builds the transfer requested in `params`, fetching the mint and the token account
+    /// of the recipient for token transfers
+    async fn solana_transfer_plan(
+        &self,
+        sender: Pubkey,
+        params: SolanaSendParams,
+    ) -> AtollWalletResult<PlannedTransfer> {
+        let cluster = params
+            .chain
+            .map(|chain| SolanaCluster::from(chain.as_str()))
+            .unwrap_or_default();
+
+        let parse_pubkey = |value: &str| {
+            Pubkey::from_str(value.trim()).or(Err(AtollWalletError::Input(format!(
+                "`{value}` is not a valid Solana address"
+            ))))
+        };
+
+        let recipient = parse_pubkey(&params.recipient)?;
+        let amount = params.amount.0;
+
+        let rpc = SolanaRpc::new(self.transport.clone(), cluster);
+        let mut builder = SolanaTransferBuilder::new(sender, recipient, amount);
+
+        if let Some(mint) = params.mint.as_deref() {
+            let mint = parse_pubkey(mint)?;
+
+            builder = builder.set_token(SolanaTokenTransfer::fetch(&rpc, &mint, &recipient).await?);
+        }
+
+        if let Some(memo) = params.memo {
+            builder = builder.set_memo(&memo);
+        }
+
+        let priority_level = *self.priority_fee.read().await;
+        match (params.priority_fee, priority_level) {
+            (Some(priority_fee), _) => builder = builder.set_priority_fee(priority_fee),
+            (None, Some(level)) => {
+                let writable_accounts = SolanaComputeBudget::writable_accounts(
+                    &builder
+                        .build()?
+                        .to_transaction(solana_hash::Hash::default())
+                        .message,
+                );
+                let priority_fee = SolanaPriorityFees::fetch(&rpc, &writable_accounts)
+                    .await?
+                    .fee(level);
+
+                builder = builder.set_priority_fee(priority_fee);
+            }
+            (None, None) => (),
+        }
+
+        if let Some(limit) = params.compute_unit_limit {
+            builder = builder.set_compute_unit_limit(limit);
+        }
+
+        Ok(PlannedTransfer {
+            plan: builder.build()?,
+            cluster,
+        })
+    }
+}
+
+/// A transfer built for `atoll:solanaSend` with the cluster it is sent to
+struct PlannedTransfer {
+    plan: SolanaTransferPlan,
+    cluster: SolanaCluster,
+}
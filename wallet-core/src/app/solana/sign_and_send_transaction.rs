@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+use solana_signature::Signature;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, DappFeature, HttpTransport, ProtocolValue,
+    SendOptions, SolanaAccountKeypair, SolanaAccountParams, SolanaActivityEntry, SolanaCluster,
+    SolanaComputeBudget, SolanaRpc, SolanaSpending, SolanaWireTransaction,
+};
+
+/// The input of `solana:signAndSendTransaction`
+#[derive(Debug, Deserialize)]
+pub struct SolanaSignAndSendTransactionParams {
+    pub account: SolanaAccountParams,
+    /// The transaction serialized with bincode
+    pub transaction: ByteBuf,
+    pub chain: String,
+    #[serde(default)]
+    pub options: Option<SendOptions>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Signs and sends the transaction of a dapp. The dapp at `origin` must be connected with a
+    /// policy allowing the `chain` and what the transaction spends before the cluster is reached.
+    /// With a priority level opted into, the compute budget of an unsigned transaction is then
+    /// set from a simulation before signing. The signature is recorded in the activity of the
+    /// account with the `origin` of the dapp.
+    pub async fn solana_sign_and_send_transaction(
+        &self,
+        origin: Option<String>,
+        params: SolanaSignAndSendTransactionParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let public_key = params.account.public_key.0;
+
+        let mut transaction = SolanaWireTransaction::decode(&params.transaction)?;
+
+        let cluster: SolanaCluster = params.chain.as_str().into();
+        let options = params.options.unwrap_or_default();
+
+        let active_hash = *self.active.read().await;
+        let keypairs = self.keypairs.read().await;
+        let active_keypair = keypairs
+            .get(&active_hash)
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        let account = active_keypair.pubkey();
+        let dapp_spending = SolanaSpending::of_message(&transaction.message, &account);
+
+        // Checked before the cluster is reached so a dapp that is not allowed to send
+        // transactions cannot make the wallet send any request
+        self.authorize_dapp(origin.as_deref(), now_ms, |policy| {
+            policy.authorize(&account, DappFeature::Transactions)?;
+            policy.authorize_chain(Some(cluster))?;
+            policy.spend(&account, &transaction.message, now_ms)
+        })
+        .await?;
+
+        let rpc = SolanaRpc::new(self.transport.clone(), cluster);
+        let blockhash = rpc.get_latest_blockhash().await?;
+
+        SolanaAccountKeypair::refresh_blockhash(&mut transaction, blockhash);
+
+        if let Some(level) = *self.priority_fee.read().await {
+            transaction = SolanaComputeBudget::inject(&rpc, transaction, level).await?;
+
+            // The priority fee the wallet adds counts against the allowance of the dapp too
+            let priority_fee = SolanaSpending::of_message(&transaction.message, &account)
+                .zip(dapp_spending)
+                .map(|(spent, dapp_spent)| spent.saturating_sub(dapp_spent))
+                .unwrap_or_default();
+
+            self.authorize_dapp(origin.as_deref(), now_ms, |policy| {
+                policy.spend_lamports(priority_fee, now_ms)
+            })
+            .await?;
+        }
+
+        let message = transaction.message.clone();
+
+        let json_string = active_keypair
+            .sign_and_send_transaction(
+                &self.transport,
+                public_key,
+                transaction,
+                options,
+                blockhash,
+                cluster,
+            )
+            .await?;
+
+        let response =
+            serde_json::from_str::<SendTransactionResponse>(&json_string).map_err(|error| {
+                AtollWalletError::SolanaRpc(format!(
+                    "Invalid response for `sendTransaction`. {error}. Response: {json_string}"
+                ))
+            })?;
+
+        let signature = match response {
+            SendTransactionResponse {
+                error: Some(error), ..
+            } => {
+                return Err(AtollWalletError::SolanaRpc(format!(
+                    "`sendTransaction` failed with code `{}`. {}",
+                    error.code, error.message
+                )));
+            }
+            SendTransactionResponse {
+                result: Some(result),
+                ..
+            } => Signature::from_str(&result).or(Err(AtollWalletError::SolanaRpc(format!(
+                "`{result}` is not a valid transaction signature"
+            ))))?,
+            _ => {
+                return Err(AtollWalletError::SolanaRpc(
+                    "`sendTransaction` returned no result".to_string(),
+                ));
+            }
+        };
+
+        self.activity
+            .write()
+            .await
+            .entry(active_hash)
+            .or_default()
+            .record(SolanaActivityEntry::signed(
+                signature, cluster, origin, &message, now_ms,
+            ));
+
+        let mut signature_object = ProtocolValue::new_object();
+        signature_object.set("signature", ProtocolValue::new_bytes(signature.as_ref()));
+
+        Ok(ProtocolValue::Array(vec![signature_object]))
+    }
+}
+
+/// The JSON-RPC response of `sendTransaction`, where the codes of the errors are negative
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+struct SendTransactionResponse {
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    error: Option<ErrorInfo>,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+struct ErrorInfo {
+    code: i64,
+    message: String,
+}
@@ -0,0 +1,64 @@
+use crate::{
+    App, AtollWalletError, AtollWalletResult, DappFeature, HttpTransport, ProtocolValue,
+    SignInInputParser, SignInSession, SolanaCluster, SolanaSignInParams, ToProtocolValue,
+};
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `solana:signIn` for the connected dapp at `origin`, checking the `chainId`
+    /// of the request against its policy when there is one
+    pub async fn solana_sign_in(
+        &self,
+        origin: Option<String>,
+        params: SolanaSignInParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let mut input = SignInInputParser::new(Self::system_time(now_ms)?);
+
+        let formatted_input = input.parse(&params)?.format();
+
+        let active_hash = *self.active.read().await;
+        let keypairs = self.keypairs.read().await;
+        let active_keypair = keypairs
+            .get(&active_hash)
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        self.authorize_dapp(origin.as_deref(), now_ms, |policy| {
+            policy.authorize(&active_keypair.pubkey(), DappFeature::SignIn)?;
+
+            match params.chain_id.as_deref() {
+                Some(chain) => policy.authorize_chain(Some(SolanaCluster::from(chain))),
+                None => Ok(()),
+            }
+        })
+        .await?;
+
+        let (wallet_account, signature) = active_keypair.sign_in(&formatted_input).await?;
+
+        if let (Some(origin), Ok(session)) = (
+            origin.as_deref(),
+            SignInSession::new(active_keypair.pubkey(), &formatted_input, now_ms),
+        ) {
+            self.vault
+                .write()
+                .await
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?
+                .sites_mut()
+                .sign_in(origin, session);
+
+            self.save_sites().await?;
+        }
+
+        let mut sign_in_output = ProtocolValue::new_object();
+        sign_in_output
+            .set("account", wallet_account.to_protocol_value())
+            .set(
+                "signedMessage",
+                ProtocolValue::new_bytes(formatted_input.as_bytes()),
+            )
+            .set("signature", ProtocolValue::new_bytes(&signature))
+            .set("signatureType", "ed25519");
+
+        Ok(ProtocolValue::Array(vec![sign_in_output]))
+    }
+}
@@ -0,0 +1,98 @@
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, DappFeature, HttpTransport, ProtocolValue,
+    SolanaAccountParams, SolanaMessageMode, SolanaSignableMessage,
+};
+
+/// The input of `solana:signMessage` and `atoll:solanaPreviewMessage`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct SolanaSignMessageParams {
+    pub account: SolanaAccountParams,
+    pub message: ByteBuf,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `solana:signMessage` for the connected dapp at `origin`. Messages that are
+    /// transactions are refused and off-chain messages must list the active account as a signer.
+    pub async fn solana_sign_message(
+        &self,
+        origin: Option<String>,
+        params: SolanaSignMessageParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let active_hash = *self.active.read().await;
+        let keypairs = self.keypairs.read().await;
+        let active_keypair = keypairs
+            .get(&active_hash)
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        self.authorize_dapp(origin.as_deref(), now_ms, |policy| {
+            policy.authorize(&active_keypair.pubkey(), DappFeature::SignMessage)
+        })
+        .await?;
+
+        let message =
+            SolanaSignableMessage::inspect(params.message.into_vec(), &active_keypair.pubkey())?;
+        let signature = active_keypair.sign_message(&message).await?;
+
+        let mut signed_message_output = ProtocolValue::new_object();
+        signed_message_output
+            .set("signedMessage", ProtocolValue::new_bytes(message.bytes()))
+            .set("signature", ProtocolValue::new_bytes(&signature))
+            .set("signatureType", "ed25519");
+
+        Ok(ProtocolValue::Array(vec![signed_message_output]))
+    }
+
+    /// Handles `atoll:solanaPreviewMessage` with the params of `solana:signMessage`,
+    /// giving what the user approves before the message is signed. The output is
+    /// `{ mode, preview: { kind, value } }` where `mode` is `raw` or `offchain` and `kind`
+    /// is `text` or `hex`. Off-chain messages add `{ format, applicationDomain, signers }`
+    /// with the application domain and signers in base58, and their preview is of the
+    /// message without the header.
+    pub async fn solana_preview_message(
+        &self,
+        params: SolanaSignMessageParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let signer = self
+            .active_account()
+            .await
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+        let message = SolanaSignableMessage::inspect(params.message.into_vec(), &signer)?;
+
+        let preview = message.preview();
+        let mut preview_output = ProtocolValue::new_object();
+        preview_output
+            .set("kind", preview.kind())
+            .set("value", preview.value());
+
+        let mut output = ProtocolValue::new_object();
+        output.set("preview", preview_output);
+
+        match message.mode() {
+            SolanaMessageMode::Raw => {
+                output.set("mode", "raw");
+            }
+            SolanaMessageMode::Offchain(offchain) => {
+                let signers = offchain
+                    .signers()
+                    .iter()
+                    .map(|signer| ProtocolValue::from(signer.to_string()))
+                    .collect::<ProtocolValue>();
+
+                output
+                    .set("mode", "offchain")
+                    .set("format", offchain.format().as_str())
+                    .set(
+                        "applicationDomain",
+                        bs58::encode(offchain.application_domain()).into_string(),
+                    )
+                    .set("signers", signers);
+            }
+        }
+
+        Ok(output)
+    }
+}
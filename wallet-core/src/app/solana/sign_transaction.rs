@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
+
+use crate::{
+    App, AtollWalletError, AtollWalletResult, DappFeature, HttpTransport, ProtocolValue,
+    SolanaAccountParams, SolanaCluster, SolanaWireTransaction,
+};
+
+/// The input of `solana:signTransaction`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct SolanaSignTransactionParams {
+    pub account: SolanaAccountParams,
+    /// The transaction serialized with bincode
+    pub transaction: ByteBuf,
+    #[serde(default)]
+    pub chain: Option<String>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `solana:signTransaction` for the connected dapp at `origin`, checking the
+    /// `chain` and what the transaction spends against its policy
+    pub async fn solana_sign_transaction(
+        &self,
+        origin: Option<String>,
+        params: SolanaSignTransactionParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let public_key = params.account.public_key.0;
+
+        let transaction = SolanaWireTransaction::decode(&params.transaction)?;
+        let chain = params.chain.as_deref().map(SolanaCluster::from);
+
+        let active_hash = *self.active.read().await;
+        let keypairs = self.keypairs.read().await;
+        let active_keypair = keypairs
+            .get(&active_hash)
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        self.authorize_dapp(origin.as_deref(), now_ms, |policy| {
+            let account = active_keypair.pubkey();
+
+            policy.authorize(&account, DappFeature::Transactions)?;
+            policy.authorize_chain(chain)?;
+            policy.spend(&account, &transaction.message, now_ms)
+        })
+        .await?;
+
+        let signed_transaction = active_keypair
+            .sign_transaction(&public_key, transaction)
+            .await?;
+        let signed_transaction_bytes = bincode::serialize(&signed_transaction).or(Err(
+            AtollWalletError::Input("Unable to encode signed transaction".to_string()),
+        ))?;
+
+        let mut signed_transaction_output = ProtocolValue::new_object();
+        signed_transaction_output.set(
+            "signedTransaction",
+            ProtocolValue::new_bytes(&signed_transaction_bytes),
+        );
+
+        Ok(ProtocolValue::Array(vec![signed_transaction_output]))
+    }
+}
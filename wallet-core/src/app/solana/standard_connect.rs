@@ -0,0 +1,56 @@
+use crate::{
+    App, AtollWalletError, AtollWalletResult, HttpTransport, ProtocolValue, StandardConnectParams,
+    ToProtocolValue,
+};
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Connects the dapp at `origin`, which is the origin of the page that sent the request,
+    /// and records it in the connected sites. See
+    /// [DappPolicies::connect](crate::DappPolicies::connect) for the accounts it is granted.
+    ///
+    /// The output is `{ accounts }`. A `silent` request connects a site that connected with
+    /// the account before and gives other sites no accounts rather than asking the user.
+    pub async fn standard_connect(
+        &self,
+        origin: Option<String>,
+        params: StandardConnectParams,
+        now_ms: f64,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let uri = origin.ok_or(AtollWalletError::InvalidRequest(
+            "`standard:connect` requires the origin of the page".to_string(),
+        ))?;
+
+        let active_hash = *self.active.read().await;
+        let keypairs = self.keypairs.read().await;
+        let active_keypair = keypairs
+            .get(&active_hash)
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+        let account = active_keypair.pubkey();
+
+        let mut accounts = ProtocolValue::new_array();
+
+        {
+            let mut vault = self.vault.write().await;
+            let vault = vault
+                .as_mut()
+                .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+            if !params.silent || vault.sites().is_trusted(&uri, &account) {
+                vault.policies_mut().connect(&uri, account)?;
+                vault
+                    .sites_mut()
+                    .connect(&uri, account, params.icon, now_ms);
+
+                accounts.push(active_keypair.standard_connect().to_protocol_value());
+            }
+        }
+
+        self.save_policies().await?;
+        self.save_sites().await?;
+
+        let mut output = ProtocolValue::new_object();
+        output.set("accounts", accounts);
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,192 @@
+use solana_pubkey::Pubkey;
+use wallet_standard_base::{Cluster, WalletAccount};
+
+use crate::{
+    ProtocolValue, SolanaActivityEntry, SolanaActivityPage, SolanaActivityRow, SolanaPortfolio,
+    SolanaPriorityFees, SolanaTokenHolding, SolanaWalletAccount, ToProtocolValue,
+    ToProtocolValueFor,
+};
+
+impl ToProtocolValue for SolanaWalletAccount<'_> {
+    /// Converts to the wallet-standard `{ address, publicKey, chains, features, icon, label }`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut wallet_account_object = ProtocolValue::new_object();
+        wallet_account_object
+            .set("address", self.address())
+            .set("publicKey", ProtocolValue::new_bytes(self.public_key()))
+            .set(
+                "chains",
+                ProtocolValue::new_str_array(self.chains().as_ref()),
+            )
+            .set(
+                "features",
+                ProtocolValue::new_str_array(self.features().as_ref()),
+            )
+            .set("icon", self.icon().map(|value| value.base64().to_string()))
+            .set("label", self.label());
+
+        wallet_account_object
+    }
+}
+
+impl ToProtocolValue for SolanaPriorityFees {
+    /// Converts to `{ low, medium, high }`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("low", self.low as f64)
+            .set("medium", self.medium as f64)
+            .set("high", self.high as f64);
+
+        output
+    }
+}
+
+impl ToProtocolValue for SolanaPortfolio {
+    /// Converts to `{ owner, chain, lamports, fetchedAt, holdings }`
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("owner", self.owner.to_string())
+            .set("chain", self.cluster.chain())
+            .set("lamports", self.lamports as f64)
+            .set("fetchedAt", self.fetched_at)
+            .set(
+                "holdings",
+                self.holdings
+                    .iter()
+                    .map(ToProtocolValue::to_protocol_value)
+                    .collect::<ProtocolValue>(),
+            );
+
+        output
+    }
+}
+
+impl ToProtocolValue for SolanaTokenHolding {
+    /// Converts to an object where the raw `amount` is a string since
+    /// it may not fit in a JavaScript number
+    fn to_protocol_value(&self) -> ProtocolValue {
+        let optional_string = |value: Option<&String>| -> ProtocolValue {
+            value
+                .filter(|value| !value.is_empty())
+                .map(|value| value.as_str())
+                .into()
+        };
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("address", self.address.to_string())
+            .set("mint", self.mint.to_string())
+            .set("program", self.program.as_str())
+            .set("amount", self.amount.to_string())
+            .set("decimals", u32::from(self.decimals))
+            .set("uiAmount", self.ui_amount)
+            .set(
+                "name",
+                optional_string(self.metadata.as_ref().map(|metadata| &metadata.name)),
+            )
+            .set(
+                "symbol",
+                optional_string(self.metadata.as_ref().map(|metadata| &metadata.symbol)),
+            )
+            .set(
+                "uri",
+                optional_string(self.metadata.as_ref().map(|metadata| &metadata.uri)),
+            )
+            .set("frozen", self.frozen)
+            .set("memoRequired", self.memo_required)
+            .set("nonTransferable", self.non_transferable)
+            .set(
+                "transferFeeBasisPoints",
+                self.transfer_fee.map(|fee| fee.basis_points),
+            )
+            .set(
+                "transferFeeMaximum",
+                self.transfer_fee.map(|fee| fee.maximum_fee.to_string()),
+            )
+            .set("interestRate", self.interest_rate.map(i32::from));
+
+        output
+    }
+}
+
+impl ToProtocolValueFor for SolanaActivityEntry {
+    /// Converts to `{ signature, chain, origin, signedAt, slot, blockTime, status, error,
+    /// fee, rows }` where the rows are described from the point of view of `owner`
+    fn to_protocol_value(&self, owner: &Pubkey) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("signature", self.signature.to_string())
+            .set("chain", self.cluster.chain())
+            .set("origin", self.origin.as_deref())
+            .set("signedAt", self.signed_at)
+            .set("slot", self.slot.map(|slot| slot as f64))
+            .set(
+                "blockTime",
+                self.block_time.map(|block_time| block_time as f64),
+            )
+            .set("status", self.status())
+            .set("error", self.error.as_deref())
+            .set("fee", self.fee.map(|fee| fee as f64))
+            .set(
+                "rows",
+                self.rows
+                    .iter()
+                    .map(|row| row.to_protocol_value(owner))
+                    .collect::<ProtocolValue>(),
+            );
+
+        output
+    }
+}
+
+impl ToProtocolValueFor for SolanaActivityPage<'_> {
+    /// Converts to `{ entries, nextCursor }`
+    fn to_protocol_value(&self, owner: &Pubkey) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output
+            .set(
+                "entries",
+                self.entries
+                    .iter()
+                    .map(|entry| entry.to_protocol_value(owner))
+                    .collect::<ProtocolValue>(),
+            )
+            .set(
+                "nextCursor",
+                self.next_cursor.map(|signature| signature.to_string()),
+            );
+
+        output
+    }
+}
+
+impl ToProtocolValueFor for SolanaActivityRow {
+    /// Converts to `{ kind, description, direction, amount, mint }` where `amount` is the raw
+    /// amount as a string for transfers and `direction` is relative to `owner`
+    fn to_protocol_value(&self, owner: &Pubkey) -> ProtocolValue {
+        let (amount, mint) = match self {
+            Self::SolTransfer { lamports, .. } | Self::CreateAccount { lamports, .. } => {
+                (Some(*lamports), None)
+            }
+            Self::TokenTransfer { amount, mint, .. } => (Some(*amount), *mint),
+            Self::CreateTokenAccount { mint, .. } => (None, Some(*mint)),
+            Self::Memo(_) | Self::ProgramCall { .. } => (None, None),
+        };
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("kind", self.kind())
+            .set("description", self.description(owner))
+            .set(
+                "direction",
+                self.direction(owner)
+                    .map(|direction| ProtocolValue::from(direction.as_str())),
+            )
+            .set("amount", amount.map(|amount| amount.to_string()))
+            .set("mint", mint.map(|mint| mint.to_string()));
+
+        output
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use async_lock::RwLock;
+use solana_pubkey::Pubkey;
+
+use crate::{
+    AtollStorageKeys, AtollWalletError, AtollWalletResult, ConnectedSites, DappPolicies,
+    DomainReport, DomainReputation, HttpTransport, SolanaAccountKeypair, SolanaActivityLog,
+    SolanaPortfolio, SolanaPriorityLevel, WalletStorage, WalletVault,
+};
+
+/// Called with the report of a dapp the phishing protection warns about
+pub type DomainWarningHook = Box<dyn Fn(&DomainReport)>;
+
+/// The state of the background and the handlers of the methods of [ExtensionMessage](crate::ExtensionMessage).
+///
+/// Everything the handlers reach outside the process goes through the [HttpTransport] and
+/// the time is passed with each request, so the extension only adapts the messages and
+/// the browser fetch API to it.
+pub struct App<T: HttpTransport + Clone> {
+    pub(crate) transport: T,
+    pub(crate) active: RwLock<blake3::Hash>,
+    pub(crate) keypairs: RwLock<HashMap<blake3::Hash, SolanaAccountKeypair>>,
+    pub(crate) vault: RwLock<Option<WalletVault>>,
+    pub(crate) portfolios: RwLock<HashMap<blake3::Hash, SolanaPortfolio>>,
+    /// The priority level the user opted into for the compute budget of sent transactions
+    pub(crate) priority_fee: RwLock<Option<SolanaPriorityLevel>>,
+    pub(crate) activity: RwLock<HashMap<blake3::Hash, SolanaActivityLog>>,
+    /// The origin of the pages of the extension, the only sender of the popup-only methods
+    pub(crate) extension_origin: Option<String>,
+    /// The key trusted to sign the domain lists
+    pub(crate) domain_list_publisher: Option<Pubkey>,
+    pub(crate) on_domain_warning: Option<DomainWarningHook>,
+    /// Where the vault and the permissions are kept across restarts of the background
+    pub(crate) storage: Option<Box<dyn WalletStorage>>,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// A background without a vault, reaching the clusters and backends over `transport`
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            active: RwLock::new(blake3::hash(&[0u8; 32])),
+            keypairs: RwLock::new(HashMap::default()),
+            vault: RwLock::new(Option::default()),
+            portfolios: RwLock::new(HashMap::default()),
+            priority_fee: RwLock::new(Option::default()),
+            activity: RwLock::new(HashMap::default()),
+            extension_origin: Option::default(),
+            domain_list_publisher: Option::default(),
+            on_domain_warning: Option::default(),
+            storage: Option::default(),
+        }
+    }
+
+    /// Opens `vault`, making its Solana account the active account
+    pub fn set_vault(mut self, vault: WalletVault) -> AtollWalletResult<Self> {
+        let keypair = vault.solana_keypair()?;
+        let hash = Self::hash_active(&keypair);
+
+        *self.active.get_mut() = hash;
+        self.keypairs.get_mut().insert(hash, keypair);
+        self.vault.get_mut().replace(vault);
+
+        Ok(self)
+    }
+
+    pub fn set_priority_level(mut self, level: SolanaPriorityLevel) -> Self {
+        self.priority_fee.get_mut().replace(level);
+
+        self
+    }
+
+    /// The origin of the pages of the extension, like `chrome-extension://<id>`. Without it
+    /// every method that is [popup-only](crate::ExtensionMessage::is_popup_only) is refused.
+    pub fn set_extension_origin(mut self, origin: &str) -> Self {
+        self.extension_origin.replace(origin.to_string());
+
+        self
+    }
+
+    /// The key trusted to sign the lists of `atoll:updateDomainList`
+    pub fn set_domain_list_publisher(mut self, publisher: Pubkey) -> Self {
+        self.domain_list_publisher.replace(publisher);
+
+        self
+    }
+
+    /// Called with the report of every dapp request the phishing protection lets through
+    /// with a warning, until there is a prompt to show it
+    pub fn set_on_domain_warning(mut self, hook: impl Fn(&DomainReport) + 'static) -> Self {
+        self.on_domain_warning.replace(Box::new(hook));
+
+        self
+    }
+
+    /// Keeps the sealed vault, the connected sites, the dapp policies, the domain
+    /// reputation and the watch-only accounts in `storage`, see [App::load_storage] to
+    /// read them back
+    pub fn set_storage(mut self, storage: impl WalletStorage + 'static) -> Self {
+        self.storage.replace(Box::new(storage));
+
+        self
+    }
+
+    /// Reads the watch-only accounts back from the storage, and the connected sites, the
+    /// dapp policies and the domain reputation into the open vault, if any
+    pub async fn load_storage(&self) -> AtollWalletResult<()> {
+        let Some(storage) = self.storage.as_ref() else {
+            return Ok(());
+        };
+
+        // Watch-only accounts hold no secret so they are listed while the vault is locked
+        if let Some(accounts) = storage.get(AtollStorageKeys::WATCH_ONLY_ACCOUNTS).await? {
+            self.load_watch_only(&accounts).await?;
+        }
+
+        let sites = storage.get(AtollStorageKeys::CONNECTED_SITES).await?;
+        let policies = storage.get(AtollStorageKeys::DAPP_POLICIES).await?;
+        let reputation = storage.get(AtollStorageKeys::DOMAIN_REPUTATION).await?;
+
+        let mut vault = self.vault.write().await;
+        let Some(vault) = vault.as_mut() else {
+            return Ok(());
+        };
+
+        if let Some(sites) = sites {
+            vault.set_sites(ConnectedSites::from_json(&sites)?);
+        }
+        if let Some(policies) = policies {
+            vault.set_policies(DappPolicies::from_json(&policies)?);
+        }
+        if let Some(reputation) = reputation {
+            vault.set_reputation(DomainReputation::from_json(&reputation)?);
+        }
+
+        Ok(())
+    }
+
+    /// Writes the connected sites of the open vault to the storage
+    pub(crate) async fn save_sites(&self) -> AtollWalletResult<()> {
+        self.save(AtollStorageKeys::CONNECTED_SITES, |vault| {
+            vault.sites().to_json()
+        })
+        .await
+    }
+
+    /// Writes the dapp policies of the open vault, with what they spent, to the storage
+    pub(crate) async fn save_policies(&self) -> AtollWalletResult<()> {
+        self.save(AtollStorageKeys::DAPP_POLICIES, |vault| {
+            vault.policies().to_json()
+        })
+        .await
+    }
+
+    /// Writes the domain reputation of the open vault, with the signed list, the policy
+    /// and the hosts seen, to the storage
+    pub(crate) async fn save_reputation(&self) -> AtollWalletResult<()> {
+        self.save(AtollStorageKeys::DOMAIN_REPUTATION, |vault| {
+            vault.reputation().to_json()
+        })
+        .await
+    }
+
+    /// Writes `to_json` of the open vault at `key`. The vault stays locked until it is
+    /// written so that an older state never replaces a newer one.
+    async fn save(
+        &self,
+        key: &str,
+        to_json: impl FnOnce(&WalletVault) -> String,
+    ) -> AtollWalletResult<()> {
+        let Some(storage) = self.storage.as_ref() else {
+            return Ok(());
+        };
+
+        let vault = self.vault.read().await;
+        if let Some(vault) = vault.as_ref() {
+            storage.set(key, to_json(vault)).await?;
+        }
+
+        Ok(())
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// The public key of the active account
+    pub async fn active_account(&self) -> Option<Pubkey> {
+        let active = *self.active.read().await;
+
+        self.keypairs
+            .read()
+            .await
+            .get(&active)
+            .map(|keypair| keypair.pubkey())
+    }
+
+    pub fn hash_active(keypair: &SolanaAccountKeypair) -> blake3::Hash {
+        blake3::hash(&keypair.pubkey().to_bytes())
+    }
+
+    /// The hash of a base58 address as used for the keys of the accounts
+    pub fn hash_address(address: &str) -> AtollWalletResult<blake3::Hash> {
+        let public_key = Pubkey::from_str(address.trim()).or(Err(AtollWalletError::Input(
+            format!("`{address}` is not a valid Solana address"),
+        )))?;
+
+        Ok(blake3::hash(&public_key.to_bytes()))
+    }
+
+    /// Converts the time of a request to a [SystemTime]
+    pub fn system_time(now_ms: f64) -> AtollWalletResult<SystemTime> {
+        UNIX_EPOCH
+            .checked_add(Duration::from_millis(now_ms.max(0.0) as u64))
+            .ok_or(AtollWalletError::Input(format!(
+                "`{now_ms}` is not a valid time in milliseconds since the unix epoch"
+            )))
+    }
+}
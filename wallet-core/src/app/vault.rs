@@ -0,0 +1,292 @@
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use crate::{
+    App, AtollStorageKeys, AtollWalletError, AtollWalletResult, DomainReputation, HttpTransport,
+    ProtocolValue, SignerKind, Slip39Config, Slip39Group, Slip39Recovery, ToProtocolValue,
+    WalletVault,
+};
+
+/// The input of `atoll:createVault`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateVaultParams {
+    pub password: Zeroizing<String>,
+    #[serde(default)]
+    pub passphrase: Option<Zeroizing<String>>,
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// The input of `atoll:restoreVault`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreVaultParams {
+    pub mnemonic: Zeroizing<String>,
+    pub password: Zeroizing<String>,
+    #[serde(default)]
+    pub passphrase: Option<Zeroizing<String>>,
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// The input of `atoll:unlockVault`
+#[derive(Debug, Deserialize)]
+pub struct UnlockVaultParams {
+    pub password: Zeroizing<String>,
+}
+
+/// The input of `atoll:vaultSplitShares`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultSplitSharesParams {
+    #[serde(default = "VaultSplitSharesParams::default_group_threshold")]
+    pub group_threshold: u8,
+    pub groups: Vec<VaultShareGroupParams>,
+    #[serde(default)]
+    pub passphrase: Zeroizing<String>,
+}
+
+impl VaultSplitSharesParams {
+    fn default_group_threshold() -> u8 {
+        1
+    }
+}
+
+/// A group of `groups` in [VaultSplitSharesParams]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultShareGroupParams {
+    pub member_threshold: u8,
+    pub member_count: u8,
+}
+
+/// The input of `atoll:vaultRecoverFromShares`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultRecoverFromSharesParams {
+    pub shares: Vec<Zeroizing<String>>,
+    /// The password the recovered vault is sealed with
+    pub password: Zeroizing<String>,
+    #[serde(default)]
+    pub passphrase: Zeroizing<String>,
+    #[serde(default)]
+    pub bip39_passphrase: Option<Zeroizing<String>>,
+    #[serde(default)]
+    pub replace: bool,
+}
+
+impl<T: HttpTransport + Clone> App<T> {
+    /// Handles `atoll:vaultStatus`, the output is `{ created, unlocked }` so that the popup
+    /// knows whether to onboard the user or ask for the password
+    pub async fn vault_status(&self) -> AtollWalletResult<ProtocolValue> {
+        let unlocked = self.vault.read().await.is_some();
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("created", unlocked || self.stored_vault().await?.is_some())
+            .set("unlocked", unlocked);
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:createVault`. The params are `{ password, passphrase?, replace? }` and
+    /// the output is `{ mnemonic, account }` with the new mnemonic to write down.
+    pub async fn create_vault(
+        &self,
+        params: CreateVaultParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let (vault, mnemonic) = WalletVault::new(params.passphrase)?;
+
+        let mut output = self
+            .replace_vault(vault, &params.password, params.replace)
+            .await?;
+        output.set("mnemonic", mnemonic.as_str());
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:restoreVault`. The params are `{ mnemonic, password, passphrase?,
+    /// replace? }` and the output is `{ account }`.
+    pub async fn restore_vault(
+        &self,
+        params: RestoreVaultParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let vault = WalletVault::new_from_mnemonic(params.mnemonic, params.passphrase)?;
+
+        self.replace_vault(vault, &params.password, params.replace)
+            .await
+    }
+
+    /// Handles `atoll:unlockVault`, opening the stored vault with `{ password }` and reading
+    /// its permissions back. The output is `{ account }`.
+    pub async fn unlock_vault(
+        &self,
+        params: UnlockVaultParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let sealed = self
+            .stored_vault()
+            .await?
+            .ok_or(AtollWalletError::InvalidParams(
+                "No vault has been created yet".to_string(),
+            ))?;
+
+        let output = self
+            .open_vault(WalletVault::unseal(&sealed, &params.password)?)
+            .await?;
+        self.load_storage().await?;
+
+        Ok(output)
+    }
+
+    /// Handles `atoll:lockVault`, removing the vault and its accounts from memory until
+    /// it is unlocked again
+    pub async fn lock_vault(&self) -> AtollWalletResult<ProtocolValue> {
+        self.keypairs
+            .write()
+            .await
+            .retain(|_, keypair| keypair.signer_kind() != SignerKind::Local);
+        self.vault.write().await.take();
+
+        Ok(ProtocolValue::Null)
+    }
+
+    /// Splits the vault seed into SLIP-39 shares.
+    ///
+    /// The params are `{ groupThreshold?, groups: [{ memberThreshold, memberCount }], passphrase? }`
+    /// and the output is an array of groups, each an array of mnemonic shares.
+    pub async fn vault_split_shares(
+        &self,
+        params: VaultSplitSharesParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let groups = params
+            .groups
+            .iter()
+            .map(|group| Slip39Group::new(group.member_threshold, group.member_count))
+            .collect::<Vec<Slip39Group>>();
+
+        let config = Slip39Config::new_with_groups(params.group_threshold, &groups);
+
+        let vault = self.vault.read().await;
+        let vault = vault
+            .as_ref()
+            .ok_or(AtollWalletError::UnauthorizedKeypairRequest)?;
+
+        let shares = vault.split_into_shares(&config, &params.passphrase)?;
+
+        Ok(shares
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|share| ProtocolValue::from(share.as_str()))
+                    .collect::<ProtocolValue>()
+            })
+            .collect())
+    }
+
+    /// Recovers the vault from SLIP-39 shares.
+    ///
+    /// The params are `{ shares: string[], password, passphrase?, bip39Passphrase?, replace? }`.
+    /// Until enough shares are provided the output describes how many more are needed. Once
+    /// the vault is recovered it is sealed with `password`, replaces the current vault and
+    /// the output contains the recovered account.
+    pub async fn vault_recover_from_shares(
+        &self,
+        params: VaultRecoverFromSharesParams,
+    ) -> AtollWalletResult<ProtocolValue> {
+        let mut recovery = Slip39Recovery::new();
+        params
+            .shares
+            .iter()
+            .try_for_each(|share| recovery.add_mnemonic(share).map(|_| ()))?;
+
+        let status = recovery.status();
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("complete", status.is_complete())
+            .set("groupsNeeded", status.groups_needed())
+            .set("sharesNeeded", status.shares_needed())
+            .set("message", status.describe());
+
+        if !status.is_complete() {
+            return Ok(output);
+        }
+
+        let vault = WalletVault::recover_from_shares(
+            &recovery,
+            &params.passphrase,
+            params.bip39_passphrase,
+        )?;
+        let replaced = self
+            .replace_vault(vault, &params.password, params.replace)
+            .await?;
+        output.set(
+            "account",
+            replaced.get("account").cloned().unwrap_or_default(),
+        );
+
+        Ok(output)
+    }
+
+    /// The sealed vault in the storage, if any
+    async fn stored_vault(&self) -> AtollWalletResult<Option<String>> {
+        match self.storage.as_ref() {
+            Some(storage) => storage.get(AtollStorageKeys::VAULT).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Seals `vault` with `password` in the storage and opens it. The vault starts without
+    /// connected sites or dapp policies but keeps the domain reputation. An existing vault
+    /// is only replaced when `replace` is set.
+    async fn replace_vault(
+        &self,
+        mut vault: WalletVault,
+        password: &str,
+        replace: bool,
+    ) -> AtollWalletResult<ProtocolValue> {
+        if !replace && (self.vault.read().await.is_some() || self.stored_vault().await?.is_some()) {
+            return Err(AtollWalletError::InvalidParams(
+                "A vault already exists. Set `replace` to replace it together with its connected sites".to_string(),
+            ));
+        }
+
+        let sealed = vault.seal(password)?;
+
+        if let Some(storage) = self.storage.as_ref() {
+            if let Some(reputation) = storage.get(AtollStorageKeys::DOMAIN_REPUTATION).await? {
+                vault.set_reputation(DomainReputation::from_json(&reputation)?);
+            }
+
+            storage.set(AtollStorageKeys::VAULT, sealed).await?;
+        }
+
+        let output = self.open_vault(vault).await?;
+
+        self.save_sites().await?;
+        self.save_policies().await?;
+
+        Ok(output)
+    }
+
+    /// Makes `vault` the open vault and its Solana account the active account, replacing
+    /// the accounts of the previous vault. The output is `{ account }`.
+    async fn open_vault(&self, vault: WalletVault) -> AtollWalletResult<ProtocolValue> {
+        let keypair = vault.solana_keypair()?;
+        let hash = Self::hash_active(&keypair);
+
+        let mut output = ProtocolValue::new_object();
+        output.set("account", keypair.get_wallet_account().to_protocol_value());
+
+        {
+            let mut keypairs = self.keypairs.write().await;
+            keypairs.retain(|_, keypair| keypair.signer_kind() != SignerKind::Local);
+            keypairs.insert(hash, keypair);
+        }
+        *self.active.write().await = hash;
+        self.vault.write().await.replace(vault);
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,53 @@
+/// Messages sent by the extension pages (popup) to the background
+/// which are not part of the wallet-standard.
+pub struct AtollConstants;
+
+impl AtollConstants {
+    pub const VAULT_STATUS: &str = "atoll:vaultStatus";
+    pub const CREATE_VAULT: &str = "atoll:createVault";
+    pub const RESTORE_VAULT: &str = "atoll:restoreVault";
+    pub const UNLOCK_VAULT: &str = "atoll:unlockVault";
+    pub const LOCK_VAULT: &str = "atoll:lockVault";
+    pub const VAULT_SPLIT_SHARES: &str = "atoll:vaultSplitShares";
+    pub const VAULT_RECOVER_FROM_SHARES: &str = "atoll:vaultRecoverFromShares";
+
+    pub const LIST_ACCOUNTS: &str = "atoll:listAccounts";
+    pub const ADD_WATCH_ONLY_ACCOUNT: &str = "atoll:addWatchOnlyAccount";
+    pub const REMOVE_WATCH_ONLY_ACCOUNT: &str = "atoll:removeWatchOnlyAccount";
+    pub const SET_ACTIVE_ACCOUNT: &str = "atoll:setActiveAccount";
+
+    pub const BITCOIN_BALANCE: &str = "atoll:bitcoinBalance";
+    pub const BITCOIN_ESTIMATE_SEND: &str = "atoll:bitcoinEstimateSend";
+    pub const BITCOIN_SEND: &str = "atoll:bitcoinSend";
+
+    pub const SOLANA_PORTFOLIO: &str = "atoll:solanaPortfolio";
+    pub const SOLANA_ESTIMATE_SEND: &str = "atoll:solanaEstimateSend";
+    pub const SOLANA_SEND: &str = "atoll:solanaSend";
+    pub const SOLANA_PRIORITY_FEES: &str = "atoll:solanaPriorityFees";
+    pub const SET_SOLANA_PRIORITY_FEE: &str = "atoll:setSolanaPriorityFee";
+    pub const SOLANA_ACTIVITY: &str = "atoll:solanaActivity";
+    pub const SOLANA_PREVIEW_MESSAGE: &str = "atoll:solanaPreviewMessage";
+
+    pub const LIST_DAPP_POLICIES: &str = "atoll:listDappPolicies";
+    pub const SET_DAPP_POLICY: &str = "atoll:setDappPolicy";
+
+    pub const CHECK_DOMAIN: &str = "atoll:checkDomain";
+    pub const UPDATE_DOMAIN_LIST: &str = "atoll:updateDomainList";
+    pub const SET_DOMAIN_POLICY: &str = "atoll:setDomainPolicy";
+
+    pub const LIST_CONNECTED_SITES: &str = "atoll:listConnectedSites";
+    pub const REVOKE_SITE: &str = "atoll:revokeSite";
+    pub const REVOKE_ALL_SITES: &str = "atoll:revokeAllSites";
+}
+
+/// The keys of the state the background keeps in its [WalletStorage](crate::WalletStorage)
+pub struct AtollStorageKeys;
+
+impl AtollStorageKeys {
+    /// The vault sealed with the password of the user, see [WalletVault::seal](crate::WalletVault::seal)
+    pub const VAULT: &str = "atoll:vault";
+    pub const CONNECTED_SITES: &str = "atoll:connectedSites";
+    pub const DAPP_POLICIES: &str = "atoll:dappPolicies";
+    pub const DOMAIN_REPUTATION: &str = "atoll:domainReputation";
+    pub const WATCH_ONLY_ACCOUNTS: &str = "atoll:watchOnlyAccounts";
+}
@@ -0,0 +1,225 @@
+pub type AtollWalletResult<T> = Result<T, AtollWalletError>;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum AtollWalletError {
+    #[error("Expected a JsValue to be of type js_sys::Object but it is of type `{0}`")]
+    JsValueIsNotAnObject(String),
+    #[error("Unable to check the type of `{0}`")]
+    UnableToCheckTypeOfJsValue(String),
+    #[error("Encountered an error when performing Bip39 operation. Error: `{0}`")]
+    Bip39(String),
+    #[error("Encountered an error when trying to convert a mnemonic to a keypair. Error: `{0}`")]
+    UnableToConvertMnemonicToKeypair(String),
+    #[error("extension.runtime is missing from extension object")]
+    ExtensionRuntimeIsMissing,
+    #[error("extension.runtime.onMessage is missing")]
+    ExtensionRuntimeMessageIsMissing,
+    #[error("extension.runtime.onMessage.addListener is missing in onMessage object")]
+    ExtensionRuntimeMessageAddListenerIsMissing,
+    #[error("extension.storage.local is missing from extension object")]
+    ExtensionStorageIsMissing,
+    #[error("{0}")]
+    JsCast(String),
+    #[error("{0}")]
+    Input(String),
+    #[error("The method `{0}` is not supported")]
+    UnsupportedExtensionMessage(String),
+    #[error("Invalid request. {0}")]
+    InvalidRequest(String),
+    #[error("The protocol version `{0}` is not supported")]
+    UnsupportedProtocolVersion(u16),
+    #[error("{0}")]
+    InvalidParams(String),
+    #[error("The mnemonic provided to reconstruct the Solana Keypair is invalid")]
+    UnableToRecoverSolanaKeypairFromMnemonic,
+    #[error("A request was made to authorize a dapp but a keypair doesn't exist yet")]
+    UnauthorizedKeypairRequest,
+    #[error("The `{0}` timestamp is not a valid ISO8601 timestamp.")]
+    InvalidIS08601Timestamp(String),
+    #[error("Unable to generate random bytes. Error: `{0}`")]
+    Random(String),
+    #[error("Invalid SLIP-39 share. {0}")]
+    InvalidSlip39Share(String),
+    #[error("Invalid SLIP-39 sharing configuration. {0}")]
+    InvalidSlip39Config(String),
+    #[error("The SLIP-39 shares do not belong together. {0}")]
+    Slip39ShareMismatch(String),
+    #[error("Not enough SLIP-39 shares to recover the wallet. {0}")]
+    InsufficientSlip39Shares(String),
+    #[error(
+        "The SLIP-39 shares could not be combined. Check that the shares and passphrase are correct"
+    )]
+    Slip39DigestMismatch,
+    #[error(
+        "The account `{0}` is watch-only and cannot sign. Use an account with a signer instead"
+    )]
+    WatchOnlyAccount(String),
+    #[error("The account `{0}` is not a required signer of the transaction")]
+    SignerNotRequired(String),
+    #[error("The message to sign is a Solana transaction. Request a transaction signature instead")]
+    MessageIsTransaction,
+    #[error("The dapp is not authorized. {0}")]
+    DappNotAuthorized(String),
+    #[error("The spending limit of the dapp does not allow the transaction. {0}")]
+    SpendingLimitExceeded(String),
+    #[error("The method `{0}` can only be called from the pages of the wallet")]
+    PopupOnlyMethod(String),
+    #[error("The site is blocked by the phishing protection. {0}")]
+    PhishingDomain(String),
+    #[error("The password does not unlock the vault")]
+    VaultPasswordIncorrect,
+    #[error("The user rejected the request. {0}")]
+    UserRejected(String),
+    #[error("Ledger device error. {0}")]
+    Ledger(String),
+    #[error("Remote signer error. {0}")]
+    RemoteSigner(String),
+    #[error("Air-gapped signer error. {0}")]
+    AirGapped(String),
+    #[error("The account `{0}` was not found in the wallet")]
+    AccountNotFound(String),
+    #[error("The account `{0}` already exists in the wallet")]
+    AccountAlreadyExists(String),
+    #[error("Bitcoin error. {0}")]
+    Bitcoin(String),
+    #[error("The Bitcoin chain `{0}` is not supported")]
+    UnsupportedBitcoinChain(String),
+    #[error("Solana RPC error. {0}")]
+    SolanaRpc(String),
+    #[error("Invalid SPL token account data. {0}")]
+    InvalidTokenAccountData(String),
+}
+
+impl From<bip39::ErrorKind> for AtollWalletError {
+    fn from(value: bip39::ErrorKind) -> Self {
+        Self::Bip39(value.to_string())
+    }
+}
+
+impl AtollWalletError {
+    /// The category sent to dapps, which decides the code of the error
+    pub fn category(&self) -> AtollWalletErrorCategory {
+        match self {
+            Self::UserRejected(_) => AtollWalletErrorCategory::UserRejected,
+            Self::UnauthorizedKeypairRequest
+            | Self::WatchOnlyAccount(_)
+            | Self::DappNotAuthorized(_)
+            | Self::SpendingLimitExceeded(_)
+            | Self::PopupOnlyMethod(_)
+            | Self::PhishingDomain(_)
+            | Self::VaultPasswordIncorrect => AtollWalletErrorCategory::Unauthorized,
+            Self::UnsupportedBitcoinChain(_) => AtollWalletErrorCategory::UnsupportedChain,
+            Self::InvalidRequest(_) | Self::UnsupportedProtocolVersion(_) => {
+                AtollWalletErrorCategory::InvalidRequest
+            }
+            Self::UnsupportedExtensionMessage(_) => AtollWalletErrorCategory::MethodNotFound,
+            Self::JsValueIsNotAnObject(_)
+            | Self::Bip39(_)
+            | Self::UnableToConvertMnemonicToKeypair(_)
+            | Self::UnableToRecoverSolanaKeypairFromMnemonic
+            | Self::Input(_)
+            | Self::InvalidParams(_)
+            | Self::InvalidIS08601Timestamp(_)
+            | Self::InvalidSlip39Share(_)
+            | Self::InvalidSlip39Config(_)
+            | Self::Slip39ShareMismatch(_)
+            | Self::InsufficientSlip39Shares(_)
+            | Self::Slip39DigestMismatch
+            | Self::SignerNotRequired(_)
+            | Self::MessageIsTransaction
+            | Self::AccountAlreadyExists(_) => AtollWalletErrorCategory::InvalidParams,
+            Self::AccountNotFound(_) => AtollWalletErrorCategory::ResourceNotFound,
+            Self::Ledger(_) | Self::RemoteSigner(_) | Self::AirGapped(_) | Self::SolanaRpc(_) => {
+                AtollWalletErrorCategory::ResourceUnavailable
+            }
+            Self::Bitcoin(_) => AtollWalletErrorCategory::TransactionRejected,
+            Self::UnableToCheckTypeOfJsValue(_)
+            | Self::ExtensionRuntimeIsMissing
+            | Self::ExtensionRuntimeMessageIsMissing
+            | Self::ExtensionRuntimeMessageAddListenerIsMissing
+            | Self::ExtensionStorageIsMissing
+            | Self::JsCast(_)
+            | Self::Random(_)
+            | Self::InvalidTokenAccountData(_) => AtollWalletErrorCategory::Internal,
+        }
+    }
+
+    /// The stable numeric code of the error, see [AtollWalletErrorCategory::code]
+    pub fn code(&self) -> i32 {
+        self.category().code()
+    }
+}
+
+/// What kind of failure an [AtollWalletError] is, so that dapps can tell a rejection
+/// from an invalid request or an unreachable RPC.
+///
+/// The codes are the EIP-1193 provider error codes and the JSON-RPC 2.0 codes with the
+/// EIP-1474 server error range, as used by the wallet adapters.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum AtollWalletErrorCategory {
+    /// `4001` the user rejected the request
+    UserRejected,
+    /// `4100` the account or method has not been authorized, or cannot sign
+    Unauthorized,
+    /// `4901` the wallet does not support the requested chain
+    UnsupportedChain,
+    /// `-32600` the request envelope is not valid
+    InvalidRequest,
+    /// `-32601` the method does not exist
+    MethodNotFound,
+    /// `-32602` the params of the method are not valid
+    InvalidParams,
+    /// `-32603` an error in the wallet itself
+    Internal,
+    /// `-32001` the requested resource, such as an account, does not exist
+    ResourceNotFound,
+    /// `-32002` a device, signer or RPC the request depends on is unavailable
+    ResourceUnavailable,
+    /// `-32003` the transaction could not be built or was rejected
+    TransactionRejected,
+}
+
+impl AtollWalletErrorCategory {
+    pub const ALL: &[Self] = &[
+        Self::UserRejected,
+        Self::Unauthorized,
+        Self::UnsupportedChain,
+        Self::InvalidRequest,
+        Self::MethodNotFound,
+        Self::InvalidParams,
+        Self::Internal,
+        Self::ResourceNotFound,
+        Self::ResourceUnavailable,
+        Self::TransactionRejected,
+    ];
+
+    pub fn code(&self) -> i32 {
+        match self {
+            Self::UserRejected => 4001,
+            Self::Unauthorized => 4100,
+            Self::UnsupportedChain => 4901,
+            Self::InvalidRequest => -32600,
+            Self::MethodNotFound => -32601,
+            Self::InvalidParams => -32602,
+            Self::Internal => -32603,
+            Self::ResourceNotFound => -32001,
+            Self::ResourceUnavailable => -32002,
+            Self::TransactionRejected => -32003,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::UserRejected => "userRejected",
+            Self::Unauthorized => "unauthorized",
+            Self::UnsupportedChain => "unsupportedChain",
+            Self::InvalidRequest => "invalidRequest",
+            Self::MethodNotFound => "methodNotFound",
+            Self::InvalidParams => "invalidParams",
+            Self::Internal => "internal",
+            Self::ResourceNotFound => "resourceNotFound",
+            Self::ResourceUnavailable => "resourceUnavailable",
+            Self::TransactionRejected => "transactionRejected",
+        }
+    }
+}
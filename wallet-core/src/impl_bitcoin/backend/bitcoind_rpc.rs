@@ -0,0 +1,220 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use bitcoin::{
+    Address, Amount, BlockHash, OutPoint, Transaction, Txid, consensus::encode::serialize_hex,
+};
+use serde::{Deserialize, de::DeserializeOwned};
+
+use crate::{
+    AtollWalletError, AtollWalletResult, BitcoinBackend, BitcoinBackendFuture, BitcoinFeeEstimates,
+    BitcoinTransactionStatus, BitcoinUtxo, HttpTransport, parse_txid,
+};
+
+/// A [BitcoinBackend] for the JSON-RPC interface of `bitcoind`, mostly for regtest.
+///
+/// `bitcoind` neither answers CORS requests nor accepts credentials in the URL,
+/// so `url` is expected to be a proxy that adds the RPC credentials. Unspent outputs
+/// are found with `scantxoutset` which does not need a wallet but only sees confirmed
+/// outputs, and transaction status requires `-txindex` once a transaction is confirmed.
+pub struct BitcoindRpcBackend<T: HttpTransport> {
+    transport: T,
+    url: String,
+}
+
+impl<T: HttpTransport> BitcoindRpcBackend<T> {
+    /// The confirmation targets queried with `estimatesmartfee`
+    pub const FEE_TARGETS: [u16; 6] = [1, 2, 3, 6, 144, 1008];
+
+    pub fn new(transport: T, url: &str) -> Self {
+        Self {
+            transport,
+            url: url.to_string(),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        self.url.as_str()
+    }
+
+    async fn call<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: jzon::JsonValue,
+    ) -> AtollWalletResult<R> {
+        let body = jzon::object! {
+            "jsonrpc": "1.0",
+            "id": "atoll",
+            "method": method,
+            "params": params,
+        }
+        .to_string();
+
+        let response = self.transport.post_json(&self.url, body).await?;
+
+        let response = serde_json::from_str::<RpcResponse<R>>(&response).map_err(|error| {
+            AtollWalletError::Bitcoin(format!(
+                "Invalid response from bitcoind for `{method}`. {error}. Response: {response}"
+            ))
+        })?;
+
+        match response {
+            RpcResponse {
+                error: Some(error), ..
+            } => Err(AtollWalletError::Bitcoin(format!(
+                "bitcoind `{method}` failed with code `{}`. {}",
+                error.code, error.message
+            ))),
+            RpcResponse {
+                result: Some(result),
+                ..
+            } => Ok(result),
+            _ => Err(AtollWalletError::Bitcoin(format!(
+                "bitcoind `{method}` returned no result"
+            ))),
+        }
+    }
+}
+
+impl<T: HttpTransport> BitcoinBackend for BitcoindRpcBackend<T> {
+    fn utxos<'a>(&'a self, address: &'a Address) -> BitcoinBackendFuture<'a, Vec<BitcoinUtxo>> {
+        Box::pin(async move {
+            let scan = self
+                .call::<ScanTxOutSet>(
+                    "scantxoutset",
+                    jzon::array!["start", jzon::array![format!("addr({address})")]],
+                )
+                .await?;
+
+            let script_pubkey = address.script_pubkey();
+
+            scan.unspents
+                .into_iter()
+                .map(|unspent| {
+                    Ok(BitcoinUtxo {
+                        outpoint: OutPoint::new(parse_txid(&unspent.txid)?, unspent.vout),
+                        value: btc_to_amount(unspent.amount)?,
+                        script_pubkey: script_pubkey.clone(),
+                        status: BitcoinTransactionStatus {
+                            confirmed: true,
+                            block_height: Some(unspent.height),
+                            ..Default::default()
+                        },
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn fee_estimates(&self) -> BitcoinBackendFuture<'_, BitcoinFeeEstimates> {
+        Box::pin(async move {
+            let mut estimates = BTreeMap::<u16, f64>::new();
+
+            for target in Self::FEE_TARGETS {
+                let estimate = self
+                    .call::<EstimateSmartFee>("estimatesmartfee", jzon::array![target])
+                    .await?;
+
+                // The fee rate is in BTC/kvB and is missing until bitcoind has seen enough blocks
+                if let Some(fee_rate) = estimate.feerate {
+                    estimates.insert(target, fee_rate * 100_000.0);
+                }
+            }
+
+            Ok(BitcoinFeeEstimates::new(estimates))
+        })
+    }
+
+    fn tip_height(&self) -> BitcoinBackendFuture<'_, u32> {
+        Box::pin(async move { self.call::<u32>("getblockcount", jzon::array![]).await })
+    }
+
+    fn broadcast<'a>(&'a self, transaction: &'a Transaction) -> BitcoinBackendFuture<'a, Txid> {
+        Box::pin(async move {
+            let txid = self
+                .call::<String>(
+                    "sendrawtransaction",
+                    jzon::array![serialize_hex(transaction)],
+                )
+                .await?;
+
+            parse_txid(&txid)
+        })
+    }
+
+    fn transaction_status<'a>(
+        &'a self,
+        txid: &'a Txid,
+    ) -> BitcoinBackendFuture<'a, BitcoinTransactionStatus> {
+        Box::pin(async move {
+            let transaction = self
+                .call::<RawTransaction>("getrawtransaction", jzon::array![txid.to_string(), true])
+                .await?;
+
+            let Some(block_hash) = transaction.blockhash else {
+                return Ok(BitcoinTransactionStatus::default());
+            };
+
+            let header = self
+                .call::<BlockHeader>("getblockheader", jzon::array![block_hash.as_str()])
+                .await?;
+
+            let block_hash = BlockHash::from_str(&block_hash).or(Err(
+                AtollWalletError::Bitcoin(format!("`{block_hash}` is not a valid block hash")),
+            ))?;
+
+            Ok(BitcoinTransactionStatus {
+                confirmed: true,
+                block_height: Some(header.height),
+                block_hash: Some(block_hash),
+                block_time: transaction.blocktime,
+            })
+        })
+    }
+}
+
+fn btc_to_amount(btc: f64) -> AtollWalletResult<Amount> {
+    Amount::from_btc(btc).map_err(|error| {
+        AtollWalletError::Bitcoin(format!("`{btc}` is not a valid BTC amount. {error}"))
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<R> {
+    result: Option<R>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanTxOutSet {
+    unspents: Vec<ScanTxOutSetUnspent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanTxOutSetUnspent {
+    txid: String,
+    vout: u32,
+    amount: f64,
+    height: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct EstimateSmartFee {
+    feerate: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawTransaction {
+    blockhash: Option<String>,
+    blocktime: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BlockHeader {
+    height: u32,
+}
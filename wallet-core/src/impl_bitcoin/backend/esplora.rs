@@ -0,0 +1,161 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use bitcoin::{
+    Address, Amount, BlockHash, OutPoint, Transaction, Txid, consensus::encode::serialize_hex,
+};
+use serde::{Deserialize, de::DeserializeOwned};
+use wallet_standard_base::Cluster;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, BitcoinBackend, BitcoinBackendFuture, BitcoinCluster,
+    BitcoinFeeEstimates, BitcoinTransactionStatus, BitcoinUtxo, HttpTransport,
+};
+
+/// A [BitcoinBackend] for the [Esplora HTTP API](https://github.com/Blockstream/esplora/blob/master/API.md)
+/// served by blockstream.info, mempool.space and `electrs`
+pub struct EsploraBackend<T: HttpTransport> {
+    transport: T,
+    base_url: String,
+}
+
+impl<T: HttpTransport> EsploraBackend<T> {
+    pub fn new(transport: T, base_url: &str) -> Self {
+        Self {
+            transport,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Uses the Esplora endpoint of `cluster`
+    pub fn new_with_cluster(transport: T, cluster: BitcoinCluster) -> Self {
+        Self::new(transport, cluster.endpoint())
+    }
+
+    pub fn base_url(&self) -> &str {
+        self.base_url.as_str()
+    }
+
+    async fn get<R: DeserializeOwned>(&self, path: &str) -> AtollWalletResult<R> {
+        let response = self
+            .transport
+            .get(&format!("{}{path}", self.base_url))
+            .await?;
+
+        serde_json::from_str::<R>(&response).map_err(|error| {
+            AtollWalletError::Bitcoin(format!(
+                "Invalid response from Esplora for `{path}`. {error}. Response: {response}"
+            ))
+        })
+    }
+}
+
+impl<T: HttpTransport> BitcoinBackend for EsploraBackend<T> {
+    fn utxos<'a>(&'a self, address: &'a Address) -> BitcoinBackendFuture<'a, Vec<BitcoinUtxo>> {
+        Box::pin(async move {
+            let utxos = self
+                .get::<Vec<EsploraUtxo>>(&format!("/address/{address}/utxo"))
+                .await?;
+
+            let script_pubkey = address.script_pubkey();
+
+            utxos
+                .into_iter()
+                .map(|utxo| {
+                    Ok(BitcoinUtxo {
+                        outpoint: OutPoint::new(parse_txid(&utxo.txid)?, utxo.vout),
+                        value: Amount::from_sat(utxo.value),
+                        script_pubkey: script_pubkey.clone(),
+                        status: utxo.status.try_into()?,
+                    })
+                })
+                .collect()
+        })
+    }
+
+    fn fee_estimates(&self) -> BitcoinBackendFuture<'_, BitcoinFeeEstimates> {
+        Box::pin(async move {
+            let estimates = self
+                .get::<BTreeMap<String, f64>>("/fee-estimates")
+                .await?
+                .into_iter()
+                .filter_map(|(blocks, fee_rate)| {
+                    blocks.parse::<u16>().ok().map(|blocks| (blocks, fee_rate))
+                })
+                .collect();
+
+            Ok(BitcoinFeeEstimates::new(estimates))
+        })
+    }
+
+    fn tip_height(&self) -> BitcoinBackendFuture<'_, u32> {
+        Box::pin(async move { self.get::<u32>("/blocks/tip/height").await })
+    }
+
+    fn broadcast<'a>(&'a self, transaction: &'a Transaction) -> BitcoinBackendFuture<'a, Txid> {
+        Box::pin(async move {
+            let response = self
+                .transport
+                .post_text(&format!("{}/tx", self.base_url), serialize_hex(transaction))
+                .await?;
+
+            Txid::from_str(response.trim()).or(Err(AtollWalletError::Bitcoin(format!(
+                "The transaction was rejected by Esplora. {response}"
+            ))))
+        })
+    }
+
+    fn transaction_status<'a>(
+        &'a self,
+        txid: &'a Txid,
+    ) -> BitcoinBackendFuture<'a, BitcoinTransactionStatus> {
+        Box::pin(async move {
+            self.get::<EsploraStatus>(&format!("/tx/{txid}/status"))
+                .await?
+                .try_into()
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraUtxo {
+    txid: String,
+    vout: u32,
+    value: u64,
+    status: EsploraStatus,
+}
+
+#[derive(Debug, Deserialize)]
+struct EsploraStatus {
+    confirmed: bool,
+    block_height: Option<u32>,
+    block_hash: Option<String>,
+    block_time: Option<u64>,
+}
+
+impl TryFrom<EsploraStatus> for BitcoinTransactionStatus {
+    type Error = AtollWalletError;
+
+    fn try_from(value: EsploraStatus) -> Result<Self, Self::Error> {
+        let block_hash = value
+            .block_hash
+            .map(|block_hash| {
+                BlockHash::from_str(&block_hash).or(Err(AtollWalletError::Bitcoin(format!(
+                    "`{block_hash}` is not a valid block hash"
+                ))))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            confirmed: value.confirmed,
+            block_height: value.block_height,
+            block_hash,
+            block_time: value.block_time,
+        })
+    }
+}
+
+pub(crate) fn parse_txid(txid: &str) -> AtollWalletResult<Txid> {
+    Txid::from_str(txid).or(Err(AtollWalletError::Bitcoin(format!(
+        "`{txid}` is not a valid txid"
+    ))))
+}
@@ -0,0 +1,124 @@
+mod esplora;
+pub use esplora::*;
+
+mod bitcoind_rpc;
+pub use bitcoind_rpc::*;
+
+use std::{collections::BTreeMap, future::Future, pin::Pin};
+
+use bitcoin::{Address, Amount, BlockHash, OutPoint, ScriptBuf, Transaction, Txid};
+
+use crate::AtollWalletResult;
+
+/// The future returned by a [BitcoinBackend]
+pub type BitcoinBackendFuture<'a, T> = Pin<Box<dyn Future<Output = AtollWalletResult<T>> + 'a>>;
+
+/// A service that indexes the Bitcoin chain for the wallet
+pub trait BitcoinBackend {
+    /// The unspent outputs paying to `address`, including unconfirmed ones if the backend
+    /// tracks the mempool
+    fn utxos<'a>(&'a self, address: &'a Address) -> BitcoinBackendFuture<'a, Vec<BitcoinUtxo>>;
+
+    /// The fee rates needed to confirm within a number of blocks
+    fn fee_estimates(&self) -> BitcoinBackendFuture<'_, BitcoinFeeEstimates>;
+
+    /// The height of the best block
+    fn tip_height(&self) -> BitcoinBackendFuture<'_, u32>;
+
+    /// Broadcasts a fully signed `transaction` and returns its txid
+    fn broadcast<'a>(&'a self, transaction: &'a Transaction) -> BitcoinBackendFuture<'a, Txid>;
+
+    /// Whether the transaction `txid` is confirmed and where
+    fn transaction_status<'a>(
+        &'a self,
+        txid: &'a Txid,
+    ) -> BitcoinBackendFuture<'a, BitcoinTransactionStatus>;
+
+    /// The confirmed and unconfirmed balance of `address`
+    fn balance<'a>(&'a self, address: &'a Address) -> BitcoinBackendFuture<'a, BitcoinBalance> {
+        Box::pin(async move {
+            let utxos = self.utxos(address).await?;
+
+            Ok(BitcoinBalance::from_utxos(&utxos))
+        })
+    }
+}
+
+/// An unspent transaction output owned by the wallet
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BitcoinUtxo {
+    pub outpoint: OutPoint,
+    pub value: Amount,
+    pub script_pubkey: ScriptBuf,
+    pub status: BitcoinTransactionStatus,
+}
+
+/// The confirmation status of a transaction
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct BitcoinTransactionStatus {
+    pub confirmed: bool,
+    pub block_height: Option<u32>,
+    pub block_hash: Option<BlockHash>,
+    pub block_time: Option<u64>,
+}
+
+impl BitcoinTransactionStatus {
+    /// The number of confirmations when the best block is at `tip_height`
+    pub fn confirmations(&self, tip_height: u32) -> u32 {
+        match self.block_height {
+            Some(block_height) if self.confirmed && tip_height >= block_height => {
+                tip_height - block_height + 1
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// The balance of an address
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct BitcoinBalance {
+    pub confirmed: Amount,
+    pub unconfirmed: Amount,
+}
+
+impl BitcoinBalance {
+    pub fn from_utxos(utxos: &[BitcoinUtxo]) -> Self {
+        utxos.iter().fold(Self::default(), |mut balance, utxo| {
+            if utxo.status.confirmed {
+                balance.confirmed += utxo.value;
+            } else {
+                balance.unconfirmed += utxo.value;
+            }
+
+            balance
+        })
+    }
+
+    pub fn total(&self) -> Amount {
+        self.confirmed + self.unconfirmed
+    }
+}
+
+/// Fee rates in sat/vB keyed by the number of blocks to confirm within
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct BitcoinFeeEstimates(BTreeMap<u16, f64>);
+
+impl BitcoinFeeEstimates {
+    pub fn new(estimates: BTreeMap<u16, f64>) -> Self {
+        Self(estimates)
+    }
+
+    pub fn estimates(&self) -> &BTreeMap<u16, f64> {
+        &self.0
+    }
+
+    /// The fee rate to confirm within `blocks`, from the closest target that is not slower.
+    /// Falls back to the fastest target when every estimate is slower than `blocks`.
+    pub fn fee_rate(&self, blocks: u16) -> Option<f64> {
+        self.0
+            .range(..=blocks)
+            .next_back()
+            .or_else(|| self.0.first_key_value())
+            .map(|(_, fee_rate)| *fee_rate)
+    }
+}
@@ -0,0 +1,93 @@
+use bitcoin::Network;
+use wallet_standard_base::Cluster;
+
+use crate::{AtollWalletError, AtollWalletResult, BitcoinConstants};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Default, Hash)]
+pub enum BitcoinCluster {
+    /// Bitcoin Mainnet, e.g. [https://blockstream.info/api](https://blockstream.info/api)
+    Mainnet,
+    /// Bitcoin Testnet, e.g. [https://blockstream.info/testnet/api](https://blockstream.info/testnet/api)
+    Testnet,
+    /// Bitcoin Signet, e.g. [https://mempool.space/signet/api](https://mempool.space/signet/api)
+    Signet,
+    /// A local regtest node with an Esplora server, e.g. [http://localhost:3002](http://localhost:3002)
+    #[default]
+    Regtest,
+}
+
+impl BitcoinCluster {
+    pub fn network(&self) -> Network {
+        match self {
+            Self::Mainnet => Network::Bitcoin,
+            Self::Testnet => Network::Testnet,
+            Self::Signet => Network::Signet,
+            Self::Regtest => Network::Regtest,
+        }
+    }
+
+    /// The BIP44 coin type, `0` on mainnet and `1` on every test network
+    pub fn coin_type(&self) -> u32 {
+        match self {
+            Self::Mainnet => 0,
+            _ => 1,
+        }
+    }
+
+    /// Parses a chain such as `bitcoin:mainnet` or an identifier such as `mainnet`
+    pub fn from_chain(value: &str) -> AtollWalletResult<Self> {
+        match value {
+            BitcoinConstants::MAINNET_IDENTIFIER | BitcoinConstants::MAINNET_CHAIN => {
+                Ok(Self::Mainnet)
+            }
+            BitcoinConstants::TESTNET_IDENTIFIER | BitcoinConstants::TESTNET_CHAIN => {
+                Ok(Self::Testnet)
+            }
+            BitcoinConstants::SIGNET_IDENTIFIER | BitcoinConstants::SIGNET_CHAIN => {
+                Ok(Self::Signet)
+            }
+            BitcoinConstants::REGTEST_IDENTIFIER | BitcoinConstants::REGTEST_CHAIN => {
+                Ok(Self::Regtest)
+            }
+            _ => Err(AtollWalletError::UnsupportedBitcoinChain(value.to_string())),
+        }
+    }
+}
+
+impl Cluster for BitcoinCluster {
+    fn identifier(&self) -> &str {
+        match self {
+            Self::Mainnet => BitcoinConstants::MAINNET_IDENTIFIER,
+            Self::Testnet => BitcoinConstants::TESTNET_IDENTIFIER,
+            Self::Signet => BitcoinConstants::SIGNET_IDENTIFIER,
+            Self::Regtest => BitcoinConstants::REGTEST_IDENTIFIER,
+        }
+    }
+
+    fn chain(&self) -> &str {
+        match self {
+            Self::Mainnet => BitcoinConstants::MAINNET_CHAIN,
+            Self::Testnet => BitcoinConstants::TESTNET_CHAIN,
+            Self::Signet => BitcoinConstants::SIGNET_CHAIN,
+            Self::Regtest => BitcoinConstants::REGTEST_CHAIN,
+        }
+    }
+
+    fn endpoint(&self) -> &str {
+        match self {
+            Self::Mainnet => BitcoinConstants::MAINNET_ENDPOINT,
+            Self::Testnet => BitcoinConstants::TESTNET_ENDPOINT,
+            Self::Signet => BitcoinConstants::SIGNET_ENDPOINT,
+            Self::Regtest => BitcoinConstants::REGTEST_ENDPOINT,
+        }
+    }
+
+    fn chains(&self) -> &'static [&'static str] {
+        &[
+            BitcoinConstants::MAINNET_CHAIN,
+            BitcoinConstants::TESTNET_CHAIN,
+            BitcoinConstants::SIGNET_CHAIN,
+            BitcoinConstants::REGTEST_CHAIN,
+        ]
+    }
+}
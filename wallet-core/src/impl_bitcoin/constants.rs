@@ -0,0 +1,29 @@
+pub struct BitcoinConstants;
+
+impl BitcoinConstants {
+    pub const STANDARD_NAMESPACE: &str = "bitcoin";
+
+    pub const CONNECT: &str = "bitcoin:connect";
+    pub const SIGN_IN: &str = "bitcoin:signIn";
+    pub const SIGN_MESSAGE: &str = "bitcoin:signMessage";
+    pub const SIGN_TRANSACTION: &str = "bitcoin:signTransaction";
+    pub const SIGN_AND_SEND_TRANSACTION: &str = "bitcoin:signAndSendTransaction";
+
+    pub const MAINNET_IDENTIFIER: &str = "mainnet";
+    pub const TESTNET_IDENTIFIER: &str = "testnet";
+    pub const SIGNET_IDENTIFIER: &str = "signet";
+    pub const REGTEST_IDENTIFIER: &str = "regtest";
+
+    pub const MAINNET_CHAIN: &str = "bitcoin:mainnet";
+    pub const TESTNET_CHAIN: &str = "bitcoin:testnet";
+    pub const SIGNET_CHAIN: &str = "bitcoin:signet";
+    pub const REGTEST_CHAIN: &str = "bitcoin:regtest";
+
+    pub const MAINNET_ENDPOINT: &str = "https://blockstream.info/api";
+    pub const TESTNET_ENDPOINT: &str = "https://blockstream.info/testnet/api";
+    pub const SIGNET_ENDPOINT: &str = "https://mempool.space/signet/api";
+    pub const REGTEST_ENDPOINT: &str = "http://localhost:3002";
+
+    pub const PURPOSE_PAYMENT: &str = "payment";
+    pub const PURPOSE_ORDINALS: &str = "ordinals";
+}
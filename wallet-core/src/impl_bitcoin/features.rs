@@ -1,5 +1,7 @@
 use wallet_standard_base::StandardFeatures;
 
+use crate::BitcoinConstants;
+
 /// [Bitcoin Extension](https://github.com/wallet-standard/wallet-standard/blob/master/extensions/bitcoin.md)
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub struct StandardFeaturesBitcoin {
@@ -16,34 +18,51 @@ impl StandardFeaturesBitcoin {
 
         self
     }
+
+    /// The features advertised by Bitcoin accounts
+    pub fn features(&self) -> Vec<&str> {
+        let mut features = vec![self.connect()];
+
+        if let Some(sign_in) = self.sign_in() {
+            features.push(sign_in);
+        }
+
+        features.extend_from_slice(&[
+            self.sign_message(),
+            self.sign_transaction(),
+            self.sign_and_send_transaction(),
+        ]);
+
+        features
+    }
 }
 
 impl StandardFeatures for StandardFeaturesBitcoin {
     fn namespace(&self) -> &str {
-        "bitcoin"
+        BitcoinConstants::STANDARD_NAMESPACE
     }
 
     fn connect(&self) -> &str {
-        "bitcoin:connect"
+        BitcoinConstants::CONNECT
     }
 
     fn sign_in(&self) -> Option<&str> {
         if self.sign_in {
-            Option::Some("bitcoin:signIn")
+            Option::Some(BitcoinConstants::SIGN_IN)
         } else {
             Option::default()
         }
     }
 
     fn sign_message(&self) -> &str {
-        "bitcoin:signMessage"
+        BitcoinConstants::SIGN_MESSAGE
     }
 
     fn sign_transaction(&self) -> &str {
-        "bitcoin:signTransaction"
+        BitcoinConstants::SIGN_TRANSACTION
     }
 
     fn sign_and_send_transaction(&self) -> &str {
-        "bitcoin:signAndSendTransaction"
+        BitcoinConstants::SIGN_AND_SEND_TRANSACTION
     }
 }
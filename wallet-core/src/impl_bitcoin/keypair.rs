@@ -0,0 +1,199 @@
+use bitcoin::{
+    Address, CompressedPublicKey, XOnlyPublicKey,
+    bip32::{DerivationPath, Fingerprint, Xpriv},
+    key::Secp256k1,
+    secp256k1::{All, Keypair, PublicKey},
+};
+use zeroize::Zeroizing;
+
+use crate::{AtollWalletError, AtollWalletResult, BitcoinCluster, BitcoinConstants};
+
+/// What an account is used for, which decides its derivation path and address type
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum BitcoinPurpose {
+    /// BIP84 native SegWit (P2WPKH) account for payments
+    #[default]
+    Payment,
+    /// BIP86 Taproot (P2TR) account for ordinals and inscriptions
+    Ordinals,
+}
+
+impl BitcoinPurpose {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Payment => BitcoinConstants::PURPOSE_PAYMENT,
+            Self::Ordinals => BitcoinConstants::PURPOSE_ORDINALS,
+        }
+    }
+
+    /// The BIP43 purpose of the derivation path
+    pub fn bip43_purpose(&self) -> u32 {
+        match self {
+            Self::Payment => 84,
+            Self::Ordinals => 86,
+        }
+    }
+
+    pub fn address_type(&self) -> &str {
+        match self {
+            Self::Payment => "p2wpkh",
+            Self::Ordinals => "p2tr",
+        }
+    }
+
+    /// The path of the first address of `keychain`, `m/purpose'/coin_type'/account'/keychain/0`
+    pub fn derivation_path(
+        &self,
+        cluster: BitcoinCluster,
+        account: u32,
+        keychain: BitcoinKeychain,
+    ) -> AtollWalletResult<DerivationPath> {
+        format!(
+            "m/{}'/{}'/{account}'/{}/0",
+            self.bip43_purpose(),
+            cluster.coin_type(),
+            keychain.index()
+        )
+        .parse::<DerivationPath>()
+        .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))
+    }
+}
+
+impl TryFrom<&str> for BitcoinPurpose {
+    type Error = AtollWalletError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            BitcoinConstants::PURPOSE_PAYMENT => Ok(Self::Payment),
+            BitcoinConstants::PURPOSE_ORDINALS => Ok(Self::Ordinals),
+            _ => Err(AtollWalletError::Input(format!(
+                "`{value}` is not a supported Bitcoin account purpose"
+            ))),
+        }
+    }
+}
+
+/// The BIP44 chain of an address
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum BitcoinKeychain {
+    /// The external chain of addresses given out to receive payments
+    #[default]
+    External,
+    /// The internal chain of change addresses
+    Internal,
+}
+
+impl BitcoinKeychain {
+    pub fn index(&self) -> u32 {
+        match self {
+            Self::External => 0,
+            Self::Internal => 1,
+        }
+    }
+}
+
+/// A Bitcoin account derived from the BIP39 seed of the vault
+pub struct BitcoinAccountKeypair {
+    purpose: BitcoinPurpose,
+    cluster: BitcoinCluster,
+    keychain: BitcoinKeychain,
+    derivation_path: DerivationPath,
+    master_fingerprint: Fingerprint,
+    keypair: Keypair,
+    address: Address,
+}
+
+impl BitcoinAccountKeypair {
+    pub(crate) fn new_from_seed(
+        seed: &Zeroizing<Vec<u8>>,
+        purpose: BitcoinPurpose,
+        cluster: BitcoinCluster,
+        account: u32,
+        keychain: BitcoinKeychain,
+    ) -> AtollWalletResult<Self> {
+        let secp = Secp256k1::new();
+        let derivation_path = purpose.derivation_path(cluster, account, keychain)?;
+
+        let master = Xpriv::new_master(cluster.network(), seed)
+            .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?;
+        let keypair = master
+            .derive_priv(&secp, &derivation_path)
+            .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?
+            .to_keypair(&secp);
+
+        let address = Self::address_for(&secp, purpose, cluster, &keypair.public_key());
+
+        Ok(Self {
+            purpose,
+            cluster,
+            keychain,
+            derivation_path,
+            master_fingerprint: master.fingerprint(&secp),
+            keypair,
+            address,
+        })
+    }
+
+    fn address_for(
+        secp: &Secp256k1<All>,
+        purpose: BitcoinPurpose,
+        cluster: BitcoinCluster,
+        public_key: &PublicKey,
+    ) -> Address {
+        match purpose {
+            BitcoinPurpose::Payment => {
+                Address::p2wpkh(&CompressedPublicKey(*public_key), cluster.network())
+            }
+            BitcoinPurpose::Ordinals => Address::p2tr(
+                secp,
+                XOnlyPublicKey::from(*public_key),
+                None,
+                cluster.network(),
+            ),
+        }
+    }
+
+    pub fn purpose(&self) -> BitcoinPurpose {
+        self.purpose
+    }
+
+    pub fn cluster(&self) -> BitcoinCluster {
+        self.cluster
+    }
+
+    pub fn keychain(&self) -> BitcoinKeychain {
+        self.keychain
+    }
+
+    pub fn derivation_path(&self) -> &DerivationPath {
+        &self.derivation_path
+    }
+
+    /// The fingerprint of the master key, as found in PSBT key origins
+    pub fn master_fingerprint(&self) -> Fingerprint {
+        self.master_fingerprint
+    }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        self.keypair.public_key()
+    }
+
+    /// The key advertised to dapps: the 33 byte compressed key for payment
+    /// accounts and the 32 byte x-only internal key for Taproot accounts
+    pub fn public_key_bytes(&self) -> Vec<u8> {
+        match self.purpose {
+            BitcoinPurpose::Payment => self.public_key().serialize().to_vec(),
+            BitcoinPurpose::Ordinals => {
+                XOnlyPublicKey::from(self.public_key()).serialize().to_vec()
+            }
+        }
+    }
+
+    pub(crate) fn keypair(&self) -> &Keypair {
+        &self.keypair
+    }
+}
@@ -0,0 +1,396 @@
+use core::str::FromStr;
+
+use bitcoin::{
+    Address, AddressType, Amount, CompressedPublicKey, EcdsaSighashType, OutPoint, ScriptBuf,
+    Sequence, TapSighashType, Transaction, TxIn, TxOut, Txid, VarInt, Witness, XOnlyPublicKey,
+    consensus::{Decodable, encode::serialize},
+    ecdsa,
+    hashes::{Hash, HashEngine, sha256, sha256d},
+    key::{Secp256k1, TapTweak},
+    opcodes::{OP_0, all::OP_RETURN},
+    script::Builder,
+    secp256k1::{
+        Keypair, Message,
+        ecdsa::{RecoverableSignature, RecoveryId},
+    },
+    sighash::{Prevouts, SighashCache},
+    taproot,
+    transaction::Version,
+};
+
+use crate::{AtollWalletError, AtollWalletResult, BitcoinAccountKeypair, BitcoinPurpose};
+
+/// How a Bitcoin message signature is encoded
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum BitcoinMessageProtocol {
+    /// [BIP322](https://github.com/bitcoin/bips/blob/master/bip-0322.mediawiki) "simple"
+    /// signature, the consensus encoded witness of the `to_sign` transaction
+    #[default]
+    Bip322Simple,
+    /// The 65 byte recoverable signature of `Bitcoin Signed Message` as extended
+    /// by [BIP137](https://github.com/bitcoin/bips/blob/master/bip-0137.mediawiki)
+    Legacy,
+}
+
+impl BitcoinMessageProtocol {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Bip322Simple => "bip322-simple",
+            Self::Legacy => "legacy",
+        }
+    }
+}
+
+impl TryFrom<&str> for BitcoinMessageProtocol {
+    type Error = AtollWalletError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "bip322" | "bip322-simple" => Ok(Self::Bip322Simple),
+            "legacy" | "bip137" => Ok(Self::Legacy),
+            _ => Err(AtollWalletError::Input(format!(
+                "`{value}` is not a supported Bitcoin message signing protocol"
+            ))),
+        }
+    }
+}
+
+/// Signs and verifies Bitcoin messages
+pub struct BitcoinMessage;
+
+impl BitcoinMessage {
+    const BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+    const LEGACY_PREFIX: &[u8] = b"\x18Bitcoin Signed Message:\n";
+
+    /// The BIP340 tagged hash of `message` committed to by the `to_spend` transaction
+    pub fn bip322_message_hash(message: &[u8]) -> [u8; 32] {
+        let tag = sha256::Hash::hash(Self::BIP322_TAG);
+
+        let mut engine = sha256::Hash::engine();
+        engine.input(tag.as_byte_array());
+        engine.input(tag.as_byte_array());
+        engine.input(message);
+
+        sha256::Hash::from_engine(engine).to_byte_array()
+    }
+
+    /// The virtual transaction paying to `script_pubkey` that the signature spends
+    pub fn bip322_to_spend(script_pubkey: &ScriptBuf, message: &[u8]) -> Transaction {
+        let script_sig = Builder::new()
+            .push_opcode(OP_0)
+            .push_slice(Self::bip322_message_hash(message))
+            .into_script();
+
+        Transaction {
+            version: Version(0),
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(Txid::all_zeros(), u32::MAX),
+                script_sig,
+                sequence: Sequence::ZERO,
+                witness: Witness::new(),
+            }],
+            output: vec![TxOut {
+                value: Amount::ZERO,
+                script_pubkey: script_pubkey.clone(),
+            }],
+        }
+    }
+
+    /// The virtual transaction spending `to_spend` with `witness`
+    pub fn bip322_to_sign(to_spend: &Transaction, witness: Witness) -> Transaction {
+        Transaction {
+            version: Version(0),
+            lock_time: bitcoin::absolute::LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::new(to_spend.compute_txid(), 0),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ZERO,
+                witness,
+            }],
+            output: vec![TxOut {
+                value: Amount::ZERO,
+                script_pubkey: Builder::new().push_opcode(OP_RETURN).into_script(),
+            }],
+        }
+    }
+
+    /// Signs `message` with the account using `protocol`
+    pub fn sign(
+        keypair: &BitcoinAccountKeypair,
+        message: &[u8],
+        protocol: BitcoinMessageProtocol,
+    ) -> AtollWalletResult<Vec<u8>> {
+        match protocol {
+            BitcoinMessageProtocol::Bip322Simple => {
+                Self::sign_bip322_simple(keypair.keypair(), keypair.address(), message)
+            }
+            BitcoinMessageProtocol::Legacy => {
+                if keypair.purpose() == BitcoinPurpose::Ordinals {
+                    return Err(AtollWalletError::Bitcoin(
+                        "Legacy message signatures are not defined for Taproot addresses"
+                            .to_string(),
+                    ));
+                }
+
+                Ok(Self::sign_legacy(keypair.keypair(), keypair.address(), message)?.to_vec())
+            }
+        }
+    }
+
+    /// Creates a BIP322 "simple" signature for a P2WPKH or P2TR `address` owned by `keypair`
+    pub fn sign_bip322_simple(
+        keypair: &Keypair,
+        address: &Address,
+        message: &[u8],
+    ) -> AtollWalletResult<Vec<u8>> {
+        let secp = Secp256k1::new();
+        let to_spend = Self::bip322_to_spend(&address.script_pubkey(), message);
+        let to_sign = Self::bip322_to_sign(&to_spend, Witness::new());
+
+        let witness = match address.address_type() {
+            Some(AddressType::P2wpkh) => {
+                let public_key = CompressedPublicKey(keypair.public_key());
+
+                if address.script_pubkey() != ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash()) {
+                    return Err(Self::address_mismatch(address));
+                }
+
+                let sighash = SighashCache::new(&to_sign)
+                    .p2wpkh_signature_hash(
+                        0,
+                        &to_spend.output[0].script_pubkey,
+                        Amount::ZERO,
+                        EcdsaSighashType::All,
+                    )
+                    .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?;
+
+                let signature = ecdsa::Signature {
+                    signature: secp.sign_ecdsa_low_r(
+                        &Message::from_digest(sighash.to_byte_array()),
+                        &keypair.secret_key(),
+                    ),
+                    sighash_type: EcdsaSighashType::All,
+                };
+
+                Witness::p2wpkh(&signature, &public_key.0)
+            }
+            Some(AddressType::P2tr) => {
+                let x_only = XOnlyPublicKey::from(keypair.public_key());
+
+                if address.script_pubkey() != ScriptBuf::new_p2tr(&secp, x_only, None) {
+                    return Err(Self::address_mismatch(address));
+                }
+
+                let sighash = SighashCache::new(&to_sign)
+                    .taproot_key_spend_signature_hash(
+                        0,
+                        &Prevouts::All(&to_spend.output),
+                        TapSighashType::Default,
+                    )
+                    .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?;
+
+                let tweaked = keypair.tap_tweak(&secp, None).to_keypair();
+
+                let mut aux_rand = [0u8; 32];
+                getrandom::fill(&mut aux_rand)
+                    .map_err(|error| AtollWalletError::Random(error.to_string()))?;
+
+                let signature = taproot::Signature {
+                    signature: secp.sign_schnorr_with_aux_rand(
+                        &Message::from_digest(sighash.to_byte_array()),
+                        &tweaked,
+                        &aux_rand,
+                    ),
+                    sighash_type: TapSighashType::Default,
+                };
+
+                Witness::p2tr_key_spend(&signature)
+            }
+            _ => return Err(Self::unsupported_address(address)),
+        };
+
+        Ok(serialize(&witness))
+    }
+
+    /// Creates a legacy signature with the BIP137 header of a P2PKH or P2WPKH `address`
+    pub fn sign_legacy(
+        keypair: &Keypair,
+        address: &Address,
+        message: &[u8],
+    ) -> AtollWalletResult<[u8; 65]> {
+        let secp = Secp256k1::new();
+        let public_key = CompressedPublicKey(keypair.public_key());
+
+        let header_base = match address.address_type() {
+            Some(AddressType::P2pkh) if address.is_related_to_pubkey(&public_key.into()) => 31,
+            Some(AddressType::P2wpkh) if address.is_related_to_pubkey(&public_key.into()) => 39,
+            Some(AddressType::P2pkh | AddressType::P2wpkh) => {
+                return Err(Self::address_mismatch(address));
+            }
+            _ => return Err(Self::unsupported_address(address)),
+        };
+
+        let (recovery_id, compact) = secp
+            .sign_ecdsa_recoverable(
+                &Message::from_digest(Self::legacy_message_hash(message).to_byte_array()),
+                &keypair.secret_key(),
+            )
+            .serialize_compact();
+
+        let mut signature = [0u8; 65];
+        signature[0] = header_base + recovery_id.to_i32() as u8;
+        signature[1..].copy_from_slice(&compact);
+
+        Ok(signature)
+    }
+
+    /// The double SHA256 of `message` behind the `Bitcoin Signed Message` prefix
+    pub fn legacy_message_hash(message: &[u8]) -> sha256d::Hash {
+        let mut engine = sha256d::Hash::engine();
+        engine.input(Self::LEGACY_PREFIX);
+        engine.input(&serialize(&VarInt(message.len() as u64)));
+        engine.input(message);
+
+        sha256d::Hash::from_engine(engine)
+    }
+
+    /// Verifies a BIP322 "simple" signature or a legacy signature of `message` by `address`.
+    /// Errors when the address cannot be parsed or its type is not supported and
+    /// returns `false` for any signature that does not verify.
+    pub fn verify(address: &str, message: &[u8], signature: &[u8]) -> AtollWalletResult<bool> {
+        let address = Address::from_str(address)
+            .map_err(|error| AtollWalletError::Bitcoin(format!("Invalid address. {error}")))?
+            .assume_checked();
+
+        match address.address_type() {
+            Some(AddressType::P2pkh) => Ok(Self::verify_legacy(&address, message, signature)),
+            Some(AddressType::P2wpkh) if signature.len() == 65 && signature[0] >= 27 => {
+                Ok(Self::verify_legacy(&address, message, signature))
+            }
+            Some(AddressType::P2wpkh | AddressType::P2tr) => {
+                Ok(Self::verify_bip322_simple(&address, message, signature))
+            }
+            _ => Err(Self::unsupported_address(&address)),
+        }
+    }
+
+    fn verify_bip322_simple(address: &Address, message: &[u8], signature: &[u8]) -> bool {
+        let mut reader = signature;
+        let Ok(witness) = Witness::consensus_decode(&mut reader) else {
+            return false;
+        };
+        if !reader.is_empty() {
+            return false;
+        }
+
+        let secp = Secp256k1::verification_only();
+        let script_pubkey = address.script_pubkey();
+        let to_spend = Self::bip322_to_spend(&script_pubkey, message);
+        let to_sign = Self::bip322_to_sign(&to_spend, witness.clone());
+        let mut cache = SighashCache::new(&to_sign);
+
+        match address.address_type() {
+            Some(AddressType::P2wpkh) => {
+                let (Some(signature), Some(public_key), 2) =
+                    (witness.nth(0), witness.nth(1), witness.len())
+                else {
+                    return false;
+                };
+                let (Ok(signature), Ok(public_key)) = (
+                    ecdsa::Signature::from_slice(signature),
+                    CompressedPublicKey::from_slice(public_key),
+                ) else {
+                    return false;
+                };
+
+                if script_pubkey != ScriptBuf::new_p2wpkh(&public_key.wpubkey_hash()) {
+                    return false;
+                }
+
+                cache
+                    .p2wpkh_signature_hash(0, &script_pubkey, Amount::ZERO, signature.sighash_type)
+                    .is_ok_and(|sighash| {
+                        secp.verify_ecdsa(
+                            &Message::from_digest(sighash.to_byte_array()),
+                            &signature.signature,
+                            &public_key.0,
+                        )
+                        .is_ok()
+                    })
+            }
+            Some(AddressType::P2tr) => {
+                let (Some(signature), 1) = (witness.nth(0), witness.len()) else {
+                    return false;
+                };
+                let (Ok(signature), Ok(output_key)) = (
+                    taproot::Signature::from_slice(signature),
+                    XOnlyPublicKey::from_slice(&script_pubkey.as_bytes()[2..]),
+                ) else {
+                    return false;
+                };
+
+                cache
+                    .taproot_key_spend_signature_hash(
+                        0,
+                        &Prevouts::All(&to_spend.output),
+                        signature.sighash_type,
+                    )
+                    .is_ok_and(|sighash| {
+                        secp.verify_schnorr(
+                            &signature.signature,
+                            &Message::from_digest(sighash.to_byte_array()),
+                            &output_key,
+                        )
+                        .is_ok()
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    fn verify_legacy(address: &Address, message: &[u8], signature: &[u8]) -> bool {
+        let Some((&header, compact)) = signature.split_first() else {
+            return false;
+        };
+        if compact.len() != 64 || !(27..=42).contains(&header) {
+            return false;
+        }
+
+        let Ok(recovery_id) = RecoveryId::from_i32(((header - 27) % 4) as i32) else {
+            return false;
+        };
+        let Ok(recoverable) = RecoverableSignature::from_compact(compact, recovery_id) else {
+            return false;
+        };
+
+        let secp = Secp256k1::verification_only();
+        let Ok(public_key) = secp.recover_ecdsa(
+            &Message::from_digest(Self::legacy_message_hash(message).to_byte_array()),
+            &recoverable,
+        ) else {
+            return false;
+        };
+
+        // Many wallets sign SegWit addresses with the P2PKH header, so any
+        // compressed key header is accepted for the address of that key
+        match header {
+            27..=30 => {
+                address.is_related_to_pubkey(&bitcoin::PublicKey::new_uncompressed(public_key))
+            }
+            _ => address.is_related_to_pubkey(&bitcoin::PublicKey::new(public_key)),
+        }
+    }
+
+    fn address_mismatch(address: &Address) -> AtollWalletError {
+        AtollWalletError::Bitcoin(format!(
+            "The key does not belong to the address `{address}`"
+        ))
+    }
+
+    fn unsupported_address(address: &Address) -> AtollWalletError {
+        AtollWalletError::Bitcoin(format!(
+            "Signing messages for the address `{address}` is not supported"
+        ))
+    }
+}
@@ -0,0 +1,23 @@
+mod features;
+pub use features::*;
+
+mod clusters;
+pub use clusters::*;
+
+mod constants;
+pub use constants::*;
+
+mod keypair;
+pub use keypair::*;
+
+mod backend;
+pub use backend::*;
+
+mod message;
+pub use message::*;
+
+mod psbt;
+pub use psbt::*;
+
+mod send;
+pub use send::*;
@@ -0,0 +1,353 @@
+mod v2;
+pub use v2::*;
+
+use bitcoin::{
+    CompressedPublicKey, Psbt, ScriptBuf, TapSighashType, Transaction, TxOut, Witness,
+    XOnlyPublicKey, ecdsa,
+    hashes::Hash,
+    key::{Secp256k1, TapTweak},
+    psbt::{Input, PsbtSighashType},
+    secp256k1::{All, Message},
+    sighash::{EcdsaSighashType, Prevouts, SighashCache},
+    taproot,
+};
+
+use crate::{AtollWalletError, AtollWalletResult, BitcoinAccountKeypair, BitcoinPurpose};
+
+/// The inputs an account is asked to sign, as in the `inputsToSign`
+/// option of `bitcoin:signTransaction`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct BitcoinInputsToSign {
+    pub address: String,
+    pub signing_indexes: Vec<usize>,
+    pub sighash_type: Option<u32>,
+}
+
+/// A PSBT being signed by the wallet. Version 2 PSBTs are handled
+/// as version 0 and converted back when serialized.
+pub struct BitcoinPsbt {
+    psbt: Psbt,
+    v2_fields: Option<PsbtV2Fields>,
+}
+
+impl From<Psbt> for BitcoinPsbt {
+    fn from(psbt: Psbt) -> Self {
+        Self {
+            psbt,
+            v2_fields: None,
+        }
+    }
+}
+
+impl BitcoinPsbt {
+    /// Parses a BIP174 (version 0) or BIP370 (version 2) PSBT
+    pub fn from_bytes(bytes: &[u8]) -> AtollWalletResult<Self> {
+        let (bytes, v2_fields) = match PsbtV2::version(bytes)? {
+            0 => (bytes.to_vec(), None),
+            2 => {
+                let (bytes, fields) = PsbtV2::to_v0(bytes)?;
+                (bytes, Some(fields))
+            }
+            version => {
+                return Err(AtollWalletError::Bitcoin(format!(
+                    "PSBT version `{version}` is not supported"
+                )));
+            }
+        };
+
+        let psbt = Psbt::deserialize(&bytes)
+            .map_err(|error| AtollWalletError::Bitcoin(format!("Invalid PSBT. {error}")))?;
+
+        Ok(Self { psbt, v2_fields })
+    }
+
+    /// Serializes the PSBT in the version it was parsed from
+    pub fn to_bytes(&self) -> AtollWalletResult<Vec<u8>> {
+        let bytes = self.psbt.serialize();
+
+        match self.v2_fields.as_ref() {
+            Some(fields) => PsbtV2::from_v0(&bytes, fields),
+            None => Ok(bytes),
+        }
+    }
+
+    pub fn psbt(&self) -> &Psbt {
+        &self.psbt
+    }
+
+    pub fn is_v2(&self) -> bool {
+        self.v2_fields.is_some()
+    }
+
+    /// Signs the inputs in `inputs_to_sign`, or every input one of `keypairs`
+    /// can sign if it is empty. Returns the indexes of the signed inputs.
+    pub fn sign(
+        &mut self,
+        keypairs: &[&BitcoinAccountKeypair],
+        inputs_to_sign: &[BitcoinInputsToSign],
+    ) -> AtollWalletResult<Vec<usize>> {
+        let secp = Secp256k1::new();
+
+        let requests = if inputs_to_sign.is_empty() {
+            (0..self.psbt.inputs.len())
+                .filter_map(|index| {
+                    keypairs
+                        .iter()
+                        .find(|keypair| self.can_sign(&secp, index, keypair))
+                        .map(|keypair| (index, *keypair, None))
+                })
+                .collect::<Vec<(usize, &BitcoinAccountKeypair, Option<u32>)>>()
+        } else {
+            let mut requests = Vec::new();
+
+            for request in inputs_to_sign {
+                let keypair = keypairs
+                    .iter()
+                    .find(|keypair| keypair.address().to_string() == request.address)
+                    .ok_or(AtollWalletError::AccountNotFound(request.address.clone()))?;
+
+                for index in request.signing_indexes.iter() {
+                    if !self.can_sign(&secp, *index, keypair) {
+                        return Err(AtollWalletError::Bitcoin(format!(
+                            "The input `{index}` cannot be signed by `{}`",
+                            request.address
+                        )));
+                    }
+
+                    requests.push((*index, *keypair, request.sighash_type));
+                }
+            }
+
+            requests
+        };
+
+        let mut signed = Vec::<usize>::new();
+
+        for (index, keypair, sighash_type) in requests {
+            if let Some(sighash_type) = sighash_type {
+                let sighash_type = PsbtSighashType::from_u32(sighash_type);
+
+                match self.psbt.inputs[index].sighash_type {
+                    Some(existing) if existing != sighash_type => {
+                        return Err(AtollWalletError::Bitcoin(format!(
+                            "The input `{index}` requires the sighash type `{existing}`"
+                        )));
+                    }
+                    _ => {
+                        self.psbt.inputs[index].sighash_type.replace(sighash_type);
+                    }
+                }
+            }
+
+            match keypair.purpose() {
+                BitcoinPurpose::Payment => self.sign_p2wpkh(&secp, index, keypair)?,
+                BitcoinPurpose::Ordinals => self.sign_p2tr_key_path(&secp, index, keypair)?,
+            }
+
+            signed.push(index);
+        }
+
+        Ok(signed)
+    }
+
+    /// Finalizes the signed P2WPKH and P2TR key path inputs in `indexes`
+    /// and removes the fields that are no longer needed
+    pub fn finalize(&mut self, indexes: &[usize]) -> AtollWalletResult<&mut Self> {
+        for index in indexes {
+            let input = self
+                .psbt
+                .inputs
+                .get_mut(*index)
+                .ok_or(AtollWalletError::Bitcoin(format!(
+                    "The PSBT has no input `{index}`"
+                )))?;
+
+            let witness = if let Some(signature) = input.tap_key_sig {
+                Witness::p2tr_key_spend(&signature)
+            } else if let Some((public_key, signature)) = input.partial_sigs.first_key_value() {
+                Witness::p2wpkh(signature, &public_key.inner)
+            } else {
+                return Err(AtollWalletError::Bitcoin(format!(
+                    "The input `{index}` has no signature to finalize"
+                )));
+            };
+
+            *input = Input {
+                non_witness_utxo: input.non_witness_utxo.take(),
+                witness_utxo: input.witness_utxo.take(),
+                final_script_witness: Some(witness),
+                unknown: std::mem::take(&mut input.unknown),
+                proprietary: std::mem::take(&mut input.proprietary),
+                ..Default::default()
+            };
+        }
+
+        Ok(self)
+    }
+
+    /// The signed transaction once every input is finalized
+    pub fn extract_transaction(&self) -> AtollWalletResult<Transaction> {
+        if let Some(index) = self.psbt.inputs.iter().position(|input| {
+            input.final_script_witness.is_none() && input.final_script_sig.is_none()
+        }) {
+            return Err(AtollWalletError::Bitcoin(format!(
+                "The input `{index}` of the PSBT is not finalized"
+            )));
+        }
+
+        self.psbt
+            .clone()
+            .extract_tx()
+            .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))
+    }
+
+    fn prevout(&self, index: usize) -> Option<TxOut> {
+        let input = self.psbt.inputs.get(index)?;
+
+        if let Some(witness_utxo) = input.witness_utxo.as_ref() {
+            return Some(witness_utxo.clone());
+        }
+
+        let vout = self.psbt.unsigned_tx.input.get(index)?.previous_output.vout;
+
+        input
+            .non_witness_utxo
+            .as_ref()?
+            .output
+            .get(vout as usize)
+            .cloned()
+    }
+
+    /// An input can be signed when its previous output pays to the key of `keypair`
+    /// and its BIP32 derivation or tap key origin fields, if any, name that key
+    fn can_sign(
+        &self,
+        secp: &Secp256k1<All>,
+        index: usize,
+        keypair: &BitcoinAccountKeypair,
+    ) -> bool {
+        let (Some(input), Some(prevout)) = (self.psbt.inputs.get(index), self.prevout(index))
+        else {
+            return false;
+        };
+
+        let public_key = keypair.public_key();
+
+        match keypair.purpose() {
+            BitcoinPurpose::Payment => {
+                let script = ScriptBuf::new_p2wpkh(&CompressedPublicKey(public_key).wpubkey_hash());
+
+                let origin_matches =
+                    input.bip32_derivation.is_empty()
+                        || input.bip32_derivation.get(&public_key).is_some_and(
+                            |(fingerprint, _)| *fingerprint == keypair.master_fingerprint(),
+                        );
+
+                prevout.script_pubkey == script && origin_matches
+            }
+            BitcoinPurpose::Ordinals => {
+                let x_only = XOnlyPublicKey::from(public_key);
+                let script = ScriptBuf::new_p2tr(secp, x_only, input.tap_merkle_root);
+
+                let internal_key_matches = input
+                    .tap_internal_key
+                    .is_none_or(|internal_key| internal_key == x_only);
+
+                let origin_matches = input.tap_key_origins.is_empty()
+                    || input.tap_key_origins.get(&x_only).is_some_and(
+                        |(leaf_hashes, (fingerprint, _))| {
+                            leaf_hashes.is_empty() && *fingerprint == keypair.master_fingerprint()
+                        },
+                    );
+
+                prevout.script_pubkey == script && internal_key_matches && origin_matches
+            }
+        }
+    }
+
+    fn sign_p2wpkh(
+        &mut self,
+        secp: &Secp256k1<All>,
+        index: usize,
+        keypair: &BitcoinAccountKeypair,
+    ) -> AtollWalletResult<()> {
+        let prevout = self
+            .prevout(index)
+            .ok_or(AtollWalletError::Bitcoin(format!(
+                "The input `{index}` has no previous output"
+            )))?;
+
+        let sighash_type = match self.psbt.inputs[index].sighash_type {
+            Some(sighash_type) => sighash_type
+                .ecdsa_hash_ty()
+                .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?,
+            None => EcdsaSighashType::All,
+        };
+
+        let sighash = SighashCache::new(&self.psbt.unsigned_tx)
+            .p2wpkh_signature_hash(index, &prevout.script_pubkey, prevout.value, sighash_type)
+            .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?;
+
+        let signature = ecdsa::Signature {
+            signature: secp.sign_ecdsa(
+                &Message::from_digest(sighash.to_byte_array()),
+                &keypair.keypair().secret_key(),
+            ),
+            sighash_type,
+        };
+
+        self.psbt.inputs[index]
+            .partial_sigs
+            .insert(bitcoin::PublicKey::new(keypair.public_key()), signature);
+
+        Ok(())
+    }
+
+    fn sign_p2tr_key_path(
+        &mut self,
+        secp: &Secp256k1<All>,
+        index: usize,
+        keypair: &BitcoinAccountKeypair,
+    ) -> AtollWalletResult<()> {
+        let prevouts = (0..self.psbt.inputs.len())
+            .map(|input_index| {
+                self.prevout(input_index).ok_or(AtollWalletError::Bitcoin(format!(
+                    "Signing a Taproot input requires the previous output of every input but input `{input_index}` has none"
+                )))
+            })
+            .collect::<AtollWalletResult<Vec<TxOut>>>()?;
+
+        let sighash_type = match self.psbt.inputs[index].sighash_type {
+            Some(sighash_type) => sighash_type
+                .taproot_hash_ty()
+                .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?,
+            None => TapSighashType::Default,
+        };
+
+        let sighash = SighashCache::new(&self.psbt.unsigned_tx)
+            .taproot_key_spend_signature_hash(index, &Prevouts::All(&prevouts), sighash_type)
+            .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?;
+
+        let tweaked = keypair
+            .keypair()
+            .tap_tweak(secp, self.psbt.inputs[index].tap_merkle_root)
+            .to_keypair();
+
+        let mut aux_rand = [0u8; 32];
+        getrandom::fill(&mut aux_rand)
+            .map_err(|error| AtollWalletError::Random(error.to_string()))?;
+
+        let signature = taproot::Signature {
+            signature: secp.sign_schnorr_with_aux_rand(
+                &Message::from_digest(sighash.to_byte_array()),
+                &tweaked,
+                &aux_rand,
+            ),
+            sighash_type,
+        };
+
+        self.psbt.inputs[index].tap_key_sig.replace(signature);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,405 @@
+use bitcoin::{
+    Amount, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+    absolute::LockTime,
+    consensus::{Decodable, encode::serialize},
+    hashes::Hash,
+    transaction::Version,
+};
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+const MAGIC: &[u8] = b"psbt\xff";
+
+const GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const GLOBAL_TX_VERSION: u8 = 0x02;
+const GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const GLOBAL_INPUT_COUNT: u8 = 0x04;
+const GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const GLOBAL_TX_MODIFIABLE: u8 = 0x06;
+const GLOBAL_VERSION: u8 = 0xFB;
+
+const IN_PREVIOUS_TXID: u8 = 0x0E;
+const IN_OUTPUT_INDEX: u8 = 0x0F;
+const IN_SEQUENCE: u8 = 0x10;
+const IN_REQUIRED_TIME_LOCKTIME: u8 = 0x11;
+const IN_REQUIRED_HEIGHT_LOCKTIME: u8 = 0x12;
+
+const OUT_AMOUNT: u8 = 0x03;
+const OUT_SCRIPT: u8 = 0x04;
+
+type KeyValue = (Vec<u8>, Vec<u8>);
+
+/// The key-value maps of a serialized PSBT without interpreting the values
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+struct RawPsbt {
+    global: Vec<KeyValue>,
+    inputs: Vec<Vec<KeyValue>>,
+    outputs: Vec<Vec<KeyValue>>,
+}
+
+/// The fields only found in a version 2 PSBT, kept aside while the PSBT is
+/// handled as version 0 so they can be restored unchanged afterwards.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct PsbtV2Fields {
+    global: Vec<KeyValue>,
+    inputs: Vec<Vec<KeyValue>>,
+    outputs: Vec<Vec<KeyValue>>,
+}
+
+/// Converts between BIP370 version 2 PSBTs and BIP174 version 0 PSBTs
+pub struct PsbtV2;
+
+impl PsbtV2 {
+    /// Returns the PSBT version declared in the global map, `0` if absent
+    pub fn version(bytes: &[u8]) -> AtollWalletResult<u32> {
+        let mut reader = Reader::new(bytes)?;
+        let global = reader.map()?;
+
+        match find(&global, GLOBAL_VERSION) {
+            Some(value) => read_u32(value),
+            None => Ok(0),
+        }
+    }
+
+    /// Converts a version 2 PSBT into an equivalent version 0 PSBT
+    pub fn to_v0(bytes: &[u8]) -> AtollWalletResult<(Vec<u8>, PsbtV2Fields)> {
+        let mut reader = Reader::new(bytes)?;
+        let global = reader.map()?;
+
+        let input_count = read_compact_value(
+            find(&global, GLOBAL_INPUT_COUNT)
+                .ok_or(psbt_error("The version 2 PSBT has no input count"))?,
+        )?;
+        let output_count = read_compact_value(
+            find(&global, GLOBAL_OUTPUT_COUNT)
+                .ok_or(psbt_error("The version 2 PSBT has no output count"))?,
+        )?;
+
+        let mut raw = RawPsbt {
+            global,
+            ..Default::default()
+        };
+        for _ in 0..input_count {
+            raw.inputs.push(reader.map()?);
+        }
+        for _ in 0..output_count {
+            raw.outputs.push(reader.map()?);
+        }
+
+        let version = match find(&raw.global, GLOBAL_TX_VERSION) {
+            Some(value) => Version(read_u32(value)? as i32),
+            None => return Err(psbt_error("The version 2 PSBT has no transaction version")),
+        };
+
+        let input = raw
+            .inputs
+            .iter()
+            .map(|map| {
+                let txid = find(map, IN_PREVIOUS_TXID)
+                    .ok_or(psbt_error("An input of the PSBT has no previous txid"))
+                    .and_then(|value| {
+                        Txid::from_slice(value).or(Err(psbt_error("Invalid previous txid")))
+                    })?;
+                let vout = find(map, IN_OUTPUT_INDEX)
+                    .ok_or(psbt_error("An input of the PSBT has no output index"))
+                    .and_then(read_u32)?;
+                let sequence = match find(map, IN_SEQUENCE) {
+                    Some(value) => Sequence(read_u32(value)?),
+                    None => Sequence::MAX,
+                };
+
+                Ok(TxIn {
+                    previous_output: OutPoint { txid, vout },
+                    script_sig: ScriptBuf::new(),
+                    sequence,
+                    witness: Witness::new(),
+                })
+            })
+            .collect::<AtollWalletResult<Vec<TxIn>>>()?;
+
+        let output = raw
+            .outputs
+            .iter()
+            .map(|map| {
+                let amount = find(map, OUT_AMOUNT)
+                    .ok_or(psbt_error("An output of the PSBT has no amount"))
+                    .and_then(read_u64)?;
+                let script = find(map, OUT_SCRIPT)
+                    .ok_or(psbt_error("An output of the PSBT has no script"))?;
+
+                Ok(TxOut {
+                    value: Amount::from_sat(amount),
+                    script_pubkey: ScriptBuf::from_bytes(script.to_vec()),
+                })
+            })
+            .collect::<AtollWalletResult<Vec<TxOut>>>()?;
+
+        let transaction = Transaction {
+            version,
+            lock_time: Self::lock_time(&raw)?,
+            input,
+            output,
+        };
+
+        let mut fields = PsbtV2Fields::default();
+
+        let (v2_global, mut global) = split(
+            raw.global,
+            &[
+                GLOBAL_TX_VERSION,
+                GLOBAL_FALLBACK_LOCKTIME,
+                GLOBAL_INPUT_COUNT,
+                GLOBAL_OUTPUT_COUNT,
+                GLOBAL_TX_MODIFIABLE,
+                GLOBAL_VERSION,
+            ],
+        );
+        fields.global = v2_global;
+        global.insert(0, (vec![GLOBAL_UNSIGNED_TX], serialize(&transaction)));
+
+        let inputs = raw
+            .inputs
+            .into_iter()
+            .map(|map| {
+                let (v2, v0) = split(
+                    map,
+                    &[
+                        IN_PREVIOUS_TXID,
+                        IN_OUTPUT_INDEX,
+                        IN_SEQUENCE,
+                        IN_REQUIRED_TIME_LOCKTIME,
+                        IN_REQUIRED_HEIGHT_LOCKTIME,
+                    ],
+                );
+                fields.inputs.push(v2);
+
+                v0
+            })
+            .collect();
+
+        let outputs = raw
+            .outputs
+            .into_iter()
+            .map(|map| {
+                let (v2, v0) = split(map, &[OUT_AMOUNT, OUT_SCRIPT]);
+                fields.outputs.push(v2);
+
+                v0
+            })
+            .collect();
+
+        let v0 = RawPsbt {
+            global,
+            inputs,
+            outputs,
+        };
+
+        Ok((v0.serialize(), fields))
+    }
+
+    /// Restores a version 0 PSBT produced by [Self::to_v0] into a version 2 PSBT
+    pub fn from_v0(bytes: &[u8], fields: &PsbtV2Fields) -> AtollWalletResult<Vec<u8>> {
+        let mut reader = Reader::new(bytes)?;
+        let global = reader.map()?;
+
+        let mut raw = RawPsbt {
+            global: global
+                .into_iter()
+                .filter(|(key, _)| key.first() != Some(&GLOBAL_UNSIGNED_TX))
+                .collect(),
+            ..Default::default()
+        };
+
+        for (index, v2) in fields.inputs.iter().enumerate() {
+            let mut map = reader.map().map_err(|_| {
+                psbt_error(&format!("The PSBT is missing the map of input `{index}`"))
+            })?;
+            map.extend_from_slice(v2);
+            raw.inputs.push(map);
+        }
+
+        for (index, v2) in fields.outputs.iter().enumerate() {
+            let mut map = reader.map().map_err(|_| {
+                psbt_error(&format!("The PSBT is missing the map of output `{index}`"))
+            })?;
+            map.extend_from_slice(v2);
+            raw.outputs.push(map);
+        }
+
+        raw.global.extend_from_slice(&fields.global);
+
+        Ok(raw.serialize())
+    }
+
+    /// Picks the locktime as BIP370 describes: the fallback when no input requires
+    /// one, otherwise the largest height or time all the inputs agree on
+    fn lock_time(raw: &RawPsbt) -> AtollWalletResult<LockTime> {
+        let fallback = match find(&raw.global, GLOBAL_FALLBACK_LOCKTIME) {
+            Some(value) => read_u32(value)?,
+            None => 0,
+        };
+
+        let mut heights = Vec::<u32>::new();
+        let mut times = Vec::<u32>::new();
+        let mut requiring = 0usize;
+        let mut supports_height = true;
+        let mut supports_time = true;
+
+        for map in &raw.inputs {
+            let height = find(map, IN_REQUIRED_HEIGHT_LOCKTIME)
+                .map(read_u32)
+                .transpose()?;
+            let time = find(map, IN_REQUIRED_TIME_LOCKTIME)
+                .map(read_u32)
+                .transpose()?;
+
+            if height.is_none() && time.is_none() {
+                continue;
+            }
+
+            requiring += 1;
+            supports_height &= height.is_some();
+            supports_time &= time.is_some();
+            heights.extend(height);
+            times.extend(time);
+        }
+
+        let lock_time = if requiring == 0 {
+            fallback
+        } else if supports_height {
+            heights.into_iter().max().unwrap_or_default()
+        } else if supports_time {
+            times.into_iter().max().unwrap_or_default()
+        } else {
+            return Err(psbt_error(
+                "The inputs of the PSBT require both a height and a time based locktime",
+            ));
+        };
+
+        Ok(LockTime::from_consensus(lock_time))
+    }
+}
+
+impl RawPsbt {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+
+        std::iter::once(&self.global)
+            .chain(self.inputs.iter())
+            .chain(self.outputs.iter())
+            .for_each(|map| {
+                map.iter().for_each(|(key, value)| {
+                    write_compact_size(&mut bytes, key.len() as u64);
+                    bytes.extend_from_slice(key);
+                    write_compact_size(&mut bytes, value.len() as u64);
+                    bytes.extend_from_slice(value);
+                });
+                bytes.push(0x00);
+            });
+
+        bytes
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> AtollWalletResult<Self> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(psbt_error(
+                "The data does not start with the PSBT magic bytes",
+            ));
+        }
+
+        Ok(Self {
+            bytes,
+            position: MAGIC.len(),
+        })
+    }
+
+    fn map(&mut self) -> AtollWalletResult<Vec<KeyValue>> {
+        let mut map = Vec::<KeyValue>::new();
+
+        loop {
+            let key_length = self.compact_size()? as usize;
+            if key_length == 0 {
+                return Ok(map);
+            }
+            let key = self.take(key_length)?.to_vec();
+
+            let value_length = self.compact_size()? as usize;
+            let value = self.take(value_length)?.to_vec();
+
+            map.push((key, value));
+        }
+    }
+
+    fn take(&mut self, length: usize) -> AtollWalletResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(length)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(psbt_error("The PSBT ended unexpectedly"))?;
+
+        let taken = &self.bytes[self.position..end];
+        self.position = end;
+
+        Ok(taken)
+    }
+
+    fn compact_size(&mut self) -> AtollWalletResult<u64> {
+        let mut remaining = &self.bytes[self.position.min(self.bytes.len())..];
+        let before = remaining.len();
+
+        let value = bitcoin::VarInt::consensus_decode(&mut remaining)
+            .or(Err(psbt_error("Invalid compact size in the PSBT")))?;
+        self.position += before - remaining.len();
+
+        Ok(value.0)
+    }
+}
+
+fn find(map: &[KeyValue], key_type: u8) -> Option<&[u8]> {
+    map.iter()
+        .find(|(key, _)| key.as_slice() == [key_type])
+        .map(|(_, value)| value.as_slice())
+}
+
+/// Splits out the entries whose key type is one of `key_types`
+fn split(map: Vec<KeyValue>, key_types: &[u8]) -> (Vec<KeyValue>, Vec<KeyValue>) {
+    map.into_iter()
+        .partition(|(key, _)| key.len() == 1 && key_types.contains(&key[0]))
+}
+
+fn read_u32(value: &[u8]) -> AtollWalletResult<u32> {
+    Ok(u32::from_le_bytes(
+        value
+            .try_into()
+            .or(Err(psbt_error("Expected a 4 byte PSBT value")))?,
+    ))
+}
+
+fn read_u64(value: &[u8]) -> AtollWalletResult<u64> {
+    Ok(u64::from_le_bytes(
+        value
+            .try_into()
+            .or(Err(psbt_error("Expected an 8 byte PSBT value")))?,
+    ))
+}
+
+fn read_compact_value(mut value: &[u8]) -> AtollWalletResult<u64> {
+    bitcoin::VarInt::consensus_decode(&mut value)
+        .map(|value| value.0)
+        .or(Err(psbt_error("Invalid compact size in the PSBT")))
+}
+
+fn write_compact_size(bytes: &mut Vec<u8>, value: u64) {
+    bytes.extend_from_slice(&serialize(&bitcoin::VarInt(value)));
+}
+
+fn psbt_error(message: &str) -> AtollWalletError {
+    AtollWalletError::Bitcoin(message.to_string())
+}
@@ -0,0 +1,333 @@
+use bitcoin::{Amount, FeeRate, ScriptBuf, Weight};
+
+use crate::{AtollWalletError, AtollWalletResult, BitcoinUtxo};
+
+/// The weight of the version, locktime, SegWit marker and flag and the input
+/// and output counts of a transaction with less than 253 inputs and outputs
+pub const TX_OVERHEAD_WEIGHT: Weight = Weight::from_wu(4 * (4 + 4 + 1 + 1) + 2);
+
+/// The weight of an input spending `script_pubkey` once signed, or `None`
+/// if the wallet cannot sign for that script type
+pub fn input_weight(script_pubkey: &ScriptBuf) -> Option<Weight> {
+    // The outpoint, empty script sig and sequence are not witness data
+    let base = 4 * (32 + 4 + 1 + 4);
+
+    let witness = if script_pubkey.is_p2wpkh() {
+        // Item count, a 72 byte DER signature with its sighash byte and a compressed key
+        1 + 1 + 72 + 1 + 33
+    } else if script_pubkey.is_p2tr() {
+        // Item count and a 64 byte Schnorr signature with the default sighash
+        1 + 1 + 64
+    } else {
+        return None;
+    };
+
+    Some(Weight::from_wu(base + witness))
+}
+
+/// The weight of an output paying to `script_pubkey`
+pub fn output_weight(script_pubkey: &ScriptBuf) -> Weight {
+    let script_len = script_pubkey.len() as u64;
+    let script_len_prefix = bitcoin::VarInt(script_len).size() as u64;
+
+    Weight::from_wu(4 * (8 + script_len_prefix + script_len))
+}
+
+/// The fee for `weight` at `fee_rate`, rounded up. The weight is rounded up to whole
+/// virtual bytes first since relay policy compares the fee rate in sat/vB.
+pub fn fee_for_weight(fee_rate: FeeRate, weight: Weight) -> Amount {
+    Amount::from_sat((fee_rate.to_sat_per_kwu() * weight.to_vbytes_ceil() * 4).div_ceil(1000))
+}
+
+/// An unspent output with the value it adds to a transaction after paying for its own input
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Candidate {
+    index: usize,
+    effective_value: u64,
+}
+
+/// Selects the unspent outputs funding a transaction.
+///
+/// Branch and bound, as in Bitcoin Core, searches for a set of outputs that pays the target
+/// without change. When there is none, the knapsack solver picks a set leaving at least
+/// `min_change` for a change output. The knapsack solver uses a fixed seed so that the same
+/// unspent outputs always give the same selection.
+pub struct CoinSelection;
+
+impl CoinSelection {
+    /// The number of branches explored by branch and bound before giving up
+    pub const BNB_TOTAL_TRIES: usize = 100_000;
+    /// The number of random subsets tried by the knapsack solver
+    pub const KNAPSACK_ITERATIONS: usize = 1000;
+
+    /// Returns the indexes of the `utxos` selected to pay `target`, which must include the fee
+    /// of everything but the inputs. `cost_of_change` is the fee of creating and later spending
+    /// a change output and `change_fee` the fee of creating it.
+    pub fn select(
+        utxos: &[BitcoinUtxo],
+        target: Amount,
+        fee_rate: FeeRate,
+        cost_of_change: Amount,
+        change_fee: Amount,
+        min_change: Amount,
+    ) -> AtollWalletResult<Vec<usize>> {
+        let mut candidates = utxos
+            .iter()
+            .enumerate()
+            .filter_map(|(index, utxo)| {
+                let input_fee = fee_for_weight(fee_rate, input_weight(&utxo.script_pubkey)?);
+
+                utxo.value
+                    .checked_sub(input_fee)
+                    .filter(|effective_value| *effective_value > Amount::ZERO)
+                    .map(|effective_value| Candidate {
+                        index,
+                        effective_value: effective_value.to_sat(),
+                    })
+            })
+            .collect::<Vec<Candidate>>();
+
+        // Largest first, ties broken by position so the order is deterministic
+        candidates.sort_by(|a, b| {
+            b.effective_value
+                .cmp(&a.effective_value)
+                .then(a.index.cmp(&b.index))
+        });
+
+        let available = candidates
+            .iter()
+            .map(|candidate| candidate.effective_value)
+            .sum::<u64>();
+
+        if available < target.to_sat() {
+            return Err(AtollWalletError::Bitcoin(format!(
+                "Insufficient funds. `{}` is needed but only `{}` can be spent at this fee rate",
+                target,
+                Amount::from_sat(available)
+            )));
+        }
+
+        if let Some(selection) =
+            Self::branch_and_bound(&candidates, target.to_sat(), cost_of_change.to_sat())
+        {
+            return Ok(selection);
+        }
+
+        // Spending everything without change still pays `target` when the
+        // knapsack solver cannot also pay for a change output
+        Ok(Self::knapsack(
+            &candidates,
+            (target + change_fee).to_sat(),
+            min_change.to_sat(),
+        )
+        .unwrap_or_else(|| candidates.iter().map(|candidate| candidate.index).collect()))
+    }
+
+    /// Depth first search for the selection closest to `target` without exceeding
+    /// `target + cost_of_change`. `candidates` must be sorted largest first.
+    fn branch_and_bound(
+        candidates: &[Candidate],
+        target: u64,
+        cost_of_change: u64,
+    ) -> Option<Vec<usize>> {
+        let mut current_value = 0u64;
+        let mut current_available = candidates
+            .iter()
+            .map(|candidate| candidate.effective_value)
+            .sum::<u64>();
+        let mut current_selection = Vec::<usize>::new();
+        let mut best_selection = Option::<Vec<usize>>::None;
+        let mut best_waste = u64::MAX;
+
+        let mut index = 0usize;
+
+        for _ in 0..Self::BNB_TOTAL_TRIES {
+            let backtrack = if current_value + current_available < target
+                || current_value > target + cost_of_change
+            {
+                true
+            } else if current_value >= target {
+                let waste = current_value - target;
+
+                if waste <= best_waste {
+                    best_waste = waste;
+                    best_selection.replace(current_selection.clone());
+                }
+
+                true
+            } else {
+                false
+            };
+
+            if backtrack {
+                let Some(&last) = current_selection.last() else {
+                    break;
+                };
+
+                // Give back the candidates omitted after the last selected one
+                while index > last + 1 {
+                    index -= 1;
+                    current_available += candidates[index].effective_value;
+                }
+
+                // Explore the branch omitting the last selected candidate
+                current_selection.pop();
+                current_value -= candidates[last].effective_value;
+                index = last + 1;
+            } else {
+                let candidate = &candidates[index];
+                current_available -= candidate.effective_value;
+
+                // Omitting a candidate equal to the previous omitted one gives the same branch
+                let previous_omitted_equal = current_selection
+                    .last()
+                    .is_some_and(|last| *last != index - 1)
+                    && candidates[index - 1].effective_value == candidate.effective_value;
+
+                if !previous_omitted_equal {
+                    current_selection.push(index);
+                    current_value += candidate.effective_value;
+                }
+
+                index += 1;
+            }
+
+            if best_waste == 0 {
+                break;
+            }
+        }
+
+        best_selection.map(|selection| {
+            selection
+                .into_iter()
+                .map(|position| candidates[position].index)
+                .collect()
+        })
+    }
+
+    /// The knapsack solver of Bitcoin Core. `candidates` must be sorted largest first.
+    fn knapsack(candidates: &[Candidate], target: u64, min_change: u64) -> Option<Vec<usize>> {
+        let mut applicable = Vec::<&Candidate>::new();
+        let mut lowest_larger = Option::<&Candidate>::None;
+        let mut total_lower = 0u64;
+
+        for candidate in candidates {
+            if candidate.effective_value == target {
+                return Some(vec![candidate.index]);
+            } else if candidate.effective_value < target + min_change {
+                applicable.push(candidate);
+                total_lower += candidate.effective_value;
+            } else if lowest_larger
+                .is_none_or(|lowest| candidate.effective_value < lowest.effective_value)
+            {
+                lowest_larger.replace(candidate);
+            }
+        }
+
+        if total_lower == target {
+            return Some(applicable.iter().map(|candidate| candidate.index).collect());
+        }
+
+        if total_lower < target {
+            return lowest_larger.map(|candidate| vec![candidate.index]);
+        }
+
+        let values = applicable
+            .iter()
+            .map(|candidate| candidate.effective_value)
+            .collect::<Vec<u64>>();
+
+        let (mut best, mut best_value) =
+            Self::approximate_best_subset(&values, total_lower, target);
+
+        if best_value != target && total_lower >= target + min_change {
+            (best, best_value) =
+                Self::approximate_best_subset(&values, total_lower, target + min_change);
+        }
+
+        match lowest_larger {
+            Some(lowest_larger)
+                if (best_value != target && best_value < target + min_change)
+                    || lowest_larger.effective_value <= best_value =>
+            {
+                Some(vec![lowest_larger.index])
+            }
+            _ => Some(
+                applicable
+                    .iter()
+                    .zip(best)
+                    .filter_map(|(candidate, included)| included.then_some(candidate.index))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn approximate_best_subset(values: &[u64], total_lower: u64, target: u64) -> (Vec<bool>, u64) {
+        let mut rng = XorShift::default();
+
+        let mut best = vec![true; values.len()];
+        let mut best_value = total_lower;
+
+        for _ in 0..Self::KNAPSACK_ITERATIONS {
+            if best_value == target {
+                break;
+            }
+
+            let mut included = vec![false; values.len()];
+            let mut total = 0u64;
+            let mut reached_target = false;
+
+            for pass in 0..2 {
+                if reached_target {
+                    break;
+                }
+
+                for (position, value) in values.iter().enumerate() {
+                    let include = if pass == 0 {
+                        rng.next_bool()
+                    } else {
+                        !included[position]
+                    };
+
+                    if include {
+                        total += value;
+                        included[position] = true;
+
+                        if total >= target {
+                            reached_target = true;
+
+                            if total < best_value {
+                                best_value = total;
+                                best = included.clone();
+                            }
+
+                            total -= value;
+                            included[position] = false;
+                        }
+                    }
+                }
+            }
+        }
+
+        (best, best_value)
+    }
+}
+
+/// A xorshift64 generator with a fixed seed so coin selection is reproducible
+struct XorShift(u64);
+
+impl Default for XorShift {
+    fn default() -> Self {
+        Self(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+impl XorShift {
+    fn next_bool(&mut self) -> bool {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+
+        self.0 & 1 == 1
+    }
+}
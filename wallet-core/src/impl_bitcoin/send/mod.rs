@@ -0,0 +1,317 @@
+mod coin_selection;
+pub use coin_selection::*;
+
+use std::collections::BTreeMap;
+
+use bitcoin::{
+    Address, Amount, FeeRate, OutPoint, Psbt, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Witness, XOnlyPublicKey, absolute::LockTime, transaction::Version,
+};
+
+use crate::{
+    AtollWalletError, AtollWalletResult, BitcoinAccountKeypair, BitcoinPsbt, BitcoinPurpose,
+    BitcoinUtxo,
+};
+
+/// Builds a transaction sending BTC from the unspent outputs of the wallet
+#[derive(Debug, Clone)]
+pub struct BitcoinSendBuilder {
+    utxos: Vec<BitcoinUtxo>,
+    recipients: Vec<TxOut>,
+    change_script: ScriptBuf,
+    fee_rate: FeeRate,
+    rbf: bool,
+}
+
+impl BitcoinSendBuilder {
+    /// The lowest fee rate in sat/vB, the default minimum relay fee, which is also
+    /// used when no fee rate is set
+    pub const MIN_FEE_RATE: f64 = 1.0;
+
+    /// Spends from `utxos` and sends any change to `change_address`
+    pub fn new(utxos: Vec<BitcoinUtxo>, change_address: &Address) -> Self {
+        Self {
+            utxos,
+            recipients: Vec::default(),
+            change_script: change_address.script_pubkey(),
+            fee_rate: FeeRate::BROADCAST_MIN,
+            rbf: true,
+        }
+    }
+
+    pub fn add_recipient(mut self, address: &Address, amount: Amount) -> Self {
+        self.recipients.push(TxOut {
+            value: amount,
+            script_pubkey: address.script_pubkey(),
+        });
+
+        self
+    }
+
+    /// Sets the fee rate in sat/vB. Fractional rates are rounded up to the next sat/kwu.
+    pub fn set_fee_rate(mut self, sat_per_vb: f64) -> AtollWalletResult<Self> {
+        if !sat_per_vb.is_finite() || sat_per_vb < Self::MIN_FEE_RATE {
+            return Err(AtollWalletError::Input(format!(
+                "The fee rate `{sat_per_vb}` sat/vB is below the minimum relay fee of `{}` sat/vB",
+                Self::MIN_FEE_RATE
+            )));
+        }
+
+        self.fee_rate = FeeRate::from_sat_per_kwu((sat_per_vb * 250.0).ceil() as u64);
+
+        Ok(self)
+    }
+
+    /// Signals [BIP125](https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki)
+    /// replace-by-fee on every input, enabled by default
+    pub fn set_rbf(mut self, rbf: bool) -> Self {
+        self.rbf = rbf;
+
+        self
+    }
+
+    pub fn fee_rate(&self) -> FeeRate {
+        self.fee_rate
+    }
+
+    /// Selects the inputs and builds the unsigned transaction
+    pub fn build(&self) -> AtollWalletResult<BitcoinSendPlan> {
+        if self.recipients.is_empty() {
+            return Err(AtollWalletError::Input(
+                "A Bitcoin transaction requires at least one recipient".to_string(),
+            ));
+        }
+
+        if let Some(recipient) = self
+            .recipients
+            .iter()
+            .find(|recipient| recipient.value < recipient.script_pubkey.minimal_non_dust())
+        {
+            return Err(AtollWalletError::Input(format!(
+                "`{}` is below the dust threshold of `{}` for the recipient",
+                recipient.value,
+                recipient.script_pubkey.minimal_non_dust()
+            )));
+        }
+
+        let amount = self
+            .recipients
+            .iter()
+            .try_fold(Amount::ZERO, |total, recipient| {
+                total.checked_add(recipient.value)
+            })
+            .ok_or(AtollWalletError::Input(
+                "The total amount sent overflows".to_string(),
+            ))?;
+
+        let recipients_weight = self
+            .recipients
+            .iter()
+            .map(|recipient| output_weight(&recipient.script_pubkey))
+            .fold(TX_OVERHEAD_WEIGHT, |total, weight| total + weight);
+
+        let change_weight = output_weight(&self.change_script);
+        let change_fee = fee_for_weight(self.fee_rate, change_weight);
+        let change_spend_fee = fee_for_weight(
+            self.fee_rate,
+            input_weight(&self.change_script).ok_or(AtollWalletError::Bitcoin(
+                "The change address must be a P2WPKH or P2TR address".to_string(),
+            ))?,
+        );
+        let dust_threshold = self.change_script.minimal_non_dust();
+
+        let selection = CoinSelection::select(
+            &self.utxos,
+            amount + fee_for_weight(self.fee_rate, recipients_weight),
+            self.fee_rate,
+            change_fee + change_spend_fee,
+            change_fee,
+            dust_threshold,
+        )?;
+
+        let inputs = selection
+            .into_iter()
+            .map(|index| self.utxos[index].clone())
+            .collect::<Vec<BitcoinUtxo>>();
+
+        let input_value = inputs.iter().map(|utxo| utxo.value).sum::<Amount>();
+        let inputs_weight = inputs
+            .iter()
+            .filter_map(|utxo| input_weight(&utxo.script_pubkey))
+            .fold(recipients_weight, |total, weight| total + weight);
+
+        let fee_without_change = fee_for_weight(self.fee_rate, inputs_weight);
+        let fee_with_change = fee_for_weight(self.fee_rate, inputs_weight + change_weight);
+
+        let change = (input_value.checked_sub(amount + fee_with_change))
+            .filter(|change| *change >= dust_threshold);
+
+        let fee = match change {
+            Some(change) => input_value - amount - change,
+            None => input_value
+                .checked_sub(amount)
+                .filter(|fee| *fee >= fee_without_change)
+                .ok_or(AtollWalletError::Bitcoin(format!(
+                    "Insufficient funds to pay `{amount}` and a fee of `{fee_without_change}`"
+                )))?,
+        };
+
+        let sequence = if self.rbf {
+            Sequence::ENABLE_RBF_NO_LOCKTIME
+        } else {
+            Sequence::ENABLE_LOCKTIME_NO_RBF
+        };
+
+        let mut output = self.recipients.clone();
+        let change_index = change.map(|change| {
+            output.push(TxOut {
+                value: change,
+                script_pubkey: self.change_script.clone(),
+            });
+
+            output.len() - 1
+        });
+
+        let transaction = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: inputs
+                .iter()
+                .map(|utxo| TxIn {
+                    previous_output: utxo.outpoint,
+                    script_sig: ScriptBuf::new(),
+                    sequence,
+                    witness: Witness::new(),
+                })
+                .collect(),
+            output,
+        };
+
+        Ok(BitcoinSendPlan {
+            transaction,
+            inputs,
+            fee,
+            change_index,
+        })
+    }
+}
+
+/// The unsigned transaction built by a [BitcoinSendBuilder]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BitcoinSendPlan {
+    transaction: Transaction,
+    inputs: Vec<BitcoinUtxo>,
+    fee: Amount,
+    change_index: Option<usize>,
+}
+
+impl BitcoinSendPlan {
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// The unspent outputs spent, in the order of the transaction inputs
+    pub fn inputs(&self) -> &[BitcoinUtxo] {
+        &self.inputs
+    }
+
+    pub fn outpoints(&self) -> Vec<OutPoint> {
+        self.inputs.iter().map(|utxo| utxo.outpoint).collect()
+    }
+
+    pub fn fee(&self) -> Amount {
+        self.fee
+    }
+
+    /// The change output, if the change was above the dust threshold
+    pub fn change(&self) -> Option<&TxOut> {
+        self.change_index
+            .and_then(|index| self.transaction.output.get(index))
+    }
+
+    /// The virtual size of the transaction once signed
+    pub fn estimated_vsize(&self) -> u64 {
+        let weight = self
+            .inputs
+            .iter()
+            .filter_map(|utxo| input_weight(&utxo.script_pubkey))
+            .chain(
+                self.transaction
+                    .output
+                    .iter()
+                    .map(|output| output_weight(&output.script_pubkey)),
+            )
+            .fold(TX_OVERHEAD_WEIGHT, |total, weight| total + weight);
+
+        weight.to_vbytes_ceil()
+    }
+
+    /// A PSBT of the transaction with the previous outputs of the inputs and the key
+    /// origins of the inputs and change output belonging to `keypairs`
+    pub fn to_psbt(&self, keypairs: &[&BitcoinAccountKeypair]) -> AtollWalletResult<BitcoinPsbt> {
+        let mut psbt = Psbt::from_unsigned_tx(self.transaction.clone())
+            .map_err(|error| AtollWalletError::Bitcoin(error.to_string()))?;
+
+        let keypair_for = |script_pubkey: &ScriptBuf| {
+            keypairs
+                .iter()
+                .find(|keypair| keypair.address().script_pubkey() == *script_pubkey)
+        };
+
+        for (input, utxo) in psbt.inputs.iter_mut().zip(self.inputs.iter()) {
+            input.witness_utxo.replace(TxOut {
+                value: utxo.value,
+                script_pubkey: utxo.script_pubkey.clone(),
+            });
+
+            if let Some(keypair) = keypair_for(&utxo.script_pubkey) {
+                Self::set_key_origin(
+                    keypair,
+                    &mut input.bip32_derivation,
+                    &mut input.tap_key_origins,
+                    &mut input.tap_internal_key,
+                );
+            }
+        }
+
+        for (output, tx_out) in psbt.outputs.iter_mut().zip(self.transaction.output.iter()) {
+            if let Some(keypair) = keypair_for(&tx_out.script_pubkey) {
+                Self::set_key_origin(
+                    keypair,
+                    &mut output.bip32_derivation,
+                    &mut output.tap_key_origins,
+                    &mut output.tap_internal_key,
+                );
+            }
+        }
+
+        Ok(psbt.into())
+    }
+
+    fn set_key_origin(
+        keypair: &BitcoinAccountKeypair,
+        bip32_derivation: &mut BTreeMap<bitcoin::secp256k1::PublicKey, bitcoin::bip32::KeySource>,
+        tap_key_origins: &mut BTreeMap<
+            XOnlyPublicKey,
+            (Vec<bitcoin::TapLeafHash>, bitcoin::bip32::KeySource),
+        >,
+        tap_internal_key: &mut Option<XOnlyPublicKey>,
+    ) {
+        let key_source = (
+            keypair.master_fingerprint(),
+            keypair.derivation_path().clone(),
+        );
+
+        match keypair.purpose() {
+            BitcoinPurpose::Payment => {
+                bip32_derivation.insert(keypair.public_key(), key_source);
+            }
+            BitcoinPurpose::Ordinals => {
+                let x_only = XOnlyPublicKey::from(keypair.public_key());
+
+                tap_key_origins.insert(x_only, (Vec::default(), key_source));
+                tap_internal_key.replace(x_only);
+            }
+        }
+    }
+}
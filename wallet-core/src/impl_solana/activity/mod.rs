@@ -0,0 +1,317 @@
+mod rows;
+pub use rows::*;
+
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use wallet_standard_base::Commitment;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, HttpTransport, SolanaCluster, SolanaCommitment,
+    SolanaConfirmedTransaction, SolanaRpc, SolanaSignatureStatus,
+};
+
+/// A transaction of an account, either signed by the wallet or found in the history
+/// of the account on chain
+#[derive(Debug, PartialEq, Clone)]
+pub struct SolanaActivityEntry {
+    pub signature: Signature,
+    pub cluster: SolanaCluster,
+    /// The origin of the dapp that requested the signature, `None` for transfers
+    /// sent from the popup and for history found on chain
+    pub origin: Option<String>,
+    /// The unix timestamp in milliseconds at which the wallet signed the transaction
+    pub signed_at: Option<f64>,
+    pub slot: Option<u64>,
+    /// The unix timestamp in seconds of the block that included the transaction
+    pub block_time: Option<i64>,
+    /// `None` until the cluster reports the transaction
+    pub commitment: Option<SolanaCommitment>,
+    /// The transaction error as JSON if the transaction failed or expired
+    pub error: Option<String>,
+    /// The fee in lamports charged to the fee payer, known once the transaction is fetched
+    pub fee: Option<u64>,
+    pub rows: Vec<SolanaActivityRow>,
+}
+
+impl SolanaActivityEntry {
+    /// How long in milliseconds after signing a transaction that the cluster has not seen
+    /// is considered dropped, which is when its blockhash has expired
+    pub const EXPIRY_MS: f64 = 120_000.0;
+
+    /// An entry for a transaction the wallet signed and sent at `now_ms`
+    pub fn signed(
+        signature: Signature,
+        cluster: SolanaCluster,
+        origin: Option<String>,
+        message: &Message,
+        now_ms: f64,
+    ) -> Self {
+        Self {
+            signature,
+            cluster,
+            origin,
+            signed_at: Some(now_ms),
+            slot: None,
+            block_time: None,
+            commitment: None,
+            error: None,
+            fee: None,
+            rows: SolanaActivityRow::from_instructions(
+                &message.account_keys,
+                &message.instructions,
+            ),
+        }
+    }
+
+    /// An entry for a transaction fetched from `cluster`
+    pub fn confirmed(cluster: SolanaCluster, transaction: &SolanaConfirmedTransaction) -> Self {
+        Self {
+            signature: transaction.signature,
+            cluster,
+            origin: None,
+            signed_at: None,
+            slot: Some(transaction.slot),
+            block_time: transaction.block_time,
+            commitment: Some(SolanaCommitment::Confirmed),
+            error: transaction.error.clone(),
+            fee: transaction.fee,
+            rows: SolanaActivityRow::from_instructions(
+                &transaction.account_keys,
+                &transaction.instructions,
+            ),
+        }
+    }
+
+    /// `pending`, `failed` or the commitment reached
+    pub fn status(&self) -> &str {
+        match (&self.error, &self.commitment) {
+            (Some(_), _) => "failed",
+            (None, Some(commitment)) => commitment.as_str(),
+            (None, None) => "pending",
+        }
+    }
+
+    /// Whether the status of the entry can no longer change
+    pub fn is_settled(&self) -> bool {
+        self.error.is_some() || self.commitment == Some(SolanaCommitment::Finalized)
+    }
+
+    /// The unix timestamp in milliseconds entries are ordered by
+    pub fn timestamp(&self) -> f64 {
+        self.block_time
+            .map(|block_time| block_time as f64 * 1000.0)
+            .or(self.signed_at)
+            .unwrap_or_default()
+    }
+
+    pub fn update_status(&mut self, status: &SolanaSignatureStatus) {
+        self.slot.replace(status.slot);
+        self.block_time = status.block_time.or(self.block_time);
+        self.commitment = status.commitment.or(self.commitment);
+        self.error = status.error.clone();
+    }
+
+    /// Marks the entry failed if the cluster has not seen it long after it was signed
+    pub fn expire(&mut self, now_ms: f64) {
+        if self.commitment.is_none()
+            && self
+                .signed_at
+                .is_some_and(|signed_at| now_ms - signed_at > Self::EXPIRY_MS)
+        {
+            self.error
+                .replace("The transaction expired before it was confirmed".to_string());
+        }
+    }
+
+    /// Takes what the cluster knows from `other`, keeping the origin and signing time
+    /// recorded locally
+    fn merge(&mut self, other: Self) {
+        self.slot = other.slot.or(self.slot);
+        self.block_time = other.block_time.or(self.block_time);
+        self.commitment = other.commitment.max(self.commitment);
+        self.error = other.error.or(self.error.take());
+        self.fee = other.fee.or(self.fee);
+        self.origin = self.origin.take().or(other.origin);
+        self.signed_at = self.signed_at.or(other.signed_at);
+
+        if !other.rows.is_empty() {
+            self.rows = other.rows;
+        }
+    }
+}
+
+/// The activity of an account across clusters, newest first
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SolanaActivityLog {
+    entries: Vec<SolanaActivityEntry>,
+}
+
+impl SolanaActivityLog {
+    /// The most entries kept per account, dropping the oldest
+    pub const MAX_ENTRIES: usize = 1_000;
+    pub const DEFAULT_PAGE_SIZE: usize = 20;
+    pub const MAX_PAGE_SIZE: usize = 100;
+
+    pub fn entries(&self) -> &[SolanaActivityEntry] {
+        &self.entries
+    }
+
+    pub fn get(&self, signature: &Signature) -> Option<&SolanaActivityEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.signature == *signature)
+    }
+
+    /// Adds `entry`, or merges it into the entry with the same signature
+    pub fn record(&mut self, entry: SolanaActivityEntry) {
+        match self
+            .entries
+            .iter_mut()
+            .find(|existing| existing.signature == entry.signature)
+        {
+            Some(existing) => existing.merge(entry),
+            None => self.entries.push(entry),
+        }
+
+        self.entries.sort_by(|a, b| {
+            b.timestamp()
+                .total_cmp(&a.timestamp())
+                .then(b.slot.cmp(&a.slot))
+        });
+        self.entries.truncate(Self::MAX_ENTRIES);
+    }
+
+    /// Up to `limit` entries on `cluster` older than the entry `before`, or the newest
+    /// entries without a cursor. A full page carries the cursor of the next one.
+    pub fn page(
+        &self,
+        cluster: SolanaCluster,
+        before: Option<&Signature>,
+        limit: usize,
+    ) -> AtollWalletResult<SolanaActivityPage<'_>> {
+        let mut entries = self.entries.iter().filter(|entry| entry.cluster == cluster);
+
+        if let Some(before) = before {
+            entries
+                .by_ref()
+                .find(|entry| entry.signature == *before)
+                .ok_or(AtollWalletError::Input(format!(
+                    "The cursor `{before}` is not in the activity of the account"
+                )))?;
+        }
+
+        let entries = entries.take(limit).collect::<Vec<&SolanaActivityEntry>>();
+        let next_cursor = entries
+            .last()
+            .filter(|_| entries.len() == limit)
+            .map(|entry| entry.signature);
+
+        Ok(SolanaActivityPage {
+            entries,
+            next_cursor,
+        })
+    }
+
+    /// The signature to continue the history on chain from for the page after `before`,
+    /// which is the newest entry the cluster knows at or after `before`. Entries only
+    /// recorded locally may never have landed so the cluster cannot continue from them.
+    fn chain_cursor(
+        &self,
+        cluster: SolanaCluster,
+        before: Option<&Signature>,
+    ) -> Option<Signature> {
+        let before = before?;
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| entry.cluster == cluster)
+            .collect::<Vec<&SolanaActivityEntry>>();
+        let position = entries
+            .iter()
+            .position(|entry| entry.signature == *before)?;
+
+        entries[position..]
+            .iter()
+            .chain(entries[..position].iter().rev())
+            .find(|entry| entry.slot.is_some())
+            .map(|entry| entry.signature)
+    }
+
+    /// Fetches the history of `owner` on `cluster` for the page after `before` and
+    /// refreshes the status of the unsettled entries of that page
+    pub async fn sync<T: HttpTransport>(
+        &mut self,
+        rpc: &SolanaRpc<T>,
+        owner: &Pubkey,
+        cluster: SolanaCluster,
+        before: Option<&Signature>,
+        limit: usize,
+        now_ms: f64,
+    ) -> AtollWalletResult<()> {
+        let history = rpc
+            .get_signatures_for_address(owner, self.chain_cursor(cluster, before).as_ref(), limit)
+            .await?;
+
+        for status in history.iter() {
+            let existing = self.get(&status.signature).cloned();
+
+            // Transactions are only fetched once, after that only their status changes
+            let mut entry = match existing {
+                Some(entry) if entry.fee.is_some() => entry,
+                existing => match rpc.get_transaction(&status.signature).await? {
+                    Some(transaction) => SolanaActivityEntry::confirmed(cluster, &transaction),
+                    None => match existing {
+                        Some(entry) => entry,
+                        None => continue,
+                    },
+                },
+            };
+            entry.update_status(status);
+
+            self.record(entry);
+        }
+
+        let unsettled = self
+            .page(cluster, before, limit)?
+            .entries
+            .into_iter()
+            .filter(|entry| {
+                !entry.is_settled()
+                    && !history
+                        .iter()
+                        .any(|status| status.signature == entry.signature)
+            })
+            .map(|entry| entry.signature)
+            .collect::<Vec<Signature>>();
+
+        if unsettled.is_empty() {
+            return Ok(());
+        }
+
+        let statuses = rpc.get_signature_statuses(&unsettled).await?;
+
+        for (signature, status) in unsettled.iter().zip(statuses) {
+            if let Some(entry) = self
+                .entries
+                .iter_mut()
+                .find(|entry| entry.signature == *signature)
+            {
+                match status {
+                    Some(status) => entry.update_status(&status),
+                    None => entry.expire(now_ms),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A page of a [SolanaActivityLog]
+#[derive(Debug, PartialEq, Clone)]
+pub struct SolanaActivityPage<'a> {
+    pub entries: Vec<&'a SolanaActivityEntry>,
+    /// The signature to pass as `before` for the next page, `None` on the last page
+    pub next_cursor: Option<Signature>,
+}
@@ -0,0 +1,330 @@
+use solana_message::compiled_instruction::CompiledInstruction;
+use solana_pubkey::Pubkey;
+
+use crate::{SolanaInstructions, SplTokenProgram};
+
+/// What an instruction of a transaction did, decoded for the programs the wallet knows
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SolanaActivityRow {
+    /// A System program transfer
+    SolTransfer {
+        from: Pubkey,
+        to: Pubkey,
+        lamports: u64,
+    },
+    /// A System program account creation funded by `funder`
+    CreateAccount {
+        funder: Pubkey,
+        account: Pubkey,
+        lamports: u64,
+        owner: Pubkey,
+    },
+    /// A `Transfer`, `TransferChecked` or `TransferCheckedWithFee` of the token programs.
+    /// `mint` and `decimals` are only known for checked transfers.
+    TokenTransfer {
+        program: SplTokenProgram,
+        source: Pubkey,
+        destination: Pubkey,
+        authority: Pubkey,
+        mint: Option<Pubkey>,
+        amount: u64,
+        decimals: Option<u8>,
+        /// The transfer fee withheld from `amount`
+        fee: Option<u64>,
+    },
+    /// The creation of the associated token account of `wallet` for `mint`
+    CreateTokenAccount {
+        account: Pubkey,
+        wallet: Pubkey,
+        mint: Pubkey,
+    },
+    Memo(String),
+    /// An instruction of a program the wallet does not decode
+    ProgramCall {
+        program_id: Pubkey,
+    },
+}
+
+impl SolanaActivityRow {
+    /// The first version of the Memo program which does not require signers
+    pub const MEMO_V1_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+    /// The decimals of SOL
+    pub const SOL_DECIMALS: u8 = 9;
+
+    const SYSTEM_CREATE_ACCOUNT: u32 = 0;
+    const SYSTEM_TRANSFER: u32 = 2;
+    const TOKEN_TRANSFER: u8 = 3;
+    const TOKEN_TRANSFER_CHECKED: u8 = 12;
+    const TOKEN_TRANSFER_FEE_EXTENSION: u8 = 26;
+    const TOKEN_TRANSFER_CHECKED_WITH_FEE: u8 = 1;
+
+    /// The rows of the compiled `instructions` of a message with `account_keys`, skipping
+    /// `ComputeBudget` instructions and instructions referencing missing accounts
+    pub fn from_instructions(
+        account_keys: &[Pubkey],
+        instructions: &[CompiledInstruction],
+    ) -> Vec<Self> {
+        instructions
+            .iter()
+            .filter_map(|instruction| {
+                let program_id = account_keys.get(instruction.program_id_index as usize)?;
+                let accounts = instruction
+                    .accounts
+                    .iter()
+                    .map(|index| account_keys.get(*index as usize).copied())
+                    .collect::<Option<Vec<Pubkey>>>()?;
+
+                Self::parse(program_id, &accounts, &instruction.data)
+            })
+            .collect()
+    }
+
+    /// Decodes one instruction, `None` for `ComputeBudget` instructions
+    pub fn parse(program_id: &Pubkey, accounts: &[Pubkey], data: &[u8]) -> Option<Self> {
+        if *program_id == SolanaInstructions::COMPUTE_BUDGET_PROGRAM_ID {
+            return None;
+        }
+
+        let program_call = Self::ProgramCall {
+            program_id: *program_id,
+        };
+
+        let decoded = if *program_id == SolanaInstructions::SYSTEM_PROGRAM_ID {
+            Self::parse_system(accounts, data)
+        } else if let Some(program) = SplTokenProgram::from_program_id(program_id) {
+            Self::parse_token(program, accounts, data)
+        } else if *program_id == SolanaInstructions::ASSOCIATED_TOKEN_PROGRAM_ID {
+            Self::parse_associated_token(accounts, data)
+        } else if *program_id == SolanaInstructions::MEMO_PROGRAM_ID
+            || *program_id == Self::MEMO_V1_PROGRAM_ID
+        {
+            Some(Self::Memo(String::from_utf8_lossy(data).to_string()))
+        } else {
+            None
+        };
+
+        Some(decoded.unwrap_or(program_call))
+    }
+
+    fn parse_system(accounts: &[Pubkey], data: &[u8]) -> Option<Self> {
+        match u32::from_le_bytes(data.get(..4)?.try_into().ok()?) {
+            Self::SYSTEM_TRANSFER => Some(Self::SolTransfer {
+                from: *accounts.first()?,
+                to: *accounts.get(1)?,
+                lamports: read_u64(data, 4)?,
+            }),
+            Self::SYSTEM_CREATE_ACCOUNT => Some(Self::CreateAccount {
+                funder: *accounts.first()?,
+                account: *accounts.get(1)?,
+                lamports: read_u64(data, 4)?,
+                owner: Pubkey::try_from(data.get(20..52)?).ok()?,
+            }),
+            _ => None,
+        }
+    }
+
+    fn parse_token(program: SplTokenProgram, accounts: &[Pubkey], data: &[u8]) -> Option<Self> {
+        match (data.first()?, data.get(1)) {
+            (&Self::TOKEN_TRANSFER, _) => Some(Self::TokenTransfer {
+                program,
+                source: *accounts.first()?,
+                destination: *accounts.get(1)?,
+                authority: *accounts.get(2)?,
+                mint: None,
+                amount: read_u64(data, 1)?,
+                decimals: None,
+                fee: None,
+            }),
+            (&Self::TOKEN_TRANSFER_CHECKED, _) => Some(Self::TokenTransfer {
+                program,
+                source: *accounts.first()?,
+                destination: *accounts.get(2)?,
+                authority: *accounts.get(3)?,
+                mint: Some(*accounts.get(1)?),
+                amount: read_u64(data, 1)?,
+                decimals: Some(*data.get(9)?),
+                fee: None,
+            }),
+            (&Self::TOKEN_TRANSFER_FEE_EXTENSION, Some(&Self::TOKEN_TRANSFER_CHECKED_WITH_FEE)) => {
+                Some(Self::TokenTransfer {
+                    program,
+                    source: *accounts.first()?,
+                    destination: *accounts.get(2)?,
+                    authority: *accounts.get(3)?,
+                    mint: Some(*accounts.get(1)?),
+                    amount: read_u64(data, 2)?,
+                    decimals: Some(*data.get(10)?),
+                    fee: Some(read_u64(data, 11)?),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_associated_token(accounts: &[Pubkey], data: &[u8]) -> Option<Self> {
+        // `Create` is sent with no data or a zero byte and `CreateIdempotent` with a one
+        match data.first() {
+            None | Some(0) | Some(1) => Some(Self::CreateTokenAccount {
+                account: *accounts.get(1)?,
+                wallet: *accounts.get(2)?,
+                mint: *accounts.get(3)?,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::SolTransfer { .. } => "solTransfer",
+            Self::CreateAccount { .. } => "createAccount",
+            Self::TokenTransfer { .. } => "tokenTransfer",
+            Self::CreateTokenAccount { .. } => "createTokenAccount",
+            Self::Memo(_) => "memo",
+            Self::ProgramCall { .. } => "programCall",
+        }
+    }
+
+    /// Whether the row moves funds out of or into `owner`, `None` if it does neither
+    /// or the token account receiving funds is not the associated account of `owner`
+    pub fn direction(&self, owner: &Pubkey) -> Option<SolanaActivityDirection> {
+        match self {
+            Self::SolTransfer { from, .. } | Self::CreateAccount { funder: from, .. }
+                if from == owner =>
+            {
+                Some(SolanaActivityDirection::Sent)
+            }
+            Self::SolTransfer { to, .. } if to == owner => Some(SolanaActivityDirection::Received),
+            Self::TokenTransfer { authority, .. } if authority == owner => {
+                Some(SolanaActivityDirection::Sent)
+            }
+            Self::TokenTransfer {
+                program,
+                destination,
+                mint: Some(mint),
+                ..
+            } if *destination
+                == SolanaInstructions::associated_token_address(owner, mint, *program) =>
+            {
+                Some(SolanaActivityDirection::Received)
+            }
+            _ => None,
+        }
+    }
+
+    /// A sentence describing the row from the point of view of `owner`
+    pub fn description(&self, owner: &Pubkey) -> String {
+        let direction = self.direction(owner);
+
+        match self {
+            Self::SolTransfer { from, to, lamports } => {
+                let amount = format_amount(*lamports, Self::SOL_DECIMALS);
+
+                match direction {
+                    Some(SolanaActivityDirection::Sent) => {
+                        format!("Sent {amount} SOL to {}", short_address(to))
+                    }
+                    Some(SolanaActivityDirection::Received) => {
+                        format!("Received {amount} SOL from {}", short_address(from))
+                    }
+                    None => format!(
+                        "Transferred {amount} SOL from {} to {}",
+                        short_address(from),
+                        short_address(to)
+                    ),
+                }
+            }
+            Self::CreateAccount {
+                account,
+                lamports,
+                owner: program,
+                ..
+            } => format!(
+                "Created the account {} owned by {} with {} SOL",
+                short_address(account),
+                short_address(program),
+                format_amount(*lamports, Self::SOL_DECIMALS)
+            ),
+            Self::TokenTransfer {
+                source,
+                destination,
+                mint,
+                amount,
+                decimals,
+                ..
+            } => {
+                let amount = format_amount(*amount, decimals.unwrap_or_default());
+                let token = mint
+                    .map(|mint| format!(" of {}", short_address(&mint)))
+                    .unwrap_or_default();
+
+                match direction {
+                    Some(SolanaActivityDirection::Sent) => format!(
+                        "Sent {amount}{token} to the token account {}",
+                        short_address(destination)
+                    ),
+                    Some(SolanaActivityDirection::Received) => format!(
+                        "Received {amount}{token} from the token account {}",
+                        short_address(source)
+                    ),
+                    None => format!(
+                        "Transferred {amount}{token} from the token account {} to {}",
+                        short_address(source),
+                        short_address(destination)
+                    ),
+                }
+            }
+            Self::CreateTokenAccount { wallet, mint, .. } => format!(
+                "Created the {} token account of {}",
+                short_address(mint),
+                short_address(wallet)
+            ),
+            Self::Memo(memo) => format!("Memo: {memo}"),
+            Self::ProgramCall { program_id } => {
+                format!("Called the program {}", short_address(program_id))
+            }
+        }
+    }
+}
+
+/// Whether funds left or reached the account an activity is shown for
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SolanaActivityDirection {
+    Sent,
+    Received,
+}
+
+impl SolanaActivityDirection {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Sent => "sent",
+            Self::Received => "received",
+        }
+    }
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+/// Formats `amount` in the smallest unit with `decimals` without losing precision
+fn format_amount(amount: u64, decimals: u8) -> String {
+    let amount = format!("{amount:0>width$}", width = decimals as usize + 1);
+    let (whole, fraction) = amount.split_at(amount.len() - decimals as usize);
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{whole}.{fraction}")
+    }
+}
+
+/// The first and last four characters of `address`
+fn short_address(address: &Pubkey) -> String {
+    let address = address.to_string();
+
+    format!("{}…{}", &address[..4], &address[address.len() - 4..])
+}
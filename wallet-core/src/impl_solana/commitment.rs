@@ -1,3 +1,4 @@
+use serde::Deserialize;
 use wallet_standard_base::Commitment;
 
 /// The commitment level of a Solana transaction.
@@ -11,7 +12,8 @@ use wallet_standard_base::Commitment;
 /// `root` and `max` are parsed as `finalized`,
 ///
 /// Note that invalid commitment will always be converted to `Commitment::default()`
-#[derive(Debug, PartialEq, Eq, Default, PartialOrd, Ord, Clone, Copy, Hash)]
+#[derive(Debug, PartialEq, Eq, Default, PartialOrd, Ord, Clone, Copy, Hash, Deserialize)]
+#[serde(from = "String")]
 pub enum SolanaCommitment {
     /// A transaction has been validated and recorded in the blockchain by a single node
     Processed,
@@ -64,3 +66,9 @@ impl From<&str> for SolanaCommitment {
         }
     }
 }
+
+impl From<String> for SolanaCommitment {
+    fn from(value: String) -> Self {
+        value.as_str().into()
+    }
+}
@@ -0,0 +1,286 @@
+use solana_instruction::{AccountMeta, Instruction};
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+
+use crate::{AtollWalletError, AtollWalletResult, HttpTransport, SolanaInstructions, SolanaRpc};
+
+/// How urgently a transaction should land, mapped to a percentile of recent priority fees
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum SolanaPriorityLevel {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl SolanaPriorityLevel {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    /// The percentile of recent priority fees paid at this level
+    pub fn percentile(&self) -> usize {
+        match self {
+            Self::Low => 25,
+            Self::Medium => 50,
+            Self::High => 75,
+        }
+    }
+}
+
+impl TryFrom<&str> for SolanaPriorityLevel {
+    type Error = AtollWalletError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => Err(AtollWalletError::Input(format!(
+                "`{value}` is not a priority level. Expected `low`, `medium` or `high`"
+            ))),
+        }
+    }
+}
+
+/// Suggested priority fees in micro-lamports per compute unit
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SolanaPriorityFees {
+    pub low: u64,
+    pub medium: u64,
+    pub high: u64,
+}
+
+impl SolanaPriorityFees {
+    /// Suggests fees from the recent prioritization fees of the accounts a transaction writes to
+    pub async fn fetch<T: HttpTransport>(
+        rpc: &SolanaRpc<T>,
+        writable_accounts: &[Pubkey],
+    ) -> AtollWalletResult<Self> {
+        Ok(Self::from_recent_fees(
+            rpc.get_recent_prioritization_fees(writable_accounts)
+                .await?,
+        ))
+    }
+
+    /// The nearest-rank percentiles of `fees` for each [SolanaPriorityLevel]
+    pub fn from_recent_fees(mut fees: Vec<u64>) -> Self {
+        fees.sort_unstable();
+
+        let percentile = |level: SolanaPriorityLevel| {
+            if fees.is_empty() {
+                return 0;
+            }
+
+            let rank = (level.percentile() * fees.len()).div_ceil(100).max(1);
+
+            fees[rank - 1]
+        };
+
+        Self {
+            low: percentile(SolanaPriorityLevel::Low),
+            medium: percentile(SolanaPriorityLevel::Medium),
+            high: percentile(SolanaPriorityLevel::High),
+        }
+    }
+
+    pub fn fee(&self, level: SolanaPriorityLevel) -> u64 {
+        match level {
+            SolanaPriorityLevel::Low => self.low,
+            SolanaPriorityLevel::Medium => self.medium,
+            SolanaPriorityLevel::High => self.high,
+        }
+    }
+}
+
+/// The `ComputeBudget` instructions of a transaction that decide its priority
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SolanaComputeBudget {
+    pub unit_limit: Option<u32>,
+    /// The priority fee in micro-lamports per compute unit
+    pub unit_price: Option<u64>,
+}
+
+impl SolanaComputeBudget {
+    /// The most compute units a transaction can request
+    pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+    /// Headroom over the simulated compute units since state can change before the
+    /// transaction lands
+    pub const COMPUTE_UNIT_MARGIN: f64 = 1.1;
+
+    /// Reads the compute unit limit and price set by `instructions`
+    pub fn from_instructions(instructions: &[Instruction]) -> Self {
+        let mut budget = Self::default();
+
+        instructions
+            .iter()
+            .filter(|instruction| {
+                instruction.program_id == SolanaInstructions::COMPUTE_BUDGET_PROGRAM_ID
+            })
+            .for_each(|instruction| match instruction.data.split_first() {
+                Some((&SolanaInstructions::SET_COMPUTE_UNIT_LIMIT, units)) => {
+                    budget.unit_limit = units
+                        .try_into()
+                        .ok()
+                        .map(u32::from_le_bytes)
+                        .or(budget.unit_limit)
+                }
+                Some((&SolanaInstructions::SET_COMPUTE_UNIT_PRICE, micro_lamports)) => {
+                    budget.unit_price = micro_lamports
+                        .try_into()
+                        .ok()
+                        .map(u64::from_le_bytes)
+                        .or(budget.unit_price)
+                }
+                _ => (),
+            });
+
+        budget
+    }
+
+    /// Replaces the compute unit limit and price instructions of `instructions`, keeping
+    /// any other `ComputeBudget` instruction, and places them first
+    pub fn apply(&self, instructions: &[Instruction]) -> Vec<Instruction> {
+        let mut applied = Vec::<Instruction>::with_capacity(instructions.len() + 2);
+
+        if let Some(units) = self.unit_limit {
+            applied.push(SolanaInstructions::set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.unit_price {
+            applied.push(SolanaInstructions::set_compute_unit_price(micro_lamports));
+        }
+
+        applied.extend(
+            instructions
+                .iter()
+                .filter(|instruction| {
+                    instruction.program_id != SolanaInstructions::COMPUTE_BUDGET_PROGRAM_ID
+                        || !matches!(
+                            instruction.data.first(),
+                            Some(
+                                &SolanaInstructions::SET_COMPUTE_UNIT_LIMIT
+                                    | &SolanaInstructions::SET_COMPUTE_UNIT_PRICE
+                            )
+                        )
+                })
+                .cloned(),
+        );
+
+        applied
+    }
+
+    /// The instructions of a legacy `message` with their account metas
+    pub fn decompile(message: &Message) -> AtollWalletResult<Vec<Instruction>> {
+        let account_key = |index: u8| {
+            message
+                .account_keys
+                .get(index as usize)
+                .copied()
+                .ok_or(AtollWalletError::Input(format!(
+                    "The transaction references the account `{index}` but has `{}` accounts",
+                    message.account_keys.len()
+                )))
+        };
+
+        message
+            .instructions
+            .iter()
+            .map(|instruction| {
+                Ok(Instruction {
+                    program_id: account_key(instruction.program_id_index)?,
+                    accounts: instruction
+                        .accounts
+                        .iter()
+                        .map(|index| {
+                            Ok(AccountMeta {
+                                pubkey: account_key(*index)?,
+                                is_signer: message.is_signer(*index as usize),
+                                is_writable: message.is_maybe_writable(*index as usize, None),
+                            })
+                        })
+                        .collect::<AtollWalletResult<Vec<AccountMeta>>>()?,
+                    data: instruction.data.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// The accounts `message` write locks, which are the accounts whose fee markets
+    /// decide the priority fee
+    pub fn writable_accounts(message: &Message) -> Vec<Pubkey> {
+        message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| message.is_maybe_writable(*index, None))
+            .map(|(_, key)| *key)
+            .collect()
+    }
+
+    /// Sets the priority fee of `transaction` to the fee suggested for `level`, unless the
+    /// transaction already pays more, and its compute unit limit to the simulated units.
+    ///
+    /// Changing the instructions invalidates signatures, so a transaction that already carries
+    /// a signature, or whose simulation fails, is returned unchanged.
+    pub async fn inject<T: HttpTransport>(
+        rpc: &SolanaRpc<T>,
+        transaction: Transaction,
+        level: SolanaPriorityLevel,
+    ) -> AtollWalletResult<Transaction> {
+        if transaction
+            .signatures
+            .iter()
+            .any(|signature| *signature != Signature::default())
+        {
+            return Ok(transaction);
+        }
+
+        let message = &transaction.message;
+        let payer = *message.account_keys.first().ok_or(AtollWalletError::Input(
+            "The transaction has no fee payer".to_string(),
+        ))?;
+        let instructions = Self::decompile(message)?;
+        let existing = Self::from_instructions(&instructions);
+
+        let suggested = SolanaPriorityFees::fetch(rpc, &Self::writable_accounts(message))
+            .await?
+            .fee(level);
+        let unit_price = existing.unit_price.unwrap_or_default().max(suggested);
+
+        let rebuild = |budget: Self| {
+            Transaction::new_unsigned(Message::new_with_blockhash(
+                &budget.apply(&instructions),
+                Some(&payer),
+                &message.recent_blockhash,
+            ))
+        };
+
+        let simulation = rpc
+            .simulate_transaction(&rebuild(Self {
+                unit_limit: Some(Self::MAX_COMPUTE_UNIT_LIMIT),
+                unit_price: Some(unit_price),
+            }))
+            .await?;
+
+        let Some(units_consumed) = simulation
+            .units_consumed
+            .filter(|_| simulation.error.is_none())
+        else {
+            return Ok(transaction);
+        };
+
+        let unit_limit = ((units_consumed as f64 * Self::COMPUTE_UNIT_MARGIN).ceil() as u32)
+            .min(Self::MAX_COMPUTE_UNIT_LIMIT);
+
+        Ok(rebuild(Self {
+            unit_limit: Some(unit_limit),
+            unit_price: Some(unit_price),
+        }))
+    }
+}
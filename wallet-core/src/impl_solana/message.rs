@@ -0,0 +1,359 @@
+use bincode::Options;
+use solana_message::VersionedMessage;
+use solana_pubkey::Pubkey;
+use solana_transaction::versioned::VersionedTransaction;
+
+use crate::{AtollWalletError, AtollWalletResult, SolanaWireTransaction};
+
+/// The bytes of a `solana:signMessage` request once they have been inspected,
+/// the only form of a message the wallet signs
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolanaSignableMessage {
+    bytes: Vec<u8>,
+    mode: SolanaMessageMode,
+}
+
+/// How the bytes of a message are signed
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SolanaMessageMode {
+    /// Arbitrary bytes signed as they are
+    Raw,
+    /// A message in the off-chain message format, which can never be a transaction
+    Offchain(SolanaOffchainMessage),
+}
+
+impl SolanaSignableMessage {
+    /// Checks the `bytes` a dapp asks `signer` to sign. Bytes starting with
+    /// [SolanaOffchainMessage::SIGNING_DOMAIN] must be a valid off-chain message
+    /// listing `signer`, and bytes that parse as a transaction or its message are refused
+    /// since their signature would be valid on chain.
+    pub fn inspect(bytes: Vec<u8>, signer: &Pubkey) -> AtollWalletResult<Self> {
+        if bytes.starts_with(SolanaOffchainMessage::SIGNING_DOMAIN) {
+            let message = SolanaOffchainMessage::decode(&bytes)?;
+
+            if !message.signers().contains(signer) {
+                return Err(AtollWalletError::Input(format!(
+                    "The account `{signer}` is not a signer of the off-chain message"
+                )));
+            }
+
+            return Ok(Self {
+                bytes,
+                mode: SolanaMessageMode::Offchain(message),
+            });
+        }
+
+        if Self::is_transaction(&bytes) {
+            return Err(AtollWalletError::MessageIsTransaction);
+        }
+
+        Ok(Self {
+            bytes,
+            mode: SolanaMessageMode::Raw,
+        })
+    }
+
+    /// Whether `bytes` are exactly a sanitized transaction or transaction message,
+    /// legacy or versioned
+    pub fn is_transaction(bytes: &[u8]) -> bool {
+        if bytes.len() as u64 > SolanaWireTransaction::MAX_SIZE {
+            return false;
+        }
+
+        let options = bincode::options()
+            .with_limit(SolanaWireTransaction::MAX_SIZE)
+            .with_fixint_encoding()
+            .reject_trailing_bytes();
+
+        options
+            .deserialize::<VersionedMessage>(bytes)
+            .is_ok_and(|message| message.sanitize().is_ok())
+            || options
+                .deserialize::<VersionedTransaction>(bytes)
+                .is_ok_and(|transaction| transaction.sanitize().is_ok())
+    }
+
+    /// The bytes that are signed
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn mode(&self) -> &SolanaMessageMode {
+        &self.mode
+    }
+
+    /// What the user is shown before signing, the body for an off-chain message
+    pub fn preview(&self) -> SolanaMessagePreview {
+        match &self.mode {
+            SolanaMessageMode::Raw => SolanaMessagePreview::new(&self.bytes),
+            SolanaMessageMode::Offchain(message) => SolanaMessagePreview::new(message.message()),
+        }
+    }
+}
+
+/// A message shown as text when it is readable, as hex otherwise
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SolanaMessagePreview {
+    Text(String),
+    Hex(String),
+}
+
+impl SolanaMessagePreview {
+    /// Bytes are shown as text if they are UTF-8 without control characters, other than
+    /// line breaks and tabs, or the direction overrides that can reorder what is shown
+    pub fn new(bytes: &[u8]) -> Self {
+        let is_hidden = |character: char| {
+            (character.is_control() && !matches!(character, '\n' | '\r' | '\t'))
+                || matches!(character, '\u{202A}'..='\u{202E}' | '\u{2066}'..='\u{2069}')
+        };
+
+        match core::str::from_utf8(bytes) {
+            Ok(text) if !text.chars().any(is_hidden) => Self::Text(text.to_string()),
+            _ => Self::Hex(bytes.iter().map(|byte| format!("{byte:02x}")).collect()),
+        }
+    }
+
+    /// `text` or `hex`
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::Text(_) => "text",
+            Self::Hex(_) => "hex",
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Text(value) | Self::Hex(value) => value,
+        }
+    }
+}
+
+/// A message in the Solana off-chain message format, version `0`:
+/// the signing domain, the version, the application domain, the format,
+/// the signers and the message prefixed with its length as a little-endian `u16`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolanaOffchainMessage {
+    application_domain: [u8; 32],
+    format: SolanaOffchainMessageFormat,
+    signers: Vec<Pubkey>,
+    message: Vec<u8>,
+}
+
+impl SolanaOffchainMessage {
+    /// Starts every off-chain message. A transaction message cannot start with `0xff`.
+    pub const SIGNING_DOMAIN: &[u8] = b"\xffsolana offchain";
+    /// The only version of the header
+    pub const VERSION: u8 = 0;
+    /// The most bytes, header included, of a message in a limited format so that
+    /// hardware wallets can sign it
+    pub const MAX_LIMITED_SIZE: usize = 1232;
+
+    /// A message for `application_domain` signed by `signers`, in the most restrictive
+    /// format that can hold `message`
+    pub fn new(
+        application_domain: [u8; 32],
+        signers: Vec<Pubkey>,
+        message: Vec<u8>,
+    ) -> AtollWalletResult<Self> {
+        let size = Self::header_size(signers.len()) + message.len();
+        let format = [
+            SolanaOffchainMessageFormat::RestrictedAscii,
+            SolanaOffchainMessageFormat::LimitedUtf8,
+            SolanaOffchainMessageFormat::ExtendedUtf8,
+        ]
+        .into_iter()
+        .find(|format| format.accepts(size, &message))
+        .ok_or(AtollWalletError::Input(
+            "The off-chain message is not UTF-8 or is too long".to_string(),
+        ))?;
+
+        let new_self = Self {
+            application_domain,
+            format,
+            signers,
+            message,
+        };
+        new_self.check()?;
+
+        Ok(new_self)
+    }
+
+    /// Decodes a message, refusing unknown versions, a format that does not match
+    /// the message and any byte past the message
+    pub fn decode(bytes: &[u8]) -> AtollWalletResult<Self> {
+        let invalid = |reason: &str| {
+            AtollWalletError::Input(format!("The off-chain message is invalid. {reason}"))
+        };
+
+        let mut rest = bytes
+            .strip_prefix(Self::SIGNING_DOMAIN)
+            .ok_or(invalid("It does not start with the signing domain"))?;
+        let mut take = |length: usize| {
+            let (taken, remaining) = rest
+                .split_at_checked(length)
+                .ok_or(invalid("It ends before the message"))?;
+            rest = remaining;
+
+            Ok::<_, AtollWalletError>(taken)
+        };
+
+        let version = take(1)?[0];
+        if version != Self::VERSION {
+            return Err(invalid(&format!(
+                "The version `{version}` is not supported"
+            )));
+        }
+
+        let application_domain: [u8; 32] = take(32)?.try_into().unwrap_or_default();
+        let format = SolanaOffchainMessageFormat::try_from(take(1)?[0])?;
+
+        let signer_count = take(1)?[0] as usize;
+        let signers = (0..signer_count)
+            .map(|_| {
+                take(32).map(|signer| Pubkey::new_from_array(signer.try_into().unwrap_or_default()))
+            })
+            .collect::<AtollWalletResult<Vec<Pubkey>>>()?;
+
+        let length = take(2)?;
+        let length = u16::from_le_bytes([length[0], length[1]]) as usize;
+        let message = take(length)?.to_vec();
+
+        if !rest.is_empty() {
+            return Err(invalid("There are bytes after the message"));
+        }
+
+        if !format.accepts(bytes.len(), &message) {
+            return Err(invalid(&format!(
+                "The message does not fit the `{}` format",
+                format.as_str()
+            )));
+        }
+
+        let decoded = Self {
+            application_domain,
+            format,
+            signers,
+            message,
+        };
+        decoded.check()?;
+
+        Ok(decoded)
+    }
+
+    /// The bytes that are signed
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes =
+            Vec::with_capacity(Self::header_size(self.signers.len()) + self.message.len());
+
+        bytes.extend_from_slice(Self::SIGNING_DOMAIN);
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&self.application_domain);
+        bytes.push(self.format as u8);
+        bytes.push(self.signers.len() as u8);
+        self.signers
+            .iter()
+            .for_each(|signer| bytes.extend_from_slice(signer.as_ref()));
+        bytes.extend_from_slice(&(self.message.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.message);
+
+        bytes
+    }
+
+    /// Chosen by the application to keep its messages apart from those of others
+    pub fn application_domain(&self) -> &[u8; 32] {
+        &self.application_domain
+    }
+
+    pub fn format(&self) -> SolanaOffchainMessageFormat {
+        self.format
+    }
+
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.signers
+    }
+
+    /// The message without the header
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    fn check(&self) -> AtollWalletResult<()> {
+        let invalid = |reason: &str| {
+            AtollWalletError::Input(format!("The off-chain message is invalid. {reason}"))
+        };
+
+        if self.signers.is_empty() || self.signers.len() > u8::MAX as usize {
+            return Err(invalid("It must have between 1 and 255 signers"));
+        }
+
+        if self
+            .signers
+            .iter()
+            .enumerate()
+            .any(|(index, signer)| self.signers[..index].contains(signer))
+        {
+            return Err(invalid("A signer is listed twice"));
+        }
+
+        if self.message.is_empty() || self.message.len() > u16::MAX as usize {
+            return Err(invalid("The message must be between 1 and 65535 bytes"));
+        }
+
+        Ok(())
+    }
+
+    fn header_size(signer_count: usize) -> usize {
+        Self::SIGNING_DOMAIN.len() + 1 + 32 + 1 + 1 + signer_count * 32 + 2
+    }
+}
+
+/// What an off-chain message may hold
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum SolanaOffchainMessageFormat {
+    /// Printable ASCII, within [SolanaOffchainMessage::MAX_LIMITED_SIZE]
+    RestrictedAscii = 0,
+    /// UTF-8, within [SolanaOffchainMessage::MAX_LIMITED_SIZE]
+    LimitedUtf8 = 1,
+    /// UTF-8 up to `65535` bytes, which hardware wallets may not be able to sign
+    ExtendedUtf8 = 2,
+}
+
+impl SolanaOffchainMessageFormat {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::RestrictedAscii => "restrictedAscii",
+            Self::LimitedUtf8 => "limitedUtf8",
+            Self::ExtendedUtf8 => "extendedUtf8",
+        }
+    }
+
+    /// Whether an encoded message of `size` bytes holding `message` is in this format
+    fn accepts(&self, size: usize, message: &[u8]) -> bool {
+        match self {
+            Self::RestrictedAscii => {
+                size <= SolanaOffchainMessage::MAX_LIMITED_SIZE
+                    && message.iter().all(|byte| (0x20..=0x7e).contains(byte))
+            }
+            Self::LimitedUtf8 => {
+                size <= SolanaOffchainMessage::MAX_LIMITED_SIZE
+                    && core::str::from_utf8(message).is_ok()
+            }
+            Self::ExtendedUtf8 => core::str::from_utf8(message).is_ok(),
+        }
+    }
+}
+
+impl TryFrom<u8> for SolanaOffchainMessageFormat {
+    type Error = AtollWalletError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::RestrictedAscii),
+            1 => Ok(Self::LimitedUtf8),
+            2 => Ok(Self::ExtendedUtf8),
+            _ => Err(AtollWalletError::Input(format!(
+                "The off-chain message format `{value}` is not supported"
+            ))),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+mod clusters;
+pub use clusters::*;
+
+mod features;
+
+mod wallet;
+pub use wallet::*;
+
+mod wallet_account;
+pub use wallet_account::*;
+
+mod commitment;
+pub use commitment::*;
+mod constants;
+pub use constants::*;
+
+mod rpc;
+pub use rpc::*;
+
+mod portfolio;
+pub use portfolio::*;
+
+mod transaction;
+pub use transaction::*;
+
+mod transfer;
+pub use transfer::*;
+
+mod fees;
+pub use fees::*;
+
+mod activity;
+pub use activity::*;
+
+mod sign_in;
+pub use sign_in::*;
+
+mod message;
+pub use message::*;
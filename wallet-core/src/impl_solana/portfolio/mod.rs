@@ -0,0 +1,161 @@
+mod token;
+pub use token::*;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use solana_pubkey::Pubkey;
+
+use crate::{AtollWalletResult, HttpTransport, SolanaCluster, SolanaRpc};
+
+/// The SOL and token balances of an account on a cluster
+#[derive(Debug, PartialEq, Clone)]
+pub struct SolanaPortfolio {
+    pub owner: Pubkey,
+    pub cluster: SolanaCluster,
+    pub lamports: u64,
+    pub holdings: Vec<SolanaTokenHolding>,
+    /// The unix timestamp in milliseconds at which the portfolio was fetched
+    pub fetched_at: f64,
+}
+
+impl SolanaPortfolio {
+    /// How long in milliseconds a fetched portfolio is served from the cache
+    pub const TTL_MS: f64 = 30_000.0;
+
+    /// Fetches the SOL balance and the token accounts of `owner` for both token
+    /// programs, together with their mints and metadata
+    pub async fn fetch<T: HttpTransport>(
+        rpc: &SolanaRpc<T>,
+        owner: Pubkey,
+        cluster: SolanaCluster,
+        now_ms: f64,
+    ) -> AtollWalletResult<Self> {
+        let lamports = rpc.get_balance(&owner).await?;
+
+        let mut accounts = Vec::<(Pubkey, SplTokenProgram, SplTokenAccount)>::new();
+
+        for program in [SplTokenProgram::Token, SplTokenProgram::Token2022] {
+            for (address, account) in rpc
+                .get_token_accounts_by_owner(&owner, &program.program_id())
+                .await?
+            {
+                accounts.push((address, program, SplTokenAccount::unpack(&account.data)?));
+            }
+        }
+
+        let mut mints = BTreeMap::<Pubkey, SplMint>::new();
+        let mint_addresses = accounts
+            .iter()
+            .map(|(_, _, account)| account.mint)
+            .collect::<BTreeSet<Pubkey>>()
+            .into_iter()
+            .collect::<Vec<Pubkey>>();
+
+        for (address, account) in mint_addresses
+            .iter()
+            .zip(rpc.get_multiple_accounts(&mint_addresses).await?)
+        {
+            if let Some(account) = account {
+                mints.insert(*address, SplMint::unpack(&account.data)?);
+            }
+        }
+
+        // Mints without the metadata extension fall back to Metaplex metadata
+        let without_metadata = mints
+            .iter()
+            .filter(|(_, mint)| mint.metadata.is_none())
+            .map(|(address, _)| *address)
+            .collect::<Vec<Pubkey>>();
+        let metaplex_addresses = without_metadata
+            .iter()
+            .map(SplTokenMetadata::metaplex_address)
+            .collect::<Vec<Pubkey>>();
+
+        for (address, account) in without_metadata
+            .iter()
+            .zip(rpc.get_multiple_accounts(&metaplex_addresses).await?)
+        {
+            let metadata = account
+                .filter(|account| account.owner == SplTokenMetadata::METAPLEX_PROGRAM_ID)
+                .and_then(|account| SplTokenMetadata::unpack_metaplex(&account.data).ok());
+
+            if let Some(mint) = mints.get_mut(address) {
+                mint.metadata = metadata;
+            }
+        }
+
+        let epoch = if mints.values().any(|mint| mint.transfer_fee.is_some()) {
+            Some(rpc.get_epoch().await?)
+        } else {
+            None
+        };
+
+        let unix_timestamp = (now_ms / 1000.0) as i64;
+
+        let mut holdings = accounts
+            .into_iter()
+            .map(|(address, program, account)| {
+                let mint = mints.get(&account.mint).cloned().unwrap_or_default();
+
+                SolanaTokenHolding {
+                    address,
+                    mint: account.mint,
+                    program,
+                    amount: account.amount,
+                    decimals: mint.decimals,
+                    ui_amount: mint.ui_amount(account.amount, unix_timestamp),
+                    frozen: account.frozen,
+                    memo_required: account.memo_required,
+                    non_transferable: mint.non_transferable || account.non_transferable,
+                    transfer_fee: mint
+                        .transfer_fee
+                        .zip(epoch)
+                        .map(|(config, epoch)| *config.fee_for_epoch(epoch)),
+                    interest_rate: mint.interest_bearing.map(|config| config.current_rate),
+                    metadata: mint.metadata,
+                }
+            })
+            .collect::<Vec<SolanaTokenHolding>>();
+
+        // Largest balances first, with the token account as a stable tie breaker
+        holdings.sort_by(|a, b| {
+            b.ui_amount
+                .total_cmp(&a.ui_amount)
+                .then(a.address.cmp(&b.address))
+        });
+
+        Ok(Self {
+            owner,
+            cluster,
+            lamports,
+            holdings,
+            fetched_at: now_ms,
+        })
+    }
+
+    /// Whether the portfolio is older than [Self::TTL_MS] at `now_ms`
+    pub fn is_stale(&self, now_ms: f64) -> bool {
+        now_ms - self.fetched_at > Self::TTL_MS
+    }
+}
+
+/// A token account of the portfolio together with what its mint defines
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SolanaTokenHolding {
+    /// The address of the token account
+    pub address: Pubkey,
+    pub mint: Pubkey,
+    pub program: SplTokenProgram,
+    /// The raw amount in the smallest unit of the token
+    pub amount: u64,
+    pub decimals: u8,
+    pub ui_amount: f64,
+    pub frozen: bool,
+    pub memo_required: bool,
+    pub non_transferable: bool,
+    /// The transfer fee in effect for the current epoch
+    pub transfer_fee: Option<SplTransferFee>,
+    /// The interest rate in basis points per year of interest-bearing mints
+    pub interest_rate: Option<i16>,
+    pub metadata: Option<SplTokenMetadata>,
+}
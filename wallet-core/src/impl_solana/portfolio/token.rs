@@ -0,0 +1,428 @@
+use solana_pubkey::Pubkey;
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+/// The SPL token program owning a mint or token account
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum SplTokenProgram {
+    #[default]
+    Token,
+    Token2022,
+}
+
+impl SplTokenProgram {
+    pub const TOKEN_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+    pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            Self::Token => Self::TOKEN_PROGRAM_ID,
+            Self::Token2022 => Self::TOKEN_2022_PROGRAM_ID,
+        }
+    }
+
+    pub fn from_program_id(program_id: &Pubkey) -> Option<Self> {
+        if *program_id == Self::TOKEN_PROGRAM_ID {
+            Some(Self::Token)
+        } else if *program_id == Self::TOKEN_2022_PROGRAM_ID {
+            Some(Self::Token2022)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Token => "token",
+            Self::Token2022 => "token-2022",
+        }
+    }
+}
+
+/// A token account of the SPL Token or Token-2022 program
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SplTokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub frozen: bool,
+    /// The `MemoTransfer` extension requires incoming transfers to carry a memo
+    pub memo_required: bool,
+    /// The `NonTransferableAccount` extension, set on accounts of non-transferable mints
+    pub non_transferable: bool,
+}
+
+impl SplTokenAccount {
+    /// The size of a token account without extensions
+    pub const LEN: usize = 165;
+
+    const ACCOUNT_TYPE: u8 = 2;
+    const STATE_FROZEN: u8 = 2;
+
+    pub fn unpack(data: &[u8]) -> AtollWalletResult<Self> {
+        if data.len() < Self::LEN {
+            return Err(AtollWalletError::InvalidTokenAccountData(format!(
+                "A token account is at least `{}` bytes but the data is `{}` bytes",
+                Self::LEN,
+                data.len()
+            )));
+        }
+
+        let mut account = Self {
+            mint: read_pubkey(data, 0)?,
+            owner: read_pubkey(data, 32)?,
+            amount: read_u64(data, 64)?,
+            frozen: data[108] == Self::STATE_FROZEN,
+            ..Default::default()
+        };
+
+        for (extension_type, value) in SplExtensions::parse(data, Self::ACCOUNT_TYPE)? {
+            match extension_type {
+                SplExtensions::MEMO_TRANSFER => {
+                    account.memo_required = value.first().is_some_and(|enabled| *enabled != 0)
+                }
+                SplExtensions::NON_TRANSFERABLE_ACCOUNT => account.non_transferable = true,
+                _ => (),
+            }
+        }
+
+        Ok(account)
+    }
+}
+
+/// A mint of the SPL Token or Token-2022 program with the extensions that
+/// change what a holder receives or can do with the token
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct SplMint {
+    pub supply: u64,
+    pub decimals: u8,
+    pub transfer_fee: Option<SplTransferFeeConfig>,
+    pub interest_bearing: Option<SplInterestBearingConfig>,
+    pub non_transferable: bool,
+    pub transfer_hook: Option<Pubkey>,
+    /// The account holding the metadata of the mint, which is the mint
+    /// itself when the metadata is stored in the `TokenMetadata` extension
+    pub metadata_pointer: Option<Pubkey>,
+    pub metadata: Option<SplTokenMetadata>,
+}
+
+impl SplMint {
+    /// The size of a mint without extensions
+    pub const LEN: usize = 82;
+
+    const ACCOUNT_TYPE: u8 = 1;
+
+    pub fn unpack(data: &[u8]) -> AtollWalletResult<Self> {
+        if data.len() < Self::LEN {
+            return Err(AtollWalletError::InvalidTokenAccountData(format!(
+                "A mint is at least `{}` bytes but the data is `{}` bytes",
+                Self::LEN,
+                data.len()
+            )));
+        }
+
+        let mut mint = Self {
+            supply: read_u64(data, 36)?,
+            decimals: data[44],
+            ..Default::default()
+        };
+
+        for (extension_type, value) in SplExtensions::parse(data, Self::ACCOUNT_TYPE)? {
+            match extension_type {
+                SplExtensions::TRANSFER_FEE_CONFIG => {
+                    mint.transfer_fee = Some(SplTransferFeeConfig::unpack(value)?)
+                }
+                SplExtensions::NON_TRANSFERABLE => mint.non_transferable = true,
+                SplExtensions::INTEREST_BEARING_CONFIG => {
+                    mint.interest_bearing = Some(SplInterestBearingConfig::unpack(value)?)
+                }
+                SplExtensions::TRANSFER_HOOK => {
+                    mint.transfer_hook = read_optional_pubkey(value, 32)?
+                }
+                SplExtensions::METADATA_POINTER => {
+                    mint.metadata_pointer = read_optional_pubkey(value, 32)?
+                }
+                SplExtensions::TOKEN_METADATA => {
+                    mint.metadata = Some(SplTokenMetadata::unpack_token_metadata(value)?)
+                }
+                _ => (),
+            }
+        }
+
+        Ok(mint)
+    }
+
+    /// The amount in whole tokens, including the interest accrued by
+    /// interest-bearing mints at `unix_timestamp`
+    pub fn ui_amount(&self, amount: u64, unix_timestamp: i64) -> f64 {
+        let scale = self
+            .interest_bearing
+            .as_ref()
+            .map(|config| config.scale(unix_timestamp))
+            .unwrap_or(1.0);
+
+        amount as f64 * scale / 10f64.powi(self.decimals as i32)
+    }
+}
+
+/// The fee charged on transfers of a Token-2022 mint
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SplTransferFee {
+    /// The first epoch in which this fee applies
+    pub epoch: u64,
+    pub maximum_fee: u64,
+    pub basis_points: u16,
+}
+
+impl SplTransferFee {
+    const MAX_BASIS_POINTS: u128 = 10_000;
+
+    /// The fee withheld from a transfer of `amount`, rounded up as the program does
+    pub fn fee(&self, amount: u64) -> u64 {
+        if self.basis_points == 0 || amount == 0 {
+            return 0;
+        }
+
+        let fee = (amount as u128 * self.basis_points as u128).div_ceil(Self::MAX_BASIS_POINTS);
+
+        (fee as u64).min(self.maximum_fee)
+    }
+
+    fn unpack(data: &[u8], offset: usize) -> AtollWalletResult<Self> {
+        Ok(Self {
+            epoch: read_u64(data, offset)?,
+            maximum_fee: read_u64(data, offset + 8)?,
+            basis_points: read_u16(data, offset + 16)?,
+        })
+    }
+}
+
+/// The `TransferFeeConfig` extension of a Token-2022 mint
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SplTransferFeeConfig {
+    pub withheld_amount: u64,
+    pub older_transfer_fee: SplTransferFee,
+    pub newer_transfer_fee: SplTransferFee,
+}
+
+impl SplTransferFeeConfig {
+    fn unpack(data: &[u8]) -> AtollWalletResult<Self> {
+        Ok(Self {
+            withheld_amount: read_u64(data, 64)?,
+            older_transfer_fee: SplTransferFee::unpack(data, 72)?,
+            newer_transfer_fee: SplTransferFee::unpack(data, 90)?,
+        })
+    }
+
+    /// The transfer fee in effect during `epoch`
+    pub fn fee_for_epoch(&self, epoch: u64) -> &SplTransferFee {
+        if epoch >= self.newer_transfer_fee.epoch {
+            &self.newer_transfer_fee
+        } else {
+            &self.older_transfer_fee
+        }
+    }
+}
+
+/// The `InterestBearingConfig` extension of a Token-2022 mint. Interest is not
+/// minted, it only changes how the raw amount is displayed.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct SplInterestBearingConfig {
+    pub initialization_timestamp: i64,
+    pub pre_update_average_rate: i16,
+    pub last_update_timestamp: i64,
+    /// The current rate in basis points per year
+    pub current_rate: i16,
+}
+
+impl SplInterestBearingConfig {
+    /// The seconds in a year as defined by the Token-2022 program
+    pub const SECONDS_PER_YEAR: f64 = 60.0 * 60.0 * 24.0 * 365.24;
+
+    fn unpack(data: &[u8]) -> AtollWalletResult<Self> {
+        Ok(Self {
+            initialization_timestamp: read_u64(data, 32)? as i64,
+            pre_update_average_rate: read_u16(data, 40)? as i16,
+            last_update_timestamp: read_u64(data, 42)? as i64,
+            current_rate: read_u16(data, 50)? as i16,
+        })
+    }
+
+    /// The continuously compounded growth of an amount from the initialization of the mint
+    /// until `unix_timestamp`
+    pub fn scale(&self, unix_timestamp: i64) -> f64 {
+        let growth = |rate: i16, elapsed: i64| {
+            (rate as f64 * elapsed as f64 / Self::SECONDS_PER_YEAR / 10_000.0).exp()
+        };
+
+        growth(
+            self.pre_update_average_rate,
+            self.last_update_timestamp - self.initialization_timestamp,
+        ) * growth(
+            self.current_rate,
+            unix_timestamp - self.last_update_timestamp,
+        )
+    }
+}
+
+/// The name, symbol and URI of a token, either from the `TokenMetadata` extension
+/// of a Token-2022 mint or from its Metaplex metadata account
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SplTokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl SplTokenMetadata {
+    /// The program of Metaplex metadata accounts
+    pub const METAPLEX_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+    /// The Metaplex metadata account of `mint`
+    pub fn metaplex_address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                b"metadata",
+                Self::METAPLEX_PROGRAM_ID.as_ref(),
+                mint.as_ref(),
+            ],
+            &Self::METAPLEX_PROGRAM_ID,
+        )
+        .0
+    }
+
+    /// Parses the `TokenMetadata` extension which follows the update authority and mint
+    pub fn unpack_token_metadata(data: &[u8]) -> AtollWalletResult<Self> {
+        Self::unpack_strings(data, 64)
+    }
+
+    /// Parses a Metaplex metadata account, where the strings follow the account key,
+    /// update authority and mint and are padded with null bytes
+    pub fn unpack_metaplex(data: &[u8]) -> AtollWalletResult<Self> {
+        Self::unpack_strings(data, 1 + 32 + 32)
+    }
+
+    fn unpack_strings(data: &[u8], mut offset: usize) -> AtollWalletResult<Self> {
+        let mut next = || {
+            let value = read_borsh_string(data, offset)?;
+            offset += 4 + value.len();
+
+            AtollWalletResult::Ok(value.trim_end_matches('\0').trim().to_string())
+        };
+
+        Ok(Self {
+            name: next()?,
+            symbol: next()?,
+            uri: next()?,
+        })
+    }
+}
+
+/// Reads the type-length-value extensions of Token-2022 accounts
+struct SplExtensions;
+
+impl SplExtensions {
+    const TRANSFER_FEE_CONFIG: u16 = 1;
+    const MEMO_TRANSFER: u16 = 8;
+    const NON_TRANSFERABLE: u16 = 9;
+    const INTEREST_BEARING_CONFIG: u16 = 10;
+    const NON_TRANSFERABLE_ACCOUNT: u16 = 13;
+    const TRANSFER_HOOK: u16 = 14;
+    const METADATA_POINTER: u16 = 18;
+    const TOKEN_METADATA: u16 = 19;
+
+    /// Mints are padded to the size of a token account so that the
+    /// account type is at the same offset for both
+    const ACCOUNT_TYPE_OFFSET: usize = SplTokenAccount::LEN;
+
+    /// The extensions of `data` as pairs of extension type and value. Data of the
+    /// size of a base account has no extensions.
+    fn parse(data: &[u8], account_type: u8) -> AtollWalletResult<Vec<(u16, &[u8])>> {
+        let mut extensions = Vec::<(u16, &[u8])>::new();
+
+        if data.len() <= Self::ACCOUNT_TYPE_OFFSET {
+            return Ok(extensions);
+        }
+
+        if data[Self::ACCOUNT_TYPE_OFFSET] != account_type {
+            return Err(AtollWalletError::InvalidTokenAccountData(format!(
+                "Expected the account type `{account_type}` but found `{}`",
+                data[Self::ACCOUNT_TYPE_OFFSET]
+            )));
+        }
+
+        let mut offset = Self::ACCOUNT_TYPE_OFFSET + 1;
+
+        // Trailing bytes too short for a header are unused space
+        while offset + 4 <= data.len() {
+            let extension_type = read_u16(data, offset)?;
+            let length = read_u16(data, offset + 2)? as usize;
+            offset += 4;
+
+            // Uninitialized space past the last extension
+            if extension_type == 0 {
+                break;
+            }
+
+            let value = data.get(offset..offset + length).ok_or(
+                AtollWalletError::InvalidTokenAccountData(format!(
+                    "The extension `{extension_type}` of `{length}` bytes overflows the account data"
+                )),
+            )?;
+
+            extensions.push((extension_type, value));
+            offset += length;
+        }
+
+        Ok(extensions)
+    }
+}
+
+fn read_bytes<const N: usize>(data: &[u8], offset: usize) -> AtollWalletResult<[u8; N]> {
+    data.get(offset..offset + N)
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(AtollWalletError::InvalidTokenAccountData(format!(
+            "Expected `{N}` bytes at offset `{offset}` but the data is `{}` bytes",
+            data.len()
+        )))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> AtollWalletResult<u16> {
+    Ok(u16::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> AtollWalletResult<u32> {
+    Ok(u32::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> AtollWalletResult<u64> {
+    Ok(u64::from_le_bytes(read_bytes(data, offset)?))
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> AtollWalletResult<Pubkey> {
+    Ok(Pubkey::new_from_array(read_bytes(data, offset)?))
+}
+
+/// An `OptionalNonZeroPubkey` of Token-2022 which is all zeros when unset
+fn read_optional_pubkey(data: &[u8], offset: usize) -> AtollWalletResult<Option<Pubkey>> {
+    let pubkey = read_pubkey(data, offset)?;
+
+    Ok((pubkey != Pubkey::default()).then_some(pubkey))
+}
+
+fn read_borsh_string(data: &[u8], offset: usize) -> AtollWalletResult<String> {
+    let length = read_u32(data, offset)? as usize;
+
+    let bytes = data.get(offset + 4..offset + 4 + length).ok_or(
+        AtollWalletError::InvalidTokenAccountData(format!(
+            "The string of `{length}` bytes at offset `{offset}` overflows the account data"
+        )),
+    )?;
+
+    String::from_utf8(bytes.to_vec()).or(Err(AtollWalletError::InvalidTokenAccountData(format!(
+        "The string at offset `{offset}` is not valid UTF-8"
+    ))))
+}
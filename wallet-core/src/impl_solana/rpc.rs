@@ -0,0 +1,620 @@
+use std::str::FromStr;
+
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, de::DeserializeOwned};
+use solana_message::compiled_instruction::CompiledInstruction;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+use wallet_standard_base::Cluster;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, HttpTransport, SendOptions, SolanaCluster,
+    SolanaCommitment,
+};
+
+/// A JSON-RPC client for a Solana cluster
+pub struct SolanaRpc<T: HttpTransport> {
+    transport: T,
+    endpoint: String,
+}
+
+impl<T: HttpTransport> SolanaRpc<T> {
+    /// The most accounts `getMultipleAccounts` returns in one request
+    pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+    /// The most accounts `getRecentPrioritizationFees` accepts
+    pub const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+    /// The most signatures `getSignatureStatuses` accepts
+    pub const MAX_SIGNATURE_STATUSES: usize = 256;
+
+    pub fn new(transport: T, cluster: SolanaCluster) -> Self {
+        Self::new_with_endpoint(transport, cluster.endpoint())
+    }
+
+    pub fn new_with_endpoint(transport: T, endpoint: &str) -> Self {
+        Self {
+            transport,
+            endpoint: endpoint.to_string(),
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_str()
+    }
+
+    /// Calls `method` with `params` and returns the `result` of the response
+    pub async fn call<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: jzon::JsonValue,
+    ) -> AtollWalletResult<R> {
+        let body = jzon::object! {
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        }
+        .to_string();
+
+        let response = self.transport.post_json(&self.endpoint, body).await?;
+
+        let response = serde_json::from_str::<RpcResponse<R>>(&response).map_err(|error| {
+            AtollWalletError::SolanaRpc(format!(
+                "Invalid response for `{method}`. {error}. Response: {response}"
+            ))
+        })?;
+
+        match response {
+            RpcResponse {
+                error: Some(error), ..
+            } => Err(AtollWalletError::SolanaRpc(format!(
+                "`{method}` failed with code `{}`. {}",
+                error.code, error.message
+            ))),
+            RpcResponse {
+                result: Some(result),
+                ..
+            } => Ok(result),
+            _ => Err(AtollWalletError::SolanaRpc(format!(
+                "`{method}` returned no result"
+            ))),
+        }
+    }
+
+    /// The lamports held by `address`
+    pub async fn get_balance(&self, address: &Pubkey) -> AtollWalletResult<u64> {
+        let balance = self
+            .call::<RpcContext<u64>>(
+                "getBalance",
+                jzon::array![
+                    address.to_string(),
+                    jzon::object! { "commitment": "confirmed" }
+                ],
+            )
+            .await?;
+
+        Ok(balance.value)
+    }
+
+    /// The latest blockhash at `finalized` commitment
+    pub async fn get_latest_blockhash(&self) -> AtollWalletResult<solana_hash::Hash> {
+        let blockhash = self
+            .call::<RpcContext<RpcBlockhash>>(
+                "getLatestBlockhash",
+                jzon::array![jzon::object! { "commitment": "finalized" }],
+            )
+            .await?;
+
+        solana_hash::Hash::from_str(&blockhash.value.blockhash).or(Err(
+            AtollWalletError::SolanaRpc(format!(
+                "`{}` is not a valid blockhash",
+                blockhash.value.blockhash
+            )),
+        ))
+    }
+
+    /// Sends a signed `transaction` and returns its signature
+    pub async fn send_transaction(
+        &self,
+        transaction: &Transaction,
+        options: &SendOptions,
+    ) -> AtollWalletResult<Signature> {
+        let transaction_bytes = bincode::serialize(transaction).or(Err(
+            AtollWalletError::Input("Unable to serialize the signed transaction".to_string()),
+        ))?;
+
+        let signature = self
+            .call::<String>(
+                "sendTransaction",
+                jzon::array![Base64::encode_string(&transaction_bytes), options.to_json()],
+            )
+            .await?;
+
+        parse_signature(&signature)
+    }
+
+    /// The prioritization fees in micro-lamports per compute unit paid in recent slots
+    /// by transactions locking any of `writable_accounts`
+    pub async fn get_recent_prioritization_fees(
+        &self,
+        writable_accounts: &[Pubkey],
+    ) -> AtollWalletResult<Vec<u64>> {
+        let accounts = writable_accounts
+            .iter()
+            .take(Self::MAX_PRIORITIZATION_FEE_ACCOUNTS)
+            .map(|address| jzon::JsonValue::from(address.to_string()))
+            .collect::<Vec<jzon::JsonValue>>();
+
+        let fees = self
+            .call::<Vec<RpcPrioritizationFee>>(
+                "getRecentPrioritizationFees",
+                jzon::array![accounts],
+            )
+            .await?;
+
+        Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+    }
+
+    /// Simulates `transaction` without verifying its signatures and with
+    /// the latest blockhash of the cluster
+    pub async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> AtollWalletResult<SolanaSimulation> {
+        let transaction_bytes = bincode::serialize(transaction).or(Err(
+            AtollWalletError::Input("Unable to serialize the transaction to simulate".to_string()),
+        ))?;
+
+        let simulation = self
+            .call::<RpcContext<RpcSimulation>>(
+                "simulateTransaction",
+                jzon::array![
+                    Base64::encode_string(&transaction_bytes),
+                    jzon::object! {
+                        "encoding": "base64",
+                        "sigVerify": false,
+                        "replaceRecentBlockhash": true,
+                        "commitment": "confirmed",
+                    },
+                ],
+            )
+            .await?;
+
+        Ok(SolanaSimulation {
+            error: transaction_error(simulation.value.err),
+            units_consumed: simulation.value.units_consumed,
+            logs: simulation.value.logs.unwrap_or_default(),
+        })
+    }
+
+    /// The signatures of the transactions involving `address`, newest first, starting
+    /// after `before` when set
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<&Signature>,
+        limit: usize,
+    ) -> AtollWalletResult<Vec<SolanaSignatureStatus>> {
+        let mut config = jzon::object! { "limit": limit, "commitment": "confirmed" };
+        if let Some(before) = before {
+            config["before"] = before.to_string().into();
+        }
+
+        self.call::<Vec<RpcSignatureInfo>>(
+            "getSignaturesForAddress",
+            jzon::array![address.to_string(), config],
+        )
+        .await?
+        .into_iter()
+        .map(|info| {
+            Ok(SolanaSignatureStatus {
+                signature: parse_signature(&info.signature)?,
+                slot: info.slot,
+                block_time: info.block_time,
+                commitment: info
+                    .confirmation_status
+                    .as_deref()
+                    .map(SolanaCommitment::from),
+                error: transaction_error(info.err),
+            })
+        })
+        .collect()
+    }
+
+    /// The status of each of `signatures` in the same order, `None` for signatures
+    /// the cluster has not seen
+    pub async fn get_signature_statuses(
+        &self,
+        signatures: &[Signature],
+    ) -> AtollWalletResult<Vec<Option<SolanaSignatureStatus>>> {
+        let mut statuses = Vec::<Option<SolanaSignatureStatus>>::with_capacity(signatures.len());
+
+        for chunk in signatures.chunks(Self::MAX_SIGNATURE_STATUSES) {
+            let encoded = chunk
+                .iter()
+                .map(|signature| jzon::JsonValue::from(signature.to_string()))
+                .collect::<Vec<jzon::JsonValue>>();
+
+            let response = self
+                .call::<RpcContext<Vec<Option<RpcSignatureStatus>>>>(
+                    "getSignatureStatuses",
+                    jzon::array![encoded, jzon::object! { "searchTransactionHistory": true }],
+                )
+                .await?;
+
+            statuses.extend(chunk.iter().zip(response.value).map(|(signature, status)| {
+                status.map(|status| SolanaSignatureStatus {
+                    signature: *signature,
+                    slot: status.slot,
+                    block_time: None,
+                    commitment: status
+                        .confirmation_status
+                        .as_deref()
+                        .map(SolanaCommitment::from),
+                    error: transaction_error(status.err),
+                })
+            }));
+        }
+
+        Ok(statuses)
+    }
+
+    /// The confirmed transaction with `signature`, `None` if it is not found
+    pub async fn get_transaction(
+        &self,
+        signature: &Signature,
+    ) -> AtollWalletResult<Option<SolanaConfirmedTransaction>> {
+        let transaction = self
+            .call::<Option<RpcConfirmedTransaction>>(
+                "getTransaction",
+                jzon::array![
+                    signature.to_string(),
+                    jzon::object! {
+                        "encoding": "json",
+                        "commitment": "confirmed",
+                        "maxSupportedTransactionVersion": 0,
+                    },
+                ],
+            )
+            .await?;
+
+        let Some(transaction) = transaction else {
+            return Ok(None);
+        };
+
+        let message = transaction.transaction.message;
+        let (fee, error, loaded_addresses) = match transaction.meta {
+            Some(meta) => (
+                Some(meta.fee),
+                transaction_error(meta.err),
+                meta.loaded_addresses.unwrap_or_default(),
+            ),
+            None => (None, None, RpcLoadedAddresses::default()),
+        };
+
+        // Accounts loaded from lookup tables follow the static keys, writable ones first
+        let account_keys = message
+            .account_keys
+            .iter()
+            .chain(loaded_addresses.writable.iter())
+            .chain(loaded_addresses.readonly.iter())
+            .map(|key| parse_pubkey(key))
+            .collect::<AtollWalletResult<Vec<Pubkey>>>()?;
+
+        let instructions = message
+            .instructions
+            .into_iter()
+            .map(|instruction| {
+                Ok(CompiledInstruction {
+                    program_id_index: instruction.program_id_index,
+                    accounts: instruction.accounts,
+                    data: bs58::decode(&instruction.data).into_vec().or(Err(
+                        AtollWalletError::SolanaRpc(format!(
+                            "The instruction data of `{signature}` is not valid base58"
+                        )),
+                    ))?,
+                })
+            })
+            .collect::<AtollWalletResult<Vec<CompiledInstruction>>>()?;
+
+        Ok(Some(SolanaConfirmedTransaction {
+            signature: *signature,
+            slot: transaction.slot,
+            block_time: transaction.block_time,
+            fee,
+            error,
+            account_keys,
+            instructions,
+        }))
+    }
+
+    /// The current epoch of the cluster
+    pub async fn get_epoch(&self) -> AtollWalletResult<u64> {
+        let epoch_info = self
+            .call::<RpcEpochInfo>(
+                "getEpochInfo",
+                jzon::array![jzon::object! { "commitment": "confirmed" }],
+            )
+            .await?;
+
+        Ok(epoch_info.epoch)
+    }
+
+    /// The token accounts of `owner` created by `program_id`
+    pub async fn get_token_accounts_by_owner(
+        &self,
+        owner: &Pubkey,
+        program_id: &Pubkey,
+    ) -> AtollWalletResult<Vec<(Pubkey, SolanaRpcAccount)>> {
+        let accounts = self
+            .call::<RpcContext<Vec<RpcKeyedAccount>>>(
+                "getTokenAccountsByOwner",
+                jzon::array![
+                    owner.to_string(),
+                    jzon::object! { "programId": program_id.to_string() },
+                    jzon::object! { "encoding": "base64", "commitment": "confirmed" },
+                ],
+            )
+            .await?;
+
+        accounts
+            .value
+            .into_iter()
+            .map(|keyed| {
+                let pubkey = parse_pubkey(&keyed.pubkey)?;
+
+                Ok((pubkey, keyed.account.try_into()?))
+            })
+            .collect()
+    }
+
+    /// The accounts at `addresses` in the same order, `None` for accounts that do not exist
+    pub async fn get_multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> AtollWalletResult<Vec<Option<SolanaRpcAccount>>> {
+        let mut accounts = Vec::<Option<SolanaRpcAccount>>::with_capacity(addresses.len());
+
+        for chunk in addresses.chunks(Self::MAX_MULTIPLE_ACCOUNTS) {
+            let keys = chunk
+                .iter()
+                .map(|address| jzon::JsonValue::from(address.to_string()))
+                .collect::<Vec<jzon::JsonValue>>();
+
+            let response = self
+                .call::<RpcContext<Vec<Option<RpcAccount>>>>(
+                    "getMultipleAccounts",
+                    jzon::array![
+                        keys,
+                        jzon::object! { "encoding": "base64", "commitment": "confirmed" },
+                    ],
+                )
+                .await?;
+
+            for account in response.value {
+                accounts.push(account.map(SolanaRpcAccount::try_from).transpose()?);
+            }
+        }
+
+        Ok(accounts)
+    }
+
+    /// The account at `address`, `None` if it does not exist
+    pub async fn get_account(
+        &self,
+        address: &Pubkey,
+    ) -> AtollWalletResult<Option<SolanaRpcAccount>> {
+        Ok(self
+            .get_multiple_accounts(&[*address])
+            .await?
+            .into_iter()
+            .next()
+            .flatten())
+    }
+}
+
+/// The outcome of `simulateTransaction`
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SolanaSimulation {
+    /// The transaction error as JSON if the simulation failed
+    pub error: Option<String>,
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+}
+
+/// Where a transaction stands on the cluster
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolanaSignatureStatus {
+    pub signature: Signature,
+    pub slot: u64,
+    /// The unix timestamp in seconds of the block, when known
+    pub block_time: Option<i64>,
+    pub commitment: Option<SolanaCommitment>,
+    /// The transaction error as JSON if the transaction failed
+    pub error: Option<String>,
+}
+
+/// A transaction returned by `getTransaction` with the keys loaded from lookup
+/// tables appended to its account keys
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolanaConfirmedTransaction {
+    pub signature: Signature,
+    pub slot: u64,
+    /// The unix timestamp in seconds of the block, when known
+    pub block_time: Option<i64>,
+    /// The fee in lamports charged to the fee payer
+    pub fee: Option<u64>,
+    /// The transaction error as JSON if the transaction failed
+    pub error: Option<String>,
+    pub account_keys: Vec<Pubkey>,
+    pub instructions: Vec<CompiledInstruction>,
+}
+
+/// An account returned by the RPC with its data decoded
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SolanaRpcAccount {
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+    pub executable: bool,
+}
+
+impl TryFrom<RpcAccount> for SolanaRpcAccount {
+    type Error = AtollWalletError;
+
+    fn try_from(value: RpcAccount) -> Result<Self, Self::Error> {
+        let (data, encoding) = value.data;
+
+        if encoding != "base64" {
+            return Err(AtollWalletError::SolanaRpc(format!(
+                "Expected the account data to be encoded as base64 but it is `{encoding}`"
+            )));
+        }
+
+        let data = Base64::decode_vec(&data).or(Err(AtollWalletError::SolanaRpc(
+            "The account data is not valid base64".to_string(),
+        )))?;
+
+        Ok(Self {
+            lamports: value.lamports,
+            owner: parse_pubkey(&value.owner)?,
+            data,
+            executable: value.executable,
+        })
+    }
+}
+
+pub(crate) fn parse_pubkey(value: &str) -> AtollWalletResult<Pubkey> {
+    Pubkey::from_str(value).or(Err(AtollWalletError::SolanaRpc(format!(
+        "`{value}` is not a valid public key"
+    ))))
+}
+
+pub(crate) fn parse_signature(value: &str) -> AtollWalletResult<Signature> {
+    Signature::from_str(value).or(Err(AtollWalletError::SolanaRpc(format!(
+        "`{value}` is not a valid signature"
+    ))))
+}
+
+fn transaction_error(error: Option<serde_json::Value>) -> Option<String> {
+    error
+        .filter(|error| !error.is_null())
+        .map(|error| error.to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<R> {
+    result: Option<R>,
+    error: Option<RpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// The `{ context, value }` wrapper of RPC results
+#[derive(Debug, Deserialize)]
+pub(crate) struct RpcContext<V> {
+    pub(crate) value: V,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcKeyedAccount {
+    pubkey: String,
+    account: RpcAccount,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcAccount {
+    lamports: u64,
+    owner: String,
+    data: (String, String),
+    executable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcEpochInfo {
+    epoch: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcBlockhash {
+    blockhash: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcPrioritizationFee {
+    prioritization_fee: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSimulation {
+    err: Option<serde_json::Value>,
+    units_consumed: Option<u64>,
+    logs: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSignatureInfo {
+    signature: String,
+    slot: u64,
+    err: Option<serde_json::Value>,
+    block_time: Option<i64>,
+    confirmation_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcSignatureStatus {
+    slot: u64,
+    err: Option<serde_json::Value>,
+    confirmation_status: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcConfirmedTransaction {
+    slot: u64,
+    block_time: Option<i64>,
+    meta: Option<RpcTransactionMeta>,
+    transaction: RpcEncodedTransaction,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransactionMeta {
+    err: Option<serde_json::Value>,
+    fee: u64,
+    loaded_addresses: Option<RpcLoadedAddresses>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RpcLoadedAddresses {
+    writable: Vec<String>,
+    readonly: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcEncodedTransaction {
+    message: RpcEncodedMessage,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcEncodedMessage {
+    account_keys: Vec<String>,
+    instructions: Vec<RpcCompiledInstruction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcCompiledInstruction {
+    program_id_index: u8,
+    accounts: Vec<u8>,
+    data: String,
+}
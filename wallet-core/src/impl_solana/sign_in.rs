@@ -0,0 +1,375 @@
+use std::{str::FromStr, time::SystemTime};
+
+use serde::Deserialize;
+use solana_pubkey::Pubkey;
+use wallet_standard_base::SignInInput;
+
+use crate::{AtollWalletError, AtollWalletResult, SolanaCluster};
+
+/// The `SignInInput` of `solana:signIn`, all of which is optional
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SolanaSignInParams {
+    pub domain: Option<String>,
+    pub address: Option<String>,
+    pub statement: Option<String>,
+    pub uri: Option<String>,
+    pub version: Option<String>,
+    pub chain_id: Option<String>,
+    pub nonce: Option<String>,
+    pub issued_at: Option<String>,
+    pub expiration_time: Option<String>,
+    pub not_before: Option<String>,
+    pub request_id: Option<String>,
+    pub resources: Vec<String>,
+}
+
+impl SolanaSignInParams {
+    /// Ends the first line of a message, after the domain
+    pub const DOMAIN_SUFFIX: &str = " wants you to sign in with your Solana account: ";
+
+    /// Reads the fields back from a message laid out by [SignInInputParser::format].
+    /// The address and statement are both lines followed by a blank line, so a line is
+    /// read as the address only if it is a valid address. Any line out of place is an error.
+    pub fn from_message(message: &str) -> AtollWalletResult<Self> {
+        let invalid = |reason: String| {
+            AtollWalletError::Input(format!(
+                "The Sign In With Solana message is invalid. {reason}"
+            ))
+        };
+
+        if !message.is_empty() && !message.ends_with('\n') {
+            return Err(invalid(
+                "The last line does not end with a line break".to_string(),
+            ));
+        }
+
+        let lines = message.split_terminator('\n').collect::<Vec<&str>>();
+        let mut index = 0usize;
+        let mut params = Self::default();
+
+        if let Some(domain) = lines
+            .first()
+            .and_then(|line| line.strip_suffix(Self::DOMAIN_SUFFIX))
+        {
+            params.domain.replace(domain.to_string());
+            index += 1;
+        }
+
+        let next_block = |index: &mut usize| {
+            let block = lines
+                .get(*index)
+                .filter(|_| lines.get(*index + 1) == Some(&""))?;
+            *index += 2;
+
+            Some(block.to_string())
+        };
+
+        if let Some(block) = next_block(&mut index) {
+            if Pubkey::from_str(&block).is_ok() {
+                params.address.replace(block);
+                params.statement = next_block(&mut index);
+            } else {
+                params.statement.replace(block);
+            }
+        }
+
+        for (label, field) in [
+            ("URI: ", &mut params.uri),
+            ("Version: ", &mut params.version),
+            ("Chain ID: ", &mut params.chain_id),
+            ("Nonce: ", &mut params.nonce),
+            ("Issued At: ", &mut params.issued_at),
+            ("Expiration Time: ", &mut params.expiration_time),
+            ("Not Before: ", &mut params.not_before),
+            ("Request ID: ", &mut params.request_id),
+        ] {
+            if let Some(value) = lines.get(index).and_then(|line| line.strip_prefix(label)) {
+                field.replace(value.to_string());
+                index += 1;
+            }
+        }
+
+        if lines.get(index) == Some(&"Resources:") {
+            index += 1;
+
+            while let Some(resource) = lines.get(index).and_then(|line| line.strip_prefix("- ")) {
+                params.resources.push(resource.to_string());
+                index += 1;
+            }
+
+            if params.resources.is_empty() {
+                return Err(invalid(
+                    "`Resources:` is not followed by a resource".to_string(),
+                ));
+            }
+        }
+
+        if let Some(line) = lines.get(index) {
+            return Err(invalid(format!("The line `{line}` is out of place")));
+        }
+
+        Ok(params)
+    }
+}
+
+/// Builds the Sign In With Solana message of a `solana:signIn` request
+#[derive(Debug)]
+pub struct SignInInputParser<'wa> {
+    input: SignInInput<'wa>,
+    /// The time the expiration and not-before times are checked against
+    now: SystemTime,
+}
+
+impl<'wa> SignInInputParser<'wa> {
+    pub fn new(now: SystemTime) -> Self {
+        Self {
+            input: SignInInput::new(),
+            now,
+        }
+    }
+
+    pub fn parse(&'wa mut self, params: &SolanaSignInParams) -> AtollWalletResult<&'wa mut Self> {
+        self.domain(params)?
+            .address(params)?
+            .statement(params)?
+            .uri(params)?
+            .version(params)?
+            .chain_id(params)?
+            .nonce(params)?
+            .issued_at(params)?
+            .expiration_time(params)?
+            .not_before(params)?
+            .request_id(params)?
+            .resources(params)
+    }
+
+    /*
+            ${domain} wants you to sign in with your Solana account:
+    ${address}
+
+    ${statement}
+
+    URI: ${uri}
+    Version: ${version}
+    Chain ID: ${chain-id}
+    Nonce: ${nonce}
+    Issued At: ${issued-at}
+    Expiration Time: ${expiration-time}
+    Not Before: ${not-before}
+    Request ID: ${request-id}
+    Resources:
+    - ${resources[0]}
+    - ${resources[1]}
+    ...
+    - ${resources[n]}
+     */
+    pub fn format(&'wa self) -> String {
+        let mut formatted = String::default();
+
+        if let Some(domain) = self.input.domain().as_ref() {
+            formatted.push_str(domain);
+            formatted.push_str(" wants you to sign in with your Solana account: \n");
+        }
+        if let Some(address) = self.input.address().as_ref() {
+            formatted.push_str(address);
+            formatted.push_str("\n\n");
+        }
+        if let Some(statement) = self.input.statement().as_ref() {
+            formatted.push_str(statement);
+            formatted.push_str("\n\n");
+        }
+        if let Some(uri) = self.input.uri().as_ref() {
+            formatted.push_str("URI: ");
+            formatted.push_str(uri);
+            formatted.push('\n');
+        }
+        if let Some(version) = self.input.version().as_ref() {
+            formatted.push_str("Version: ");
+            formatted.push_str(version);
+            formatted.push('\n');
+        }
+        if let Some(chain_id) = self.input.chain_id().as_ref() {
+            formatted.push_str("Chain ID: ");
+            formatted.push_str(chain_id);
+            formatted.push('\n');
+        }
+        if let Some(nonce) = self.input.nonce().as_ref() {
+            formatted.push_str("Nonce: ");
+            formatted.push_str(nonce);
+            formatted.push('\n');
+        }
+        if let Some(issued_at) = self.input.issued_at().as_ref() {
+            formatted.push_str("Issued At: ");
+            formatted.push_str(issued_at);
+            formatted.push('\n');
+        }
+        if let Some(expiration_time) = self.input.expiration_time().as_ref() {
+            formatted.push_str("Expiration Time: ");
+            formatted.push_str(expiration_time);
+            formatted.push('\n');
+        };
+        if let Some(not_before) = self.input.not_before().as_ref() {
+            formatted.push_str("Not Before: ");
+            formatted.push_str(not_before);
+            formatted.push('\n');
+        }
+
+        if let Some(request_id) = self.input.request_id().as_ref() {
+            formatted.push_str("Request ID: ");
+            formatted.push_str(request_id);
+            formatted.push('\n');
+        }
+
+        if !self.input.resources().as_ref().is_empty() {
+            formatted.push_str("Resources:\n");
+            self.input.resources().iter().for_each(|resource| {
+                formatted.push_str("- ");
+                formatted.push_str(resource);
+                formatted.push('\n');
+            });
+        }
+
+        formatted
+    }
+
+    pub fn domain(&'wa mut self, params: &SolanaSignInParams) -> AtollWalletResult<&'wa mut Self> {
+        if let Some(domain_value) = params.domain.as_deref() {
+            self.input
+                .set_domain(Self::single_line("domain", domain_value)?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn address(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(address) = params.address.as_deref() {
+            self.input
+                .set_address(Self::single_line("address", address)?)
+                .map_err(|error| AtollWalletError::Input(error.to_string()))?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn statement(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(statement) = params.statement.as_deref() {
+            self.input
+                .set_statement(Self::single_line("statement", statement)?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn uri(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(uri) = params.uri.as_deref() {
+            self.input.set_uri(Self::single_line("uri", uri)?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn version(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(version) = params.version.as_deref() {
+            self.input
+                .set_version(Self::single_line("version", version)?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn chain_id(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(chain_id) = params.chain_id.as_deref() {
+            let cluster: SolanaCluster = Self::single_line("chainId", chain_id)?.into();
+
+            self.input.set_chain_id(cluster);
+        }
+
+        Ok(self)
+    }
+
+    pub fn nonce(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(nonce) = params.nonce.as_deref() {
+            self.input
+                .set_custom_nonce(Self::single_line("nonce", nonce)?)
+                .map_err(|error| AtollWalletError::Input(error.to_string()))?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn issued_at(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(issued_at) = params.issued_at.as_deref() {
+            let issued_at = humantime::parse_rfc3339(issued_at).or(Err(
+                AtollWalletError::InvalidIS08601Timestamp(issued_at.to_string()),
+            ))?;
+            self.input.set_issued_at(issued_at);
+        }
+
+        Ok(self)
+    }
+
+    pub fn expiration_time(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(expiration_time) = params.expiration_time.as_deref() {
+            let expiration_time = humantime::parse_rfc3339(expiration_time).or(Err(
+                AtollWalletError::InvalidIS08601Timestamp(expiration_time.to_string()),
+            ))?;
+            self.input
+                .set_expiration_time(self.now, expiration_time)
+                .map_err(|error| AtollWalletError::Input(error.to_string()))?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn not_before(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(not_before) = params.not_before.as_deref() {
+            let not_before = humantime::parse_rfc3339(not_before).or(Err(
+                AtollWalletError::InvalidIS08601Timestamp(not_before.to_string()),
+            ))?;
+            self.input
+                .set_not_before_time(self.now, not_before)
+                .map_err(|error| AtollWalletError::Input(error.to_string()))?;
+        }
+
+        Ok(self)
+    }
+
+    pub fn request_id(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        if let Some(request_id) = params.request_id.as_deref() {
+            self.input
+                .set_request_id(Self::single_line("requestId", request_id)?);
+        }
+
+        Ok(self)
+    }
+
+    pub fn resources(&mut self, params: &SolanaSignInParams) -> AtollWalletResult<&mut Self> {
+        let resources = params
+            .resources
+            .iter()
+            .map(|resource| Self::single_line("resources", resource))
+            .filter(|resource| !matches!(resource, Ok("")))
+            .collect::<AtollWalletResult<Vec<&str>>>()?;
+
+        if !resources.is_empty() {
+            self.input.add_resources(&resources);
+        }
+
+        Ok(self)
+    }
+
+    /// Each field is a line of the message, so a line break in a field could forge the
+    /// lines that follow it, like the `URI` or `Chain ID`
+    fn single_line<'a>(field: &str, value: &'a str) -> AtollWalletResult<&'a str> {
+        let value = value.trim();
+
+        if value.contains(['\n', '\r']) {
+            return Err(AtollWalletError::Input(format!(
+                "The `{field}` of Sign In With Solana cannot contain a line break"
+            )));
+        }
+
+        Ok(value)
+    }
+}
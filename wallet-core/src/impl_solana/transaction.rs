@@ -0,0 +1,44 @@
+use bincode::Options;
+use solana_sanitize::Sanitize;
+use solana_transaction::Transaction;
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+/// Decodes the bincode transactions sent by dapps in `solana:signTransaction` and
+/// `solana:signAndSendTransaction`
+pub struct SolanaWireTransaction;
+
+impl SolanaWireTransaction {
+    /// The most bytes a transaction can take on the wire, an IPv6 packet less its headers
+    pub const MAX_SIZE: u64 = 1232;
+
+    /// Decodes a legacy transaction the way the cluster does, refusing more than
+    /// [Self::MAX_SIZE] bytes. The transaction is sanitized so that the header and the
+    /// account indexes of its instructions are consistent before anything reads the message.
+    pub fn decode(bytes: &[u8]) -> AtollWalletResult<Transaction> {
+        // The limit of the bincode options is not enforced for every type, so the length
+        // is checked first as the cluster does
+        if bytes.len() as u64 > Self::MAX_SIZE {
+            return Err(AtollWalletError::Input(format!(
+                "The transaction is `{}` bytes, more than the `{}` bytes a transaction can be",
+                bytes.len(),
+                Self::MAX_SIZE
+            )));
+        }
+
+        let transaction = bincode::options()
+            .with_limit(Self::MAX_SIZE)
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .deserialize::<Transaction>(bytes)
+            .map_err(|error| {
+                AtollWalletError::Input(format!("The transaction could not be decoded. {error}"))
+            })?;
+
+        transaction.sanitize().map_err(|error| {
+            AtollWalletError::Input(format!("The transaction is malformed. {error}"))
+        })?;
+
+        Ok(transaction)
+    }
+}
@@ -0,0 +1,158 @@
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+
+use crate::SplTokenProgram;
+
+/// Builders for the instructions of the programs the wallet sends to directly,
+/// so that transactions are assembled without the program crates
+pub struct SolanaInstructions;
+
+impl SolanaInstructions {
+    pub const SYSTEM_PROGRAM_ID: Pubkey = solana_system_interface::program::ID;
+    pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+    pub const MEMO_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+    pub const COMPUTE_BUDGET_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("ComputeBudget111111111111111111111111111111");
+
+    const TRANSFER_CHECKED: u8 = 12;
+    const TRANSFER_FEE_EXTENSION: u8 = 26;
+    const TRANSFER_CHECKED_WITH_FEE: u8 = 1;
+    const CREATE_IDEMPOTENT: u8 = 1;
+    pub(crate) const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+    pub(crate) const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+    /// Transfers `lamports` with the System program
+    pub fn system_transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+        solana_system_interface::instruction::transfer(from, to, lamports)
+    }
+
+    /// The associated token account of `wallet` for `mint`
+    pub fn associated_token_address(
+        wallet: &Pubkey,
+        mint: &Pubkey,
+        program: SplTokenProgram,
+    ) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                wallet.as_ref(),
+                program.program_id().as_ref(),
+                mint.as_ref(),
+            ],
+            &Self::ASSOCIATED_TOKEN_PROGRAM_ID,
+        )
+        .0
+    }
+
+    /// Creates the associated token account of `wallet` for `mint`, paid by `payer`.
+    /// Succeeds without changes if the account already exists.
+    pub fn create_associated_token_account_idempotent(
+        payer: &Pubkey,
+        wallet: &Pubkey,
+        mint: &Pubkey,
+        program: SplTokenProgram,
+    ) -> Instruction {
+        Instruction::new_with_bytes(
+            Self::ASSOCIATED_TOKEN_PROGRAM_ID,
+            &[Self::CREATE_IDEMPOTENT],
+            vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new(Self::associated_token_address(wallet, mint, program), false),
+                AccountMeta::new_readonly(*wallet, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(Self::SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(program.program_id(), false),
+            ],
+        )
+    }
+
+    /// `TransferChecked` of the SPL Token and Token-2022 programs
+    pub fn transfer_checked(
+        program: SplTokenProgram,
+        source: &Pubkey,
+        mint: &Pubkey,
+        destination: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        decimals: u8,
+    ) -> Instruction {
+        let mut data = vec![Self::TRANSFER_CHECKED];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(decimals);
+
+        Instruction::new_with_bytes(
+            program.program_id(),
+            &data,
+            Self::transfer_accounts(source, mint, destination, owner),
+        )
+    }
+
+    /// `TransferCheckedWithFee` of the Token-2022 transfer fee extension, which fails
+    /// unless `fee` is the fee the mint withholds from `amount`
+    pub fn transfer_checked_with_fee(
+        source: &Pubkey,
+        mint: &Pubkey,
+        destination: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+        decimals: u8,
+        fee: u64,
+    ) -> Instruction {
+        let mut data = vec![
+            Self::TRANSFER_FEE_EXTENSION,
+            Self::TRANSFER_CHECKED_WITH_FEE,
+        ];
+        data.extend_from_slice(&amount.to_le_bytes());
+        data.push(decimals);
+        data.extend_from_slice(&fee.to_le_bytes());
+
+        Instruction::new_with_bytes(
+            SplTokenProgram::Token2022.program_id(),
+            &data,
+            Self::transfer_accounts(source, mint, destination, owner),
+        )
+    }
+
+    /// A memo signed by `signers`
+    pub fn memo(memo: &str, signers: &[&Pubkey]) -> Instruction {
+        Instruction::new_with_bytes(
+            Self::MEMO_PROGRAM_ID,
+            memo.as_bytes(),
+            signers
+                .iter()
+                .map(|signer| AccountMeta::new_readonly(**signer, true))
+                .collect(),
+        )
+    }
+
+    /// Sets the most compute units the transaction may consume
+    pub fn set_compute_unit_limit(units: u32) -> Instruction {
+        let mut data = vec![Self::SET_COMPUTE_UNIT_LIMIT];
+        data.extend_from_slice(&units.to_le_bytes());
+
+        Instruction::new_with_bytes(Self::COMPUTE_BUDGET_PROGRAM_ID, &data, Vec::default())
+    }
+
+    /// Sets the priority fee in micro-lamports per compute unit
+    pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+        let mut data = vec![Self::SET_COMPUTE_UNIT_PRICE];
+        data.extend_from_slice(&micro_lamports.to_le_bytes());
+
+        Instruction::new_with_bytes(Self::COMPUTE_BUDGET_PROGRAM_ID, &data, Vec::default())
+    }
+
+    fn transfer_accounts(
+        source: &Pubkey,
+        mint: &Pubkey,
+        destination: &Pubkey,
+        owner: &Pubkey,
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ]
+    }
+}
@@ -0,0 +1,352 @@
+mod instructions;
+pub use instructions::*;
+
+use solana_instruction::Instruction;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_transaction::Transaction;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, HttpTransport, SolanaAccountKeypair, SolanaRpc, SplMint,
+    SplTokenAccount, SplTokenProgram, SplTransferFee,
+};
+
+/// The mint and recipient state a token transfer depends on
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SolanaTokenTransfer {
+    pub mint: Pubkey,
+    pub program: SplTokenProgram,
+    pub decimals: u8,
+    pub non_transferable: bool,
+    /// The transfer fee of the mint in effect for the current epoch
+    pub transfer_fee: Option<SplTransferFee>,
+    /// The associated token account of the recipient, `None` if it does not exist yet
+    pub recipient_account: Option<SplTokenAccount>,
+}
+
+impl SolanaTokenTransfer {
+    /// Fetches the mint and the associated token account of `recipient` for `mint`
+    pub async fn fetch<T: HttpTransport>(
+        rpc: &SolanaRpc<T>,
+        mint: &Pubkey,
+        recipient: &Pubkey,
+    ) -> AtollWalletResult<Self> {
+        let mint_account = rpc
+            .get_account(mint)
+            .await?
+            .ok_or(AtollWalletError::Input(format!(
+                "The mint `{mint}` does not exist"
+            )))?;
+
+        let program = SplTokenProgram::from_program_id(&mint_account.owner).ok_or(
+            AtollWalletError::Input(format!(
+                "`{mint}` is not a mint of the SPL Token or Token-2022 program"
+            )),
+        )?;
+        let mint_state = SplMint::unpack(&mint_account.data)?;
+
+        if mint_state.transfer_hook.is_some() {
+            return Err(AtollWalletError::Input(format!(
+                "Transfers of `{mint}` call a transfer hook which is not supported"
+            )));
+        }
+
+        let recipient_account = rpc
+            .get_account(&SolanaInstructions::associated_token_address(
+                recipient, mint, program,
+            ))
+            .await?
+            .map(|account| SplTokenAccount::unpack(&account.data))
+            .transpose()?;
+
+        let transfer_fee = match mint_state.transfer_fee {
+            Some(config) => Some(*config.fee_for_epoch(rpc.get_epoch().await?)),
+            None => None,
+        };
+
+        Ok(Self {
+            mint: *mint,
+            program,
+            decimals: mint_state.decimals,
+            non_transferable: mint_state.non_transferable,
+            transfer_fee,
+            recipient_account,
+        })
+    }
+}
+
+/// Builds a transaction sending SOL or an SPL token from one wallet to another
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolanaTransferBuilder {
+    sender: Pubkey,
+    recipient: Pubkey,
+    amount: u64,
+    token: Option<SolanaTokenTransfer>,
+    memo: Option<String>,
+    compute_unit_price: Option<u64>,
+    compute_unit_limit: Option<u32>,
+}
+
+impl SolanaTransferBuilder {
+    /// The fee in lamports for each signature of a transaction
+    pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+    /// Compute units estimated per instruction, rounded up from what the programs
+    /// consume so that the limit set with a priority fee is not exceeded
+    pub const COMPUTE_BUDGET_UNITS: u32 = 150;
+    pub const SYSTEM_TRANSFER_UNITS: u32 = 150;
+    pub const TRANSFER_CHECKED_UNITS: u32 = 10_000;
+    pub const CREATE_ACCOUNT_UNITS: u32 = 40_000;
+    pub const MEMO_UNITS: u32 = 15_000;
+
+    /// Sends `amount` lamports, or the smallest unit of a token once [Self::set_token] is set,
+    /// from `sender` to the wallet `recipient`
+    pub fn new(sender: Pubkey, recipient: Pubkey, amount: u64) -> Self {
+        Self {
+            sender,
+            recipient,
+            amount,
+            token: None,
+            memo: None,
+            compute_unit_price: None,
+            compute_unit_limit: None,
+        }
+    }
+
+    /// Sends the token described by `token` instead of SOL
+    pub fn set_token(mut self, token: SolanaTokenTransfer) -> Self {
+        self.token.replace(token);
+
+        self
+    }
+
+    /// Adds a memo right before the transfer, as required by recipients
+    /// with the Token-2022 `MemoTransfer` extension
+    pub fn set_memo(mut self, memo: &str) -> Self {
+        self.memo.replace(memo.to_string());
+
+        self
+    }
+
+    /// Sets the priority fee in micro-lamports per compute unit
+    pub fn set_priority_fee(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price.replace(micro_lamports);
+
+        self
+    }
+
+    /// Sets the compute unit limit instead of the estimate of the instructions
+    pub fn set_compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit.replace(units);
+
+        self
+    }
+
+    pub fn build(&self) -> AtollWalletResult<SolanaTransferPlan> {
+        if self.amount == 0 {
+            return Err(AtollWalletError::Input(
+                "The amount to send must be greater than zero".to_string(),
+            ));
+        }
+
+        let mut instructions = Vec::<Instruction>::new();
+        let mut compute_units = 0u32;
+        let mut transfer_fee = 0u64;
+        let mut creates_recipient_account = false;
+
+        let memo = self.memo.as_deref().map(|memo| {
+            compute_units += Self::MEMO_UNITS;
+
+            SolanaInstructions::memo(memo, &[&self.sender])
+        });
+
+        match self.token.as_ref() {
+            None => {
+                instructions.extend(memo);
+                instructions.push(SolanaInstructions::system_transfer(
+                    &self.sender,
+                    &self.recipient,
+                    self.amount,
+                ));
+                compute_units += Self::SYSTEM_TRANSFER_UNITS;
+            }
+            Some(token) => {
+                if token.non_transferable {
+                    return Err(AtollWalletError::Input(format!(
+                        "The mint `{}` is non-transferable",
+                        token.mint
+                    )));
+                }
+
+                match token.recipient_account.as_ref() {
+                    Some(account) if account.frozen => {
+                        return Err(AtollWalletError::Input(format!(
+                            "The token account of `{}` for `{}` is frozen",
+                            self.recipient, token.mint
+                        )));
+                    }
+                    Some(account) if account.memo_required && memo.is_none() => {
+                        return Err(AtollWalletError::Input(format!(
+                            "`{}` requires a memo for incoming transfers",
+                            self.recipient
+                        )));
+                    }
+                    Some(_) => (),
+                    None => {
+                        instructions.push(
+                            SolanaInstructions::create_associated_token_account_idempotent(
+                                &self.sender,
+                                &self.recipient,
+                                &token.mint,
+                                token.program,
+                            ),
+                        );
+                        compute_units += Self::CREATE_ACCOUNT_UNITS;
+                        creates_recipient_account = true;
+                    }
+                }
+
+                let source = SolanaInstructions::associated_token_address(
+                    &self.sender,
+                    &token.mint,
+                    token.program,
+                );
+                let destination = SolanaInstructions::associated_token_address(
+                    &self.recipient,
+                    &token.mint,
+                    token.program,
+                );
+
+                let transfer = match (token.program, token.transfer_fee) {
+                    (SplTokenProgram::Token2022, Some(fee)) => {
+                        transfer_fee = fee.fee(self.amount);
+
+                        SolanaInstructions::transfer_checked_with_fee(
+                            &source,
+                            &token.mint,
+                            &destination,
+                            &self.sender,
+                            self.amount,
+                            token.decimals,
+                            transfer_fee,
+                        )
+                    }
+                    _ => SolanaInstructions::transfer_checked(
+                        token.program,
+                        &source,
+                        &token.mint,
+                        &destination,
+                        &self.sender,
+                        self.amount,
+                        token.decimals,
+                    ),
+                };
+
+                // The memo must be the instruction right before the transfer
+                instructions.extend(memo);
+                instructions.push(transfer);
+                compute_units += Self::TRANSFER_CHECKED_UNITS;
+            }
+        }
+
+        let compute_unit_limit = match (self.compute_unit_limit, self.compute_unit_price) {
+            (Some(limit), _) => Some(limit),
+            (None, Some(_)) => Some(compute_units + 2 * Self::COMPUTE_BUDGET_UNITS),
+            (None, None) => None,
+        };
+
+        let mut compute_budget = Vec::<Instruction>::new();
+        if let Some(limit) = compute_unit_limit {
+            compute_budget.push(SolanaInstructions::set_compute_unit_limit(limit));
+        }
+        if let Some(price) = self.compute_unit_price {
+            compute_budget.push(SolanaInstructions::set_compute_unit_price(price));
+        }
+        instructions.splice(0..0, compute_budget);
+
+        Ok(SolanaTransferPlan {
+            payer: self.sender,
+            instructions,
+            amount: self.amount,
+            transfer_fee,
+            compute_unit_limit,
+            compute_unit_price: self.compute_unit_price.unwrap_or_default(),
+            creates_recipient_account,
+        })
+    }
+}
+
+/// The instructions built by a [SolanaTransferBuilder]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolanaTransferPlan {
+    payer: Pubkey,
+    instructions: Vec<Instruction>,
+    amount: u64,
+    transfer_fee: u64,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: u64,
+    creates_recipient_account: bool,
+}
+
+impl SolanaTransferPlan {
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// The fee withheld by the mint from the amount sent
+    pub fn transfer_fee(&self) -> u64 {
+        self.transfer_fee
+    }
+
+    /// The amount the recipient receives after the transfer fee
+    pub fn received(&self) -> u64 {
+        self.amount - self.transfer_fee
+    }
+
+    pub fn compute_unit_limit(&self) -> Option<u32> {
+        self.compute_unit_limit
+    }
+
+    /// The priority fee in micro-lamports per compute unit
+    pub fn compute_unit_price(&self) -> u64 {
+        self.compute_unit_price
+    }
+
+    /// Whether the associated token account of the recipient is created, with its
+    /// rent paid by the sender
+    pub fn creates_recipient_account(&self) -> bool {
+        self.creates_recipient_account
+    }
+
+    /// The signature fee and priority fee in lamports, excluding any rent
+    pub fn network_fee(&self) -> u64 {
+        let priority_fee = (self.compute_unit_price as u128
+            * self.compute_unit_limit.unwrap_or_default() as u128)
+            .div_ceil(1_000_000) as u64;
+
+        SolanaTransferBuilder::LAMPORTS_PER_SIGNATURE + priority_fee
+    }
+
+    /// The unsigned transaction paid by the sender
+    pub fn to_transaction(&self, recent_blockhash: solana_hash::Hash) -> Transaction {
+        Transaction::new_unsigned(Message::new_with_blockhash(
+            &self.instructions,
+            Some(&self.payer),
+            &recent_blockhash,
+        ))
+    }
+
+    /// Signs the transaction with the signer of `keypair`, which must be the sender
+    pub async fn sign(
+        &self,
+        keypair: &SolanaAccountKeypair,
+        recent_blockhash: solana_hash::Hash,
+    ) -> AtollWalletResult<Transaction> {
+        keypair
+            .sign_transaction(
+                &self.payer.to_bytes(),
+                self.to_transaction(recent_blockhash),
+            )
+            .await
+    }
+}
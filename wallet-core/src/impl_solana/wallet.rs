@@ -0,0 +1,230 @@
+use std::str::FromStr;
+
+use base64ct::{Base64, Encoding};
+use bip39::{Language, Mnemonic, MnemonicType};
+use serde::Deserialize;
+use solana_derivation_path::DerivationPath;
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_seed_derivable::SeedDerivable;
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+use wallet_standard_base::{Cluster, Commitment};
+use zeroize::Zeroizing;
+
+use crate::{
+    ApduTransport, AtollWalletError, AtollWalletResult, HttpTransport, LedgerSigner, SignerKind,
+    SolanaCluster, SolanaCommitment, SolanaSignableMessage, SolanaWalletAccount, WalletSigner,
+    WatchOnlySigner,
+};
+
+pub struct SolanaAccountKeypair {
+    signer: Box<dyn WalletSigner>,
+}
+
+impl<'wa> SolanaAccountKeypair {
+    pub(crate) fn new_from_mnemonic(
+        mnemonic: Zeroizing<String>,
+        passphrase: Option<Zeroizing<String>>,
+    ) -> AtollWalletResult<Self> {
+        let mnemonic = Mnemonic::from_phrase(&mnemonic, Language::English)?;
+
+        let phrase: &str = mnemonic.phrase();
+
+        let keypair =
+            Keypair::from_seed_phrase_and_passphrase(phrase, &passphrase.unwrap_or_default())
+                .map_err(|error| {
+                    AtollWalletError::UnableToConvertMnemonicToKeypair(error.to_string())
+                })?;
+
+        Ok(Self::new_with_signer(keypair))
+    }
+
+    /// An account backed by any [WalletSigner], for example a hardware wallet
+    pub fn new_with_signer(signer: impl WalletSigner + 'static) -> Self {
+        Self {
+            signer: Box::new(signer),
+        }
+    }
+
+    /// An account at `derivation_path` on a Ledger device reached over `transport`.
+    /// Signing requests for the account are confirmed on the device.
+    pub async fn new_ledger<T: ApduTransport + 'static>(
+        transport: T,
+        derivation_path: DerivationPath,
+    ) -> AtollWalletResult<Self> {
+        let signer = LedgerSigner::connect(transport, derivation_path).await?;
+
+        Ok(Self::new_with_signer(signer))
+    }
+
+    /// Adds an account that can be monitored but not used for signing
+    pub fn new_watch_only(address: &str) -> AtollWalletResult<Self> {
+        let public_key = Pubkey::from_str(address.trim()).or(Err(AtollWalletError::Input(
+            format!("`{address}` is not a valid Solana address"),
+        )))?;
+
+        Ok(Self::new_with_signer(WatchOnlySigner::new(public_key)))
+    }
+
+    pub(crate) fn _new(
+        passphrase: Option<Zeroizing<String>>,
+    ) -> AtollWalletResult<(Self, Zeroizing<String>)> {
+        let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+
+        let phrase = mnemonic.phrase().to_owned();
+
+        let keypair =
+            Keypair::from_seed_phrase_and_passphrase(&phrase, &passphrase.unwrap_or_default())
+                .map_err(|error| {
+                    AtollWalletError::UnableToConvertMnemonicToKeypair(error.to_string())
+                })?;
+
+        Ok((Self::new_with_signer(keypair), Zeroizing::new(phrase)))
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    pub fn signer_kind(&self) -> SignerKind {
+        self.signer.kind()
+    }
+
+    pub fn is_watch_only(&self) -> bool {
+        self.signer_kind() == SignerKind::WatchOnly
+    }
+
+    /// The account given to a connecting dapp. The sites connected are recorded in
+    /// [ConnectedSites](crate::ConnectedSites), which outlive the keypair.
+    pub fn standard_connect(&'wa self) -> SolanaWalletAccount<'wa> {
+        self.get_wallet_account()
+    }
+
+    /// Signs the formatted Sign In With Solana message, see
+    /// [SignInSession](crate::SignInSession) to record the session
+    pub async fn sign_in(
+        &'wa self,
+        formatted_input: &str,
+    ) -> AtollWalletResult<(SolanaWalletAccount<'wa>, [u8; 64])> {
+        let signature = self.signer.sign_message(formatted_input.as_bytes()).await?;
+
+        Ok((self.get_wallet_account(), *signature.as_array()))
+    }
+
+    /// Signs a message that was inspected with [SolanaSignableMessage::inspect]
+    pub async fn sign_message(
+        &self,
+        message: &SolanaSignableMessage,
+    ) -> AtollWalletResult<[u8; 64]> {
+        let signature = self.signer.sign_message(message.bytes()).await?;
+
+        Ok(*signature.as_array())
+    }
+
+    /// Signs `transaction` as the account `public_key`, which must be this account
+    pub async fn sign_transaction(
+        &self,
+        public_key: &[u8; 32],
+        transaction: Transaction,
+    ) -> AtollWalletResult<Transaction> {
+        self.check_public_key(public_key)?;
+
+        self.signer.sign_transaction(transaction).await
+    }
+
+    /// Signs `transaction` as the account `public_key` and sends it to `cluster`. The
+    /// blockhash is refreshed as [SolanaAccountKeypair::refresh_blockhash] allows.
+    // TODO Use getSignatureStatuses to ensure a transaction is processed and confirmed.
+    pub async fn sign_and_send_transaction(
+        &self,
+        transport: &impl HttpTransport,
+        public_key: [u8; 32],
+        mut transaction: Transaction,
+        send_options: crate::SendOptions,
+        recent_blockhash: solana_hash::Hash,
+        cluster: SolanaCluster,
+    ) -> AtollWalletResult<String> {
+        self.check_public_key(&public_key)?;
+
+        Self::refresh_blockhash(&mut transaction, recent_blockhash);
+        let transaction = self.signer.sign_transaction(transaction).await?;
+
+        let signed_transaction_bytes = bincode::serialize(&transaction).or(Err(
+            AtollWalletError::Input("Unable to convert the signed transaction into bytes for `solana:signAndSendTransaction`".to_string())
+        ))?;
+
+        let encoded_signed_transaction = Base64::encode_string(&signed_transaction_bytes);
+        let json_body = jzon::object! {
+          "jsonrpc": "2.0",
+          "id": 1,
+          "method": "sendTransaction",
+          "params": [
+            encoded_signed_transaction,
+            send_options.to_json()
+          ]
+        }
+        .to_string();
+
+        transport.post_json(cluster.endpoint(), json_body).await
+    }
+
+    /// Replaces the blockhash of `transaction` with `recent_blockhash` unless another signer
+    /// already signed it, since the new message would invalidate that signature. A partially
+    /// signed transaction keeps the blockhash it was signed with.
+    pub fn refresh_blockhash(transaction: &mut Transaction, recent_blockhash: solana_hash::Hash) {
+        if transaction
+            .signatures
+            .iter()
+            .all(|signature| *signature == Signature::default())
+        {
+            transaction.message.recent_blockhash = recent_blockhash;
+        }
+    }
+
+    fn check_public_key(&self, public_key: &[u8; 32]) -> AtollWalletResult<()> {
+        if self.pubkey().to_bytes() != *public_key {
+            return Err(AtollWalletError::AccountNotFound(
+                Pubkey::new_from_array(*public_key).to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_wallet_account(&'wa self) -> SolanaWalletAccount<'wa> {
+        let public_key = self.pubkey().to_bytes();
+
+        let wallet_account = SolanaWalletAccount::new(public_key);
+
+        if self.is_watch_only() {
+            wallet_account.set_watch_only()
+        } else {
+            wallet_account
+        }
+    }
+}
+
+/// The `options` of `solana:signAndSendTransaction`
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SendOptions {
+    pub preflight_commitment: SolanaCommitment,
+    pub skip_preflight: bool,
+    pub max_retries: usize,
+}
+
+impl SendOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn to_json(&self) -> jzon::JsonValue {
+        jzon::object! {
+            preflightCommitment: self.preflight_commitment.as_str(),
+            skip_preflight: self.skip_preflight,
+            max_retries: self.max_retries,
+            encoding: "base64"
+        }
+    }
+}
@@ -4,9 +4,8 @@ use std::borrow::Cow;
 use wallet_standard_base::{
     Byte32Array, Cluster, ClusterEnabled, StandardFeatures, WalletAccount, WalletStandardIcon,
 };
-use wasm_bindgen::JsValue;
 
-use crate::{Reflection, SolanaCluster};
+use crate::SolanaCluster;
 
 #[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct SolanaWalletAccount<'wa> {
@@ -15,6 +14,7 @@ pub struct SolanaWalletAccount<'wa> {
     icon: Option<WalletStandardIcon>,
     label: Option<&'wa str>,
     mainnet_enabled: bool,
+    watch_only: bool,
     clusters: Cow<'wa, [SolanaCluster]>,
 }
 
@@ -58,6 +58,17 @@ impl<'wa> SolanaWalletAccount<'wa> {
         self
     }
 
+    /// Watch-only accounts do not advertise any signing features
+    pub fn set_watch_only(mut self) -> Self {
+        self.watch_only = true;
+
+        self
+    }
+
+    pub fn watch_only(&self) -> bool {
+        self.watch_only
+    }
+
     pub fn chains(&'wa self) -> Cow<'wa, [&'wa str]> {
         let chains = self
             .clusters
@@ -71,6 +82,10 @@ impl<'wa> SolanaWalletAccount<'wa> {
     pub fn features(&'wa self) -> Cow<'wa, [&'wa str]> {
         let mut features = Vec::<&str>::default();
 
+        if self.watch_only {
+            return Cow::Owned(features);
+        }
+
         if let Some(value) = self.sign_in() {
             features.push(value)
         }
@@ -83,27 +98,6 @@ impl<'wa> SolanaWalletAccount<'wa> {
 
         Cow::Owned(features)
     }
-
-    pub fn to_js_value_object(&self) -> JsValue {
-        let wallet_account_object = Reflection::new_object();
-
-        let public_key_js_value = Reflection::new_uint8_array(&self.public_key);
-        let chains_js_value = Reflection::new_str_array(self.chains().as_ref());
-        let features_js_value = Reflection::new_str_array(self.features().as_ref());
-
-        wallet_account_object
-            .set_object_secure("address", &self.address.as_ref().into())
-            .set_object_secure("publicKey", &public_key_js_value)
-            .set_object_secure("chains", &chains_js_value)
-            .set_object_secure("features", &features_js_value)
-            .set_object_secure(
-                "icon",
-                &self.icon.map(|value| value.base64().to_string()).into(),
-            )
-            .set_object_secure("label", &self.label().into());
-
-        wallet_account_object.take()
-    }
 }
 
 impl<'wa> WalletAccount for SolanaWalletAccount<'wa> {
@@ -148,6 +142,7 @@ impl fmt::Display for SolanaWalletAccount<'_> {
             .field("address", &self.address)
             .field("public_key", &bs58::encode(&self.public_key).into_string())
             .field("mainnet_enabled", &self.mainnet_enabled)
+            .field("watch_only", &self.watch_only)
             .field("icon", &self.icon)
             .field("label", &self.label)
             .finish()
@@ -0,0 +1,37 @@
+//! The wallet logic of Atoll Wallet: keys, signing, Sign In With Solana, transactions and
+//! RPC clients. Everything that reaches outside the process, like HTTP, hardware wallets or
+//! the clock, is passed in behind a trait or as a value so that the crate builds and is
+//! tested natively, while `atoll-wallet-extension` adapts it to the browser.
+
+mod errors;
+pub use errors::*;
+
+mod transport;
+pub use transport::*;
+
+mod storage;
+pub use storage::*;
+
+mod constants;
+pub use constants::*;
+
+mod protocol;
+pub use protocol::*;
+
+mod impl_solana;
+pub use impl_solana::*;
+
+mod impl_bitcoin;
+pub use impl_bitcoin::*;
+
+mod signer;
+pub use signer::*;
+
+mod vault;
+pub use vault::*;
+
+mod permissions;
+pub use permissions::*;
+
+mod app;
+pub use app::*;
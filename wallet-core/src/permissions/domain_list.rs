@@ -0,0 +1,157 @@
+use std::collections::BTreeSet;
+
+use base64ct::{Base64, Encoding};
+use serde::{Deserialize, Serialize};
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+
+use crate::{AtollWalletError, AtollWalletResult, DomainHost};
+
+/// The blocklist and allowlist of domains from the list publisher. A domain on a list
+/// covers its subdomains.
+///
+/// Lists are published as signed files, `{ "list": "<base64>", "signature": "<base58>" }`,
+/// where `list` is the JSON document `{ "version": 1, "sequence", "blocklist": [],
+/// "allowlist": [] }` and `signature` its ed25519 signature by the publisher. Each file
+/// holds the full lists and replaces those with a lower `sequence`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct DomainList {
+    sequence: u64,
+    blocklist: BTreeSet<String>,
+    allowlist: BTreeSet<String>,
+}
+
+impl DomainList {
+    pub const VERSION: u16 = 1;
+
+    /// Lists of hosts in their ASCII form
+    pub fn new<'a>(
+        sequence: u64,
+        blocklist: impl IntoIterator<Item = &'a str>,
+        allowlist: impl IntoIterator<Item = &'a str>,
+    ) -> AtollWalletResult<Self> {
+        Ok(Self {
+            sequence,
+            blocklist: Self::hosts(blocklist)?,
+            allowlist: Self::hosts(allowlist)?,
+        })
+    }
+
+    /// Reads a signed list file, refusing it unless `publisher` signed it
+    pub fn from_signed(file: &str, publisher: &Pubkey) -> AtollWalletResult<Self> {
+        let invalid = |reason: String| {
+            AtollWalletError::Input(format!("The domain list file is invalid. {reason}"))
+        };
+
+        let signed = serde_json::from_str::<SignedDomainList>(file)
+            .map_err(|error| invalid(error.to_string()))?;
+        let document = Base64::decode_vec(&signed.list)
+            .map_err(|_| invalid("The list is not base64".to_string()))?;
+        let signature = bs58::decode(&signed.signature)
+            .into_vec()
+            .ok()
+            .and_then(|bytes| Signature::try_from(bytes.as_slice()).ok())
+            .ok_or(invalid(
+                "The signature is not a base58 ed25519 signature".to_string(),
+            ))?;
+
+        if !signature.verify(publisher.as_ref(), &document) {
+            return Err(invalid(format!(
+                "It is not signed by the publisher `{publisher}`"
+            )));
+        }
+
+        serde_json::from_slice::<StoredDomainList>(&document)
+            .map_err(|error| invalid(error.to_string()))?
+            .try_into()
+    }
+
+    /// The signed file of this list, as the publisher makes it
+    pub fn to_signed(&self, publisher: &Keypair) -> String {
+        let document = serde_json::to_vec(&StoredDomainList::from(self)).unwrap_or_default();
+        let signed = SignedDomainList {
+            list: Base64::encode_string(&document),
+            signature: bs58::encode(publisher.sign_message(&document)).into_string(),
+        };
+
+        serde_json::to_string(&signed).unwrap_or_default()
+    }
+
+    /// Increases with every list the publisher signs
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn blocklist(&self) -> impl Iterator<Item = &str> {
+        self.blocklist.iter().map(String::as_str)
+    }
+
+    pub fn allowlist(&self) -> impl Iterator<Item = &str> {
+        self.allowlist.iter().map(String::as_str)
+    }
+
+    pub fn blocks(&self, host: &DomainHost) -> bool {
+        host.domains().any(|domain| self.blocklist.contains(domain))
+    }
+
+    pub fn allows(&self, host: &DomainHost) -> bool {
+        host.domains().any(|domain| self.allowlist.contains(domain))
+    }
+
+    fn hosts<'a>(
+        domains: impl IntoIterator<Item = &'a str>,
+    ) -> AtollWalletResult<BTreeSet<String>> {
+        domains
+            .into_iter()
+            .map(|domain| DomainHost::new(domain).map(|host| host.ascii().to_string()))
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SignedDomainList {
+    list: String,
+    signature: String,
+}
+
+/// The document of a signed list file, and how the vault stores the list
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredDomainList {
+    version: u16,
+    sequence: u64,
+    blocklist: Vec<String>,
+    allowlist: Vec<String>,
+}
+
+impl From<&DomainList> for StoredDomainList {
+    fn from(list: &DomainList) -> Self {
+        Self {
+            version: DomainList::VERSION,
+            sequence: list.sequence,
+            blocklist: list.blocklist.iter().cloned().collect(),
+            allowlist: list.allowlist.iter().cloned().collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredDomainList> for DomainList {
+    type Error = AtollWalletError;
+
+    fn try_from(stored: StoredDomainList) -> Result<Self, Self::Error> {
+        if stored.version != Self::VERSION {
+            return Err(AtollWalletError::Input(format!(
+                "The version `{}` of the domain list is not supported",
+                stored.version
+            )));
+        }
+
+        Self::new(
+            stored.sequence,
+            stored.blocklist.iter().map(String::as_str),
+            stored.allowlist.iter().map(String::as_str),
+        )
+    }
+}
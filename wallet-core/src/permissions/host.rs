@@ -0,0 +1,117 @@
+use crate::{AtollWalletError, AtollWalletResult, Punycode};
+
+/// The host of a dapp origin, in the ASCII form browsers give and the Unicode form the
+/// user reads
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DomainHost {
+    ascii: String,
+    unicode: String,
+}
+
+impl DomainHost {
+    /// The most bytes of a host name
+    pub const MAX_LENGTH: usize = 253;
+    /// The most bytes of a label of a host name
+    pub const MAX_LABEL_LENGTH: usize = 63;
+
+    /// The host of `origin`, `scheme://host[:port]`, without its port
+    pub fn from_origin(origin: &str) -> AtollWalletResult<Self> {
+        let invalid = |reason: &str| {
+            AtollWalletError::Input(format!("The origin `{origin}` is invalid. {reason}"))
+        };
+
+        let (scheme, rest) = origin
+            .split_once("://")
+            .ok_or(invalid("It has no scheme"))?;
+        if scheme.is_empty() {
+            return Err(invalid("It has no scheme"));
+        }
+
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        if authority.contains('@') {
+            return Err(invalid("It has user info"));
+        }
+
+        if let Some(bracketed) = authority.strip_prefix('[') {
+            let (address, _port) = bracketed
+                .split_once(']')
+                .ok_or(invalid("The IPv6 address is not closed"))?;
+            if address.is_empty() || !address.chars().all(|c| c.is_ascii_hexdigit() || c == ':') {
+                return Err(invalid("The IPv6 address is invalid"));
+            }
+
+            let host = format!("[{}]", address.to_ascii_lowercase());
+
+            return Ok(Self {
+                ascii: host.clone(),
+                unicode: host,
+            });
+        }
+
+        let host = authority
+            .rsplit_once(':')
+            .map(|(host, _port)| host)
+            .unwrap_or(authority);
+
+        Self::new(host)
+    }
+
+    /// A host name in its ASCII form, with internationalized labels as `xn--` punycode
+    pub fn new(host: &str) -> AtollWalletResult<Self> {
+        let invalid = |reason: &str| {
+            AtollWalletError::Input(format!("The host `{host}` is invalid. {reason}"))
+        };
+
+        let ascii = host.strip_suffix('.').unwrap_or(host).to_ascii_lowercase();
+        if ascii.is_empty() || ascii.len() > Self::MAX_LENGTH {
+            return Err(invalid("It is empty or too long"));
+        }
+        if !ascii
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err(invalid("It is not in its ASCII form"));
+        }
+
+        let unicode = ascii
+            .split('.')
+            .map(|label| {
+                if label.is_empty() || label.len() > Self::MAX_LABEL_LENGTH {
+                    return Err(invalid("A label is empty or too long"));
+                }
+
+                Punycode::decode_label(label)
+                    .map(|label| label.to_lowercase())
+                    .ok_or(invalid("A label is not valid punycode"))
+            })
+            .collect::<AtollWalletResult<Vec<String>>>()?
+            .join(".");
+
+        Ok(Self { ascii, unicode })
+    }
+
+    /// The host as browsers give it, which is how lists name it
+    pub fn ascii(&self) -> &str {
+        &self.ascii
+    }
+
+    /// The host with internationalized labels decoded, which is what lookalikes imitate
+    pub fn unicode(&self) -> &str {
+        &self.unicode
+    }
+
+    /// The host and the domains it is a subdomain of, `a.example.com`, `example.com`
+    /// and `com`
+    pub fn domains(&self) -> impl Iterator<Item = &str> {
+        core::iter::successors(Some(self.ascii.as_str()), |domain| {
+            domain.split_once('.').map(|(_, parent)| parent)
+        })
+    }
+
+    /// Whether this host is `domain` or one of its subdomains
+    pub fn is_within(&self, domain: &str) -> bool {
+        self.ascii
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+    }
+}
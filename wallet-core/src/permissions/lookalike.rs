@@ -0,0 +1,114 @@
+use crate::DomainHost;
+
+/// Hosts imitating the domains of popular Solana dapps with characters that look the same
+pub struct DomainLookalike;
+
+impl DomainLookalike {
+    /// The domains of popular Solana dapps, which are trusted along with their subdomains
+    pub const POPULAR_DAPPS: &[&str] = &[
+        "solana.com",
+        "phantom.app",
+        "solflare.com",
+        "jup.ag",
+        "raydium.io",
+        "orca.so",
+        "meteora.ag",
+        "magiceden.io",
+        "tensor.trade",
+        "marinade.finance",
+        "jito.network",
+        "sanctum.so",
+        "kamino.finance",
+        "drift.trade",
+        "marginfi.com",
+        "solend.fi",
+        "pump.fun",
+        "solscan.io",
+    ];
+
+    /// The popular domain `host` is, or is a subdomain of
+    pub fn popular(host: &DomainHost) -> Option<&'static str> {
+        Self::POPULAR_DAPPS
+            .iter()
+            .find(|domain| host.is_within(domain))
+            .copied()
+    }
+
+    /// The popular domain `host` imitates: its labels read the same as those of the domain
+    /// once [Self::skeleton] maps the characters that look alike, either as the end of the
+    /// host or in front of another domain like `jup.ag.claim.example`
+    pub fn of(host: &DomainHost) -> Option<&'static str> {
+        if Self::popular(host).is_some() {
+            return None;
+        }
+
+        let labels = host
+            .unicode()
+            .split('.')
+            .map(Self::skeleton)
+            .collect::<Vec<String>>();
+
+        Self::POPULAR_DAPPS
+            .iter()
+            .find(|domain| {
+                let popular = domain.split('.').map(Self::skeleton).collect::<Vec<_>>();
+
+                labels
+                    .windows(popular.len())
+                    .any(|window| window == popular)
+            })
+            .copied()
+    }
+
+    /// `text` with the characters of other scripts, accented letters and digits that look
+    /// like ASCII letters replaced by those letters. `i`, `l` and `1` become `l`, `0`
+    /// becomes `o`, `rn` becomes `m` and `vv` becomes `w`.
+    pub fn skeleton(text: &str) -> String {
+        text.chars()
+            .map(Self::base_letter)
+            .collect::<String>()
+            .replace("rn", "m")
+            .replace("vv", "w")
+    }
+
+    fn base_letter(character: char) -> char {
+        // Fullwidth forms
+        let character = match character {
+            'ａ'..='ｚ' | 'Ａ'..='Ｚ' | '０'..='９' => {
+                char::from_u32(character as u32 - 0xfee0).unwrap_or(character)
+            }
+            _ => character,
+        }
+        .to_lowercase()
+        .next()
+        .unwrap_or(character);
+
+        match character {
+            'à'..='å' | 'ā' | 'ă' | 'ą' | 'а' | 'ɑ' | 'α' => 'a',
+            'ь' => 'b',
+            'ç' | 'ć' | 'č' | 'с' | 'ϲ' => 'c',
+            'ď' | 'đ' | 'ԁ' => 'd',
+            'è'..='ë' | 'ē' | 'ė' | 'ę' | 'ě' | 'е' | 'ё' | 'ε' => 'e',
+            'ɡ' | 'ğ' => 'g',
+            'һ' => 'h',
+            'i' | 'ì'..='ï' | 'ı' | 'ī' | 'į' | 'і' | 'ї' | 'ι' | 'ӏ' | 'l' | 'ł' | '1' | '|' => {
+                'l'
+            }
+            'ј' | 'ϳ' => 'j',
+            'κ' | 'к' => 'k',
+            'ñ' | 'ń' | 'ň' | 'η' => 'n',
+            'ò'..='ö' | 'ø' | 'ō' | 'о' | 'ο' | 'σ' | '0' => 'o',
+            'р' | 'ρ' => 'p',
+            'ԛ' => 'q',
+            'ѕ' | 'ś' | 'š' | 'ş' => 's',
+            'ť' | 'τ' => 't',
+            'ù'..='ü' | 'ū' | 'ů' | 'υ' | 'ս' => 'u',
+            'ν' | 'ѵ' => 'v',
+            'ԝ' | 'ω' => 'w',
+            'х' | 'χ' => 'x',
+            'ý' | 'ÿ' | 'у' | 'γ' => 'y',
+            'ź' | 'ż' | 'ž' => 'z',
+            _ => character,
+        }
+    }
+}
@@ -0,0 +1,23 @@
+mod policy;
+pub use policy::*;
+
+mod spending;
+pub use spending::*;
+
+mod punycode;
+pub use punycode::*;
+
+mod host;
+pub use host::*;
+
+mod lookalike;
+pub use lookalike::*;
+
+mod domain_list;
+pub use domain_list::*;
+
+mod reputation;
+pub use reputation::*;
+
+mod sites;
+pub use sites::*;
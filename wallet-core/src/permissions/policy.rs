@@ -0,0 +1,451 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use wallet_standard_base::Cluster;
+
+use crate::{AtollWalletError, AtollWalletResult, SolanaCluster, SolanaSpending};
+
+/// What a dapp can ask the wallet for once connected
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum DappFeature {
+    /// `solana:signIn`
+    SignIn,
+    /// `solana:signMessage`
+    SignMessage,
+    /// `solana:signTransaction` and `solana:signAndSendTransaction`
+    Transactions,
+}
+
+impl DappFeature {
+    pub const ALL: &[Self] = &[Self::SignIn, Self::SignMessage, Self::Transactions];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SignIn => "signIn",
+            Self::SignMessage => "signMessage",
+            Self::Transactions => "transactions",
+        }
+    }
+}
+
+impl TryFrom<&str> for DappFeature {
+    type Error = AtollWalletError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .find(|feature| feature.as_str() == value)
+            .copied()
+            .ok_or(AtollWalletError::Input(format!(
+                "`{value}` is not a dapp feature"
+            )))
+    }
+}
+
+/// Transactions of a dapp that take up to `lamports_per_day` from the account in a UTC day
+/// are signed without asking the user
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DappAutoApprove {
+    lamports_per_day: u64,
+    /// The day, counted from the unix epoch, of `spent`
+    day: u64,
+    spent: u64,
+}
+
+impl DappAutoApprove {
+    pub const DAY_MS: f64 = 86_400_000.0;
+
+    pub fn new(lamports_per_day: u64) -> Self {
+        Self {
+            lamports_per_day,
+            day: u64::default(),
+            spent: u64::default(),
+        }
+    }
+
+    pub fn lamports_per_day(&self) -> u64 {
+        self.lamports_per_day
+    }
+
+    /// The lamports left to spend on the day of `now_ms`
+    pub fn remaining(&self, now_ms: f64) -> u64 {
+        if Self::day(now_ms) == self.day {
+            self.lamports_per_day.saturating_sub(self.spent)
+        } else {
+            self.lamports_per_day
+        }
+    }
+
+    /// Records `lamports` spent at `now_ms` if they are within what is left of the day
+    pub fn spend(&mut self, lamports: u64, now_ms: f64) -> AtollWalletResult<()> {
+        let remaining = self.remaining(now_ms);
+
+        if lamports > remaining {
+            return Err(AtollWalletError::SpendingLimitExceeded(format!(
+                "The transaction spends `{lamports}` lamports but `{remaining}` of the `{}` lamports a day are left",
+                self.lamports_per_day
+            )));
+        }
+
+        let day = Self::day(now_ms);
+        if day != self.day {
+            self.day = day;
+            self.spent = 0;
+        }
+        self.spent += lamports;
+
+        Ok(())
+    }
+
+    fn day(now_ms: f64) -> u64 {
+        (now_ms / Self::DAY_MS).floor().max(0.0) as u64
+    }
+}
+
+/// What the dapp at an origin is granted: the accounts it sees, the chains and features it
+/// can use and an optional [DappAutoApprove] rule.
+///
+/// The daily spending limit is part of that rule and there is no other. A dapp without a
+/// rule has transactions of any amount signed once it is granted [DappFeature::Transactions],
+/// so its spending is capped by setting a rule or by removing that feature.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DappPolicy {
+    origin: String,
+    accounts: Vec<Pubkey>,
+    chains: Vec<SolanaCluster>,
+    features: Vec<DappFeature>,
+    auto_approve: Option<DappAutoApprove>,
+}
+
+impl DappPolicy {
+    /// The policy of a dapp that just connected with `account`, which can use every
+    /// chain and feature
+    pub fn new(origin: &str, account: Pubkey) -> Self {
+        Self {
+            origin: origin.to_string(),
+            accounts: vec![account],
+            chains: Self::all_chains(),
+            features: DappFeature::ALL.to_vec(),
+            auto_approve: Option::default(),
+        }
+    }
+
+    pub fn set_accounts(mut self, accounts: Vec<Pubkey>) -> Self {
+        self.accounts = accounts;
+
+        self
+    }
+
+    pub fn set_chains(mut self, chains: Vec<SolanaCluster>) -> Self {
+        self.chains = chains;
+
+        self
+    }
+
+    pub fn set_features(mut self, features: Vec<DappFeature>) -> Self {
+        self.features = features;
+
+        self
+    }
+
+    /// Replacing the rule with the same allowance keeps what was already spent today
+    pub fn set_auto_approve(mut self, auto_approve: Option<DappAutoApprove>) -> Self {
+        self.auto_approve = match (self.auto_approve, auto_approve) {
+            (Some(existing), Some(new))
+                if existing.lamports_per_day() == new.lamports_per_day() =>
+            {
+                Some(existing)
+            }
+            (_, new) => new,
+        };
+
+        self
+    }
+
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    pub fn accounts(&self) -> &[Pubkey] {
+        &self.accounts
+    }
+
+    pub fn chains(&self) -> &[SolanaCluster] {
+        &self.chains
+    }
+
+    pub fn features(&self) -> &[DappFeature] {
+        &self.features
+    }
+
+    pub fn auto_approve(&self) -> Option<&DappAutoApprove> {
+        self.auto_approve.as_ref()
+    }
+
+    /// Checks that the dapp can use `feature` with `account`
+    pub fn authorize(&self, account: &Pubkey, feature: DappFeature) -> AtollWalletResult<()> {
+        if !self.accounts.contains(account) {
+            return Err(self.not_authorized(format!("was not granted the account `{account}`")));
+        }
+
+        if !self.features.contains(&feature) {
+            return Err(self.not_authorized(format!("cannot use `{}`", feature.as_str())));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the dapp can use `chain`. A request that does not name its chain is
+    /// only allowed when the dapp can use every chain.
+    pub fn authorize_chain(&self, chain: Option<SolanaCluster>) -> AtollWalletResult<()> {
+        match chain {
+            Some(chain) if !self.chains.contains(&chain) => {
+                Err(self.not_authorized(format!("cannot use `{}`", chain.chain())))
+            }
+            None if Self::all_chains()
+                .iter()
+                .any(|chain| !self.chains.contains(chain)) =>
+            {
+                Err(self.not_authorized(
+                    "is limited to some chains and the request does not name its chain".to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a transaction `account` is asked to sign at `now_ms` against the
+    /// [DappAutoApprove] rule, recording what it spends. Transactions past the allowance,
+    /// or whose spending cannot be decoded, are refused as there is no prompt to ask the
+    /// user instead. Without a rule every transaction is passed on. What is spent is recorded
+    /// before the transaction is sent, so one that then fails still counts.
+    pub fn spend(
+        &mut self,
+        account: &Pubkey,
+        message: &Message,
+        now_ms: f64,
+    ) -> AtollWalletResult<()> {
+        match SolanaSpending::of_message(message, account) {
+            Some(lamports) => self.spend_lamports(lamports, now_ms),
+            None => self.spend_undecoded(),
+        }
+    }
+
+    /// Records `lamports` spent at `now_ms` against the [DappAutoApprove] rule, like the
+    /// priority fee the wallet adds to a transaction that passed [Self::spend]
+    pub fn spend_lamports(&mut self, lamports: u64, now_ms: f64) -> AtollWalletResult<()> {
+        match self.auto_approve.as_mut() {
+            Some(auto_approve) => auto_approve.spend(lamports, now_ms),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a transaction whose spending cannot be counted in lamports, like a Bitcoin
+    /// transaction, which is refused under a [DappAutoApprove] rule
+    pub fn spend_undecoded(&self) -> AtollWalletResult<()> {
+        match self.auto_approve {
+            Some(_) => Err(AtollWalletError::SpendingLimitExceeded(
+                "What the transaction spends cannot be decoded".to_string(),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn not_authorized(&self, reason: String) -> AtollWalletError {
+        AtollWalletError::DappNotAuthorized(format!("`{}` {reason}", self.origin))
+    }
+
+    fn all_chains() -> Vec<SolanaCluster> {
+        vec![
+            SolanaCluster::Mainnet,
+            SolanaCluster::Testnet,
+            SolanaCluster::Devnet,
+            SolanaCluster::Localnet,
+        ]
+    }
+}
+
+/// The [DappPolicy] of every origin, kept in the vault and stored as a versioned JSON document
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct DappPolicies(BTreeMap<String, DappPolicy>);
+
+impl DappPolicies {
+    /// The version of the JSON document
+    pub const VERSION: u16 = 1;
+
+    pub fn get(&self, origin: &str) -> Option<&DappPolicy> {
+        self.0.get(origin)
+    }
+
+    pub fn get_mut(&mut self, origin: &str) -> Option<&mut DappPolicy> {
+        self.0.get_mut(origin)
+    }
+
+    /// Adds the policy or replaces the policy of the same origin
+    pub fn insert(&mut self, policy: DappPolicy) -> &mut Self {
+        self.0.insert(policy.origin.clone(), policy);
+
+        self
+    }
+
+    pub fn remove(&mut self, origin: &str) -> Option<DappPolicy> {
+        self.0.remove(origin)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DappPolicy> {
+        self.0.values()
+    }
+
+    /// Connects the dapp at `origin` with `account`. A dapp connecting for the first time is
+    /// granted the account, a dapp with a policy must have been granted it already.
+    pub fn connect(&mut self, origin: &str, account: Pubkey) -> AtollWalletResult<&DappPolicy> {
+        let policy = self
+            .0
+            .entry(origin.to_string())
+            .or_insert_with(|| DappPolicy::new(origin, account));
+
+        if !policy.accounts.contains(&account) {
+            return Err(policy.not_authorized(format!("was not granted the account `{account}`")));
+        }
+
+        Ok(policy)
+    }
+
+    /// The policy of the dapp at `origin`, which must have connected
+    pub fn connected(&self, origin: Option<&str>) -> AtollWalletResult<&DappPolicy> {
+        let origin = Self::require_origin(origin)?;
+
+        self.0.get(origin).ok_or(Self::not_connected(origin))
+    }
+
+    pub fn connected_mut(&mut self, origin: Option<&str>) -> AtollWalletResult<&mut DappPolicy> {
+        let origin = Self::require_origin(origin)?;
+
+        self.0.get_mut(origin).ok_or(Self::not_connected(origin))
+    }
+
+    fn require_origin(origin: Option<&str>) -> AtollWalletResult<&str> {
+        origin.ok_or(AtollWalletError::InvalidRequest(
+            "The request requires the origin of the page".to_string(),
+        ))
+    }
+
+    fn not_connected(origin: &str) -> AtollWalletError {
+        AtollWalletError::DappNotAuthorized(format!("`{origin}` is not connected"))
+    }
+
+    pub fn to_json(&self) -> String {
+        let stored = StoredDappPolicies {
+            version: Self::VERSION,
+            policies: self.iter().map(StoredDappPolicy::from).collect(),
+        };
+
+        serde_json::to_string(&stored).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> AtollWalletResult<Self> {
+        let stored = serde_json::from_str::<StoredDappPolicies>(json).map_err(|error| {
+            AtollWalletError::Input(format!("The dapp policies could not be read. {error}"))
+        })?;
+
+        if stored.version != Self::VERSION {
+            return Err(AtollWalletError::Input(format!(
+                "The version `{}` of the dapp policies is not supported",
+                stored.version
+            )));
+        }
+
+        let mut policies = Self::default();
+        for policy in stored.policies {
+            policies.insert(policy.try_into()?);
+        }
+
+        Ok(policies)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredDappPolicies {
+    version: u16,
+    policies: Vec<StoredDappPolicy>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredDappPolicy {
+    origin: String,
+    accounts: Vec<String>,
+    chains: Vec<String>,
+    features: Vec<String>,
+    auto_approve: Option<StoredDappAutoApprove>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredDappAutoApprove {
+    lamports_per_day: u64,
+    day: u64,
+    spent: u64,
+}
+
+impl From<&DappPolicy> for StoredDappPolicy {
+    fn from(policy: &DappPolicy) -> Self {
+        Self {
+            origin: policy.origin.clone(),
+            accounts: policy.accounts.iter().map(Pubkey::to_string).collect(),
+            chains: policy
+                .chains
+                .iter()
+                .map(|chain| chain.chain().to_string())
+                .collect(),
+            features: policy
+                .features
+                .iter()
+                .map(|feature| feature.as_str().to_string())
+                .collect(),
+            auto_approve: policy
+                .auto_approve
+                .map(|auto_approve| StoredDappAutoApprove {
+                    lamports_per_day: auto_approve.lamports_per_day,
+                    day: auto_approve.day,
+                    spent: auto_approve.spent,
+                }),
+        }
+    }
+}
+
+impl TryFrom<StoredDappPolicy> for DappPolicy {
+    type Error = AtollWalletError;
+
+    fn try_from(stored: StoredDappPolicy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            origin: stored.origin,
+            accounts: stored
+                .accounts
+                .iter()
+                .map(|account| {
+                    Pubkey::from_str(account).or(Err(AtollWalletError::Input(format!(
+                        "`{account}` is not a valid Solana address"
+                    ))))
+                })
+                .collect::<AtollWalletResult<Vec<Pubkey>>>()?,
+            chains: stored
+                .chains
+                .iter()
+                .map(|chain| SolanaCluster::from(chain.as_str()))
+                .collect(),
+            features: stored
+                .features
+                .iter()
+                .map(|feature| DappFeature::try_from(feature.as_str()))
+                .collect::<AtollWalletResult<Vec<DappFeature>>>()?,
+            auto_approve: stored.auto_approve.map(|auto_approve| DappAutoApprove {
+                lamports_per_day: auto_approve.lamports_per_day,
+                day: auto_approve.day,
+                spent: auto_approve.spent,
+            }),
+        })
+    }
+}
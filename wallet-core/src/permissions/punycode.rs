@@ -0,0 +1,93 @@
+/// Decodes the `xn--` labels of internationalized domain names, RFC 3492
+pub struct Punycode;
+
+impl Punycode {
+    /// Starts a label in the ASCII form of an internationalized domain name
+    pub const ACE_PREFIX: &str = "xn--";
+
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+    const INITIAL_BIAS: u32 = 72;
+    const INITIAL_N: u32 = 0x80;
+
+    /// The Unicode form of a domain `label`, which is returned as it is unless it starts
+    /// with [Self::ACE_PREFIX]. `None` if the label is not valid punycode.
+    pub fn decode_label(label: &str) -> Option<String> {
+        match label.strip_prefix(Self::ACE_PREFIX) {
+            Some(encoded) => Self::decode(encoded),
+            None => Some(label.to_string()),
+        }
+    }
+
+    /// Decodes `input` without the [Self::ACE_PREFIX]
+    pub fn decode(input: &str) -> Option<String> {
+        let (basic, extended) = match input.rfind('-') {
+            Some(index) => (&input[..index], &input[index + 1..]),
+            None => ("", input),
+        };
+
+        if !basic.is_ascii() {
+            return None;
+        }
+
+        let mut output = basic.chars().collect::<Vec<char>>();
+        let mut digits = extended.bytes().peekable();
+        let mut code_point = Self::INITIAL_N;
+        let mut bias = Self::INITIAL_BIAS;
+        let mut index = 0u32;
+
+        while digits.peek().is_some() {
+            let previous_index = index;
+            let mut weight = 1u32;
+            let mut k = Self::BASE;
+
+            loop {
+                let digit = match digits.next()? {
+                    byte @ b'a'..=b'z' => byte - b'a',
+                    byte @ b'A'..=b'Z' => byte - b'A',
+                    byte @ b'0'..=b'9' => byte - b'0' + 26,
+                    _ => return None,
+                } as u32;
+                index = index.checked_add(digit.checked_mul(weight)?)?;
+
+                let threshold = k.saturating_sub(bias).clamp(Self::T_MIN, Self::T_MAX);
+                if digit < threshold {
+                    break;
+                }
+
+                weight = weight.checked_mul(Self::BASE - threshold)?;
+                k = k.checked_add(Self::BASE)?;
+            }
+
+            let length = output.len() as u32 + 1;
+            bias = Self::adapt(index - previous_index, length, previous_index == 0);
+            code_point = code_point.checked_add(index / length)?;
+            index %= length;
+
+            output.insert(index as usize, char::from_u32(code_point)?);
+            index += 1;
+        }
+
+        Some(output.into_iter().collect())
+    }
+
+    fn adapt(delta: u32, points: u32, is_first: bool) -> u32 {
+        let mut delta = if is_first {
+            delta / Self::DAMP
+        } else {
+            delta / 2
+        };
+        delta += delta / points;
+
+        let mut k = 0;
+        while delta > ((Self::BASE - Self::T_MIN) * Self::T_MAX) / 2 {
+            delta /= Self::BASE - Self::T_MIN;
+            k += Self::BASE;
+        }
+
+        k + ((Self::BASE - Self::T_MIN + 1) * delta) / (delta + Self::SKEW)
+    }
+}
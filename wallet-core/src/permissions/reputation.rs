@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use solana_pubkey::Pubkey;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, DappAutoApprove, DomainHost, DomainList, DomainLookalike,
+    StoredDomainList,
+};
+
+/// What is done with a request from a domain
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DomainAction {
+    Allow,
+    /// The request goes on and the warning is shown to the user
+    Warn,
+    Block,
+}
+
+impl DomainAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Warn => "warn",
+            Self::Block => "block",
+        }
+    }
+}
+
+impl TryFrom<&str> for DomainAction {
+    type Error = AtollWalletError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        [Self::Allow, Self::Warn, Self::Block]
+            .into_iter()
+            .find(|action| action.as_str() == value)
+            .ok_or(AtollWalletError::Input(format!(
+                "`{value}` is not a domain action"
+            )))
+    }
+}
+
+/// What is known of a domain
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DomainVerdict {
+    /// On the allowlist or a popular dapp
+    Trusted,
+    Blocklisted,
+    /// Looks like the domain of the popular dapp
+    Lookalike(&'static str),
+    /// First seen within [DomainReputationPolicy::newly_seen_days]
+    NewlySeen,
+    /// Seen for longer
+    Known,
+}
+
+impl DomainVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Trusted => "trusted",
+            Self::Blocklisted => "blocklisted",
+            Self::Lookalike(_) => "lookalike",
+            Self::NewlySeen => "newlySeen",
+            Self::Known => "known",
+        }
+    }
+}
+
+/// What is done for each [DomainVerdict] that is not always allowed
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DomainReputationPolicy {
+    pub blocklisted: DomainAction,
+    pub lookalike: DomainAction,
+    pub newly_seen: DomainAction,
+    /// How long a domain is newly seen after the wallet first sees it
+    pub newly_seen_days: u64,
+}
+
+impl Default for DomainReputationPolicy {
+    fn default() -> Self {
+        Self {
+            blocklisted: DomainAction::Block,
+            lookalike: DomainAction::Block,
+            newly_seen: DomainAction::Warn,
+            newly_seen_days: 7,
+        }
+    }
+}
+
+/// The [DomainVerdict] of the host of an origin and the [DomainAction] the policy takes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DomainReport {
+    host: DomainHost,
+    verdict: DomainVerdict,
+    action: DomainAction,
+}
+
+impl DomainReport {
+    pub fn host(&self) -> &DomainHost {
+        &self.host
+    }
+
+    pub fn verdict(&self) -> &DomainVerdict {
+        &self.verdict
+    }
+
+    pub fn action(&self) -> DomainAction {
+        self.action
+    }
+
+    /// Why the domain is not allowed, for the warning or error shown to the user
+    pub fn reason(&self) -> Option<String> {
+        let host = self.host.unicode();
+
+        match self.verdict {
+            _ if self.action == DomainAction::Allow => None,
+            DomainVerdict::Blocklisted => Some(format!("`{host}` is on the blocklist")),
+            DomainVerdict::Lookalike(domain) => Some(format!("`{host}` looks like `{domain}`")),
+            DomainVerdict::NewlySeen => Some(format!("`{host}` was first seen recently")),
+            DomainVerdict::Trusted | DomainVerdict::Known => None,
+        }
+    }
+}
+
+/// Checks the origins of dapps against the signed [DomainList], lookalikes of popular dapps
+/// and the domains seen before, kept in the vault and stored as a versioned JSON document
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct DomainReputation {
+    list: DomainList,
+    policy: DomainReputationPolicy,
+    /// When each host was first seen, in milliseconds since the unix epoch
+    seen: BTreeMap<String, u64>,
+}
+
+impl DomainReputation {
+    pub const VERSION: u16 = 1;
+    /// The most hosts remembered, the first seen are forgotten beyond it
+    pub const MAX_SEEN: usize = 10_000;
+
+    pub fn list(&self) -> &DomainList {
+        &self.list
+    }
+
+    pub fn policy(&self) -> &DomainReputationPolicy {
+        &self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: DomainReputationPolicy) -> &mut Self {
+        self.policy = policy;
+
+        self
+    }
+
+    /// When the wallet first saw `host`, in milliseconds since the unix epoch
+    pub fn first_seen(&self, host: &DomainHost) -> Option<u64> {
+        self.seen.get(host.ascii()).copied()
+    }
+
+    /// Replaces the list with the signed list `file` of `publisher` if it is newer,
+    /// returning its sequence
+    pub fn update(&mut self, file: &str, publisher: &Pubkey) -> AtollWalletResult<u64> {
+        let list = DomainList::from_signed(file, publisher)?;
+
+        if list.sequence() <= self.list.sequence() {
+            return Err(AtollWalletError::Input(format!(
+                "The domain list `{}` is not newer than the list `{}`",
+                list.sequence(),
+                self.list.sequence()
+            )));
+        }
+
+        self.list = list;
+
+        Ok(self.list.sequence())
+    }
+
+    /// The report on the host of `origin` at `now_ms` without recording that it was seen
+    pub fn check(&self, origin: &str, now_ms: f64) -> AtollWalletResult<DomainReport> {
+        let host = DomainHost::from_origin(origin)?;
+
+        let (verdict, action) = if self.list.allows(&host) {
+            (DomainVerdict::Trusted, DomainAction::Allow)
+        } else if self.list.blocks(&host) {
+            (DomainVerdict::Blocklisted, self.policy.blocklisted)
+        } else if DomainLookalike::popular(&host).is_some() {
+            (DomainVerdict::Trusted, DomainAction::Allow)
+        } else if let Some(domain) = DomainLookalike::of(&host) {
+            (DomainVerdict::Lookalike(domain), self.policy.lookalike)
+        } else if self.first_seen(&host).is_none_or(|first_seen_ms| {
+            now_ms - (first_seen_ms as f64)
+                < self.policy.newly_seen_days as f64 * DappAutoApprove::DAY_MS
+        }) {
+            (DomainVerdict::NewlySeen, self.policy.newly_seen)
+        } else {
+            (DomainVerdict::Known, DomainAction::Allow)
+        };
+
+        Ok(DomainReport {
+            host,
+            verdict,
+            action,
+        })
+    }
+
+    /// Checks a request from `origin` at `now_ms`, recording when its host was first seen.
+    /// Blocked domains are an error, the report says whether to warn.
+    pub fn authorize(&mut self, origin: &str, now_ms: f64) -> AtollWalletResult<DomainReport> {
+        let report = self.check(origin, now_ms)?;
+
+        if report.action == DomainAction::Block {
+            return Err(AtollWalletError::PhishingDomain(
+                report.reason().unwrap_or_default(),
+            ));
+        }
+
+        if !self.seen.contains_key(report.host.ascii()) {
+            if self.seen.len() >= Self::MAX_SEEN
+                && let Some(oldest) = self
+                    .seen
+                    .iter()
+                    .min_by_key(|(_, first_seen_ms)| **first_seen_ms)
+                    .map(|(host, _)| host.clone())
+            {
+                self.seen.remove(&oldest);
+            }
+
+            self.seen
+                .insert(report.host.ascii().to_string(), now_ms.max(0.0) as u64);
+        }
+
+        Ok(report)
+    }
+
+    pub fn to_json(&self) -> String {
+        let stored = StoredDomainReputation {
+            version: Self::VERSION,
+            list: StoredDomainList::from(&self.list),
+            policy: StoredDomainReputationPolicy {
+                blocklisted: self.policy.blocklisted.as_str().to_string(),
+                lookalike: self.policy.lookalike.as_str().to_string(),
+                newly_seen: self.policy.newly_seen.as_str().to_string(),
+                newly_seen_days: self.policy.newly_seen_days,
+            },
+            seen: self.seen.clone(),
+        };
+
+        serde_json::to_string(&stored).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> AtollWalletResult<Self> {
+        let stored = serde_json::from_str::<StoredDomainReputation>(json).map_err(|error| {
+            AtollWalletError::Input(format!("The domain reputation could not be read. {error}"))
+        })?;
+
+        if stored.version != Self::VERSION {
+            return Err(AtollWalletError::Input(format!(
+                "The version `{}` of the domain reputation is not supported",
+                stored.version
+            )));
+        }
+
+        Ok(Self {
+            list: stored.list.try_into()?,
+            policy: DomainReputationPolicy {
+                blocklisted: stored.policy.blocklisted.as_str().try_into()?,
+                lookalike: stored.policy.lookalike.as_str().try_into()?,
+                newly_seen: stored.policy.newly_seen.as_str().try_into()?,
+                newly_seen_days: stored.policy.newly_seen_days,
+            },
+            seen: stored.seen,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredDomainReputation {
+    version: u16,
+    list: StoredDomainList,
+    policy: StoredDomainReputationPolicy,
+    seen: BTreeMap<String, u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredDomainReputationPolicy {
+    blocklisted: String,
+    lookalike: String,
+    newly_seen: String,
+    newly_seen_days: u64,
+}
@@ -0,0 +1,329 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use solana_pubkey::Pubkey;
+
+use crate::{AtollWalletError, AtollWalletResult, SolanaSignInParams};
+
+/// A site the user connected, remembered so that it can be listed, revoked and
+/// reconnected silently
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConnectedSite {
+    origin: String,
+    /// The icon of the page as a URL, often a data URL
+    icon: Option<String>,
+    /// In milliseconds since the unix epoch
+    first_connected_ms: u64,
+    /// When the site last connected or had a request signed, in milliseconds since the
+    /// unix epoch
+    last_used_ms: u64,
+    /// The accounts the site connected with
+    accounts: Vec<Pubkey>,
+    sign_in: Option<SignInSession>,
+}
+
+impl ConnectedSite {
+    /// The most bytes of an icon, larger icons are not kept
+    pub const MAX_ICON_LENGTH: usize = 64 * 1024;
+
+    pub fn origin(&self) -> &str {
+        &self.origin
+    }
+
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    pub fn first_connected_ms(&self) -> u64 {
+        self.first_connected_ms
+    }
+
+    pub fn last_used_ms(&self) -> u64 {
+        self.last_used_ms
+    }
+
+    pub fn accounts(&self) -> &[Pubkey] {
+        &self.accounts
+    }
+
+    /// The last Sign In With Solana of the site
+    pub fn sign_in(&self) -> Option<&SignInSession> {
+        self.sign_in.as_ref()
+    }
+}
+
+/// A Sign In With Solana message the user signed for a site
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SignInSession {
+    account: Pubkey,
+    domain: Option<String>,
+    chain_id: Option<String>,
+    nonce: Option<String>,
+    issued_at: Option<String>,
+    expiration_time: Option<String>,
+    /// In milliseconds since the unix epoch
+    signed_at_ms: u64,
+}
+
+impl SignInSession {
+    /// The session of the signed `message` of `account`, read back with
+    /// [SolanaSignInParams::from_message]
+    pub fn new(account: Pubkey, message: &str, now_ms: f64) -> AtollWalletResult<Self> {
+        let params = SolanaSignInParams::from_message(message)?;
+
+        Ok(Self {
+            account,
+            domain: params.domain,
+            chain_id: params.chain_id,
+            nonce: params.nonce,
+            issued_at: params.issued_at,
+            expiration_time: params.expiration_time,
+            signed_at_ms: now_ms.max(0.0) as u64,
+        })
+    }
+
+    pub fn account(&self) -> &Pubkey {
+        &self.account
+    }
+
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_deref()
+    }
+
+    pub fn chain_id(&self) -> Option<&str> {
+        self.chain_id.as_deref()
+    }
+
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_deref()
+    }
+
+    /// As RFC 3339
+    pub fn issued_at(&self) -> Option<&str> {
+        self.issued_at.as_deref()
+    }
+
+    /// As RFC 3339
+    pub fn expiration_time(&self) -> Option<&str> {
+        self.expiration_time.as_deref()
+    }
+
+    pub fn signed_at_ms(&self) -> u64 {
+        self.signed_at_ms
+    }
+}
+
+/// The [ConnectedSite] of every origin, kept in the vault and stored as a versioned JSON
+/// document so that they outlive the background
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct ConnectedSites(BTreeMap<String, ConnectedSite>);
+
+impl ConnectedSites {
+    /// The version of the JSON document
+    pub const VERSION: u16 = 1;
+
+    pub fn get(&self, origin: &str) -> Option<&ConnectedSite> {
+        self.0.get(origin)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConnectedSite> {
+        self.0.values()
+    }
+
+    /// The sites connected with `account`
+    pub fn for_account(&self, account: &Pubkey) -> impl Iterator<Item = &ConnectedSite> {
+        self.iter()
+            .filter(move |site| site.accounts.contains(account))
+    }
+
+    /// Records that `origin` connected with `account` at `now_ms`, keeping the `icon` it
+    /// sent unless it is too large
+    pub fn connect(
+        &mut self,
+        origin: &str,
+        account: Pubkey,
+        icon: Option<String>,
+        now_ms: f64,
+    ) -> &ConnectedSite {
+        let now_ms = now_ms.max(0.0) as u64;
+        let site = self
+            .0
+            .entry(origin.to_string())
+            .or_insert_with(|| ConnectedSite {
+                origin: origin.to_string(),
+                icon: None,
+                first_connected_ms: now_ms,
+                last_used_ms: now_ms,
+                accounts: Vec::default(),
+                sign_in: None,
+            });
+
+        if !site.accounts.contains(&account) {
+            site.accounts.push(account);
+        }
+        if let Some(icon) = icon.filter(|icon| icon.len() <= ConnectedSite::MAX_ICON_LENGTH) {
+            site.icon.replace(icon);
+        }
+        site.last_used_ms = site.last_used_ms.max(now_ms);
+
+        site
+    }
+
+    /// Whether `origin` connected with `account` before, which lets it reconnect silently
+    pub fn is_trusted(&self, origin: &str, account: &Pubkey) -> bool {
+        self.get(origin)
+            .is_some_and(|site| site.accounts.contains(account))
+    }
+
+    /// Records that a request of `origin` was signed at `now_ms`
+    pub fn touch(&mut self, origin: &str, now_ms: f64) -> &mut Self {
+        if let Some(site) = self.0.get_mut(origin) {
+            site.last_used_ms = site.last_used_ms.max(now_ms.max(0.0) as u64);
+        }
+
+        self
+    }
+
+    /// Records the Sign In With Solana `session` of `origin`
+    pub fn sign_in(&mut self, origin: &str, session: SignInSession) -> &mut Self {
+        if let Some(site) = self.0.get_mut(origin) {
+            site.last_used_ms = site.last_used_ms.max(session.signed_at_ms);
+            site.sign_in.replace(session);
+        }
+
+        self
+    }
+
+    pub fn revoke(&mut self, origin: &str) -> Option<ConnectedSite> {
+        self.0.remove(origin)
+    }
+
+    /// Forgets every site, returning the origins that were connected
+    pub fn revoke_all(&mut self) -> Vec<String> {
+        core::mem::take(&mut self.0).into_keys().collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let stored = StoredConnectedSites {
+            version: Self::VERSION,
+            sites: self.iter().map(StoredConnectedSite::from).collect(),
+        };
+
+        serde_json::to_string(&stored).unwrap_or_default()
+    }
+
+    pub fn from_json(json: &str) -> AtollWalletResult<Self> {
+        let stored = serde_json::from_str::<StoredConnectedSites>(json).map_err(|error| {
+            AtollWalletError::Input(format!("The connected sites could not be read. {error}"))
+        })?;
+
+        if stored.version != Self::VERSION {
+            return Err(AtollWalletError::Input(format!(
+                "The version `{}` of the connected sites is not supported",
+                stored.version
+            )));
+        }
+
+        stored
+            .sites
+            .into_iter()
+            .map(|site| {
+                let site = ConnectedSite::try_from(site)?;
+
+                Ok((site.origin.clone(), site))
+            })
+            .collect::<AtollWalletResult<BTreeMap<String, ConnectedSite>>>()
+            .map(Self)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredConnectedSites {
+    version: u16,
+    sites: Vec<StoredConnectedSite>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredConnectedSite {
+    origin: String,
+    icon: Option<String>,
+    first_connected_ms: u64,
+    last_used_ms: u64,
+    /// Base58 addresses
+    accounts: Vec<String>,
+    sign_in: Option<StoredSignInSession>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredSignInSession {
+    account: String,
+    domain: Option<String>,
+    chain_id: Option<String>,
+    nonce: Option<String>,
+    issued_at: Option<String>,
+    expiration_time: Option<String>,
+    signed_at_ms: u64,
+}
+
+impl From<&ConnectedSite> for StoredConnectedSite {
+    fn from(site: &ConnectedSite) -> Self {
+        Self {
+            origin: site.origin.clone(),
+            icon: site.icon.clone(),
+            first_connected_ms: site.first_connected_ms,
+            last_used_ms: site.last_used_ms,
+            accounts: site.accounts.iter().map(ToString::to_string).collect(),
+            sign_in: site.sign_in.as_ref().map(|session| StoredSignInSession {
+                account: session.account.to_string(),
+                domain: session.domain.clone(),
+                chain_id: session.chain_id.clone(),
+                nonce: session.nonce.clone(),
+                issued_at: session.issued_at.clone(),
+                expiration_time: session.expiration_time.clone(),
+                signed_at_ms: session.signed_at_ms,
+            }),
+        }
+    }
+}
+
+impl TryFrom<StoredConnectedSite> for ConnectedSite {
+    type Error = AtollWalletError;
+
+    fn try_from(stored: StoredConnectedSite) -> Result<Self, Self::Error> {
+        let parse_address = |address: &str| {
+            Pubkey::from_str(address).or(Err(AtollWalletError::Input(format!(
+                "`{address}` is not a valid Solana address"
+            ))))
+        };
+
+        let sign_in = stored
+            .sign_in
+            .map(|session| {
+                Ok::<_, AtollWalletError>(SignInSession {
+                    account: parse_address(&session.account)?,
+                    domain: session.domain,
+                    chain_id: session.chain_id,
+                    nonce: session.nonce,
+                    issued_at: session.issued_at,
+                    expiration_time: session.expiration_time,
+                    signed_at_ms: session.signed_at_ms,
+                })
+            })
+            .transpose()?;
+
+        Ok(Self {
+            origin: stored.origin,
+            icon: stored.icon,
+            first_connected_ms: stored.first_connected_ms,
+            last_used_ms: stored.last_used_ms,
+            accounts: stored
+                .accounts
+                .iter()
+                .map(|account| parse_address(account))
+                .collect::<AtollWalletResult<Vec<Pubkey>>>()?,
+            sign_in,
+        })
+    }
+}
@@ -0,0 +1,83 @@
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+
+use crate::{SolanaActivityRow, SolanaComputeBudget, SolanaTransferBuilder};
+
+/// The lamports a transaction takes from an account, decoded from its instructions
+pub struct SolanaSpending;
+
+impl SolanaSpending {
+    /// The most lamports `message` can take from `account`: the fees if it pays them and the
+    /// System program transfers and account creations it funds.
+    ///
+    /// `None` if the spending cannot be bounded, which is when an instruction other than
+    /// those, a memo or a `ComputeBudget` instruction is given the account, since a program
+    /// given a signer or writable account can move its lamports or tokens.
+    pub fn of_message(message: &Message, account: &Pubkey) -> Option<u64> {
+        let mut lamports = 0u64;
+
+        if message.account_keys.first() == Some(account) {
+            lamports = lamports.checked_add(Self::fee(message)?)?;
+        }
+
+        for instruction in &message.instructions {
+            let program_id = message
+                .account_keys
+                .get(instruction.program_id_index as usize)?;
+            let accounts = instruction
+                .accounts
+                .iter()
+                .map(|index| message.account_keys.get(*index as usize).copied())
+                .collect::<Option<Vec<Pubkey>>>()?;
+
+            let spent = match SolanaActivityRow::parse(program_id, &accounts, &instruction.data) {
+                None | Some(SolanaActivityRow::Memo(_)) => 0,
+                Some(SolanaActivityRow::SolTransfer {
+                    from,
+                    lamports: transferred,
+                    ..
+                }) => {
+                    if from == *account {
+                        transferred
+                    } else {
+                        0
+                    }
+                }
+                Some(SolanaActivityRow::CreateAccount {
+                    funder,
+                    lamports: funded,
+                    ..
+                }) => {
+                    if funder == *account {
+                        funded
+                    } else {
+                        0
+                    }
+                }
+                Some(_) if accounts.contains(account) || program_id == account => return None,
+                Some(_) => 0,
+            };
+
+            lamports = lamports.checked_add(spent)?;
+        }
+
+        Some(lamports)
+    }
+
+    /// The signature fees and the most priority fee of `message`, taking the most compute
+    /// units a transaction can use when it does not set a limit
+    fn fee(message: &Message) -> Option<u64> {
+        let instructions = SolanaComputeBudget::decompile(message).ok()?;
+        let budget = SolanaComputeBudget::from_instructions(&instructions);
+
+        let priority_fee = (budget.unit_price.unwrap_or_default() as u128
+            * budget
+                .unit_limit
+                .unwrap_or(SolanaComputeBudget::MAX_COMPUTE_UNIT_LIMIT) as u128)
+            .div_ceil(1_000_000);
+
+        (message.header.num_required_signatures as u64)
+            .checked_mul(SolanaTransferBuilder::LAMPORTS_PER_SIGNATURE)?
+            .checked_add(u64::try_from(priority_fee).ok()?)
+    }
+}
@@ -0,0 +1,77 @@
+use serde::{Deserialize, Deserializer};
+
+use crate::{AtollWalletError, AtollWalletResult, ExtensionMessage};
+
+/// The envelope of a request in the shape `{ version, id, method, origin, params }`
+/// without the `params`, which are decoded for the method once it is known
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ProtocolHeader {
+    pub version: u16,
+    /// Chosen by the sender to match the response to the request
+    pub id: String,
+    pub method: ExtensionMessage,
+    /// The origin the sender claims to act for, checked against the runtime sender
+    pub origin: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawProtocolHeader {
+    version: u16,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    origin: Option<String>,
+}
+
+impl ProtocolHeader {
+    /// The version of the protocol the background speaks
+    pub const VERSION: u16 = 1;
+    /// Prefixed to a method by the content script to name the message relaying the response
+    /// back to the page
+    pub const RELAY_PREFIX: &str = "relay:";
+
+    /// Decodes the header of a request, checking the version before the rest of the envelope
+    pub fn decode<'de, D: Deserializer<'de>>(deserializer: D) -> AtollWalletResult<Self> {
+        let raw = RawProtocolHeader::deserialize(deserializer)
+            .map_err(|error| AtollWalletError::InvalidRequest(error.to_string()))?;
+
+        if raw.version != Self::VERSION {
+            return Err(AtollWalletError::UnsupportedProtocolVersion(raw.version));
+        }
+
+        let id = raw
+            .id
+            .filter(|id| !id.is_empty())
+            .ok_or(AtollWalletError::InvalidRequest(
+                "The request has no `id`".to_string(),
+            ))?;
+        let method = raw.method.ok_or(AtollWalletError::InvalidRequest(
+            "The request has no `method`".to_string(),
+        ))?;
+
+        Ok(Self {
+            version: raw.version,
+            id,
+            method: method.as_str().try_into()?,
+            origin: raw.origin,
+        })
+    }
+
+    /// The origin the request acts for. The runtime knows which page sent the message,
+    /// so a request cannot claim to act for another origin.
+    pub fn resolve_origin(
+        &self,
+        sender_origin: Option<String>,
+    ) -> AtollWalletResult<Option<String>> {
+        match (sender_origin, self.origin.clone()) {
+            (Some(sender), Some(claimed)) if sender != claimed => {
+                Err(AtollWalletError::InvalidRequest(format!(
+                    "The origin `{claimed}` of the request does not match the sender `{sender}`"
+                )))
+            }
+            (sender, claimed) => Ok(sender.or(claimed)),
+        }
+    }
+}
@@ -0,0 +1,251 @@
+use crate::{AtollConstants, AtollWalletError, BitcoinConstants, SolanaConstants};
+
+/// The methods the background handles, from wallet-standard features
+/// relayed by the content script and from the extension pages
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum ExtensionMessage {
+    StandardConnect,
+    SolanaSignIn,
+    SolanaSignMessage,
+    SolanaSignTransaction,
+    SolanaSignAndSendTransaction,
+    BitcoinConnect,
+    BitcoinSignMessage,
+    BitcoinSignTransaction,
+    BitcoinSignAndSendTransaction,
+    VaultStatus,
+    CreateVault,
+    RestoreVault,
+    UnlockVault,
+    LockVault,
+    VaultSplitShares,
+    VaultRecoverFromShares,
+    ListAccounts,
+    AddWatchOnlyAccount,
+    RemoveWatchOnlyAccount,
+    SetActiveAccount,
+    BitcoinBalance,
+    BitcoinEstimateSend,
+    BitcoinSend,
+    SolanaPortfolio,
+    SolanaEstimateSend,
+    SolanaSend,
+    SolanaPriorityFees,
+    SetSolanaPriorityFee,
+    SolanaActivity,
+    SolanaPreviewMessage,
+    ListDappPolicies,
+    SetDappPolicy,
+    CheckDomain,
+    UpdateDomainList,
+    SetDomainPolicy,
+    ListConnectedSites,
+    RevokeSite,
+    RevokeAllSites,
+}
+
+impl ExtensionMessage {
+    pub const ALL: &[Self] = &[
+        Self::StandardConnect,
+        Self::SolanaSignIn,
+        Self::SolanaSignMessage,
+        Self::SolanaSignTransaction,
+        Self::SolanaSignAndSendTransaction,
+        Self::BitcoinConnect,
+        Self::BitcoinSignMessage,
+        Self::BitcoinSignTransaction,
+        Self::BitcoinSignAndSendTransaction,
+        Self::VaultStatus,
+        Self::CreateVault,
+        Self::RestoreVault,
+        Self::UnlockVault,
+        Self::LockVault,
+        Self::VaultSplitShares,
+        Self::VaultRecoverFromShares,
+        Self::ListAccounts,
+        Self::AddWatchOnlyAccount,
+        Self::RemoveWatchOnlyAccount,
+        Self::SetActiveAccount,
+        Self::BitcoinBalance,
+        Self::BitcoinEstimateSend,
+        Self::BitcoinSend,
+        Self::SolanaPortfolio,
+        Self::SolanaEstimateSend,
+        Self::SolanaSend,
+        Self::SolanaPriorityFees,
+        Self::SetSolanaPriorityFee,
+        Self::SolanaActivity,
+        Self::SolanaPreviewMessage,
+        Self::ListDappPolicies,
+        Self::SetDappPolicy,
+        Self::CheckDomain,
+        Self::UpdateDomainList,
+        Self::SetDomainPolicy,
+        Self::ListConnectedSites,
+        Self::RevokeSite,
+        Self::RevokeAllSites,
+    ];
+
+    /// The method as sent in the `method` of a request
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::StandardConnect => SolanaConstants::STANDARD_CONNECT,
+            Self::SolanaSignIn => SolanaConstants::SIGN_IN,
+            Self::SolanaSignMessage => SolanaConstants::SIGN_MESSAGE,
+            Self::SolanaSignTransaction => SolanaConstants::SIGN_TRANSACTION,
+            Self::SolanaSignAndSendTransaction => SolanaConstants::SIGN_AND_SEND_TRANSACTION,
+            Self::BitcoinConnect => BitcoinConstants::CONNECT,
+            Self::BitcoinSignMessage => BitcoinConstants::SIGN_MESSAGE,
+            Self::BitcoinSignTransaction => BitcoinConstants::SIGN_TRANSACTION,
+            Self::BitcoinSignAndSendTransaction => BitcoinConstants::SIGN_AND_SEND_TRANSACTION,
+            Self::VaultStatus => AtollConstants::VAULT_STATUS,
+            Self::CreateVault => AtollConstants::CREATE_VAULT,
+            Self::RestoreVault => AtollConstants::RESTORE_VAULT,
+            Self::UnlockVault => AtollConstants::UNLOCK_VAULT,
+            Self::LockVault => AtollConstants::LOCK_VAULT,
+            Self::VaultSplitShares => AtollConstants::VAULT_SPLIT_SHARES,
+            Self::VaultRecoverFromShares => AtollConstants::VAULT_RECOVER_FROM_SHARES,
+            Self::ListAccounts => AtollConstants::LIST_ACCOUNTS,
+            Self::AddWatchOnlyAccount => AtollConstants::ADD_WATCH_ONLY_ACCOUNT,
+            Self::RemoveWatchOnlyAccount => AtollConstants::REMOVE_WATCH_ONLY_ACCOUNT,
+            Self::SetActiveAccount => AtollConstants::SET_ACTIVE_ACCOUNT,
+            Self::BitcoinBalance => AtollConstants::BITCOIN_BALANCE,
+            Self::BitcoinEstimateSend => AtollConstants::BITCOIN_ESTIMATE_SEND,
+            Self::BitcoinSend => AtollConstants::BITCOIN_SEND,
+            Self::SolanaPortfolio => AtollConstants::SOLANA_PORTFOLIO,
+            Self::SolanaEstimateSend => AtollConstants::SOLANA_ESTIMATE_SEND,
+            Self::SolanaSend => AtollConstants::SOLANA_SEND,
+            Self::SolanaPriorityFees => AtollConstants::SOLANA_PRIORITY_FEES,
+            Self::SetSolanaPriorityFee => AtollConstants::SET_SOLANA_PRIORITY_FEE,
+            Self::SolanaActivity => AtollConstants::SOLANA_ACTIVITY,
+            Self::SolanaPreviewMessage => AtollConstants::SOLANA_PREVIEW_MESSAGE,
+            Self::ListDappPolicies => AtollConstants::LIST_DAPP_POLICIES,
+            Self::SetDappPolicy => AtollConstants::SET_DAPP_POLICY,
+            Self::CheckDomain => AtollConstants::CHECK_DOMAIN,
+            Self::UpdateDomainList => AtollConstants::UPDATE_DOMAIN_LIST,
+            Self::SetDomainPolicy => AtollConstants::SET_DOMAIN_POLICY,
+            Self::ListConnectedSites => AtollConstants::LIST_CONNECTED_SITES,
+            Self::RevokeSite => AtollConstants::REVOKE_SITE,
+            Self::RevokeAllSites => AtollConstants::REVOKE_ALL_SITES,
+        }
+    }
+
+    /// The key of the method in the `methods` given to the content script
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::StandardConnect => "standardConnect",
+            Self::SolanaSignIn => "solanaSignIn",
+            Self::SolanaSignMessage => "solanaSignMessage",
+            Self::SolanaSignTransaction => "solanaSignTransaction",
+            Self::SolanaSignAndSendTransaction => "solanaSignAndSendTransaction",
+            Self::BitcoinConnect => "bitcoinConnect",
+            Self::BitcoinSignMessage => "bitcoinSignMessage",
+            Self::BitcoinSignTransaction => "bitcoinSignTransaction",
+            Self::BitcoinSignAndSendTransaction => "bitcoinSignAndSendTransaction",
+            Self::VaultStatus => "vaultStatus",
+            Self::CreateVault => "createVault",
+            Self::RestoreVault => "restoreVault",
+            Self::UnlockVault => "unlockVault",
+            Self::LockVault => "lockVault",
+            Self::VaultSplitShares => "vaultSplitShares",
+            Self::VaultRecoverFromShares => "vaultRecoverFromShares",
+            Self::ListAccounts => "listAccounts",
+            Self::AddWatchOnlyAccount => "addWatchOnlyAccount",
+            Self::RemoveWatchOnlyAccount => "removeWatchOnlyAccount",
+            Self::SetActiveAccount => "setActiveAccount",
+            Self::BitcoinBalance => "bitcoinBalance",
+            Self::BitcoinEstimateSend => "bitcoinEstimateSend",
+            Self::BitcoinSend => "bitcoinSend",
+            Self::SolanaPortfolio => "solanaPortfolio",
+            Self::SolanaEstimateSend => "solanaEstimateSend",
+            Self::SolanaSend => "solanaSend",
+            Self::SolanaPriorityFees => "solanaPriorityFees",
+            Self::SetSolanaPriorityFee => "setSolanaPriorityFee",
+            Self::SolanaActivity => "solanaActivity",
+            Self::SolanaPreviewMessage => "solanaPreviewMessage",
+            Self::ListDappPolicies => "listDappPolicies",
+            Self::SetDappPolicy => "setDappPolicy",
+            Self::CheckDomain => "checkDomain",
+            Self::UpdateDomainList => "updateDomainList",
+            Self::SetDomainPolicy => "setDomainPolicy",
+            Self::ListConnectedSites => "listConnectedSites",
+            Self::RevokeSite => "revokeSite",
+            Self::RevokeAllSites => "revokeAllSites",
+        }
+    }
+
+    /// Whether the method is a request of a dapp to connect or sign, which the phishing
+    /// protection screens by its origin
+    pub fn is_dapp_request(&self) -> bool {
+        matches!(
+            self,
+            Self::StandardConnect
+                | Self::SolanaSignIn
+                | Self::SolanaSignMessage
+                | Self::SolanaSignTransaction
+                | Self::SolanaSignAndSendTransaction
+                | Self::BitcoinConnect
+                | Self::BitcoinSignMessage
+                | Self::BitcoinSignTransaction
+                | Self::BitcoinSignAndSendTransaction
+        )
+    }
+
+    /// Whether the method lists the accounts, permissions or activity of the wallet, changes
+    /// the wallet or its settings or moves funds, so only the pages of the extension itself,
+    /// like the popup, can call it
+    pub fn is_popup_only(&self) -> bool {
+        matches!(
+            self,
+            Self::VaultStatus
+                | Self::CreateVault
+                | Self::RestoreVault
+                | Self::UnlockVault
+                | Self::LockVault
+                | Self::VaultSplitShares
+                | Self::VaultRecoverFromShares
+                | Self::SetDappPolicy
+                | Self::UpdateDomainList
+                | Self::SetDomainPolicy
+                | Self::ListConnectedSites
+                | Self::RevokeSite
+                | Self::RevokeAllSites
+                | Self::ListAccounts
+                | Self::AddWatchOnlyAccount
+                | Self::RemoveWatchOnlyAccount
+                | Self::SetActiveAccount
+                | Self::BitcoinSend
+                | Self::SolanaSend
+                | Self::SetSolanaPriorityFee
+                | Self::SolanaActivity
+                | Self::ListDappPolicies
+        )
+    }
+
+    /// Whether the params carry secrets such as shares, passwords or passphrases and must
+    /// not be logged
+    pub fn is_sensitive(&self) -> bool {
+        matches!(
+            self,
+            Self::CreateVault
+                | Self::RestoreVault
+                | Self::UnlockVault
+                | Self::VaultSplitShares
+                | Self::VaultRecoverFromShares
+        )
+    }
+}
+
+impl TryFrom<&str> for ExtensionMessage {
+    type Error = AtollWalletError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .iter()
+            .find(|method| method.as_str() == value)
+            .copied()
+            .ok_or(AtollWalletError::UnsupportedExtensionMessage(
+                value.to_string(),
+            ))
+    }
+}
@@ -0,0 +1,14 @@
+mod method;
+pub use method::*;
+
+mod header;
+pub use header::*;
+
+mod params;
+pub use params::*;
+
+mod request;
+pub use request::*;
+
+mod value;
+pub use value::*;
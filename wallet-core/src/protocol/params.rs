@@ -0,0 +1,246 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, de::Error};
+use serde_bytes::ByteBuf;
+use solana_pubkey::Pubkey;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, DappAutoApprove, DappFeature, DappPolicy, DomainAction,
+    DomainReputationPolicy, SolanaCluster,
+};
+
+/// An Ed25519 public key sent as a `Uint8Array` of 32 bytes
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PublicKeyParams(pub [u8; 32]);
+
+impl<'de> Deserialize<'de> for PublicKeyParams {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = ByteBuf::deserialize(deserializer)?;
+        let length = bytes.len();
+
+        bytes
+            .into_vec()
+            .try_into()
+            .map(Self)
+            .map_err(|_| D::Error::invalid_length(length, &"a public key of 32 bytes"))
+    }
+}
+
+/// The wallet-standard account of a Solana request
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolanaAccountParams {
+    pub public_key: PublicKeyParams,
+}
+
+/// The wallet-standard account of a Bitcoin request
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct BitcoinAccountParams {
+    pub address: String,
+}
+
+/// A whole number sent as a number or, past `Number.MAX_SAFE_INTEGER`, as a string
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WholeNumberParams(pub u64);
+
+impl<'de> Deserialize<'de> for WholeNumberParams {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Number(f64),
+            String(String),
+        }
+
+        let expected = "a whole number as a number or string";
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Number(number)
+                if number.fract() == 0.0 && number >= 0.0 && number <= u64::MAX as f64 =>
+            {
+                Ok(Self(number as u64))
+            }
+            Raw::Number(number) => Err(D::Error::custom(format!("`{number}` is not {expected}"))),
+            Raw::String(string) => string
+                .trim()
+                .parse::<u64>()
+                .map(Self)
+                .map_err(|_| D::Error::custom(format!("`{string}` is not {expected}"))),
+        }
+    }
+}
+
+/// The input of `standard:connect`, the `StandardConnectInput` of the wallet-standard
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandardConnectParams {
+    /// Connects only an origin that connected before, without asking the user. Other
+    /// origins get no accounts. Also read from `onlyIfTrusted` as wallet adapters send it.
+    #[serde(default, alias = "onlyIfTrusted")]
+    pub silent: bool,
+    /// The icon of the page, kept with the [ConnectedSite](crate::ConnectedSite)
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+/// The input of `atoll:listConnectedSites`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Deserialize)]
+pub struct ConnectedSitesParams {
+    /// Lists only the sites connected with this base58 address
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+impl ConnectedSitesParams {
+    pub fn account(&self) -> AtollWalletResult<Option<Pubkey>> {
+        self.account.as_deref().map(parse_address).transpose()
+    }
+}
+
+/// The input of `atoll:revokeSite`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct RevokeSiteParams {
+    pub origin: String,
+}
+
+/// The input of `atoll:setDappPolicy`. The fields left out keep their value, an
+/// `autoApprove` of `null` removes the rule and the policy of an origin that never
+/// connected needs its `accounts`.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DappPolicyParams {
+    pub origin: String,
+    /// Base58 addresses
+    #[serde(default)]
+    pub accounts: Option<Vec<String>>,
+    /// Chains such as `solana:devnet`
+    #[serde(default)]
+    pub chains: Option<Vec<String>>,
+    /// `signIn`, `signMessage` or `transactions`
+    #[serde(default)]
+    pub features: Option<Vec<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub auto_approve: Option<Option<DappAutoApproveParams>>,
+}
+
+/// The `autoApprove` of [DappPolicyParams]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DappAutoApproveParams {
+    pub lamports_per_day: WholeNumberParams,
+}
+
+impl DappPolicyParams {
+    /// Applies the params to the `existing` policy of the origin
+    pub fn into_policy(self, existing: Option<DappPolicy>) -> AtollWalletResult<DappPolicy> {
+        let mut policy = match (existing, self.accounts.as_deref()) {
+            (Some(existing), _) => existing,
+            (None, Some([first, ..])) => DappPolicy::new(&self.origin, parse_address(first)?),
+            (None, _) => {
+                return Err(AtollWalletError::InvalidParams(format!(
+                    "`{}` has no policy yet so `accounts` are required",
+                    self.origin
+                )));
+            }
+        };
+
+        if let Some(accounts) = self.accounts {
+            policy = policy.set_accounts(
+                accounts
+                    .iter()
+                    .map(|account| parse_address(account))
+                    .collect::<AtollWalletResult<Vec<Pubkey>>>()?,
+            );
+        }
+
+        if let Some(chains) = self.chains {
+            policy = policy.set_chains(
+                chains
+                    .iter()
+                    .map(|chain| SolanaCluster::from(chain.as_str()))
+                    .collect(),
+            );
+        }
+
+        if let Some(features) = self.features {
+            policy = policy.set_features(
+                features
+                    .iter()
+                    .map(|feature| DappFeature::try_from(feature.as_str()))
+                    .collect::<AtollWalletResult<Vec<DappFeature>>>()?,
+            );
+        }
+
+        if let Some(auto_approve) = self.auto_approve {
+            policy = policy.set_auto_approve(
+                auto_approve
+                    .map(|auto_approve| DappAutoApprove::new(auto_approve.lamports_per_day.0)),
+            );
+        }
+
+        Ok(policy)
+    }
+}
+
+/// The input of `atoll:checkDomain`
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct DomainCheckParams {
+    pub origin: String,
+}
+
+/// The input of `atoll:updateDomainList`, a signed list file as described by
+/// [DomainList](crate::DomainList)
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+pub struct DomainListParams {
+    pub file: String,
+}
+
+/// The input of `atoll:setDomainPolicy`. The actions are `allow`, `warn` or `block` and
+/// the fields left out keep their value.
+#[derive(Debug, PartialEq, Eq, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DomainReputationPolicyParams {
+    #[serde(default)]
+    pub blocklisted: Option<String>,
+    #[serde(default)]
+    pub lookalike: Option<String>,
+    #[serde(default)]
+    pub newly_seen: Option<String>,
+    #[serde(default)]
+    pub newly_seen_days: Option<WholeNumberParams>,
+}
+
+impl DomainReputationPolicyParams {
+    /// Applies the params to the `existing` policy
+    pub fn into_policy(
+        self,
+        existing: DomainReputationPolicy,
+    ) -> AtollWalletResult<DomainReputationPolicy> {
+        let action = |action: Option<String>, existing: DomainAction| match action {
+            Some(action) => DomainAction::try_from(action.as_str()),
+            None => Ok(existing),
+        };
+
+        Ok(DomainReputationPolicy {
+            blocklisted: action(self.blocklisted, existing.blocklisted)?,
+            lookalike: action(self.lookalike, existing.lookalike)?,
+            newly_seen: action(self.newly_seen, existing.newly_seen)?,
+            newly_seen_days: self
+                .newly_seen_days
+                .map_or(existing.newly_seen_days, |days| days.0),
+        })
+    }
+}
+
+fn parse_address(address: &str) -> AtollWalletResult<Pubkey> {
+    Pubkey::from_str(address.trim()).or(Err(AtollWalletError::InvalidParams(format!(
+        "`{address}` is not a valid Solana address"
+    ))))
+}
+
+/// Tells a field set to `null` apart from a field left out
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
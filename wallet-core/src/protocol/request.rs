@@ -0,0 +1,127 @@
+use serde::de::DeserializeOwned;
+
+use crate::{
+    AtollWalletError, AtollWalletErrorCategory, AtollWalletResult, ProtocolHeader, ProtocolValue,
+};
+
+/// The `params` of a request, deserialized once the method is known. The extension reads
+/// them from the `JsValue` of the message and the tests from JSON.
+pub trait ProtocolParams {
+    /// Whether the request left the params out
+    fn is_missing(&self) -> bool;
+
+    /// Deserializes the params, describing why they do not fit `T` otherwise
+    fn deserialize<T: DeserializeOwned>(&self) -> Result<T, String>;
+}
+
+impl ProtocolParams for serde_json::Value {
+    fn is_missing(&self) -> bool {
+        self.is_null()
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self) -> Result<T, String> {
+        T::deserialize(self).map_err(|error| error.to_string())
+    }
+}
+
+/// A request to the background in the shape `{ version, id, method, origin, params }`
+#[derive(Debug, Clone)]
+pub struct ProtocolRequest<P: ProtocolParams> {
+    pub header: ProtocolHeader,
+    pub params: P,
+}
+
+impl ProtocolRequest<serde_json::Value> {
+    /// Decodes a request sent as JSON, checking the [ProtocolHeader] before reading the params
+    pub fn from_json(value: &serde_json::Value) -> AtollWalletResult<Self> {
+        Ok(Self {
+            header: ProtocolHeader::decode(value)?,
+            params: value
+                .get("params")
+                .cloned()
+                .unwrap_or(serde_json::Value::Null),
+        })
+    }
+}
+
+impl<P: ProtocolParams> ProtocolRequest<P> {
+    /// Deserializes the params of the method
+    pub fn params<T: DeserializeOwned>(&self) -> AtollWalletResult<T> {
+        self.params.deserialize::<T>().map_err(|error| {
+            AtollWalletError::InvalidParams(format!(
+                "Invalid params for `{}`. {error}",
+                self.header.method.as_str()
+            ))
+        })
+    }
+
+    /// Deserializes the params of a method whose params are all optional, so they may be
+    /// left out
+    pub fn params_or_default<T: DeserializeOwned + Default>(&self) -> AtollWalletResult<T> {
+        if self.params.is_missing() {
+            return Ok(T::default());
+        }
+
+        self.params()
+    }
+}
+
+/// The error of a response in the shape `{ code, message, data: { category } }`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ProtocolError {
+    pub code: i32,
+    pub message: String,
+    pub category: AtollWalletErrorCategory,
+}
+
+impl ProtocolError {
+    pub fn to_protocol_value(&self) -> ProtocolValue {
+        let mut data = ProtocolValue::new_object();
+        data.set("category", self.category.as_str());
+
+        let mut output = ProtocolValue::new_object();
+        output
+            .set("code", self.code)
+            .set("message", self.message.as_str())
+            .set("data", data);
+
+        output
+    }
+}
+
+impl From<&AtollWalletError> for ProtocolError {
+    fn from(error: &AtollWalletError) -> Self {
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+            category: error.category(),
+        }
+    }
+}
+
+/// Builds responses in the shape `{ version, id, ok }` or `{ version, id, err }`
+pub struct ProtocolResponse;
+
+impl ProtocolResponse {
+    pub fn ok(id: &str, value: ProtocolValue) -> ProtocolValue {
+        let mut output = Self::envelope(Some(id));
+        output.set("ok", value);
+
+        output
+    }
+
+    /// `id` is `None` when the request was too malformed to read it
+    pub fn err(id: Option<&str>, error: &AtollWalletError) -> ProtocolValue {
+        let mut output = Self::envelope(id);
+        output.set("err", ProtocolError::from(error).to_protocol_value());
+
+        output
+    }
+
+    fn envelope(id: Option<&str>) -> ProtocolValue {
+        let mut output = ProtocolValue::new_object();
+        output.set("version", ProtocolHeader::VERSION).set("id", id);
+
+        output
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{
+    Serialize, Serializer,
+    ser::{SerializeMap, SerializeSeq},
+};
+
+/// The output of a method and the envelope of a response, built by the background and
+/// serialized to a JavaScript value by the extension or to JSON by the tests.
+///
+/// Bytes are serialized with `serialize_bytes` so that the extension gives them to the
+/// page as a `Uint8Array`, while JSON has them as an array of numbers.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum ProtocolValue {
+    #[default]
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Array(Vec<ProtocolValue>),
+    /// The keys in the order they were set
+    Object(Vec<(String, ProtocolValue)>),
+}
+
+impl ProtocolValue {
+    pub fn new_object() -> Self {
+        Self::Object(Vec::default())
+    }
+
+    pub fn new_array() -> Self {
+        Self::Array(Vec::default())
+    }
+
+    pub fn new_bytes(bytes: &[u8]) -> Self {
+        Self::Bytes(bytes.to_vec())
+    }
+
+    /// An array of strings
+    pub fn new_str_array(values: &[&str]) -> Self {
+        values.iter().map(|value| Self::from(*value)).collect()
+    }
+
+    /// Sets `key` of an object, replacing the value it had. Setting a key of a value that
+    /// is not an object does nothing.
+    pub fn set(&mut self, key: &str, value: impl Into<Self>) -> &mut Self {
+        if let Self::Object(entries) = self {
+            let value = value.into();
+
+            match entries.iter_mut().find(|(existing, _)| existing == key) {
+                Some((_, existing)) => *existing = value,
+                None => entries.push((key.to_string(), value)),
+            }
+        }
+
+        self
+    }
+
+    /// Pushes to an array. Pushing to a value that is not an array does nothing.
+    pub fn push(&mut self, value: impl Into<Self>) -> &mut Self {
+        if let Self::Array(values) = self {
+            values.push(value.into());
+        }
+
+        self
+    }
+
+    /// The value of `key` of an object
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find(|(existing, _)| existing == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Self::Null
+    }
+}
+
+impl Serialize for ProtocolValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        /// `Number.MAX_SAFE_INTEGER`, whole numbers up to it are serialized as integers
+        const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+
+        match self {
+            Self::Null => serializer.serialize_unit(),
+            Self::Bool(value) => serializer.serialize_bool(*value),
+            Self::Number(value) if value.fract() == 0.0 && value.abs() <= MAX_SAFE_INTEGER => {
+                serializer.serialize_i64(*value as i64)
+            }
+            Self::Number(value) => serializer.serialize_f64(*value),
+            Self::String(value) => serializer.serialize_str(value),
+            Self::Bytes(value) => serializer.serialize_bytes(value),
+            Self::Array(values) => {
+                let mut sequence = serializer.serialize_seq(Some(values.len()))?;
+                values
+                    .iter()
+                    .try_for_each(|value| sequence.serialize_element(value))?;
+
+                sequence.end()
+            }
+            Self::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                entries
+                    .iter()
+                    .try_for_each(|(key, value)| map.serialize_entry(key, value))?;
+
+                map.end()
+            }
+        }
+    }
+}
+
+impl From<bool> for ProtocolValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for ProtocolValue {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<u32> for ProtocolValue {
+    fn from(value: u32) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<i32> for ProtocolValue {
+    fn from(value: i32) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<u16> for ProtocolValue {
+    fn from(value: u16) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<u8> for ProtocolValue {
+    fn from(value: u8) -> Self {
+        Self::Number(value.into())
+    }
+}
+
+impl From<&str> for ProtocolValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<String> for ProtocolValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<T: Into<ProtocolValue>> From<Option<T>> for ProtocolValue {
+    /// `None` is `null`
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or_default()
+    }
+}
+
+impl FromIterator<ProtocolValue> for ProtocolValue {
+    fn from_iter<I: IntoIterator<Item = ProtocolValue>>(iter: I) -> Self {
+        Self::Array(iter.into_iter().collect())
+    }
+}
+
+/// Converts a type to the value sent to the extension pages and dapps
+pub trait ToProtocolValue {
+    fn to_protocol_value(&self) -> ProtocolValue;
+}
+
+/// Converts a type that is described from the point of view of the account `owner`, for
+/// example the direction of a transfer
+pub trait ToProtocolValueFor {
+    fn to_protocol_value(&self, owner: &solana_pubkey::Pubkey) -> ProtocolValue;
+}
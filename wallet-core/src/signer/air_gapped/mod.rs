@@ -0,0 +1,93 @@
+mod ur;
+pub use ur::*;
+
+use solana_derivation_path::DerivationPath;
+use solana_pubkey::Pubkey;
+
+use crate::{AtollWalletError, SignerFuture, SignerKind, WalletSigner};
+
+/// What the offline device is asked to sign
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum AirGappedSignType {
+    /// The serialized message of a transaction
+    #[default]
+    Transaction,
+    /// Arbitrary message bytes
+    Message,
+}
+
+/// A request shown to an offline device, usually as an animated QR code
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AirGappedSignRequest {
+    pub sign_data: Vec<u8>,
+    pub derivation_path: DerivationPath,
+    pub public_key: Pubkey,
+    pub sign_type: AirGappedSignType,
+}
+
+/// Carries a sign request to an offline device and waits for the device to return a signature,
+/// for example by displaying a QR code and then scanning the one shown by the device.
+pub trait SignatureRelay {
+    fn request_signature<'a>(&'a self, request: AirGappedSignRequest) -> SignerFuture<'a>;
+}
+
+/// An account whose secret key lives on an offline device
+pub struct AirGappedSigner<R: SignatureRelay> {
+    relay: R,
+    derivation_path: DerivationPath,
+    public_key: Pubkey,
+}
+
+impl<R: SignatureRelay> AirGappedSigner<R> {
+    pub fn new(relay: R, derivation_path: DerivationPath, public_key: Pubkey) -> Self {
+        Self {
+            relay,
+            derivation_path,
+            public_key,
+        }
+    }
+
+    pub fn derivation_path(&self) -> &DerivationPath {
+        &self.derivation_path
+    }
+
+    fn request(&self, sign_data: &[u8], sign_type: AirGappedSignType) -> SignerFuture<'_> {
+        let request = AirGappedSignRequest {
+            sign_data: sign_data.to_vec(),
+            derivation_path: self.derivation_path.clone(),
+            public_key: self.public_key,
+            sign_type,
+        };
+
+        Box::pin(async move {
+            let signature = self.relay.request_signature(request.clone()).await?;
+
+            if !signature.verify(self.public_key.as_ref(), &request.sign_data) {
+                return Err(AtollWalletError::AirGapped(
+                    "The signature returned by the offline device is not valid for this request"
+                        .to_string(),
+                ));
+            }
+
+            Ok(signature)
+        })
+    }
+}
+
+impl<R: SignatureRelay> WalletSigner for AirGappedSigner<R> {
+    fn pubkey(&self) -> Pubkey {
+        self.public_key
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::AirGapped
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignerFuture<'a> {
+        self.request(message, AirGappedSignType::Message)
+    }
+
+    fn sign_transaction_message<'a>(&'a self, message_data: &'a [u8]) -> SignerFuture<'a> {
+        self.request(message_data, AirGappedSignType::Transaction)
+    }
+}
@@ -0,0 +1,486 @@
+use minicbor::{Decoder, Encoder, data::Tag};
+use solana_derivation_path::DerivationPath;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+
+use crate::{AirGappedSignRequest, AirGappedSignType, AtollWalletError, AtollWalletResult};
+
+type CborEncodeError = minicbor::encode::Error<core::convert::Infallible>;
+
+/// The UR registry types and CBOR tags used by offline Solana signers
+pub struct UrRegistry;
+
+impl UrRegistry {
+    pub const SOL_SIGN_REQUEST: &str = "sol-sign-request";
+    pub const SOL_SIGNATURE: &str = "sol-signature";
+    pub const TAG_UUID: u64 = 37;
+    pub const TAG_KEYPATH: u64 = 304;
+    pub const TAG_SOL_SIGN_REQUEST: u64 = 1101;
+    pub const TAG_SOL_SIGNATURE: u64 = 1102;
+    /// The largest fragment carried by a single part of an animated QR code
+    pub const DEFAULT_MAX_FRAGMENT_LENGTH: usize = 200;
+    const HARDENED_BIT: u32 = 0x8000_0000;
+}
+
+/// A `sol-sign-request` shown to an offline device
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SolSignRequest {
+    request_id: [u8; 16],
+    sign_data: Vec<u8>,
+    derivation_path: DerivationPath,
+    master_fingerprint: Option<u32>,
+    address: Option<Pubkey>,
+    origin: Option<String>,
+    sign_type: AirGappedSignType,
+}
+
+impl SolSignRequest {
+    /// Builds a request with a random version 4 UUID as its request ID
+    pub fn new(request: &AirGappedSignRequest) -> AtollWalletResult<Self> {
+        let mut request_id = [0u8; 16];
+        getrandom::fill(&mut request_id)
+            .map_err(|error| AtollWalletError::Random(error.to_string()))?;
+        request_id[6] = (request_id[6] & 0x0F) | 0x40;
+        request_id[8] = (request_id[8] & 0x3F) | 0x80;
+
+        Ok(Self::new_with_request_id(request, request_id))
+    }
+
+    pub fn new_with_request_id(request: &AirGappedSignRequest, request_id: [u8; 16]) -> Self {
+        Self {
+            request_id,
+            sign_data: request.sign_data.clone(),
+            derivation_path: request.derivation_path.clone(),
+            master_fingerprint: None,
+            address: Some(request.public_key),
+            origin: None,
+            sign_type: request.sign_type,
+        }
+    }
+
+    /// The fingerprint of the master key, which devices use to pick the seed to sign with
+    pub fn set_master_fingerprint(mut self, master_fingerprint: u32) -> Self {
+        self.master_fingerprint.replace(master_fingerprint);
+
+        self
+    }
+
+    /// The name of the wallet shown on the device
+    pub fn set_origin(mut self, origin: &str) -> Self {
+        self.origin.replace(origin.to_string());
+
+        self
+    }
+
+    pub fn request_id(&self) -> [u8; 16] {
+        self.request_id
+    }
+
+    pub fn sign_data(&self) -> &[u8] {
+        &self.sign_data
+    }
+
+    pub fn derivation_path(&self) -> &DerivationPath {
+        &self.derivation_path
+    }
+
+    pub fn master_fingerprint(&self) -> Option<u32> {
+        self.master_fingerprint
+    }
+
+    pub fn address(&self) -> Option<Pubkey> {
+        self.address
+    }
+
+    pub fn origin(&self) -> Option<&str> {
+        self.origin.as_deref()
+    }
+
+    pub fn sign_type(&self) -> AirGappedSignType {
+        self.sign_type
+    }
+
+    /// Encodes the request as the CBOR map
+    /// `{1: uuid, 2: sign-data, 3: crypto-keypath, ?4: address, ?5: origin, 6: sign-type}`
+    pub fn to_cbor(&self) -> AtollWalletResult<Vec<u8>> {
+        self.encode_cbor().map_err(|error| {
+            AtollWalletError::AirGapped(format!("Unable to encode `sol-sign-request`. {error}"))
+        })
+    }
+
+    fn encode_cbor(&self) -> Result<Vec<u8>, CborEncodeError> {
+        let length = 4 + self.address.is_some() as u64 + self.origin.is_some() as u64;
+
+        let mut encoder = Encoder::new(Vec::<u8>::new());
+        encoder
+            .map(length)?
+            .u8(1)?
+            .tag(Tag::new(UrRegistry::TAG_UUID))?
+            .bytes(&self.request_id)?
+            .u8(2)?
+            .bytes(&self.sign_data)?
+            .u8(3)?
+            .tag(Tag::new(UrRegistry::TAG_KEYPATH))?;
+        encode_keypath(&mut encoder, &self.derivation_path, self.master_fingerprint)?;
+
+        if let Some(address) = self.address.as_ref() {
+            encoder.u8(4)?.bytes(address.as_ref())?;
+        }
+
+        if let Some(origin) = self.origin.as_ref() {
+            encoder.u8(5)?.str(origin)?;
+        }
+
+        encoder.u8(6)?.u8(self.sign_type.to_cbor())?;
+
+        Ok(encoder.into_writer())
+    }
+
+    /// Decodes a request encoded by [Self::to_cbor]
+    pub fn from_cbor(bytes: &[u8]) -> AtollWalletResult<Self> {
+        let error = |error: minicbor::decode::Error| {
+            AtollWalletError::AirGapped(format!("Invalid `sol-sign-request` CBOR. {error}"))
+        };
+
+        let mut decoder = Decoder::new(bytes);
+        let length = decoder.map().map_err(error)?.ok_or(indefinite_error())?;
+
+        let mut request_id = Option::<[u8; 16]>::None;
+        let mut sign_data = Option::<Vec<u8>>::None;
+        let mut keypath = Option::<(DerivationPath, Option<u32>)>::None;
+        let mut address = Option::<Pubkey>::None;
+        let mut origin = Option::<String>::None;
+        let mut sign_type = AirGappedSignType::default();
+
+        for _ in 0..length {
+            match decoder.u8().map_err(error)? {
+                1 => request_id = Some(decode_uuid(&mut decoder)?),
+                2 => sign_data = Some(decoder.bytes().map_err(error)?.to_vec()),
+                3 => {
+                    expect_tag(&mut decoder, UrRegistry::TAG_KEYPATH)?;
+                    keypath = Some(decode_keypath(&mut decoder)?);
+                }
+                4 => {
+                    let bytes: [u8; 32] = decoder.bytes().map_err(error)?.try_into().or(Err(
+                        AtollWalletError::AirGapped(
+                            "The address of the `sol-sign-request` is not 32 bytes".to_string(),
+                        ),
+                    ))?;
+                    address = Some(Pubkey::from(bytes));
+                }
+                5 => origin = Some(decoder.str().map_err(error)?.to_string()),
+                6 => sign_type = AirGappedSignType::from_cbor(decoder.u8().map_err(error)?)?,
+                _ => decoder.skip().map_err(error)?,
+            }
+        }
+
+        let missing = |field: &str| {
+            AtollWalletError::AirGapped(format!("The `sol-sign-request` has no `{field}`"))
+        };
+        let (derivation_path, master_fingerprint) = keypath.ok_or(missing("derivation path"))?;
+
+        Ok(Self {
+            request_id: request_id.ok_or(missing("request ID"))?,
+            sign_data: sign_data.ok_or(missing("sign data"))?,
+            derivation_path,
+            master_fingerprint,
+            address,
+            origin,
+            sign_type,
+        })
+    }
+
+    /// Encodes the request as a single `ur:sol-sign-request` URI
+    pub fn to_ur(&self) -> AtollWalletResult<String> {
+        ur::ur::try_encode(
+            &self.to_cbor()?,
+            &ur::Type::Custom(UrRegistry::SOL_SIGN_REQUEST),
+        )
+        .map_err(ur_error)
+    }
+
+    /// Encodes the request as the parts of an animated QR code. The first parts
+    /// carry the fragments in order, the rest are fountain coded combinations so
+    /// a device that missed a frame can still complete the request.
+    pub fn to_ur_parts(&self, max_fragment_length: usize) -> AtollWalletResult<Vec<String>> {
+        let cbor = self.to_cbor()?;
+        let mut encoder =
+            ur::Encoder::new(&cbor, max_fragment_length, UrRegistry::SOL_SIGN_REQUEST)
+                .map_err(ur_error)?;
+
+        let count = if encoder.fragment_count() == 1 {
+            1
+        } else {
+            encoder.fragment_count() * 2
+        };
+
+        (0..count)
+            .map(|_| encoder.next_part().map_err(ur_error))
+            .collect()
+    }
+}
+
+/// A `sol-signature` returned by an offline device
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SolSignature {
+    pub request_id: [u8; 16],
+    pub signature: Signature,
+}
+
+impl SolSignature {
+    /// Encodes the signature as the CBOR map `{1: uuid, 2: signature}`
+    pub fn to_cbor(&self) -> AtollWalletResult<Vec<u8>> {
+        let encode = || -> Result<Vec<u8>, CborEncodeError> {
+            let mut encoder = Encoder::new(Vec::<u8>::new());
+            encoder
+                .map(2)?
+                .u8(1)?
+                .tag(Tag::new(UrRegistry::TAG_UUID))?
+                .bytes(&self.request_id)?
+                .u8(2)?
+                .bytes(self.signature.as_ref())?;
+
+            Ok(encoder.into_writer())
+        };
+
+        encode().map_err(|error| {
+            AtollWalletError::AirGapped(format!("Unable to encode `sol-signature`. {error}"))
+        })
+    }
+
+    pub fn from_cbor(bytes: &[u8]) -> AtollWalletResult<Self> {
+        let error = |error: minicbor::decode::Error| {
+            AtollWalletError::AirGapped(format!("Invalid `sol-signature` CBOR. {error}"))
+        };
+
+        let mut decoder = Decoder::new(bytes);
+        let length = decoder.map().map_err(error)?.ok_or(indefinite_error())?;
+
+        let mut request_id = Option::<[u8; 16]>::None;
+        let mut signature = Option::<Signature>::None;
+
+        for _ in 0..length {
+            match decoder.u8().map_err(error)? {
+                1 => request_id = Some(decode_uuid(&mut decoder)?),
+                2 => {
+                    let bytes: [u8; 64] = decoder.bytes().map_err(error)?.try_into().or(Err(
+                        AtollWalletError::AirGapped(
+                            "The signature of the `sol-signature` is not 64 bytes".to_string(),
+                        ),
+                    ))?;
+                    signature = Some(Signature::from(bytes));
+                }
+                _ => decoder.skip().map_err(error)?,
+            }
+        }
+
+        Ok(Self {
+            request_id: request_id.ok_or(AtollWalletError::AirGapped(
+                "The `sol-signature` has no request ID".to_string(),
+            ))?,
+            signature: signature.ok_or(AtollWalletError::AirGapped(
+                "The `sol-signature` has no signature".to_string(),
+            ))?,
+        })
+    }
+
+    /// Encodes the signature as a single `ur:sol-signature` URI
+    pub fn to_ur(&self) -> AtollWalletResult<String> {
+        ur::ur::try_encode(
+            &self.to_cbor()?,
+            &ur::Type::Custom(UrRegistry::SOL_SIGNATURE),
+        )
+        .map_err(ur_error)
+    }
+
+    /// Decodes a single `ur:sol-signature` URI or all the parts of an animated one
+    pub fn from_ur_parts(parts: &[&str]) -> AtollWalletResult<Self> {
+        let mut decoder = UrDecoder::default();
+
+        for part in parts {
+            decoder.receive(part)?;
+        }
+
+        let (ur_type, cbor) = decoder.message()?.ok_or(AtollWalletError::AirGapped(
+            "Not enough QR code parts were scanned to decode the signature".to_string(),
+        ))?;
+
+        if ur_type != UrRegistry::SOL_SIGNATURE {
+            return Err(AtollWalletError::AirGapped(format!(
+                "Expected a `ur:{}` but scanned a `ur:{ur_type}`",
+                UrRegistry::SOL_SIGNATURE
+            )));
+        }
+
+        Self::from_cbor(&cbor)
+    }
+}
+
+/// Collects the parts of a scanned UR, whether it is a single part or an animated QR code
+#[derive(Default)]
+pub struct UrDecoder {
+    single: Option<(String, Vec<u8>)>,
+    multi: ur::Decoder,
+}
+
+impl UrDecoder {
+    pub fn receive(&mut self, part: &str) -> AtollWalletResult<&mut Self> {
+        let part = part.trim();
+
+        match ur::decode(part).map_err(ur_error)? {
+            (ur::ur::Kind::SinglePart, cbor) => {
+                self.single.replace((ur_type(part)?, cbor));
+            }
+            (ur::ur::Kind::MultiPart, _) => self.multi.receive(part).map_err(ur_error)?,
+        }
+
+        Ok(self)
+    }
+
+    pub fn complete(&self) -> bool {
+        self.single.is_some() || self.multi.complete()
+    }
+
+    /// The type and CBOR payload of the UR once all parts have been received
+    pub fn message(&self) -> AtollWalletResult<Option<(String, Vec<u8>)>> {
+        if let Some(single) = self.single.as_ref() {
+            return Ok(Some(single.clone()));
+        }
+
+        let ur_type = self.multi.ur_type().unwrap_or_default().to_string();
+
+        Ok(self
+            .multi
+            .message()
+            .map_err(ur_error)?
+            .map(|cbor| (ur_type, cbor)))
+    }
+}
+
+impl AirGappedSignType {
+    pub fn to_cbor(&self) -> u8 {
+        match self {
+            Self::Transaction => 1,
+            Self::Message => 2,
+        }
+    }
+
+    pub fn from_cbor(value: u8) -> AtollWalletResult<Self> {
+        match value {
+            1 => Ok(Self::Transaction),
+            2 => Ok(Self::Message),
+            _ => Err(AtollWalletError::AirGapped(format!(
+                "`{value}` is not a supported sign type"
+            ))),
+        }
+    }
+}
+
+/// Encodes a `crypto-keypath` as `{1: [index, hardened, ...], ?2: source-fingerprint}`
+fn encode_keypath(
+    encoder: &mut Encoder<Vec<u8>>,
+    derivation_path: &DerivationPath,
+    master_fingerprint: Option<u32>,
+) -> Result<(), CborEncodeError> {
+    let path = derivation_path.path();
+
+    encoder
+        .map(1 + master_fingerprint.is_some() as u64)?
+        .u8(1)?
+        .array(path.len() as u64 * 2)?;
+
+    for index in path {
+        let bits = index.to_bits();
+
+        encoder
+            .u32(bits & !UrRegistry::HARDENED_BIT)?
+            .bool(bits & UrRegistry::HARDENED_BIT != 0)?;
+    }
+
+    if let Some(master_fingerprint) = master_fingerprint {
+        encoder.u8(2)?.u32(master_fingerprint)?;
+    }
+
+    Ok(())
+}
+
+fn decode_keypath(decoder: &mut Decoder) -> AtollWalletResult<(DerivationPath, Option<u32>)> {
+    let error = |error: minicbor::decode::Error| {
+        AtollWalletError::AirGapped(format!("Invalid `crypto-keypath` CBOR. {error}"))
+    };
+
+    let length = decoder.map().map_err(error)?.ok_or(indefinite_error())?;
+
+    let mut path = String::from("m");
+    let mut master_fingerprint = Option::<u32>::None;
+
+    for _ in 0..length {
+        match decoder.u8().map_err(error)? {
+            1 => {
+                let components = decoder.array().map_err(error)?.ok_or(indefinite_error())?;
+
+                for _ in 0..components / 2 {
+                    let index = decoder.u32().map_err(error)?;
+
+                    if !decoder.bool().map_err(error)? {
+                        return Err(AtollWalletError::AirGapped(
+                            "Solana derivation paths only support hardened indexes".to_string(),
+                        ));
+                    }
+
+                    path.push_str(&format!("/{index}'"));
+                }
+            }
+            2 => master_fingerprint = Some(decoder.u32().map_err(error)?),
+            _ => decoder.skip().map_err(error)?,
+        }
+    }
+
+    let derivation_path = DerivationPath::from_absolute_path_str(&path)
+        .map_err(|error| AtollWalletError::AirGapped(error.to_string()))?;
+
+    Ok((derivation_path, master_fingerprint))
+}
+
+fn decode_uuid(decoder: &mut Decoder) -> AtollWalletResult<[u8; 16]> {
+    expect_tag(decoder, UrRegistry::TAG_UUID)?;
+
+    decoder
+        .bytes()
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(AtollWalletError::AirGapped(
+            "The request ID is not a 16 byte UUID".to_string(),
+        ))
+}
+
+fn expect_tag(decoder: &mut Decoder, expected: u64) -> AtollWalletResult<()> {
+    let tag = decoder
+        .tag()
+        .map_err(|error| AtollWalletError::AirGapped(format!("Expected a CBOR tag. {error}")))?;
+
+    if tag.as_u64() != expected {
+        return Err(AtollWalletError::AirGapped(format!(
+            "Expected the CBOR tag `{expected}` but found `{}`",
+            tag.as_u64()
+        )));
+    }
+
+    Ok(())
+}
+
+fn ur_type(part: &str) -> AtollWalletResult<String> {
+    part.to_ascii_lowercase()
+        .strip_prefix("ur:")
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(ur_type, _)| ur_type.to_string())
+        .ok_or(AtollWalletError::AirGapped(format!("`{part}` is not a UR")))
+}
+
+fn indefinite_error() -> AtollWalletError {
+    AtollWalletError::AirGapped("Indefinite length CBOR is not supported".to_string())
+}
+
+fn ur_error(error: ur::ur::Error) -> AtollWalletError {
+    AtollWalletError::AirGapped(format!("Invalid UR. {error}"))
+}
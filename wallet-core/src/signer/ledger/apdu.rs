@@ -0,0 +1,165 @@
+use crate::{AtollWalletError, AtollWalletResult};
+
+/// The largest data field of a short APDU
+pub const APDU_MAX_DATA_LENGTH: usize = u8::MAX as usize;
+
+/// A command APDU sent to the device
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ApduCommand {
+    pub cla: u8,
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    pub data: Vec<u8>,
+}
+
+impl ApduCommand {
+    pub fn new(cla: u8, ins: u8, p1: u8, p2: u8, data: &[u8]) -> Self {
+        Self {
+            cla,
+            ins,
+            p1,
+            p2,
+            data: data.to_vec(),
+        }
+    }
+
+    /// Encodes the command as `CLA INS P1 P2 Lc DATA`
+    pub fn encode(&self) -> AtollWalletResult<Vec<u8>> {
+        let length = u8::try_from(self.data.len()).or(Err(AtollWalletError::Ledger(format!(
+            "The APDU data field is {} bytes which is larger than the maximum of {APDU_MAX_DATA_LENGTH} bytes",
+            self.data.len()
+        ))))?;
+
+        let mut command = Vec::with_capacity(5 + self.data.len());
+        command.extend_from_slice(&[self.cla, self.ins, self.p1, self.p2, length]);
+        command.extend_from_slice(&self.data);
+
+        Ok(command)
+    }
+
+    /// Decodes a command encoded by [Self::encode]
+    pub fn decode(bytes: &[u8]) -> AtollWalletResult<Self> {
+        let (header, data) = bytes.split_at_checked(5).ok_or(AtollWalletError::Ledger(
+            "The APDU command is shorter than its 5 byte header".to_string(),
+        ))?;
+
+        if header[4] as usize != data.len() {
+            return Err(AtollWalletError::Ledger(format!(
+                "The APDU command declares {} bytes of data but contains {}",
+                header[4],
+                data.len()
+            )));
+        }
+
+        Ok(Self::new(header[0], header[1], header[2], header[3], data))
+    }
+}
+
+/// A response APDU returned by the device
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ApduResponse {
+    pub data: Vec<u8>,
+    pub status: LedgerStatus,
+}
+
+impl ApduResponse {
+    /// Decodes a response made up of the data followed by the two status word bytes
+    pub fn decode(bytes: &[u8]) -> AtollWalletResult<Self> {
+        let (data, status) =
+            bytes
+                .split_at_checked(bytes.len().wrapping_sub(2))
+                .ok_or(AtollWalletError::Ledger(
+                    "The APDU response is shorter than its 2 byte status word".to_string(),
+                ))?;
+
+        Ok(Self {
+            data: data.to_vec(),
+            status: LedgerStatus::from(u16::from_be_bytes([status[0], status[1]])),
+        })
+    }
+
+    /// Returns the data if the device reported success, otherwise the status as an error
+    pub fn into_result(self) -> AtollWalletResult<Vec<u8>> {
+        match self.status {
+            LedgerStatus::Ok => Ok(self.data),
+            LedgerStatus::UserRejected => {
+                Err(AtollWalletError::UserRejected(self.status.describe()))
+            }
+            status => Err(AtollWalletError::Ledger(status.describe())),
+        }
+    }
+}
+
+/// The status word returned at the end of every response APDU
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum LedgerStatus {
+    /// `0x9000`
+    Ok,
+    /// `0x6985` the request was rejected on the device
+    UserRejected,
+    /// `0x6982` or `0x5515` the device is locked
+    DeviceLocked,
+    /// `0x6808` the transaction cannot be displayed and blind signing is disabled
+    BlindSigningDisabled,
+    /// `0x6D00`, `0x6E00`, `0x6E01` or `0x6511` the Solana app is not open
+    AppNotOpen,
+    /// `0x6700` the data field has an unexpected length
+    WrongLength,
+    /// `0x6A80` the data field is invalid
+    InvalidData,
+    /// `0x6B00` the parameters P1 or P2 are invalid
+    InvalidParameters,
+    /// Any other status word
+    Unknown(u16),
+}
+
+impl LedgerStatus {
+    pub fn code(&self) -> u16 {
+        match self {
+            Self::Ok => 0x9000,
+            Self::UserRejected => 0x6985,
+            Self::DeviceLocked => 0x6982,
+            Self::BlindSigningDisabled => 0x6808,
+            Self::AppNotOpen => 0x6E00,
+            Self::WrongLength => 0x6700,
+            Self::InvalidData => 0x6A80,
+            Self::InvalidParameters => 0x6B00,
+            Self::Unknown(code) => *code,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Ok => "The device processed the request".to_string(),
+            Self::UserRejected => "The request was rejected on the device".to_string(),
+            Self::DeviceLocked => "The device is locked. Unlock it and try again".to_string(),
+            Self::BlindSigningDisabled => {
+                "The device cannot display this transaction. Enable blind signing in the Solana app settings".to_string()
+            }
+            Self::AppNotOpen => "Open the Solana app on the device and try again".to_string(),
+            Self::WrongLength => "The device rejected the length of the request".to_string(),
+            Self::InvalidData => "The device rejected the data of the request".to_string(),
+            Self::InvalidParameters => {
+                "The device rejected the parameters of the request".to_string()
+            }
+            Self::Unknown(code) => format!("The device returned the status word `{code:04X}`"),
+        }
+    }
+}
+
+impl From<u16> for LedgerStatus {
+    fn from(value: u16) -> Self {
+        match value {
+            0x9000 => Self::Ok,
+            0x6985 => Self::UserRejected,
+            0x6982 | 0x5515 => Self::DeviceLocked,
+            0x6808 => Self::BlindSigningDisabled,
+            0x6D00 | 0x6E00 | 0x6E01 | 0x6511 => Self::AppNotOpen,
+            0x6700 => Self::WrongLength,
+            0x6A80 => Self::InvalidData,
+            0x6B00 => Self::InvalidParameters,
+            _ => Self::Unknown(value),
+        }
+    }
+}
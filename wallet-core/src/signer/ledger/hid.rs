@@ -0,0 +1,170 @@
+use std::{future::Future, pin::Pin};
+
+use crate::{ApduFuture, ApduTransport, AtollWalletError, AtollWalletResult};
+
+/// The future returned when writing a HID report
+pub type HidWriteFuture<'a> = Pin<Box<dyn Future<Output = AtollWalletResult<()>> + 'a>>;
+
+/// The future returned when reading a HID report
+pub type HidReadFuture<'a> = Pin<Box<dyn Future<Output = AtollWalletResult<Vec<u8>>> + 'a>>;
+
+/// A HID device exchanging fixed size reports, for example a WebHID `HIDDevice`
+pub trait HidDevice {
+    /// Writes one report of [LedgerHidFraming::PACKET_SIZE] bytes without a report ID
+    fn write<'a>(&'a self, report: &'a [u8]) -> HidWriteFuture<'a>;
+
+    /// Waits for the next input report from the device
+    fn read(&self) -> HidReadFuture<'_>;
+}
+
+/// Splits APDUs into the HID packets understood by Ledger devices and joins
+/// the packets of a response back together.
+///
+/// Every packet starts with the channel, the tag and a sequence index.
+/// The first packet also carries the length of the whole APDU.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct LedgerHidFraming {
+    channel: u16,
+}
+
+impl Default for LedgerHidFraming {
+    fn default() -> Self {
+        Self {
+            channel: Self::DEFAULT_CHANNEL,
+        }
+    }
+}
+
+impl LedgerHidFraming {
+    pub const PACKET_SIZE: usize = 64;
+    pub const DEFAULT_CHANNEL: u16 = 0x0101;
+    pub const TAG_APDU: u8 = 0x05;
+    const HEADER_SIZE: usize = 5;
+
+    pub fn new(channel: u16) -> Self {
+        Self { channel }
+    }
+
+    pub fn channel(&self) -> u16 {
+        self.channel
+    }
+
+    /// Splits `apdu` into zero padded packets
+    pub fn frame(&self, apdu: &[u8]) -> AtollWalletResult<Vec<[u8; Self::PACKET_SIZE]>> {
+        let length = u16::try_from(apdu.len()).or(Err(AtollWalletError::Ledger(
+            "The APDU is too large to be sent over HID".to_string(),
+        )))?;
+
+        let mut payload = length.to_be_bytes().to_vec();
+        payload.extend_from_slice(apdu);
+
+        payload
+            .chunks(Self::PACKET_SIZE - Self::HEADER_SIZE)
+            .enumerate()
+            .map(|(sequence, chunk)| {
+                let sequence = u16::try_from(sequence).or(Err(AtollWalletError::Ledger(
+                    "The APDU requires more HID packets than the sequence index allows".to_string(),
+                )))?;
+
+                let mut packet = [0u8; Self::PACKET_SIZE];
+                packet[0..2].copy_from_slice(&self.channel.to_be_bytes());
+                packet[2] = Self::TAG_APDU;
+                packet[3..5].copy_from_slice(&sequence.to_be_bytes());
+                packet[Self::HEADER_SIZE..Self::HEADER_SIZE + chunk.len()].copy_from_slice(chunk);
+
+                Ok(packet)
+            })
+            .collect()
+    }
+
+    /// Joins the packets of a response. Returns `None` if more packets are needed.
+    pub fn unframe(&self, packets: &[Vec<u8>]) -> AtollWalletResult<Option<Vec<u8>>> {
+        let mut payload = Vec::<u8>::new();
+
+        for (sequence, packet) in packets.iter().enumerate() {
+            if packet.len() < Self::HEADER_SIZE {
+                return Err(AtollWalletError::Ledger(
+                    "The device returned a HID packet shorter than its header".to_string(),
+                ));
+            }
+
+            if u16::from_be_bytes([packet[0], packet[1]]) != self.channel {
+                return Err(AtollWalletError::Ledger(
+                    "The device returned a HID packet on an unexpected channel".to_string(),
+                ));
+            }
+
+            if packet[2] != Self::TAG_APDU {
+                return Err(AtollWalletError::Ledger(format!(
+                    "The device returned a HID packet with the unexpected tag `{:02X}`",
+                    packet[2]
+                )));
+            }
+
+            if u16::from_be_bytes([packet[3], packet[4]]) as usize != sequence {
+                return Err(AtollWalletError::Ledger(
+                    "The device returned HID packets out of order".to_string(),
+                ));
+            }
+
+            payload.extend_from_slice(&packet[Self::HEADER_SIZE..]);
+        }
+
+        if payload.len() < 2 {
+            return Ok(None);
+        }
+
+        let length = u16::from_be_bytes([payload[0], payload[1]]) as usize;
+
+        if payload.len() - 2 < length {
+            return Ok(None);
+        }
+
+        Ok(Some(payload[2..2 + length].to_vec()))
+    }
+}
+
+/// An [ApduTransport] speaking the Ledger HID protocol over a [HidDevice]
+pub struct LedgerHidTransport<D: HidDevice> {
+    device: D,
+    framing: LedgerHidFraming,
+}
+
+impl<D: HidDevice> LedgerHidTransport<D> {
+    pub fn new(device: D) -> Self {
+        Self {
+            device,
+            framing: LedgerHidFraming::default(),
+        }
+    }
+
+    pub fn set_framing(mut self, framing: LedgerHidFraming) -> Self {
+        self.framing = framing;
+
+        self
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+}
+
+impl<D: HidDevice> ApduTransport for LedgerHidTransport<D> {
+    fn exchange<'a>(&'a self, command: &'a [u8]) -> ApduFuture<'a> {
+        Box::pin(async move {
+            for packet in self.framing.frame(command)? {
+                self.device.write(&packet).await?;
+            }
+
+            let mut packets = Vec::<Vec<u8>>::new();
+
+            loop {
+                packets.push(self.device.read().await?);
+
+                if let Some(response) = self.framing.unframe(&packets)? {
+                    break Ok(response);
+                }
+            }
+        })
+    }
+}
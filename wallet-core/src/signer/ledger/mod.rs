@@ -0,0 +1,114 @@
+mod apdu;
+pub use apdu::*;
+
+mod hid;
+pub use hid::*;
+
+mod solana_app;
+pub use solana_app::*;
+
+mod recorded;
+pub use recorded::*;
+
+use std::{future::Future, pin::Pin};
+
+use solana_derivation_path::DerivationPath;
+use solana_pubkey::Pubkey;
+
+use crate::{
+    AtollWalletError, AtollWalletResult, SignerFuture, SignerKind, SolanaOffchainMessage,
+    SolanaOffchainMessageFormat, WalletSigner,
+};
+
+/// The future returned by an [ApduTransport] exchange
+pub type ApduFuture<'a> = Pin<Box<dyn Future<Output = AtollWalletResult<Vec<u8>>> + 'a>>;
+
+/// Sends a command APDU to a device and returns the response APDU
+/// including the two status word bytes.
+pub trait ApduTransport {
+    fn exchange<'a>(&'a self, command: &'a [u8]) -> ApduFuture<'a>;
+}
+
+/// An account whose secret key lives on a Ledger-style device running the Solana app
+pub struct LedgerSigner<T: ApduTransport> {
+    transport: T,
+    derivation_path: DerivationPath,
+    public_key: Pubkey,
+}
+
+impl<T: ApduTransport> LedgerSigner<T> {
+    pub fn new(transport: T, derivation_path: DerivationPath, public_key: Pubkey) -> Self {
+        Self {
+            transport,
+            derivation_path,
+            public_key,
+        }
+    }
+
+    /// Fetches the public key at `derivation_path` from the device
+    pub async fn connect(transport: T, derivation_path: DerivationPath) -> AtollWalletResult<Self> {
+        let public_key = LedgerSolanaApp::new(&transport)
+            .get_pubkey(&derivation_path, false)
+            .await?;
+
+        Ok(Self::new(transport, derivation_path, public_key))
+    }
+
+    pub fn derivation_path(&self) -> &DerivationPath {
+        &self.derivation_path
+    }
+
+    pub fn app(&self) -> LedgerSolanaApp<'_, T> {
+        LedgerSolanaApp::new(&self.transport)
+    }
+}
+
+impl<T: ApduTransport> WalletSigner for LedgerSigner<T> {
+    fn pubkey(&self) -> Pubkey {
+        self.public_key
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::Ledger
+    }
+
+    /// Signs an off-chain message listing this account. The Solana app cannot sign raw
+    /// bytes, and off-chain messages in the extended format are too long for it.
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignerFuture<'a> {
+        Box::pin(async move {
+            if !message.starts_with(SolanaOffchainMessage::SIGNING_DOMAIN) {
+                return Err(AtollWalletError::Ledger(
+                    "The device only signs off-chain messages. Wrap the message in the off-chain message format".to_string(),
+                ));
+            }
+
+            let message = SolanaOffchainMessage::decode(message)?;
+
+            if !message.signers().contains(&self.public_key) {
+                return Err(AtollWalletError::Ledger(format!(
+                    "The account `{}` is not a signer of the off-chain message",
+                    self.public_key
+                )));
+            }
+
+            if message.format() == SolanaOffchainMessageFormat::ExtendedUtf8 {
+                return Err(AtollWalletError::Ledger(format!(
+                    "The device cannot sign off-chain messages longer than {} bytes",
+                    SolanaOffchainMessage::MAX_LIMITED_SIZE
+                )));
+            }
+
+            self.app()
+                .sign_offchain_message(&self.derivation_path, &message)
+                .await
+        })
+    }
+
+    fn sign_transaction_message<'a>(&'a self, message_data: &'a [u8]) -> SignerFuture<'a> {
+        Box::pin(async move {
+            self.app()
+                .sign_message(&self.derivation_path, message_data)
+                .await
+        })
+    }
+}
@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+
+use crate::{ApduFuture, ApduTransport, AtollWalletError};
+
+/// An [ApduTransport] that replays exchanges recorded from a device.
+///
+/// Every command must match the next recorded command, so the codec can be
+/// checked without a device attached.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct RecordedApduTransport {
+    exchanges: RefCell<Vec<(Vec<u8>, Vec<u8>)>>,
+}
+
+impl RecordedApduTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `command` is answered with `response`
+    pub fn record(self, command: &[u8], response: &[u8]) -> Self {
+        self.exchanges
+            .borrow_mut()
+            .push((command.to_vec(), response.to_vec()));
+
+        self
+    }
+
+    /// The number of recorded exchanges that have not been replayed
+    pub fn remaining(&self) -> usize {
+        self.exchanges.borrow().len()
+    }
+}
+
+impl ApduTransport for RecordedApduTransport {
+    fn exchange<'a>(&'a self, command: &'a [u8]) -> ApduFuture<'a> {
+        Box::pin(async move {
+            let mut exchanges = self.exchanges.borrow_mut();
+
+            if exchanges.is_empty() {
+                return Err(AtollWalletError::Ledger(
+                    "No recorded exchange is left to replay".to_string(),
+                ));
+            }
+
+            let (expected, response) = exchanges.remove(0);
+
+            if expected != command {
+                return Err(AtollWalletError::Ledger(format!(
+                    "Expected the recorded command `{}` but got `{}`",
+                    hex(&expected),
+                    hex(command)
+                )));
+            }
+
+            Ok(response)
+        })
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).collect()
+}
@@ -0,0 +1,213 @@
+use solana_derivation_path::DerivationPath;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+
+use crate::{
+    APDU_MAX_DATA_LENGTH, ApduCommand, ApduResponse, ApduTransport, AtollWalletError,
+    AtollWalletResult, SolanaOffchainMessage,
+};
+
+/// The configuration reported by the Solana app
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct LedgerAppConfig {
+    pub blind_signing_enabled: bool,
+    pub pubkey_display_mode: u8,
+    pub version: (u8, u8, u8),
+}
+
+impl LedgerAppConfig {
+    pub fn version(&self) -> String {
+        format!("{}.{}.{}", self.version.0, self.version.1, self.version.2)
+    }
+}
+
+/// The commands of the Ledger Solana app
+pub struct LedgerSolanaApp<'a, T: ApduTransport> {
+    transport: &'a T,
+}
+
+impl<'a, T: ApduTransport> LedgerSolanaApp<'a, T> {
+    pub const CLA: u8 = 0xE0;
+    pub const INS_GET_APP_CONFIG: u8 = 0x04;
+    pub const INS_GET_PUBKEY: u8 = 0x05;
+    pub const INS_SIGN_MESSAGE: u8 = 0x06;
+    pub const INS_SIGN_OFFCHAIN_MESSAGE: u8 = 0x07;
+    pub const P1_NON_CONFIRM: u8 = 0x00;
+    pub const P1_CONFIRM: u8 = 0x01;
+    pub const P2_EXTEND: u8 = 0x01;
+    pub const P2_MORE: u8 = 0x02;
+
+    pub fn new(transport: &'a T) -> Self {
+        Self { transport }
+    }
+
+    /// Fetches whether blind signing is enabled and the version of the app
+    pub async fn get_app_config(&self) -> AtollWalletResult<LedgerAppConfig> {
+        let data = self
+            .send(Self::INS_GET_APP_CONFIG, Self::P1_NON_CONFIRM, &[])
+            .await?;
+
+        Self::parse_app_config(&data)
+    }
+
+    /// Fetches the public key at `derivation_path`, asking the user to
+    /// confirm the address on the device if `display` is true
+    pub async fn get_pubkey(
+        &self,
+        derivation_path: &DerivationPath,
+        display: bool,
+    ) -> AtollWalletResult<Pubkey> {
+        let p1 = if display {
+            Self::P1_CONFIRM
+        } else {
+            Self::P1_NON_CONFIRM
+        };
+
+        let data = self
+            .send(
+                Self::INS_GET_PUBKEY,
+                p1,
+                &Self::encode_derivation_path(derivation_path)?,
+            )
+            .await?;
+
+        Self::parse_pubkey(&data)
+    }
+
+    /// Signs the serialized message of a transaction
+    pub async fn sign_message(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+    ) -> AtollWalletResult<Signature> {
+        self.sign(Self::INS_SIGN_MESSAGE, derivation_path, message)
+            .await
+    }
+
+    /// Signs an off-chain message, sent encoded with its signing domain and header
+    /// since the device refuses anything else
+    pub async fn sign_offchain_message(
+        &self,
+        derivation_path: &DerivationPath,
+        message: &SolanaOffchainMessage,
+    ) -> AtollWalletResult<Signature> {
+        self.sign(
+            Self::INS_SIGN_OFFCHAIN_MESSAGE,
+            derivation_path,
+            &message.encode(),
+        )
+        .await
+    }
+
+    async fn sign(
+        &self,
+        instruction: u8,
+        derivation_path: &DerivationPath,
+        message: &[u8],
+    ) -> AtollWalletResult<Signature> {
+        let data = self
+            .send(
+                instruction,
+                Self::P1_CONFIRM,
+                &Self::sign_payload(derivation_path, message)?,
+            )
+            .await?;
+
+        Self::parse_signature(&data)
+    }
+
+    async fn send(&self, instruction: u8, p1: u8, payload: &[u8]) -> AtollWalletResult<Vec<u8>> {
+        let mut data = Vec::<u8>::new();
+
+        for command in Self::commands(instruction, p1, payload) {
+            let response = self.transport.exchange(&command.encode()?).await?;
+            data = ApduResponse::decode(&response)?.into_result()?;
+        }
+
+        Ok(data)
+    }
+
+    /// Splits `payload` into the command APDUs sent for `instruction`.
+    /// Every chunk after the first sets [Self::P2_EXTEND] and every chunk
+    /// before the last sets [Self::P2_MORE].
+    pub fn commands(instruction: u8, p1: u8, payload: &[u8]) -> Vec<ApduCommand> {
+        if payload.is_empty() {
+            return vec![ApduCommand::new(Self::CLA, instruction, p1, 0x00, &[])];
+        }
+
+        let chunks = payload.chunks(APDU_MAX_DATA_LENGTH).collect::<Vec<&[u8]>>();
+        let last = chunks.len() - 1;
+
+        chunks
+            .into_iter()
+            .enumerate()
+            .map(|(index, chunk)| {
+                let mut p2 = 0x00;
+                if index > 0 {
+                    p2 |= Self::P2_EXTEND;
+                }
+                if index < last {
+                    p2 |= Self::P2_MORE;
+                }
+
+                ApduCommand::new(Self::CLA, instruction, p1, p2, chunk)
+            })
+            .collect()
+    }
+
+    /// Encodes the number of path indexes followed by each index as a big endian `u32`
+    pub fn encode_derivation_path(derivation_path: &DerivationPath) -> AtollWalletResult<Vec<u8>> {
+        let path = derivation_path.path();
+
+        let length = u8::try_from(path.len()).or(Err(AtollWalletError::Ledger(
+            "The derivation path has too many indexes".to_string(),
+        )))?;
+
+        let mut encoded = vec![length];
+        path.iter()
+            .for_each(|index| encoded.extend_from_slice(&index.to_bits().to_be_bytes()));
+
+        Ok(encoded)
+    }
+
+    /// The payload of a sign command: the number of signers, the derivation path and the message
+    pub fn sign_payload(
+        derivation_path: &DerivationPath,
+        message: &[u8],
+    ) -> AtollWalletResult<Vec<u8>> {
+        let mut payload = vec![1u8];
+        payload.extend_from_slice(&Self::encode_derivation_path(derivation_path)?);
+        payload.extend_from_slice(message);
+
+        Ok(payload)
+    }
+
+    pub fn parse_app_config(data: &[u8]) -> AtollWalletResult<LedgerAppConfig> {
+        match data {
+            [blind_signing, pubkey_display_mode, major, minor, patch, ..] => Ok(LedgerAppConfig {
+                blind_signing_enabled: *blind_signing != 0,
+                pubkey_display_mode: *pubkey_display_mode,
+                version: (*major, *minor, *patch),
+            }),
+            _ => Err(AtollWalletError::Ledger(
+                "The device returned an app configuration shorter than 5 bytes".to_string(),
+            )),
+        }
+    }
+
+    pub fn parse_pubkey(data: &[u8]) -> AtollWalletResult<Pubkey> {
+        let public_key: [u8; 32] = data.try_into().or(Err(AtollWalletError::Ledger(
+            "The device returned a public key that is not 32 bytes".to_string(),
+        )))?;
+
+        Ok(Pubkey::from(public_key))
+    }
+
+    pub fn parse_signature(data: &[u8]) -> AtollWalletResult<Signature> {
+        let signature: [u8; 64] = data.try_into().or(Err(AtollWalletError::Ledger(
+            "The device returned a signature that is not 64 bytes".to_string(),
+        )))?;
+
+        Ok(Signature::from(signature))
+    }
+}
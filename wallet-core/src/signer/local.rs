@@ -0,0 +1,47 @@
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+use crate::{AtollWalletError, SignerFuture, SignerKind, WalletSigner};
+
+impl WalletSigner for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(self)
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::Local
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignerFuture<'a> {
+        let signature = Signer::sign_message(self, message);
+
+        Box::pin(async move { Ok(signature) })
+    }
+}
+
+/// An account added by address. It shows up in account lists but refuses to sign.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct WatchOnlySigner(Pubkey);
+
+impl WatchOnlySigner {
+    pub fn new(public_key: Pubkey) -> Self {
+        Self(public_key)
+    }
+}
+
+impl WalletSigner for WatchOnlySigner {
+    fn pubkey(&self) -> Pubkey {
+        self.0
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::WatchOnly
+    }
+
+    fn sign_message<'a>(&'a self, _message: &'a [u8]) -> SignerFuture<'a> {
+        let address = self.0.to_string();
+
+        Box::pin(async move { Err(AtollWalletError::WatchOnlyAccount(address)) })
+    }
+}
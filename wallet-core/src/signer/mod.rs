@@ -0,0 +1,103 @@
+mod local;
+pub use local::*;
+
+mod ledger;
+pub use ledger::*;
+
+mod remote;
+pub use remote::*;
+
+mod air_gapped;
+pub use air_gapped::*;
+
+use std::{future::Future, pin::Pin};
+
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+/// The future returned by a [WalletSigner] when signing
+pub type SignerFuture<'a> = Pin<Box<dyn Future<Output = AtollWalletResult<Signature>> + 'a>>;
+
+/// Where the secret key of an account lives
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum SignerKind {
+    /// A keypair held in the wasm heap
+    Local,
+    /// An account that can only be monitored
+    WatchOnly,
+    /// A hardware wallet reached over an APDU transport
+    Ledger,
+    /// A remote signing service reached over HTTP
+    Remote,
+    /// An offline device reached by scanning QR codes
+    AirGapped,
+}
+
+impl SignerKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Local => "local",
+            Self::WatchOnly => "watchOnly",
+            Self::Ledger => "ledger",
+            Self::Remote => "remote",
+            Self::AirGapped => "airGapped",
+        }
+    }
+}
+
+/// A backend that holds the secret key of an account and produces ed25519 signatures.
+///
+/// Signing is asynchronous since the key may live outside the wasm heap,
+/// for example on a hardware wallet or a remote service.
+pub trait WalletSigner {
+    /// The public key of the account
+    fn pubkey(&self) -> Pubkey;
+
+    /// The kind of backend holding the secret key
+    fn kind(&self) -> SignerKind;
+
+    /// Signs arbitrary bytes
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignerFuture<'a>;
+
+    /// Signs the serialized message of a transaction. Backends that show
+    /// transactions differently from messages to the user override this.
+    fn sign_transaction_message<'a>(&'a self, message_data: &'a [u8]) -> SignerFuture<'a> {
+        self.sign_message(message_data)
+    }
+
+    /// Signs the message of `transaction` and places the signature at the position
+    /// of this signer in the transaction's required signers.
+    fn sign_transaction<'a>(
+        &'a self,
+        mut transaction: Transaction,
+    ) -> Pin<Box<dyn Future<Output = AtollWalletResult<Transaction>> + 'a>> {
+        Box::pin(async move {
+            let pubkey = self.pubkey();
+
+            let position = transaction
+                .get_signing_keypair_positions(&[pubkey])
+                .or(Err(AtollWalletError::Input(
+                    "The transaction has fewer account keys than required signatures".to_string(),
+                )))?
+                .first()
+                .copied()
+                .flatten()
+                .ok_or(AtollWalletError::SignerNotRequired(pubkey.to_string()))?;
+
+            let message_data = transaction.message_data();
+            let signature = self.sign_transaction_message(&message_data).await?;
+
+            let num_required_signatures =
+                transaction.message.header.num_required_signatures as usize;
+            if transaction.signatures.len() != num_required_signatures {
+                transaction.signatures = vec![Signature::default(); num_required_signatures];
+            }
+            transaction.signatures[position] = signature;
+
+            Ok(transaction)
+        })
+    }
+}
@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+use base64ct::{Base64, Encoding};
+use serde::Deserialize;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+
+use crate::{AtollWalletError, HttpTransport, SignerFuture, SignerKind, WalletSigner};
+
+/// An account whose secret key is held by a remote signing service.
+///
+/// The service receives a `POST` request with the JSON body
+/// `{ "publicKey": "<base58>", "message": "<base64>" }` and responds with
+/// `{ "signature": "<base58>" }` or `{ "error": "<reason>" }`.
+pub struct RemoteSigner<T: HttpTransport> {
+    transport: T,
+    endpoint: String,
+    public_key: Pubkey,
+}
+
+impl<T: HttpTransport> RemoteSigner<T> {
+    pub fn new(transport: T, endpoint: &str, public_key: Pubkey) -> Self {
+        Self {
+            transport,
+            endpoint: endpoint.to_string(),
+            public_key,
+        }
+    }
+
+    pub fn endpoint(&self) -> &str {
+        self.endpoint.as_str()
+    }
+}
+
+impl<T: HttpTransport> WalletSigner for RemoteSigner<T> {
+    fn pubkey(&self) -> Pubkey {
+        self.public_key
+    }
+
+    fn kind(&self) -> SignerKind {
+        SignerKind::Remote
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignerFuture<'a> {
+        Box::pin(async move {
+            let body = jzon::object! {
+                "publicKey": self.public_key.to_string(),
+                "message": Base64::encode_string(message),
+            }
+            .to_string();
+
+            let response = self.transport.post_json(&self.endpoint, body).await?;
+
+            let response = serde_json::from_str::<RemoteSignerResponse>(&response).or(Err(
+                AtollWalletError::RemoteSigner(
+                    "The response of the remote signer is not valid JSON".to_string(),
+                ),
+            ))?;
+
+            match response {
+                RemoteSignerResponse {
+                    signature: Some(signature),
+                    ..
+                } => {
+                    let signature =
+                        Signature::from_str(&signature).or(Err(AtollWalletError::RemoteSigner(
+                            "The remote signer returned an invalid base58 signature".to_string(),
+                        )))?;
+
+                    if !signature.verify(self.public_key.as_ref(), message) {
+                        return Err(AtollWalletError::RemoteSigner(
+                            "The signature returned by the remote signer is not valid for this account"
+                                .to_string(),
+                        ));
+                    }
+
+                    Ok(signature)
+                }
+                RemoteSignerResponse {
+                    error: Some(error), ..
+                } => Err(AtollWalletError::RemoteSigner(error)),
+                _ => Err(AtollWalletError::RemoteSigner(
+                    "The remote signer did not return a signature".to_string(),
+                )),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone)]
+struct RemoteSignerResponse {
+    signature: Option<String>,
+    error: Option<String>,
+}
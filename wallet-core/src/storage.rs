@@ -0,0 +1,16 @@
+use std::{future::Future, pin::Pin};
+
+use crate::AtollWalletResult;
+
+/// The future returned by a [WalletStorage]
+pub type StorageFuture<'a, O> = Pin<Box<dyn Future<Output = AtollWalletResult<O>> + 'a>>;
+
+/// Stores the state of the background that must outlive it, like the connected sites,
+/// as JSON strings by key. The extension backs it with `chrome.storage.local`.
+pub trait WalletStorage {
+    /// The value stored at `key`, if any
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<String>>;
+
+    /// Stores `value` at `key`, replacing the previous value
+    fn set<'a>(&'a self, key: &'a str, value: String) -> StorageFuture<'a, ()>;
+}
@@ -0,0 +1,19 @@
+use std::{future::Future, pin::Pin};
+
+use crate::AtollWalletResult;
+
+/// The future returned by an [HttpTransport] containing the response body
+pub type HttpFuture<'a> = Pin<Box<dyn Future<Output = AtollWalletResult<String>> + 'a>>;
+
+/// Sends requests over HTTP. This allows services like remote signers and
+/// Bitcoin backends to be reached without depending on the browser fetch API directly.
+pub trait HttpTransport {
+    /// Sends `body` as a JSON `POST` request to `url` and returns the response body
+    fn post_json<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a>;
+
+    /// Sends `body` as a `text/plain` `POST` request to `url` and returns the response body
+    fn post_text<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a>;
+
+    /// Sends a `GET` request to `url` and returns the response body
+    fn get<'a>(&'a self, url: &'a str) -> HttpFuture<'a>;
+}
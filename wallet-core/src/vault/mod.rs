@@ -0,0 +1,7 @@
+mod sealed;
+
+mod slip39;
+pub use slip39::*;
+
+mod wallet_vault;
+pub use wallet_vault::*;
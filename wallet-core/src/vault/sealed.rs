@@ -0,0 +1,134 @@
+use base64ct::{Base64, Encoding};
+use chacha20poly1305::{
+    ChaCha20Poly1305, Key, KeyInit, Nonce,
+    aead::{Aead, Payload},
+};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{AtollWalletError, AtollWalletResult, WalletVault};
+
+/// The version of the sealed document
+const VERSION: u16 = 1;
+
+/// Bound into the associated data so that the key only opens sealed vaults
+const DOMAIN: &[u8] = b"atoll-vault";
+
+const SALT_LENGTH: usize = 16;
+
+const NONCE_LENGTH: usize = 12;
+
+const KEY_LENGTH: usize = 32;
+
+/// Encrypts `secret` with ChaCha20-Poly1305 under a key derived from `password` with
+/// [WalletVault::SEAL_ITERATIONS] rounds of PBKDF2-HMAC-SHA256 and a random salt.
+/// The version, iterations and salt are authenticated as associated data.
+pub(crate) fn seal(secret: &[u8], password: &str) -> AtollWalletResult<String> {
+    let iterations = WalletVault::SEAL_ITERATIONS;
+
+    let mut salt = [0u8; SALT_LENGTH];
+    let mut nonce = [0u8; NONCE_LENGTH];
+    getrandom::fill(&mut salt).map_err(|error| AtollWalletError::Random(error.to_string()))?;
+    getrandom::fill(&mut nonce).map_err(|error| AtollWalletError::Random(error.to_string()))?;
+
+    let ciphertext = cipher(password, &salt, iterations)
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: secret,
+                aad: &associated_data(iterations, &salt),
+            },
+        )
+        .or(Err(AtollWalletError::Input(
+            "The vault could not be encrypted".to_string(),
+        )))?;
+
+    let stored = StoredSealedVault {
+        version: VERSION,
+        iterations,
+        salt: Base64::encode_string(&salt),
+        nonce: Base64::encode_string(&nonce),
+        ciphertext: Base64::encode_string(&ciphertext),
+    };
+
+    serde_json::to_string(&stored).map_err(|error| {
+        AtollWalletError::Input(format!("The sealed vault could not be serialized. {error}"))
+    })
+}
+
+/// Decrypts the document of [seal], refusing it unless it authenticates under `password`
+pub(crate) fn unseal(json: &str, password: &str) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+    let stored = serde_json::from_str::<StoredSealedVault>(json).map_err(|error| {
+        AtollWalletError::Input(format!("The stored vault could not be read. {error}"))
+    })?;
+
+    if stored.version != VERSION {
+        return Err(AtollWalletError::Input(format!(
+            "The version `{}` of the stored vault is not supported",
+            stored.version
+        )));
+    }
+
+    // A document with fewer iterations than sealing uses would make the password
+    // cheaper to guess, so it can only have been tampered with
+    if stored.iterations < WalletVault::SEAL_ITERATIONS {
+        return Err(AtollWalletError::Input(format!(
+            "The stored vault has `{}` key derivation iterations, at least `{}` are required",
+            stored.iterations,
+            WalletVault::SEAL_ITERATIONS
+        )));
+    }
+
+    let decode = |field: &str, value: &str| {
+        Base64::decode_vec(value).or(Err(AtollWalletError::Input(format!(
+            "The `{field}` of the stored vault is not valid base64"
+        ))))
+    };
+    let salt = decode("salt", &stored.salt)?;
+    let nonce = decode("nonce", &stored.nonce)?;
+    let ciphertext = decode("ciphertext", &stored.ciphertext)?;
+
+    if nonce.len() != NONCE_LENGTH {
+        return Err(AtollWalletError::Input(format!(
+            "The `nonce` of the stored vault must be {NONCE_LENGTH} bytes"
+        )));
+    }
+
+    let secret = cipher(password, &salt, stored.iterations)
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: &associated_data(stored.iterations, &salt),
+            },
+        )
+        .or(Err(AtollWalletError::VaultPasswordIncorrect))?;
+
+    Ok(Zeroizing::new(secret))
+}
+
+fn cipher(password: &str, salt: &[u8], iterations: u32) -> ChaCha20Poly1305 {
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, key.as_mut());
+
+    ChaCha20Poly1305::new(Key::from_slice(key.as_ref()))
+}
+
+fn associated_data(iterations: u32, salt: &[u8]) -> Vec<u8> {
+    let mut data = DOMAIN.to_vec();
+    data.extend_from_slice(&VERSION.to_be_bytes());
+    data.extend_from_slice(&iterations.to_be_bytes());
+    data.extend_from_slice(salt);
+
+    data
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredSealedVault {
+    version: u16,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
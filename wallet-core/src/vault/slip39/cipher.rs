@@ -0,0 +1,110 @@
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+use super::CUSTOMIZATION_STRING_ORIGINAL;
+
+/// The minimum number of PBKDF2 iterations which is multiplied by `2^iteration_exponent`
+const BASE_ITERATION_COUNT: u32 = 10_000;
+
+/// The number of rounds of the Feistel network
+const ROUND_COUNT: u8 = 4;
+
+/// Encrypts the master secret with a four round Feistel network
+/// using PBKDF2-HMAC-SHA256 as the round function.
+pub(crate) fn encrypt(
+    master_secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+    feistel(
+        master_secret,
+        passphrase,
+        iteration_exponent,
+        salt(identifier, extendable),
+        (0..ROUND_COUNT).collect::<Vec<u8>>().as_slice(),
+    )
+}
+
+/// Decrypts the encrypted master secret recovered from the shares
+pub(crate) fn decrypt(
+    encrypted_master_secret: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    identifier: u16,
+    extendable: bool,
+) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+    feistel(
+        encrypted_master_secret,
+        passphrase,
+        iteration_exponent,
+        salt(identifier, extendable),
+        (0..ROUND_COUNT).rev().collect::<Vec<u8>>().as_slice(),
+    )
+}
+
+/// The salt of the round function, empty for extendable backups so that shares added
+/// later with a new identifier decrypt to the same master secret
+fn salt(identifier: u16, extendable: bool) -> Vec<u8> {
+    if extendable {
+        return Vec::new();
+    }
+
+    let mut salt = CUSTOMIZATION_STRING_ORIGINAL.to_vec();
+    salt.extend_from_slice(&identifier.to_be_bytes());
+
+    salt
+}
+
+fn feistel(
+    input: &[u8],
+    passphrase: &str,
+    iteration_exponent: u8,
+    salt: Vec<u8>,
+    rounds: &[u8],
+) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+    if !input.len().is_multiple_of(2) {
+        return Err(AtollWalletError::InvalidSlip39Config(
+            "The length of the master secret in bytes must be an even number".to_string(),
+        ));
+    }
+
+    let iterations = 1u32
+        .checked_shl(iteration_exponent as u32)
+        .and_then(|multiplier| (BASE_ITERATION_COUNT / ROUND_COUNT as u32).checked_mul(multiplier))
+        .ok_or(AtollWalletError::InvalidSlip39Config(format!(
+            "The iteration exponent `{iteration_exponent}` is too large"
+        )))?;
+
+    let half = input.len() / 2;
+    let mut left = Zeroizing::new(input[..half].to_vec());
+    let mut right = Zeroizing::new(input[half..].to_vec());
+
+    for round in rounds {
+        let mut password = Zeroizing::new(vec![*round]);
+        password.extend_from_slice(passphrase.as_bytes());
+
+        let mut round_salt = salt.clone();
+        round_salt.extend_from_slice(&right);
+
+        let mut round_output = Zeroizing::new(vec![0u8; half]);
+        pbkdf2::pbkdf2_hmac::<Sha256>(&password, &round_salt, iterations, &mut round_output);
+
+        let next_right = left
+            .iter()
+            .zip(round_output.iter())
+            .map(|(left_byte, round_byte)| left_byte ^ round_byte)
+            .collect::<Vec<u8>>();
+
+        left = right;
+        right = Zeroizing::new(next_right);
+    }
+
+    let mut output = Zeroizing::new(right.to_vec());
+    output.extend_from_slice(&left);
+
+    Ok(output)
+}
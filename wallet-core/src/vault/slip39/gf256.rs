@@ -0,0 +1,101 @@
+use crate::{AtollWalletError, AtollWalletResult};
+
+/// Exponent and logarithm tables for GF(256) using the Rijndael
+/// polynomial `x^8 + x^4 + x^3 + x + 1` with `3` as the generator.
+const TABLES: ([u8; 255], [u8; 256]) = {
+    let mut exp = [0u8; 255];
+    let mut log = [0u8; 256];
+
+    let mut poly: u16 = 1;
+    let mut index = 0usize;
+
+    while index < 255 {
+        exp[index] = poly as u8;
+        log[poly as usize] = index as u8;
+
+        // Multiply by the generator `x + 1`
+        poly = (poly << 1) ^ poly;
+        if poly & 0x100 != 0 {
+            poly ^= 0x11b;
+        }
+
+        index += 1;
+    }
+
+    (exp, log)
+};
+
+const EXP: [u8; 255] = TABLES.0;
+const LOG: [u8; 256] = TABLES.1;
+
+/// A point `(x, y)` on the polynomials used by Shamir's secret sharing where each byte
+/// of `y` belongs to a different polynomial.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SharePoint {
+    pub(crate) x: u8,
+    pub(crate) y: Vec<u8>,
+}
+
+impl SharePoint {
+    pub(crate) fn new(x: u8, y: Vec<u8>) -> Self {
+        Self { x, y }
+    }
+}
+
+/// Evaluates the Lagrange interpolation polynomial passing through `points` at `x`.
+pub(crate) fn interpolate(points: &[SharePoint], x: u8) -> AtollWalletResult<Vec<u8>> {
+    let first = points.first().ok_or(AtollWalletError::InvalidSlip39Share(
+        "Interpolation requires at least one share".to_string(),
+    ))?;
+
+    let value_length = first.y.len();
+
+    for (position, point) in points.iter().enumerate() {
+        if point.y.len() != value_length {
+            return Err(AtollWalletError::InvalidSlip39Share(
+                "All share values must have the same length".to_string(),
+            ));
+        }
+
+        if points[..position].iter().any(|other| other.x == point.x) {
+            return Err(AtollWalletError::InvalidSlip39Share(format!(
+                "Share index `{}` was provided more than once",
+                point.x
+            )));
+        }
+    }
+
+    if let Some(point) = points.iter().find(|point| point.x == x) {
+        return Ok(point.y.clone());
+    }
+
+    let log_product: usize = points
+        .iter()
+        .map(|point| LOG[(point.x ^ x) as usize] as usize)
+        .sum();
+
+    let mut result = vec![0u8; value_length];
+
+    for point in points {
+        let log_denominator: usize = points
+            .iter()
+            .filter(|other| other.x != point.x)
+            .map(|other| LOG[(point.x ^ other.x) as usize] as usize)
+            .sum();
+
+        let log_basis = (log_product + 255 * points.len()
+            - LOG[(point.x ^ x) as usize] as usize
+            - log_denominator)
+            % 255;
+
+        result
+            .iter_mut()
+            .zip(point.y.iter())
+            .filter(|(_, y)| **y != 0)
+            .for_each(|(accumulator, y)| {
+                *accumulator ^= EXP[(LOG[*y as usize] as usize + log_basis) % 255];
+            });
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,576 @@
+mod cipher;
+mod gf256;
+mod rs1024;
+
+mod share;
+pub use share::*;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+use gf256::SharePoint;
+
+/// Customization string used in the RS1024 checksum and the PBKDF2 salt of backups
+/// that are not extendable
+pub(crate) const CUSTOMIZATION_STRING_ORIGINAL: &[u8] = b"shamir";
+
+/// Customization string used in the RS1024 checksum of extendable backups
+pub(crate) const CUSTOMIZATION_STRING_EXTENDABLE: &[u8] = b"shamir_extendable";
+
+/// The master secret must have at least 128 bits of entropy
+pub(crate) const MIN_MASTER_SECRET_LENGTH: usize = 16;
+
+/// Maximum number of groups or members in a group
+const MAX_SHARE_COUNT: u8 = 16;
+
+/// The `x` value of the share containing the digest of the shared secret
+const DIGEST_INDEX: u8 = 254;
+
+/// The `x` value of the share containing the shared secret
+const SECRET_INDEX: u8 = 255;
+
+/// Length of the digest used to verify that the shares recover the correct secret
+const DIGEST_LENGTH: usize = 4;
+
+/// The largest iteration exponent, which is encoded in 4 bits
+const MAX_ITERATION_EXPONENT: u8 = 0xF;
+
+pub(crate) fn customization_string(extendable: bool) -> &'static [u8] {
+    if extendable {
+        CUSTOMIZATION_STRING_EXTENDABLE
+    } else {
+        CUSTOMIZATION_STRING_ORIGINAL
+    }
+}
+
+/// A group of member shares. `member_threshold` of the `member_count`
+/// shares in the group recover the group share.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Slip39Group {
+    pub member_threshold: u8,
+    pub member_count: u8,
+}
+
+impl Slip39Group {
+    pub fn new(member_threshold: u8, member_count: u8) -> Self {
+        Self {
+            member_threshold,
+            member_count,
+        }
+    }
+}
+
+/// How the master secret is split into shares.
+///
+/// For a team treasury a single group like `3-of-5` is usually enough,
+/// [Slip39Config::new] creates that.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Slip39Config {
+    group_threshold: u8,
+    groups: Vec<Slip39Group>,
+    iteration_exponent: u8,
+    extendable: bool,
+}
+
+impl Slip39Config {
+    /// A single group where any `threshold` of the `share_count` shares recover the seed
+    pub fn new(threshold: u8, share_count: u8) -> Self {
+        Self {
+            group_threshold: 1,
+            groups: vec![Slip39Group::new(threshold, share_count)],
+            iteration_exponent: 1,
+            extendable: true,
+        }
+    }
+
+    /// Multiple groups where `group_threshold` of the groups recover the seed
+    pub fn new_with_groups(group_threshold: u8, groups: &[Slip39Group]) -> Self {
+        Self {
+            group_threshold,
+            groups: groups.to_vec(),
+            iteration_exponent: 1,
+            extendable: true,
+        }
+    }
+
+    /// Sets the exponent used to derive the number of PBKDF2 iterations
+    pub fn set_iteration_exponent(mut self, iteration_exponent: u8) -> Self {
+        self.iteration_exponent = iteration_exponent;
+
+        self
+    }
+
+    /// Whether more shares can be created for the backup later, which is the default.
+    /// Backups that are not extendable encrypt the master secret with their identifier.
+    pub fn set_extendable(mut self, extendable: bool) -> Self {
+        self.extendable = extendable;
+
+        self
+    }
+
+    pub fn group_threshold(&self) -> u8 {
+        self.group_threshold
+    }
+
+    pub fn groups(&self) -> &[Slip39Group] {
+        self.groups.as_slice()
+    }
+
+    pub fn iteration_exponent(&self) -> u8 {
+        self.iteration_exponent
+    }
+
+    pub fn extendable(&self) -> bool {
+        self.extendable
+    }
+
+    fn validate(&self) -> AtollWalletResult<()> {
+        let group_count = self.groups.len();
+
+        if group_count == 0 || group_count > MAX_SHARE_COUNT as usize {
+            return Err(AtollWalletError::InvalidSlip39Config(format!(
+                "The number of groups must be between 1 and {MAX_SHARE_COUNT}"
+            )));
+        }
+
+        if self.group_threshold == 0 || self.group_threshold as usize > group_count {
+            return Err(AtollWalletError::InvalidSlip39Config(format!(
+                "The group threshold `{}` must be between 1 and the number of groups `{group_count}`",
+                self.group_threshold
+            )));
+        }
+
+        if self.iteration_exponent > MAX_ITERATION_EXPONENT {
+            return Err(AtollWalletError::InvalidSlip39Config(
+                "The iteration exponent must fit in 4 bits".to_string(),
+            ));
+        }
+
+        self.groups.iter().try_for_each(|group| {
+            if group.member_count == 0 || group.member_count > MAX_SHARE_COUNT {
+                return Err(AtollWalletError::InvalidSlip39Config(format!(
+                    "The number of shares in a group must be between 1 and {MAX_SHARE_COUNT}"
+                )));
+            }
+
+            if group.member_threshold == 0 || group.member_threshold > group.member_count {
+                return Err(AtollWalletError::InvalidSlip39Config(format!(
+                    "The member threshold `{}` must be between 1 and the number of shares `{}`",
+                    group.member_threshold, group.member_count
+                )));
+            }
+
+            if group.member_threshold == 1 && group.member_count > 1 {
+                return Err(AtollWalletError::InvalidSlip39Config(
+                    "Creating multiple member shares with a member threshold of 1 is not allowed. Use 1-of-1 member sharing instead".to_string(),
+                ));
+            }
+
+            Ok(())
+        })
+    }
+}
+
+/// SLIP-39 Shamir's secret sharing of a master secret into mnemonic shares
+pub struct Slip39;
+
+impl Slip39 {
+    /// Splits `master_secret` into groups of mnemonic shares. The outer [Vec] holds the groups
+    /// and the inner [Vec] the member shares of each group.
+    pub fn split(
+        master_secret: &[u8],
+        passphrase: &str,
+        config: &Slip39Config,
+    ) -> AtollWalletResult<Vec<Vec<Zeroizing<String>>>> {
+        config.validate()?;
+
+        if master_secret.len() < MIN_MASTER_SECRET_LENGTH || !master_secret.len().is_multiple_of(2)
+        {
+            return Err(AtollWalletError::InvalidSlip39Config(format!(
+                "The master secret must be at least {MIN_MASTER_SECRET_LENGTH} bytes and have an even length"
+            )));
+        }
+
+        let mut identifier_bytes = [0u8; 2];
+        getrandom::fill(&mut identifier_bytes)
+            .map_err(|error| AtollWalletError::Random(error.to_string()))?;
+        let identifier = u16::from_be_bytes(identifier_bytes) & 0x7FFF;
+
+        let encrypted_master_secret = cipher::encrypt(
+            master_secret,
+            passphrase,
+            config.iteration_exponent,
+            identifier,
+            config.extendable,
+        )?;
+
+        let group_shares = Self::split_secret(
+            config.group_threshold,
+            config.groups.len() as u8,
+            &encrypted_master_secret,
+        )?;
+
+        config
+            .groups
+            .iter()
+            .zip(group_shares)
+            .map(|(group, group_share)| {
+                let member_shares =
+                    Self::split_secret(group.member_threshold, group.member_count, &group_share.y)?;
+
+                Ok(member_shares
+                    .into_iter()
+                    .map(|member_share| {
+                        Slip39Share {
+                            identifier,
+                            extendable: config.extendable,
+                            iteration_exponent: config.iteration_exponent,
+                            group_index: group_share.x,
+                            group_threshold: config.group_threshold,
+                            group_count: config.groups.len() as u8,
+                            member_index: member_share.x,
+                            member_threshold: group.member_threshold,
+                            value: Zeroizing::new(member_share.y),
+                        }
+                        .to_mnemonic()
+                    })
+                    .collect::<Vec<Zeroizing<String>>>())
+            })
+            .collect()
+    }
+
+    /// Recovers the master secret from the mnemonic shares
+    pub fn combine(mnemonics: &[&str], passphrase: &str) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+        let mut recovery = Slip39Recovery::new();
+
+        mnemonics
+            .iter()
+            .try_for_each(|mnemonic| recovery.add_mnemonic(mnemonic).map(|_| ()))?;
+
+        recovery.recover(passphrase)
+    }
+
+    fn split_secret(
+        threshold: u8,
+        share_count: u8,
+        secret: &[u8],
+    ) -> AtollWalletResult<Vec<SharePoint>> {
+        if threshold == 1 {
+            return Ok((0..share_count)
+                .map(|index| SharePoint::new(index, secret.to_vec()))
+                .collect());
+        }
+
+        let random_share_count = threshold - 2;
+
+        let mut shares = (0..random_share_count)
+            .map(|index| {
+                let mut value = vec![0u8; secret.len()];
+                getrandom::fill(&mut value)
+                    .map_err(|error| AtollWalletError::Random(error.to_string()))?;
+
+                Ok(SharePoint::new(index, value))
+            })
+            .collect::<AtollWalletResult<Vec<SharePoint>>>()?;
+
+        let mut random_part = Zeroizing::new(vec![0u8; secret.len() - DIGEST_LENGTH]);
+        getrandom::fill(&mut random_part)
+            .map_err(|error| AtollWalletError::Random(error.to_string()))?;
+
+        let mut digest_share = Self::digest(&random_part, secret)?.to_vec();
+        digest_share.extend_from_slice(&random_part);
+
+        let mut base_shares = shares.clone();
+        base_shares.push(SharePoint::new(DIGEST_INDEX, digest_share));
+        base_shares.push(SharePoint::new(SECRET_INDEX, secret.to_vec()));
+
+        for index in random_share_count..share_count {
+            shares.push(SharePoint::new(
+                index,
+                gf256::interpolate(&base_shares, index)?,
+            ));
+        }
+
+        Ok(shares)
+    }
+
+    fn recover_secret(threshold: u8, shares: &[SharePoint]) -> AtollWalletResult<Vec<u8>> {
+        if threshold == 1 {
+            return shares.first().map(|share| share.y.clone()).ok_or(
+                AtollWalletError::InvalidSlip39Share(
+                    "No share was provided to recover the secret".to_string(),
+                ),
+            );
+        }
+
+        let shared_secret = gf256::interpolate(shares, SECRET_INDEX)?;
+        let digest_share = gf256::interpolate(shares, DIGEST_INDEX)?;
+
+        let (digest, random_part) = digest_share.split_at(DIGEST_LENGTH);
+
+        if Self::digest(random_part, &shared_secret)? != digest {
+            return Err(AtollWalletError::Slip39DigestMismatch);
+        }
+
+        Ok(shared_secret)
+    }
+
+    fn digest(random_part: &[u8], secret: &[u8]) -> AtollWalletResult<[u8; DIGEST_LENGTH]> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(random_part)
+            .map_err(|error| AtollWalletError::InvalidSlip39Share(error.to_string()))?;
+        mac.update(secret);
+
+        let mut digest = [0u8; DIGEST_LENGTH];
+        digest.copy_from_slice(&mac.finalize().into_bytes()[..DIGEST_LENGTH]);
+
+        Ok(digest)
+    }
+}
+
+/// Collects shares one at a time and reports how many more are needed
+/// before the master secret can be recovered.
+#[derive(Debug, Default, Clone)]
+pub struct Slip39Recovery {
+    shares: Vec<Slip39Share>,
+}
+
+impl Slip39Recovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validates `mnemonic` against the shares already provided and adds it
+    pub fn add_mnemonic(&mut self, mnemonic: &str) -> AtollWalletResult<Slip39RecoveryStatus> {
+        let share = Slip39Share::from_mnemonic(mnemonic)?;
+
+        self.add_share(share)
+    }
+
+    /// Validates `share` against the shares already provided and adds it
+    pub fn add_share(&mut self, share: Slip39Share) -> AtollWalletResult<Slip39RecoveryStatus> {
+        if let Some(first) = self.shares.first() {
+            if first.identifier != share.identifier
+                || first.extendable != share.extendable
+                || first.iteration_exponent != share.iteration_exponent
+            {
+                return Err(AtollWalletError::Slip39ShareMismatch(
+                    "The share belongs to a different backup".to_string(),
+                ));
+            }
+
+            if first.group_threshold != share.group_threshold
+                || first.group_count != share.group_count
+            {
+                return Err(AtollWalletError::Slip39ShareMismatch(
+                    "The share has a different group configuration".to_string(),
+                ));
+            }
+        }
+
+        if let Some(existing) = self
+            .shares
+            .iter()
+            .find(|existing| existing.group_index == share.group_index)
+            && existing.member_threshold != share.member_threshold
+        {
+            return Err(AtollWalletError::Slip39ShareMismatch(format!(
+                "The share has a different member threshold than other shares in group `{}`",
+                share.group_index + 1
+            )));
+        }
+
+        if self.shares.iter().any(|existing| {
+            existing.group_index == share.group_index && existing.member_index == share.member_index
+        }) {
+            return Err(AtollWalletError::Slip39ShareMismatch(format!(
+                "Share `{}` of group `{}` was already provided",
+                share.member_index + 1,
+                share.group_index + 1
+            )));
+        }
+
+        self.shares.push(share);
+
+        Ok(self.status())
+    }
+
+    /// The progress of the recovery
+    pub fn status(&self) -> Slip39RecoveryStatus {
+        let mut groups = Vec::<Slip39GroupStatus>::default();
+
+        self.shares.iter().for_each(|share| {
+            if let Some(group) = groups
+                .iter_mut()
+                .find(|group| group.group_index == share.group_index)
+            {
+                group.members_provided += 1;
+            } else {
+                groups.push(Slip39GroupStatus {
+                    group_index: share.group_index,
+                    member_threshold: share.member_threshold,
+                    members_provided: 1,
+                });
+            }
+        });
+        groups.sort_by_key(|group| group.group_index);
+
+        let first = self.shares.first();
+
+        Slip39RecoveryStatus {
+            group_threshold: first.map(|share| share.group_threshold),
+            group_count: first.map(|share| share.group_count),
+            groups,
+        }
+    }
+
+    /// Recovers the master secret once enough shares have been provided
+    pub fn recover(&self, passphrase: &str) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+        let status = self.status();
+
+        let (group_threshold, first) = status.group_threshold.zip(self.shares.first()).ok_or(
+            AtollWalletError::InsufficientSlip39Shares(
+                "No shares have been provided yet".to_string(),
+            ),
+        )?;
+
+        if !status.is_complete() {
+            return Err(AtollWalletError::InsufficientSlip39Shares(
+                status.describe(),
+            ));
+        }
+
+        let group_shares = status
+            .groups
+            .iter()
+            .filter(|group| group.is_complete())
+            .take(group_threshold as usize)
+            .map(|group| {
+                let members = self
+                    .shares
+                    .iter()
+                    .filter(|share| share.group_index == group.group_index)
+                    .take(group.member_threshold as usize)
+                    .map(|share| SharePoint::new(share.member_index, share.value.to_vec()))
+                    .collect::<Vec<SharePoint>>();
+
+                Ok(SharePoint::new(
+                    group.group_index,
+                    Slip39::recover_secret(group.member_threshold, &members)?,
+                ))
+            })
+            .collect::<AtollWalletResult<Vec<SharePoint>>>()?;
+
+        let encrypted_master_secret =
+            Zeroizing::new(Slip39::recover_secret(group_threshold, &group_shares)?);
+
+        cipher::decrypt(
+            &encrypted_master_secret,
+            passphrase,
+            first.iteration_exponent,
+            first.identifier,
+            first.extendable,
+        )
+    }
+}
+
+/// Progress of a single group during recovery
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct Slip39GroupStatus {
+    pub group_index: u8,
+    pub member_threshold: u8,
+    pub members_provided: u8,
+}
+
+impl Slip39GroupStatus {
+    pub fn is_complete(&self) -> bool {
+        self.members_provided >= self.member_threshold
+    }
+
+    /// Number of shares still required to recover this group
+    pub fn members_needed(&self) -> u8 {
+        self.member_threshold.saturating_sub(self.members_provided)
+    }
+}
+
+/// Progress of recovering a master secret from shares
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Slip39RecoveryStatus {
+    /// `None` until the first share is provided
+    pub group_threshold: Option<u8>,
+    /// `None` until the first share is provided
+    pub group_count: Option<u8>,
+    pub groups: Vec<Slip39GroupStatus>,
+}
+
+impl Slip39RecoveryStatus {
+    /// Number of groups that can already be recovered
+    pub fn groups_complete(&self) -> u8 {
+        self.groups
+            .iter()
+            .filter(|group| group.is_complete())
+            .count() as u8
+    }
+
+    /// Number of groups that still need to be completed
+    pub fn groups_needed(&self) -> u8 {
+        self.group_threshold
+            .unwrap_or(1)
+            .saturating_sub(self.groups_complete())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.group_threshold.is_some() && self.groups_needed() == 0
+    }
+
+    /// The fewest additional shares from groups already started that would
+    /// complete the recovery. Returns `None` if shares from groups not yet
+    /// seen are needed since their member thresholds are not known yet.
+    pub fn shares_needed(&self) -> Option<u8> {
+        self.group_threshold?;
+
+        let groups_needed = self.groups_needed() as usize;
+
+        let mut incomplete = self
+            .groups
+            .iter()
+            .filter(|group| !group.is_complete())
+            .map(|group| group.members_needed())
+            .collect::<Vec<u8>>();
+
+        if incomplete.len() < groups_needed {
+            return None;
+        }
+
+        incomplete.sort_unstable();
+
+        Some(incomplete.iter().take(groups_needed).sum())
+    }
+
+    /// A human readable summary of the shares still needed
+    pub fn describe(&self) -> String {
+        if self.group_threshold.is_none() {
+            return "No shares have been provided yet".to_string();
+        }
+
+        if self.is_complete() {
+            return "Enough shares have been provided to recover the wallet".to_string();
+        }
+
+        match (self.group_count, self.shares_needed()) {
+            (Some(1), Some(shares_needed)) => {
+                format!("{shares_needed} more share(s) needed")
+            }
+            (_, Some(shares_needed)) => format!(
+                "{shares_needed} more share(s) needed to complete {} more group(s)",
+                self.groups_needed()
+            ),
+            (_, None) => format!(
+                "{} more group(s) needed. Shares from groups not yet provided are required",
+                self.groups_needed()
+            ),
+        }
+    }
+}
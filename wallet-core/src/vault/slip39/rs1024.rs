@@ -0,0 +1,54 @@
+/// Generator of the Reed-Solomon code over GF(1024) used for the SLIP-39 checksum
+const GENERATOR: [u32; 10] = [
+    0x00E0_E040,
+    0x01C1_C080,
+    0x0383_8100,
+    0x0707_0200,
+    0x0E0E_0009,
+    0x1C0C_2412,
+    0x3808_6C24,
+    0x3090_FC48,
+    0x21B1_F890,
+    0x03F3_F120,
+];
+
+/// Number of words used by the checksum at the end of every share
+pub(crate) const CHECKSUM_LENGTH_WORDS: usize = 3;
+
+fn polymod(customization: &[u8], values: &[u16]) -> u32 {
+    customization
+        .iter()
+        .map(|byte| *byte as u32)
+        .chain(values.iter().map(|value| *value as u32))
+        .fold(1u32, |checksum, value| {
+            let top = checksum >> 20;
+            let mut checksum = ((checksum & 0xF_FFFF) << 10) ^ value;
+
+            GENERATOR.iter().enumerate().for_each(|(index, generator)| {
+                if (top >> index) & 1 == 1 {
+                    checksum ^= generator;
+                }
+            });
+
+            checksum
+        })
+}
+
+/// Computes the three checksum words for `data`
+pub(crate) fn create_checksum(customization: &[u8], data: &[u16]) -> [u16; CHECKSUM_LENGTH_WORDS] {
+    let mut values = data.to_vec();
+    values.extend_from_slice(&[0u16; CHECKSUM_LENGTH_WORDS]);
+
+    let polymod = polymod(customization, &values) ^ 1;
+
+    [
+        ((polymod >> 20) & 1023) as u16,
+        ((polymod >> 10) & 1023) as u16,
+        (polymod & 1023) as u16,
+    ]
+}
+
+/// Checks that `data`, including the trailing checksum words, has a valid checksum
+pub(crate) fn verify_checksum(customization: &[u8], data: &[u16]) -> bool {
+    polymod(customization, data) == 1
+}
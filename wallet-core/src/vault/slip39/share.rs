@@ -0,0 +1,228 @@
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{AtollWalletError, AtollWalletResult};
+
+use super::{
+    MIN_MASTER_SECRET_LENGTH, customization_string,
+    rs1024::{self, CHECKSUM_LENGTH_WORDS},
+};
+
+const WORDLIST: &str = include_str!("wordlist_english.txt");
+
+/// Number of bits encoded by a single word of the wordlist
+const RADIX_BITS: usize = 10;
+
+/// Identifier, extendable flag, iteration exponent, group and member parameters take four words
+const HEADER_LENGTH_WORDS: usize = 4;
+
+/// Words used by the header and the checksum
+const METADATA_LENGTH_WORDS: usize = HEADER_LENGTH_WORDS + CHECKSUM_LENGTH_WORDS;
+
+/// The shortest share which encodes a 128 bit master secret
+const MIN_MNEMONIC_LENGTH_WORDS: usize =
+    METADATA_LENGTH_WORDS + (MIN_MASTER_SECRET_LENGTH * 8).div_ceil(RADIX_BITS);
+
+/// A single SLIP-39 share decoded from, or to be encoded into, a mnemonic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slip39Share {
+    /// Random 15 bit identifier common to all shares of a backup
+    pub identifier: u16,
+    /// Whether shares can be added to the backup later. The identifier is then left out of
+    /// the encryption of the master secret and the checksum uses `shamir_extendable`.
+    pub extendable: bool,
+    /// PBKDF2 uses `10000 * 2^iteration_exponent` iterations, a 4 bit value
+    pub iteration_exponent: u8,
+    /// The `x` value of the group share
+    pub group_index: u8,
+    /// The number of groups required to recover the master secret
+    pub group_threshold: u8,
+    /// The total number of groups
+    pub group_count: u8,
+    /// The `x` value of the member share within its group
+    pub member_index: u8,
+    /// The number of members of this group required to recover the group secret
+    pub member_threshold: u8,
+    pub(crate) value: Zeroizing<Vec<u8>>,
+}
+
+impl Slip39Share {
+    /// Parses and validates a space separated SLIP-39 mnemonic
+    pub fn from_mnemonic(mnemonic: &str) -> AtollWalletResult<Self> {
+        let words = mnemonic
+            .split_whitespace()
+            .map(Self::word_index)
+            .collect::<AtollWalletResult<Vec<u16>>>()?;
+
+        if words.len() < MIN_MNEMONIC_LENGTH_WORDS {
+            return Err(AtollWalletError::InvalidSlip39Share(format!(
+                "A share must have at least {MIN_MNEMONIC_LENGTH_WORDS} words but `{}` words were provided",
+                words.len()
+            )));
+        }
+
+        let padding_length = (RADIX_BITS * (words.len() - METADATA_LENGTH_WORDS)) % 16;
+        if padding_length > 8 {
+            return Err(AtollWalletError::InvalidSlip39Share(
+                "The share has an invalid number of words".to_string(),
+            ));
+        }
+
+        // The extendable flag is the 16th bit of the header, in the second word
+        let extendable = (words[1] >> 4) & 1 == 1;
+
+        if !rs1024::verify_checksum(customization_string(extendable), &words) {
+            return Err(AtollWalletError::InvalidSlip39Share(
+                "The checksum of the share is invalid. Check each word for mistakes".to_string(),
+            ));
+        }
+
+        let header = words[..HEADER_LENGTH_WORDS]
+            .iter()
+            .fold(0u64, |header, word| (header << RADIX_BITS) | *word as u64);
+
+        let identifier = (header >> 25) as u16;
+        let iteration_exponent = ((header >> 20) & 0xF) as u8;
+        let group_index = ((header >> 16) & 0xF) as u8;
+        let group_threshold = ((header >> 12) & 0xF) as u8 + 1;
+        let group_count = ((header >> 8) & 0xF) as u8 + 1;
+        let member_index = ((header >> 4) & 0xF) as u8;
+        let member_threshold = (header & 0xF) as u8 + 1;
+
+        if group_threshold > group_count {
+            return Err(AtollWalletError::InvalidSlip39Share(format!(
+                "The group threshold `{group_threshold}` is greater than the group count `{group_count}`"
+            )));
+        }
+
+        let value_words = &words[HEADER_LENGTH_WORDS..words.len() - CHECKSUM_LENGTH_WORDS];
+        let value = Self::words_to_bytes(value_words, padding_length)?;
+
+        Ok(Self {
+            identifier,
+            extendable,
+            iteration_exponent,
+            group_index,
+            group_threshold,
+            group_count,
+            member_index,
+            member_threshold,
+            value,
+        })
+    }
+
+    /// Encodes the share into a space separated SLIP-39 mnemonic
+    pub fn to_mnemonic(&self) -> Zeroizing<String> {
+        let header = ((self.identifier as u64) << 25)
+            | ((self.extendable as u64) << 24)
+            | ((self.iteration_exponent as u64) << 20)
+            | ((self.group_index as u64) << 16)
+            | (((self.group_threshold - 1) as u64) << 12)
+            | (((self.group_count - 1) as u64) << 8)
+            | ((self.member_index as u64) << 4)
+            | ((self.member_threshold - 1) as u64);
+
+        let mut words = (0..HEADER_LENGTH_WORDS)
+            .rev()
+            .map(|position| ((header >> (position * RADIX_BITS)) & 1023) as u16)
+            .collect::<Vec<u16>>();
+
+        words.extend(Self::bytes_to_words(&self.value));
+        let checksum = rs1024::create_checksum(customization_string(self.extendable), &words);
+        words.extend_from_slice(&checksum);
+
+        let mnemonic = Zeroizing::new(
+            words
+                .iter()
+                .map(|index| Self::word(*index))
+                .collect::<Vec<&str>>()
+                .join(" "),
+        );
+        words.zeroize();
+
+        mnemonic
+    }
+
+    /// The value of the share
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    fn word(index: u16) -> &'static str {
+        WORDLIST.lines().nth(index as usize).unwrap_or_default()
+    }
+
+    fn word_index(word: &str) -> AtollWalletResult<u16> {
+        let word = word.to_lowercase();
+
+        WORDLIST
+            .lines()
+            .position(|candidate| candidate == word)
+            .map(|index| index as u16)
+            .ok_or(AtollWalletError::InvalidSlip39Share(format!(
+                "The word `{word}` is not part of the SLIP-39 wordlist"
+            )))
+    }
+
+    fn bytes_to_words(bytes: &[u8]) -> Vec<u16> {
+        let word_count = (bytes.len() * 8).div_ceil(RADIX_BITS);
+        let mut words = Vec::with_capacity(word_count);
+
+        let mut accumulator = 0u32;
+        let mut bits = word_count * RADIX_BITS - bytes.len() * 8;
+
+        for byte in bytes {
+            accumulator = (accumulator << 8) | *byte as u32;
+            bits += 8;
+
+            while bits >= RADIX_BITS {
+                bits -= RADIX_BITS;
+                words.push(((accumulator >> bits) & 1023) as u16);
+            }
+
+            accumulator &= (1 << bits) - 1;
+        }
+
+        words
+    }
+
+    fn words_to_bytes(
+        words: &[u16],
+        padding_length: usize,
+    ) -> AtollWalletResult<Zeroizing<Vec<u8>>> {
+        let mut bytes = Zeroizing::new(Vec::with_capacity(words.len() * RADIX_BITS / 8));
+
+        let mut accumulator = 0u32;
+        let mut bits = 0usize;
+        let mut padding_remaining = padding_length;
+
+        for word in words {
+            accumulator = (accumulator << RADIX_BITS) | *word as u32;
+            bits += RADIX_BITS;
+
+            if padding_remaining > 0 {
+                bits -= padding_remaining;
+                if accumulator >> bits != 0 {
+                    return Err(AtollWalletError::InvalidSlip39Share(
+                        "The padding bits of the share are not zero".to_string(),
+                    ));
+                }
+                padding_remaining = 0;
+            }
+
+            while bits >= 8 {
+                bits -= 8;
+                bytes.push(((accumulator >> bits) & 0xFF) as u8);
+            }
+
+            accumulator &= (1 << bits) - 1;
+        }
+
+        if bytes.len() < MIN_MASTER_SECRET_LENGTH {
+            return Err(AtollWalletError::InvalidSlip39Share(format!(
+                "The share value must be at least {MIN_MASTER_SECRET_LENGTH} bytes long"
+            )));
+        }
+
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,280 @@
+use bip39::{Language, Mnemonic, MnemonicType, Seed};
+use zeroize::Zeroizing;
+
+use super::sealed;
+use crate::{
+    AtollWalletError, AtollWalletResult, BitcoinAccountKeypair, BitcoinCluster, BitcoinKeychain,
+    BitcoinPurpose, ConnectedSite, ConnectedSites, DappPolicies, DomainReputation, Slip39,
+    Slip39Config, Slip39Recovery, SolanaAccountKeypair,
+};
+
+const TEST_PASSPHRASE: &str = "quick brown fox";
+
+const TEST_MNEMONIC: &str =
+    "wrap kingdom punch clog kiss useless celery exist bulk catch share creek";
+
+/// Holds the seed entropy of the wallet mnemonic from which accounts are derived.
+///
+/// The entropy, not the seed, is what is backed up so that the same mnemonic
+/// and therefore the same accounts can be recovered.
+pub struct WalletVault {
+    entropy: Zeroizing<Vec<u8>>,
+    passphrase: Zeroizing<String>,
+    policies: DappPolicies,
+    reputation: DomainReputation,
+    sites: ConnectedSites,
+}
+
+impl WalletVault {
+    /// The PBKDF2 iterations deriving the key of [WalletVault::seal] from the password,
+    /// also the fewest [WalletVault::unseal] accepts
+    pub const SEAL_ITERATIONS: u32 = 600_000;
+
+    /// The shortest password accepted by [WalletVault::seal]
+    pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+    /// The vault of the public test mnemonic, which must never hold funds
+    pub fn new_test() -> AtollWalletResult<Self> {
+        Self::new_from_mnemonic(
+            Zeroizing::new(TEST_MNEMONIC.to_string()),
+            Some(Zeroizing::new(TEST_PASSPHRASE.to_string())),
+        )
+    }
+
+    /// Generates a new 12 word mnemonic and returns the vault together with the phrase
+    pub fn new(
+        passphrase: Option<Zeroizing<String>>,
+    ) -> AtollWalletResult<(Self, Zeroizing<String>)> {
+        let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+        let phrase = Zeroizing::new(mnemonic.phrase().to_owned());
+
+        Ok((
+            Self {
+                entropy: Zeroizing::new(mnemonic.entropy().to_vec()),
+                passphrase: passphrase.unwrap_or_default(),
+                policies: DappPolicies::default(),
+                reputation: DomainReputation::default(),
+                sites: ConnectedSites::default(),
+            },
+            phrase,
+        ))
+    }
+
+    pub fn new_from_mnemonic(
+        mnemonic: Zeroizing<String>,
+        passphrase: Option<Zeroizing<String>>,
+    ) -> AtollWalletResult<Self> {
+        let mnemonic = Mnemonic::from_phrase(&mnemonic, Language::English)?;
+
+        Ok(Self {
+            entropy: Zeroizing::new(mnemonic.entropy().to_vec()),
+            passphrase: passphrase.unwrap_or_default(),
+            policies: DappPolicies::default(),
+            reputation: DomainReputation::default(),
+            sites: ConnectedSites::default(),
+        })
+    }
+
+    /// Whether this is the vault of the public test mnemonic
+    pub fn is_test(&self) -> bool {
+        Mnemonic::from_phrase(TEST_MNEMONIC, Language::English)
+            .is_ok_and(|mnemonic| mnemonic.entropy() == self.entropy.as_slice())
+    }
+
+    /// Encrypts the entropy and passphrase with `password`, see [WalletVault::unseal].
+    /// The permissions are not part of it, they are stored on their own.
+    pub fn seal(&self, password: &str) -> AtollWalletResult<String> {
+        if self.is_test() {
+            return Err(AtollWalletError::InvalidParams(
+                "The test mnemonic is public and must not be stored".to_string(),
+            ));
+        }
+
+        if password.chars().count() < Self::MIN_PASSWORD_LENGTH {
+            return Err(AtollWalletError::InvalidParams(format!(
+                "The password must have at least {} characters",
+                Self::MIN_PASSWORD_LENGTH
+            )));
+        }
+
+        let mut secret = Zeroizing::new(vec![self.entropy.len() as u8]);
+        secret.extend_from_slice(&self.entropy);
+        secret.extend_from_slice(self.passphrase.as_bytes());
+
+        sealed::seal(&secret, password)
+    }
+
+    /// Decrypts a vault sealed with [WalletVault::seal]
+    pub fn unseal(json: &str, password: &str) -> AtollWalletResult<Self> {
+        let secret = sealed::unseal(json, password)?;
+
+        let invalid = || AtollWalletError::Input("The stored vault is not valid".to_string());
+
+        let (entropy_length, rest) = secret.split_first().ok_or_else(invalid)?;
+        let entropy_length = *entropy_length as usize;
+        if rest.len() < entropy_length {
+            return Err(invalid());
+        }
+        let (entropy, passphrase) = rest.split_at(entropy_length);
+
+        Mnemonic::from_entropy(entropy, Language::English)?;
+
+        Ok(Self {
+            entropy: Zeroizing::new(entropy.to_vec()),
+            passphrase: Zeroizing::new(
+                String::from_utf8(passphrase.to_vec()).map_err(|_| invalid())?,
+            ),
+            policies: DappPolicies::default(),
+            reputation: DomainReputation::default(),
+            sites: ConnectedSites::default(),
+        })
+    }
+
+    /// The mnemonic phrase encoding the entropy of this vault
+    pub fn mnemonic(&self) -> AtollWalletResult<Zeroizing<String>> {
+        let mnemonic = Mnemonic::from_entropy(&self.entropy, Language::English)?;
+
+        Ok(Zeroizing::new(mnemonic.phrase().to_owned()))
+    }
+
+    /// The permissions granted to dapps, see [DappPolicies::to_json] to store them
+    pub fn policies(&self) -> &DappPolicies {
+        &self.policies
+    }
+
+    pub fn policies_mut(&mut self) -> &mut DappPolicies {
+        &mut self.policies
+    }
+
+    /// Replaces the policies, for example with those read back with [DappPolicies::from_json]
+    pub fn set_policies(&mut self, policies: DappPolicies) -> &mut Self {
+        self.policies = policies;
+
+        self
+    }
+
+    /// The phishing protection checking the origins of dapps, see
+    /// [DomainReputation::to_json] to store it
+    pub fn reputation(&self) -> &DomainReputation {
+        &self.reputation
+    }
+
+    pub fn reputation_mut(&mut self) -> &mut DomainReputation {
+        &mut self.reputation
+    }
+
+    /// Replaces the reputation, for example with one read back with [DomainReputation::from_json]
+    pub fn set_reputation(&mut self, reputation: DomainReputation) -> &mut Self {
+        self.reputation = reputation;
+
+        self
+    }
+
+    /// The sites the user connected, see [ConnectedSites::to_json] to store them
+    pub fn sites(&self) -> &ConnectedSites {
+        &self.sites
+    }
+
+    pub fn sites_mut(&mut self) -> &mut ConnectedSites {
+        &mut self.sites
+    }
+
+    /// Replaces the sites, for example with those read back with [ConnectedSites::from_json]
+    pub fn set_sites(&mut self, sites: ConnectedSites) -> &mut Self {
+        self.sites = sites;
+
+        self
+    }
+
+    /// Disconnects the site at `origin`, removing its policy too so that it must connect
+    /// again before it can request signatures
+    pub fn revoke_site(&mut self, origin: &str) -> Option<ConnectedSite> {
+        self.policies.remove(origin);
+
+        self.sites.revoke(origin)
+    }
+
+    /// Disconnects every site and removes every policy, returning the origins that were
+    /// connected
+    pub fn revoke_all_sites(&mut self) -> Vec<String> {
+        self.policies = DappPolicies::default();
+
+        self.sites.revoke_all()
+    }
+
+    /// Derives the Solana account from the mnemonic and passphrase of this vault
+    pub fn solana_keypair(&self) -> AtollWalletResult<SolanaAccountKeypair> {
+        SolanaAccountKeypair::new_from_mnemonic(self.mnemonic()?, Some(self.passphrase.clone()))
+    }
+
+    /// Derives the first receive address of the BIP84 or BIP86 Bitcoin `account`
+    /// from the BIP39 seed of this vault
+    pub fn bitcoin_keypair(
+        &self,
+        purpose: BitcoinPurpose,
+        cluster: BitcoinCluster,
+        account: u32,
+    ) -> AtollWalletResult<BitcoinAccountKeypair> {
+        self.bitcoin_keypair_in_keychain(purpose, cluster, account, BitcoinKeychain::External)
+    }
+
+    /// Derives the first change address of the BIP84 or BIP86 Bitcoin `account`
+    pub fn bitcoin_change_keypair(
+        &self,
+        purpose: BitcoinPurpose,
+        cluster: BitcoinCluster,
+        account: u32,
+    ) -> AtollWalletResult<BitcoinAccountKeypair> {
+        self.bitcoin_keypair_in_keychain(purpose, cluster, account, BitcoinKeychain::Internal)
+    }
+
+    fn bitcoin_keypair_in_keychain(
+        &self,
+        purpose: BitcoinPurpose,
+        cluster: BitcoinCluster,
+        account: u32,
+        keychain: BitcoinKeychain,
+    ) -> AtollWalletResult<BitcoinAccountKeypair> {
+        let mnemonic = Mnemonic::from_entropy(&self.entropy, Language::English)?;
+        let seed = Zeroizing::new(Seed::new(&mnemonic, &self.passphrase).as_bytes().to_vec());
+
+        BitcoinAccountKeypair::new_from_seed(&seed, purpose, cluster, account, keychain)
+    }
+
+    /// Splits the seed entropy into SLIP-39 mnemonic shares.
+    /// The `slip39_passphrase` is required together with the shares to recover the vault.
+    pub fn split_into_shares(
+        &self,
+        config: &Slip39Config,
+        slip39_passphrase: &str,
+    ) -> AtollWalletResult<Vec<Vec<Zeroizing<String>>>> {
+        if self.is_test() {
+            return Err(AtollWalletError::InvalidSlip39Config(
+                "The test mnemonic is public and must not be backed up".to_string(),
+            ));
+        }
+
+        Slip39::split(&self.entropy, slip39_passphrase, config)
+    }
+
+    /// Recovers a vault from the SLIP-39 shares collected by `recovery`.
+    /// The `passphrase` is the BIP39 passphrase the original accounts were derived with.
+    pub fn recover_from_shares(
+        recovery: &Slip39Recovery,
+        slip39_passphrase: &str,
+        passphrase: Option<Zeroizing<String>>,
+    ) -> AtollWalletResult<Self> {
+        let entropy = recovery.recover(slip39_passphrase)?;
+
+        // Ensures the recovered entropy is a valid BIP39 entropy length
+        Mnemonic::from_entropy(&entropy, Language::English)?;
+
+        Ok(Self {
+            entropy,
+            passphrase: passphrase.unwrap_or_default(),
+            policies: DappPolicies::default(),
+            reputation: DomainReputation::default(),
+            sites: ConnectedSites::default(),
+        })
+    }
+}
@@ -0,0 +1,648 @@
+//! Instructions decoded into activity rows, the paginated activity log and the history
+//! of an account merged from a scripted cluster
+
+mod common;
+
+use atoll_wallet_core::{
+    App, AtollWalletError, SolanaActivityDirection, SolanaActivityEntry, SolanaActivityLog,
+    SolanaActivityRow, SolanaCluster, SolanaCommitment, SolanaConfirmedTransaction,
+    SolanaInstructions, SolanaSignatureStatus, SolanaTokenTransfer, SolanaTransferBuilder,
+    SplTokenProgram, SplTransferFee, WalletVault,
+};
+use serde_json::{Value, json};
+use solana_message::compiled_instruction::CompiledInstruction;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use zeroize::Zeroizing;
+
+use common::{FakeRuntime, MockRpcServer, NOW_MS};
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+const EXTENSION_ORIGIN: &str = "chrome-extension://atoll";
+
+fn pubkey(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+fn signature(byte: u8) -> Signature {
+    Signature::from([byte; 64])
+}
+
+/// The first and last four characters of `address` as the descriptions show them
+fn short(address: &Pubkey) -> String {
+    let address = address.to_string();
+
+    format!("{}…{}", &address[..4], &address[address.len() - 4..])
+}
+
+fn system_transfer_data(lamports: u64) -> Vec<u8> {
+    let mut data = 2u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&lamports.to_le_bytes());
+
+    data
+}
+
+#[test]
+fn the_instructions_the_wallet_builds_are_decoded() {
+    let transfer = SolanaTokenTransfer {
+        mint: pubkey(5),
+        program: SplTokenProgram::Token2022,
+        decimals: 6,
+        transfer_fee: Some(SplTransferFee {
+            epoch: 0,
+            maximum_fee: 100,
+            basis_points: 100,
+        }),
+        ..Default::default()
+    };
+    let message = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 2_500_000)
+        .set_token(transfer)
+        .set_memo("invoice 42")
+        .set_priority_fee(1_000)
+        .build()
+        .unwrap()
+        .to_transaction(solana_hash::Hash::default())
+        .message;
+
+    let rows = SolanaActivityRow::from_instructions(&message.account_keys, &message.instructions);
+
+    // The compute budget is left out
+    assert_eq!(
+        rows,
+        [
+            SolanaActivityRow::CreateTokenAccount {
+                account: SolanaInstructions::associated_token_address(
+                    &pubkey(2),
+                    &pubkey(5),
+                    SplTokenProgram::Token2022
+                ),
+                wallet: pubkey(2),
+                mint: pubkey(5),
+            },
+            SolanaActivityRow::Memo("invoice 42".to_string()),
+            SolanaActivityRow::TokenTransfer {
+                program: SplTokenProgram::Token2022,
+                source: SolanaInstructions::associated_token_address(
+                    &pubkey(1),
+                    &pubkey(5),
+                    SplTokenProgram::Token2022
+                ),
+                destination: SolanaInstructions::associated_token_address(
+                    &pubkey(2),
+                    &pubkey(5),
+                    SplTokenProgram::Token2022
+                ),
+                authority: pubkey(1),
+                mint: Some(pubkey(5)),
+                amount: 2_500_000,
+                decimals: Some(6),
+                fee: Some(100),
+            },
+        ]
+    );
+
+    let message = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_500_000)
+        .build()
+        .unwrap()
+        .to_transaction(solana_hash::Hash::default())
+        .message;
+    assert_eq!(
+        SolanaActivityRow::from_instructions(&message.account_keys, &message.instructions),
+        [SolanaActivityRow::SolTransfer {
+            from: pubkey(1),
+            to: pubkey(2),
+            lamports: 1_500_000,
+        }]
+    );
+}
+
+#[test]
+fn other_instructions_are_decoded_or_named_by_their_program() {
+    let token = SplTokenProgram::TOKEN_PROGRAM_ID;
+    let system = SolanaInstructions::SYSTEM_PROGRAM_ID;
+
+    // `Transfer` knows neither the mint nor the decimals
+    let mut transfer_data = vec![3u8];
+    transfer_data.extend_from_slice(&7u64.to_le_bytes());
+    assert_eq!(
+        SolanaActivityRow::parse(&token, &[pubkey(1), pubkey(2), pubkey(3)], &transfer_data),
+        Some(SolanaActivityRow::TokenTransfer {
+            program: SplTokenProgram::Token,
+            source: pubkey(1),
+            destination: pubkey(2),
+            authority: pubkey(3),
+            mint: None,
+            amount: 7,
+            decimals: None,
+            fee: None,
+        })
+    );
+
+    let mut data = 0u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&890_880u64.to_le_bytes());
+    data.extend_from_slice(&165u64.to_le_bytes());
+    data.extend_from_slice(token.as_ref());
+    assert_eq!(
+        SolanaActivityRow::parse(&system, &[pubkey(1), pubkey(2)], &data),
+        Some(SolanaActivityRow::CreateAccount {
+            funder: pubkey(1),
+            account: pubkey(2),
+            lamports: 890_880,
+            owner: token,
+        })
+    );
+
+    // `Create` of the associated token program, with and without its instruction byte
+    let accounts = [pubkey(1), pubkey(4), pubkey(2), pubkey(5)];
+    for data in [&[][..], &[0]] {
+        assert_eq!(
+            SolanaActivityRow::parse(
+                &SolanaInstructions::ASSOCIATED_TOKEN_PROGRAM_ID,
+                &accounts,
+                data
+            ),
+            Some(SolanaActivityRow::CreateTokenAccount {
+                account: pubkey(4),
+                wallet: pubkey(2),
+                mint: pubkey(5),
+            })
+        );
+    }
+
+    assert_eq!(
+        SolanaActivityRow::parse(&SolanaActivityRow::MEMO_V1_PROGRAM_ID, &[], b"gm"),
+        Some(SolanaActivityRow::Memo("gm".to_string()))
+    );
+    assert_eq!(
+        SolanaActivityRow::parse(&SolanaInstructions::COMPUTE_BUDGET_PROGRAM_ID, &[], &[3]),
+        None
+    );
+
+    // Unknown programs, unknown instructions, truncated data and missing accounts
+    let program_call = |program_id: Pubkey| Some(SolanaActivityRow::ProgramCall { program_id });
+    assert_eq!(
+        SolanaActivityRow::parse(&pubkey(9), &[pubkey(1)], &[1, 2, 3]),
+        program_call(pubkey(9))
+    );
+    assert_eq!(
+        SolanaActivityRow::parse(&system, &[pubkey(1), pubkey(2)], &8u32.to_le_bytes()),
+        program_call(system)
+    );
+    assert_eq!(
+        SolanaActivityRow::parse(&system, &[pubkey(1), pubkey(2)], &[2, 0, 0, 0, 1]),
+        program_call(system)
+    );
+    assert_eq!(
+        SolanaActivityRow::parse(&token, &[pubkey(1)], &transfer_data),
+        program_call(token)
+    );
+
+    // Instructions referencing accounts the message does not have are skipped
+    let instructions = [
+        CompiledInstruction::new_from_raw_parts(1, system_transfer_data(1), vec![0, 7]),
+        CompiledInstruction::new_from_raw_parts(1, system_transfer_data(2), vec![0, 2]),
+    ];
+    assert_eq!(
+        SolanaActivityRow::from_instructions(&[pubkey(1), system, pubkey(2)], &instructions),
+        [SolanaActivityRow::SolTransfer {
+            from: pubkey(1),
+            to: pubkey(2),
+            lamports: 2,
+        }]
+    );
+}
+
+#[test]
+fn rows_are_described_from_the_point_of_view_of_the_account() {
+    let row = SolanaActivityRow::SolTransfer {
+        from: pubkey(1),
+        to: pubkey(2),
+        lamports: 1_500_000,
+    };
+    assert_eq!(row.kind(), "solTransfer");
+    assert_eq!(
+        row.direction(&pubkey(1)),
+        Some(SolanaActivityDirection::Sent)
+    );
+    assert_eq!(
+        row.description(&pubkey(1)),
+        format!("Sent 0.0015 SOL to {}", short(&pubkey(2)))
+    );
+    assert_eq!(
+        row.direction(&pubkey(2)),
+        Some(SolanaActivityDirection::Received)
+    );
+    assert_eq!(
+        row.description(&pubkey(2)),
+        format!("Received 0.0015 SOL from {}", short(&pubkey(1)))
+    );
+    assert_eq!(row.direction(&pubkey(3)), None);
+    assert_eq!(
+        row.description(&pubkey(3)),
+        format!(
+            "Transferred 0.0015 SOL from {} to {}",
+            short(&pubkey(1)),
+            short(&pubkey(2))
+        )
+    );
+
+    // Tokens are received in the associated token account of the account
+    let destination = SolanaInstructions::associated_token_address(
+        &pubkey(2),
+        &pubkey(5),
+        SplTokenProgram::Token,
+    );
+    let row = SolanaActivityRow::TokenTransfer {
+        program: SplTokenProgram::Token,
+        source: pubkey(7),
+        destination,
+        authority: pubkey(1),
+        mint: Some(pubkey(5)),
+        amount: 2_000_000,
+        decimals: Some(6),
+        fee: None,
+    };
+    assert_eq!(
+        row.direction(&pubkey(2)),
+        Some(SolanaActivityDirection::Received)
+    );
+    assert_eq!(
+        row.description(&pubkey(2)),
+        format!(
+            "Received 2 of {} from the token account {}",
+            short(&pubkey(5)),
+            short(&pubkey(7))
+        )
+    );
+    assert_eq!(
+        row.description(&pubkey(1)),
+        format!(
+            "Sent 2 of {} to the token account {}",
+            short(&pubkey(5)),
+            short(&destination)
+        )
+    );
+
+    assert_eq!(
+        SolanaActivityRow::Memo("gm".to_string()).description(&pubkey(1)),
+        "Memo: gm"
+    );
+    assert_eq!(
+        SolanaActivityRow::ProgramCall {
+            program_id: pubkey(9)
+        }
+        .description(&pubkey(1)),
+        format!("Called the program {}", short(&pubkey(9)))
+    );
+}
+
+fn signed_entry(byte: u8, cluster: SolanaCluster, signed_at: f64) -> SolanaActivityEntry {
+    let message = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_000)
+        .build()
+        .unwrap()
+        .to_transaction(solana_hash::Hash::default())
+        .message;
+
+    SolanaActivityEntry::signed(
+        signature(byte),
+        cluster,
+        Some("https://dapp.example".to_string()),
+        &message,
+        signed_at,
+    )
+}
+
+fn confirmed_entry(byte: u8, block_time: i64) -> SolanaActivityEntry {
+    SolanaActivityEntry::confirmed(
+        SolanaCluster::Devnet,
+        &SolanaConfirmedTransaction {
+            signature: signature(byte),
+            slot: block_time as u64,
+            block_time: Some(block_time),
+            fee: Some(5_000),
+            error: None,
+            account_keys: vec![pubkey(1), pubkey(2), SolanaInstructions::SYSTEM_PROGRAM_ID],
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                2,
+                system_transfer_data(1_000),
+                vec![0, 1],
+            )],
+        },
+    )
+}
+
+#[test]
+fn the_log_is_ordered_merged_and_paginated() {
+    let mut log = SolanaActivityLog::default();
+    log.record(confirmed_entry(1, 1_000));
+    log.record(signed_entry(2, SolanaCluster::Devnet, 3_000_000.0));
+    log.record(confirmed_entry(3, 2_000));
+    log.record(signed_entry(4, SolanaCluster::Mainnet, 4_000_000.0));
+
+    let devnet = |log: &SolanaActivityLog| {
+        log.entries()
+            .iter()
+            .filter(|entry| entry.cluster == SolanaCluster::Devnet)
+            .map(|entry| entry.signature)
+            .collect::<Vec<Signature>>()
+    };
+    assert_eq!(devnet(&log), [signature(2), signature(3), signature(1)]);
+
+    // The cluster reports a transaction signed locally
+    let mut landed = confirmed_entry(2, 2_500);
+    landed.rows.clear();
+    log.record(landed);
+    let entry = log.get(&signature(2)).unwrap();
+    assert_eq!(entry.origin.as_deref(), Some("https://dapp.example"));
+    assert_eq!(entry.signed_at, Some(3_000_000.0));
+    assert_eq!(entry.block_time, Some(2_500));
+    assert_eq!(entry.fee, Some(5_000));
+    assert_eq!(entry.rows.len(), 1);
+    assert_eq!(devnet(&log), [signature(2), signature(3), signature(1)]);
+
+    let page = log.page(SolanaCluster::Devnet, None, 2).unwrap();
+    assert_eq!(
+        page.entries
+            .iter()
+            .map(|entry| entry.signature)
+            .collect::<Vec<Signature>>(),
+        [signature(2), signature(3)]
+    );
+    assert_eq!(page.next_cursor, Some(signature(3)));
+
+    let page = log
+        .page(SolanaCluster::Devnet, page.next_cursor.as_ref(), 2)
+        .unwrap();
+    assert_eq!(page.entries.len(), 1);
+    assert_eq!(page.entries[0].signature, signature(1));
+    assert_eq!(page.next_cursor, None);
+
+    // A cursor on another cluster is not in the page
+    let error = log
+        .page(SolanaCluster::Devnet, Some(&signature(4)), 2)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        AtollWalletError::Input(message) if message.contains("is not in the activity of the account")
+    ));
+}
+
+#[test]
+fn entries_settle_or_expire() {
+    let mut entry = signed_entry(1, SolanaCluster::Devnet, NOW_MS);
+    assert_eq!(entry.status(), "pending");
+    assert!(!entry.is_settled());
+    assert_eq!(entry.timestamp(), NOW_MS);
+
+    entry.expire(NOW_MS + SolanaActivityEntry::EXPIRY_MS);
+    assert_eq!(entry.status(), "pending");
+
+    let mut status = SolanaSignatureStatus {
+        signature: signature(1),
+        slot: 42,
+        block_time: Some(1_700_000_000),
+        commitment: Some(SolanaCommitment::Confirmed),
+        error: None,
+    };
+    entry.update_status(&status);
+    assert_eq!(entry.status(), "confirmed");
+    assert_eq!(entry.timestamp(), 1_700_000_000_000.0);
+    assert!(!entry.is_settled());
+
+    status.commitment = Some(SolanaCommitment::Finalized);
+    entry.update_status(&status);
+    assert_eq!(entry.status(), "finalized");
+    assert!(entry.is_settled());
+
+    // A transaction the cluster never saw is dropped once its blockhash expired
+    let mut entry = signed_entry(2, SolanaCluster::Devnet, NOW_MS);
+    entry.expire(NOW_MS + SolanaActivityEntry::EXPIRY_MS + 1.0);
+    assert_eq!(entry.status(), "failed");
+    assert!(entry.is_settled());
+}
+
+fn signature_info(signature: &Signature, block_time: i64, err: Value, status: &str) -> Value {
+    json!({
+        "signature": signature.to_string(),
+        "slot": block_time,
+        "err": err,
+        "memo": null,
+        "blockTime": block_time,
+        "confirmationStatus": status,
+    })
+}
+
+fn transaction(
+    block_time: i64,
+    err: Value,
+    account_keys: &[Pubkey],
+    writable: &[Pubkey],
+    program_id_index: u8,
+    accounts: &[u8],
+    data: &[u8],
+) -> Value {
+    let keys = |keys: &[Pubkey]| {
+        keys.iter()
+            .map(|key| key.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    json!({
+        "slot": block_time,
+        "blockTime": block_time,
+        "meta": {
+            "err": err,
+            "fee": 5_000,
+            "loadedAddresses": { "writable": keys(writable), "readonly": [] },
+        },
+        "transaction": {
+            "signatures": [],
+            "message": {
+                "accountKeys": keys(account_keys),
+                "header": {
+                    "numRequiredSignatures": 1,
+                    "numReadonlySignedAccounts": 0,
+                    "numReadonlyUnsignedAccounts": 1,
+                },
+                "recentBlockhash": solana_hash::Hash::default().to_string(),
+                "instructions": [{
+                    "programIdIndex": program_id_index,
+                    "accounts": accounts,
+                    "data": bs58::encode(data).into_string(),
+                }],
+            },
+        },
+    })
+}
+
+#[test]
+fn the_activity_message_merges_the_history_on_chain() {
+    let vault = WalletVault::new_from_mnemonic(Zeroizing::new(MNEMONIC.to_string()), None).unwrap();
+    let owner = vault.solana_keypair().unwrap().pubkey();
+
+    let sent = signature(1);
+    let received = signature(2);
+    let failed = signature(3);
+    let now = (NOW_MS / 1000.0) as i64;
+
+    let server = MockRpcServer::start();
+    server
+        .script_cluster(
+            &solana_hash::Hash::new_from_array([7u8; 32]),
+            4_000,
+            &[],
+            &sent.to_string(),
+        )
+        .reply(
+            "getSignaturesForAddress",
+            json!([
+                signature_info(&received, now - 60, Value::Null, "confirmed"),
+                signature_info(
+                    &failed,
+                    now - 120,
+                    json!({ "InstructionError": [0, { "Custom": 1 }] }),
+                    "finalized"
+                ),
+            ]),
+        )
+        .reply_matching(
+            "getSignaturesForAddress",
+            &received.to_string(),
+            json!([signature_info(
+                &failed,
+                now - 120,
+                json!({ "InstructionError": [0, { "Custom": 1 }] }),
+                "finalized"
+            )]),
+        )
+        // The account is loaded from a lookup table after the static keys
+        .reply_matching(
+            "getTransaction",
+            &received.to_string(),
+            transaction(
+                now - 60,
+                Value::Null,
+                &[pubkey(9), SolanaInstructions::SYSTEM_PROGRAM_ID],
+                &[owner],
+                1,
+                &[0, 2],
+                &system_transfer_data(1_000_000_000),
+            ),
+        )
+        .reply_matching(
+            "getTransaction",
+            &failed.to_string(),
+            transaction(
+                now - 120,
+                json!({ "InstructionError": [0, { "Custom": 1 }] }),
+                &[owner, SolanaActivityRow::MEMO_V1_PROGRAM_ID],
+                &[],
+                1,
+                &[],
+                b"gm",
+            ),
+        )
+        // The transfer sent from the popup has not landed yet
+        .reply(
+            "getSignatureStatuses",
+            json!({ "context": { "slot": 1 }, "value": [null] }),
+        );
+
+    let app = App::new(server.transport())
+        .set_extension_origin(EXTENSION_ORIGIN)
+        .set_vault(vault)
+        .unwrap();
+    let runtime = FakeRuntime::new();
+    common::listen(app, &runtime);
+
+    let response = runtime.request(
+        "send",
+        "atoll:solanaSend",
+        EXTENSION_ORIGIN,
+        json!({ "recipient": pubkey(2).to_string(), "amount": "1500000" }),
+    );
+    assert_eq!(
+        response["ok"]["signature"],
+        json!(sent.to_string()),
+        "{response}"
+    );
+
+    let activity = |params: Value| {
+        let response =
+            runtime.request("activity", "atoll:solanaActivity", EXTENSION_ORIGIN, params);
+        assert!(response["err"].is_null(), "{response}");
+
+        response["ok"].clone()
+    };
+
+    let page = activity(json!({ "limit": 2 }));
+    let entries = page["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(page["nextCursor"], json!(received.to_string()));
+
+    assert_eq!(entries[0]["signature"], json!(sent.to_string()));
+    assert_eq!(entries[0]["status"], json!("pending"));
+    assert_eq!(entries[0]["signedAt"].as_f64(), Some(NOW_MS));
+    assert_eq!(entries[0]["origin"], Value::Null);
+    assert_eq!(entries[0]["rows"][0]["direction"], json!("sent"));
+    assert_eq!(
+        entries[0]["rows"][0]["description"],
+        json!(format!("Sent 0.0015 SOL to {}", short(&pubkey(2))))
+    );
+
+    assert_eq!(entries[1]["signature"], json!(received.to_string()));
+    assert_eq!(entries[1]["status"], json!("confirmed"));
+    assert_eq!(entries[1]["chain"], json!("solana:devnet"));
+    assert_eq!(entries[1]["fee"].as_f64(), Some(5_000.0));
+    assert_eq!(entries[1]["blockTime"].as_f64(), Some((now - 60) as f64));
+    assert_eq!(entries[1]["rows"][0]["kind"], json!("solTransfer"));
+    assert_eq!(entries[1]["rows"][0]["direction"], json!("received"));
+    assert_eq!(entries[1]["rows"][0]["amount"], json!("1000000000"));
+    assert_eq!(
+        entries[1]["rows"][0]["description"],
+        json!(format!("Received 1 SOL from {}", short(&pubkey(9))))
+    );
+
+    // The next page continues the history on chain from the cursor
+    let page = activity(json!({ "limit": 2, "before": received.to_string() }));
+    let entries = page["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(page["nextCursor"], Value::Null);
+    assert_eq!(entries[0]["signature"], json!(failed.to_string()));
+    assert_eq!(entries[0]["status"], json!("failed"));
+    assert_eq!(
+        entries[0]["error"],
+        json!(r#"{"InstructionError":[0,{"Custom":1}]}"#)
+    );
+    assert_eq!(entries[0]["rows"][0]["description"], json!("Memo: gm"));
+
+    let params = server.last_params("getSignaturesForAddress").unwrap();
+    assert_eq!(params[0], json!(owner.to_string()));
+    assert_eq!(params[1]["before"], json!(received.to_string()));
+    assert_eq!(params[1]["limit"].as_f64(), Some(2.0));
+
+    // Transactions are only fetched once
+    assert_eq!(
+        server
+            .methods()
+            .iter()
+            .filter(|method| *method == "getTransaction")
+            .count(),
+        2
+    );
+
+    let response = runtime.request(
+        "activity",
+        "atoll:solanaActivity",
+        EXTENSION_ORIGIN,
+        json!({ "before": "not a signature" }),
+    );
+    assert!(
+        response["err"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("`not a signature` is not a valid signature")
+    );
+}
@@ -0,0 +1,161 @@
+//! BIP322 "simple" signatures checked against the test vectors of the BIP
+
+use std::str::FromStr;
+
+use atoll_wallet_core::BitcoinMessage;
+use base64ct::{Base64, Encoding};
+use bitcoin::{
+    Address, CompressedPublicKey, Network, PrivateKey, hashes::Hash, key::Secp256k1,
+    secp256k1::Keypair,
+};
+
+/// The private key of every vector
+const WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+
+const P2WPKH: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+const P2TR: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+
+const EMPTY_P2WPKH_SIGNATURE: &str = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+const HELLO_P2WPKH_SIGNATURE: &str = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+const HELLO_P2TR_SIGNATURE: &str =
+    "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+
+fn keypair() -> Keypair {
+    let private_key = PrivateKey::from_wif(WIF).unwrap();
+
+    Keypair::from_secret_key(&Secp256k1::new(), &private_key.inner)
+}
+
+fn address(address: &str) -> Address {
+    Address::from_str(address)
+        .unwrap()
+        .require_network(Network::Bitcoin)
+        .unwrap()
+}
+
+fn base64(signature: &str) -> Vec<u8> {
+    Base64::decode_vec(signature).unwrap()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+#[test]
+fn the_vector_key_owns_both_addresses() {
+    let secp = Secp256k1::new();
+    let keypair = keypair();
+    let public_key = CompressedPublicKey(keypair.public_key());
+
+    assert_eq!(
+        Address::p2wpkh(&public_key, Network::Bitcoin),
+        address(P2WPKH)
+    );
+    assert_eq!(
+        Address::p2tr(&secp, keypair.x_only_public_key().0, None, Network::Bitcoin),
+        address(P2TR)
+    );
+}
+
+#[test]
+fn message_hashes_match_the_vectors() {
+    assert_eq!(
+        hex(&BitcoinMessage::bip322_message_hash(b"")),
+        "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+    );
+    assert_eq!(
+        hex(&BitcoinMessage::bip322_message_hash(b"Hello World")),
+        "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+    );
+}
+
+#[test]
+fn virtual_transactions_match_the_vectors() {
+    let script_pubkey = address(P2WPKH).script_pubkey();
+
+    for (message, to_spend_id, to_sign_id) in [
+        (
+            b"".as_slice(),
+            "c5680aa69bb8d860bf82d4e9cd3504b55dde018de765a91bb566283c545a99a7",
+            "1e9654e951a5ba44c8604c4de6c67fd78a27e81dcadcfe1edf638ba3aaebaed6",
+        ),
+        (
+            b"Hello World".as_slice(),
+            "b79d196740ad5217771c1098fc4a4b51e0535c32236c71f1ea4d61a2d603352b",
+            "88737ae86f2077145f93cc4b153ae9a1cb8d56afa511988c149c5c8c9d93bddf",
+        ),
+    ] {
+        let to_spend = BitcoinMessage::bip322_to_spend(&script_pubkey, message);
+        let to_sign = BitcoinMessage::bip322_to_sign(&to_spend, Default::default());
+
+        assert_eq!(to_spend.compute_txid().to_string(), to_spend_id);
+        assert_eq!(to_sign.compute_txid().to_string(), to_sign_id);
+        assert_eq!(
+            to_sign.input[0].previous_output.txid.as_byte_array(),
+            to_spend.compute_txid().as_byte_array()
+        );
+    }
+}
+
+#[test]
+fn p2wpkh_vectors_verify_and_sign_deterministically() {
+    for (message, signature) in [
+        (b"".as_slice(), EMPTY_P2WPKH_SIGNATURE),
+        (b"Hello World".as_slice(), HELLO_P2WPKH_SIGNATURE),
+    ] {
+        assert!(BitcoinMessage::verify(P2WPKH, message, &base64(signature)).unwrap());
+
+        // ECDSA signatures are deterministic so signing reproduces the vector
+        let signed =
+            BitcoinMessage::sign_bip322_simple(&keypair(), &address(P2WPKH), message).unwrap();
+        assert_eq!(Base64::encode_string(&signed), signature);
+    }
+
+    // A signature of one message does not verify another
+    assert!(!BitcoinMessage::verify(P2WPKH, b"", &base64(HELLO_P2WPKH_SIGNATURE)).unwrap());
+    assert!(
+        !BitcoinMessage::verify(P2WPKH, b"Hello World", &base64(EMPTY_P2WPKH_SIGNATURE)).unwrap()
+    );
+}
+
+#[test]
+fn p2tr_vectors_verify_and_signatures_round_trip() {
+    // The vector carries an explicit `SIGHASH_ALL` byte after the 64 byte signature
+    assert_eq!(base64(HELLO_P2TR_SIGNATURE).len(), 1 + 1 + 65);
+    assert!(BitcoinMessage::verify(P2TR, b"Hello World", &base64(HELLO_P2TR_SIGNATURE)).unwrap());
+    assert!(!BitcoinMessage::verify(P2TR, b"", &base64(HELLO_P2TR_SIGNATURE)).unwrap());
+
+    // Schnorr signatures use random auxiliary data, so fresh ones are checked by verifying
+    for message in [b"".as_slice(), b"Hello World".as_slice()] {
+        let signed =
+            BitcoinMessage::sign_bip322_simple(&keypair(), &address(P2TR), message).unwrap();
+        // One witness item of 64 bytes since the default sighash is left out
+        assert_eq!(signed[..2], [0x01, 0x40]);
+        assert_eq!(signed.len(), 1 + 1 + 64);
+        assert!(BitcoinMessage::verify(P2TR, message, &signed).unwrap());
+    }
+}
+
+#[test]
+fn signatures_for_other_addresses_do_not_verify() {
+    let other = Keypair::from_seckey_slice(&Secp256k1::new(), &[1u8; 32]).unwrap();
+    let other_p2wpkh =
+        Address::p2wpkh(&CompressedPublicKey(other.public_key()), Network::Bitcoin).to_string();
+
+    assert!(
+        !BitcoinMessage::verify(
+            &other_p2wpkh,
+            b"Hello World",
+            &base64(HELLO_P2WPKH_SIGNATURE)
+        )
+        .unwrap()
+    );
+
+    // The P2WPKH witness does not verify for the P2TR address of the same key
+    assert!(
+        !BitcoinMessage::verify(P2TR, b"Hello World", &base64(HELLO_P2WPKH_SIGNATURE)).unwrap()
+    );
+
+    // Signing for an address of another key is refused
+    assert!(BitcoinMessage::sign_bip322_simple(&other, &address(P2WPKH), b"Hello World").is_err());
+}
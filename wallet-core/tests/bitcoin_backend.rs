@@ -0,0 +1,384 @@
+//! The Esplora and bitcoind RPC backends parsing the responses of scripted servers
+
+mod common;
+
+use std::str::FromStr;
+
+use atoll_wallet_core::{
+    AtollWalletError, BitcoinBackend, BitcoinBalance, BitcoinFeeEstimates,
+    BitcoinTransactionStatus, BitcoindRpcBackend, EsploraBackend,
+};
+use bitcoin::{
+    Address, Amount, BlockHash, Network, OutPoint, ScriptBuf, Sequence, Transaction, TxIn, TxOut,
+    Txid, WPubkeyHash, Witness, absolute::LockTime, consensus::encode::serialize_hex, hashes::Hash,
+    transaction::Version,
+};
+use serde_json::{Value, json};
+
+use common::{MockHttpTransport, MockRpcServer, block_on};
+
+const ESPLORA: &str = "https://esplora.example/api";
+
+/// A proxy adding the RPC credentials in front of `bitcoind`
+const BITCOIND: &str = "http://localhost:18443/proxy";
+
+/// The genesis block
+const BLOCK_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+fn address() -> Address {
+    Address::from_script(
+        &ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([1u8; 20])),
+        Network::Regtest,
+    )
+    .unwrap()
+}
+
+fn txid(byte: u8) -> Txid {
+    Txid::from_byte_array([byte; 32])
+}
+
+fn transaction() -> Transaction {
+    Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: vec![TxIn {
+            previous_output: OutPoint::new(txid(1), 0),
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: Amount::from_sat(10_000),
+            script_pubkey: address().script_pubkey(),
+        }],
+    }
+}
+
+fn esplora(transport: &MockHttpTransport) -> EsploraBackend<MockHttpTransport> {
+    // A trailing slash is ignored
+    EsploraBackend::new(transport.clone(), &format!("{ESPLORA}/"))
+}
+
+fn bitcoin_error(error: AtollWalletError) -> String {
+    match error {
+        AtollWalletError::Bitcoin(message) => message,
+        error => panic!("expected a Bitcoin error, got {error:?}"),
+    }
+}
+
+#[test]
+fn esplora_utxos_and_balances_are_parsed() {
+    let transport = MockHttpTransport::new().reply(
+        &format!("{ESPLORA}/address/{}/utxo", address()),
+        &json!([
+            {
+                "txid": txid(1).to_string(),
+                "vout": 0,
+                "value": 50_000,
+                "status": {
+                    "confirmed": true,
+                    "block_height": 120,
+                    "block_hash": BLOCK_HASH,
+                    "block_time": 1_700_000_000u64,
+                },
+            },
+            {
+                "txid": txid(2).to_string(),
+                "vout": 3,
+                "value": 7_000,
+                "status": { "confirmed": false },
+            },
+        ])
+        .to_string(),
+    );
+    let backend = esplora(&transport);
+    assert_eq!(backend.base_url(), ESPLORA);
+
+    let utxos = block_on(backend.utxos(&address())).unwrap();
+    assert_eq!(utxos.len(), 2);
+    assert_eq!(utxos[0].outpoint, OutPoint::new(txid(1), 0));
+    assert_eq!(utxos[0].value, Amount::from_sat(50_000));
+    assert_eq!(utxos[0].script_pubkey, address().script_pubkey());
+    assert_eq!(
+        utxos[0].status,
+        BitcoinTransactionStatus {
+            confirmed: true,
+            block_height: Some(120),
+            block_hash: Some(BlockHash::from_str(BLOCK_HASH).unwrap()),
+            block_time: Some(1_700_000_000),
+        }
+    );
+    assert_eq!(utxos[1].outpoint, OutPoint::new(txid(2), 3));
+    assert_eq!(utxos[1].status, BitcoinTransactionStatus::default());
+
+    // 120 is the first confirmation
+    assert_eq!(utxos[0].status.confirmations(122), 3);
+    assert_eq!(utxos[0].status.confirmations(100), 0);
+    assert_eq!(utxos[1].status.confirmations(122), 0);
+
+    let balance = block_on(backend.balance(&address())).unwrap();
+    assert_eq!(
+        balance,
+        BitcoinBalance {
+            confirmed: Amount::from_sat(50_000),
+            unconfirmed: Amount::from_sat(7_000),
+        }
+    );
+    assert_eq!(balance.total(), Amount::from_sat(57_000));
+}
+
+#[test]
+fn esplora_fee_estimates_pick_the_closest_faster_target() {
+    let transport = MockHttpTransport::new().reply(
+        &format!("{ESPLORA}/fee-estimates"),
+        &json!({ "1": 25.5, "3": 12.0, "6": 8.25, "144": 1.1, "not a target": 99.0 }).to_string(),
+    );
+
+    let estimates = block_on(esplora(&transport).fee_estimates()).unwrap();
+    assert_eq!(
+        estimates.estimates().keys().copied().collect::<Vec<u16>>(),
+        vec![1, 3, 6, 144]
+    );
+
+    assert_eq!(estimates.fee_rate(1), Some(25.5));
+    assert_eq!(estimates.fee_rate(2), Some(25.5));
+    assert_eq!(estimates.fee_rate(6), Some(8.25));
+    assert_eq!(estimates.fee_rate(100), Some(8.25));
+    assert_eq!(estimates.fee_rate(1008), Some(1.1));
+    // Faster than every target
+    assert_eq!(estimates.fee_rate(0), Some(25.5));
+    assert_eq!(BitcoinFeeEstimates::default().fee_rate(6), None);
+}
+
+#[test]
+fn esplora_broadcasts_and_reads_the_status() {
+    let transaction = transaction();
+    let transport = MockHttpTransport::new()
+        .reply(&format!("{ESPLORA}/tx"), &format!("{}\n", txid(9)))
+        .reply(&format!("{ESPLORA}/blocks/tip/height"), "840000")
+        .reply(
+            &format!("{ESPLORA}/tx/{}/status", txid(9)),
+            &json!({ "confirmed": true, "block_height": 839_999, "block_hash": BLOCK_HASH })
+                .to_string(),
+        );
+    let backend = esplora(&transport);
+
+    assert_eq!(block_on(backend.broadcast(&transaction)).unwrap(), txid(9));
+    assert_eq!(
+        transport.requests()[0],
+        (format!("{ESPLORA}/tx"), serialize_hex(&transaction))
+    );
+
+    assert_eq!(block_on(backend.tip_height()).unwrap(), 840_000);
+
+    let status = block_on(backend.transaction_status(&txid(9))).unwrap();
+    assert!(status.confirmed);
+    assert_eq!(status.block_time, None);
+    assert_eq!(status.confirmations(840_000), 2);
+}
+
+#[test]
+fn esplora_refuses_invalid_responses() {
+    let utxos_url = format!("{ESPLORA}/address/{}/utxo", address());
+    let utxo = |txid: &str, block_hash: Value| {
+        json!([{
+            "txid": txid,
+            "vout": 0,
+            "value": 1_000,
+            "status": { "confirmed": true, "block_hash": block_hash },
+        }])
+        .to_string()
+    };
+
+    for (reply, expected) in [
+        (
+            utxo("not a txid", Value::Null),
+            "`not a txid` is not a valid txid".to_string(),
+        ),
+        (
+            utxo(&txid(1).to_string(), json!("00ff")),
+            "`00ff` is not a valid block hash".to_string(),
+        ),
+        (
+            "<html>Too many requests</html>".to_string(),
+            format!(
+                "Invalid response from Esplora for `/address/{}/utxo`",
+                address()
+            ),
+        ),
+        (
+            json!([{ "txid": txid(1).to_string(), "vout": 0 }]).to_string(),
+            "missing field `value`".to_string(),
+        ),
+    ] {
+        let transport = MockHttpTransport::new().reply(&utxos_url, &reply);
+        let error = block_on(esplora(&transport).utxos(&address())).unwrap_err();
+        assert!(bitcoin_error(error).contains(&expected), "{expected}");
+    }
+
+    // Esplora answers a rejected transaction with the reason as text
+    let transport = MockHttpTransport::new().reply(
+        &format!("{ESPLORA}/tx"),
+        "sendrawtransaction RPC error: {\"code\":-25,\"message\":\"bad-txns-inputs-missingorspent\"}",
+    );
+    let error = block_on(esplora(&transport).broadcast(&transaction())).unwrap_err();
+    assert!(
+        bitcoin_error(error)
+            .starts_with("The transaction was rejected by Esplora. sendrawtransaction RPC error")
+    );
+}
+
+fn bitcoind(server: &MockRpcServer) -> BitcoindRpcBackend<common::MockRpcTransport> {
+    BitcoindRpcBackend::new(server.transport(), BITCOIND)
+}
+
+#[test]
+fn bitcoind_utxos_are_scanned_from_the_utxo_set() {
+    let server = MockRpcServer::start();
+    server.reply(
+        "scantxoutset",
+        json!({
+            "success": true,
+            "unspents": [
+                { "txid": txid(1).to_string(), "vout": 2, "amount": 0.0001234, "height": 150 },
+                { "txid": txid(2).to_string(), "vout": 0, "amount": 1.5, "height": 151 },
+            ],
+            "total_amount": 1.5001234,
+        }),
+    );
+
+    let utxos = block_on(bitcoind(&server).utxos(&address())).unwrap();
+    assert_eq!(
+        server.last_params("scantxoutset"),
+        Some(json!(["start", [format!("addr({})", address())]]))
+    );
+
+    assert_eq!(utxos[0].outpoint, OutPoint::new(txid(1), 2));
+    assert_eq!(utxos[0].value, Amount::from_sat(12_340));
+    assert_eq!(utxos[0].script_pubkey, address().script_pubkey());
+    assert_eq!(
+        utxos[0].status,
+        BitcoinTransactionStatus {
+            confirmed: true,
+            block_height: Some(150),
+            ..Default::default()
+        }
+    );
+    assert_eq!(utxos[1].value, Amount::from_sat(150_000_000));
+
+    server.reply(
+        "scantxoutset",
+        json!({ "unspents": [{ "txid": txid(1).to_string(), "vout": 0, "amount": -1.0, "height": 1 }] }),
+    );
+    let error = block_on(bitcoind(&server).utxos(&address())).unwrap_err();
+    assert!(bitcoin_error(error).contains("is not a valid BTC amount"));
+}
+
+#[test]
+fn bitcoind_fee_rates_are_converted_from_btc_per_kvb() {
+    let server = MockRpcServer::start();
+    server.reply(
+        "estimatesmartfee",
+        json!({ "feerate": 0.00012, "blocks": 2 }),
+    );
+
+    let estimates = block_on(bitcoind(&server).fee_estimates()).unwrap();
+    assert_eq!(
+        estimates.estimates().keys().copied().collect::<Vec<u16>>(),
+        BitcoindRpcBackend::<common::MockRpcTransport>::FEE_TARGETS.to_vec()
+    );
+    assert!((estimates.fee_rate(6).unwrap() - 12.0).abs() < 1e-9);
+    assert_eq!(
+        server.methods(),
+        vec!["estimatesmartfee"; BitcoindRpcBackend::<common::MockRpcTransport>::FEE_TARGETS.len()]
+    );
+    assert_eq!(server.last_params("estimatesmartfee"), Some(json!([1008])));
+
+    // A fresh regtest node has no estimate yet
+    server.reply(
+        "estimatesmartfee",
+        json!({ "errors": ["Insufficient data or no feerate found"], "blocks": 0 }),
+    );
+    let estimates = block_on(bitcoind(&server).fee_estimates()).unwrap();
+    assert!(estimates.estimates().is_empty());
+    assert_eq!(estimates.fee_rate(6), None);
+}
+
+#[test]
+fn bitcoind_broadcasts_and_reads_the_status() {
+    let server = MockRpcServer::start();
+    let transaction = transaction();
+    server
+        .reply("sendrawtransaction", json!(txid(9).to_string()))
+        .reply("getblockcount", json!(200))
+        .reply(
+            "getrawtransaction",
+            json!({ "txid": txid(9).to_string(), "blockhash": BLOCK_HASH, "blocktime": 1_700_000_000u64 }),
+        )
+        .reply("getblockheader", json!({ "hash": BLOCK_HASH, "height": 199 }));
+    let backend = bitcoind(&server);
+
+    assert_eq!(block_on(backend.broadcast(&transaction)).unwrap(), txid(9));
+    assert_eq!(
+        server.last_params("sendrawtransaction"),
+        Some(json!([serialize_hex(&transaction)]))
+    );
+    assert_eq!(block_on(backend.tip_height()).unwrap(), 200);
+
+    let status = block_on(backend.transaction_status(&txid(9))).unwrap();
+    assert_eq!(
+        status,
+        BitcoinTransactionStatus {
+            confirmed: true,
+            block_height: Some(199),
+            block_hash: Some(BlockHash::from_str(BLOCK_HASH).unwrap()),
+            block_time: Some(1_700_000_000),
+        }
+    );
+    assert_eq!(
+        server.last_params("getrawtransaction"),
+        Some(json!([txid(9).to_string(), true]))
+    );
+    assert_eq!(
+        server.last_params("getblockheader"),
+        Some(json!([BLOCK_HASH]))
+    );
+
+    // A transaction still in the mempool has no block
+    server.reply("getrawtransaction", json!({ "txid": txid(9).to_string() }));
+    assert_eq!(
+        block_on(backend.transaction_status(&txid(9))).unwrap(),
+        BitcoinTransactionStatus::default()
+    );
+}
+
+#[test]
+fn bitcoind_errors_are_reported_with_the_method() {
+    let server = MockRpcServer::start();
+    server
+        .reply_error("sendrawtransaction", -26, "min relay fee not met")
+        .reply("getblockcount", Value::Null)
+        .reply("getrawtransaction", json!("not an object"));
+    let transport = server.transport();
+    let backend = BitcoindRpcBackend::new(transport.clone(), BITCOIND);
+
+    let error = block_on(backend.broadcast(&transaction())).unwrap_err();
+    assert_eq!(
+        bitcoin_error(error),
+        "bitcoind `sendrawtransaction` failed with code `-26`. min relay fee not met"
+    );
+
+    let error = block_on(backend.tip_height()).unwrap_err();
+    assert_eq!(
+        bitcoin_error(error),
+        "bitcoind `getblockcount` returned no result"
+    );
+
+    let error = block_on(backend.transaction_status(&txid(9))).unwrap_err();
+    assert!(
+        bitcoin_error(error).starts_with("Invalid response from bitcoind for `getrawtransaction`")
+    );
+
+    // Every call is sent to the proxy
+    assert_eq!(transport.urls(), vec![BITCOIND; 3]);
+}
@@ -0,0 +1,481 @@
+//! Coin selection and send building against deterministic sets of unspent outputs
+
+mod common;
+
+use std::str::FromStr;
+
+use atoll_wallet_core::{
+    App, BitcoinAccountKeypair, BitcoinCluster, BitcoinConstants, BitcoinKeychain, BitcoinPurpose,
+    BitcoinSendBuilder, BitcoinTransactionStatus, BitcoinUtxo, CoinSelection, TX_OVERHEAD_WEIGHT,
+    WalletVault, fee_for_weight, input_weight, output_weight,
+};
+use bitcoin::{
+    Address, Amount, FeeRate, Network, OutPoint, ScriptBuf, Sequence, Transaction, Txid,
+    WPubkeyHash, bip32::DerivationPath, consensus::encode::deserialize_hex, hashes::Hash,
+};
+use serde_json::json;
+use zeroize::Zeroizing;
+
+use common::{FakeRuntime, MockHttpTransport};
+
+/// 1 sat/vB
+const FEE_RATE: FeeRate = FeeRate::from_sat_per_kwu(250);
+
+fn p2wpkh_script(seed: u8) -> ScriptBuf {
+    ScriptBuf::new_p2wpkh(&WPubkeyHash::from_byte_array([seed; 20]))
+}
+
+fn address(seed: u8) -> Address {
+    Address::from_script(&p2wpkh_script(seed), Network::Regtest).unwrap()
+}
+
+/// The fee of spending a P2WPKH output at [FEE_RATE]
+fn input_fee() -> u64 {
+    fee_for_weight(FEE_RATE, input_weight(&p2wpkh_script(0)).unwrap()).to_sat()
+}
+
+/// Confirmed P2WPKH outputs worth `effective_values` once their input fee is paid
+fn utxos(effective_values: &[u64]) -> Vec<BitcoinUtxo> {
+    effective_values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| BitcoinUtxo {
+            outpoint: OutPoint::new(Txid::from_byte_array([index as u8 + 1; 32]), 0),
+            value: Amount::from_sat(value + input_fee()),
+            script_pubkey: p2wpkh_script(1),
+            status: BitcoinTransactionStatus {
+                confirmed: true,
+                ..Default::default()
+            },
+        })
+        .collect()
+}
+
+fn select(utxos: &[BitcoinUtxo], target: u64) -> Vec<usize> {
+    let mut selection = CoinSelection::select(
+        utxos,
+        Amount::from_sat(target),
+        FEE_RATE,
+        Amount::from_sat(100),
+        Amount::from_sat(31),
+        Amount::from_sat(294),
+    )
+    .unwrap();
+    selection.sort_unstable();
+
+    selection
+}
+
+#[test]
+fn branch_and_bound_finds_an_exact_match() {
+    let utxos = utxos(&[100_000, 50_000, 30_000, 7_000]);
+
+    assert_eq!(select(&utxos, 80_000), [1, 2]);
+    assert_eq!(select(&utxos, 87_000), [1, 2, 3]);
+}
+
+#[test]
+fn branch_and_bound_accepts_waste_below_the_cost_of_change() {
+    let utxos = utxos(&[100_000, 50_000, 30_000]);
+
+    // 50 sats over the target is cheaper than creating and spending a change output
+    assert_eq!(select(&utxos, 79_950), [1, 2]);
+}
+
+#[test]
+fn knapsack_leaves_room_for_change() {
+    let utxos = utxos(&[100_000, 50_000, 30_000]);
+
+    let selection = select(&utxos, 120_000);
+    let selected = selection
+        .iter()
+        .map(|index| utxos[*index].value.to_sat() - input_fee())
+        .sum::<u64>();
+
+    // The smallest set paying the target and a change output
+    assert!(selected >= 120_000 + 31 + 294);
+    assert_eq!(selection, [0, 2]);
+}
+
+#[test]
+fn selection_is_deterministic() {
+    let utxos = utxos(&[
+        12_345, 67_890, 4_321, 98_765, 55_555, 10_101, 77_777, 3_333, 42_424, 8_888,
+    ]);
+
+    let first = select(&utxos, 150_000);
+
+    (0..10).for_each(|_| assert_eq!(select(&utxos, 150_000), first));
+}
+
+#[test]
+fn outputs_worth_less_than_their_input_fee_are_skipped() {
+    let mut utxos = utxos(&[10_000]);
+    utxos.push(BitcoinUtxo {
+        value: Amount::from_sat(input_fee()),
+        ..utxos[0].clone()
+    });
+
+    assert_eq!(select(&utxos, 9_000), [0]);
+}
+
+#[test]
+fn insufficient_funds_is_an_error() {
+    let utxos = utxos(&[10_000, 5_000]);
+
+    let error = CoinSelection::select(
+        &utxos,
+        Amount::from_sat(15_001),
+        FEE_RATE,
+        Amount::from_sat(100),
+        Amount::from_sat(31),
+        Amount::from_sat(294),
+    )
+    .unwrap_err();
+
+    assert!(error.to_string().contains("Insufficient funds"));
+}
+
+#[test]
+fn send_builder_rejects_fee_rates_below_the_minimum_relay_fee() {
+    let builder = BitcoinSendBuilder::new(utxos(&[10_000]), &address(2));
+
+    assert!(builder.clone().set_fee_rate(0.5).is_err());
+    assert!(builder.clone().set_fee_rate(f64::NAN).is_err());
+    assert_eq!(
+        builder.set_fee_rate(2.5).unwrap().fee_rate(),
+        FeeRate::from_sat_per_kwu(625)
+    );
+}
+
+#[test]
+fn send_builder_rejects_dust_recipients() {
+    let plan = BitcoinSendBuilder::new(utxos(&[10_000]), &address(2))
+        .add_recipient(&address(3), Amount::from_sat(100))
+        .build();
+
+    assert!(plan.is_err());
+}
+
+#[test]
+fn send_builder_adds_change_and_signals_rbf() {
+    let utxos = utxos(&[100_000, 50_000, 30_000]);
+
+    let plan = BitcoinSendBuilder::new(utxos.clone(), &address(2))
+        .add_recipient(&address(3), Amount::from_sat(120_000))
+        .build()
+        .unwrap();
+
+    let change = plan.change().expect("a change output");
+    assert_eq!(change.script_pubkey, address(2).script_pubkey());
+
+    let input_value = plan.inputs().iter().map(|utxo| utxo.value).sum::<Amount>();
+    assert_eq!(
+        input_value,
+        Amount::from_sat(120_000) + change.value + plan.fee()
+    );
+    assert!(
+        plan.transaction()
+            .input
+            .iter()
+            .all(|input| input.sequence == Sequence::ENABLE_RBF_NO_LOCKTIME)
+    );
+
+    // The fee pays at least the fee rate for the signed size
+    assert!(plan.fee().to_sat() >= plan.estimated_vsize());
+}
+
+#[test]
+fn send_builder_drops_change_below_the_dust_threshold() {
+    let recipient_script = address(3).script_pubkey();
+    let weight = TX_OVERHEAD_WEIGHT
+        + output_weight(&recipient_script)
+        + input_weight(&p2wpkh_script(1)).unwrap();
+    let fee = fee_for_weight(FEE_RATE, weight).to_sat() - input_fee();
+
+    // 100 sats left over is below the dust threshold so it goes to the fee
+    let plan = BitcoinSendBuilder::new(utxos(&[50_000 + fee + 100]), &address(2))
+        .add_recipient(&address(3), Amount::from_sat(50_000))
+        .set_rbf(false)
+        .build()
+        .unwrap();
+
+    assert!(plan.change().is_none());
+    assert_eq!(plan.transaction().output.len(), 1);
+    assert_eq!(plan.fee(), Amount::from_sat(fee + input_fee() + 100));
+    assert_eq!(plan.outpoints(), [plan.inputs()[0].outpoint]);
+    assert!(
+        plan.transaction()
+            .input
+            .iter()
+            .all(|input| input.sequence == Sequence::ENABLE_LOCKTIME_NO_RBF)
+    );
+}
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+const EXTENSION_ORIGIN: &str = "chrome-extension://atoll";
+
+fn vault() -> WalletVault {
+    WalletVault::new_from_mnemonic(Zeroizing::new(MNEMONIC.to_string()), None).unwrap()
+}
+
+#[test]
+fn change_addresses_are_derived_on_the_internal_chain() {
+    let vault = vault();
+
+    // The first receive and change addresses of the BIP84 and BIP86 vectors
+    for (purpose, receive, change) in [
+        (
+            BitcoinPurpose::Payment,
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu",
+            "bc1q8c6fshw2dlwun7ekn9qwf37cu2rn755upcp6el",
+        ),
+        (
+            BitcoinPurpose::Ordinals,
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            "bc1p3qkhfews2uk44qtvauqyr2ttdsw7svhkl9nkm9s9c3x4ax5h60wqwruhk7",
+        ),
+    ] {
+        let keypair = vault
+            .bitcoin_keypair(purpose, BitcoinCluster::Mainnet, 0)
+            .unwrap();
+        let change_keypair = vault
+            .bitcoin_change_keypair(purpose, BitcoinCluster::Mainnet, 0)
+            .unwrap();
+
+        assert_eq!(keypair.address().to_string(), receive);
+        assert_eq!(change_keypair.address().to_string(), change);
+        assert_eq!(keypair.keychain(), BitcoinKeychain::External);
+        assert_eq!(change_keypair.keychain(), BitcoinKeychain::Internal);
+    }
+
+    let change = vault
+        .bitcoin_change_keypair(BitcoinPurpose::Payment, BitcoinCluster::Regtest, 0)
+        .unwrap();
+    assert_eq!(
+        change.derivation_path(),
+        &DerivationPath::from_str("m/84'/1'/0'/1/0").unwrap()
+    );
+}
+
+#[test]
+fn send_plans_carry_the_key_origins_of_the_wallet() {
+    let vault = vault();
+    let receive = vault
+        .bitcoin_keypair(BitcoinPurpose::Payment, BitcoinCluster::Regtest, 0)
+        .unwrap();
+    let change = vault
+        .bitcoin_change_keypair(BitcoinPurpose::Payment, BitcoinCluster::Regtest, 0)
+        .unwrap();
+
+    let mut utxos = utxos(&[60_000, 50_000]);
+    utxos[0].script_pubkey = receive.address().script_pubkey();
+    utxos[1].script_pubkey = change.address().script_pubkey();
+
+    let plan = BitcoinSendBuilder::new(utxos, change.address())
+        .add_recipient(&address(3), Amount::from_sat(100_000))
+        .build()
+        .unwrap();
+    assert_eq!(plan.inputs().len(), 2);
+    assert!(plan.change().is_some());
+
+    let mut psbt = plan.to_psbt(&[&receive, &change]).unwrap();
+    let inputs = &psbt.psbt().inputs;
+    for keypair in [&receive, &change] {
+        let index = plan
+            .inputs()
+            .iter()
+            .position(|utxo| utxo.script_pubkey == keypair.address().script_pubkey())
+            .unwrap();
+        assert_eq!(
+            inputs[index].bip32_derivation.get(&keypair.public_key()),
+            Some(&(
+                keypair.master_fingerprint(),
+                keypair.derivation_path().clone()
+            ))
+        );
+    }
+
+    // The change output is marked as the wallet's own and the recipient is not
+    let outputs = &psbt.psbt().outputs;
+    let change_index = plan
+        .transaction()
+        .output
+        .iter()
+        .position(|output| output.script_pubkey == change.address().script_pubkey())
+        .unwrap();
+    assert!(
+        outputs[change_index]
+            .bip32_derivation
+            .contains_key(&change.public_key())
+    );
+    assert!(outputs[1 - change_index].bip32_derivation.is_empty());
+
+    assert_eq!(psbt.sign(&[&receive, &change], &[]).unwrap().len(), 2);
+}
+
+/// A background with the vault unlocked and an Esplora server scripted for regtest
+fn send_background() -> (FakeRuntime, MockHttpTransport, [BitcoinAccountKeypair; 2]) {
+    let vault = vault();
+    let receive = vault
+        .bitcoin_keypair(BitcoinPurpose::Payment, BitcoinCluster::Regtest, 0)
+        .unwrap();
+    let change = vault
+        .bitcoin_change_keypair(BitcoinPurpose::Payment, BitcoinCluster::Regtest, 0)
+        .unwrap();
+
+    let utxo = |txid: u8, value: u64| {
+        json!({
+            "txid": Txid::from_byte_array([txid; 32]).to_string(),
+            "vout": 1,
+            "value": value,
+            "status": { "confirmed": true, "block_height": 100 },
+        })
+    };
+
+    let esplora = BitcoinConstants::REGTEST_ENDPOINT;
+    let transport = MockHttpTransport::new()
+        .reply(
+            &format!("{esplora}/address/{}/utxo", receive.address()),
+            &json!([utxo(1, 70_000), utxo(2, 20_000)]).to_string(),
+        )
+        .reply(
+            &format!("{esplora}/address/{}/utxo", change.address()),
+            &json!([utxo(3, 15_000)]).to_string(),
+        )
+        .reply(
+            &format!("{esplora}/fee-estimates"),
+            &json!({ "1": 20.0, "6": 4.0, "144": 1.0 }).to_string(),
+        )
+        .reply(
+            &format!("{esplora}/tx"),
+            &Txid::from_byte_array([7u8; 32]).to_string(),
+        );
+
+    let app = App::new(transport.clone())
+        .set_extension_origin(EXTENSION_ORIGIN)
+        .set_vault(vault)
+        .unwrap();
+    let runtime = FakeRuntime::new();
+    common::listen(app, &runtime);
+
+    (runtime, transport, [receive, change])
+}
+
+#[test]
+fn the_send_messages_spend_the_payment_account_and_its_change() {
+    let (runtime, transport, [receive, change]) = send_background();
+    let recipient = address(3).to_string();
+
+    // The fee rate of the default confirmation target
+    let estimate = runtime.request(
+        "estimate",
+        "atoll:bitcoinEstimateSend",
+        EXTENSION_ORIGIN,
+        json!({ "recipient": recipient, "amount": 80_000 }),
+    );
+    assert!(estimate["err"].is_null(), "{estimate}");
+    assert_eq!(estimate["ok"]["feeRate"].as_f64(), Some(4.0));
+    assert_eq!(estimate["ok"]["inputs"], json!(2));
+
+    let fee = estimate["ok"]["fee"].as_f64().unwrap() as u64;
+    let vsize = estimate["ok"]["vsize"].as_f64().unwrap() as u64;
+    assert!(fee >= 4 * vsize);
+    let change_value = estimate["ok"]["change"].as_f64().unwrap() as u64;
+    assert!([85_000, 90_000].contains(&(80_000 + fee + change_value)));
+    assert!(
+        transport
+            .requests()
+            .iter()
+            .all(|(url, _)| !url.ends_with("/tx"))
+    );
+
+    // Only the popup sends
+    let refused = runtime.request(
+        "send",
+        "atoll:bitcoinSend",
+        "https://dapp.example",
+        json!({ "recipient": recipient, "amount": 80_000, "feeRate": 2.0 }),
+    );
+    assert_eq!(refused["err"]["code"], json!(4100));
+
+    let sent = runtime.request(
+        "send",
+        "atoll:bitcoinSend",
+        EXTENSION_ORIGIN,
+        json!({ "recipient": recipient, "amount": 80_000, "feeRate": 2.0, "rbf": false }),
+    );
+    assert!(sent["err"].is_null(), "{sent}");
+    assert_eq!(
+        sent["ok"]["txid"],
+        json!(Txid::from_byte_array([7u8; 32]).to_string())
+    );
+
+    let requests = transport.requests();
+    let (_, broadcast) = requests
+        .iter()
+        .find(|(url, _)| url.ends_with("/tx"))
+        .unwrap();
+    let transaction = deserialize_hex::<Transaction>(broadcast).unwrap();
+
+    assert!(transaction.input.iter().all(|input| input.sequence
+        == Sequence::ENABLE_LOCKTIME_NO_RBF
+        && input.witness.len() == 2));
+    assert_eq!(
+        transaction.output[0].script_pubkey,
+        address(3).script_pubkey()
+    );
+    assert_eq!(transaction.output[0].value, Amount::from_sat(80_000));
+
+    // The values of the outputs served by Esplora
+    let input_value = transaction
+        .input
+        .iter()
+        .map(
+            |input| match input.previous_output.txid.to_byte_array()[0] {
+                1 => 70_000,
+                2 => 20_000,
+                _ => 15_000,
+            },
+        )
+        .sum::<u64>();
+    let fee = sent["ok"]["fee"].as_f64().unwrap() as u64;
+    let change_value = transaction
+        .output
+        .iter()
+        .filter(|output| output.script_pubkey == change.address().script_pubkey())
+        .map(|output| output.value.to_sat())
+        .sum::<u64>();
+    assert_eq!(change_value, input_value - 80_000 - fee);
+    assert!(fee >= 2 * transaction.vsize() as u64);
+    assert!(
+        transaction
+            .output
+            .iter()
+            .all(|output| output.script_pubkey != receive.address().script_pubkey())
+    );
+}
+
+#[test]
+fn the_send_messages_refuse_invalid_requests() {
+    let (runtime, _transport, _) = send_background();
+
+    for params in [
+        json!({ "recipient": address(3).to_string(), "amount": 0 }),
+        json!({ "recipient": "not an address", "amount": 1_000 }),
+        // A mainnet address on regtest
+        json!({ "recipient": "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu", "amount": 1_000 }),
+        json!({ "recipient": address(3).to_string(), "amount": 1_000_000 }),
+        json!({ "recipient": address(3).to_string(), "amount": 1_000, "feeRate": 0.1 }),
+    ] {
+        let response = runtime.request(
+            "estimate",
+            "atoll:bitcoinEstimateSend",
+            EXTENSION_ORIGIN,
+            params.clone(),
+        );
+        assert!(response["ok"].is_null(), "{params}: {response}");
+    }
+}
@@ -0,0 +1,442 @@
+//! A headless stand-in for the extension: a fake `runtime.onMessage` that the [App] of the
+//! core listens on, and a local JSON-RPC server with scripted replies for the cluster.
+
+#![allow(dead_code)]
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    pin::pin,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::Duration,
+};
+
+use atoll_wallet_core::{
+    App, AtollWalletError, AtollWalletResult, HttpFuture, HttpTransport, ProtocolHeader,
+    ProtocolRequest, ProtocolResponse, StorageFuture, WalletStorage,
+};
+use serde_json::{Value, json};
+
+/// Drives `future` to completion on the current thread. The transports used in the tests
+/// complete without waiting on a reactor so polling in a loop is enough.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+    }
+}
+
+/// A request received by the [MockRpcServer]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub method: String,
+    pub params: Value,
+}
+
+/// A JSON-RPC server on a local port that answers each method with a scripted reply
+/// and records the calls it receives
+pub struct MockRpcServer {
+    address: SocketAddr,
+    replies: Arc<Mutex<HashMap<String, Value>>>,
+    calls: Arc<Mutex<Vec<RecordedCall>>>,
+}
+
+impl MockRpcServer {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind the mock RPC server");
+        let address = listener
+            .local_addr()
+            .expect("address of the mock RPC server");
+        let replies = Arc::new(Mutex::new(HashMap::<String, Value>::new()));
+        let calls = Arc::new(Mutex::new(Vec::<RecordedCall>::new()));
+
+        let (thread_replies, thread_calls) = (replies.clone(), calls.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                Self::serve(stream, &thread_replies, &thread_calls);
+            }
+        });
+
+        Self {
+            address,
+            replies,
+            calls,
+        }
+    }
+
+    /// Answers `method` with `{ result }`
+    pub fn reply(&self, method: &str, result: Value) -> &Self {
+        self.replies
+            .lock()
+            .unwrap()
+            .insert(method.to_string(), json!({ "result": result }));
+
+        self
+    }
+
+    /// Answers the calls of `method` whose params contain `needle`, such as an address,
+    /// with `{ result }`. Other calls of `method` get the reply of [Self::reply].
+    pub fn reply_matching(&self, method: &str, needle: &str, result: Value) -> &Self {
+        self.replies
+            .lock()
+            .unwrap()
+            .insert(format!("{method}\0{needle}"), json!({ "result": result }));
+
+        self
+    }
+
+    /// Answers `method` with `{ error: { code, message } }`
+    pub fn reply_error(&self, method: &str, code: i64, message: &str) -> &Self {
+        self.replies.lock().unwrap().insert(
+            method.to_string(),
+            json!({ "error": { "code": code, "message": message } }),
+        );
+
+        self
+    }
+
+    /// The replies of a cluster with `blockhash`, a simulation consuming `units_consumed`,
+    /// recent priority fees of `fees` and a `sendTransaction` returning `signature`
+    pub fn script_cluster(
+        &self,
+        blockhash: &solana_hash::Hash,
+        units_consumed: u64,
+        fees: &[u64],
+        signature: &str,
+    ) -> &Self {
+        self.reply(
+            "getLatestBlockhash",
+            json!({
+                "context": { "slot": 1 },
+                "value": { "blockhash": blockhash.to_string(), "lastValidBlockHeight": 100 },
+            }),
+        )
+        .reply(
+            "simulateTransaction",
+            json!({
+                "context": { "slot": 1 },
+                "value": { "err": null, "logs": [], "unitsConsumed": units_consumed },
+            }),
+        )
+        .reply(
+            "getRecentPrioritizationFees",
+            Value::Array(
+                fees.iter()
+                    .enumerate()
+                    .map(|(slot, fee)| json!({ "slot": slot, "prioritizationFee": fee }))
+                    .collect(),
+            ),
+        )
+        .reply("sendTransaction", json!(signature))
+    }
+
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    pub fn methods(&self) -> Vec<String> {
+        self.calls()
+            .into_iter()
+            .map(|call| call.method)
+            .collect::<Vec<String>>()
+    }
+
+    /// The params of the last call to `method`
+    pub fn last_params(&self, method: &str) -> Option<Value> {
+        self.calls()
+            .into_iter()
+            .rev()
+            .find(|call| call.method == method)
+            .map(|call| call.params)
+    }
+
+    pub fn transport(&self) -> MockRpcTransport {
+        MockRpcTransport {
+            address: self.address,
+            urls: Rc::default(),
+        }
+    }
+
+    fn serve(
+        stream: TcpStream,
+        replies: &Mutex<HashMap<String, Value>>,
+        calls: &Mutex<Vec<RecordedCall>>,
+    ) {
+        let mut reader = BufReader::new(stream);
+        let mut content_length = 0usize;
+
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or_default() == 0 || line == "\r\n" {
+                break;
+            }
+
+            if let Some((name, value)) = line.split_once(':')
+                && name.eq_ignore_ascii_case("content-length")
+            {
+                content_length = value.trim().parse().unwrap_or_default();
+            }
+        }
+
+        let mut body = vec![0u8; content_length];
+        if reader.read_exact(&mut body).is_err() {
+            return;
+        }
+
+        let request = serde_json::from_slice::<Value>(&body).unwrap_or_default();
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        calls.lock().unwrap().push(RecordedCall {
+            method: method.clone(),
+            params: request["params"].clone(),
+        });
+
+        let params = request["params"].to_string();
+        let replies = replies.lock().unwrap();
+        let matching = replies.iter().find_map(|(key, reply)| {
+            key.strip_prefix(&format!("{method}\0"))
+                .filter(|needle| params.contains(needle))
+                .map(|_| reply)
+        });
+        let mut response = matching.or(replies.get(&method)).cloned().unwrap_or(
+            json!({ "error": { "code": -32601, "message": format!("`{method}` is not scripted") } }),
+        );
+        drop(replies);
+        response["jsonrpc"] = json!("2.0");
+        response["id"] = request["id"].clone();
+        let response = response.to_string();
+
+        let mut stream = reader.into_inner();
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+            response.len()
+        );
+    }
+}
+
+/// An [HttpTransport] sending every request to a [MockRpcServer], whatever the cluster
+/// endpoint, and recording the URL each request was meant for
+#[derive(Debug, Clone)]
+pub struct MockRpcTransport {
+    address: SocketAddr,
+    urls: Rc<RefCell<Vec<String>>>,
+}
+
+impl MockRpcTransport {
+    pub fn urls(&self) -> Vec<String> {
+        self.urls.borrow().clone()
+    }
+
+    fn post(&self, url: &str, content_type: &str, body: &str) -> AtollWalletResult<String> {
+        self.urls.borrow_mut().push(url.to_string());
+
+        let unreachable = |error: std::io::Error| AtollWalletError::SolanaRpc(error.to_string());
+
+        let mut stream = TcpStream::connect(self.address).map_err(unreachable)?;
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .map_err(unreachable)?;
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: {}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            self.address,
+            body.len()
+        )
+        .map_err(unreachable)?;
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).map_err(unreachable)?;
+
+        response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .ok_or(AtollWalletError::SolanaRpc(
+                "The mock RPC server sent a response without a body".to_string(),
+            ))
+    }
+}
+
+impl HttpTransport for MockRpcTransport {
+    fn post_json<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a> {
+        Box::pin(async move { self.post(url, "application/json", &body) })
+    }
+
+    fn post_text<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a> {
+        Box::pin(async move { self.post(url, "text/plain", &body) })
+    }
+
+    fn get<'a>(&'a self, url: &'a str) -> HttpFuture<'a> {
+        Box::pin(async move { self.post(url, "application/json", "") })
+    }
+}
+
+/// An [HttpTransport] answering each URL with a scripted body and recording the
+/// `(url, body)` of every request, for services other than the cluster
+#[derive(Debug, Default, Clone)]
+pub struct MockHttpTransport {
+    replies: Rc<RefCell<HashMap<String, String>>>,
+    requests: Rc<RefCell<Vec<(String, String)>>>,
+}
+
+impl MockHttpTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers every request to `url` with `body`
+    pub fn reply(self, url: &str, body: &str) -> Self {
+        self.replies
+            .borrow_mut()
+            .insert(url.to_string(), body.to_string());
+
+        self
+    }
+
+    pub fn requests(&self) -> Vec<(String, String)> {
+        self.requests.borrow().clone()
+    }
+
+    fn send(&self, url: &str, body: String) -> AtollWalletResult<String> {
+        self.requests.borrow_mut().push((url.to_string(), body));
+
+        self.replies
+            .borrow()
+            .get(url)
+            .cloned()
+            .ok_or(AtollWalletError::Input(format!(
+                "No reply is scripted for `{url}`"
+            )))
+    }
+}
+
+impl HttpTransport for MockHttpTransport {
+    fn post_json<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a> {
+        Box::pin(async move { self.send(url, body) })
+    }
+
+    fn post_text<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a> {
+        Box::pin(async move { self.send(url, body) })
+    }
+
+    fn get<'a>(&'a self, url: &'a str) -> HttpFuture<'a> {
+        Box::pin(async move { self.send(url, String::new()) })
+    }
+}
+
+/// `storage.local` of the extension kept in memory. Clones share the items so a new
+/// background can read what the previous one stored.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStorage {
+    items: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl MemoryStorage {
+    pub fn item(&self, key: &str) -> Option<String> {
+        self.items.borrow().get(key).cloned()
+    }
+}
+
+impl WalletStorage for MemoryStorage {
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(self.item(key)) })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: String) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            self.items.borrow_mut().insert(key.to_string(), value);
+
+            Ok(())
+        })
+    }
+}
+
+/// The `sender` of a message, as given to `runtime.onMessage` listeners
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MessageSender {
+    pub origin: Option<String>,
+}
+
+type Listener = Box<dyn Fn(&Value, &MessageSender) -> Value>;
+
+/// `runtime.onMessage` of the extension, delivering each message to its listeners
+#[derive(Default)]
+pub struct FakeOnMessage {
+    listeners: RefCell<Vec<Listener>>,
+}
+
+impl FakeOnMessage {
+    pub fn add_listener(&self, listener: impl Fn(&Value, &MessageSender) -> Value + 'static) {
+        self.listeners.borrow_mut().push(Box::new(listener));
+    }
+}
+
+/// The `runtime` of the extension with the content script on one side and the background
+/// on the other
+#[derive(Default)]
+pub struct FakeRuntime {
+    pub on_message: FakeOnMessage,
+}
+
+impl FakeRuntime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends `message` as the content script of a page at `origin` does and returns the
+    /// response of the first listener
+    pub fn send_message(&self, message: Value, origin: &str) -> Value {
+        let sender = MessageSender {
+            origin: Some(origin.to_string()),
+        };
+
+        self.on_message
+            .listeners
+            .borrow()
+            .first()
+            .map(|listener| listener(&message, &sender))
+            .expect("the background registered a listener")
+    }
+
+    /// Builds the request envelope the content script relays for `method`
+    pub fn request(&self, id: &str, method: &str, origin: &str, params: Value) -> Value {
+        self.send_message(
+            json!({
+                "version": ProtocolHeader::VERSION,
+                "id": id,
+                "method": method,
+                "origin": origin,
+                "params": params,
+            }),
+            origin,
+        )
+    }
+}
+
+/// The time, in milliseconds since the unix epoch, the requests of the tests are sent at
+pub const NOW_MS: f64 = 1_750_000_000_000.0;
+
+/// Listens on `runtime.onMessage` with the background `app` as `app()` does in the
+/// extension, answering every message with a response envelope
+pub fn listen<T: HttpTransport + Clone + 'static>(app: App<T>, runtime: &FakeRuntime) {
+    runtime.on_message.add_listener(move |message, sender| {
+        let response = match ProtocolRequest::from_json(message) {
+            Ok(request) => match block_on(app.handle(&request, sender.origin.clone(), NOW_MS)) {
+                Ok(output) => ProtocolResponse::ok(&request.header.id, output),
+                Err(error) => ProtocolResponse::err(Some(&request.header.id), &error),
+            },
+            Err(error) => ProtocolResponse::err(message["id"].as_str(), &error),
+        };
+
+        serde_json::to_value(&response).expect("responses serialize to JSON")
+    });
+}
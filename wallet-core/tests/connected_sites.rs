@@ -0,0 +1,163 @@
+//! The sites the user connected, their Sign In With Solana sessions and revoking them
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use atoll_wallet_core::{
+    ConnectedSite, ConnectedSites, SignInInputParser, SignInSession, SolanaSignInParams,
+    WalletVault,
+};
+use solana_pubkey::Pubkey;
+
+const ORIGIN: &str = "https://dapp.example";
+const OTHER_ORIGIN: &str = "https://other.example";
+const ACCOUNT: Pubkey = Pubkey::new_from_array([1u8; 32]);
+const OTHER: Pubkey = Pubkey::new_from_array([2u8; 32]);
+const NOW_MS: f64 = 1_750_000_000_000.0;
+
+fn sign_in_message(account: &Pubkey) -> String {
+    let params = SolanaSignInParams {
+        domain: Some("dapp.example".to_string()),
+        address: Some(account.to_string()),
+        chain_id: Some("solana:devnet".to_string()),
+        nonce: Some("8f3kd92ma0".to_string()),
+        issued_at: Some("2025-06-15T12:00:00Z".to_string()),
+        expiration_time: Some("2025-06-16T12:00:00Z".to_string()),
+        ..SolanaSignInParams::default()
+    };
+    let mut parser = SignInInputParser::new(UNIX_EPOCH + Duration::from_secs(1_750_000_000));
+
+    parser.parse(&params).unwrap().format()
+}
+
+#[test]
+fn connecting_records_the_site_for_each_account() {
+    let mut sites = ConnectedSites::default();
+
+    let site = sites.connect(
+        ORIGIN,
+        ACCOUNT,
+        Some("data:image/png;base64,AA==".into()),
+        NOW_MS,
+    );
+    assert_eq!(site.first_connected_ms(), NOW_MS as u64);
+    assert_eq!(site.icon(), Some("data:image/png;base64,AA=="));
+
+    // Reconnecting keeps the first connection and an icon too large is not kept
+    let large_icon = "A".repeat(ConnectedSite::MAX_ICON_LENGTH + 1);
+    let site = sites.connect(ORIGIN, OTHER, Some(large_icon), NOW_MS + 1_000.0);
+    assert_eq!(site.first_connected_ms(), NOW_MS as u64);
+    assert_eq!(site.last_used_ms(), NOW_MS as u64 + 1_000);
+    assert_eq!(site.accounts(), [ACCOUNT, OTHER]);
+    assert_eq!(site.icon(), Some("data:image/png;base64,AA=="));
+
+    sites.connect(OTHER_ORIGIN, OTHER, None, NOW_MS);
+    assert!(sites.is_trusted(ORIGIN, &ACCOUNT));
+    assert!(!sites.is_trusted(OTHER_ORIGIN, &ACCOUNT));
+    assert_eq!(
+        sites
+            .for_account(&ACCOUNT)
+            .map(ConnectedSite::origin)
+            .collect::<Vec<_>>(),
+        [ORIGIN]
+    );
+    assert_eq!(sites.for_account(&OTHER).count(), 2);
+}
+
+#[test]
+fn signing_updates_the_last_use_of_connected_sites() {
+    let mut sites = ConnectedSites::default();
+    sites.connect(ORIGIN, ACCOUNT, None, NOW_MS);
+
+    sites
+        .touch(ORIGIN, NOW_MS + 5_000.0)
+        .touch(OTHER_ORIGIN, NOW_MS + 5_000.0);
+    assert_eq!(
+        sites.get(ORIGIN).unwrap().last_used_ms(),
+        NOW_MS as u64 + 5_000
+    );
+    assert!(sites.get(OTHER_ORIGIN).is_none());
+
+    // Out of order requests do not move the last use back
+    sites.touch(ORIGIN, NOW_MS);
+    assert_eq!(
+        sites.get(ORIGIN).unwrap().last_used_ms(),
+        NOW_MS as u64 + 5_000
+    );
+}
+
+#[test]
+fn sign_in_sessions_are_read_from_the_signed_message() {
+    let mut sites = ConnectedSites::default();
+    sites.connect(ORIGIN, ACCOUNT, None, NOW_MS);
+
+    let session = SignInSession::new(ACCOUNT, &sign_in_message(&ACCOUNT), NOW_MS + 10.0).unwrap();
+    assert_eq!(session.account(), &ACCOUNT);
+    assert_eq!(session.domain(), Some("dapp.example"));
+    assert_eq!(session.chain_id(), Some("solana:devnet"));
+    assert_eq!(session.nonce(), Some("8f3kd92ma0"));
+    assert!(
+        session
+            .issued_at()
+            .unwrap()
+            .starts_with("2025-06-15T12:00:00")
+    );
+    assert!(
+        session
+            .expiration_time()
+            .unwrap()
+            .starts_with("2025-06-16T12:00:00")
+    );
+
+    sites.sign_in(ORIGIN, session.clone());
+    let site = sites.get(ORIGIN).unwrap();
+    assert_eq!(site.sign_in(), Some(&session));
+    assert_eq!(site.last_used_ms(), NOW_MS as u64 + 10);
+
+    assert!(SignInSession::new(ACCOUNT, "not a sign in message", NOW_MS).is_err());
+}
+
+#[test]
+fn revoking_a_site_removes_its_policy() {
+    let mut vault = WalletVault::new_test().unwrap();
+    for origin in [ORIGIN, OTHER_ORIGIN] {
+        vault.policies_mut().connect(origin, ACCOUNT).unwrap();
+        vault.sites_mut().connect(origin, ACCOUNT, None, NOW_MS);
+    }
+
+    assert_eq!(vault.revoke_site(ORIGIN).unwrap().origin(), ORIGIN);
+    assert!(vault.revoke_site(ORIGIN).is_none());
+    assert!(vault.policies().get(ORIGIN).is_none());
+    assert!(vault.policies().connected(Some(ORIGIN)).is_err());
+    assert!(vault.policies().get(OTHER_ORIGIN).is_some());
+
+    assert_eq!(vault.revoke_all_sites(), [OTHER_ORIGIN]);
+    assert_eq!(vault.sites().iter().count(), 0);
+    assert_eq!(vault.policies().iter().count(), 0);
+}
+
+#[test]
+fn connected_sites_round_trip_as_json() {
+    let mut sites = ConnectedSites::default();
+    sites.connect(
+        ORIGIN,
+        ACCOUNT,
+        Some("https://dapp.example/icon.svg".into()),
+        NOW_MS,
+    );
+    sites.connect(OTHER_ORIGIN, OTHER, None, NOW_MS);
+    sites.sign_in(
+        ORIGIN,
+        SignInSession::new(ACCOUNT, &sign_in_message(&ACCOUNT), NOW_MS).unwrap(),
+    );
+
+    let json = sites.to_json();
+    assert_eq!(ConnectedSites::from_json(&json).unwrap(), sites);
+
+    assert!(
+        ConnectedSites::from_json(&json.replacen("\"version\":1", "\"version\":2", 1)).is_err()
+    );
+    assert!(
+        ConnectedSites::from_json(&json.replacen(&ACCOUNT.to_string(), "not-an-address", 1))
+            .is_err()
+    );
+}
@@ -0,0 +1,190 @@
+//! Per-origin dapp policies and the spending their auto-approve rule is checked against
+
+use atoll_wallet_core::{
+    AtollWalletError, DappAutoApprove, DappFeature, DappPolicies, DappPolicy, SolanaCluster,
+    SolanaComputeBudget, SolanaSpending,
+};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+
+const ORIGIN: &str = "https://dapp.example";
+const ACCOUNT: Pubkey = Pubkey::new_from_array([1u8; 32]);
+const OTHER: Pubkey = Pubkey::new_from_array([2u8; 32]);
+
+fn message(instructions: &[Instruction], payer: &Pubkey) -> Message {
+    Message::new_with_blockhash(instructions, Some(payer), &solana_hash::Hash::default())
+}
+
+fn transfer(from: &Pubkey, to: &Pubkey, lamports: u64) -> Instruction {
+    solana_system_interface::instruction::transfer(from, to, lamports)
+}
+
+#[test]
+fn spending_counts_the_fees_and_the_transfers_of_the_account() {
+    let budget = SolanaComputeBudget {
+        unit_limit: Some(200_000),
+        unit_price: Some(1_000),
+    };
+    let instructions = budget.apply(&[
+        transfer(&ACCOUNT, &OTHER, 1_000),
+        transfer(&OTHER, &ACCOUNT, 50_000),
+    ]);
+
+    // Both signatures, a priority fee of 200 lamports and the outgoing transfer
+    assert_eq!(
+        SolanaSpending::of_message(&message(&instructions, &ACCOUNT), &ACCOUNT),
+        Some(2 * 5_000 + 200 + 1_000)
+    );
+    // Only the outgoing transfer once another account pays the fees
+    assert_eq!(
+        SolanaSpending::of_message(&message(&instructions, &OTHER), &ACCOUNT),
+        Some(1_000)
+    );
+}
+
+#[test]
+fn spending_is_unbounded_when_another_program_is_given_the_account() {
+    let instruction = Instruction::new_with_bytes(
+        Pubkey::new_from_array([3u8; 32]),
+        &[0],
+        vec![AccountMeta::new(ACCOUNT, true)],
+    );
+    assert_eq!(
+        SolanaSpending::of_message(&message(&[instruction], &ACCOUNT), &ACCOUNT),
+        None
+    );
+
+    let elsewhere = Instruction::new_with_bytes(
+        Pubkey::new_from_array([3u8; 32]),
+        &[0],
+        vec![AccountMeta::new(OTHER, true)],
+    );
+    assert_eq!(
+        SolanaSpending::of_message(&message(&[elsewhere], &OTHER), &ACCOUNT),
+        Some(0)
+    );
+}
+
+#[test]
+fn auto_approve_resets_every_utc_day() {
+    let now_ms = 20_000.0 * DappAutoApprove::DAY_MS + 1_000.0;
+    let mut auto_approve = DappAutoApprove::new(10_000);
+
+    auto_approve.spend(6_000, now_ms).unwrap();
+    assert_eq!(auto_approve.remaining(now_ms), 4_000);
+    assert!(matches!(
+        auto_approve.spend(6_000, now_ms),
+        Err(AtollWalletError::SpendingLimitExceeded(_))
+    ));
+    assert_eq!(auto_approve.remaining(now_ms), 4_000);
+
+    let tomorrow_ms = now_ms + DappAutoApprove::DAY_MS;
+    assert_eq!(auto_approve.remaining(tomorrow_ms), 10_000);
+    auto_approve.spend(6_000, tomorrow_ms).unwrap();
+    assert_eq!(auto_approve.remaining(tomorrow_ms), 4_000);
+}
+
+#[test]
+fn only_an_auto_approve_rule_limits_spending() {
+    let now_ms = 20_000.0 * DappAutoApprove::DAY_MS;
+    let large = message(&[transfer(&ACCOUNT, &OTHER, 1_000_000_000_000)], &ACCOUNT);
+    let mut policy = DappPolicy::new(ORIGIN, ACCOUNT);
+
+    // Without a rule nothing is counted, however much or often the dapp spends
+    for _ in 0..3 {
+        policy.spend(&ACCOUNT, &large, now_ms).unwrap();
+    }
+    policy.spend_undecoded().unwrap();
+    assert!(policy.auto_approve().is_none());
+
+    let mut policy = policy.set_auto_approve(Some(DappAutoApprove::new(10_000)));
+    assert!(matches!(
+        policy.spend(&ACCOUNT, &large, now_ms),
+        Err(AtollWalletError::SpendingLimitExceeded(_))
+    ));
+    assert!(matches!(
+        policy.spend_undecoded(),
+        Err(AtollWalletError::SpendingLimitExceeded(_))
+    ));
+
+    // Removing the transactions feature is the other way to stop the dapp spending
+    let policy = policy
+        .set_auto_approve(None)
+        .set_features(vec![DappFeature::SignIn, DappFeature::SignMessage]);
+    assert!(matches!(
+        policy.authorize(&ACCOUNT, DappFeature::Transactions),
+        Err(AtollWalletError::DappNotAuthorized(_))
+    ));
+}
+
+#[test]
+fn policies_refuse_what_the_dapp_was_not_granted() {
+    let mut policies = DappPolicies::default();
+
+    assert!(matches!(
+        policies.connected(Some(ORIGIN)),
+        Err(AtollWalletError::DappNotAuthorized(_))
+    ));
+    assert!(matches!(
+        policies.connected(None),
+        Err(AtollWalletError::InvalidRequest(_))
+    ));
+
+    policies.connect(ORIGIN, ACCOUNT).unwrap();
+    assert!(matches!(
+        policies.connect(ORIGIN, OTHER),
+        Err(AtollWalletError::DappNotAuthorized(_))
+    ));
+
+    let policy = policies
+        .connected(Some(ORIGIN))
+        .unwrap()
+        .clone()
+        .set_chains(vec![SolanaCluster::Mainnet])
+        .set_features(vec![DappFeature::SignIn]);
+
+    assert!(policy.authorize(&ACCOUNT, DappFeature::SignIn).is_ok());
+    assert!(
+        policy
+            .authorize(&ACCOUNT, DappFeature::Transactions)
+            .is_err()
+    );
+    assert!(policy.authorize(&OTHER, DappFeature::SignIn).is_err());
+    assert!(policy.authorize_chain(Some(SolanaCluster::Mainnet)).is_ok());
+    assert!(policy.authorize_chain(Some(SolanaCluster::Devnet)).is_err());
+    assert!(policy.authorize_chain(None).is_err());
+}
+
+#[test]
+fn policies_round_trip_as_json() {
+    let now_ms = 20_000.0 * DappAutoApprove::DAY_MS;
+    let mut policy = DappPolicy::new(ORIGIN, ACCOUNT)
+        .set_accounts(vec![ACCOUNT, OTHER])
+        .set_chains(vec![SolanaCluster::Devnet])
+        .set_auto_approve(Some(DappAutoApprove::new(10_000)));
+    policy
+        .spend(
+            &ACCOUNT,
+            &message(&[transfer(&ACCOUNT, &OTHER, 1_000)], &ACCOUNT),
+            now_ms,
+        )
+        .unwrap();
+
+    let mut policies = DappPolicies::default();
+    policies
+        .insert(policy)
+        .insert(DappPolicy::new("https://other.example", OTHER));
+
+    let restored = DappPolicies::from_json(&policies.to_json()).unwrap();
+    assert_eq!(restored, policies);
+    assert_eq!(
+        restored
+            .get(ORIGIN)
+            .and_then(DappPolicy::auto_approve)
+            .map(|auto_approve| auto_approve.remaining(now_ms)),
+        Some(4_000)
+    );
+
+    assert!(DappPolicies::from_json(r#"{"version":2,"policies":[]}"#).is_err());
+}
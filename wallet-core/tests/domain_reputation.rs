@@ -0,0 +1,239 @@
+//! The phishing protection: signed domain lists, lookalikes of popular dapps and newly seen
+//! domains, checked offline against the fixture lists in `tests/fixtures/domain_lists`
+
+use atoll_wallet_core::{
+    AtollWalletError, DappAutoApprove, DomainAction, DomainHost, DomainList, DomainLookalike,
+    DomainReputation, DomainReputationPolicy, DomainVerdict, Punycode,
+};
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signer::Signer;
+
+const SEQUENCE_1: &str = include_str!("fixtures/domain_lists/sequence_1.json");
+const SEQUENCE_2: &str = include_str!("fixtures/domain_lists/sequence_2.json");
+/// Signed by another key than the publisher
+const FORGED: &str = include_str!("fixtures/domain_lists/forged.json");
+
+const NOW_MS: f64 = 20_000.0 * DappAutoApprove::DAY_MS;
+
+/// The key the fixture lists are signed with
+fn publisher() -> Pubkey {
+    Keypair::new_from_array([7u8; 32]).pubkey()
+}
+
+fn verdict(reputation: &DomainReputation, origin: &str) -> DomainVerdict {
+    reputation.check(origin, NOW_MS).unwrap().verdict().clone()
+}
+
+#[test]
+fn punycode_labels_decode_to_unicode() {
+    let cases = [
+        ("xn--mnchen-3ya", "münchen"),
+        ("xn--bcher-kva", "bücher"),
+        ("xn--jp-red", "jսp"),
+        ("xn--raydum-svf", "raydіum"),
+        ("solana", "solana"),
+    ];
+
+    for (label, decoded) in cases {
+        assert_eq!(Punycode::decode_label(label).as_deref(), Some(decoded));
+    }
+
+    assert_eq!(Punycode::decode_label("xn--99999999999999"), None);
+    assert_eq!(Punycode::decode_label("xn--a!b"), None);
+}
+
+#[test]
+fn hosts_are_read_from_origins() {
+    let host = DomainHost::from_origin("https://Station.JUP.ag:8443/swap?x=1").unwrap();
+    assert_eq!(host.ascii(), "station.jup.ag");
+    assert!(host.is_within("jup.ag"));
+    assert!(!host.is_within("p.ag"));
+    assert_eq!(
+        host.domains().collect::<Vec<_>>(),
+        ["station.jup.ag", "jup.ag", "ag"]
+    );
+
+    let host = DomainHost::from_origin("https://xn--jp-red.ag").unwrap();
+    assert_eq!(host.unicode(), "jսp.ag");
+
+    assert_eq!(
+        DomainHost::from_origin("http://[::1]:3000")
+            .unwrap()
+            .ascii(),
+        "[::1]"
+    );
+
+    for origin in [
+        "jup.ag",
+        "https://",
+        "https://user@jup.ag",
+        "https://jսp.ag",
+        "https://a..ag",
+        "https://xn--a!b.ag",
+    ] {
+        assert!(DomainHost::from_origin(origin).is_err(), "{origin}");
+    }
+}
+
+#[test]
+fn lookalikes_of_popular_dapps_are_detected() {
+    let lookalikes = [
+        ("https://xn--jp-red.ag", "jup.ag"),
+        ("https://xn--raydum-svf.io", "raydium.io"),
+        ("https://xn--phntom-4nf.app", "phantom.app"),
+        ("https://xn--rca-red.so", "orca.so"),
+        ("https://raydlum.io", "raydium.io"),
+        ("https://0rca.so", "orca.so"),
+        ("https://rnagiceden.io", "magiceden.io"),
+        ("https://jup.ag.claim-rewards.example", "jup.ag"),
+    ];
+
+    for (origin, popular) in lookalikes {
+        let host = DomainHost::from_origin(origin).unwrap();
+        assert_eq!(DomainLookalike::of(&host), Some(popular), "{origin}");
+    }
+
+    for origin in [
+        "https://jup.ag",
+        "https://station.jup.ag",
+        "https://jupiter.example",
+        "https://orca.example",
+    ] {
+        let host = DomainHost::from_origin(origin).unwrap();
+        assert_eq!(DomainLookalike::of(&host), None, "{origin}");
+    }
+}
+
+#[test]
+fn signed_lists_replace_older_lists_from_the_publisher() {
+    let mut reputation = DomainReputation::default();
+
+    assert_eq!(reputation.update(SEQUENCE_1, &publisher()).unwrap(), 1);
+    assert_eq!(
+        verdict(&reputation, "https://app.drainer.example"),
+        DomainVerdict::Blocklisted
+    );
+    assert_eq!(
+        verdict(&reputation, "https://wallet-drainer.example"),
+        DomainVerdict::NewlySeen
+    );
+
+    assert_eq!(reputation.update(SEQUENCE_2, &publisher()).unwrap(), 2);
+    assert_eq!(
+        verdict(&reputation, "https://wallet-drainer.example"),
+        DomainVerdict::Blocklisted
+    );
+    assert_eq!(
+        verdict(&reputation, "https://docs.new-dapp.example"),
+        DomainVerdict::Trusted
+    );
+
+    // Older lists cannot roll back the blocklist
+    assert!(reputation.update(SEQUENCE_1, &publisher()).is_err());
+    assert!(reputation.update(SEQUENCE_2, &publisher()).is_err());
+    assert_eq!(reputation.list().sequence(), 2);
+
+    assert!(reputation.update(FORGED, &publisher()).is_err());
+    assert!(
+        reputation
+            .update(SEQUENCE_2, &Keypair::new_from_array([8u8; 32]).pubkey())
+            .is_err()
+    );
+    assert_eq!(
+        verdict(&reputation, "https://drainer.example"),
+        DomainVerdict::Blocklisted
+    );
+}
+
+#[test]
+fn tampered_lists_are_refused() {
+    let tampered = SEQUENCE_2.replacen("eyJ2", "eyJ3", 1);
+
+    assert!(DomainList::from_signed(&tampered, &publisher()).is_err());
+    assert!(DomainList::from_signed("{}", &publisher()).is_err());
+
+    let list = DomainList::new(5, ["evil.example"], []).unwrap();
+    let keypair = Keypair::new_from_array([9u8; 32]);
+    assert_eq!(
+        DomainList::from_signed(&list.to_signed(&keypair), &keypair.pubkey()).unwrap(),
+        list
+    );
+}
+
+#[test]
+fn the_policy_decides_to_warn_or_block() {
+    let mut reputation = DomainReputation::default();
+    reputation.update(SEQUENCE_2, &publisher()).unwrap();
+
+    assert!(matches!(
+        reputation.authorize("https://drainer.example", NOW_MS),
+        Err(AtollWalletError::PhishingDomain(_))
+    ));
+    assert!(matches!(
+        reputation.authorize("https://xn--jp-red.ag", NOW_MS),
+        Err(AtollWalletError::PhishingDomain(_))
+    ));
+
+    let report = reputation
+        .authorize("https://new-dapp.example", NOW_MS)
+        .unwrap();
+    assert_eq!(report.action(), DomainAction::Warn);
+    assert!(report.reason().unwrap().contains("first seen recently"));
+
+    let report = reputation.authorize("https://jup.ag", NOW_MS).unwrap();
+    assert_eq!(report.action(), DomainAction::Allow);
+    assert_eq!(report.reason(), None);
+
+    reputation.set_policy(DomainReputationPolicy {
+        blocklisted: DomainAction::Warn,
+        newly_seen: DomainAction::Block,
+        ..DomainReputationPolicy::default()
+    });
+    assert_eq!(
+        reputation
+            .authorize("https://drainer.example", NOW_MS)
+            .unwrap()
+            .action(),
+        DomainAction::Warn
+    );
+    assert!(matches!(
+        reputation.authorize("https://another-dapp.example", NOW_MS),
+        Err(AtollWalletError::PhishingDomain(_))
+    ));
+}
+
+#[test]
+fn domains_stop_being_new_after_the_policy_days() {
+    let mut reputation = DomainReputation::default();
+    let origin = "https://new-dapp.example";
+
+    assert_eq!(verdict(&reputation, origin), DomainVerdict::NewlySeen);
+    reputation.authorize(origin, NOW_MS).unwrap();
+    let host = DomainHost::from_origin(origin).unwrap();
+    assert_eq!(reputation.first_seen(&host), Some(NOW_MS as u64));
+
+    let days = reputation.policy().newly_seen_days as f64;
+    let report = reputation
+        .authorize(origin, NOW_MS + days * DappAutoApprove::DAY_MS)
+        .unwrap();
+    assert_eq!(report.verdict(), &DomainVerdict::Known);
+    assert_eq!(reputation.first_seen(&host), Some(NOW_MS as u64));
+}
+
+#[test]
+fn the_reputation_round_trips_as_json() {
+    let mut reputation = DomainReputation::default();
+    reputation.update(SEQUENCE_2, &publisher()).unwrap();
+    reputation
+        .set_policy(DomainReputationPolicy {
+            newly_seen_days: 30,
+            ..DomainReputationPolicy::default()
+        })
+        .authorize("https://new-dapp.example", NOW_MS)
+        .unwrap();
+
+    let mut restored = DomainReputation::from_json(&reputation.to_json()).unwrap();
+    assert_eq!(restored, reputation);
+    assert!(restored.update(SEQUENCE_2, &publisher()).is_err());
+}
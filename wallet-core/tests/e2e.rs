@@ -0,0 +1,1163 @@
+//! Drives wallet-standard requests through a fake `runtime.onMessage` into the background
+//! and a mock JSON-RPC cluster, asserting on the response envelopes.
+
+mod common;
+
+use atoll_wallet_core::{
+    App, AtollWalletError, ProtocolHeader, ProtocolRequest, SolanaComputeBudget, SolanaConstants,
+    SolanaOffchainMessage, SolanaPriorityLevel, WalletVault,
+};
+use base64ct::{Base64, Encoding};
+use serde_json::{Value, json};
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use common::{FakeRuntime, MemoryStorage, MockRpcServer, MockRpcTransport, NOW_MS, block_on};
+
+const ORIGIN: &str = "https://dapp.example";
+const EXTENSION_ORIGIN: &str = "chrome-extension://atoll";
+const DEVNET: &str = "solana:devnet";
+const SENT_SIGNATURE: &str =
+    "5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW";
+
+struct Harness {
+    runtime: FakeRuntime,
+    server: MockRpcServer,
+    transport: MockRpcTransport,
+    storage: MemoryStorage,
+    public_key: [u8; 32],
+    blockhash: solana_hash::Hash,
+    next_id: std::cell::Cell<usize>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self::with_priority_level(None)
+    }
+
+    fn with_priority_level(level: Option<SolanaPriorityLevel>) -> Self {
+        let harness = Self::start(level, MemoryStorage::default());
+        harness.ok("standard:connect", Value::Null);
+
+        harness
+    }
+
+    /// Starts a new background on the storage of this one, as when the browser restarts it
+    fn restart(&self) -> Self {
+        Self::start(None, self.storage.clone())
+    }
+
+    fn start(level: Option<SolanaPriorityLevel>, storage: MemoryStorage) -> Self {
+        let server = MockRpcServer::start();
+        let blockhash = solana_hash::Hash::new_from_array([7u8; 32]);
+        server.script_cluster(&blockhash, 4_000, &[0, 100, 2_000, 50_000], SENT_SIGNATURE);
+
+        let transport = server.transport();
+        let mut app = App::new(transport.clone())
+            .set_vault(WalletVault::new_test().unwrap())
+            .unwrap()
+            .set_extension_origin(EXTENSION_ORIGIN)
+            .set_domain_list_publisher(Keypair::new_from_array([7u8; 32]).pubkey())
+            .set_storage(storage.clone());
+        if let Some(level) = level {
+            app = app.set_priority_level(level);
+        }
+        block_on(app.load_storage()).unwrap();
+        let public_key = block_on(app.active_account()).unwrap().to_bytes();
+
+        let runtime = FakeRuntime::new();
+        common::listen(app, &runtime);
+
+        Self {
+            runtime,
+            server,
+            transport,
+            storage,
+            public_key,
+            blockhash,
+            next_id: std::cell::Cell::new(0),
+        }
+    }
+
+    /// Replaces the policy of the dapp, as the wallet settings do
+    fn set_policy(&self, policy: Value) -> Value {
+        let mut params = json!({ "origin": ORIGIN });
+        params
+            .as_object_mut()
+            .unwrap()
+            .extend(policy.as_object().unwrap().clone());
+
+        self.popup("atoll:setDappPolicy", params)
+    }
+
+    fn pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.public_key)
+    }
+
+    /// Sends `method` from the dapp and returns the full response envelope, checking it
+    /// answers the request
+    fn envelope(&self, method: &str, params: Value) -> Value {
+        self.envelope_from(ORIGIN, method, params)
+    }
+
+    fn envelope_from(&self, origin: &str, method: &str, params: Value) -> Value {
+        let id = format!("request-{}", self.next_id.replace(self.next_id.get() + 1));
+        let response = self.runtime.request(&id, method, origin, params);
+
+        assert_eq!(response["version"], json!(ProtocolHeader::VERSION));
+        assert_eq!(response["id"], json!(id));
+
+        response
+    }
+
+    fn ok(&self, method: &str, params: Value) -> Value {
+        let response = self.envelope(method, params);
+        assert!(response["err"].is_null(), "{method} failed: {response}");
+
+        response["ok"].clone()
+    }
+
+    /// Sends `method` from the popup of the extension
+    fn popup(&self, method: &str, params: Value) -> Value {
+        let response = self.envelope_from(EXTENSION_ORIGIN, method, params);
+        assert!(response["err"].is_null(), "{method} failed: {response}");
+
+        response["ok"].clone()
+    }
+
+    fn popup_err(&self, method: &str, params: Value) -> Value {
+        let response = self.envelope_from(EXTENSION_ORIGIN, method, params);
+        assert!(response["ok"].is_null(), "{method} should fail: {response}");
+
+        response["err"].clone()
+    }
+
+    fn err(&self, method: &str, params: Value) -> Value {
+        let response = self.envelope(method, params);
+        assert!(response["ok"].is_null(), "{method} should fail: {response}");
+
+        response["err"].clone()
+    }
+
+    fn account(&self) -> Value {
+        json!({ "publicKey": self.public_key.to_vec() })
+    }
+
+    /// A transfer from the account with a stale blockhash, as a dapp builds it
+    fn transfer(&self, lamports: u64) -> Transaction {
+        let payer = self.pubkey();
+        let instruction = solana_system_interface::instruction::transfer(
+            &payer,
+            &Pubkey::new_from_array([9u8; 32]),
+            lamports,
+        );
+
+        Transaction::new_unsigned(Message::new_with_blockhash(
+            &[instruction],
+            Some(&payer),
+            &solana_hash::Hash::default(),
+        ))
+    }
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+    serde_json::from_value::<Vec<u8>>(value.clone()).expect("an array of bytes")
+}
+
+fn signature(value: &Value) -> Signature {
+    Signature::try_from(bytes(value).as_slice()).expect("a 64 byte signature")
+}
+
+/// The transaction sent to the cluster in the last `sendTransaction`
+fn sent_transaction(server: &MockRpcServer) -> Transaction {
+    let params = server
+        .last_params("sendTransaction")
+        .expect("the transaction was sent");
+    let encoded = params[0].as_str().expect("a base64 transaction");
+
+    bincode::deserialize(&Base64::decode_vec(encoded).expect("valid base64"))
+        .expect("a valid transaction")
+}
+
+#[test]
+fn connect_returns_the_active_account() {
+    let harness = Harness::new();
+
+    let output = harness.ok("standard:connect", Value::Null);
+    assert_eq!(output["accounts"].as_array().unwrap().len(), 1);
+    let account = &output["accounts"][0];
+
+    assert_eq!(account["address"], json!(harness.pubkey().to_string()));
+    assert_eq!(bytes(&account["publicKey"]), harness.public_key);
+    assert!(
+        account["chains"]
+            .as_array()
+            .unwrap()
+            .contains(&json!(DEVNET))
+    );
+    assert_eq!(
+        account["features"],
+        json!([
+            SolanaConstants::SIGN_IN,
+            SolanaConstants::SIGN_MESSAGE,
+            SolanaConstants::SIGN_TRANSACTION,
+            SolanaConstants::SIGN_AND_SEND_TRANSACTION,
+        ])
+    );
+    assert!(harness.server.calls().is_empty());
+}
+
+#[test]
+fn sign_in_signs_the_formatted_message() {
+    let harness = Harness::new();
+
+    let output = harness.ok(
+        "solana:signIn",
+        json!({
+            "domain": "dapp.example",
+            "address": harness.pubkey().to_string(),
+            "statement": "Sign in to the example dapp",
+            "uri": "https://dapp.example/login",
+            "version": "1",
+            "chainId": DEVNET,
+            "nonce": "8f3kd92ma0",
+            "issuedAt": "2025-06-15T12:00:00Z",
+            "resources": ["https://dapp.example/terms", " "],
+        }),
+    );
+
+    let output = &output[0];
+    let message = String::from_utf8(bytes(&output["signedMessage"])).unwrap();
+
+    assert!(message.starts_with(&format!(
+        "dapp.example wants you to sign in with your Solana account: \n{}\n\nSign in to the example dapp\n\n",
+        harness.pubkey()
+    )));
+    assert!(message.contains("URI: https://dapp.example/login\n"));
+    assert!(message.contains("Nonce: 8f3kd92ma0\n"));
+    assert!(message.contains("Issued At: 2025-06-15T12:00:00"));
+    assert!(message.ends_with("Resources:\n- https://dapp.example/terms\n"));
+
+    assert_eq!(output["signatureType"], json!("ed25519"));
+    assert_eq!(bytes(&output["account"]["publicKey"]), harness.public_key);
+    assert!(signature(&output["signature"]).verify(&harness.public_key, message.as_bytes()));
+}
+
+#[test]
+fn sign_in_rejects_an_expired_request() {
+    let harness = Harness::new();
+
+    let err = harness.err(
+        "solana:signIn",
+        json!({ "domain": "dapp.example", "expirationTime": "2020-01-01T00:00:00Z" }),
+    );
+
+    assert_eq!(err["code"], json!(-32602));
+    assert_eq!(err["data"]["category"], json!("invalidParams"));
+    assert!(err["message"].as_str().unwrap().contains("expiry time"));
+}
+
+#[test]
+fn sign_message_signs_the_bytes() {
+    let harness = Harness::new();
+    let message = b"Hello from the example dapp".to_vec();
+
+    let output = harness.ok(
+        "solana:signMessage",
+        json!({ "account": harness.account(), "message": message }),
+    );
+
+    let output = &output[0];
+    assert_eq!(bytes(&output["signedMessage"]), message);
+    assert_eq!(output["signatureType"], json!("ed25519"));
+    assert!(signature(&output["signature"]).verify(&harness.public_key, &message));
+}
+
+#[test]
+fn sign_message_refuses_transactions() {
+    let harness = Harness::new();
+    let transaction = harness.transfer(5_000);
+
+    for message in [
+        transaction.message_data(),
+        bincode::serialize(&transaction).unwrap(),
+    ] {
+        let err = harness.err(
+            "solana:signMessage",
+            json!({ "account": harness.account(), "message": message }),
+        );
+
+        assert_eq!(err["code"], json!(-32602));
+        assert!(err["message"].as_str().unwrap().contains("transaction"));
+    }
+}
+
+#[test]
+fn sign_message_signs_offchain_messages_for_the_account() {
+    let harness = Harness::new();
+    let message = SolanaOffchainMessage::new(
+        [3u8; 32],
+        vec![harness.pubkey()],
+        b"Approve the trade".to_vec(),
+    )
+    .unwrap()
+    .encode();
+
+    let output = harness.ok(
+        "solana:signMessage",
+        json!({ "account": harness.account(), "message": message }),
+    );
+    assert!(signature(&output[0]["signature"]).verify(&harness.public_key, &message));
+
+    let other_signer = SolanaOffchainMessage::new(
+        [3u8; 32],
+        vec![Pubkey::new_from_array([4u8; 32])],
+        b"Approve the trade".to_vec(),
+    )
+    .unwrap()
+    .encode();
+    let err = harness.err(
+        "solana:signMessage",
+        json!({ "account": harness.account(), "message": other_signer }),
+    );
+    assert!(err["message"].as_str().unwrap().contains("not a signer"));
+}
+
+#[test]
+fn sign_transaction_signs_without_sending() {
+    let harness = Harness::new();
+    let transaction = harness.transfer(5_000);
+
+    let output = harness.ok(
+        "solana:signTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&transaction).unwrap(),
+        }),
+    );
+
+    let signed: Transaction = bincode::deserialize(&bytes(&output[0]["signedTransaction"]))
+        .expect("a signed transaction");
+
+    assert_eq!(signed.message, transaction.message);
+    assert!(signed.verify().is_ok());
+    assert!(harness.server.calls().is_empty());
+}
+
+#[test]
+fn sign_transaction_rejects_invalid_bytes() {
+    let harness = Harness::new();
+
+    let err = harness.err(
+        "solana:signTransaction",
+        json!({ "account": harness.account(), "transaction": [1, 2, 3] }),
+    );
+
+    assert_eq!(err["code"], json!(-32602));
+}
+
+#[test]
+fn sign_and_send_transaction_uses_the_latest_blockhash() {
+    let harness = Harness::new();
+    let transaction = harness.transfer(5_000);
+
+    let output = harness.ok(
+        "solana:signAndSendTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&transaction).unwrap(),
+            "chain": DEVNET,
+            "options": { "skipPreflight": true, "maxRetries": 3 },
+        }),
+    );
+
+    assert_eq!(
+        harness.server.methods(),
+        ["getLatestBlockhash", "sendTransaction"]
+    );
+    assert!(
+        harness
+            .transport
+            .urls()
+            .iter()
+            .all(|url| url == "https://api.devnet.solana.com")
+    );
+
+    let sent = sent_transaction(&harness.server);
+    assert_eq!(sent.message.recent_blockhash, harness.blockhash);
+    assert!(sent.verify().is_ok());
+    assert_eq!(
+        signature(&output[0]["signature"]).to_string(),
+        SENT_SIGNATURE
+    );
+
+    let options = &harness.server.last_params("sendTransaction").unwrap()[1];
+    assert_eq!(options["skip_preflight"], json!(true));
+    assert_eq!(options["max_retries"], json!(3));
+    assert_eq!(options["encoding"], json!("base64"));
+}
+
+#[test]
+fn sign_and_send_transaction_keeps_the_blockhash_of_other_signers() {
+    let harness = Harness::new();
+    let co_signer = Keypair::new_from_array([5u8; 32]);
+    let stale = solana_hash::Hash::new_from_array([3u8; 32]);
+
+    // The wallet pays the fee and the co-signer moves its own funds
+    let instruction = solana_system_interface::instruction::transfer(
+        &co_signer.pubkey(),
+        &Pubkey::new_from_array([9u8; 32]),
+        1_000,
+    );
+    let mut transaction = Transaction::new_unsigned(Message::new_with_blockhash(
+        &[instruction],
+        Some(&harness.pubkey()),
+        &stale,
+    ));
+    transaction.partial_sign(&[&co_signer], stale);
+
+    harness.ok(
+        "solana:signAndSendTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&transaction).unwrap(),
+            "chain": DEVNET,
+        }),
+    );
+
+    let sent = sent_transaction(&harness.server);
+    assert_eq!(sent.message.recent_blockhash, stale);
+    assert_eq!(sent.signatures[1], transaction.signatures[1]);
+    assert!(sent.verify().is_ok());
+}
+
+#[test]
+fn signing_as_another_account_is_refused() {
+    let harness = Harness::new();
+    let other = json!({ "publicKey": Pubkey::new_from_array([9u8; 32]).to_bytes().to_vec() });
+    let transaction = bincode::serialize(&harness.transfer(1)).unwrap();
+
+    for (method, params) in [
+        (
+            "solana:signTransaction",
+            json!({ "account": other, "transaction": transaction }),
+        ),
+        (
+            "solana:signAndSendTransaction",
+            json!({ "account": other, "transaction": transaction, "chain": DEVNET }),
+        ),
+    ] {
+        let err = harness.err(method, params);
+        assert_eq!(
+            err["data"]["category"],
+            json!("resourceNotFound"),
+            "{method}"
+        );
+    }
+
+    assert!(
+        !harness
+            .server
+            .methods()
+            .contains(&"sendTransaction".to_string())
+    );
+}
+
+#[test]
+fn sign_and_send_transaction_sets_the_compute_budget_when_opted_in() {
+    let harness = Harness::with_priority_level(Some(SolanaPriorityLevel::High));
+    let transaction = harness.transfer(5_000);
+
+    harness.ok(
+        "solana:signAndSendTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&transaction).unwrap(),
+            "chain": DEVNET,
+        }),
+    );
+
+    assert_eq!(
+        harness.server.methods(),
+        [
+            "getLatestBlockhash",
+            "getRecentPrioritizationFees",
+            "simulateTransaction",
+            "sendTransaction"
+        ]
+    );
+
+    let sent = sent_transaction(&harness.server);
+    let budget = SolanaComputeBudget::from_instructions(
+        &SolanaComputeBudget::decompile(&sent.message).unwrap(),
+    );
+
+    // 4000 simulated units with a 10% margin
+    assert_eq!(budget.unit_limit, Some(4_400));
+    assert!(budget.unit_price.unwrap() > 0);
+    assert_eq!(sent.message.recent_blockhash, harness.blockhash);
+    assert!(sent.verify().is_ok());
+}
+
+#[test]
+fn sign_and_send_transaction_surfaces_rpc_errors() {
+    let harness = Harness::new();
+    harness
+        .server
+        .reply_error("sendTransaction", -32002, "Blockhash not found");
+
+    let err = harness.err(
+        "solana:signAndSendTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&harness.transfer(1)).unwrap(),
+            "chain": DEVNET,
+        }),
+    );
+
+    assert_eq!(err["code"], json!(-32002));
+    assert_eq!(err["data"]["category"], json!("resourceUnavailable"));
+    assert!(
+        err["message"]
+            .as_str()
+            .unwrap()
+            .contains("Blockhash not found")
+    );
+}
+
+/// Drives `App::handle` of the core directly, as the extension does, so the negative
+/// JSON-RPC codes of `sendTransaction` are read by the real handler
+#[test]
+fn sign_and_send_transaction_handler_returns_rpc_errors() {
+    let server = MockRpcServer::start();
+    let blockhash = solana_hash::Hash::new_from_array([7u8; 32]);
+    server.script_cluster(&blockhash, 4_000, &[], SENT_SIGNATURE);
+
+    let app = App::new(server.transport())
+        .set_vault(WalletVault::new_test().unwrap())
+        .unwrap();
+    let payer = block_on(app.active_account()).unwrap();
+    let handle = |method: &str, params: Value| {
+        let request = ProtocolRequest::from_json(&json!({
+            "version": ProtocolHeader::VERSION,
+            "id": method,
+            "method": method,
+            "origin": ORIGIN,
+            "params": params,
+        }))
+        .unwrap();
+
+        block_on(app.handle(&request, Some(ORIGIN.to_string()), NOW_MS))
+    };
+
+    handle("standard:connect", Value::Null).unwrap();
+
+    let transaction = Transaction::new_unsigned(Message::new_with_blockhash(
+        &[solana_system_interface::instruction::transfer(
+            &payer,
+            &Pubkey::new_from_array([9u8; 32]),
+            1,
+        )],
+        Some(&payer),
+        &solana_hash::Hash::default(),
+    ));
+    let params = json!({
+        "account": { "publicKey": payer.to_bytes().to_vec() },
+        "transaction": bincode::serialize(&transaction).unwrap(),
+        "chain": DEVNET,
+    });
+
+    server.reply_error("sendTransaction", -32002, "Blockhash not found");
+    let error = handle("solana:signAndSendTransaction", params.clone()).unwrap_err();
+    assert_eq!(
+        error,
+        AtollWalletError::SolanaRpc(
+            "`sendTransaction` failed with code `-32002`. Blockhash not found".to_string()
+        )
+    );
+    assert_eq!(error.code(), -32002);
+
+    // A response without a result is not passed to the dapp as a success
+    server.reply("sendTransaction", Value::Null);
+    let error = handle("solana:signAndSendTransaction", params).unwrap_err();
+    assert!(matches!(error, AtollWalletError::SolanaRpc(_)));
+}
+
+#[test]
+fn unconnected_origins_cannot_sign() {
+    let harness = Harness::new();
+
+    let response = harness.runtime.request(
+        "other-dapp",
+        "solana:signMessage",
+        "https://other.example",
+        json!({ "account": harness.account(), "message": b"hello".to_vec() }),
+    );
+
+    assert_eq!(response["err"]["code"], json!(4100));
+    assert!(
+        response["err"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("`https://other.example` is not connected")
+    );
+}
+
+#[test]
+fn dapp_policies_restrict_features_and_chains() {
+    let harness = Harness::new();
+
+    let policy = harness.set_policy(json!({
+        "chains": ["solana:mainnet"],
+        "features": ["signIn", "transactions"],
+    }));
+    assert_eq!(policy["chains"], json!(["solana:mainnet"]));
+    assert_eq!(
+        harness.popup("atoll:listDappPolicies", Value::Null),
+        json!([policy])
+    );
+
+    let err = harness.err(
+        "solana:signMessage",
+        json!({ "account": harness.account(), "message": b"hello".to_vec() }),
+    );
+    assert_eq!(err["code"], json!(4100));
+
+    let err = harness.err(
+        "solana:signTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&harness.transfer(1)).unwrap(),
+            "chain": DEVNET,
+        }),
+    );
+    assert_eq!(err["code"], json!(4100));
+    assert!(harness.server.calls().is_empty());
+}
+
+#[test]
+fn auto_approve_stops_at_the_daily_allowance() {
+    let harness = Harness::new();
+    harness.set_policy(json!({ "autoApprove": { "lamportsPerDay": "10000" } }));
+    let params = json!({
+        "account": harness.account(),
+        "transaction": bincode::serialize(&harness.transfer(1_000)).unwrap(),
+        "chain": DEVNET,
+    });
+
+    // The transfer and the fee of its signature
+    harness.ok("solana:signAndSendTransaction", params.clone());
+    let policies = harness.popup("atoll:listDappPolicies", Value::Null);
+    assert_eq!(
+        policies[0]["autoApprove"],
+        json!({ "lamportsPerDay": "10000", "remainingToday": "4000" })
+    );
+
+    let err = harness.err("solana:signAndSendTransaction", params);
+    assert_eq!(err["code"], json!(4100));
+    assert_eq!(
+        harness
+            .server
+            .methods()
+            .iter()
+            .filter(|method| *method == "sendTransaction")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn dapp_policies_survive_a_restart() {
+    let harness = Harness::new();
+    harness.set_policy(json!({
+        "chains": [DEVNET],
+        "features": ["transactions"],
+        "autoApprove": { "lamportsPerDay": "10000" },
+    }));
+    let params = json!({
+        "account": harness.account(),
+        "transaction": bincode::serialize(&harness.transfer(1_000)).unwrap(),
+        "chain": DEVNET,
+    });
+    harness.ok("solana:signAndSendTransaction", params.clone());
+    let policies = harness.popup("atoll:listDappPolicies", Value::Null);
+
+    // The allowance spent before the restart still counts
+    let restarted = harness.restart();
+    assert_eq!(
+        restarted.popup("atoll:listDappPolicies", Value::Null),
+        policies
+    );
+    let err = restarted.err("solana:signAndSendTransaction", params);
+    assert_eq!(err["code"], json!(4100));
+    assert!(restarted.server.methods().is_empty());
+
+    restarted.popup("atoll:revokeSite", json!({ "origin": ORIGIN }));
+    assert_eq!(
+        restarted
+            .restart()
+            .popup("atoll:listDappPolicies", Value::Null),
+        json!([])
+    );
+}
+
+#[test]
+fn unauthorized_transactions_never_reach_the_cluster() {
+    let harness = Harness::with_priority_level(Some(SolanaPriorityLevel::High));
+    harness.set_policy(json!({ "features": ["signMessage"] }));
+
+    let err = harness.err(
+        "solana:signAndSendTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&harness.transfer(1)).unwrap(),
+            "chain": DEVNET,
+        }),
+    );
+
+    assert_eq!(err["code"], json!(4100));
+    assert!(harness.server.calls().is_empty());
+}
+
+#[test]
+fn auto_approve_counts_the_priority_fee() {
+    let harness = Harness::with_priority_level(Some(SolanaPriorityLevel::High));
+    harness.set_policy(json!({ "autoApprove": { "lamportsPerDay": "10000" } }));
+
+    harness.ok(
+        "solana:signAndSendTransaction",
+        json!({
+            "account": harness.account(),
+            "transaction": bincode::serialize(&harness.transfer(1_000)).unwrap(),
+            "chain": DEVNET,
+        }),
+    );
+
+    let sent = sent_transaction(&harness.server);
+    let budget = SolanaComputeBudget::from_instructions(
+        &SolanaComputeBudget::decompile(&sent.message).unwrap(),
+    );
+    let priority_fee =
+        (budget.unit_price.unwrap() * u64::from(budget.unit_limit.unwrap())).div_ceil(1_000_000);
+    assert!(priority_fee > 0);
+
+    let policies = harness.popup("atoll:listDappPolicies", Value::Null);
+    assert_eq!(
+        policies[0]["autoApprove"]["remainingToday"],
+        json!((10_000 - 1_000 - 5_000 - priority_fee).to_string())
+    );
+}
+
+#[test]
+fn bitcoin_requests_follow_the_dapp_policy() {
+    let harness = Harness::new();
+    let sign_message = |address: &Value| json!({ "account": { "address": address }, "message": b"hello".to_vec() });
+
+    let response = harness.runtime.request(
+        "other-dapp",
+        "bitcoin:signMessage",
+        "https://other.example",
+        sign_message(&json!("bc1qunknown")),
+    );
+    assert_eq!(response["err"]["code"], json!(4100));
+
+    let accounts = harness.ok("bitcoin:connect", Value::Null)["accounts"].clone();
+    let address = &accounts[0]["address"];
+    let output = harness.ok("bitcoin:signMessage", sign_message(address));
+    assert_eq!(bytes(&output[0]["signedMessage"]), b"hello");
+
+    harness.set_policy(json!({ "features": ["signIn"] }));
+    let err = harness.err("bitcoin:signMessage", sign_message(address));
+    assert_eq!(err["code"], json!(4100));
+
+    // What a Bitcoin transaction spends is not counted against a daily allowance
+    harness.set_policy(json!({
+        "features": ["transactions"],
+        "autoApprove": { "lamportsPerDay": "10000" },
+    }));
+    let err = harness.err("bitcoin:signTransaction", json!({ "psbt": [0] }));
+    assert_eq!(err["code"], json!(4100));
+    assert!(
+        err["message"]
+            .as_str()
+            .unwrap()
+            .contains("cannot be decoded")
+    );
+}
+
+#[test]
+fn lookalike_origins_cannot_connect() {
+    let harness = Harness::new();
+
+    let report = harness.ok(
+        "atoll:checkDomain",
+        json!({ "origin": "https://xn--jp-red.ag" }),
+    );
+    assert_eq!(report["unicodeHost"], json!("jսp.ag"));
+    assert_eq!(report["verdict"], json!("lookalike"));
+    assert_eq!(report["lookalikeOf"], json!("jup.ag"));
+    assert_eq!(report["action"], json!("block"));
+
+    let response = harness.runtime.request(
+        "lookalike",
+        "standard:connect",
+        "https://xn--jp-red.ag",
+        Value::Null,
+    );
+    assert_eq!(response["err"]["code"], json!(4100));
+    assert!(
+        response["err"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("looks like `jup.ag`")
+    );
+}
+
+#[test]
+fn signed_domain_lists_block_by_policy() {
+    let harness = Harness::new();
+    let drainer = "https://drainer.example";
+
+    let updated = harness.popup(
+        "atoll:updateDomainList",
+        json!({ "file": include_str!("fixtures/domain_lists/sequence_2.json") }),
+    );
+    assert_eq!(updated["sequence"], json!("2"));
+    let err = harness.popup_err(
+        "atoll:updateDomainList",
+        json!({ "file": include_str!("fixtures/domain_lists/forged.json") }),
+    );
+    assert_eq!(err["code"], json!(-32602));
+
+    let response = harness
+        .runtime
+        .request("blocked", "standard:connect", drainer, Value::Null);
+    assert_eq!(response["err"]["code"], json!(4100));
+
+    let policy = harness.popup("atoll:setDomainPolicy", json!({ "blocklisted": "warn" }));
+    assert_eq!(policy["blocklisted"], json!("warn"));
+    assert_eq!(policy["lookalike"], json!("block"));
+
+    let response = harness
+        .runtime
+        .request("warned", "standard:connect", drainer, Value::Null);
+    assert!(response["err"].is_null(), "{response}");
+    assert_eq!(
+        harness.ok("atoll:checkDomain", json!({ "origin": drainer }))["action"],
+        json!("warn")
+    );
+}
+
+#[test]
+fn domain_reputation_survives_a_restart() {
+    let harness = Harness::new();
+    let drainer = "https://drainer.example";
+    let list = json!({ "file": include_str!("fixtures/domain_lists/sequence_2.json") });
+    harness.popup("atoll:updateDomainList", list.clone());
+    harness.popup(
+        "atoll:setDomainPolicy",
+        json!({ "blocklisted": "warn", "newlySeenDays": 0 }),
+    );
+
+    // The dapp connected before the restart is remembered
+    let restarted = harness.restart();
+    assert_eq!(
+        restarted.ok("atoll:checkDomain", json!({ "origin": ORIGIN }))["verdict"],
+        json!("known")
+    );
+    let report = restarted.ok("atoll:checkDomain", json!({ "origin": drainer }));
+    assert_eq!(report["verdict"], json!("blocklisted"));
+    assert_eq!(report["action"], json!("warn"));
+
+    // The list read back is the newest one
+    let err = restarted.popup_err("atoll:updateDomainList", list);
+    assert_eq!(err["code"], json!(-32602));
+}
+
+#[test]
+fn connected_sites_are_listed_and_revoked() {
+    let harness = Harness::new();
+    let other = "https://other.example";
+    let response = harness.runtime.request(
+        "other-connect",
+        "standard:connect",
+        other,
+        json!({ "icon": "https://other.example/icon.svg" }),
+    );
+    assert!(response["err"].is_null(), "{response}");
+
+    harness.ok(
+        "solana:signIn",
+        json!({ "domain": "dapp.example", "nonce": "8f3kd92ma0" }),
+    );
+    let sites = harness.popup("atoll:listConnectedSites", Value::Null);
+    assert_eq!(sites.as_array().unwrap().len(), 2);
+    let site = &sites[0];
+    assert_eq!(site["origin"], json!(ORIGIN));
+    assert_eq!(site["accounts"], json!([harness.pubkey().to_string()]));
+    assert_eq!(site["signIn"]["domain"], json!("dapp.example"));
+    assert_eq!(site["signIn"]["nonce"], json!("8f3kd92ma0"));
+    assert_eq!(sites[1]["icon"], json!("https://other.example/icon.svg"));
+    assert_eq!(
+        harness.popup(
+            "atoll:listConnectedSites",
+            json!({ "account": Pubkey::new_from_array([9u8; 32]).to_string() }),
+        ),
+        json!([])
+    );
+
+    assert_eq!(
+        harness.popup("atoll:revokeSite", json!({ "origin": ORIGIN })),
+        json!({ "revoked": true })
+    );
+    let err = harness.err(
+        "solana:signMessage",
+        json!({ "account": harness.account(), "message": b"hello".to_vec() }),
+    );
+    assert_eq!(err["code"], json!(4100));
+
+    assert_eq!(
+        harness.popup("atoll:revokeAllSites", Value::Null),
+        json!([other])
+    );
+    assert_eq!(
+        harness.popup("atoll:listConnectedSites", Value::Null),
+        json!([])
+    );
+}
+
+#[test]
+fn connected_sites_survive_a_restart() {
+    let harness = Harness::new();
+    let other = "https://other.example";
+    harness
+        .runtime
+        .request("other-connect", "standard:connect", other, Value::Null);
+    harness.ok(
+        "solana:signIn",
+        json!({ "domain": "dapp.example", "nonce": "8f3kd92ma0" }),
+    );
+    let sites = harness.popup("atoll:listConnectedSites", Value::Null);
+
+    let restarted = harness.restart();
+    assert_eq!(
+        restarted.popup("atoll:listConnectedSites", Value::Null),
+        sites
+    );
+    assert_eq!(
+        restarted.ok("standard:connect", json!({ "silent": true }))["accounts"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    restarted.popup("atoll:revokeSite", json!({ "origin": ORIGIN }));
+    assert_eq!(
+        harness
+            .restart()
+            .popup("atoll:listConnectedSites", Value::Null),
+        json!([sites[1]])
+    );
+
+    restarted.popup("atoll:revokeAllSites", Value::Null);
+    assert_eq!(
+        restarted
+            .restart()
+            .popup("atoll:listConnectedSites", Value::Null),
+        json!([])
+    );
+}
+
+#[test]
+fn silent_connect_only_reconnects_trusted_origins() {
+    let harness = Harness::new();
+
+    let output = harness.ok("standard:connect", json!({ "silent": true }));
+    assert_eq!(
+        bytes(&output["accounts"][0]["publicKey"]),
+        harness.public_key
+    );
+
+    // Unknown origins get no accounts and are not connected
+    let other = "https://other.example";
+    for params in [json!({ "silent": true }), json!({ "onlyIfTrusted": true })] {
+        let response = harness
+            .runtime
+            .request("silent", "standard:connect", other, params);
+        assert_eq!(response["ok"], json!({ "accounts": [] }), "{response}");
+    }
+    let sites = harness.popup("atoll:listConnectedSites", Value::Null);
+    assert_eq!(sites.as_array().unwrap().len(), 1);
+    assert_eq!(
+        harness
+            .popup("atoll:listDappPolicies", Value::Null)
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+
+    harness.popup("atoll:revokeSite", json!({ "origin": ORIGIN }));
+    assert_eq!(
+        harness.ok("standard:connect", json!({ "silent": true })),
+        json!({ "accounts": [] })
+    );
+    assert_eq!(
+        harness.popup("atoll:listConnectedSites", Value::Null),
+        json!([])
+    );
+
+    // Connecting again without `silent` is up to the user
+    let output = harness.ok("standard:connect", json!({ "silent": false }));
+    assert_eq!(output["accounts"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn pages_cannot_list_or_revoke_connected_sites() {
+    let harness = Harness::new();
+    let other = "https://other.example";
+    harness.envelope_from(other, "standard:connect", Value::Null);
+
+    // A connected page can neither enumerate the other sites nor disconnect them
+    for (method, params) in [
+        ("atoll:listConnectedSites", Value::Null),
+        ("atoll:revokeSite", json!({ "origin": other })),
+    ] {
+        let err = harness.err(method, params.clone());
+        assert_eq!(err["code"], json!(4100), "{method}");
+
+        let response = harness.envelope_from(other, method, params);
+        assert_eq!(response["err"]["code"], json!(4100), "{method}");
+    }
+
+    let sites = harness.popup("atoll:listConnectedSites", Value::Null);
+    assert_eq!(sites.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn watch_only_accounts_survive_a_restart() {
+    let harness = Harness::new();
+    let address = Pubkey::new_from_array([9u8; 32]).to_string();
+    harness.popup("atoll:addWatchOnlyAccount", json!(address));
+
+    let watch_only = |harness: &Harness| {
+        harness
+            .popup("atoll:listAccounts", Value::Null)
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|account| account["watchOnly"] == json!(true))
+            .map(|account| account["address"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>()
+    };
+
+    let restarted = harness.restart();
+    assert_eq!(watch_only(&restarted), vec![address.clone()]);
+    assert!(harness.storage.item("atoll:watchOnlyAccounts").is_some());
+
+    restarted.popup("atoll:removeWatchOnlyAccount", json!(address));
+    assert!(watch_only(&restarted.restart()).is_empty());
+}
+
+#[test]
+fn popup_only_methods_refuse_other_senders() {
+    let harness = Harness::new();
+
+    for (method, params) in [
+        (
+            "atoll:setDappPolicy",
+            json!({ "origin": ORIGIN, "features": [] }),
+        ),
+        ("atoll:setDomainPolicy", json!({ "blocklisted": "allow" })),
+        ("atoll:revokeAllSites", Value::Null),
+        (
+            "atoll:setActiveAccount",
+            json!(harness.pubkey().to_string()),
+        ),
+        (
+            "atoll:vaultSplitShares",
+            json!({ "groups": [{ "memberThreshold": 1, "memberCount": 1 }] }),
+        ),
+        (
+            "atoll:solanaSend",
+            json!({ "recipient": Pubkey::new_from_array([9u8; 32]).to_string(), "amount": "1" }),
+        ),
+        (
+            "atoll:bitcoinSend",
+            json!({ "recipient": "bcrt1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqq", "amount": 1 }),
+        ),
+        ("atoll:setSolanaPriorityFee", json!("high")),
+        (
+            "atoll:removeWatchOnlyAccount",
+            json!(harness.pubkey().to_string()),
+        ),
+        // Reading the accounts, permissions and activity is as private
+        ("atoll:listAccounts", Value::Null),
+        ("atoll:listConnectedSites", Value::Null),
+        ("atoll:revokeSite", json!({ "origin": ORIGIN })),
+        ("atoll:listDappPolicies", Value::Null),
+        ("atoll:solanaActivity", Value::Null),
+    ] {
+        let err = harness.err(method, params);
+        assert_eq!(err["code"], json!(4100), "{method}");
+        assert!(
+            err["message"]
+                .as_str()
+                .unwrap()
+                .contains("can only be called from the pages of the wallet")
+        );
+    }
+
+    // Claiming the origin of the extension does not help a page
+    let response = harness.runtime.send_message(
+        json!({
+            "version": ProtocolHeader::VERSION,
+            "id": "spoofed",
+            "method": "atoll:revokeAllSites",
+            "origin": EXTENSION_ORIGIN,
+        }),
+        ORIGIN,
+    );
+    assert_eq!(response["err"]["code"], json!(4100));
+
+    // The policy and sites are unchanged
+    assert_eq!(
+        harness.popup("atoll:listConnectedSites", Value::Null)[0]["origin"],
+        json!(ORIGIN)
+    );
+    harness.ok(
+        "solana:signMessage",
+        json!({ "account": harness.account(), "message": b"hello".to_vec() }),
+    );
+}
+
+#[test]
+fn envelope_errors_keep_the_request_id() {
+    let harness = Harness::new();
+
+    let response = harness.runtime.send_message(
+        json!({ "version": 2, "id": "old-client", "method": "standard:connect" }),
+        ORIGIN,
+    );
+    assert_eq!(response["id"], json!("old-client"));
+    assert_eq!(response["err"]["code"], json!(-32600));
+
+    let response = harness.runtime.send_message(
+        json!({
+            "version": ProtocolHeader::VERSION,
+            "id": "spoofed",
+            "method": "standard:connect",
+            "origin": "https://other.example",
+        }),
+        ORIGIN,
+    );
+    assert_eq!(response["err"]["code"], json!(-32600));
+
+    let err = harness.err("solana:unknownMethod", Value::Null);
+    assert_eq!(err["code"], json!(-32601));
+}
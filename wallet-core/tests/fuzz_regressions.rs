@@ -0,0 +1,120 @@
+//! Inputs found by the targets in `fuzz/`, kept so the fixes stay fixed
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use atoll_wallet_core::{SignInInputParser, SolanaSignInParams, SolanaWireTransaction};
+use solana_message::{Message, compiled_instruction::CompiledInstruction};
+use solana_pubkey::Pubkey;
+use solana_transaction::Transaction;
+
+const ADDRESS: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+fn format_sign_in(params: &SolanaSignInParams) -> Result<String, String> {
+    let mut parser = SignInInputParser::new(UNIX_EPOCH + Duration::from_secs(1_750_000_000));
+
+    parser
+        .parse(params)
+        .map(|parsed| parsed.format())
+        .map_err(|error| error.to_string())
+}
+
+/// `sign_in`: the `notBefore` of a request was written as the `Expiration Time`
+#[test]
+fn sign_in_keeps_not_before_apart_from_the_expiration_time() {
+    let params = SolanaSignInParams {
+        domain: Some("dapp.example".to_string()),
+        address: Some(ADDRESS.to_string()),
+        not_before: Some("4050-06-15T12:00:30Z".to_string()),
+        request_id: Some("req-42".to_string()),
+        ..Default::default()
+    };
+
+    let message = format_sign_in(&params).unwrap();
+
+    assert!(message.contains("Not Before: 4050-06-15T12:00:30.000Z\n"));
+    assert!(!message.contains("Expiration Time"));
+
+    let read = SolanaSignInParams::from_message(&message).unwrap();
+    assert_eq!(read.not_before.as_deref(), Some("4050-06-15T12:00:30.000Z"));
+    assert_eq!(read.expiration_time, None);
+}
+
+/// `sign_in`: a line break in a field forged the lines that follow it
+#[test]
+fn sign_in_rejects_line_breaks_in_fields() {
+    let params = serde_json::from_str::<SolanaSignInParams>("[\"\\\"\\nzanzaf\"]").unwrap();
+    assert!(format_sign_in(&params).unwrap_err().contains("line break"));
+
+    let forged = SolanaSignInParams {
+        domain: Some("dapp.example".to_string()),
+        address: Some(ADDRESS.to_string()),
+        statement: Some("Sign in\n\nURI: https://wallet.example\r".to_string()),
+        ..Default::default()
+    };
+    assert!(format_sign_in(&forged).unwrap_err().contains("`statement`"));
+
+    let resource = SolanaSignInParams {
+        resources: vec!["https://dapp.example\n- https://wallet.example".to_string()],
+        ..Default::default()
+    };
+    assert!(
+        format_sign_in(&resource)
+            .unwrap_err()
+            .contains("`resources`")
+    );
+}
+
+/// `solana_transaction`: a transaction whose instructions reference accounts it does not
+/// have was decoded and passed on to be signed
+#[test]
+fn transaction_decoding_rejects_missing_accounts() {
+    let payer = Pubkey::new_from_array([1u8; 32]);
+    let mut message = Message::new_with_blockhash(
+        &[solana_system_interface::instruction::transfer(
+            &payer,
+            &Pubkey::new_from_array([2u8; 32]),
+            1,
+        )],
+        Some(&payer),
+        &solana_hash::Hash::default(),
+    );
+    message.instructions.push(CompiledInstruction {
+        program_id_index: 2,
+        accounts: vec![64],
+        data: Vec::default(),
+    });
+
+    let bytes = bincode::serialize(&Transaction::new_unsigned(message)).unwrap();
+
+    assert!(
+        SolanaWireTransaction::decode(&bytes)
+            .unwrap_err()
+            .to_string()
+            .contains("malformed")
+    );
+}
+
+/// `solana_transaction`: transactions larger than a packet were decoded since the limit of
+/// the bincode options is not enforced for every type
+#[test]
+fn transaction_decoding_is_limited_to_a_packet() {
+    let payer = Pubkey::new_from_array([1u8; 32]);
+    let instructions = (0..40)
+        .map(|index| {
+            solana_system_interface::instruction::transfer(
+                &payer,
+                &Pubkey::new_from_array([index; 32]),
+                1,
+            )
+        })
+        .collect::<Vec<_>>();
+    let transaction = Transaction::new_unsigned(Message::new_with_blockhash(
+        &instructions,
+        Some(&payer),
+        &solana_hash::Hash::default(),
+    ));
+    let bytes = bincode::serialize(&transaction).unwrap();
+
+    assert!(bytes.len() as u64 > SolanaWireTransaction::MAX_SIZE);
+    assert!(SolanaWireTransaction::decode(&bytes).is_err());
+}
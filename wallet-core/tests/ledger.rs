@@ -0,0 +1,313 @@
+//! The Ledger Solana app codec replayed against exchanges recorded from a device
+
+mod common;
+
+use atoll_wallet_core::{
+    AtollWalletError, LedgerAppConfig, LedgerSigner, LedgerSolanaApp, RecordedApduTransport,
+    SolanaOffchainMessage, WalletSigner,
+};
+use solana_derivation_path::DerivationPath;
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use common::block_on;
+
+/// `m/44'/501'/0'/0'` as the app encodes it: the number of indexes, then each hardened index
+const DERIVATION_PATH: &str = "04 8000002C 800001F5 80000000 80000000";
+
+const OK: &str = "9000";
+
+fn hex(text: &str) -> Vec<u8> {
+    let digits = text.split_whitespace().collect::<String>();
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).unwrap())
+        .collect()
+}
+
+fn derivation_path() -> DerivationPath {
+    DerivationPath::new_bip44(Some(0), Some(0))
+}
+
+/// The account on the device
+fn public_key() -> Pubkey {
+    Signer::pubkey(&keypair())
+}
+
+/// Signs `bytes` as the device does with the key of [public_key]
+fn sign(bytes: &[u8]) -> Signature {
+    Signer::sign_message(&keypair(), bytes)
+}
+
+fn keypair() -> Keypair {
+    Keypair::new_from_array([3u8; 32])
+}
+
+/// The response of a command that returns `data`
+fn response(data: &[u8]) -> Vec<u8> {
+    let mut response = data.to_vec();
+    response.extend_from_slice(&hex(OK));
+
+    response
+}
+
+/// The command APDU `E0 <ins> <p1> <p2> <length> <data>`
+fn command(ins: u8, p1: u8, p2: u8, data: &[u8]) -> Vec<u8> {
+    let mut command = vec![0xE0, ins, p1, p2, data.len() as u8];
+    command.extend_from_slice(data);
+
+    command
+}
+
+/// The data of a sign command: one signer, the derivation path and `message`
+fn sign_data(message: &[u8]) -> Vec<u8> {
+    let mut data = vec![0x01];
+    data.extend_from_slice(&hex(DERIVATION_PATH));
+    data.extend_from_slice(message);
+
+    data
+}
+
+fn ledger_error(error: AtollWalletError) -> String {
+    match error {
+        AtollWalletError::Ledger(message) => message,
+        error => panic!("expected a Ledger error, got {error:?}"),
+    }
+}
+
+#[test]
+fn get_app_config_reads_the_settings_and_version() {
+    let transport =
+        RecordedApduTransport::new().record(&hex("E0 04 00 00 00"), &hex("01 00 01 04 02 9000"));
+
+    let config = block_on(LedgerSolanaApp::new(&transport).get_app_config()).unwrap();
+    assert_eq!(
+        config,
+        LedgerAppConfig {
+            blind_signing_enabled: true,
+            pubkey_display_mode: 0,
+            version: (1, 4, 2),
+        }
+    );
+    assert_eq!(config.version(), "1.4.2");
+    assert_eq!(transport.remaining(), 0);
+
+    let transport = RecordedApduTransport::new().record(&hex("E0 04 00 00 00"), &hex("00 01 9000"));
+    let error = block_on(LedgerSolanaApp::new(&transport).get_app_config()).unwrap_err();
+    assert!(ledger_error(error).contains("shorter than 5 bytes"));
+}
+
+#[test]
+fn get_pubkey_sends_the_derivation_path() {
+    let public_key = public_key();
+
+    let transport = RecordedApduTransport::new()
+        .record(
+            &hex(&format!("E0 05 00 00 11 {DERIVATION_PATH}")),
+            &response(public_key.as_ref()),
+        )
+        .record(
+            &hex(&format!("E0 05 01 00 11 {DERIVATION_PATH}")),
+            &response(public_key.as_ref()),
+        );
+    let app = LedgerSolanaApp::new(&transport);
+
+    assert_eq!(
+        block_on(app.get_pubkey(&derivation_path(), false)).unwrap(),
+        public_key
+    );
+    assert_eq!(
+        block_on(app.get_pubkey(&derivation_path(), true)).unwrap(),
+        public_key
+    );
+    assert_eq!(transport.remaining(), 0);
+
+    let transport = RecordedApduTransport::new().record(
+        &hex(&format!("E0 05 00 00 11 {DERIVATION_PATH}")),
+        &response(&[1u8; 31]),
+    );
+    let error = block_on(LedgerSolanaApp::new(&transport).get_pubkey(&derivation_path(), false))
+        .unwrap_err();
+    assert!(ledger_error(error).contains("not 32 bytes"));
+}
+
+#[test]
+fn sign_message_is_chunked_with_the_more_and_extend_flags() {
+    let message = (0..300).map(|index| index as u8).collect::<Vec<u8>>();
+    let data = sign_data(&message);
+    assert_eq!(data.len(), 318);
+
+    let signature = sign(&message);
+    let transport = RecordedApduTransport::new()
+        .record(&command(0x06, 0x01, 0x02, &data[..255]), &hex(OK))
+        .record(
+            &command(0x06, 0x01, 0x01, &data[255..]),
+            &response(signature.as_ref()),
+        );
+
+    assert_eq!(
+        block_on(LedgerSolanaApp::new(&transport).sign_message(&derivation_path(), &message))
+            .unwrap(),
+        signature
+    );
+    assert_eq!(transport.remaining(), 0);
+
+    // A payload that fills three chunks sets both flags on the middle one
+    let commands = LedgerSolanaApp::<RecordedApduTransport>::commands(0x06, 0x01, &[0u8; 600]);
+    assert_eq!(
+        commands
+            .iter()
+            .map(|command| (command.p2, command.data.len()))
+            .collect::<Vec<_>>(),
+        vec![(0x02, 255), (0x03, 255), (0x01, 90)]
+    );
+}
+
+#[test]
+fn sign_offchain_message_sends_the_encoded_message() {
+    let message =
+        SolanaOffchainMessage::new([0u8; 32], vec![public_key()], b"Hello Ledger".to_vec())
+            .unwrap();
+    let encoded = message.encode();
+
+    let signature = sign(&encoded);
+    let transport = RecordedApduTransport::new().record(
+        &command(0x07, 0x01, 0x00, &sign_data(&encoded)),
+        &response(signature.as_ref()),
+    );
+
+    assert_eq!(
+        block_on(
+            LedgerSolanaApp::new(&transport).sign_offchain_message(&derivation_path(), &message)
+        )
+        .unwrap(),
+        signature
+    );
+    assert_eq!(transport.remaining(), 0);
+}
+
+#[test]
+fn status_words_become_errors() {
+    let get_app_config = hex("E0 04 00 00 00");
+
+    for (status, expected) in [
+        ("6982", "The device is locked. Unlock it and try again"),
+        ("5515", "The device is locked. Unlock it and try again"),
+        (
+            "6808",
+            "The device cannot display this transaction. Enable blind signing in the Solana app settings",
+        ),
+        ("6E00", "Open the Solana app on the device and try again"),
+        ("6D00", "Open the Solana app on the device and try again"),
+        ("6A80", "The device rejected the data of the request"),
+        ("6F42", "The device returned the status word `6F42`"),
+    ] {
+        let transport = RecordedApduTransport::new().record(&get_app_config, &hex(status));
+        let error = block_on(LedgerSolanaApp::new(&transport).get_app_config()).unwrap_err();
+        assert_eq!(ledger_error(error), expected, "{status}");
+    }
+
+    let transport = RecordedApduTransport::new().record(&get_app_config, &hex("6985"));
+    assert!(matches!(
+        block_on(LedgerSolanaApp::new(&transport).get_app_config()),
+        Err(AtollWalletError::UserRejected(_))
+    ));
+
+    let transport = RecordedApduTransport::new().record(&get_app_config, &hex("90"));
+    let error = block_on(LedgerSolanaApp::new(&transport).get_app_config()).unwrap_err();
+    assert!(ledger_error(error).contains("shorter than its 2 byte status word"));
+}
+
+#[test]
+fn a_rejected_chunk_stops_the_exchange() {
+    let message = vec![7u8; 300];
+    let data = sign_data(&message);
+
+    let transport = RecordedApduTransport::new()
+        .record(&command(0x06, 0x01, 0x02, &data[..255]), &hex("6985"))
+        .record(&command(0x06, 0x01, 0x01, &data[255..]), &hex(OK));
+
+    assert!(matches!(
+        block_on(LedgerSolanaApp::new(&transport).sign_message(&derivation_path(), &message)),
+        Err(AtollWalletError::UserRejected(_))
+    ));
+    assert_eq!(transport.remaining(), 1);
+}
+
+#[test]
+fn the_signer_connects_and_signs_transactions() {
+    let transaction = Transaction::new_unsigned(Message::new_with_blockhash(
+        &[solana_system_interface::instruction::transfer(
+            &public_key(),
+            &Pubkey::new_from_array([9u8; 32]),
+            1_000,
+        )],
+        Some(&public_key()),
+        &solana_hash::Hash::new_from_array([5u8; 32]),
+    ));
+    let message_data = transaction.message_data();
+
+    let transport = RecordedApduTransport::new()
+        .record(
+            &hex(&format!("E0 05 00 00 11 {DERIVATION_PATH}")),
+            &response(public_key().as_ref()),
+        )
+        .record(
+            &command(0x06, 0x01, 0x00, &sign_data(&message_data)),
+            &response(sign(&message_data).as_ref()),
+        );
+
+    let signer = block_on(LedgerSigner::connect(transport, derivation_path())).unwrap();
+    assert_eq!(signer.pubkey(), public_key());
+
+    let signed = block_on(signer.sign_transaction(transaction)).unwrap();
+    assert!(signed.verify().is_ok());
+}
+
+#[test]
+fn the_signer_only_signs_offchain_messages_listing_it() {
+    let signer = LedgerSigner::new(
+        RecordedApduTransport::new(),
+        derivation_path(),
+        public_key(),
+    );
+
+    // Raw bytes are refused before anything is sent to the device
+    let error = block_on(signer.sign_message(b"hello")).unwrap_err();
+    assert!(ledger_error(error).contains("only signs off-chain messages"));
+
+    let other = SolanaOffchainMessage::new(
+        [0u8; 32],
+        vec![Pubkey::new_from_array([8u8; 32])],
+        b"hello".to_vec(),
+    )
+    .unwrap();
+    let error = block_on(signer.sign_message(&other.encode())).unwrap_err();
+    assert!(ledger_error(error).contains("is not a signer"));
+
+    let extended =
+        SolanaOffchainMessage::new([0u8; 32], vec![public_key()], vec![b'a'; 2_000]).unwrap();
+    let error = block_on(signer.sign_message(&extended.encode())).unwrap_err();
+    assert!(ledger_error(error).contains("longer than 1232 bytes"));
+
+    let message =
+        SolanaOffchainMessage::new([0u8; 32], vec![public_key()], b"hello".to_vec()).unwrap();
+    let encoded = message.encode();
+    let signature = sign(&encoded);
+    let signer = LedgerSigner::new(
+        RecordedApduTransport::new().record(
+            &command(0x07, 0x01, 0x00, &sign_data(&encoded)),
+            &response(signature.as_ref()),
+        ),
+        derivation_path(),
+        public_key(),
+    );
+
+    let signed: Signature = block_on(signer.sign_message(&encoded)).unwrap();
+    assert!(signed.verify(public_key().as_ref(), &encoded));
+}
@@ -0,0 +1,131 @@
+//! Legacy `signmessage` signatures with the BIP137 headers, checked against the vector of
+//! the `rpc_signmessage` functional test of Bitcoin Core
+
+use std::str::FromStr;
+
+use atoll_wallet_core::BitcoinMessage;
+use base64ct::{Base64, Encoding};
+use bitcoin::{
+    Address, CompressedPublicKey, Network, PrivateKey, PublicKey, key::Secp256k1,
+    secp256k1::Keypair,
+};
+
+/// The private key of the Bitcoin Core vector
+const WIF: &str = "cUeKHd5orzT3mz8P9pxyREHfsWtVfgsfDjiZZBcjUBAaGk1BTj7N";
+
+const P2PKH: &str = "mpLQjfK79b7CCV4VMJWEWAj5Mpx8Up5zxB";
+const MESSAGE: &[u8] = b"This is just a test message";
+const SIGNATURE: &str =
+    "INbVnW4e6PeRmsv2Qgu8NuopvrVjkcxob+sX8OcZG0SALhWybUjzMLPdAsXI46YZGb0KQTRii+wWIQzRpG/U+S0=";
+
+fn keypair() -> Keypair {
+    let private_key = PrivateKey::from_wif(WIF).unwrap();
+
+    Keypair::from_secret_key(&Secp256k1::new(), &private_key.inner)
+}
+
+fn address(address: &str) -> Address {
+    Address::from_str(address)
+        .unwrap()
+        .require_network(Network::Testnet)
+        .unwrap()
+}
+
+fn signature() -> Vec<u8> {
+    Base64::decode_vec(SIGNATURE).unwrap()
+}
+
+fn p2wpkh() -> Address {
+    Address::p2wpkh(
+        &CompressedPublicKey(keypair().public_key()),
+        Network::Testnet,
+    )
+}
+
+#[test]
+fn the_vector_verifies_and_signs_deterministically() {
+    let signature = signature();
+    // The header of a compressed P2PKH key
+    assert_eq!(signature[0], 32);
+
+    assert!(BitcoinMessage::verify(P2PKH, MESSAGE, &signature).unwrap());
+
+    let signed = BitcoinMessage::sign_legacy(&keypair(), &address(P2PKH), MESSAGE).unwrap();
+    assert_eq!(Base64::encode_string(&signed), SIGNATURE);
+}
+
+#[test]
+fn segwit_signatures_use_their_header_and_round_trip() {
+    let address = p2wpkh();
+    let signed = BitcoinMessage::sign_legacy(&keypair(), &address, MESSAGE).unwrap();
+
+    // Only the header differs from the P2PKH signature of the same key
+    let signature = signature();
+    assert_eq!(signed[0], signature[0] + 8);
+    assert_eq!(signed[1..], signature[1..]);
+
+    assert!(BitcoinMessage::verify(&address.to_string(), MESSAGE, &signed).unwrap());
+    // Many wallets sign SegWit addresses with the P2PKH header
+    assert!(BitcoinMessage::verify(&address.to_string(), MESSAGE, &signature).unwrap());
+}
+
+#[test]
+fn the_uncompressed_header_verifies_for_the_uncompressed_address() {
+    let mut signature = signature();
+    signature[0] -= 4;
+
+    let uncompressed = Address::p2pkh(
+        PublicKey::new_uncompressed(keypair().public_key()),
+        Network::Testnet,
+    );
+    assert!(BitcoinMessage::verify(&uncompressed.to_string(), MESSAGE, &signature).unwrap());
+    assert!(!BitcoinMessage::verify(P2PKH, MESSAGE, &signature).unwrap());
+}
+
+#[test]
+fn tampered_signatures_do_not_verify() {
+    let signature = signature();
+    assert!(!BitcoinMessage::verify(P2PKH, b"This is just a test message!", &signature).unwrap());
+
+    for index in [1, 32, 64] {
+        let mut tampered = signature.clone();
+        tampered[index] ^= 1;
+        assert!(
+            !BitcoinMessage::verify(P2PKH, MESSAGE, &tampered).unwrap(),
+            "{index}"
+        );
+    }
+
+    // Another recovery id recovers another key
+    let mut tampered = signature.clone();
+    tampered[0] += 1;
+    assert!(!BitcoinMessage::verify(P2PKH, MESSAGE, &tampered).unwrap());
+
+    for tampered in [&signature[..64], &[&signature[..], &[0]].concat()[..], &[]] {
+        assert!(!BitcoinMessage::verify(P2PKH, MESSAGE, tampered).unwrap());
+    }
+
+    let mut header = signature.clone();
+    header[0] = 43;
+    assert!(!BitcoinMessage::verify(P2PKH, MESSAGE, &header).unwrap());
+}
+
+#[test]
+fn signatures_for_other_addresses_are_refused() {
+    let other = Keypair::from_secret_key(
+        &Secp256k1::new(),
+        &bitcoin::secp256k1::SecretKey::from_slice(&[1u8; 32]).unwrap(),
+    );
+    let other_address = Address::p2pkh(CompressedPublicKey(other.public_key()), Network::Testnet);
+
+    assert!(!BitcoinMessage::verify(&other_address.to_string(), MESSAGE, &signature()).unwrap());
+    assert!(BitcoinMessage::sign_legacy(&keypair(), &other_address, MESSAGE).is_err());
+
+    let taproot = Address::p2tr(
+        &Secp256k1::new(),
+        keypair().x_only_public_key().0,
+        None,
+        Network::Testnet,
+    );
+    assert!(BitcoinMessage::sign_legacy(&keypair(), &taproot, MESSAGE).is_err());
+}
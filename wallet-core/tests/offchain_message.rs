@@ -0,0 +1,158 @@
+//! Inspection of the bytes of `solana:signMessage` and the off-chain message format
+
+use atoll_wallet_core::{
+    SolanaMessageMode, SolanaMessagePreview, SolanaOffchainMessage, SolanaOffchainMessageFormat,
+    SolanaSignableMessage,
+};
+use solana_message::{Message, VersionedMessage, v0};
+use solana_pubkey::Pubkey;
+
+const SIGNER: Pubkey = Pubkey::new_from_array([1u8; 32]);
+
+fn transfer_message() -> Message {
+    Message::new_with_blockhash(
+        &[solana_system_interface::instruction::transfer(
+            &SIGNER,
+            &Pubkey::new_from_array([2u8; 32]),
+            1,
+        )],
+        Some(&SIGNER),
+        &solana_hash::Hash::default(),
+    )
+}
+
+#[test]
+fn offchain_messages_round_trip_in_the_narrowest_format() {
+    let cases = [
+        (
+            b"Sign in to the game".to_vec(),
+            SolanaOffchainMessageFormat::RestrictedAscii,
+        ),
+        (
+            "Échange accepté".as_bytes().to_vec(),
+            SolanaOffchainMessageFormat::LimitedUtf8,
+        ),
+        (vec![b'a'; 2_000], SolanaOffchainMessageFormat::ExtendedUtf8),
+    ];
+
+    for (body, format) in cases {
+        let message = SolanaOffchainMessage::new([9u8; 32], vec![SIGNER], body.clone()).unwrap();
+        assert_eq!(message.format(), format);
+
+        let encoded = message.encode();
+        assert!(encoded.starts_with(SolanaOffchainMessage::SIGNING_DOMAIN));
+
+        let decoded = SolanaOffchainMessage::decode(&encoded).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.message(), body);
+        assert_eq!(decoded.application_domain(), &[9u8; 32]);
+    }
+}
+
+#[test]
+fn offchain_messages_are_decoded_strictly() {
+    let encoded = SolanaOffchainMessage::new([9u8; 32], vec![SIGNER], b"hello".to_vec())
+        .unwrap()
+        .encode();
+    let format_index = SolanaOffchainMessage::SIGNING_DOMAIN.len() + 1 + 32;
+
+    let mut version = encoded.clone();
+    version[SolanaOffchainMessage::SIGNING_DOMAIN.len()] = 1;
+
+    let mut trailing = encoded.clone();
+    trailing.push(0);
+
+    let mut no_signers = encoded[..format_index + 1].to_vec();
+    no_signers.push(0);
+    no_signers.extend_from_slice(&5u16.to_le_bytes());
+    no_signers.extend_from_slice(b"hello");
+
+    // Bytes that are not ASCII in a restricted ASCII message
+    let mut format = encoded.clone();
+    *format.last_mut().unwrap() = 0xc3;
+
+    for invalid in [
+        version,
+        trailing,
+        no_signers,
+        format,
+        encoded[..encoded.len() - 1].to_vec(),
+    ] {
+        assert!(SolanaOffchainMessage::decode(&invalid).is_err());
+        assert!(SolanaSignableMessage::inspect(invalid, &SIGNER).is_err());
+    }
+
+    assert!(SolanaOffchainMessage::new([0u8; 32], vec![SIGNER, SIGNER], b"hi".to_vec()).is_err());
+    assert!(SolanaOffchainMessage::new([0u8; 32], vec![SIGNER], Vec::default()).is_err());
+    assert!(SolanaOffchainMessage::new([0u8; 32], vec![SIGNER], vec![0xff]).is_err());
+}
+
+#[test]
+fn inspection_refuses_transactions_and_their_messages() {
+    let legacy = transfer_message();
+    let versioned = VersionedMessage::V0(
+        v0::Message::try_compile(
+            &SIGNER,
+            &[solana_system_interface::instruction::transfer(
+                &SIGNER,
+                &Pubkey::new_from_array([2u8; 32]),
+                1,
+            )],
+            &[],
+            solana_hash::Hash::default(),
+        )
+        .unwrap(),
+    );
+
+    for bytes in [
+        legacy.serialize(),
+        versioned.serialize(),
+        bincode::serialize(&solana_transaction::Transaction::new_unsigned(legacy)).unwrap(),
+    ] {
+        assert!(SolanaSignableMessage::is_transaction(&bytes));
+        assert!(
+            SolanaSignableMessage::inspect(bytes, &SIGNER)
+                .unwrap_err()
+                .to_string()
+                .contains("transaction")
+        );
+    }
+}
+
+#[test]
+fn inspection_signs_other_bytes_as_they_are() {
+    let message = SolanaSignableMessage::inspect(b"Hello".to_vec(), &SIGNER).unwrap();
+    assert_eq!(message.mode(), &SolanaMessageMode::Raw);
+    assert_eq!(message.bytes(), b"Hello");
+
+    let offchain = SolanaOffchainMessage::new([0u8; 32], vec![SIGNER], b"Hello".to_vec()).unwrap();
+    let message = SolanaSignableMessage::inspect(offchain.encode(), &SIGNER).unwrap();
+    assert_eq!(
+        message.mode(),
+        &SolanaMessageMode::Offchain(offchain.clone())
+    );
+    assert_eq!(message.bytes(), offchain.encode());
+    assert_eq!(
+        message.preview(),
+        SolanaMessagePreview::Text("Hello".to_string())
+    );
+}
+
+#[test]
+fn previews_show_readable_text_and_hex_otherwise() {
+    assert_eq!(
+        SolanaMessagePreview::new("Sign in\nto the game".as_bytes()),
+        SolanaMessagePreview::Text("Sign in\nto the game".to_string())
+    );
+
+    let hex = SolanaMessagePreview::new(&[0xde, 0xad, 0x00]);
+    assert_eq!(hex.kind(), "hex");
+    assert_eq!(hex.value(), "dead00");
+
+    // A direction override could reorder what the user reads
+    assert_eq!(
+        SolanaMessagePreview::new("pay \u{202E}01".as_bytes()).kind(),
+        "hex"
+    );
+    assert_eq!(SolanaMessagePreview::new(b"bell\x07").kind(), "hex");
+}
@@ -0,0 +1,339 @@
+//! Creating, restoring, locking and unlocking the vault from the popup, with the sealed
+//! vault kept in the storage across restarts of the background.
+
+mod common;
+
+use atoll_wallet_core::{
+    App, AtollStorageKeys, ProtocolHeader, Slip39Config, WalletStorage, WalletVault,
+};
+use serde_json::{Value, json};
+
+use common::{FakeRuntime, MemoryStorage, MockRpcServer, block_on};
+
+const ORIGIN: &str = "https://dapp.example";
+const EXTENSION_ORIGIN: &str = "chrome-extension://atoll";
+const PASSWORD: &str = "correct horse battery";
+
+/// A mnemonic other than the public test one
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// A background without a vault until the popup onboards the user
+struct Background {
+    runtime: FakeRuntime,
+    storage: MemoryStorage,
+    _server: MockRpcServer,
+}
+
+impl Background {
+    fn new() -> Self {
+        Self::start(MemoryStorage::default())
+    }
+
+    /// Starts a new background on the storage of this one, as when the browser restarts it
+    fn restart(&self) -> Self {
+        Self::start(self.storage.clone())
+    }
+
+    fn start(storage: MemoryStorage) -> Self {
+        let server = MockRpcServer::start();
+        let app = App::new(server.transport())
+            .set_extension_origin(EXTENSION_ORIGIN)
+            .set_storage(storage.clone());
+        block_on(app.load_storage()).unwrap();
+
+        let runtime = FakeRuntime::new();
+        common::listen(app, &runtime);
+
+        Self {
+            runtime,
+            storage,
+            _server: server,
+        }
+    }
+
+    fn envelope(&self, origin: &str, method: &str, params: Value) -> Value {
+        let response = self.runtime.request("request", method, origin, params);
+        assert_eq!(response["version"], json!(ProtocolHeader::VERSION));
+
+        response
+    }
+
+    fn popup(&self, method: &str, params: Value) -> Value {
+        let response = self.envelope(EXTENSION_ORIGIN, method, params);
+        assert!(response["err"].is_null(), "{method} failed: {response}");
+
+        response["ok"].clone()
+    }
+
+    fn popup_err(&self, method: &str, params: Value) -> Value {
+        let response = self.envelope(EXTENSION_ORIGIN, method, params);
+        assert!(response["ok"].is_null(), "{method} should fail: {response}");
+
+        response["err"].clone()
+    }
+
+    fn status(&self) -> Value {
+        self.popup("atoll:vaultStatus", Value::Null)
+    }
+}
+
+#[test]
+fn the_background_starts_without_a_vault() {
+    let background = Background::new();
+
+    assert_eq!(
+        background.status(),
+        json!({ "created": false, "unlocked": false })
+    );
+
+    let err = background
+        .envelope(ORIGIN, "standard:connect", Value::Null)
+        .get("err")
+        .cloned()
+        .unwrap();
+    assert_eq!(err["code"], json!(4100));
+
+    let err = background.popup_err("atoll:unlockVault", json!({ "password": PASSWORD }));
+    assert_eq!(err["code"], json!(-32602));
+}
+
+#[test]
+fn a_created_vault_is_sealed_and_unlocked_after_a_restart() {
+    let background = Background::new();
+
+    let created = background.popup("atoll:createVault", json!({ "password": PASSWORD }));
+    assert_eq!(
+        created["mnemonic"]
+            .as_str()
+            .unwrap()
+            .split_whitespace()
+            .count(),
+        12
+    );
+    assert_eq!(
+        background.status(),
+        json!({ "created": true, "unlocked": true })
+    );
+
+    let sealed = background.storage.item(AtollStorageKeys::VAULT).unwrap();
+    assert!(!sealed.contains(created["mnemonic"].as_str().unwrap()));
+
+    background.envelope(ORIGIN, "standard:connect", Value::Null);
+
+    let restarted = background.restart();
+    assert_eq!(
+        restarted.status(),
+        json!({ "created": true, "unlocked": false })
+    );
+
+    let err = restarted.popup_err("atoll:unlockVault", json!({ "password": "wrong password" }));
+    assert_eq!(err["code"], json!(4100));
+
+    let unlocked = restarted.popup("atoll:unlockVault", json!({ "password": PASSWORD }));
+    assert_eq!(unlocked["account"], created["account"]);
+
+    // The connected sites are read back with the vault
+    let sites = restarted.popup("atoll:listConnectedSites", Value::Null);
+    assert_eq!(sites[0]["origin"], json!(ORIGIN));
+    let connected = restarted.envelope(ORIGIN, "standard:connect", json!({ "silent": true }));
+    assert_eq!(
+        connected["ok"]["accounts"][0]["publicKey"],
+        created["account"]["publicKey"]
+    );
+}
+
+#[test]
+fn a_tampered_vault_is_refused() {
+    let background = Background::new();
+    background.popup(
+        "atoll:restoreVault",
+        json!({ "mnemonic": MNEMONIC, "password": PASSWORD }),
+    );
+
+    let sealed = background.storage.item(AtollStorageKeys::VAULT).unwrap();
+    let stored = serde_json::from_str::<Value>(&sealed).unwrap();
+    assert_eq!(
+        stored["iterations"].as_f64(),
+        Some(WalletVault::SEAL_ITERATIONS as f64)
+    );
+    assert!(!sealed.contains("abandon"));
+
+    let unlock_error = |stored: &Value| {
+        block_on(
+            background
+                .storage
+                .set(AtollStorageKeys::VAULT, stored.to_string()),
+        )
+        .unwrap();
+
+        background
+            .restart()
+            .popup_err("atoll:unlockVault", json!({ "password": PASSWORD }))
+    };
+
+    // Lowering the work factor of the key derivation is refused before deriving the key
+    let mut weakened = stored.clone();
+    weakened["iterations"] = json!(1);
+    let err = unlock_error(&weakened);
+    assert!(
+        err["message"]
+            .as_str()
+            .unwrap()
+            .contains("key derivation iterations"),
+        "{err}"
+    );
+
+    // Raising it changes the key, and so does any change to the ciphertext
+    let mut strengthened = stored.clone();
+    strengthened["iterations"] = json!(WalletVault::SEAL_ITERATIONS + 1);
+    assert_eq!(unlock_error(&strengthened)["code"], json!(4100));
+
+    let mut ciphertext = stored["ciphertext"].as_str().unwrap().to_string();
+    let first = if ciphertext.starts_with('A') {
+        "B"
+    } else {
+        "A"
+    };
+    ciphertext.replace_range(..1, first);
+    let mut modified = stored.clone();
+    modified["ciphertext"] = json!(ciphertext);
+    assert_eq!(unlock_error(&modified)["code"], json!(4100));
+
+    // The untouched vault still opens
+    block_on(background.storage.set(AtollStorageKeys::VAULT, sealed)).unwrap();
+    background
+        .restart()
+        .popup("atoll:unlockVault", json!({ "password": PASSWORD }));
+}
+
+#[test]
+fn a_locked_vault_refuses_dapps() {
+    let background = Background::new();
+    background.popup(
+        "atoll:restoreVault",
+        json!({ "mnemonic": MNEMONIC, "password": PASSWORD }),
+    );
+    background.envelope(ORIGIN, "standard:connect", Value::Null);
+
+    background.popup("atoll:lockVault", Value::Null);
+    assert_eq!(
+        background.status(),
+        json!({ "created": true, "unlocked": false })
+    );
+
+    let response = background.envelope(ORIGIN, "standard:connect", json!({ "silent": true }));
+    assert_eq!(response["err"]["code"], json!(4100));
+
+    background.popup("atoll:unlockVault", json!({ "password": PASSWORD }));
+    let response = background.envelope(ORIGIN, "standard:connect", json!({ "silent": true }));
+    assert!(response["err"].is_null(), "{response}");
+}
+
+#[test]
+fn watch_only_accounts_are_kept_while_locked() {
+    let background = Background::new();
+    background.popup(
+        "atoll:restoreVault",
+        json!({ "mnemonic": MNEMONIC, "password": PASSWORD }),
+    );
+    let address = "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin";
+    background.popup("atoll:addWatchOnlyAccount", json!(address));
+
+    let watch_only = |background: &Background| {
+        background
+            .popup("atoll:listAccounts", Value::Null)
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|account| account["watchOnly"] == json!(true))
+            .count()
+    };
+
+    // Unlocking reads the storage again without listing the account twice
+    background.popup("atoll:lockVault", Value::Null);
+    assert_eq!(watch_only(&background), 1);
+    background.popup("atoll:unlockVault", json!({ "password": PASSWORD }));
+    assert_eq!(watch_only(&background), 1);
+
+    let restarted = background.restart();
+    assert_eq!(watch_only(&restarted), 1);
+    restarted.popup("atoll:unlockVault", json!({ "password": PASSWORD }));
+    assert_eq!(watch_only(&restarted), 1);
+}
+
+#[test]
+fn an_existing_vault_is_only_replaced_on_request() {
+    let background = Background::new();
+    let restored = background.popup(
+        "atoll:restoreVault",
+        json!({ "mnemonic": MNEMONIC, "password": PASSWORD }),
+    );
+    background.envelope(ORIGIN, "standard:connect", Value::Null);
+
+    let err = background.popup_err("atoll:createVault", json!({ "password": PASSWORD }));
+    assert_eq!(err["code"], json!(-32602));
+
+    // A locked vault is not replaced either
+    let restarted = background.restart();
+    let err = restarted.popup_err("atoll:createVault", json!({ "password": PASSWORD }));
+    assert_eq!(err["code"], json!(-32602));
+
+    let created = restarted.popup(
+        "atoll:createVault",
+        json!({ "password": PASSWORD, "replace": true }),
+    );
+    assert_ne!(created["account"], restored["account"]);
+    assert_eq!(
+        restarted.popup("atoll:listConnectedSites", Value::Null),
+        json!([])
+    );
+}
+
+#[test]
+fn short_passwords_and_the_test_mnemonic_are_refused() {
+    let background = Background::new();
+
+    let err = background.popup_err("atoll:createVault", json!({ "password": "short" }));
+    assert_eq!(err["code"], json!(-32602));
+
+    let test = WalletVault::new_test().unwrap();
+    let err = background.popup_err(
+        "atoll:restoreVault",
+        json!({ "mnemonic": test.mnemonic().unwrap().as_str(), "password": PASSWORD }),
+    );
+    assert_eq!(err["code"], json!(-32602));
+
+    assert!(test.seal(PASSWORD).is_err());
+    assert!(
+        test.split_into_shares(&Slip39Config::new(2, 3), "")
+            .is_err()
+    );
+    assert!(background.storage.item(AtollStorageKeys::VAULT).is_none());
+}
+
+#[test]
+fn recovered_shares_are_sealed_with_the_password() {
+    let background = Background::new();
+    let restored = background.popup(
+        "atoll:restoreVault",
+        json!({ "mnemonic": MNEMONIC, "password": PASSWORD }),
+    );
+
+    let output = background.popup(
+        "atoll:vaultSplitShares",
+        json!({ "groups": [{ "memberThreshold": 2, "memberCount": 3 }] }),
+    );
+    let shares = output[0].clone();
+
+    let restarted = Background::new();
+    let output = restarted.popup(
+        "atoll:vaultRecoverFromShares",
+        json!({ "shares": [shares[0], shares[2]], "password": PASSWORD }),
+    );
+    assert_eq!(output["account"], restored["account"]);
+
+    let restarted = restarted.restart();
+    let unlocked = restarted.popup("atoll:unlockVault", json!({ "password": PASSWORD }));
+    assert_eq!(unlocked["account"], restored["account"]);
+}
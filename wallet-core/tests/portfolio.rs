@@ -0,0 +1,601 @@
+//! SPL Token and Token-2022 accounts and mints parsed from their raw data, and the
+//! portfolio of an account fetched from a scripted cluster and cached by the background
+
+mod common;
+
+use atoll_wallet_core::{
+    App, AtollWalletError, SplInterestBearingConfig, SplMint, SplTokenAccount, SplTokenMetadata,
+    SplTokenProgram, SplTransferFee, WalletVault,
+};
+use base64ct::{Base64, Encoding};
+use serde_json::{Value, json};
+use solana_pubkey::Pubkey;
+use zeroize::Zeroizing;
+
+use common::{FakeRuntime, MockRpcServer, NOW_MS};
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+const EXTENSION_ORIGIN: &str = "chrome-extension://atoll";
+
+const TRANSFER_FEE_CONFIG: u16 = 1;
+const MEMO_TRANSFER: u16 = 8;
+const NON_TRANSFERABLE: u16 = 9;
+const INTEREST_BEARING_CONFIG: u16 = 10;
+const NON_TRANSFERABLE_ACCOUNT: u16 = 13;
+const TRANSFER_HOOK: u16 = 14;
+const METADATA_POINTER: u16 = 18;
+const TOKEN_METADATA: u16 = 19;
+
+fn pubkey(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+/// Appends the account type and the type-length-value `extensions` after the base
+/// account, padded to the size of a token account as Token-2022 does
+fn with_extensions(mut data: Vec<u8>, account_type: u8, extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    if extensions.is_empty() {
+        return data;
+    }
+
+    data.resize(SplTokenAccount::LEN, 0);
+    data.push(account_type);
+    for (extension_type, value) in extensions {
+        data.extend_from_slice(&extension_type.to_le_bytes());
+        data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+        data.extend_from_slice(value);
+    }
+
+    data
+}
+
+fn token_account_data(
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    frozen: bool,
+    extensions: &[(u16, Vec<u8>)],
+) -> Vec<u8> {
+    let mut data = vec![0u8; SplTokenAccount::LEN];
+    data[..32].copy_from_slice(mint.as_ref());
+    data[32..64].copy_from_slice(owner.as_ref());
+    data[64..72].copy_from_slice(&amount.to_le_bytes());
+    data[108] = if frozen { 2 } else { 1 };
+
+    with_extensions(data, 2, extensions)
+}
+
+fn mint_data(supply: u64, decimals: u8, extensions: &[(u16, Vec<u8>)]) -> Vec<u8> {
+    let mut data = vec![0u8; SplMint::LEN];
+    data[36..44].copy_from_slice(&supply.to_le_bytes());
+    data[44] = decimals;
+    data[45] = 1;
+
+    with_extensions(data, 1, extensions)
+}
+
+/// `(epoch, maximum_fee, basis_points)`
+fn transfer_fee_config(withheld: u64, older: (u64, u64, u16), newer: (u64, u64, u16)) -> Vec<u8> {
+    let mut value = vec![7u8; 64];
+    value.extend_from_slice(&withheld.to_le_bytes());
+    for (epoch, maximum_fee, basis_points) in [older, newer] {
+        value.extend_from_slice(&epoch.to_le_bytes());
+        value.extend_from_slice(&maximum_fee.to_le_bytes());
+        value.extend_from_slice(&basis_points.to_le_bytes());
+    }
+
+    value
+}
+
+fn interest_bearing_config(
+    initialized: i64,
+    average_rate: i16,
+    updated: i64,
+    rate: i16,
+) -> Vec<u8> {
+    let mut value = vec![7u8; 32];
+    value.extend_from_slice(&initialized.to_le_bytes());
+    value.extend_from_slice(&average_rate.to_le_bytes());
+    value.extend_from_slice(&updated.to_le_bytes());
+    value.extend_from_slice(&rate.to_le_bytes());
+
+    value
+}
+
+/// An authority followed by `address`
+fn authority_and(address: &Pubkey) -> Vec<u8> {
+    let mut value = vec![7u8; 32];
+    value.extend_from_slice(address.as_ref());
+
+    value
+}
+
+fn borsh_string(value: &str) -> Vec<u8> {
+    let mut bytes = (value.len() as u32).to_le_bytes().to_vec();
+    bytes.extend_from_slice(value.as_bytes());
+
+    bytes
+}
+
+/// The `TokenMetadata` extension without additional metadata
+fn token_metadata(mint: &Pubkey, name: &str, symbol: &str, uri: &str) -> Vec<u8> {
+    let mut value = vec![7u8; 32];
+    value.extend_from_slice(mint.as_ref());
+    for string in [name, symbol, uri] {
+        value.extend(borsh_string(string));
+    }
+    value.extend_from_slice(&0u32.to_le_bytes());
+
+    value
+}
+
+/// A Metaplex metadata account, whose strings are padded with null bytes
+fn metaplex_data(mint: &Pubkey, name: &str, symbol: &str, uri: &str) -> Vec<u8> {
+    let padded = |value: &str, length: usize| {
+        let mut padded = value.to_string();
+        padded.extend(std::iter::repeat_n('\0', length - value.len()));
+        borsh_string(&padded)
+    };
+
+    let mut data = vec![4u8];
+    data.extend_from_slice(&[7u8; 32]);
+    data.extend_from_slice(mint.as_ref());
+    data.extend(padded(name, 32));
+    data.extend(padded(symbol, 10));
+    data.extend(padded(uri, 200));
+    // Seller fee basis points and no creators
+    data.extend_from_slice(&[0xF4, 0x01, 0x00]);
+
+    data
+}
+
+fn token_error(error: AtollWalletError) -> String {
+    match error {
+        AtollWalletError::InvalidTokenAccountData(message) => message,
+        error => panic!("expected invalid token account data, got {error:?}"),
+    }
+}
+
+#[test]
+fn token_accounts_are_parsed_with_their_extensions() {
+    let data = token_account_data(&pubkey(1), &pubkey(2), 42, true, &[]);
+    assert_eq!(
+        SplTokenAccount::unpack(&data).unwrap(),
+        SplTokenAccount {
+            mint: pubkey(1),
+            owner: pubkey(2),
+            amount: 42,
+            frozen: true,
+            memo_required: false,
+            non_transferable: false,
+        }
+    );
+
+    let data = token_account_data(
+        &pubkey(1),
+        &pubkey(2),
+        u64::MAX,
+        false,
+        &[(MEMO_TRANSFER, vec![1]), (NON_TRANSFERABLE_ACCOUNT, vec![])],
+    );
+    let account = SplTokenAccount::unpack(&data).unwrap();
+    assert_eq!(account.amount, u64::MAX);
+    assert!(!account.frozen);
+    assert!(account.memo_required);
+    assert!(account.non_transferable);
+
+    // The memo requirement can be turned off again
+    let data = token_account_data(
+        &pubkey(1),
+        &pubkey(2),
+        1,
+        false,
+        &[(MEMO_TRANSFER, vec![0])],
+    );
+    assert!(!SplTokenAccount::unpack(&data).unwrap().memo_required);
+}
+
+#[test]
+fn mints_are_parsed_with_their_extensions() {
+    let data = mint_data(1_000_000, 6, &[]);
+    let mint = SplMint::unpack(&data).unwrap();
+    assert_eq!((mint.supply, mint.decimals), (1_000_000, 6));
+    assert_eq!(mint.transfer_fee, None);
+    assert_eq!(mint.ui_amount(2_500_000, 0), 2.5);
+
+    let data = mint_data(
+        5,
+        2,
+        &[
+            (
+                TRANSFER_FEE_CONFIG,
+                transfer_fee_config(9, (10, 5_000, 50), (20, 1_000, 250)),
+            ),
+            (NON_TRANSFERABLE, vec![]),
+            (TRANSFER_HOOK, authority_and(&pubkey(8))),
+            (METADATA_POINTER, authority_and(&pubkey(3))),
+            (
+                TOKEN_METADATA,
+                token_metadata(&pubkey(3), "Atoll", "ATL", "https://atoll.example/atl.json"),
+            ),
+        ],
+    );
+    let mint = SplMint::unpack(&data).unwrap();
+
+    let fees = mint.transfer_fee.unwrap();
+    assert_eq!(fees.withheld_amount, 9);
+    assert_eq!(
+        fees.older_transfer_fee,
+        SplTransferFee {
+            epoch: 10,
+            maximum_fee: 5_000,
+            basis_points: 50,
+        }
+    );
+    assert_eq!(fees.fee_for_epoch(19), &fees.older_transfer_fee);
+    assert_eq!(fees.fee_for_epoch(20), &fees.newer_transfer_fee);
+
+    assert!(mint.non_transferable);
+    assert_eq!(mint.transfer_hook, Some(pubkey(8)));
+    assert_eq!(mint.metadata_pointer, Some(pubkey(3)));
+    assert_eq!(
+        mint.metadata,
+        Some(SplTokenMetadata {
+            name: "Atoll".to_string(),
+            symbol: "ATL".to_string(),
+            uri: "https://atoll.example/atl.json".to_string(),
+        })
+    );
+
+    // Unset optional keys are all zeros
+    let data = mint_data(5, 2, &[(TRANSFER_HOOK, authority_and(&Pubkey::default()))]);
+    assert_eq!(SplMint::unpack(&data).unwrap().transfer_hook, None);
+}
+
+#[test]
+fn transfer_fees_round_up_to_the_maximum() {
+    let fee = SplTransferFee {
+        epoch: 0,
+        maximum_fee: 3_000,
+        basis_points: 250,
+    };
+
+    assert_eq!(fee.fee(0), 0);
+    assert_eq!(fee.fee(1), 1);
+    assert_eq!(fee.fee(40), 1);
+    assert_eq!(fee.fee(41), 2);
+    assert_eq!(fee.fee(100_000), 2_500);
+    assert_eq!(fee.fee(u64::MAX), 3_000);
+
+    let free = SplTransferFee {
+        basis_points: 0,
+        ..fee
+    };
+    assert_eq!(free.fee(100_000), 0);
+}
+
+#[test]
+fn interest_bearing_amounts_accrue_continuously() {
+    let year = SplInterestBearingConfig::SECONDS_PER_YEAR as i64;
+    let data = mint_data(
+        0,
+        0,
+        &[(
+            INTEREST_BEARING_CONFIG,
+            interest_bearing_config(0, 1_000, year, -500),
+        )],
+    );
+    let mint = SplMint::unpack(&data).unwrap();
+    let config = mint.interest_bearing.unwrap();
+    assert_eq!(config.current_rate, -500);
+
+    // 10% for a year then -5% for another
+    assert!((config.scale(year) - 0.1f64.exp()).abs() < 1e-9);
+    assert!((config.scale(2 * year) - 0.05f64.exp()).abs() < 1e-9);
+    assert!((mint.ui_amount(1_000, 2 * year) - 1_000.0 * 0.05f64.exp()).abs() < 1e-6);
+}
+
+#[test]
+fn metaplex_metadata_is_trimmed_of_its_padding() {
+    let data = metaplex_data(&pubkey(1), "Wrapped SOL", "SOL", "");
+    assert_eq!(
+        SplTokenMetadata::unpack_metaplex(&data).unwrap(),
+        SplTokenMetadata {
+            name: "Wrapped SOL".to_string(),
+            symbol: "SOL".to_string(),
+            uri: String::new(),
+        }
+    );
+
+    // The PDA of the `metadata` seed, the program and the mint
+    assert_eq!(
+        SplTokenMetadata::metaplex_address(&pubkey(1)),
+        Pubkey::find_program_address(
+            &[
+                b"metadata",
+                SplTokenMetadata::METAPLEX_PROGRAM_ID.as_ref(),
+                pubkey(1).as_ref(),
+            ],
+            &SplTokenMetadata::METAPLEX_PROGRAM_ID,
+        )
+        .0
+    );
+
+    assert!(SplTokenMetadata::unpack_metaplex(&data[..80]).is_err());
+}
+
+#[test]
+fn invalid_account_data_is_refused() {
+    let error = SplTokenAccount::unpack(&[0u8; 100]).unwrap_err();
+    assert!(token_error(error).contains("at least `165` bytes"));
+
+    let error = SplMint::unpack(&[0u8; 50]).unwrap_err();
+    assert!(token_error(error).contains("at least `82` bytes"));
+
+    // A token account is not a mint
+    let data = token_account_data(
+        &pubkey(1),
+        &pubkey(2),
+        1,
+        false,
+        &[(MEMO_TRANSFER, vec![1])],
+    );
+    let error = SplMint::unpack(&data).unwrap_err();
+    assert_eq!(
+        token_error(error),
+        "Expected the account type `1` but found `2`"
+    );
+
+    let mut data = mint_data(1, 0, &[(NON_TRANSFERABLE, vec![])]);
+    data.extend_from_slice(&TRANSFER_FEE_CONFIG.to_le_bytes());
+    data.extend_from_slice(&200u16.to_le_bytes());
+    data.extend_from_slice(&[0u8; 10]);
+    let error = SplMint::unpack(&data).unwrap_err();
+    assert!(token_error(error).contains("overflows the account data"));
+
+    let data = mint_data(1, 0, &[(TRANSFER_FEE_CONFIG, vec![0u8; 70])]);
+    assert!(SplMint::unpack(&data).is_err());
+}
+
+fn rpc_account(owner: &Pubkey, data: &[u8]) -> Value {
+    json!({
+        "lamports": 2_039_280,
+        "owner": owner.to_string(),
+        "data": [Base64::encode_string(data), "base64"],
+        "executable": false,
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+fn keyed(address: &Pubkey, owner: &Pubkey, data: &[u8]) -> Value {
+    json!({ "pubkey": address.to_string(), "account": rpc_account(owner, data) })
+}
+
+fn context(value: Value) -> Value {
+    json!({ "context": { "slot": 1 }, "value": value })
+}
+
+/// A background with the vault unlocked, whose account holds two SPL Token and two
+/// Token-2022 accounts on the scripted cluster
+fn portfolio_background() -> (FakeRuntime, MockRpcServer, Pubkey) {
+    let vault = WalletVault::new_from_mnemonic(Zeroizing::new(MNEMONIC.to_string()), None).unwrap();
+    let owner = vault.solana_keypair().unwrap().pubkey();
+
+    let token = SplTokenProgram::TOKEN_PROGRAM_ID;
+    let token_2022 = SplTokenProgram::TOKEN_2022_PROGRAM_ID;
+    let metaplex = SplTokenMetadata::METAPLEX_PROGRAM_ID;
+    let now = (NOW_MS / 1000.0) as i64;
+    let year = SplInterestBearingConfig::SECONDS_PER_YEAR as i64;
+
+    let server = MockRpcServer::start();
+    server
+        .reply("getBalance", context(json!(1_500_000_000u64)))
+        .reply("getEpochInfo", json!({ "epoch": 500, "slotIndex": 0 }))
+        .reply_matching(
+            "getTokenAccountsByOwner",
+            &token.to_string(),
+            context(json!([
+                keyed(
+                    &pubkey(11),
+                    &token,
+                    &token_account_data(&pubkey(1), &owner, 2_500_000, false, &[])
+                ),
+                keyed(
+                    &pubkey(12),
+                    &token,
+                    &token_account_data(&pubkey(2), &owner, 10, true, &[])
+                ),
+            ])),
+        )
+        .reply_matching(
+            "getTokenAccountsByOwner",
+            &token_2022.to_string(),
+            context(json!([
+                keyed(
+                    &pubkey(13),
+                    &token_2022,
+                    &token_account_data(&pubkey(3), &owner, 1_000_000, false, &[])
+                ),
+                keyed(
+                    &pubkey(14),
+                    &token_2022,
+                    &token_account_data(
+                        &pubkey(4),
+                        &owner,
+                        100,
+                        false,
+                        &[(MEMO_TRANSFER, vec![1]), (NON_TRANSFERABLE_ACCOUNT, vec![])],
+                    ),
+                ),
+            ])),
+        )
+        // The mints, in the order of their addresses
+        .reply_matching(
+            "getMultipleAccounts",
+            &pubkey(1).to_string(),
+            context(json!([
+                rpc_account(&token, &mint_data(0, 6, &[])),
+                rpc_account(&token, &mint_data(0, 9, &[])),
+                rpc_account(
+                    &token_2022,
+                    &mint_data(
+                        0,
+                        2,
+                        &[
+                            (
+                                TRANSFER_FEE_CONFIG,
+                                transfer_fee_config(0, (0, 1_000, 100), (600, 5_000, 200)),
+                            ),
+                            (METADATA_POINTER, authority_and(&pubkey(3))),
+                            (
+                                TOKEN_METADATA,
+                                token_metadata(&pubkey(3), "Atoll", "ATL", "")
+                            ),
+                        ],
+                    ),
+                ),
+                rpc_account(
+                    &token_2022,
+                    &mint_data(
+                        0,
+                        0,
+                        &[
+                            (NON_TRANSFERABLE, vec![]),
+                            (
+                                INTEREST_BEARING_CONFIG,
+                                interest_bearing_config(now - year, 0, now - year, 500),
+                            ),
+                        ],
+                    ),
+                ),
+            ])),
+        )
+        // The Metaplex accounts of the mints without the metadata extension. The one of
+        // the last mint is owned by another program so it is not trusted.
+        .reply_matching(
+            "getMultipleAccounts",
+            &SplTokenMetadata::metaplex_address(&pubkey(1)).to_string(),
+            context(json!([
+                rpc_account(
+                    &metaplex,
+                    &metaplex_data(&pubkey(1), "USD Coin", "USDC", "https://usdc.example")
+                ),
+                null,
+                rpc_account(&pubkey(99), &metaplex_data(&pubkey(4), "Fake", "FAKE", "")),
+            ])),
+        );
+
+    let app = App::new(server.transport())
+        .set_extension_origin(EXTENSION_ORIGIN)
+        .set_vault(vault)
+        .unwrap();
+    let runtime = FakeRuntime::new();
+    common::listen(app, &runtime);
+
+    (runtime, server, owner)
+}
+
+fn portfolio(runtime: &FakeRuntime, params: Value) -> Value {
+    let response = runtime.request(
+        "portfolio",
+        "atoll:solanaPortfolio",
+        EXTENSION_ORIGIN,
+        params,
+    );
+    assert!(response["err"].is_null(), "{response}");
+
+    response["ok"].clone()
+}
+
+#[test]
+fn the_portfolio_lists_both_token_programs_with_their_metadata() {
+    let (runtime, server, owner) = portfolio_background();
+
+    let output = portfolio(&runtime, Value::Null);
+    assert_eq!(output["owner"], json!(owner.to_string()));
+    assert_eq!(output["lamports"].as_f64(), Some(1_500_000_000.0));
+    assert_eq!(output["fetchedAt"].as_f64(), Some(NOW_MS));
+
+    let holdings = output["holdings"].as_array().unwrap();
+    let addresses = holdings
+        .iter()
+        .map(|holding| holding["address"].as_str().unwrap().to_string())
+        .collect::<Vec<String>>();
+
+    // Largest balances first
+    assert_eq!(
+        addresses,
+        [13, 14, 11, 12].map(|byte| pubkey(byte).to_string())
+    );
+
+    let [transfer_fee, interest, usdc, frozen] = [0, 1, 2, 3].map(|index| &holdings[index]);
+
+    assert_eq!(transfer_fee["program"], json!("token-2022"));
+    assert_eq!(transfer_fee["amount"], json!("1000000"));
+    assert_eq!(transfer_fee["uiAmount"].as_f64(), Some(10_000.0));
+    assert_eq!(transfer_fee["name"], json!("Atoll"));
+    assert_eq!(transfer_fee["uri"], Value::Null);
+    // The epoch 500 is before the newer fee
+    assert_eq!(transfer_fee["transferFeeBasisPoints"].as_f64(), Some(100.0));
+    assert_eq!(transfer_fee["transferFeeMaximum"], json!("1000"));
+
+    assert!((interest["uiAmount"].as_f64().unwrap() - 100.0 * 0.05f64.exp()).abs() < 1e-6);
+    assert_eq!(interest["memoRequired"], json!(true));
+    assert_eq!(interest["nonTransferable"], json!(true));
+    assert_eq!(interest["name"], Value::Null);
+
+    assert_eq!(usdc["program"], json!("token"));
+    assert_eq!(usdc["uiAmount"].as_f64(), Some(2.5));
+    assert_eq!(usdc["decimals"].as_f64(), Some(6.0));
+    assert_eq!(usdc["name"], json!("USD Coin"));
+    assert_eq!(usdc["symbol"], json!("USDC"));
+    assert_eq!(usdc["uri"], json!("https://usdc.example"));
+
+    assert_eq!(frozen["frozen"], json!(true));
+    assert_eq!(frozen["name"], Value::Null);
+    assert_eq!(frozen["transferFeeBasisPoints"], Value::Null);
+
+    assert_eq!(
+        server.methods(),
+        [
+            "getBalance",
+            "getTokenAccountsByOwner",
+            "getTokenAccountsByOwner",
+            "getMultipleAccounts",
+            "getMultipleAccounts",
+            "getEpochInfo",
+        ]
+    );
+}
+
+#[test]
+fn the_portfolio_is_cached_per_account_and_chain() {
+    let (runtime, server, owner) = portfolio_background();
+
+    let first = portfolio(&runtime, Value::Null);
+    let calls = server.calls().len();
+
+    assert_eq!(
+        portfolio(&runtime, json!({ "address": owner.to_string() })),
+        first
+    );
+    assert_eq!(server.calls().len(), calls);
+
+    portfolio(&runtime, json!({ "refresh": true }));
+    assert_eq!(server.calls().len(), 2 * calls);
+
+    let testnet = portfolio(&runtime, json!({ "chain": "solana:testnet" }));
+    assert_eq!(testnet["chain"], json!("solana:testnet"));
+    assert_eq!(server.calls().len(), 3 * calls);
+
+    // Accounts the wallet does not hold
+    let response = runtime.request(
+        "portfolio",
+        "atoll:solanaPortfolio",
+        EXTENSION_ORIGIN,
+        json!({ "address": pubkey(9).to_string() }),
+    );
+    assert!(response["ok"].is_null(), "{response}");
+}
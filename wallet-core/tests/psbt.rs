@@ -0,0 +1,628 @@
+//! Signing BIP174 and BIP370 PSBTs with the P2WPKH and P2TR accounts of a vault
+
+use std::str::FromStr;
+
+use atoll_wallet_core::{
+    AtollWalletError, BitcoinAccountKeypair, BitcoinCluster, BitcoinInputsToSign, BitcoinPsbt,
+    BitcoinPurpose, PsbtV2, WalletVault,
+};
+use base64ct::{Base64, Encoding};
+use bitcoin::{
+    Amount, OutPoint, Psbt, ScriptBuf, Sequence, TapSighashType, Transaction, TxIn, TxOut, Txid,
+    Witness, XOnlyPublicKey,
+    absolute::LockTime,
+    bip32::{DerivationPath, Fingerprint},
+    consensus::encode::{deserialize, serialize},
+    ecdsa,
+    hashes::Hash,
+    key::{Secp256k1, TapTweak},
+    psbt::PsbtSighashType,
+    secp256k1::Message,
+    sighash::{EcdsaSighashType, Prevouts, SighashCache},
+    taproot,
+    transaction::Version,
+};
+use zeroize::Zeroizing;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+/// The first valid vector of BIP174: one P2PKH input with its previous transaction
+/// and two outputs without any field
+const BIP174_P2PKH: &str = "cHNidP8BAHUCAAAAASaBcTce3/KF6Tet7qSze3gADAVmy7OtZGQXE8pCFxv2AAAAAAD+////AtPf9QUAAAAAGXapFNDFmQPFusKGh2DpD9UhpGZap2UgiKwA4fUFAAAAABepFDVF5uM7gyxHBQ8k0+65PJwDlIvHh7MuEwAAAQD9pQEBAAAAAAECiaPHHqtNIOA3G7ukzGmPopXJRjr6Ljl/hTPMti+VZ+UBAAAAFxYAFL4Y0VKpsBIDna89p95PUzSe7LmF/////4b4qkOnHf8USIk6UwpyN+9rRgi7st0tAXHmOuxqSJC0AQAAABcWABT+Pp7xp0XpdNkCxDVZQ6vLNL1TU/////8CAMLrCwAAAAAZdqkUhc/xCX/Z4Ai7NK9wnGIZeziXikiIrHL++E4sAAAAF6kUM5cluiHv1irHU6m80GfWx6ajnQWHAkcwRAIgJxK+IuAnDzlPVoMR3HyppolwuAJf3TskAinwf4pfOiQCIAGLONfc0xTnNMkna9b7QPZzMlvEuqFEyADS8vAtsnZcASED0uFWdJQbrUqZY3LLh+GFbTZSYG2YVi/jnF6efkE/IQUCSDBFAiEA0SuFLYXc2WHS9fSrZgZU327tzHlMDDPOXMMJ/7X85Y0CIGczio4OFyXBl/saiK9Z9R5E5CVbIBZ8hoQDHAXR8lkqASECI7cr7vCWXRC+B3jv7NYfysb3mk6haTkzgHNEZPhPKrMAAAAAAAAA";
+
+/// The first invalid vector of BIP174: the network serialization of the signed transaction
+const BIP174_NETWORK_TRANSACTION: &str = "AgAAAAEmgXE3Ht/yhek3re6ks3t4AAwFZsuzrWRkFxPKQhcb9gAAAABqRzBEAiBwsiRRI+a/R01gxbUMBD1MaRpdJDXwmjSnZiqdwlF5CgIgATKcqdrPKAvfMHQOwDkEIkIsgctFg5RXrrdvwS7dlbMBIQJlfRGNM1e44PTCzUbbezn22cONmnCry5st5dyNv+TOMf7///8C09/1BQAAAAAZdqkU0MWZA8W6woaHYOkP1SGkZlqnZSCIrADh9QUAAAAAF6kUNUXm4zuDLEcFDyTT7rk8nAOUi8eHsy4TAA==";
+
+const GLOBAL_TX_VERSION: u8 = 0x02;
+const GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const GLOBAL_INPUT_COUNT: u8 = 0x04;
+const GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const GLOBAL_VERSION: u8 = 0xFB;
+const IN_WITNESS_UTXO: u8 = 0x01;
+const IN_PREVIOUS_TXID: u8 = 0x0E;
+const IN_OUTPUT_INDEX: u8 = 0x0F;
+const IN_SEQUENCE: u8 = 0x10;
+const IN_REQUIRED_TIME_LOCKTIME: u8 = 0x11;
+const IN_REQUIRED_HEIGHT_LOCKTIME: u8 = 0x12;
+const OUT_AMOUNT: u8 = 0x03;
+const OUT_SCRIPT: u8 = 0x04;
+
+fn keypair(purpose: BitcoinPurpose, account: u32) -> BitcoinAccountKeypair {
+    WalletVault::new_from_mnemonic(Zeroizing::new(MNEMONIC.to_string()), None)
+        .unwrap()
+        .bitcoin_keypair(purpose, BitcoinCluster::Regtest, account)
+        .unwrap()
+}
+
+fn outpoint(byte: u8) -> OutPoint {
+    OutPoint {
+        txid: Txid::from_byte_array([byte; 32]),
+        vout: byte as u32,
+    }
+}
+
+fn prevout(keypair: &BitcoinAccountKeypair, value: u64) -> TxOut {
+    TxOut {
+        value: Amount::from_sat(value),
+        script_pubkey: keypair.address().script_pubkey(),
+    }
+}
+
+/// A PSBT spending one output of each of `prevouts` to a single output
+fn psbt(prevouts: &[TxOut]) -> Psbt {
+    let transaction = Transaction {
+        version: Version::TWO,
+        lock_time: LockTime::ZERO,
+        input: (0..prevouts.len())
+            .map(|index| TxIn {
+                previous_output: outpoint(index as u8 + 1),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                witness: Witness::new(),
+            })
+            .collect(),
+        output: vec![TxOut {
+            value: Amount::from_sat(25_000),
+            script_pubkey: keypair(BitcoinPurpose::Payment, 9)
+                .address()
+                .script_pubkey(),
+        }],
+    };
+
+    let mut psbt = Psbt::from_unsigned_tx(transaction).unwrap();
+    psbt.inputs
+        .iter_mut()
+        .zip(prevouts)
+        .for_each(|(input, prevout)| {
+            input.witness_utxo.replace(prevout.clone());
+        });
+
+    psbt
+}
+
+fn bitcoin_error(error: AtollWalletError) -> String {
+    match error {
+        AtollWalletError::Bitcoin(message) => message,
+        error => panic!("expected a Bitcoin error, got {error:?}"),
+    }
+}
+
+/// Checks the P2WPKH signature of the finalized `index` against the extracted transaction
+fn verify_p2wpkh(
+    transaction: &Transaction,
+    index: usize,
+    prevout: &TxOut,
+    keypair: &BitcoinAccountKeypair,
+) -> EcdsaSighashType {
+    let witness = &transaction.input[index].witness;
+    assert_eq!(witness.len(), 2);
+    assert_eq!(witness.nth(1).unwrap(), keypair.public_key().serialize());
+
+    let signature = ecdsa::Signature::from_slice(witness.nth(0).unwrap()).unwrap();
+    let sighash = SighashCache::new(transaction)
+        .p2wpkh_signature_hash(
+            index,
+            &prevout.script_pubkey,
+            prevout.value,
+            signature.sighash_type,
+        )
+        .unwrap();
+    Secp256k1::new()
+        .verify_ecdsa(
+            &Message::from_digest(sighash.to_byte_array()),
+            &signature.signature,
+            &keypair.public_key(),
+        )
+        .unwrap();
+
+    signature.sighash_type
+}
+
+/// Checks the P2TR key path signature of the finalized `index` against the tweaked output key
+fn verify_p2tr(
+    transaction: &Transaction,
+    index: usize,
+    prevouts: &[TxOut],
+    keypair: &BitcoinAccountKeypair,
+) -> TapSighashType {
+    let secp = Secp256k1::new();
+    let witness = &transaction.input[index].witness;
+    assert_eq!(witness.len(), 1);
+
+    let signature = taproot::Signature::from_slice(witness.nth(0).unwrap()).unwrap();
+    let sighash = SighashCache::new(transaction)
+        .taproot_key_spend_signature_hash(index, &Prevouts::All(prevouts), signature.sighash_type)
+        .unwrap();
+    let (output_key, _) = XOnlyPublicKey::from(keypair.public_key()).tap_tweak(&secp, None);
+    secp.verify_schnorr(
+        &signature.signature,
+        &Message::from_digest(sighash.to_byte_array()),
+        &output_key.to_x_only_public_key(),
+    )
+    .unwrap();
+
+    signature.sighash_type
+}
+
+#[test]
+fn p2wpkh_inputs_are_signed_finalized_and_extracted() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let prevouts = [prevout(&payment, 30_000), prevout(&payment, 20_000)];
+
+    let mut psbt = BitcoinPsbt::from(psbt(&prevouts));
+    assert!(!psbt.is_v2());
+
+    // Every input the account owns is signed when no inputs are named
+    assert_eq!(psbt.sign(&[&payment], &[]).unwrap(), vec![0, 1]);
+    assert!(psbt.psbt().inputs.iter().all(|input| {
+        input
+            .partial_sigs
+            .contains_key(&bitcoin::PublicKey::new(payment.public_key()))
+    }));
+
+    // ECDSA signatures are deterministic
+    let mut again = BitcoinPsbt::from(self::psbt(&prevouts));
+    again.sign(&[&payment], &[]).unwrap();
+    assert_eq!(again.to_bytes().unwrap(), psbt.to_bytes().unwrap());
+
+    let error = psbt.extract_transaction().unwrap_err();
+    assert_eq!(
+        bitcoin_error(error),
+        "The input `0` of the PSBT is not finalized"
+    );
+
+    psbt.finalize(&[0, 1]).unwrap();
+    assert!(
+        psbt.psbt()
+            .inputs
+            .iter()
+            .all(|input| { input.partial_sigs.is_empty() && input.witness_utxo.is_some() })
+    );
+
+    let transaction = psbt.extract_transaction().unwrap();
+    for (index, prevout) in prevouts.iter().enumerate() {
+        assert_eq!(
+            verify_p2wpkh(&transaction, index, prevout, &payment),
+            EcdsaSighashType::All
+        );
+    }
+}
+
+#[test]
+fn p2tr_key_path_inputs_commit_to_every_prevout() {
+    let ordinals = keypair(BitcoinPurpose::Ordinals, 0);
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let prevouts = [prevout(&payment, 30_000), prevout(&ordinals, 546)];
+
+    let mut psbt = BitcoinPsbt::from(psbt(&prevouts));
+    assert_eq!(psbt.sign(&[&ordinals], &[]).unwrap(), vec![1]);
+    assert!(psbt.psbt().inputs[0].partial_sigs.is_empty());
+    assert!(psbt.psbt().inputs[1].tap_key_sig.is_some());
+
+    assert_eq!(psbt.sign(&[&payment], &[]).unwrap(), vec![0]);
+    psbt.finalize(&[0, 1]).unwrap();
+
+    let transaction = psbt.extract_transaction().unwrap();
+    verify_p2wpkh(&transaction, 0, &prevouts[0], &payment);
+    assert_eq!(
+        verify_p2tr(&transaction, 1, &prevouts, &ordinals),
+        TapSighashType::Default
+    );
+
+    // The other prevouts are part of the Taproot sighash so they are required
+    let mut partial = self::psbt(&prevouts);
+    partial.inputs[0].witness_utxo = None;
+    let error = BitcoinPsbt::from(partial)
+        .sign(&[&ordinals], &[])
+        .unwrap_err();
+    assert!(bitcoin_error(error).contains("input `0` has none"));
+}
+
+#[test]
+fn inputs_to_sign_are_checked_against_the_accounts() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let ordinals = keypair(BitcoinPurpose::Ordinals, 0);
+    let prevouts = [prevout(&payment, 30_000), prevout(&ordinals, 546)];
+
+    let request = |address: &BitcoinAccountKeypair, indexes: &[usize]| BitcoinInputsToSign {
+        address: address.address().to_string(),
+        signing_indexes: indexes.to_vec(),
+        sighash_type: None,
+    };
+
+    // Only the named inputs are signed
+    let mut psbt = BitcoinPsbt::from(psbt(&prevouts));
+    assert_eq!(
+        psbt.sign(&[&payment, &ordinals], &[request(&ordinals, &[1])])
+            .unwrap(),
+        vec![1]
+    );
+    assert!(psbt.psbt().inputs[0].partial_sigs.is_empty());
+
+    // An address the wallet has no account for
+    let other = keypair(BitcoinPurpose::Payment, 1);
+    let error = BitcoinPsbt::from(self::psbt(&prevouts))
+        .sign(&[&payment], &[request(&other, &[0])])
+        .unwrap_err();
+    assert!(
+        matches!(error, AtollWalletError::AccountNotFound(address) if address == other.address().to_string())
+    );
+
+    // An input paying to another account
+    let error = BitcoinPsbt::from(self::psbt(&prevouts))
+        .sign(&[&payment, &ordinals], &[request(&payment, &[0, 1])])
+        .unwrap_err();
+    assert_eq!(
+        bitcoin_error(error),
+        format!("The input `1` cannot be signed by `{}`", payment.address())
+    );
+
+    // An input that does not exist
+    let error = BitcoinPsbt::from(self::psbt(&prevouts))
+        .sign(&[&payment], &[request(&payment, &[2])])
+        .unwrap_err();
+    assert!(bitcoin_error(error).contains("The input `2` cannot be signed"));
+
+    // A key origin naming another wallet
+    let mut foreign = self::psbt(&prevouts);
+    foreign.inputs[0].bip32_derivation.insert(
+        payment.public_key(),
+        (
+            Fingerprint::from([1, 2, 3, 4]),
+            DerivationPath::from_str("m/84'/1'/0'/0/0").unwrap(),
+        ),
+    );
+    let mut foreign = BitcoinPsbt::from(foreign);
+    assert_eq!(foreign.sign(&[&payment], &[]).unwrap(), Vec::<usize>::new());
+    assert!(
+        foreign
+            .sign(&[&payment], &[request(&payment, &[0])])
+            .is_err()
+    );
+}
+
+#[test]
+fn sighash_types_must_match_the_psbt() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let ordinals = keypair(BitcoinPurpose::Ordinals, 0);
+    let prevouts = [prevout(&payment, 30_000), prevout(&ordinals, 546)];
+
+    let request =
+        |address: &BitcoinAccountKeypair, index: usize, sighash_type: u32| BitcoinInputsToSign {
+            address: address.address().to_string(),
+            signing_indexes: vec![index],
+            sighash_type: Some(sighash_type),
+        };
+
+    // The requested sighash type is written into the input and signed with
+    let mut psbt = BitcoinPsbt::from(psbt(&prevouts));
+    psbt.sign(
+        &[&payment, &ordinals],
+        &[request(&payment, 0, 0x83), request(&ordinals, 1, 0x81)],
+    )
+    .unwrap();
+    assert_eq!(
+        psbt.psbt().inputs[0].sighash_type,
+        Some(PsbtSighashType::from_u32(0x83))
+    );
+    psbt.finalize(&[0, 1]).unwrap();
+
+    let transaction = psbt.extract_transaction().unwrap();
+    assert_eq!(
+        verify_p2wpkh(&transaction, 0, &prevouts[0], &payment),
+        EcdsaSighashType::SinglePlusAnyoneCanPay
+    );
+    assert_eq!(
+        verify_p2tr(&transaction, 1, &prevouts, &ordinals),
+        TapSighashType::AllPlusAnyoneCanPay
+    );
+    // A Taproot signature with an explicit sighash type carries it in a 65th byte
+    assert_eq!(transaction.input[1].witness.nth(0).unwrap().len(), 65);
+
+    // A sighash type other than the one the PSBT already requires
+    let mut required = self::psbt(&prevouts);
+    required.inputs[0].sighash_type = Some(PsbtSighashType::from_u32(0x01));
+    let mut required = BitcoinPsbt::from(required);
+
+    let error = required
+        .sign(&[&payment], &[request(&payment, 0, 0x02)])
+        .unwrap_err();
+    assert_eq!(
+        bitcoin_error(error),
+        "The input `0` requires the sighash type `SIGHASH_ALL`"
+    );
+    assert_eq!(
+        required
+            .sign(&[&payment], &[request(&payment, 0, 0x01)])
+            .unwrap(),
+        vec![0]
+    );
+
+    // A sighash type that is not valid for the input
+    let error = BitcoinPsbt::from(self::psbt(&prevouts))
+        .sign(&[&ordinals], &[request(&ordinals, 1, 0x04)])
+        .unwrap_err();
+    assert!(matches!(error, AtollWalletError::Bitcoin(_)));
+}
+
+#[test]
+fn finalizing_requires_a_signature() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let mut psbt = BitcoinPsbt::from(psbt(&[prevout(&payment, 30_000)]));
+
+    let error = psbt.finalize(&[0]).map(|_| ()).unwrap_err();
+    assert_eq!(
+        bitcoin_error(error),
+        "The input `0` has no signature to finalize"
+    );
+
+    let error = psbt.finalize(&[1]).map(|_| ()).unwrap_err();
+    assert_eq!(bitcoin_error(error), "The PSBT has no input `1`");
+}
+
+#[test]
+fn bip174_vectors() {
+    let bytes = Base64::decode_vec(BIP174_P2PKH).unwrap();
+    let mut psbt = BitcoinPsbt::from_bytes(&bytes).unwrap();
+    assert!(!psbt.is_v2());
+    assert_eq!(psbt.to_bytes().unwrap(), bytes);
+
+    let unsigned = psbt.psbt().unsigned_tx.clone();
+    assert_eq!(
+        unsigned.compute_txid().to_string(),
+        "af2cac1e0e33d896d9d0751d66fcb2fa54b737c7a13199281fb57e4f497bb652"
+    );
+    assert_eq!((unsigned.input.len(), unsigned.output.len()), (1, 2));
+
+    // The previous transaction is the one the input spends
+    let previous = psbt.psbt().inputs[0].non_witness_utxo.as_ref().unwrap();
+    assert_eq!(
+        previous.compute_txid(),
+        unsigned.input[0].previous_output.txid
+    );
+
+    // The P2PKH input belongs to none of the accounts
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let ordinals = keypair(BitcoinPurpose::Ordinals, 0);
+    assert_eq!(
+        psbt.sign(&[&payment, &ordinals], &[]).unwrap(),
+        Vec::<usize>::new()
+    );
+
+    // A network transaction is not a PSBT
+    let network = Base64::decode_vec(BIP174_NETWORK_TRANSACTION).unwrap();
+    let signed: Transaction = deserialize(&network).unwrap();
+    assert_eq!(signed.compute_txid(), {
+        let mut unsigned = unsigned.clone();
+        unsigned.input[0].script_sig = signed.input[0].script_sig.clone();
+        unsigned.compute_txid()
+    });
+    assert!(BitcoinPsbt::from_bytes(&network).is_err());
+
+    // The vector without its output maps
+    assert!(BitcoinPsbt::from_bytes(&bytes[..bytes.len() - 2]).is_err());
+}
+
+/// The key-value maps of a PSBT, each key being a single key type byte
+type Maps = Vec<Vec<(u8, Vec<u8>)>>;
+
+fn raw_psbt(maps: &Maps) -> Vec<u8> {
+    let mut bytes = b"psbt\xff".to_vec();
+
+    for map in maps {
+        for (key_type, value) in map.iter() {
+            bytes.extend_from_slice(&[0x01, *key_type]);
+            bytes.extend_from_slice(&serialize(value));
+        }
+        bytes.push(0x00);
+    }
+
+    bytes
+}
+
+fn u32_value(value: u32) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+/// The maps of a version 2 PSBT spending `prevouts` with the `(time, height)`
+/// locktimes `required` by each input
+fn psbt_v2_maps(prevouts: &[TxOut], required: &[(Option<u32>, Option<u32>)]) -> Maps {
+    let global = vec![
+        (GLOBAL_TX_VERSION, u32_value(2)),
+        (GLOBAL_FALLBACK_LOCKTIME, u32_value(100)),
+        (GLOBAL_INPUT_COUNT, vec![prevouts.len() as u8]),
+        (GLOBAL_OUTPUT_COUNT, vec![1]),
+        (GLOBAL_VERSION, u32_value(2)),
+    ];
+
+    let inputs = prevouts
+        .iter()
+        .enumerate()
+        .map(|(index, prevout)| {
+            let outpoint = outpoint(index as u8 + 1);
+            let mut map = vec![
+                (IN_WITNESS_UTXO, serialize(prevout)),
+                (IN_PREVIOUS_TXID, outpoint.txid.to_byte_array().to_vec()),
+                (IN_OUTPUT_INDEX, u32_value(outpoint.vout)),
+                (IN_SEQUENCE, u32_value(0xFFFF_FFFD)),
+            ];
+            let (time, height) = required.get(index).copied().unwrap_or_default();
+            map.extend(time.map(|time| (IN_REQUIRED_TIME_LOCKTIME, u32_value(time))));
+            map.extend(height.map(|height| (IN_REQUIRED_HEIGHT_LOCKTIME, u32_value(height))));
+
+            map
+        })
+        .collect::<Vec<_>>();
+
+    let output = vec![
+        (OUT_AMOUNT, 25_000u64.to_le_bytes().to_vec()),
+        (
+            OUT_SCRIPT,
+            keypair(BitcoinPurpose::Payment, 9)
+                .address()
+                .script_pubkey()
+                .to_bytes(),
+        ),
+    ];
+
+    let mut maps = vec![global];
+    maps.extend(inputs);
+    maps.push(output);
+
+    maps
+}
+
+fn psbt_v2(prevouts: &[TxOut], required: &[(Option<u32>, Option<u32>)]) -> Vec<u8> {
+    raw_psbt(&psbt_v2_maps(prevouts, required))
+}
+
+#[test]
+fn version_2_psbts_round_trip_through_version_0() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let ordinals = keypair(BitcoinPurpose::Ordinals, 0);
+    let prevouts = [prevout(&payment, 30_000), prevout(&ordinals, 546)];
+    let v2 = psbt_v2(&prevouts, &[]);
+
+    assert_eq!(PsbtV2::version(&v2).unwrap(), 2);
+    assert_eq!(
+        PsbtV2::version(&Base64::decode_vec(BIP174_P2PKH).unwrap()).unwrap(),
+        0
+    );
+
+    let (v0, fields) = PsbtV2::to_v0(&v2).unwrap();
+    assert_eq!(PsbtV2::version(&v0).unwrap(), 0);
+    assert_eq!(PsbtV2::from_v0(&v0, &fields).unwrap(), v2);
+
+    // The version 0 PSBT holds the transaction the version 2 fields describe
+    let mut expected = psbt(&prevouts).unsigned_tx;
+    expected.lock_time = LockTime::from_consensus(100);
+    expected
+        .input
+        .iter_mut()
+        .for_each(|input| input.sequence = Sequence(0xFFFF_FFFD));
+    assert_eq!(Psbt::deserialize(&v0).unwrap().unsigned_tx, expected);
+
+    let mut psbt = BitcoinPsbt::from_bytes(&v2).unwrap();
+    assert!(psbt.is_v2());
+    assert_eq!(psbt.to_bytes().unwrap(), v2);
+
+    // Signing keeps the version and the version 2 fields
+    assert_eq!(psbt.sign(&[&payment, &ordinals], &[]).unwrap(), vec![0, 1]);
+    let signed = psbt.to_bytes().unwrap();
+    assert_eq!(PsbtV2::version(&signed).unwrap(), 2);
+
+    let reparsed = BitcoinPsbt::from_bytes(&signed).unwrap();
+    assert_eq!(reparsed.psbt().unsigned_tx, expected);
+    assert_eq!(reparsed.psbt().inputs[0].partial_sigs.len(), 1);
+    assert!(reparsed.psbt().inputs[1].tap_key_sig.is_some());
+
+    let mut reparsed = reparsed;
+    reparsed.finalize(&[0, 1]).unwrap();
+    let transaction = reparsed.extract_transaction().unwrap();
+    verify_p2wpkh(&transaction, 0, &prevouts[0], &payment);
+    verify_p2tr(&transaction, 1, &prevouts, &ordinals);
+}
+
+#[test]
+fn bip370_locktimes_are_determined_from_the_inputs() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let prevouts = [prevout(&payment, 30_000), prevout(&payment, 20_000)];
+
+    let lock_time = |required: &[(Option<u32>, Option<u32>)]| {
+        BitcoinPsbt::from_bytes(&psbt_v2(&prevouts, required))
+            .map(|psbt| psbt.psbt().unsigned_tx.lock_time.to_consensus_u32())
+    };
+
+    const TIME: u32 = 500_000_000;
+
+    // No input requires a locktime so the fallback is used
+    assert_eq!(lock_time(&[]).unwrap(), 100);
+    // The largest height required
+    assert_eq!(
+        lock_time(&[(None, Some(10_000)), (None, Some(9_000))]).unwrap(),
+        10_000
+    );
+    // The largest time required
+    assert_eq!(
+        lock_time(&[(Some(TIME + 1), None), (Some(TIME), None)]).unwrap(),
+        TIME + 1
+    );
+    // Heights are preferred when every input supports both
+    assert_eq!(
+        lock_time(&[(Some(TIME), Some(10_000)), (Some(TIME + 1), Some(9_000))]).unwrap(),
+        10_000
+    );
+    // An input supporting both does not stop the other from requiring a time
+    assert_eq!(
+        lock_time(&[(Some(TIME), Some(10_000)), (Some(TIME + 1), None)]).unwrap(),
+        TIME + 1
+    );
+    // One input requires a height and the other a time
+    let error = lock_time(&[(None, Some(10_000)), (Some(TIME), None)]).unwrap_err();
+    assert!(bitcoin_error(error).contains("both a height and a time"));
+}
+
+#[test]
+fn bip370_invalid_psbts_are_refused() {
+    let payment = keypair(BitcoinPurpose::Payment, 0);
+    let maps = psbt_v2_maps(&[prevout(&payment, 30_000)], &[]);
+    assert!(BitcoinPsbt::from_bytes(&raw_psbt(&maps)).is_ok());
+
+    // The global map, the input and the output without `key_type`
+    let without = |map: usize, key_type: u8| {
+        let mut maps = maps.clone();
+        maps[map].retain(|(key, _)| *key != key_type);
+
+        raw_psbt(&maps)
+    };
+
+    for (map, key_type, expected) in [
+        (0, GLOBAL_INPUT_COUNT, "no input count"),
+        (0, GLOBAL_OUTPUT_COUNT, "no output count"),
+        (0, GLOBAL_TX_VERSION, "no transaction version"),
+        (1, IN_PREVIOUS_TXID, "no previous txid"),
+        (1, IN_OUTPUT_INDEX, "no output index"),
+        (2, OUT_AMOUNT, "no amount"),
+        (2, OUT_SCRIPT, "no script"),
+    ] {
+        let error = BitcoinPsbt::from_bytes(&without(map, key_type))
+            .map(|_| ())
+            .unwrap_err();
+        assert!(bitcoin_error(error).contains(expected), "{expected}");
+    }
+
+    // Without the version field the PSBT is read as version 0, which requires
+    // the unsigned transaction
+    assert!(BitcoinPsbt::from_bytes(&without(0, GLOBAL_VERSION)).is_err());
+
+    let mut version_1 = maps.clone();
+    version_1[0]
+        .iter_mut()
+        .filter(|(key, _)| *key == GLOBAL_VERSION)
+        .for_each(|(_, value)| *value = u32_value(1));
+    let error = BitcoinPsbt::from_bytes(&raw_psbt(&version_1))
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(bitcoin_error(error), "PSBT version `1` is not supported");
+}
@@ -0,0 +1,218 @@
+//! The signer backends behind an account: watch-only accounts, a remote signing service
+//! over a mock HTTP transport and a Ledger over recorded exchanges
+
+mod common;
+
+use atoll_wallet_core::{
+    AtollWalletError, RecordedApduTransport, RemoteSigner, SignerKind, SolanaAccountKeypair,
+    SolanaSignableMessage, WalletSigner, WatchOnlySigner,
+};
+use base64ct::{Base64, Encoding};
+use serde_json::{Value, json};
+use solana_derivation_path::DerivationPath;
+use solana_keypair::Keypair;
+use solana_message::Message;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+use solana_transaction::Transaction;
+
+use common::{MockHttpTransport, block_on};
+
+const ENDPOINT: &str = "https://signer.example/sign";
+
+fn keypair() -> Keypair {
+    Keypair::new_from_array([4u8; 32])
+}
+
+fn public_key() -> Pubkey {
+    Signer::pubkey(&keypair())
+}
+
+fn sign(bytes: &[u8]) -> Signature {
+    Signer::sign_message(&keypair(), bytes)
+}
+
+/// A transfer from [public_key] that it has to sign
+fn transfer() -> Transaction {
+    Transaction::new_unsigned(Message::new_with_blockhash(
+        &[solana_system_interface::instruction::transfer(
+            &public_key(),
+            &Pubkey::new_from_array([9u8; 32]),
+            5_000,
+        )],
+        Some(&public_key()),
+        &solana_hash::Hash::new_from_array([6u8; 32]),
+    ))
+}
+
+fn remote_signer(reply: Value) -> RemoteSigner<MockHttpTransport> {
+    RemoteSigner::new(
+        MockHttpTransport::new().reply(ENDPOINT, &reply.to_string()),
+        ENDPOINT,
+        public_key(),
+    )
+}
+
+fn remote_error(error: AtollWalletError) -> String {
+    match error {
+        AtollWalletError::RemoteSigner(message) => message,
+        error => panic!("expected a remote signer error, got {error:?}"),
+    }
+}
+
+#[test]
+fn watch_only_accounts_refuse_to_sign() {
+    let signer = WatchOnlySigner::new(public_key());
+    assert_eq!(signer.kind(), SignerKind::WatchOnly);
+
+    assert!(matches!(
+        block_on(signer.sign_message(b"hello")),
+        Err(AtollWalletError::WatchOnlyAccount(address)) if address == public_key().to_string()
+    ));
+    assert!(matches!(
+        block_on(signer.sign_transaction(transfer())),
+        Err(AtollWalletError::WatchOnlyAccount(_))
+    ));
+
+    let account = SolanaAccountKeypair::new_watch_only(&format!(" {} ", public_key())).unwrap();
+    assert!(account.is_watch_only());
+    assert!(account.get_wallet_account().watch_only());
+
+    let message = SolanaSignableMessage::inspect(b"hello".to_vec(), &public_key()).unwrap();
+    let error = block_on(account.sign_message(&message)).unwrap_err();
+    assert_eq!(error.category().code(), 4100);
+
+    assert!(SolanaAccountKeypair::new_watch_only("not an address").is_err());
+}
+
+#[test]
+fn the_remote_signer_posts_the_message_and_checks_the_signature() {
+    let message = b"sign me remotely";
+    let signer = remote_signer(json!({ "signature": sign(message).to_string() }));
+    assert_eq!(signer.kind(), SignerKind::Remote);
+    assert_eq!(signer.endpoint(), ENDPOINT);
+
+    assert_eq!(
+        block_on(signer.sign_message(message)).unwrap(),
+        sign(message)
+    );
+
+    let transport = MockHttpTransport::new().reply(
+        ENDPOINT,
+        &json!({ "signature": sign(message).to_string() }).to_string(),
+    );
+    let signer = RemoteSigner::new(transport.clone(), ENDPOINT, public_key());
+    block_on(signer.sign_message(message)).unwrap();
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].0, ENDPOINT);
+    assert_eq!(
+        serde_json::from_str::<Value>(&requests[0].1).unwrap(),
+        json!({
+            "publicKey": public_key().to_string(),
+            "message": Base64::encode_string(message),
+        })
+    );
+}
+
+#[test]
+fn the_remote_signer_signs_transactions() {
+    let transaction = transfer();
+    let signature = sign(&transaction.message_data());
+
+    let signer = remote_signer(json!({ "signature": signature.to_string() }));
+    let signed = block_on(signer.sign_transaction(transaction)).unwrap();
+
+    assert_eq!(signed.signatures, vec![signature]);
+    assert!(signed.verify().is_ok());
+}
+
+#[test]
+fn the_remote_signer_refuses_bad_responses() {
+    let message = b"sign me remotely";
+
+    for (reply, expected) in [
+        (
+            json!({ "error": "The key is disabled" }),
+            "The key is disabled",
+        ),
+        (
+            json!({ "signature": "not base58 !" }),
+            "The remote signer returned an invalid base58 signature",
+        ),
+        (
+            json!({ "signature": sign(b"another message").to_string() }),
+            "The signature returned by the remote signer is not valid for this account",
+        ),
+        (json!({}), "The remote signer did not return a signature"),
+        (
+            json!("not an object"),
+            "The response of the remote signer is not valid JSON",
+        ),
+    ] {
+        let error = block_on(remote_signer(reply.clone()).sign_message(message)).unwrap_err();
+        assert_eq!(remote_error(error), expected, "{reply}");
+    }
+
+    // A signature from another key is refused even if it is valid for the message
+    let other = Keypair::new_from_array([5u8; 32]);
+    let signer = remote_signer(json!({
+        "signature": Signer::sign_message(&other, message).to_string()
+    }));
+    assert!(block_on(signer.sign_message(message)).is_err());
+
+    // Transport failures are passed through
+    let signer = RemoteSigner::new(MockHttpTransport::new(), ENDPOINT, public_key());
+    assert!(matches!(
+        block_on(signer.sign_message(message)),
+        Err(AtollWalletError::Input(_))
+    ));
+}
+
+#[test]
+fn ledger_accounts_sign_transactions_on_the_device() {
+    let derivation_path = DerivationPath::new_bip44(Some(1), Some(0));
+    let mut path = vec![0x04];
+    [44u32, 501, 1, 0]
+        .iter()
+        .for_each(|index| path.extend_from_slice(&(index | 0x8000_0000).to_be_bytes()));
+
+    let mut get_pubkey = vec![0xE0, 0x05, 0x00, 0x00, path.len() as u8];
+    get_pubkey.extend_from_slice(&path);
+    let mut pubkey_response = public_key().to_bytes().to_vec();
+    pubkey_response.extend_from_slice(&[0x90, 0x00]);
+
+    let transaction = transfer();
+    let message_data = transaction.message_data();
+    let mut sign_data = vec![0x01];
+    sign_data.extend_from_slice(&path);
+    sign_data.extend_from_slice(&message_data);
+    let mut sign_command = vec![0xE0, 0x06, 0x01, 0x00, sign_data.len() as u8];
+    sign_command.extend_from_slice(&sign_data);
+    let mut sign_response = sign(&message_data).as_ref().to_vec();
+    sign_response.extend_from_slice(&[0x90, 0x00]);
+
+    let transport = RecordedApduTransport::new()
+        .record(&get_pubkey, &pubkey_response)
+        .record(&sign_command, &sign_response);
+
+    let account = block_on(SolanaAccountKeypair::new_ledger(transport, derivation_path)).unwrap();
+    assert_eq!(account.pubkey(), public_key());
+    assert_eq!(account.signer_kind(), SignerKind::Ledger);
+    assert!(!account.get_wallet_account().watch_only());
+
+    let signed = block_on(account.sign_transaction(&public_key().to_bytes(), transaction)).unwrap();
+    assert!(signed.verify().is_ok());
+
+    // A rejection on the device is reported as the user rejecting the request
+    let transport = RecordedApduTransport::new().record(&get_pubkey, &[0x69, 0x85]);
+    assert!(matches!(
+        block_on(SolanaAccountKeypair::new_ledger(
+            transport,
+            DerivationPath::new_bip44(Some(1), Some(0))
+        )),
+        Err(AtollWalletError::UserRejected(_))
+    ));
+}
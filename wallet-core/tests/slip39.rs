@@ -0,0 +1,304 @@
+//! SLIP-39 shares checked against the official test vectors and split and recovered
+//! with different thresholds
+
+use atoll_wallet_core::{
+    AtollWalletError, Slip39, Slip39Config, Slip39Group, Slip39Recovery, Slip39Share,
+};
+use serde_json::Value;
+
+/// The passphrase of every vector in `vectors.json`
+const VECTOR_PASSPHRASE: &str = "TREZOR";
+
+const MASTER_SECRET: [u8; 16] = *b"ABCDEFGHIJKLMNOP";
+
+/// The `[description, mnemonics, master secret]` cases of the SLIP-39 test vectors.
+/// The master secret is empty for the cases that must fail.
+fn vectors() -> Vec<(String, Vec<String>, Vec<u8>)> {
+    let vectors: Value =
+        serde_json::from_str(include_str!("fixtures/slip39/vectors.json")).unwrap();
+
+    vectors
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|vector| {
+            let mnemonics = vector[1]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|mnemonic| mnemonic.as_str().unwrap().to_string())
+                .collect();
+            let master_secret = vector[2].as_str().unwrap();
+            let master_secret = (0..master_secret.len())
+                .step_by(2)
+                .map(|index| u8::from_str_radix(&master_secret[index..index + 2], 16).unwrap())
+                .collect();
+
+            (
+                vector[0].as_str().unwrap().to_string(),
+                mnemonics,
+                master_secret,
+            )
+        })
+        .collect()
+}
+
+fn combine(mnemonics: &[String], passphrase: &str) -> Result<Vec<u8>, AtollWalletError> {
+    let mnemonics = mnemonics.iter().map(String::as_str).collect::<Vec<&str>>();
+
+    Slip39::combine(&mnemonics, passphrase).map(|secret| secret.to_vec())
+}
+
+fn split(config: &Slip39Config) -> Vec<Vec<String>> {
+    Slip39::split(&MASTER_SECRET, "", config)
+        .unwrap()
+        .into_iter()
+        .map(|group| group.iter().map(|share| share.to_string()).collect())
+        .collect()
+}
+
+/// Replaces the identifier of every share, as when shares are added to an extendable backup
+fn with_identifier(shares: &[String], identifier: u16) -> Vec<String> {
+    shares
+        .iter()
+        .map(|share| {
+            let mut share = Slip39Share::from_mnemonic(share).unwrap();
+            share.identifier = identifier;
+
+            share.to_mnemonic().to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn valid_vectors_recover_the_master_secret() {
+    let valid = vectors()
+        .into_iter()
+        .filter(|(_, _, master_secret)| !master_secret.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(valid.len(), 10);
+
+    for (description, mnemonics, master_secret) in valid {
+        assert_eq!(
+            combine(&mnemonics, VECTOR_PASSPHRASE).unwrap(),
+            master_secret,
+            "{description}"
+        );
+
+        for mnemonic in &mnemonics {
+            let share = Slip39Share::from_mnemonic(mnemonic).unwrap();
+            assert!(!share.extendable, "{description}");
+            assert_eq!(share.to_mnemonic().as_str(), mnemonic, "{description}");
+        }
+    }
+}
+
+#[test]
+fn invalid_vectors_are_refused() {
+    let invalid = vectors()
+        .into_iter()
+        .filter(|(_, _, master_secret)| master_secret.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(invalid.len(), 30);
+
+    for (description, mnemonics, _) in invalid {
+        assert!(
+            combine(&mnemonics, VECTOR_PASSPHRASE).is_err(),
+            "{description}"
+        );
+    }
+}
+
+#[test]
+fn shares_are_extendable_by_default() {
+    let shares = split(&Slip39Config::new(2, 3)).remove(0);
+
+    let share = Slip39Share::from_mnemonic(&shares[0]).unwrap();
+    assert!(share.extendable);
+    assert_eq!(share.iteration_exponent, 1);
+    assert_eq!(combine(&shares[1..], "").unwrap(), MASTER_SECRET);
+
+    let shares = split(&Slip39Config::new(2, 3).set_extendable(false)).remove(0);
+    assert!(!Slip39Share::from_mnemonic(&shares[0]).unwrap().extendable);
+    assert_eq!(combine(&shares[..2], "").unwrap(), MASTER_SECRET);
+}
+
+#[test]
+fn extendable_shares_do_not_encrypt_with_the_identifier() {
+    let shares = split(&Slip39Config::new(2, 3)).remove(0);
+    assert_eq!(
+        combine(&with_identifier(&shares[..2], 0x1234), "").unwrap(),
+        MASTER_SECRET
+    );
+
+    let shares = split(&Slip39Config::new(2, 3).set_extendable(false)).remove(0);
+    assert_ne!(
+        combine(&with_identifier(&shares[..2], 0x1234), "").unwrap(),
+        MASTER_SECRET
+    );
+}
+
+#[test]
+fn the_extendable_flag_is_covered_by_the_checksum() {
+    let share = &split(&Slip39Config::new(1, 1))[0][0];
+    let words = share.split_whitespace().collect::<Vec<&str>>();
+
+    // The flag is the fifth bit of the second word, here flipped without fixing the checksum
+    let wordlist = include_str!("../src/vault/slip39/wordlist_english.txt")
+        .lines()
+        .collect::<Vec<&str>>();
+    let index = wordlist.iter().position(|word| *word == words[1]).unwrap();
+    let mut flipped = words.clone();
+    flipped[1] = wordlist[index ^ 0x10];
+
+    assert!(matches!(
+        Slip39Share::from_mnemonic(&flipped.join(" ")),
+        Err(AtollWalletError::InvalidSlip39Share(_))
+    ));
+}
+
+#[test]
+fn iteration_exponents_must_fit_in_four_bits() {
+    assert!(matches!(
+        Slip39::split(
+            &MASTER_SECRET,
+            "",
+            &Slip39Config::new(2, 3).set_iteration_exponent(16)
+        ),
+        Err(AtollWalletError::InvalidSlip39Config(_))
+    ));
+
+    let shares = split(&Slip39Config::new(1, 1).set_iteration_exponent(0));
+    assert_eq!(combine(&shares[0], "").unwrap(), MASTER_SECRET);
+}
+
+#[test]
+fn any_threshold_of_shares_recovers_the_secret() {
+    for (threshold, count) in [(1, 1), (2, 2), (2, 3), (3, 5), (5, 5), (4, 16)] {
+        let shares =
+            split(&Slip39Config::new(threshold, count).set_iteration_exponent(0)).remove(0);
+        assert_eq!(shares.len(), count as usize);
+
+        for start in 0..=(count - threshold) as usize {
+            let subset = &shares[start..start + threshold as usize];
+            assert_eq!(
+                combine(subset, "").unwrap(),
+                MASTER_SECRET,
+                "{threshold}-of-{count}"
+            );
+        }
+
+        if threshold > 1 {
+            let err = combine(&shares[..threshold as usize - 1], "").unwrap_err();
+            assert!(
+                matches!(err, AtollWalletError::InsufficientSlip39Shares(_)),
+                "{threshold}-of-{count}"
+            );
+        }
+    }
+}
+
+#[test]
+fn groups_recover_once_the_group_threshold_is_met() {
+    let config = Slip39Config::new_with_groups(
+        2,
+        &[
+            Slip39Group::new(2, 3),
+            Slip39Group::new(3, 5),
+            Slip39Group::new(1, 1),
+        ],
+    )
+    .set_iteration_exponent(0);
+    let groups = split(&config);
+
+    let mut shares = groups[0][1..].to_vec();
+    shares.push(groups[2][0].clone());
+    assert_eq!(combine(&shares, "").unwrap(), MASTER_SECRET);
+
+    let mut shares = groups[1][2..].to_vec();
+    shares.push(groups[0][0].clone());
+    shares.push(groups[0][2].clone());
+    assert_eq!(combine(&shares, "").unwrap(), MASTER_SECRET);
+
+    assert!(matches!(
+        combine(&groups[1][..3], ""),
+        Err(AtollWalletError::InsufficientSlip39Shares(_))
+    ));
+}
+
+#[test]
+fn the_recovery_says_how_many_more_shares_are_needed() {
+    let shares = split(&Slip39Config::new(3, 5).set_iteration_exponent(0)).remove(0);
+    let mut recovery = Slip39Recovery::new();
+    assert_eq!(
+        recovery.status().describe(),
+        "No shares have been provided yet"
+    );
+
+    let status = recovery.add_mnemonic(&shares[4]).unwrap();
+    assert_eq!(status.shares_needed(), Some(2));
+    assert_eq!(status.describe(), "2 more share(s) needed");
+    assert_eq!(
+        recovery.recover("").unwrap_err().to_string(),
+        "Not enough SLIP-39 shares to recover the wallet. 2 more share(s) needed"
+    );
+
+    assert!(matches!(
+        recovery.add_mnemonic(&shares[4]),
+        Err(AtollWalletError::Slip39ShareMismatch(_))
+    ));
+
+    assert_eq!(
+        recovery.add_mnemonic(&shares[0]).unwrap().describe(),
+        "1 more share(s) needed"
+    );
+    let status = recovery.add_mnemonic(&shares[2]).unwrap();
+    assert!(status.is_complete());
+    assert_eq!(
+        status.describe(),
+        "Enough shares have been provided to recover the wallet"
+    );
+    assert_eq!(recovery.recover("").unwrap().to_vec(), MASTER_SECRET);
+}
+
+#[test]
+fn the_recovery_of_groups_says_what_is_missing() {
+    let config =
+        Slip39Config::new_with_groups(2, &[Slip39Group::new(2, 3), Slip39Group::new(3, 5)])
+            .set_iteration_exponent(0);
+    let groups = split(&config);
+    let mut recovery = Slip39Recovery::new();
+
+    let status = recovery.add_mnemonic(&groups[0][0]).unwrap();
+    assert_eq!(status.shares_needed(), None);
+    assert_eq!(
+        status.describe(),
+        "2 more group(s) needed. Shares from groups not yet provided are required"
+    );
+
+    recovery.add_mnemonic(&groups[1][0]).unwrap();
+    let status = recovery.add_mnemonic(&groups[1][1]).unwrap();
+    assert_eq!(status.groups_complete(), 0);
+    assert_eq!(
+        status.describe(),
+        "2 more share(s) needed to complete 2 more group(s)"
+    );
+
+    recovery.add_mnemonic(&groups[0][2]).unwrap();
+    let status = recovery.add_mnemonic(&groups[1][4]).unwrap();
+    assert!(status.is_complete());
+    assert_eq!(recovery.recover("").unwrap().to_vec(), MASTER_SECRET);
+}
+
+#[test]
+fn shares_of_other_backups_are_refused() {
+    let first = split(&Slip39Config::new(2, 3)).remove(0);
+    let second = split(&Slip39Config::new(2, 3)).remove(0);
+    let mut recovery = Slip39Recovery::new();
+    recovery.add_mnemonic(&first[0]).unwrap();
+
+    assert!(matches!(
+        recovery.add_mnemonic(&second[0]),
+        Err(AtollWalletError::Slip39ShareMismatch(_))
+    ));
+}
@@ -0,0 +1,477 @@
+//! SOL and SPL token transfers built without a network, and the send messages of the
+//! background against a scripted cluster
+
+mod common;
+
+use atoll_wallet_core::{
+    App, AtollWalletError, SolanaInstructions, SolanaTokenTransfer, SolanaTransferBuilder,
+    SplTokenAccount, SplTokenProgram, SplTransferFee, WalletVault,
+};
+use base64ct::{Base64, Encoding};
+use serde_json::{Value, json};
+use solana_instruction::{AccountMeta, Instruction};
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_transaction::Transaction;
+use zeroize::Zeroizing;
+
+use common::{FakeRuntime, MockRpcServer, block_on};
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+const EXTENSION_ORIGIN: &str = "chrome-extension://atoll";
+
+fn pubkey(byte: u8) -> Pubkey {
+    Pubkey::new_from_array([byte; 32])
+}
+
+fn vault() -> WalletVault {
+    WalletVault::new_from_mnemonic(Zeroizing::new(MNEMONIC.to_string()), None).unwrap()
+}
+
+/// The associated token account derived from its seeds
+fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), program.as_ref(), mint.as_ref()],
+        &Pubkey::from_str_const("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"),
+    )
+    .0
+}
+
+fn recipient_account(frozen: bool, memo_required: bool) -> SplTokenAccount {
+    SplTokenAccount {
+        mint: pubkey(5),
+        owner: pubkey(2),
+        amount: 0,
+        frozen,
+        memo_required,
+        non_transferable: false,
+    }
+}
+
+fn token(program: SplTokenProgram) -> SolanaTokenTransfer {
+    SolanaTokenTransfer {
+        mint: pubkey(5),
+        program,
+        decimals: 6,
+        ..Default::default()
+    }
+}
+
+fn input_error(error: AtollWalletError) -> String {
+    match error {
+        AtollWalletError::Input(message) => message,
+        error => panic!("expected an input error, got {error:?}"),
+    }
+}
+
+#[test]
+fn sol_transfers_are_a_single_system_transfer() {
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_500)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        plan.instructions(),
+        [solana_system_interface::instruction::transfer(
+            &pubkey(1),
+            &pubkey(2),
+            1_500
+        )]
+    );
+    assert_eq!(plan.compute_unit_limit(), None);
+    assert_eq!(plan.compute_unit_price(), 0);
+    assert_eq!(plan.transfer_fee(), 0);
+    assert_eq!(plan.received(), 1_500);
+    assert!(!plan.creates_recipient_account());
+    assert_eq!(
+        plan.network_fee(),
+        SolanaTransferBuilder::LAMPORTS_PER_SIGNATURE
+    );
+
+    let message = plan.to_transaction(solana_hash::Hash::default()).message;
+    assert_eq!(message.account_keys[0], pubkey(1));
+    assert_eq!(message.header.num_required_signatures, 1);
+
+    let error = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 0)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        input_error(error),
+        "The amount to send must be greater than zero"
+    );
+}
+
+#[test]
+fn priority_fees_prepend_the_compute_budget() {
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_500)
+        .set_priority_fee(10_000)
+        .build()
+        .unwrap();
+
+    let units = SolanaTransferBuilder::SYSTEM_TRANSFER_UNITS
+        + 2 * SolanaTransferBuilder::COMPUTE_BUDGET_UNITS;
+    assert_eq!(units, 450);
+    assert_eq!(plan.compute_unit_limit(), Some(units));
+    assert_eq!(plan.compute_unit_price(), 10_000);
+
+    let [limit, price, transfer] = plan.instructions() else {
+        panic!("expected three instructions")
+    };
+    assert_eq!(
+        limit.program_id,
+        SolanaInstructions::COMPUTE_BUDGET_PROGRAM_ID
+    );
+    assert_eq!(limit.data, [&[2u8][..], &450u32.to_le_bytes()].concat());
+    assert!(limit.accounts.is_empty());
+    assert_eq!(price.data, [&[3u8][..], &10_000u64.to_le_bytes()].concat());
+    assert_eq!(transfer.program_id, SolanaInstructions::SYSTEM_PROGRAM_ID);
+
+    // 10 000 micro-lamports for 450 units is 4.5 lamports, rounded up
+    assert_eq!(plan.network_fee(), 5_005);
+
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_500)
+        .set_priority_fee(10_000)
+        .set_compute_unit_limit(1_000)
+        .build()
+        .unwrap();
+    assert_eq!(plan.compute_unit_limit(), Some(1_000));
+    assert_eq!(plan.network_fee(), 5_010);
+
+    // A limit alone does not add a price
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_500)
+        .set_compute_unit_limit(1_000)
+        .build()
+        .unwrap();
+    assert_eq!(plan.instructions().len(), 2);
+    assert_eq!(plan.network_fee(), 5_000);
+}
+
+#[test]
+fn token_transfers_create_the_missing_recipient_account() {
+    let token_program = SplTokenProgram::TOKEN_PROGRAM_ID;
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 2_500_000)
+        .set_token(token(SplTokenProgram::Token))
+        .build()
+        .unwrap();
+
+    let source = associated_token_address(&pubkey(1), &pubkey(5), &token_program);
+    let destination = associated_token_address(&pubkey(2), &pubkey(5), &token_program);
+    assert_eq!(
+        SolanaInstructions::associated_token_address(
+            &pubkey(2),
+            &pubkey(5),
+            SplTokenProgram::Token
+        ),
+        destination
+    );
+    assert!(plan.creates_recipient_account());
+
+    let [create, transfer] = plan.instructions() else {
+        panic!("expected two instructions")
+    };
+    assert_eq!(
+        *create,
+        Instruction::new_with_bytes(
+            SolanaInstructions::ASSOCIATED_TOKEN_PROGRAM_ID,
+            &[1],
+            vec![
+                AccountMeta::new(pubkey(1), true),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(pubkey(2), false),
+                AccountMeta::new_readonly(pubkey(5), false),
+                AccountMeta::new_readonly(SolanaInstructions::SYSTEM_PROGRAM_ID, false),
+                AccountMeta::new_readonly(token_program, false),
+            ],
+        )
+    );
+
+    // `TransferChecked` with the amount and the decimals
+    let mut data = vec![12u8];
+    data.extend_from_slice(&2_500_000u64.to_le_bytes());
+    data.push(6);
+    assert_eq!(
+        *transfer,
+        Instruction::new_with_bytes(
+            token_program,
+            &data,
+            vec![
+                AccountMeta::new(source, false),
+                AccountMeta::new_readonly(pubkey(5), false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(pubkey(1), true),
+            ],
+        )
+    );
+
+    // An existing account is not created again
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 2_500_000)
+        .set_token(SolanaTokenTransfer {
+            recipient_account: Some(recipient_account(false, false)),
+            ..token(SplTokenProgram::Token2022)
+        })
+        .build()
+        .unwrap();
+    assert!(!plan.creates_recipient_account());
+    assert_eq!(plan.instructions().len(), 1);
+    assert_eq!(
+        plan.instructions()[0].program_id,
+        SplTokenProgram::TOKEN_2022_PROGRAM_ID
+    );
+}
+
+#[test]
+fn token_2022_transfers_carry_the_fee_and_the_required_memo() {
+    let transfer = SolanaTokenTransfer {
+        transfer_fee: Some(SplTransferFee {
+            epoch: 0,
+            maximum_fee: 3_000,
+            basis_points: 250,
+        }),
+        recipient_account: Some(recipient_account(false, true)),
+        ..token(SplTokenProgram::Token2022)
+    };
+
+    let error = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 100_000)
+        .set_token(transfer.clone())
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        input_error(error),
+        format!("`{}` requires a memo for incoming transfers", pubkey(2))
+    );
+
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 100_000)
+        .set_token(transfer)
+        .set_memo("invoice 42")
+        .set_priority_fee(1)
+        .build()
+        .unwrap();
+    assert_eq!(plan.transfer_fee(), 2_500);
+    assert_eq!(plan.received(), 97_500);
+    assert_eq!(
+        plan.compute_unit_limit(),
+        Some(
+            SolanaTransferBuilder::MEMO_UNITS
+                + SolanaTransferBuilder::TRANSFER_CHECKED_UNITS
+                + 2 * SolanaTransferBuilder::COMPUTE_BUDGET_UNITS
+        )
+    );
+
+    // The memo is signed by the sender right before the transfer
+    let [_, _, memo, transfer] = plan.instructions() else {
+        panic!("expected four instructions")
+    };
+    assert_eq!(memo.program_id, SolanaInstructions::MEMO_PROGRAM_ID);
+    assert_eq!(memo.data, b"invoice 42");
+    assert_eq!(memo.accounts, [AccountMeta::new_readonly(pubkey(1), true)]);
+
+    // `TransferCheckedWithFee` of the transfer fee extension
+    let mut data = vec![26u8, 1];
+    data.extend_from_slice(&100_000u64.to_le_bytes());
+    data.push(6);
+    data.extend_from_slice(&2_500u64.to_le_bytes());
+    assert_eq!(transfer.program_id, SplTokenProgram::TOKEN_2022_PROGRAM_ID);
+    assert_eq!(transfer.data, data);
+    assert_eq!(transfer.accounts.len(), 4);
+}
+
+#[test]
+fn untransferable_tokens_are_refused() {
+    let error = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1)
+        .set_token(SolanaTokenTransfer {
+            non_transferable: true,
+            ..token(SplTokenProgram::Token2022)
+        })
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        input_error(error),
+        format!("The mint `{}` is non-transferable", pubkey(5))
+    );
+
+    let error = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1)
+        .set_token(SolanaTokenTransfer {
+            recipient_account: Some(recipient_account(true, false)),
+            ..token(SplTokenProgram::Token)
+        })
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        input_error(error),
+        format!(
+            "The token account of `{}` for `{}` is frozen",
+            pubkey(2),
+            pubkey(5)
+        )
+    );
+}
+
+#[test]
+fn plans_are_signed_by_the_sender() {
+    let keypair = vault().solana_keypair().unwrap();
+    let blockhash = solana_hash::Hash::new_from_array([7u8; 32]);
+
+    let plan = SolanaTransferBuilder::new(keypair.pubkey(), pubkey(2), 1_500)
+        .set_priority_fee(100)
+        .build()
+        .unwrap();
+    let transaction = block_on(plan.sign(&keypair, blockhash)).unwrap();
+
+    assert!(transaction.verify().is_ok());
+    assert_eq!(transaction.message.recent_blockhash, blockhash);
+    assert_eq!(transaction.message, plan.to_transaction(blockhash).message);
+
+    // Another account cannot sign for the sender
+    let plan = SolanaTransferBuilder::new(pubkey(1), pubkey(2), 1_500)
+        .build()
+        .unwrap();
+    assert!(block_on(plan.sign(&keypair, blockhash)).is_err());
+}
+
+fn rpc_account(owner: &Pubkey, data: &[u8]) -> Value {
+    json!({
+        "lamports": 1_461_600,
+        "owner": owner.to_string(),
+        "data": [Base64::encode_string(data), "base64"],
+        "executable": false,
+        "rentEpoch": 0,
+        "space": data.len(),
+    })
+}
+
+fn context(value: Value) -> Value {
+    json!({ "context": { "slot": 1 }, "value": value })
+}
+
+/// A Token-2022 mint with 6 decimals and a transfer fee of 1% up to 50 units from the
+/// epoch 100
+fn mint_with_transfer_fee() -> Vec<u8> {
+    let mut data = vec![0u8; SplTokenAccount::LEN];
+    data[44] = 6;
+    data[45] = 1;
+    data.push(1);
+
+    let mut config = vec![0u8; 72];
+    for (epoch, maximum_fee, basis_points) in [(0u64, 0u64, 0u16), (100, 50, 100)] {
+        config.extend_from_slice(&epoch.to_le_bytes());
+        config.extend_from_slice(&maximum_fee.to_le_bytes());
+        config.extend_from_slice(&basis_points.to_le_bytes());
+    }
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&(config.len() as u16).to_le_bytes());
+    data.extend(config);
+
+    data
+}
+
+fn send_background(server: &MockRpcServer) -> FakeRuntime {
+    let app = App::new(server.transport())
+        .set_extension_origin(EXTENSION_ORIGIN)
+        .set_vault(vault())
+        .unwrap();
+    let runtime = FakeRuntime::new();
+    common::listen(app, &runtime);
+
+    runtime
+}
+
+#[test]
+fn the_send_messages_fetch_the_mint_and_the_recipient_account() {
+    let mint = pubkey(5);
+    let recipient = pubkey(2);
+    let signature = Signature::from([3u8; 64]);
+    let blockhash = solana_hash::Hash::new_from_array([7u8; 32]);
+
+    let server = MockRpcServer::start();
+    server
+        .script_cluster(&blockhash, 4_000, &[], &signature.to_string())
+        .reply("getEpochInfo", json!({ "epoch": 120, "slotIndex": 0 }))
+        .reply_matching(
+            "getMultipleAccounts",
+            &mint.to_string(),
+            context(json!([rpc_account(
+                &SplTokenProgram::TOKEN_2022_PROGRAM_ID,
+                &mint_with_transfer_fee()
+            )])),
+        )
+        // The recipient has no token account yet
+        .reply("getMultipleAccounts", context(json!([null])));
+    let runtime = send_background(&server);
+
+    let params = json!({
+        "recipient": recipient.to_string(),
+        "amount": "1000000",
+        "mint": mint.to_string(),
+        "priorityFee": 1_000,
+    });
+
+    let response = runtime.request(
+        "estimate",
+        "atoll:solanaEstimateSend",
+        EXTENSION_ORIGIN,
+        params.clone(),
+    );
+    let estimate = &response["ok"];
+    assert_eq!(estimate["transferFee"], json!("50"), "{response}");
+    assert_eq!(estimate["received"], json!("999950"));
+    assert_eq!(estimate["createsRecipientAccount"], json!(true));
+    assert_eq!(estimate["priorityFee"].as_f64(), Some(1_000.0));
+    assert_eq!(estimate["computeUnitLimit"].as_f64(), Some(50_300.0));
+    assert_eq!(estimate["networkFee"].as_f64(), Some(5_051.0));
+
+    let response = runtime.request("send", "atoll:solanaSend", EXTENSION_ORIGIN, params);
+    assert_eq!(
+        response["ok"]["signature"],
+        json!(signature.to_string()),
+        "{response}"
+    );
+
+    let sent = server.last_params("sendTransaction").unwrap();
+    let bytes = Base64::decode_vec(sent[0].as_str().unwrap()).unwrap();
+    let transaction: Transaction = bincode::deserialize(&bytes).unwrap();
+    assert!(transaction.verify().is_ok());
+    assert_eq!(transaction.message.recent_blockhash, blockhash);
+    // The compute budget, the account creation and the transfer with its fee
+    assert_eq!(transaction.message.instructions.len(), 4);
+}
+
+#[test]
+fn the_send_messages_refuse_invalid_requests() {
+    let server = MockRpcServer::start();
+    server.reply("getMultipleAccounts", context(json!([null])));
+    let runtime = send_background(&server);
+
+    let error = |params: Value| {
+        let response = runtime.request(
+            "estimate",
+            "atoll:solanaEstimateSend",
+            EXTENSION_ORIGIN,
+            params,
+        );
+        assert!(response["ok"].is_null(), "{response}");
+
+        response["err"]["message"].as_str().unwrap().to_string()
+    };
+
+    assert!(
+        error(json!({ "recipient": "not an address", "amount": 1 }))
+            .contains("`not an address` is not a valid Solana address")
+    );
+    assert!(
+        error(json!({ "recipient": pubkey(2).to_string(), "amount": 0 }))
+            .contains("The amount to send must be greater than zero")
+    );
+    assert!(
+        error(json!({
+            "recipient": pubkey(2).to_string(),
+            "amount": 1,
+            "mint": pubkey(5).to_string(),
+        }))
+        .contains(&format!("The mint `{}` does not exist", pubkey(5)))
+    );
+
+    // Nothing is sent for a rejected transfer
+    assert!(!server.methods().contains(&"sendTransaction".to_string()));
+}
@@ -0,0 +1,289 @@
+//! `sol-sign-request` and `sol-signature` as offline signers such as Keystone exchange them,
+//! checked against CBOR and UR encodings derived by hand from the registry specification
+
+mod common;
+
+use atoll_wallet_core::{
+    AirGappedSignRequest, AirGappedSignType, AirGappedSigner, AtollWalletError, SignatureRelay,
+    SignerFuture, SolSignRequest, SolSignature, UrDecoder, UrRegistry, WalletSigner,
+};
+use solana_derivation_path::DerivationPath;
+use solana_keypair::Keypair;
+use solana_pubkey::Pubkey;
+use solana_signature::Signature;
+use solana_signer::Signer;
+
+use common::block_on;
+
+/// `9b1deb4d-3b7d-4bad-9bdd-2b0d7b3dcb6d`
+const REQUEST_ID: [u8; 16] = [
+    0x9b, 0x1d, 0xeb, 0x4d, 0x3b, 0x7d, 0x4b, 0xad, 0x9b, 0xdd, 0x2b, 0x0d, 0x7b, 0x3d, 0xcb, 0x6d,
+];
+
+/// The request of [request] in CBOR:
+/// `{1: 37(uuid), 2: h'68656c6c6f', 3: 304({1: [44, true, 501, true, 0, true, 0, true],
+/// 2: 0x12121212}), 4: h'0101..01', 5: "atoll", 6: 2}`
+const SIGN_REQUEST_CBOR: &str = "a6 01 d825 50 9b1deb4d3b7d4bad9bdd2b0d7b3dcb6d 02 45 68656c6c6f \
+     03 d90130 a2 01 88 182c f5 1901f5 f5 00 f5 00 f5 02 1a 12121212 \
+     04 5820 0101010101010101010101010101010101010101010101010101010101010101 \
+     05 65 61746f6c6c 06 02";
+
+const SIGN_REQUEST_UR: &str = "ur:sol-sign-request/oladtpdagdndcawmgtfrkigrpmndutdnbtkgfssbjnaofeisihjzjzjlaxtaaddyoeadlocsdwykcfadykykaeykaeykaocybgbgbgbgaahdcxadadadadadadadadadadadadadadadadadadadadadadadadadadadadadadadadahihhsjyjljzjzamaogawsweme";
+
+/// `{1: 37(uuid), 2: h'abab..ab'}`
+const SIGNATURE_CBOR: &str = "a2 01 d825 50 9b1deb4d3b7d4bad9bdd2b0d7b3dcb6d 02 5840 \
+     abababababababababababababababababababababababababababababababab\
+     abababababababababababababababababababababababababababababababab";
+
+const SIGNATURE_UR: &str = "ur:sol-signature/oeadtpdagdndcawmgtfrkigrpmndutdnbtkgfssbjnaohdfzpypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypypylrkewzsp";
+
+fn hex(text: &str) -> Vec<u8> {
+    let digits = text.split_whitespace().collect::<String>();
+
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).unwrap())
+        .collect()
+}
+
+fn derivation_path() -> DerivationPath {
+    DerivationPath::new_bip44(Some(0), Some(0))
+}
+
+fn request() -> SolSignRequest {
+    SolSignRequest::new_with_request_id(
+        &AirGappedSignRequest {
+            sign_data: b"hello".to_vec(),
+            derivation_path: derivation_path(),
+            public_key: Pubkey::new_from_array([1u8; 32]),
+            sign_type: AirGappedSignType::Message,
+        },
+        REQUEST_ID,
+    )
+    .set_master_fingerprint(0x1212_1212)
+    .set_origin("atoll")
+}
+
+#[test]
+fn sol_sign_request_matches_the_known_encoding() {
+    let request = request();
+
+    assert_eq!(request.to_cbor().unwrap(), hex(SIGN_REQUEST_CBOR));
+    assert_eq!(request.to_ur().unwrap(), SIGN_REQUEST_UR);
+
+    let decoded = SolSignRequest::from_cbor(&hex(SIGN_REQUEST_CBOR)).unwrap();
+    assert_eq!(decoded, request);
+    assert_eq!(decoded.request_id(), REQUEST_ID);
+    assert_eq!(decoded.sign_data(), b"hello");
+    assert_eq!(decoded.derivation_path(), &derivation_path());
+    assert_eq!(decoded.master_fingerprint(), Some(0x1212_1212));
+    assert_eq!(decoded.address(), Some(Pubkey::new_from_array([1u8; 32])));
+    assert_eq!(decoded.origin(), Some("atoll"));
+    assert_eq!(decoded.sign_type(), AirGappedSignType::Message);
+
+    let mut decoder = UrDecoder::default();
+    decoder.receive(SIGN_REQUEST_UR).unwrap();
+    assert!(decoder.complete());
+    assert_eq!(
+        decoder.message().unwrap(),
+        Some((
+            UrRegistry::SOL_SIGN_REQUEST.to_string(),
+            hex(SIGN_REQUEST_CBOR)
+        ))
+    );
+}
+
+#[test]
+fn sol_signature_matches_the_known_encoding() {
+    let signature = SolSignature {
+        request_id: REQUEST_ID,
+        signature: Signature::from([0xab; 64]),
+    };
+
+    assert_eq!(signature.to_cbor().unwrap(), hex(SIGNATURE_CBOR));
+    assert_eq!(signature.to_ur().unwrap(), SIGNATURE_UR);
+    assert_eq!(
+        SolSignature::from_cbor(&hex(SIGNATURE_CBOR)).unwrap(),
+        signature
+    );
+    assert_eq!(
+        SolSignature::from_ur_parts(&[SIGNATURE_UR]).unwrap(),
+        signature
+    );
+
+    // QR codes carry URs in upper case to use the alphanumeric mode
+    assert_eq!(
+        SolSignature::from_ur_parts(&[&SIGNATURE_UR.to_ascii_uppercase()]).unwrap(),
+        signature
+    );
+}
+
+#[test]
+fn requests_round_trip_without_the_optional_fields() {
+    let request = SolSignRequest::new(&AirGappedSignRequest {
+        sign_data: vec![7u8; 300],
+        derivation_path: DerivationPath::new_bip44(Some(3), None),
+        public_key: Pubkey::new_from_array([2u8; 32]),
+        sign_type: AirGappedSignType::Transaction,
+    })
+    .unwrap();
+
+    // A random version 4 UUID
+    assert_eq!(request.request_id()[6] >> 4, 4);
+    assert_eq!(request.request_id()[8] >> 6, 0b10);
+    assert_eq!(request.master_fingerprint(), None);
+    assert_eq!(request.origin(), None);
+
+    assert_eq!(
+        SolSignRequest::from_cbor(&request.to_cbor().unwrap()).unwrap(),
+        request
+    );
+}
+
+#[test]
+fn invalid_cbor_is_refused() {
+    let mut cbor = hex(SIGN_REQUEST_CBOR);
+
+    // A sign type other than transaction or message
+    let last = cbor.len() - 1;
+    cbor[last] = 0x03;
+    assert!(SolSignRequest::from_cbor(&cbor).is_err());
+
+    // A keypath with an index that is not hardened
+    let non_hardened = hex(&SIGN_REQUEST_CBOR.replacen("1901f5 f5", "1901f5 f4", 1));
+    assert!(SolSignRequest::from_cbor(&non_hardened).is_err());
+
+    // The UUID under another tag
+    let wrong_tag = hex(&SIGNATURE_CBOR.replacen("d825", "d826", 1));
+    assert!(SolSignature::from_cbor(&wrong_tag).is_err());
+
+    // A signature of the wrong length
+    let short = hex("a2 01 d825 50 9b1deb4d3b7d4bad9bdd2b0d7b3dcb6d 02 43 ababab");
+    assert!(SolSignature::from_cbor(&short).is_err());
+
+    // A request without its sign data
+    let missing = hex("a1 01 d825 50 9b1deb4d3b7d4bad9bdd2b0d7b3dcb6d");
+    assert!(SolSignRequest::from_cbor(&missing).is_err());
+
+    assert!(SolSignature::from_cbor(&[]).is_err());
+}
+
+#[test]
+fn animated_requests_survive_missed_frames() {
+    let animated = SolSignRequest::new_with_request_id(
+        &AirGappedSignRequest {
+            sign_data: (0..1_000).map(|index| index as u8).collect(),
+            derivation_path: derivation_path(),
+            public_key: Pubkey::new_from_array([1u8; 32]),
+            sign_type: AirGappedSignType::Transaction,
+        },
+        REQUEST_ID,
+    );
+    let cbor = animated.to_cbor().unwrap();
+
+    let parts = animated.to_ur_parts(100).unwrap();
+    let fragment_count = cbor.len().div_ceil(100);
+    assert_eq!(parts.len(), fragment_count * 2);
+    assert!(parts[0].starts_with(&format!("ur:sol-sign-request/1-{fragment_count}/")));
+
+    // All the fragments in order
+    let mut decoder = UrDecoder::default();
+    for part in &parts[..fragment_count] {
+        assert!(!decoder.complete());
+        decoder.receive(part).unwrap();
+    }
+    assert!(decoder.complete());
+    assert_eq!(
+        decoder.message().unwrap(),
+        Some((UrRegistry::SOL_SIGN_REQUEST.to_string(), cbor.clone()))
+    );
+
+    // The first frame is missed and the fountain coded parts make up for it
+    let mut decoder = UrDecoder::default();
+    for part in &parts[1..] {
+        decoder.receive(part).unwrap();
+    }
+    assert!(decoder.complete());
+    assert_eq!(decoder.message().unwrap().unwrap().1, cbor);
+
+    // Short requests fit in a single part
+    let parts = request().to_ur_parts(200).unwrap();
+    assert_eq!(parts.len(), 1);
+    assert!(parts[0].starts_with("ur:sol-sign-request/1-1/"));
+
+    let mut decoder = UrDecoder::default();
+    decoder.receive(&parts[0]).unwrap();
+    assert_eq!(
+        decoder.message().unwrap().unwrap().1,
+        hex(SIGN_REQUEST_CBOR)
+    );
+}
+
+#[test]
+fn signatures_are_only_decoded_once_complete_and_of_the_right_type() {
+    let error = SolSignature::from_ur_parts(&[SIGN_REQUEST_UR]).unwrap_err();
+    assert!(error.to_string().contains("Expected a `ur:sol-signature`"));
+
+    let parts = request()
+        .to_ur_parts(20)
+        .unwrap()
+        .into_iter()
+        .take(1)
+        .collect::<Vec<String>>();
+    let parts = parts.iter().map(String::as_str).collect::<Vec<&str>>();
+    assert!(matches!(
+        SolSignature::from_ur_parts(&parts),
+        Err(AtollWalletError::AirGapped(message)) if message.contains("Not enough QR code parts")
+    ));
+
+    assert!(SolSignature::from_ur_parts(&["not a ur"]).is_err());
+}
+
+/// Relays requests to an offline device as the UR it scans and returns the UR it shows
+struct QrRelay {
+    device: Keypair,
+}
+
+impl SignatureRelay for QrRelay {
+    fn request_signature<'a>(&'a self, request: AirGappedSignRequest) -> SignerFuture<'a> {
+        Box::pin(async move {
+            let scanned = SolSignRequest::new(&request)?.to_ur()?;
+
+            // The device
+            let mut decoder = UrDecoder::default();
+            decoder.receive(&scanned)?;
+            let (_, cbor) = decoder.message()?.unwrap();
+            let request = SolSignRequest::from_cbor(&cbor)?;
+            let shown = SolSignature {
+                request_id: request.request_id(),
+                signature: Signer::sign_message(&self.device, request.sign_data()),
+            }
+            .to_ur()?;
+
+            Ok(SolSignature::from_ur_parts(&[&shown])?.signature)
+        })
+    }
+}
+
+#[test]
+fn the_air_gapped_signer_checks_the_returned_signature() {
+    let device = Keypair::new_from_array([6u8; 32]);
+    let public_key = Signer::pubkey(&device);
+
+    let signer = AirGappedSigner::new(QrRelay { device }, derivation_path(), public_key);
+    let signature = block_on(signer.sign_message(b"offline")).unwrap();
+    assert!(signature.verify(public_key.as_ref(), b"offline"));
+
+    // A device holding another key
+    let signer = AirGappedSigner::new(
+        QrRelay {
+            device: Keypair::new_from_array([7u8; 32]),
+        },
+        derivation_path(),
+        public_key,
+    );
+    assert!(matches!(
+        block_on(signer.sign_message(b"offline")),
+        Err(AtollWalletError::AirGapped(_))
+    ));
+}
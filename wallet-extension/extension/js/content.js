@@ -7,37 +7,14 @@
 
   const walletInfo = wasm.get_injected_wallet_info();
 
-  const STANDARD_CONNECT = "standard:connect";
-  const RELAY_STANDARD_CONNECT = "relay:standard:connect";
+  // The protocol version and method names are generated from the Rust types
+  const protocol = wasm.get_protocol_info();
+  const { methods } = protocol;
 
-  const RELAY_SOLANA_SIGN_IN = "relay:solana:signIn";
-  const SOLANA_SIGN_IN = "solana:signIn";
+  function injectPageWallet(walletInfo, protocol) {
+    const { methods } = protocol;
 
-  const RELAY_SIGN_MESSAGE = "relay:solana:signMessage";
-  const SOLANA_SIGN_MESSAGE = "solana:signMessage";
-
-  const RELAY_SIGN_TRANSACTION = "relay:solana:signTransaction";
-  const SOLANA_SIGN_TRANSACTION = "solana:signTransaction";
-
-  const RELAY_SIGN_AND_SEND_TRANSACTION = "relay:solana:signAndSendTransaction";
-  const SOLANA_SIGN_AND_SEND_TRANSACTION = "solana:signAndSendTransaction";
-
-  function injectPageWallet(walletInfo) {
-    const STANDARD_CONNECT = "standard:connect";
-    const RELAY_STANDARD_CONNECT = "relay:standard:connect";
-
-    const RELAY_SOLANA_SIGN_IN = "relay:solana:signIn";
-    const SOLANA_SIGN_IN = "solana:signIn";
-
-    const RELAY_SIGN_MESSAGE = "relay:solana:signMessage";
-    const SOLANA_SIGN_MESSAGE = "solana:signMessage";
-
-    const RELAY_SIGN_TRANSACTION = "relay:solana:signTransaction";
-    const SOLANA_SIGN_TRANSACTION = "solana:signTransaction";
-
-    const RELAY_SIGN_AND_SEND_TRANSACTION =
-      "relay:solana:signAndSendTransaction";
-    const SOLANA_SIGN_AND_SEND_TRANSACTION = "solana:signAndSendTransaction";
+    const BITCOIN_NAMESPACE = "bitcoin:";
 
     const WALLET_REGISTER_EVENT = "wallet-standard:register-wallet";
     const APP_READY_EVENT = "wallet-standard:app-ready";
@@ -52,6 +29,16 @@
       #account = null;
       #atollWallet;
       #chains = walletInfo.chains;
+      #bitcoinFeatures = Object.fromEntries(
+        walletInfo.bitcoinFeatures.map((feature) => [
+          feature,
+          {
+            version: "1.0.0",
+            [feature.slice(BITCOIN_NAMESPACE.length)]: async (...inputs) =>
+              sendRequest({ method: feature, params: inputs[0] }),
+          },
+        ])
+      );
 
       get version() {
         return this.#version;
@@ -89,6 +76,7 @@
             version: "1.0.0",
             signMessage: this.#signMessage,
           },
+          ...this.#bitcoinFeatures,
           [AtollWalletNamespace]: { ghost: this.#atollWallet },
         };
       }
@@ -146,15 +134,13 @@
 
       #connect = async ({ silent } = {}) => {
         if (!this.#account) {
-          await this.#atollWallet.connect(
-            silent ? { onlyIfTrusted: true } : undefined
-          );
-          const result = await sendRequest({
-            requestType: STANDARD_CONNECT,
-            relayType: RELAY_STANDARD_CONNECT,
+          // A silent request from a site that never connected gets no accounts
+          const { accounts } = await sendRequest({
+            method: methods.standardConnect,
+            params: { silent: Boolean(silent), icon: pageIcon() },
           });
 
-          this.#account = result;
+          this.#account = accounts[0] ?? null;
         }
         this.#connected();
 
@@ -166,60 +152,74 @@
       };
       #signAndSendTransaction = async (...inputs) => {
         const result = await sendRequest({
-          requestType: SOLANA_SIGN_AND_SEND_TRANSACTION,
-          relayType: RELAY_SIGN_AND_SEND_TRANSACTION,
-          requestData: inputs[0],
+          method: methods.solanaSignAndSendTransaction,
+          params: inputs[0],
         });
         return result;
       };
       #signTransaction = async (...inputs) => {
         const result = await sendRequest({
-          requestType: SOLANA_SIGN_TRANSACTION,
-          relayType: RELAY_SIGN_TRANSACTION,
-          requestData: inputs[0],
+          method: methods.solanaSignTransaction,
+          params: inputs[0],
         });
 
         return result;
       };
       #signMessage = async (...inputs) => {
         const result = await sendRequest({
-          requestType: SOLANA_SIGN_MESSAGE,
-          relayType: RELAY_SIGN_MESSAGE,
-          requestData: inputs[0],
+          method: methods.solanaSignMessage,
+          params: inputs[0],
         });
 
         return result;
       };
       #signIn = async (...inputs) => {
         const result = await sendRequest({
-          requestType: SOLANA_SIGN_IN,
-          relayType: RELAY_SOLANA_SIGN_IN,
-          requestData: inputs[0],
+          method: methods.solanaSignIn,
+          params: inputs[0],
         });
 
         return result;
       };
     }
 
-    function sendRequest({ requestType, relayType, requestData }) {
+    // Carries the `code` of the error so dapps can tell a rejection from a failure
+    class WalletError extends Error {
+      constructor({ code, message, data }) {
+        super(message);
+        this.name = "WalletError";
+        this.code = code;
+        this.data = data;
+      }
+    }
+
+    // The icon of the page, kept with the connected site
+    function pageIcon() {
+      return document.querySelector('link[rel~="icon"]')?.href;
+    }
+
+    function sendRequest({ method, params }) {
+      const id = crypto.randomUUID();
+
       return new Promise((resolve, reject) => {
         const listener = (event) => {
           if (event.source !== window) return;
-          if (event.data.type === relayType) {
-            window.removeEventListener("message", listener);
+          if (event.data.type !== `${protocol.relayPrefix}${method}`) return;
+          if (event.data.id !== id) return;
 
-            if (event.data.failure) {
-              reject(new Error(event.data.failure));
-            } else {
-              resolve(event.data.success);
-            }
+          window.removeEventListener("message", listener);
+
+          if (event.data.err) {
+            reject(new WalletError(event.data.err));
+          } else {
+            resolve(event.data.ok);
           }
         };
 
         window.addEventListener("message", listener);
 
         // Fire request → content.js
-        window.postMessage({ type: requestType, requestData }, "*");
+        window.postMessage({ type: method, id, params }, "*");
       });
     }
 
@@ -242,60 +242,63 @@
   const script = document.createElement("script");
   script.textContent = `(${injectPageWallet.toString()})(${JSON.stringify(
     walletInfo
-  )});`;
+  )}, ${JSON.stringify(protocol)});`;
   (document.head || document.documentElement).appendChild(script);
   script.remove();
 
   // Content script side
   const extension = typeof browser !== "undefined" ? browser : chrome;
 
-  setupRelayListener({
-    requestType: STANDARD_CONNECT,
-    relayType: RELAY_STANDARD_CONNECT,
-    getData: () => window.location.origin,
-  });
-
-  setupRelayListener({
-    requestType: SOLANA_SIGN_IN,
-    relayType: RELAY_SOLANA_SIGN_IN,
-    getData: (event) => event.data,
-  });
-
-  setupRelayListener({
-    requestType: SOLANA_SIGN_MESSAGE,
-    relayType: RELAY_SIGN_MESSAGE,
-    getData: (event) => event.data,
-  });
-
-  setupRelayListener({
-    requestType: SOLANA_SIGN_TRANSACTION,
-    relayType: RELAY_SIGN_TRANSACTION,
-    getData: (event) => event.data,
-  });
-
-  setupRelayListener({
-    requestType: SOLANA_SIGN_AND_SEND_TRANSACTION,
-    relayType: RELAY_SIGN_AND_SEND_TRANSACTION,
-    getData: (event) => event.data,
-  });
-
-  function setupRelayListener({ requestType, relayType, getData }) {
+  // The methods a page can request, the others are only sent by the extension pages
+  [
+    methods.standardConnect,
+    methods.solanaSignIn,
+    methods.solanaSignMessage,
+    methods.solanaSignTransaction,
+    methods.solanaSignAndSendTransaction,
+    ...walletInfo.bitcoinFeatures,
+  ].forEach(setupRelayListener);
+
+  function setupRelayListener(method) {
+    const relayType = `${protocol.relayPrefix}${method}`;
+
     window.addEventListener("message", (event) => {
       if (event.source !== window) return;
-      if (event.data.type !== requestType) return;
-
-      extension.runtime.sendMessage(
-        { resource: requestType, data: getData(event) },
-        (responsePromise) => {
-          Promise.resolve(responsePromise)
-            .then((success) => {
-              window.postMessage({ type: relayType, success }, "*");
-            })
-            .catch((failure) => {
-              window.postMessage({ type: relayType, failure }, "*");
-            });
-        }
-      );
+      if (event.data.type !== method) return;
+
+      const { id, params } = event.data;
+      const request = {
+        version: protocol.version,
+        id,
+        method,
+        origin: window.location.origin,
+        params,
+      };
+
+      extension.runtime.sendMessage(request, (responsePromise) => {
+        Promise.resolve(responsePromise)
+          .then((response) => {
+            if (response.err) {
+              window.postMessage({ type: relayType, id, err: response.err }, "*");
+            } else {
+              window.postMessage({ type: relayType, id, ok: response.ok }, "*");
+            }
+          })
+          .catch((failure) => {
+            window.postMessage(
+              {
+                type: relayType,
+                id,
+                err: {
+                  code: protocol.errorCodes.internal,
+                  message: String(failure),
+                  data: { category: "internal" },
+                },
+              },
+              "*"
+            );
+          });
+      });
     });
   }
 })();
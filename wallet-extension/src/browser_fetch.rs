@@ -2,7 +2,60 @@ use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Headers, RequestInit};
 
-use crate::{AtollWalletError, AtollWalletResult};
+use crate::{AtollWalletError, AtollWalletResult, HttpFuture, HttpTransport};
+
+/// [HttpTransport] backed by the browser fetch API
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct BrowserHttpTransport;
+
+impl BrowserHttpTransport {
+    async fn text(fetch: BrowserFetch, url: &str) -> AtollWalletResult<String> {
+        let text = fetch.send(url).await?.text().map_err(|error| {
+            AtollWalletError::JsCast(format!(
+                "Unable to get the text from response body: Error: {error:?}"
+            ))
+        })?;
+
+        JsFuture::from(text)
+            .await
+            .or(Err(AtollWalletError::JsCast(
+                "Unable to read the response body of the browser fetch API".to_string(),
+            )))?
+            .as_string()
+            .ok_or(AtollWalletError::JsCast(
+                "The response body of the browser fetch API is not a String".to_string(),
+            ))
+    }
+}
+
+impl HttpTransport for BrowserHttpTransport {
+    fn post_json<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a> {
+        Box::pin(async move {
+            let mut fetch = BrowserFetch::new()?;
+            fetch.set_body(&body);
+
+            Self::text(fetch, url).await
+        })
+    }
+
+    fn post_text<'a>(&'a self, url: &'a str, body: String) -> HttpFuture<'a> {
+        Box::pin(async move {
+            let mut fetch = BrowserFetch::new()?;
+            fetch.set_content_type("text/plain")?.set_body(&body);
+
+            Self::text(fetch, url).await
+        })
+    }
+
+    fn get<'a>(&'a self, url: &'a str) -> HttpFuture<'a> {
+        Box::pin(async move {
+            let mut fetch = BrowserFetch::new()?;
+            fetch.set_method("GET");
+
+            Self::text(fetch, url).await
+        })
+    }
+}
 
 pub struct BrowserFetch {
     headers: Headers,
@@ -34,6 +87,22 @@ impl BrowserFetch {
         Ok(Self { headers, options })
     }
 
+    pub fn set_method(&mut self, method: &str) -> &mut Self {
+        self.options.set_method(method);
+
+        self
+    }
+
+    pub fn set_content_type(&mut self, content_type: &str) -> AtollWalletResult<&mut Self> {
+        self.headers
+            .set("content-type", content_type)
+            .or(Err(AtollWalletError::Input(format!(
+                "Unable to set `Content-Type: {content_type}` header for browser fetch API"
+            ))))?;
+
+        Ok(self)
+    }
+
     pub fn set_body(&mut self, json_body: &str) -> &mut Self {
         self.options.set_body(&json_body.into());
 
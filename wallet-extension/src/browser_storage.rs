@@ -0,0 +1,69 @@
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::{Function, Promise};
+
+use crate::{AtollWalletError, AtollWalletResult, Reflection, StorageFuture, WalletStorage};
+
+/// [WalletStorage] backed by `extension.storage.local`
+#[derive(Debug, Clone)]
+pub struct BrowserStorage {
+    local: JsValue,
+}
+
+impl BrowserStorage {
+    /// Reads `storage.local` from the `extension` object
+    pub fn new(extension: &JsValue) -> AtollWalletResult<Self> {
+        let local = Reflection::new(extension.clone())
+            .get_object_or_undefined("storage")
+            .filter(|storage| storage.is_object())
+            .and_then(|storage| Reflection::new(storage).get_object_or_undefined("local"))
+            .filter(|local| local.is_object())
+            .ok_or(AtollWalletError::ExtensionStorageIsMissing)?;
+
+        Ok(Self { local })
+    }
+
+    /// Calls the method `name` of `storage.local` with `argument` and awaits the promise
+    async fn call(&self, name: &str, argument: &JsValue) -> AtollWalletResult<JsValue> {
+        let promise = Reflection::new(self.local.clone())
+            .get_object_or_undefined(name)
+            .and_then(|method| method.dyn_into::<Function>().ok())
+            .ok_or(AtollWalletError::JsCast(format!(
+                "`extension.storage.local.{name}` is not a function"
+            )))?
+            .call1(&self.local, argument)
+            .and_then(|promise| promise.dyn_into::<Promise>())
+            .map_err(|error| {
+                AtollWalletError::JsCast(format!(
+                    "Unable to call `extension.storage.local.{name}`. Error: `{error:?}`"
+                ))
+            })?;
+
+        JsFuture::from(promise).await.map_err(|error| {
+            AtollWalletError::JsCast(format!(
+                "`extension.storage.local.{name}` failed. Error: `{error:?}`"
+            ))
+        })
+    }
+}
+
+impl WalletStorage for BrowserStorage {
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let items = self.call("get", &key.into()).await?;
+
+            Ok(Reflection::new(items).reflect_string_or_undefined(key))
+        })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: String) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let items = Reflection::new_object();
+            items.set_object_secure(key, &value.into())?;
+
+            self.call("set", items.peek()).await?;
+
+            Ok(())
+        })
+    }
+}
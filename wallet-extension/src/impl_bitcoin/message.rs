@@ -0,0 +1,16 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{BitcoinMessage, ProtocolError, to_js_error};
+
+/// Verifies a BIP322 "simple" or legacy signature of `message` by the Bitcoin `address`.
+/// Throws when the address cannot be parsed or its type is not supported and returns
+/// `false` for any signature that does not verify.
+#[wasm_bindgen]
+pub fn verify_bitcoin_message(
+    address: &str,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<bool, JsValue> {
+    BitcoinMessage::verify(address, message, signature)
+        .map_err(|error| to_js_error(&ProtocolError::from(&error)))
+}
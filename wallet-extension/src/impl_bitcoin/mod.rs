@@ -1,5 +1,2 @@
-mod features;
-pub use features::*;
-
-mod clusters;
-pub use clusters::*;
+mod message;
+pub use message::*;
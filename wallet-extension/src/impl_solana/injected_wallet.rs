@@ -2,11 +2,16 @@ use wallet_standard_base::{SemverVersion, WalletStandardIcon};
 use wasm_bindgen::prelude::*;
 use web_sys::js_sys;
 
-use crate::{ICON, Reflection, SolanaConstants, WALLET_NAME};
+use crate::{
+    AtollWalletResult, BitcoinConstants, ICON, ProtocolError, Reflection, SolanaConstants,
+    StandardFeaturesBitcoin, WALLET_NAME, to_js_error,
+};
 
 #[wasm_bindgen]
-pub fn get_injected_wallet_info() -> JsValue {
-    InjectedWallet::new().to_object()
+pub fn get_injected_wallet_info() -> Result<JsValue, JsValue> {
+    InjectedWallet::new()
+        .to_object()
+        .map_err(|error| to_js_error(&ProtocolError::from(&error)))
 }
 
 pub struct InjectedWallet {
@@ -16,6 +21,7 @@ pub struct InjectedWallet {
     name: &'static str,
     version: SemverVersion,
     namespace: &'static str,
+    bitcoin_features: StandardFeaturesBitcoin,
 }
 
 impl InjectedWallet {
@@ -23,51 +29,65 @@ impl InjectedWallet {
         Self::default()
     }
 
-    pub fn to_object(&self) -> JsValue {
-        self.name().version().icon().chains();
+    pub fn to_object(&self) -> AtollWalletResult<JsValue> {
+        self.name()?
+            .version()?
+            .icon()?
+            .chains()?
+            .bitcoin_features()?;
 
-        self.reflect.cloned()
+        Ok(self.reflect.cloned())
     }
 
-    pub fn name(&self) -> &Self {
-        self.reflect.set_object_secure("name", &self.name.into());
+    pub fn name(&self) -> AtollWalletResult<&Self> {
+        self.reflect.set_object_secure("name", &self.name.into())?;
 
-        self
+        Ok(self)
     }
 
-    pub fn namespace(&self) -> &Self {
+    pub fn namespace(&self) -> AtollWalletResult<&Self> {
         self.reflect
-            .set_object_secure("namespace", &self.namespace.into());
+            .set_object_secure("namespace", &self.namespace.into())?;
 
-        self
+        Ok(self)
     }
 
-    pub fn version(&self) -> &Self {
+    pub fn version(&self) -> AtollWalletResult<&Self> {
         self.reflect
-            .set_object_secure("version", &self.version.to_string().into());
+            .set_object_secure("version", &self.version.to_string().into())?;
 
-        self
+        Ok(self)
     }
 
-    pub fn icon(&self) -> &Self {
+    pub fn icon(&self) -> AtollWalletResult<&Self> {
         let icon = self.icon.base64();
 
         self.reflect
-            .set_object_secure("icon", &icon.as_ref().into());
+            .set_object_secure("icon", &icon.as_ref().into())?;
 
-        self
+        Ok(self)
     }
 
-    pub fn chains(&self) -> &Self {
+    pub fn chains(&self) -> AtollWalletResult<&Self> {
         let chains = js_sys::Array::new();
 
         self.chains.iter().for_each(|chain| {
             chains.push(&(*chain).into());
         });
 
-        self.reflect.set_object_secure("chains", &chains);
+        self.reflect.set_object_secure("chains", &chains)?;
 
-        self
+        Ok(self)
+    }
+
+    /// The `bitcoin:*` features the injected wallet should register
+    pub fn bitcoin_features(&self) -> AtollWalletResult<&Self> {
+        self.reflect.set_object_secure(
+            "bitcoinFeatures",
+            &Reflection::new_str_array(&self.bitcoin_features.features()),
+        )?;
+
+        Ok(self)
     }
 }
 
@@ -84,7 +104,12 @@ impl Default for InjectedWallet {
                 SolanaConstants::TESTNET_CHAIN,
                 SolanaConstants::DEVNET_CHAIN,
                 SolanaConstants::LOCALNET_CHAIN,
+                BitcoinConstants::MAINNET_CHAIN,
+                BitcoinConstants::TESTNET_CHAIN,
+                BitcoinConstants::SIGNET_CHAIN,
+                BitcoinConstants::REGTEST_CHAIN,
             ],
+            bitcoin_features: StandardFeaturesBitcoin::new(),
         }
     }
 }
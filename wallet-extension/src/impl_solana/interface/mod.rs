@@ -1,5 +0,0 @@
-mod sign_and_send_transaction;
-mod sign_in;
-mod sign_message;
-mod sign_transaction;
-mod standard_connect;
@@ -1,23 +1,5 @@
-mod clusters;
-pub use clusters::*;
-
-mod features;
-
-mod wallet;
-pub use wallet::*;
-
 mod injected_wallet;
 pub use injected_wallet::*;
 
-mod wallet_account;
-pub use wallet_account::*;
-
-mod commitment;
-pub use commitment::*;
 mod reflection;
 pub use reflection::*;
-
-mod interface;
-
-mod constants;
-pub use constants::*;
@@ -1,10 +1,5 @@
-use core::panic;
-
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{
-    console,
-    js_sys::{self, Reflect},
-};
+use web_sys::js_sys::{self, Reflect};
 
 use crate::{AtollWalletError, AtollWalletResult};
 
@@ -59,43 +54,42 @@ impl Reflection {
         Ok(self)
     }
 
-    /// This panics if not handled properly
-    pub fn set_object_secure(&self, key: &str, js_value: &JsValue) -> &Self {
-        if let Err(error) = Reflect::set(&self.0, &key.into(), js_value) {
-            console::log_7(
-                &"AtollWallet > Unable to set object for key `".into(),
-                &key.into(),
-                &"` for value > `".into(),
-                js_value,
-                &"`. Js Error: `".into(),
-                &error,
-                &"`. Try using `set_object` instead which returns an error you can handle.".into(),
-            );
-            panic!()
-        }
+    /// Sets `key`, failing with an error that names the key when the value is not an
+    /// object. Use [Self::set_object] to choose the error.
+    pub fn set_object_secure(&self, key: &str, js_value: &JsValue) -> AtollWalletResult<&Self> {
+        Reflect::set(&self.0, &key.into(), js_value).map_err(|error| {
+            AtollWalletError::JsCast(format!("Unable to set the key `{key}`. Error: `{error:?}`"))
+        })?;
 
-        self
+        Ok(self)
     }
 
-    /// This panics if not handled properly
-    pub fn define_property_secure(&self, property: &str, descriptor: &js_sys::Object) -> &Self {
-        if !self.0.is_object() {
-            console::log_7(
-                &"AtollWallet > `".into(),
-                &self.0,
-                &"` is not an object therefore cannot define a property. `".into(),
+    pub fn define_property(
+        &self,
+        property: &str,
+        descriptor: &js_sys::Object,
+    ) -> AtollWalletResult<&Self> {
+        let defined =
+            Reflect::define_property(
+                self.0.dyn_ref::<js_sys::Object>().ok_or(
+                    AtollWalletError::JsValueIsNotAnObject(Self::js_typeof(&self.0)?),
+                )?,
                 &property.into(),
-                &"`. with descriptor `".into(),
                 descriptor,
-                &"`.".into(),
-            );
-
-            panic!()
+            )
+            .map_err(|error| {
+                AtollWalletError::JsCast(format!(
+                    "Unable to define the property `{property}`. Error: `{error:?}`"
+                ))
+            })?;
+
+        if !defined {
+            return Err(AtollWalletError::JsCast(format!(
+                "The property `{property}` cannot be defined"
+            )));
         }
 
-        js_sys::Object::define_property(&self.0.clone().into(), &property.into(), descriptor);
-
-        self
+        Ok(self)
     }
 
     pub fn get_object(&self, key: &str, error: AtollWalletError) -> AtollWalletResult<JsValue> {
@@ -106,25 +100,6 @@ impl Reflection {
         Reflect::get(&self.0, &key.into()).ok()
     }
 
-    pub fn get_object_secure(&self, key: &str) -> JsValue {
-        match Reflect::get(&self.0, &key.into()) {
-            Ok(value) => value,
-            Err(error) => {
-                console::log_7(
-                    &"AtollWallet > Unable to get object for key `".into(),
-                    &key.into(),
-                    &"` for js_sys::Object > `".into(),
-                    &self.0,
-                    &"`. Js Error: `".into(),
-                    &error,
-                    &"`.".into(),
-                );
-
-                panic!()
-            }
-        }
-    }
-
     pub fn reflect_string_or_undefined(&self, key: &str) -> Option<String> {
         let js_value_string = self.get_object_or_undefined(key);
 
@@ -147,8 +122,8 @@ impl Reflection {
         js_value
             .js_typeof()
             .as_string()
-            .ok_or(AtollWalletError::UnableToCheckTypeOfJsValue(
-                js_value.clone(),
-            ))
+            .ok_or(AtollWalletError::UnableToCheckTypeOfJsValue(format!(
+                "{js_value:?}"
+            )))
     }
 }
@@ -1,20 +1,25 @@
+pub use atoll_wallet_core::*;
+
 mod message_handler;
 pub use message_handler::*;
 
+mod protocol;
+pub use protocol::*;
+
 mod impl_solana;
 pub use impl_solana::*;
 
 mod impl_bitcoin;
 pub use impl_bitcoin::*;
 
-mod errors;
-pub use errors::*;
-
 mod browser_fetch;
 pub use browser_fetch::*;
 
-mod app;
-pub use app::*;
+mod browser_storage;
+pub use browser_storage::*;
+
+mod signer;
+pub use signer::*;
 
 pub(crate) const WALLET_NAME: &str = "Atoll Wallet";
 
@@ -1,163 +1,210 @@
-use std::panic;
+use std::{panic, rc::Rc, str::FromStr};
 
+use solana_pubkey::Pubkey;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen_futures::future_to_promise;
+use wasm_bindgen_futures::{JsFuture, future_to_promise};
 use web_sys::{
     console,
-    js_sys::{self, Function, Reflect},
+    js_sys::{self, Function, Promise},
 };
 
 use crate::{
-    ActiveHash, App, AtollWalletError, AtollWalletResult, KeypairOps, Reflection, SolanaConstants,
+    App, AtollWalletError, AtollWalletResult, BrowserHttpTransport, BrowserStorage, ProtocolError,
+    ProtocolResponse, Reflection, ToProtocolValue, decode_request, request_id_of, to_js_error,
+    to_js_value,
 };
 
+/// The base58 key that signs the domain lists, set when the extension is built
+const DOMAIN_LIST_PUBLISHER: Option<&str> = option_env!("ATOLL_DOMAIN_LIST_PUBLISHER");
+
+/// Starts the background. Errors are logged and thrown to the caller.
 #[wasm_bindgen]
-pub fn app(extension: JsValue) {
+pub fn app(extension: JsValue) -> Result<(), JsValue> {
     panic::set_hook(Box::new(console_error_panic_hook::hook));
 
-    let app = App::load_test_keypairs().unwrap();
-    let active_hash = app.active.clone();
-    let keypair_ops = app.keypairs.clone();
-
-    let runtime = Reflect::get(&extension, &JsValue::from_str("runtime")).unwrap_or_else(|_| {
-        panic!(
-            "{}",
-            AtollWalletError::ExtensionRuntimeIsMissing.to_string()
-        )
-    });
-
-    let on_message = Reflect::get(&runtime, &JsValue::from_str("onMessage")).unwrap_or_else(|_| {
-        panic!(
-            "{}",
-            AtollWalletError::ExtensionRuntimeMessageIsMissing.to_string()
-        )
-    });
-
-    let add_listener = Reflect::get(&on_message, &JsValue::from_str("addListener"))
-        .unwrap_or_else(|_| {
-            panic!(
-                "{}",
-                AtollWalletError::ExtensionRuntimeMessageAddListenerIsMissing.to_string()
-            )
-        })
-        .dyn_into::<js_sys::Function>()
-        .unwrap_or_else(|_| {
-            panic!("{}", AtollWalletError::JsCast(
-                "Unable to convert `extension.runtime.onMessage.addListener` to js_sys::Function"
-                    .to_string(),
-            )
-            .to_string())
+    init(extension)
+        .inspect_err(app_error_log)
+        .map_err(|error| to_js_error(&ProtocolError::from(&error)))
+}
+
+/// Loads the wallet and listens on `extension.runtime.onMessage`. The listener is added
+/// right away, as the browser requires, and the messages wait for the storage to be read.
+/// The vault stays locked until the popup creates, restores or unlocks it.
+fn init(extension: JsValue) -> AtollWalletResult<()> {
+    let storage = BrowserStorage::new(&extension)?;
+
+    let runtime = Reflection::new_object_from_js_value(extension)?
+        .get_object_or_undefined("runtime")
+        .filter(|runtime| runtime.is_object())
+        .ok_or(AtollWalletError::ExtensionRuntimeIsMissing)?;
+
+    let mut app = App::new(BrowserHttpTransport)
+        .set_extension_origin(&extension_origin(&runtime)?)
+        .set_storage(storage)
+        .set_on_domain_warning(|report| {
+            app_console_log("domainWarning", &to_js_value(&report.to_protocol_value()))
         });
 
+    if let Some(publisher) =
+        DOMAIN_LIST_PUBLISHER.and_then(|publisher| Pubkey::from_str(publisher).ok())
+    {
+        app = app.set_domain_list_publisher(publisher);
+    }
+
+    let app = Rc::new(app);
+
+    let loaded = {
+        let app = app.clone();
+
+        future_to_promise(async move {
+            app.load_storage()
+                .await
+                .map(|_| JsValue::UNDEFINED)
+                .map_err(|error| to_js_error(&ProtocolError::from(&error)))
+        })
+    };
+
+    let on_message = Reflection::new(runtime)
+        .get_object_or_undefined("onMessage")
+        .filter(|on_message| on_message.is_object())
+        .ok_or(AtollWalletError::ExtensionRuntimeMessageIsMissing)?;
+
+    let add_listener = Reflection::new(on_message.clone())
+        .get_object_or_undefined("addListener")
+        .and_then(|add_listener| add_listener.dyn_into::<Function>().ok())
+        .ok_or(AtollWalletError::ExtensionRuntimeMessageAddListenerIsMissing)?;
+
     let send_response_callback = Closure::wrap(Box::new(
-        move |message: JsValue, _sender: JsValue, send_response: JsValue| {
-            let active_hash = active_hash.clone();
-            let keypair_ops = keypair_ops.clone();
-
-            let processed = async {
-                match_message(message, active_hash, keypair_ops)
-                    .await
-                    .map_err(|value| {
-                        let value: JsValue = value.into();
-
-                        value
-                    })
-            };
-            let reply = future_to_promise(processed);
+        move |message: JsValue, sender: JsValue, send_response: JsValue| {
+            let app = app.clone();
+            let loaded = loaded.clone();
+            let origin = sender_origin(&sender);
 
-            let send_response_fn = send_response
-                    .dyn_into::<Function>()
-                    .expect("Unable to convert a `sendResponse` for `extension.runtime.onMessage.addListener` to a js_sys::Function");
+            let processed = async move { Ok(handle_message(&app, &loaded, message, origin).await) };
+            let reply = future_to_promise(processed);
 
-            send_response_fn
-                .call1(&JsValue::NULL, &reply.into())
-                .expect("Unable to call `sendResponse`");
+            if let Err(error) = respond(send_response, &reply.into()) {
+                app_error_log(&error);
+            }
 
             JsValue::from_bool(true)
         },
     )
         as Box<dyn FnMut(JsValue, JsValue, JsValue) -> JsValue>);
 
-    let send_response_callback_fn = send_response_callback
-        .as_ref()
-        .dyn_ref::<Function>()
-        .expect("Unable to convert a `send_response_callback` to a js_sys::Function");
-
     add_listener
-        .call1(&on_message, send_response_callback_fn)
-        .expect("Calling `send_response_callback` error");
+        .call1(&on_message, send_response_callback.as_ref())
+        .map_err(|error| {
+            AtollWalletError::JsCast(format!(
+                "Unable to add a listener to `extension.runtime.onMessage`. Error: `{error:?}`"
+            ))
+        })?;
 
     send_response_callback.forget();
+
+    Ok(())
+}
+
+/// The origin of the pages of the extension, like `chrome-extension://<id>`, from
+/// `extension.runtime.getURL`
+fn extension_origin(runtime: &JsValue) -> AtollWalletResult<String> {
+    let get_url = Reflection::new(runtime.clone())
+        .get_object_or_undefined("getURL")
+        .and_then(|get_url| get_url.dyn_into::<Function>().ok())
+        .ok_or(AtollWalletError::JsCast(
+            "`extension.runtime.getURL` is not a function".to_string(),
+        ))?;
+
+    get_url
+        .call1(runtime, &"".into())
+        .ok()
+        .and_then(|url| url.as_string())
+        .map(|url| url.trim_end_matches('/').to_string())
+        .ok_or(AtollWalletError::JsCast(
+            "Unable to read the URL of the extension from `extension.runtime.getURL`".to_string(),
+        ))
+}
+
+/// Passes `reply` to the `sendResponse` of `extension.runtime.onMessage`
+fn respond(send_response: JsValue, reply: &JsValue) -> AtollWalletResult<()> {
+    send_response
+        .dyn_into::<Function>()
+        .or(Err(AtollWalletError::JsCast(
+            "The `sendResponse` of `extension.runtime.onMessage` is not a function".to_string(),
+        )))?
+        .call1(&JsValue::NULL, reply)
+        .map_err(|error| {
+            AtollWalletError::JsCast(format!("Unable to call `sendResponse`. Error: `{error:?}`"))
+        })?;
+
+    Ok(())
 }
 
-async fn match_message(
+/// Decodes the request envelope and always answers with a response envelope,
+/// carrying either the output of the handler or the error
+async fn handle_message(
+    app: &App<BrowserHttpTransport>,
+    loaded: &Promise,
     message: JsValue,
-    active_hash: ActiveHash,
-    keypair_ops: KeypairOps,
-) -> AtollWalletResult<JsValue> {
-    let message_object = Reflection::new_object_from_js_value(message)?;
+    sender_origin: Option<String>,
+) -> JsValue {
+    let request = match decode_request(message.clone()) {
+        Ok(request) => request,
+        Err(error) => {
+            app_error_log(&error);
+
+            return to_js_value(&ProtocolResponse::err(
+                request_id_of(&message).as_deref(),
+                &error,
+            ));
+        }
+    };
 
-    let resource_js_value = message_object.get_object(
-        "resource",
-        AtollWalletError::ResourceNotFoundInMessageObject,
-    )?;
+    if let Err(error) = JsFuture::from(loaded.clone()).await {
+        let error = AtollWalletError::JsCast(format!(
+            "Unable to read the wallet from `extension.storage.local`. Error: `{error:?}`"
+        ));
+        app_error_log(&error);
 
-    let data = message_object.get_object("data", AtollWalletError::DataNotFoundInMessageObject)?;
+        return to_js_value(&ProtocolResponse::err(Some(&request.header.id), &error));
+    }
 
-    let resource: ExtensionMessage = resource_js_value.as_ref().try_into()?;
+    if request.header.method.is_sensitive() {
+        app_console_log(request.header.method.as_str(), &JsValue::UNDEFINED);
+    } else {
+        app_console_log(request.header.method.as_str(), &request.params.0);
+    }
 
-    match resource {
-        ExtensionMessage::StandardConnect => {
-            App::standard_connect(*active_hash.read().await, keypair_ops, &data).await
-        }
-        ExtensionMessage::SolanaSignIn => {
-            App::solana_sign_in(*active_hash.read().await, keypair_ops, data).await
-        }
+    let response = match app
+        .handle(&request, sender_origin, js_sys::Date::now())
+        .await
+    {
+        Ok(output) => ProtocolResponse::ok(&request.header.id, output),
+        Err(error) => {
+            app_error_log(&error);
 
-        ExtensionMessage::SolanaSignMessage => {
-            App::solana_sign_message(*active_hash.read().await, keypair_ops, data).await
-        }
-        ExtensionMessage::SolanaSignTransaction => {
-            App::solana_sign_transaction(*active_hash.read().await, keypair_ops, data).await
-        }
-        ExtensionMessage::SolanaSignAndSendTransaction => {
-            App::solana_sign_and_transaction(*active_hash.read().await, keypair_ops, data).await
+            ProtocolResponse::err(Some(&request.header.id), &error)
         }
-    }
-}
+    };
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
-pub enum ExtensionMessage {
-    StandardConnect,
-    SolanaSignIn,
-    SolanaSignMessage,
-    SolanaSignTransaction,
-    SolanaSignAndSendTransaction,
+    to_js_value(&response)
 }
 
-impl TryFrom<&JsValue> for ExtensionMessage {
-    type Error = AtollWalletError;
+/// The origin of the page that sent a message, from the `sender` of
+/// `extension.runtime.onMessage` which only has a `url` in some browsers
+fn sender_origin(sender: &JsValue) -> Option<String> {
+    let sender = Reflection::new_object_from_js_value(sender.clone()).ok()?;
 
-    fn try_from(js_value: &JsValue) -> Result<Self, Self::Error> {
-        let parsed_js_value = js_value.as_string().ok_or(AtollWalletError::JsCast(
-            "The `resource` JsValue from the `extension.runtime.onMessage.addListener.message` is not a String type"
-                .to_string(),
-        ))?;
-        let matched = match parsed_js_value.as_str() {
-            SolanaConstants::STANDARD_CONNECT => Self::StandardConnect,
-            SolanaConstants::SIGN_IN => Self::SolanaSignIn,
-            SolanaConstants::SIGN_MESSAGE => Self::SolanaSignMessage,
-            SolanaConstants::SIGN_TRANSACTION => Self::SolanaSignTransaction,
-            SolanaConstants::SIGN_AND_SEND_TRANSACTION => Self::SolanaSignAndSendTransaction,
-            _ => {
-                return Err(AtollWalletError::UnsupportedExtensionMessage(
-                    parsed_js_value,
-                ));
-            }
-        };
+    sender
+        .reflect_string_or_undefined("origin")
+        .filter(|origin| origin != "null")
+        .or_else(|| {
+            let url = sender.reflect_string_or_undefined("url")?;
+            let (scheme, rest) = url.split_once("://")?;
+            let host = rest.split(['/', '?', '#']).next()?;
 
-        Ok(matched)
-    }
+            Some(format!("{scheme}://{host}"))
+        })
 }
 
 pub fn app_console_log(event_name: &str, message: &JsValue) {
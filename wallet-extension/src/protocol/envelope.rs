@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use wasm_bindgen::prelude::*;
+use web_sys::js_sys;
+
+use crate::{
+    AtollWalletError, AtollWalletErrorCategory, AtollWalletResult, ExtensionMessage, ProtocolError,
+    ProtocolHeader, ProtocolParams, ProtocolRequest, ProtocolValue, Reflection, app_error_log,
+};
+
+#[wasm_bindgen]
+pub fn get_protocol_info() -> Result<JsValue, JsValue> {
+    protocol_info().map_err(|error| to_js_error(&ProtocolError::from(&error)))
+}
+
+fn protocol_info() -> AtollWalletResult<JsValue> {
+    let methods = Reflection::new_object();
+    for method in ExtensionMessage::ALL {
+        methods.set_object_secure(method.name(), &method.as_str().into())?;
+    }
+
+    let error_codes = Reflection::new_object();
+    for category in AtollWalletErrorCategory::ALL {
+        error_codes.set_object_secure(category.as_str(), &category.code().into())?;
+    }
+
+    let output = Reflection::new_object();
+    output
+        .set_object_secure("version", &ProtocolHeader::VERSION.into())?
+        .set_object_secure("relayPrefix", &ProtocolHeader::RELAY_PREFIX.into())?
+        .set_object_secure("methods", &methods.take())?
+        .set_object_secure("errorCodes", &error_codes.take())?;
+
+    Ok(output.take())
+}
+
+/// The `params` of a request as sent by the page, deserialized once the method is known
+#[derive(Debug, Clone)]
+pub struct JsProtocolParams(pub JsValue);
+
+impl ProtocolParams for JsProtocolParams {
+    fn is_missing(&self) -> bool {
+        self.0.is_undefined() || self.0.is_null()
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self) -> Result<T, String> {
+        serde_wasm_bindgen::from_value::<T>(self.0.clone()).map_err(|error| error.to_string())
+    }
+}
+
+/// A request to the background decoded from the message of `extension.runtime.onMessage`
+pub type JsProtocolRequest = ProtocolRequest<JsProtocolParams>;
+
+#[derive(Deserialize)]
+struct RawProtocolParams {
+    #[serde(default, with = "serde_wasm_bindgen::preserve")]
+    params: JsValue,
+}
+
+/// Decodes a request, checking the [ProtocolHeader] before reading the params
+pub fn decode_request(value: JsValue) -> AtollWalletResult<JsProtocolRequest> {
+    let header = ProtocolHeader::decode(serde_wasm_bindgen::Deserializer::from(value.clone()))?;
+    let raw = serde_wasm_bindgen::from_value::<RawProtocolParams>(value)
+        .map_err(|error| AtollWalletError::InvalidRequest(error.to_string()))?;
+
+    Ok(ProtocolRequest {
+        header,
+        params: JsProtocolParams(raw.params),
+    })
+}
+
+/// The `id` of a request that could not be decoded, if it has one
+pub fn request_id_of(value: &JsValue) -> Option<String> {
+    Reflection::new_object_from_js_value(value.clone())
+        .ok()?
+        .reflect_string_or_undefined("id")
+}
+
+/// Converts the output of the background to the value given to the page, with objects as
+/// plain objects and bytes as a `Uint8Array`
+pub fn to_js_value(value: &ProtocolValue) -> JsValue {
+    let serializer = serde_wasm_bindgen::Serializer::new()
+        .serialize_maps_as_objects(true)
+        .serialize_missing_as_null(true);
+
+    value.serialize(&serializer).unwrap_or_else(|error| {
+        app_error_log(&AtollWalletError::JsCast(error.to_string()));
+
+        JsValue::NULL
+    })
+}
+
+/// An `Error` carrying the `code` and `data` of `error` for code that throws rather than
+/// responds. When the fields cannot be set the plain `Error` with the message is returned
+/// and the failure is logged.
+pub fn to_js_error(error: &ProtocolError) -> JsValue {
+    let output = Reflection::new(js_sys::Error::new(&error.message).into());
+
+    if let Err(set_error) = set_js_error_fields(&output, error) {
+        app_error_log(&set_error);
+    }
+
+    output.take()
+}
+
+fn set_js_error_fields(output: &Reflection, error: &ProtocolError) -> AtollWalletResult<()> {
+    let mut data = ProtocolValue::new_object();
+    data.set("category", error.category.as_str());
+
+    output
+        .set_object_secure("code", &error.code.into())?
+        .set_object_secure("message", &error.message.as_str().into())?
+        .set_object_secure("data", &to_js_value(&data))?;
+
+    Ok(())
+}
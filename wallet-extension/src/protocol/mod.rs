@@ -0,0 +1,2 @@
+mod envelope;
+pub use envelope::*;
@@ -0,0 +1,108 @@
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::{Array, Function, Promise};
+
+use crate::{
+    AirGappedSignRequest, AtollWalletError, AtollWalletResult, SignatureRelay, SignerFuture,
+    SolSignRequest, SolSignature, UrRegistry, WALLET_NAME,
+};
+
+/// A [SignatureRelay] that shows the request as an animated `ur:sol-sign-request`
+/// QR code and decodes the `ur:sol-signature` scanned back from the device.
+///
+/// The JavaScript function receives the UR parts to cycle through as an
+/// `Array<string>` and resolves with the scanned parts of the signature.
+pub struct JsUrRelay {
+    show_and_scan: Function,
+    master_fingerprint: Option<u32>,
+    max_fragment_length: usize,
+}
+
+impl JsUrRelay {
+    pub fn new(show_and_scan: JsValue) -> AtollWalletResult<Self> {
+        let show_and_scan =
+            show_and_scan
+                .dyn_into::<Function>()
+                .or(Err(AtollWalletError::JsCast(
+                    "The QR code relay is not a function".to_string(),
+                )))?;
+
+        Ok(Self {
+            show_and_scan,
+            master_fingerprint: Option::default(),
+            max_fragment_length: UrRegistry::DEFAULT_MAX_FRAGMENT_LENGTH,
+        })
+    }
+
+    /// The fingerprint of the master key of the device
+    pub fn set_master_fingerprint(mut self, master_fingerprint: u32) -> Self {
+        self.master_fingerprint.replace(master_fingerprint);
+
+        self
+    }
+
+    /// Smaller fragments give less dense QR codes at the cost of more frames
+    pub fn set_max_fragment_length(mut self, max_fragment_length: usize) -> Self {
+        self.max_fragment_length = max_fragment_length;
+
+        self
+    }
+
+    async fn relay(&self, request: AirGappedSignRequest) -> AtollWalletResult<SolSignature> {
+        let mut sol_request = SolSignRequest::new(&request)?.set_origin(WALLET_NAME);
+        if let Some(master_fingerprint) = self.master_fingerprint {
+            sol_request = sol_request.set_master_fingerprint(master_fingerprint);
+        }
+
+        let parts = sol_request
+            .to_ur_parts(self.max_fragment_length)?
+            .into_iter()
+            .map(JsValue::from)
+            .collect::<Array>();
+
+        let promise = self
+            .show_and_scan
+            .call1(&JsValue::NULL, &parts)
+            .map_err(|error| {
+                AtollWalletError::AirGapped(format!("Unable to show the QR code. {error:?}"))
+            })?
+            .dyn_into::<Promise>()
+            .or(Err(AtollWalletError::JsCast(
+                "The QR code relay did not return a Promise".to_string(),
+            )))?;
+
+        let scanned = JsFuture::from(promise).await.map_err(|error| {
+            AtollWalletError::AirGapped(format!("Scanning the signature failed. {error:?}"))
+        })?;
+
+        let scanned = match scanned.as_string() {
+            Some(single) => vec![single],
+            None => Array::from(&scanned)
+                .iter()
+                .map(|part| {
+                    part.as_string().ok_or(AtollWalletError::JsCast(
+                        "A scanned QR code part is not a String".to_string(),
+                    ))
+                })
+                .collect::<AtollWalletResult<Vec<String>>>()?,
+        };
+
+        let signature = SolSignature::from_ur_parts(
+            &scanned.iter().map(String::as_str).collect::<Vec<&str>>(),
+        )?;
+
+        if signature.request_id != sol_request.request_id() {
+            return Err(AtollWalletError::AirGapped(
+                "The scanned signature belongs to a different request".to_string(),
+            ));
+        }
+
+        Ok(signature)
+    }
+}
+
+impl SignatureRelay for JsUrRelay {
+    fn request_signature<'a>(&'a self, request: AirGappedSignRequest) -> SignerFuture<'a> {
+        Box::pin(async move { Ok(self.relay(request).await?.signature) })
+    }
+}
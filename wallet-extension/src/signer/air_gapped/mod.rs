@@ -0,0 +1,2 @@
+mod js_relay;
+pub use js_relay::*;
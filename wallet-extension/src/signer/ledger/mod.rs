@@ -0,0 +1,2 @@
+mod web_hid;
+pub use web_hid::*;
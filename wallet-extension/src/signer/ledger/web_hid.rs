@@ -0,0 +1,88 @@
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys::{Function, Promise, Uint8Array};
+
+use crate::{
+    AtollWalletError, AtollWalletResult, HidDevice, HidReadFuture, HidWriteFuture, Reflection,
+};
+
+/// A [HidDevice] backed by a JavaScript object exposing
+/// `write(report: Uint8Array): Promise<void>` and `read(): Promise<Uint8Array>`.
+///
+/// The page holding the WebHID `HIDDevice` wraps `sendReport(0, report)` and the
+/// `inputreport` events in this object since WebHID is not reachable from Rust directly.
+pub struct JsHidDevice {
+    device: JsValue,
+    write: Function,
+    read: Function,
+}
+
+impl JsHidDevice {
+    pub fn new(device: JsValue) -> AtollWalletResult<Self> {
+        let reflection = Reflection::new_object_from_js_value(device.clone())?;
+
+        let write = Self::function(&reflection, "write")?;
+        let read = Self::function(&reflection, "read")?;
+
+        Ok(Self {
+            device,
+            write,
+            read,
+        })
+    }
+
+    fn function(reflection: &Reflection, key: &str) -> AtollWalletResult<Function> {
+        reflection
+            .get_object_or_undefined(key)
+            .and_then(|value| value.dyn_into::<Function>().ok())
+            .ok_or(AtollWalletError::JsCast(format!(
+                "The HID device object does not have a `{key}` function"
+            )))
+    }
+
+    async fn promise(value: JsValue, operation: &str) -> AtollWalletResult<JsValue> {
+        let promise = value
+            .dyn_into::<Promise>()
+            .or(Err(AtollWalletError::JsCast(format!(
+                "The HID device `{operation}` function did not return a Promise"
+            ))))?;
+
+        JsFuture::from(promise).await.map_err(|error| {
+            AtollWalletError::Ledger(format!("The HID device `{operation}` failed. {error:?}"))
+        })
+    }
+}
+
+impl HidDevice for JsHidDevice {
+    fn write<'a>(&'a self, report: &'a [u8]) -> HidWriteFuture<'a> {
+        Box::pin(async move {
+            let value = self
+                .write
+                .call1(&self.device, &Reflection::new_uint8_array(report))
+                .map_err(|error| {
+                    AtollWalletError::Ledger(format!("The HID device `write` failed. {error:?}"))
+                })?;
+
+            Self::promise(value, "write").await?;
+
+            Ok(())
+        })
+    }
+
+    fn read(&self) -> HidReadFuture<'_> {
+        Box::pin(async move {
+            let value = self.read.call0(&self.device).map_err(|error| {
+                AtollWalletError::Ledger(format!("The HID device `read` failed. {error:?}"))
+            })?;
+
+            let report = Self::promise(value, "read")
+                .await?
+                .dyn_into::<Uint8Array>()
+                .or(Err(AtollWalletError::JsCast(
+                    "The HID device `read` function did not resolve to a Uint8Array".to_string(),
+                )))?;
+
+            Ok(report.to_vec())
+        })
+    }
+}
@@ -0,0 +1,5 @@
+mod ledger;
+pub use ledger::*;
+
+mod air_gapped;
+pub use air_gapped::*;